@@ -0,0 +1,169 @@
+// src-tauri/src/activitypub.rs
+// Sharing to the fediverse through the account's Pleroma account. A document
+// (or a share link to it) becomes a status posted with the account's own
+// token, so it federates as that account's post. The Alem server passes the
+// Mastodon API through to Pleroma:
+//
+// GET  /api/v1/namespaces/account → {account: {id, username, …}}
+// POST /api/v1/statuses           {status, visibility, content_type}
+//                                 Idempotency-Key → {id, url, account: {id}}
+//
+// The post carries the document's title, description, tags (as hashtags) and
+// link, and names the local DID as its author in a closing "Author:" line, so
+// whoever resolves the DID can tell whose document it is. The Pleroma account
+// is looked up once and kept in local_identity.pleroma_account_id; what was
+// posted is kept in fediverse_posts (db/fediverse.rs).
+use anyhow::{bail, Context, Result};
+use libsql::{Connection, Value};
+use serde_json::Value as Json;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Url};
+
+use crate::db::{self, fediverse, identity, models::{row_to_summary, summary_columns, DocumentSummary, FediversePost, PostVisibility}};
+use crate::http::{self, SendRetrying};
+use crate::oauth;
+use crate::sync::{connectivity::ConnectionState, engine};
+
+const TIMEOUT: Duration = Duration::from_secs(20);
+/// Pleroma's default post limit is 5000 characters; the description gets
+/// what the rest leaves, up to this.
+const MAX_DESCRIPTION_CHARS: usize = 1000;
+
+/// Post `doc_id` to the active account's Pleroma account with `visibility`,
+/// carrying `link` (a share link, or any http(s) URL) when given.
+pub async fn publish(
+    app: &AppHandle,
+    doc_id: &str,
+    visibility: PostVisibility,
+    link: Option<&str>,
+) -> Result<FediversePost> {
+    let link = link.map(str::trim).filter(|l| !l.is_empty());
+    if let Some(link) = link {
+        let url = Url::parse(link).context("Not a valid link")?;
+        if !matches!(url.scheme(), "http" | "https") {
+            bail!("Only http(s) links can be shared");
+        }
+    }
+
+    let state = app.state::<crate::AppState>();
+    if state.connectivity.state() == ConnectionState::Offline {
+        bail!("Offline: posting needs the server");
+    }
+    let conn = db::connect(&state.db).await?;
+    if oauth::session_expired(&conn).await? {
+        bail!("The session has expired; sign in again to post");
+    }
+    let token = oauth::access_token(app).await?.context("Not signed in")?;
+    let doc = document(&conn, doc_id).await?;
+    let author_did = identity::did(&conn).await?;
+
+    let server_url = engine::query_server_url(&conn).await;
+    let client = http::client(TIMEOUT)?;
+    let account = pleroma_account(&conn, &client, &server_url, &token).await?;
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let resp: Json = client
+        .post(format!("{server_url}/api/v1/statuses"))
+        .bearer_auth(&token)
+        // A retried post isn't posted twice
+        .header("Idempotency-Key", &id)
+        .json(&serde_json::json!({
+            "status":       compose(&doc, link, author_did.as_deref()),
+            "visibility":   visibility.as_str(),
+            "content_type": "text/plain",
+        }))
+        .send_retrying().await?
+        .error_for_status()?
+        .json().await?;
+    let status_id = match &resp["id"] {
+        Json::String(id) => id.clone(),
+        Json::Number(id) => id.to_string(),
+        _ => bail!("Pleroma's answer has no status id"),
+    };
+    log::info!("[activitypub] Posted {doc_id} as status {status_id}");
+
+    let post = FediversePost {
+        id,
+        doc_id: doc_id.to_string(),
+        status_id,
+        url: resp["url"].as_str().or_else(|| resp["uri"].as_str()).map(str::to_string),
+        visibility,
+        pleroma_account_id: resp["account"]["id"].as_str().map(str::to_string).unwrap_or(account),
+        author_did,
+        link: link.map(str::to_string),
+        created_at: String::new(),
+    };
+    fediverse::record(&conn, &post).await
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+/// The live document `doc_id`.
+async fn document(conn: &Connection, doc_id: &str) -> Result<DocumentSummary> {
+    let mut rows = conn.query(
+        &format!("SELECT {} FROM documents WHERE id = ?1 AND status != 'deleted' AND trashed_at IS NULL",
+                 summary_columns("")),
+        libsql::params![doc_id],
+    ).await?;
+    let row = rows.next().await?.with_context(|| format!("Document {doc_id} not found"))?;
+    row_to_summary(&row)
+}
+
+/// The id of the Pleroma account the token belongs to: the one kept in
+/// local_identity, or asked of the server and kept there.
+async fn pleroma_account(conn: &Connection, client: &reqwest::Client, server_url: &str, token: &str) -> Result<String> {
+    let mut rows = conn.query(
+        "SELECT pleroma_account_id FROM local_identity WHERE id = 'singleton' AND pleroma_account_id IS NOT NULL",
+        (),
+    ).await?;
+    if let Some(Value::Text(id)) = rows.next().await?.and_then(|r| r.get_value(0).ok()) {
+        return Ok(id);
+    }
+    drop(rows);
+
+    let resp: Json = client
+        .get(format!("{server_url}/api/v1/namespaces/account"))
+        .bearer_auth(token)
+        .send_retrying().await?
+        .error_for_status()?
+        .json().await?;
+    let id = match &resp["account"]["id"] {
+        Json::String(id) => id.clone(),
+        Json::Number(id) => id.to_string(),
+        _ => bail!("No Pleroma account is linked to this account"),
+    };
+    conn.execute(
+        "UPDATE local_identity SET pleroma_account_id = ?1 WHERE id = 'singleton'",
+        libsql::params![id.as_str()],
+    ).await?;
+    Ok(id)
+}
+
+/// The status text: title, description, link, hashtags and author, a
+/// paragraph each.
+fn compose(doc: &DocumentSummary, link: Option<&str>, author_did: Option<&str>) -> String {
+    let text = |key: &str| doc.metadata[key].as_str().map(str::trim).filter(|s| !s.is_empty());
+    let mut paragraphs = vec![text("title").unwrap_or(&doc.filename).to_string()];
+    if let Some(description) = text("description") {
+        let mut short: String = description.chars().take(MAX_DESCRIPTION_CHARS).collect();
+        if short.len() < description.len() {
+            short.push('…');
+        }
+        paragraphs.push(short);
+    }
+    if let Some(link) = link {
+        paragraphs.push(link.to_string());
+    }
+    let hashtags: Vec<String> = doc.tags.iter()
+        .map(|tag| tag.chars().filter(|c| c.is_alphanumeric() || *c == '_').collect::<String>())
+        .filter(|tag| !tag.is_empty())
+        .map(|tag| format!("#{tag}"))
+        .collect();
+    if !hashtags.is_empty() {
+        paragraphs.push(hashtags.join(" "));
+    }
+    if let Some(did) = author_did {
+        paragraphs.push(format!("Author: {did}"));
+    }
+    paragraphs.join("\n\n")
+}
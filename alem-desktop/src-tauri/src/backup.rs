@@ -0,0 +1,664 @@
+// src-tauri/src/backup.rs
+// Full and incremental local backups, verification and restore.
+//
+// A backup is a consistent VACUUM INTO snapshot of alem.db. Without files it
+// is written as-is (a plain database file); with files, or as an incremental
+// on top of an earlier archive, it is a tar archive:
+//
+//     backup.json          BackupManifest
+//     alem.db              full backups: the whole snapshot
+//     db/<n>               incrementals: only the DB_CHUNK-sized runs of pages
+//                          that changed since the base
+//     files/<first2>/<sha256>
+//                          blobs not already in the base
+//
+// The manifest lists the sha256 of every database chunk and the name of every
+// blob in the store, so the next incremental knows what its base holds. An
+// incremental names its base (id and file name); restore and verification
+// look for the whole chain next to it. VACUUM INTO packs the database, so an
+// insert early in a table shifts the pages after it, and an incremental then
+// carries more than the rows changed.
+//
+// Restore rebuilds the snapshot from the chain, checks it against the chunk
+// hashes, integrity_check and schema version, checks every blob against its
+// hash, adds the blobs to the store, and stages the database as
+// alem.db.restore. The live Database can't be swapped underneath running
+// commands, so the staged file replaces alem.db on the next launch
+// (apply_pending_restore), keeping the old one as alem.db.pre-restore.
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context as TaskContext, Poll};
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite};
+
+use crate::db::{self, encryption, schema, stats};
+use crate::jobs::JobHandle;
+use crate::storage::{
+    archive::{self, TarReader, TarWriter},
+    cas,
+};
+
+const MANIFEST_ENTRY:     &str = "backup.json";
+const DB_ENTRY:           &str = "alem.db";
+const DB_CHUNK_PREFIX:    &str = "db/";
+const FILES_PREFIX:       &str = "files/";
+const RESTORE_SUFFIX:     &str = "restore";
+const PRE_RESTORE_SUFFIX: &str = "pre-restore";
+const FORMAT_VERSION:     u64 = 2;
+/// Unit of change for incrementals: whole pages at any SQLite page size.
+const DB_CHUNK: u64 = 64 * 1024;
+/// Longest base chain followed.
+const MAX_CHAIN: usize = 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format:         u64,
+    pub created_at:     String,
+    pub schema_version: i64,
+    /// Blob entries in this archive.
+    pub files:          u64,
+    // Format 2 on; earlier archives can't be the base of an incremental.
+    #[serde(default)]
+    pub id:             Option<String>,
+    #[serde(default)]
+    pub parent:         Option<BackupParent>,
+    #[serde(default)]
+    pub db_size:        u64,
+    /// sha256 of each DB_CHUNK of the snapshot.
+    #[serde(default)]
+    pub chunks:         Vec<String>,
+    /// Every blob in the store at backup time ("<first2>/<name>"); empty
+    /// when files weren't included.
+    #[serde(default)]
+    pub blobs:          Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupParent {
+    pub id:   String,
+    /// File name of the base; it must sit next to this archive.
+    pub file: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct BackupVerification {
+    pub ok:             bool,
+    /// Archives checked, base first.
+    pub chain:          Vec<PathBuf>,
+    pub schema_version: Option<i64>,
+    pub db_chunks:      usize,
+    pub files_checked:  u64,
+    pub missing_files:  Vec<String>,
+    pub corrupt_files:  Vec<String>,
+    pub problems:       Vec<String>,
+}
+
+/// Write a backup of the database (and optionally the blob store) to `dest`.
+/// With `base`, only what changed since that archive backup is written.
+pub async fn create(
+    app: &AppHandle,
+    dest: &Path,
+    include_files: bool,
+    base: Option<&Path>,
+    job: &JobHandle,
+) -> Result<serde_json::Value> {
+    let data_dir = app.path().app_data_dir()?;
+    let snapshot = data_dir.join(format!(".backup-{}.db", uuid::Uuid::new_v4()));
+    let partial  = with_suffix(dest, "partial");
+
+    let result = async {
+        let parent = match base {
+            Some(base) => {
+                let manifest = read_manifest(base).await?
+                    .filter(|m| m.id.is_some())
+                    .with_context(|| format!("{} can't be the base of an incremental backup", base.display()))?;
+                Some((base, manifest))
+            }
+            None => None,
+        };
+
+        job.advance(0, Some("Snapshotting database".into()));
+        snapshot_db(app, &snapshot).await?;
+        let (chunks, db_size) = hash_chunks(&snapshot).await?;
+
+        if !include_files && parent.is_none() {
+            tokio::fs::copy(&snapshot, &partial).await?;
+            tokio::fs::rename(&partial, dest).await
+                .with_context(|| format!("Writing {}", dest.display()))?;
+            let size = tokio::fs::metadata(dest).await?.len();
+            return Ok(serde_json::json!({ "path": dest, "size": size, "files": 0, "incremental": false }));
+        }
+
+        let blobs = if include_files { list_blobs(&cas::files_dir(app)?).await? } else { Vec::new() };
+        let (changed, new_blobs): (Vec<usize>, Vec<&(String, PathBuf)>) = match &parent {
+            Some((_, base)) => {
+                let known: HashSet<&String> = base.blobs.iter().collect();
+                (
+                    (0..chunks.len()).filter(|&i| base.chunks.get(i) != Some(&chunks[i])).collect(),
+                    blobs.iter().filter(|(name, _)| !known.contains(name)).collect(),
+                )
+            }
+            None => (Vec::new(), blobs.iter().collect()),
+        };
+        job.set_total(new_blobs.len() as u64 + 1);
+
+        let manifest = BackupManifest {
+            format:         FORMAT_VERSION,
+            created_at:     chrono::Utc::now().to_rfc3339(),
+            schema_version: schema::latest_version(),
+            files:          new_blobs.len() as u64,
+            id:             Some(uuid::Uuid::new_v4().to_string()),
+            parent:         parent.as_ref().map(|(path, m)| BackupParent {
+                id:   m.id.clone().unwrap_or_default(),
+                file: path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+            }),
+            db_size,
+            chunks,
+            blobs:          blobs.iter().map(|(name, _)| name.clone()).collect(),
+        };
+
+        let out = tokio::fs::File::create(&partial).await?;
+        let mut tar = TarWriter::new(tokio::io::BufWriter::new(out));
+        tar.append_bytes(MANIFEST_ENTRY, &serde_json::to_vec(&manifest)?).await?;
+        if parent.is_some() {
+            let mut db = tokio::fs::File::open(&snapshot).await?;
+            for &i in &changed {
+                job.check_cancelled()?;
+                db.seek(SeekFrom::Start(i as u64 * DB_CHUNK)).await?;
+                let mut chunk = Vec::with_capacity(DB_CHUNK as usize);
+                (&mut db).take(DB_CHUNK).read_to_end(&mut chunk).await?;
+                tar.append_bytes(&format!("{DB_CHUNK_PREFIX}{i}"), &chunk).await?;
+            }
+        } else {
+            tar.append_file(DB_ENTRY, &snapshot).await?;
+        }
+        job.advance(1, Some("Archiving files".into()));
+
+        for (name, path) in &new_blobs {
+            job.check_cancelled()?;
+            tar.append_file(&format!("{FILES_PREFIX}{name}"), path).await?;
+            job.advance(1, None);
+        }
+        tar.finish().await?.into_inner().sync_all().await?;
+
+        tokio::fs::rename(&partial, dest).await
+            .with_context(|| format!("Writing {}", dest.display()))?;
+        let size = tokio::fs::metadata(dest).await?.len();
+        Ok(serde_json::json!({
+            "path":           dest,
+            "size":           size,
+            "files":          new_blobs.len(),
+            "incremental":    parent.is_some(),
+            "changed_chunks": if parent.is_some() { changed.len() } else { manifest.chunks.len() },
+            "total_chunks":   manifest.chunks.len(),
+        }))
+    }.await;
+
+    let _ = tokio::fs::remove_file(&snapshot).await;
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&partial).await;
+    }
+    result
+}
+
+/// Verify the backup at `src` (with its base chain), restore its blobs and
+/// stage its database for the next launch.
+pub async fn stage_restore(app: &AppHandle, src: &Path, job: &JobHandle) -> Result<serde_json::Value> {
+    let data_dir  = app.path().app_data_dir()?;
+    let files_dir = cas::files_dir(app)?;
+    let incoming  = data_dir.join(format!(".restore-{}.db", uuid::Uuid::new_v4()));
+
+    let result = async {
+        let mut files = 0u64;
+        let mut missing = 0usize;
+        if is_archive(src).await? {
+            let chain = load_chain(src).await?;
+            job.set_total(chain.iter().map(|(_, m)| m.as_ref().map_or(0, |m| m.files)).sum());
+
+            let mut has_db = false;
+            for (path, manifest) in &chain {
+                let mut tar = open_archive(path).await?;
+                while let Some(entry) = tar.next_entry().await? {
+                    job.check_cancelled()?;
+                    if entry.name == DB_ENTRY {
+                        let mut out = tokio::fs::File::create(&incoming).await?;
+                        tar.copy_entry(&mut out).await?;
+                        out.sync_all().await?;
+                        has_db = true;
+                    } else if let Some(i) = chunk_index(&entry.name) {
+                        if !has_db {
+                            bail!("{} has database pages but its base has no database", path.display());
+                        }
+                        let mut out = tokio::fs::OpenOptions::new().write(true).open(&incoming).await?;
+                        out.seek(SeekFrom::Start(i * DB_CHUNK)).await?;
+                        tar.copy_entry(&mut out).await?;
+                        out.sync_all().await?;
+                    } else if let Some(name) = entry.name.strip_prefix(FILES_PREFIX) {
+                        restore_blob(&files_dir, name, &mut tar).await?;
+                        files += 1;
+                        job.advance(1, None);
+                    }
+                }
+                if let Some(m) = manifest.as_ref().filter(|m| m.parent.is_some()) {
+                    tokio::fs::OpenOptions::new().write(true).open(&incoming).await?.set_len(m.db_size).await?;
+                }
+            }
+            if !has_db {
+                bail!("Backup archive has no {DB_ENTRY}");
+            }
+
+            let last = chain.last().and_then(|(_, m)| m.as_ref());
+            if let Some(last) = last.filter(|m| !m.chunks.is_empty()) {
+                if hash_chunks(&incoming).await?.0 != last.chunks {
+                    bail!("Rebuilt database does not match the backup manifest");
+                }
+                missing = last.blobs.iter()
+                    .filter(|name| archive::safe_relative_path(name).map_or(true, |rel| !files_dir.join(rel).exists()))
+                    .count();
+            }
+        } else {
+            tokio::fs::copy(src, &incoming).await?;
+        }
+
+        job.advance(0, Some("Verifying database".into()));
+        let version = verify_db(&incoming).await?;
+        tokio::fs::rename(&incoming, with_suffix(&data_dir.join(DB_ENTRY), RESTORE_SUFFIX)).await?;
+
+        Ok(serde_json::json!({
+            "schema_version":   version,
+            "files":            files,
+            "missing_files":    missing,
+            "restart_required": true,
+        }))
+    }.await;
+
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&incoming).await;
+    }
+    result
+}
+
+/// Check that the backup at `src` (and its base chain) is complete and
+/// intact without restoring anything: database chunks and blobs against
+/// their hashes, and every blob the manifest lists present somewhere in the
+/// chain. A plain database backup gets integrity_check on a temporary copy.
+pub async fn verify(app: &AppHandle, src: &Path, job: &JobHandle) -> Result<BackupVerification> {
+    let mut report = BackupVerification { chain: vec![src.to_path_buf()], ..Default::default() };
+
+    if !is_archive(src).await? {
+        let copy = app.path().app_data_dir()?.join(format!(".verify-{}.db", uuid::Uuid::new_v4()));
+        tokio::fs::copy(src, &copy).await?;
+        let outcome = verify_db(&copy).await;
+        let _ = tokio::fs::remove_file(&copy).await;
+        match outcome {
+            Ok(version) => report.schema_version = Some(version),
+            Err(e)      => report.problems.push(e.to_string()),
+        }
+        report.ok = report.problems.is_empty();
+        return Ok(report);
+    }
+
+    let chain = match load_chain(src).await {
+        Ok(chain) => chain,
+        Err(e) => {
+            report.problems.push(format!("{e:#}"));
+            return Ok(report);
+        }
+    };
+    report.chain = chain.iter().map(|(p, _)| p.clone()).collect();
+    job.set_total(chain.iter().map(|(_, m)| m.as_ref().map_or(0, |m| m.files)).sum());
+
+    let mut db: Vec<String> = Vec::new();
+    let mut has_db  = false;
+    let mut present = HashSet::new();
+    for (path, manifest) in &chain {
+        let mut tar = open_archive(path).await?;
+        while let Some(entry) = tar.next_entry().await? {
+            job.check_cancelled()?;
+            if entry.name == DB_ENTRY {
+                let mut hasher = ChunkHasher::new(DB_CHUNK);
+                tar.copy_entry(&mut hasher).await?;
+                db = hasher.finish();
+                has_db = true;
+            } else if let Some(i) = chunk_index(&entry.name) {
+                let mut hasher = ChunkHasher::new(DB_CHUNK);
+                tar.copy_entry(&mut hasher).await?;
+                let i = i as usize;
+                if db.len() <= i {
+                    db.resize(i + 1, String::new());
+                }
+                db[i] = hasher.finish().pop().unwrap_or_default();
+            } else if let Some(name) = entry.name.strip_prefix(FILES_PREFIX) {
+                match check_blob(name, &mut tar).await {
+                    Ok(true)  => { present.insert(name.to_string()); }
+                    Ok(false) => report.corrupt_files.push(name.to_string()),
+                    Err(e)    => report.problems.push(format!("{name}: {e}")),
+                }
+                report.files_checked += 1;
+                job.advance(1, None);
+            }
+        }
+
+        if let Some(m) = manifest.as_ref().filter(|m| !m.chunks.is_empty()) {
+            db.truncate(m.chunks.len());
+            if db != m.chunks {
+                report.problems.push(format!("Database pages in {} do not match its manifest", path.display()));
+            }
+        }
+    }
+
+    let last = chain.last().and_then(|(_, m)| m.as_ref());
+    if !has_db {
+        report.problems.push(format!("Backup has no {DB_ENTRY}"));
+    }
+    if let Some(last) = last {
+        report.schema_version = Some(last.schema_version);
+        if last.schema_version > schema::latest_version() {
+            report.problems.push(format!("Backup is from a newer version of the app (schema v{})", last.schema_version));
+        }
+        report.missing_files = last.blobs.iter().filter(|b| !present.contains(*b)).cloned().collect();
+    }
+    report.db_chunks = db.len();
+    report.ok = report.problems.is_empty() && report.missing_files.is_empty() && report.corrupt_files.is_empty();
+    log::info!(
+        "[backup] Verified {} ({} archives): {}",
+        src.display(), report.chain.len(), if report.ok { "ok" } else { "problems found" },
+    );
+    Ok(report)
+}
+
+/// Called before the database is opened: swap in a staged restore, if any.
+/// Returns true if one was applied.
+pub fn apply_pending_restore(db_path: &Path) -> Result<bool> {
+    let staged = with_suffix(db_path, RESTORE_SUFFIX);
+    if !staged.exists() {
+        return Ok(false);
+    }
+
+    // The old database's WAL/SHM must not be replayed into the restored file
+    let old = with_suffix(db_path, PRE_RESTORE_SUFFIX);
+    for ext in ["", "-wal", "-shm"] {
+        let from = PathBuf::from(format!("{}{ext}", db_path.display()));
+        let to   = PathBuf::from(format!("{}{ext}", old.display()));
+        let _ = std::fs::remove_file(&to);
+        if from.exists() {
+            std::fs::rename(&from, &to)?;
+        }
+    }
+    std::fs::rename(&staged, db_path)?;
+    log::info!("[backup] Restored database from staged backup (previous kept as {})", old.display());
+    Ok(true)
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+/// VACUUM INTO gives a consistent, compacted copy without blocking writers
+/// for longer than the copy itself. The copy gets the live database's key.
+async fn snapshot_db(app: &AppHandle, path: &Path) -> Result<()> {
+    let conn = db::connect(&app.state::<crate::AppState>().db).await?;
+    let target = path.to_str().context("Non-UTF-8 data dir")?;
+    conn.execute("VACUUM INTO ?1", libsql::params![target]).await?;
+    encryption::open_copy(target).await?;
+    Ok(())
+}
+
+/// integrity_check must pass and the schema must not be newer than this
+/// build's migrations. Returns the backup's schema version.
+async fn verify_db(path: &Path) -> Result<i64> {
+    let db   = encryption::open_copy(path.to_str().context("Non-UTF-8 data dir")?).await?;
+    let conn = db.connect()?;
+
+    let problems = stats::integrity_problems(&conn).await?;
+    if !problems.is_empty() {
+        anyhow::bail!("Backup failed integrity check: {}", problems.join("; "));
+    }
+
+    let version = schema::current_version(&conn).await?;
+    if version > schema::latest_version() {
+        anyhow::bail!("Backup is from a newer version of the app (schema v{version})");
+    }
+    Ok(version)
+}
+
+/// Extract one blob, checking its content against the hash it's named by.
+pub(crate) async fn restore_blob<R>(files_dir: &Path, name: &str, tar: &mut TarReader<R>) -> Result<()>
+where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    let rel  = archive::safe_relative_path(name)?;
+    let file = rel.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    let hash = cas::blob_hash(file);
+    if !cas::is_sha256_hex(hash) {
+        anyhow::bail!("Unexpected file in backup: {name}");
+    }
+    let compressed = file != hash;
+    let dest = if compressed { cas::compressed_blob_path(files_dir, hash) } else { cas::blob_path(files_dir, hash) };
+    if tokio::fs::metadata(&dest).await.is_ok() {
+        return Ok(());
+    }
+
+    tokio::fs::create_dir_all(files_dir).await?;
+    let tmp = files_dir.join(format!(".tmp-{}", uuid::Uuid::new_v4()));
+    let result = async {
+        let mut out = tokio::fs::File::create(&tmp).await?;
+        tar.copy_entry(&mut out).await?;
+        out.sync_all().await?;
+        if compressed {
+            // Named after the plain content, so check that
+            let mut hasher = UnpackHasher::new()?;
+            tokio::io::copy(&mut tokio::fs::File::open(&tmp).await?, &mut hasher).await?;
+            if hasher.finish()? != hash {
+                anyhow::bail!("Corrupt file in backup: {name}");
+            }
+            tokio::fs::create_dir_all(dest.parent().expect("blob has parent")).await?;
+            tokio::fs::rename(&tmp, &dest).await?;
+            return Ok(dest);
+        }
+        if cas::hash_file(&tmp).await? != hash {
+            anyhow::bail!("Corrupt file in backup: {name}");
+        }
+        cas::place(files_dir, &tmp, hash).await
+    }.await;
+
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&tmp).await;
+    }
+    result.map(|_| ())
+}
+
+/// (archive name "<first2>/<hash>", path) of every blob in the store.
+async fn list_blobs(files_dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+    let mut out = Vec::new();
+    let Ok(mut shards) = tokio::fs::read_dir(files_dir).await else { return Ok(out) };
+    while let Some(shard) = shards.next_entry().await? {
+        if !shard.file_type().await?.is_dir() {
+            continue;
+        }
+        let mut blobs = tokio::fs::read_dir(shard.path()).await?;
+        while let Some(blob) = blobs.next_entry().await? {
+            let path = blob.path();
+            if cas::is_blob_path(files_dir, &path) {
+                let name = format!("{}/{}", shard.file_name().to_string_lossy(), blob.file_name().to_string_lossy());
+                out.push((name, path));
+            }
+        }
+    }
+    Ok(out)
+}
+
+async fn is_archive(path: &Path) -> Result<bool> {
+    let mut head = vec![0u8; 512];
+    let n = tokio::fs::File::open(path).await
+        .with_context(|| format!("Opening {}", path.display()))?
+        .read(&mut head).await?;
+    Ok(archive::is_tar(&head[..n]))
+}
+
+async fn open_archive(path: &Path) -> Result<TarReader<tokio::io::BufReader<tokio::fs::File>>> {
+    let file = tokio::fs::File::open(path).await.with_context(|| format!("Opening {}", path.display()))?;
+    Ok(TarReader::new(tokio::io::BufReader::new(file)))
+}
+
+/// The manifest of an archive backup; None for archives without one.
+async fn read_manifest(path: &Path) -> Result<Option<BackupManifest>> {
+    if !is_archive(path).await? {
+        return Ok(None);
+    }
+    let mut tar = open_archive(path).await?;
+    match tar.next_entry().await? {
+        Some(e) if e.name == MANIFEST_ENTRY => {}
+        _ => return Ok(None),
+    }
+    let manifest: BackupManifest = serde_json::from_slice(&tar.read_entry(archive::MAX_MANIFEST).await?)
+        .with_context(|| format!("Unreadable manifest in {}", path.display()))?;
+    if manifest.format > FORMAT_VERSION {
+        bail!("Backup was made by a newer version of the app");
+    }
+    Ok(Some(manifest))
+}
+
+/// `src` and its bases, base first. Each base must sit next to the archive
+/// that names it and carry the id it names.
+async fn load_chain(src: &Path) -> Result<Vec<(PathBuf, Option<BackupManifest>)>> {
+    let mut chain = vec![(src.to_path_buf(), read_manifest(src).await?)];
+    while let Some(parent) = chain.last().and_then(|(_, m)| m.as_ref()).and_then(|m| m.parent.clone()) {
+        if chain.len() >= MAX_CHAIN {
+            bail!("Backup chain is longer than {MAX_CHAIN} archives");
+        }
+        let dir  = chain.last().and_then(|(p, _)| p.parent().map(Path::to_path_buf)).unwrap_or_default();
+        let path = dir.join(archive::safe_relative_path(&parent.file)?);
+        if !path.exists() {
+            bail!("Incremental backup needs its base {} next to it", parent.file);
+        }
+        let manifest = read_manifest(&path).await?;
+        if manifest.as_ref().and_then(|m| m.id.as_deref()) != Some(parent.id.as_str()) {
+            bail!("{} is not the base this backup was made from", path.display());
+        }
+        chain.push((path, manifest));
+    }
+    chain.reverse();
+    Ok(chain)
+}
+
+fn chunk_index(name: &str) -> Option<u64> {
+    name.strip_prefix(DB_CHUNK_PREFIX)?.parse().ok()
+}
+
+/// sha256 of each DB_CHUNK of `path`, and its size.
+async fn hash_chunks(path: &Path) -> Result<(Vec<String>, u64)> {
+    let mut file   = tokio::fs::File::open(path).await?;
+    let mut hasher = ChunkHasher::new(DB_CHUNK);
+    let size = tokio::io::copy(&mut file, &mut hasher).await?;
+    Ok((hasher.finish(), size))
+}
+
+/// Whether a blob entry's content matches the hash it's named by.
+async fn check_blob<R>(name: &str, tar: &mut TarReader<R>) -> Result<bool>
+where
+    R: AsyncRead + Unpin,
+{
+    let file = name.rsplit('/').next().unwrap_or_default();
+    let hash = cas::blob_hash(file);
+    if !cas::is_sha256_hex(hash) {
+        bail!("Unexpected file in backup");
+    }
+    let actual = if file != hash {
+        let mut hasher = UnpackHasher::new()?;
+        tar.copy_entry(&mut hasher).await?;
+        hasher.finish()?
+    } else {
+        let mut hasher = ChunkHasher::new(u64::MAX);
+        tar.copy_entry(&mut hasher).await?;
+        hasher.finish().pop().unwrap_or_default()
+    };
+    Ok(actual == hash)
+}
+
+/// A sink hashing what is written to it in `size`-byte chunks.
+struct ChunkHasher {
+    size:    u64,
+    filled:  u64,
+    current: Sha256,
+    hashes:  Vec<String>,
+}
+
+impl ChunkHasher {
+    fn new(size: u64) -> Self {
+        Self { size, filled: 0, current: Sha256::new(), hashes: Vec::new() }
+    }
+
+    /// Hashes of every chunk; the last may be short. Empty input is one
+    /// empty chunk.
+    fn finish(mut self) -> Vec<String> {
+        if self.filled > 0 || self.hashes.is_empty() {
+            self.hashes.push(cas::hex(&self.current.finalize()));
+        }
+        self.hashes
+    }
+}
+
+impl AsyncWrite for ChunkHasher {
+    fn poll_write(mut self: Pin<&mut Self>, _: &mut TaskContext<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        let mut rest = buf;
+        while !rest.is_empty() {
+            let take = ((self.size - self.filled) as usize).min(rest.len());
+            self.current.update(&rest[..take]);
+            self.filled += take as u64;
+            rest = &rest[take..];
+            if self.filled == self.size {
+                let done = std::mem::replace(&mut self.current, Sha256::new());
+                self.hashes.push(cas::hex(&done.finalize()));
+                self.filled = 0;
+            }
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut TaskContext<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut TaskContext<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// A sink decompressing a zstd blob as it's written and hashing the plain
+/// content, so a packed blob is checked without holding it in memory.
+struct UnpackHasher(zstd::stream::write::Decoder<'static, Sha256>);
+
+impl UnpackHasher {
+    fn new() -> Result<Self> {
+        Ok(Self(zstd::stream::write::Decoder::new(Sha256::new())?))
+    }
+
+    /// sha256 of the plain content.
+    fn finish(mut self) -> Result<String> {
+        std::io::Write::flush(&mut self.0)?;
+        Ok(cas::hex(&self.0.into_inner().finalize()))
+    }
+}
+
+impl AsyncWrite for UnpackHasher {
+    fn poll_write(mut self: Pin<&mut Self>, _: &mut TaskContext<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        Poll::Ready(std::io::Write::write(&mut self.0, buf))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut TaskContext<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut TaskContext<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// "<path>.<suffix>" — Path::with_extension would replace ".db".
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    PathBuf::from(format!("{}.{suffix}", path.display()))
+}
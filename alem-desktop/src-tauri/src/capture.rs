@@ -0,0 +1,272 @@
+// src-tauri/src/capture.rs
+// Quick capture: documents made from the system clipboard or a web address
+// rather than a file. Both go through import::import_bytes, so a capture is
+// indexed, scanned and uploaded like any import. A page keeps its address,
+// title and description in metadata.
+//
+// Captures can also be asked for from outside the app, e.g. by a browser
+// bookmarklet, as a deep link:
+//
+//     alem://capture?url=<percent-encoded address>
+//
+// Any web page can open such a link, and whatever the machine can reach
+// (localhost, the intranet) would end up in the library and on the server.
+// So a link only asks: it becomes a "capture-requested" event, and the
+// capture happens once the user confirms it in the app
+// (create_document_from_url). A bad link is reported as "capture-failed".
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::json;
+use std::path::Path;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Url};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use crate::commands::import::{self, ImportOptions};
+use crate::http::{self, SendRetrying};
+
+pub const DEEP_LINK_SCHEME: &str = "alem";
+/// Larger downloads are refused rather than held in memory.
+const MAX_CAPTURE_BYTES: usize = 200 * 1024 * 1024;
+/// How much of a page is searched for its title and meta tags.
+const HEAD_BYTES: usize = 256 * 1024;
+/// Extensions tried when a download's name has none (see content_type_for).
+const EXTENSIONS: &[&str] = &[
+    "pdf", "png", "jpg", "gif", "webp", "heic", "tiff", "txt", "md", "csv", "json",
+    "mp4", "mov", "mp3", "m4a", "doc", "docx",
+];
+
+/// A document from the clipboard: its text as a .txt file, or failing that
+/// its image as a PNG. Returns the document id.
+pub async fn from_clipboard(app: &AppHandle) -> Result<String> {
+    let stamp     = chrono::Local::now().format("%Y-%m-%d %H.%M.%S");
+    let metadata  = json!({ "captured_from": "clipboard", "captured_at": chrono::Utc::now().to_rfc3339() });
+    let clipboard = app.clipboard();
+
+    if let Some(text) = clipboard.read_text().ok().filter(|t| !t.trim().is_empty()) {
+        let filename = format!("Clipboard {stamp}.txt");
+        return import::import_bytes(app, &filename, text.as_bytes(), metadata, &ImportOptions::default()).await;
+    }
+    let image = clipboard.read_image().map_err(|_| anyhow!("The clipboard holds no text or image"))?;
+    let png = encode_png(image.rgba(), image.width(), image.height())?;
+    let filename = format!("Clipboard {stamp}.png");
+    import::import_bytes(app, &filename, &png, metadata, &ImportOptions::default()).await
+}
+
+/// A document from what `url` (http or https) serves. Returns the document id.
+pub async fn from_url(app: &AppHandle, url: &str) -> Result<String> {
+    let parsed = Url::parse(url.trim()).context("Not a valid URL")?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        bail!("Only http and https addresses can be captured");
+    }
+    let client = http::client(Duration::from_secs(120))?;
+    let mut resp = client.get(parsed.clone()).send_retrying().await?.error_for_status()?;
+    if resp.content_length().is_some_and(|n| n > MAX_CAPTURE_BYTES as u64) {
+        bail!("{url} is larger than {} MB", MAX_CAPTURE_BYTES / (1024 * 1024));
+    }
+
+    let final_url = resp.url().clone();
+    let header = |name: reqwest::header::HeaderName| {
+        resp.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string)
+    };
+    let content_type = header(reqwest::header::CONTENT_TYPE)
+        .and_then(|t| t.split(';').next().map(|t| t.trim().to_lowercase()))
+        .unwrap_or_default();
+    let attachment = header(reqwest::header::CONTENT_DISPOSITION).as_deref().and_then(disposition_filename);
+
+    let mut bytes = Vec::new();
+    while let Some(chunk) = resp.chunk().await? {
+        bytes.extend_from_slice(&chunk);
+        if bytes.len() > MAX_CAPTURE_BYTES {
+            bail!("{url} is larger than {} MB", MAX_CAPTURE_BYTES / (1024 * 1024));
+        }
+    }
+
+    let mut metadata = json!({
+        "captured_from": "url",
+        "captured_at":   chrono::Utc::now().to_rfc3339(),
+        "source_url":    parsed.as_str(),
+    });
+    if final_url != parsed {
+        metadata["final_url"] = final_url.as_str().into();
+    }
+    let host = final_url.host_str().unwrap_or("capture").to_string();
+    let last_segment = final_url.path_segments()
+        .and_then(|mut s| s.next_back())
+        .filter(|s| !s.is_empty())
+        .map(str::to_string);
+
+    let filename = if matches!(content_type.as_str(), "text/html" | "application/xhtml+xml") {
+        let page = page_info(&String::from_utf8_lossy(&bytes));
+        for (key, value) in [
+            ("title", &page.title),
+            ("description", &page.description),
+            ("author", &page.author),
+            ("site_name", &page.site_name),
+            ("canonical_url", &page.canonical_url),
+        ] {
+            if let Some(v) = value {
+                metadata[key] = v.as_str().into();
+            }
+        }
+        format!("{}.html", clean_filename(page.title.as_deref().unwrap_or(&host)))
+    } else {
+        let mut name = clean_filename(&attachment.or(last_segment).unwrap_or(host));
+        if Path::new(&name).extension().is_none() {
+            if let Some(ext) = extension_for(&content_type) {
+                name = format!("{name}.{ext}");
+            }
+        }
+        name
+    };
+
+    import::import_bytes(app, &filename, &bytes, metadata, &ImportOptions::default()).await
+}
+
+/// Act on alem:// links the app was opened with: a capture link is passed
+/// on for the user to confirm, never captured here.
+pub fn handle_deep_links(app: &AppHandle, urls: Vec<Url>) {
+    for link in urls.into_iter().filter(|u| u.scheme() == DEEP_LINK_SCHEME) {
+        let request = match (link.host_str(), link.path()) {
+            (Some("capture"), "" | "/") => match link.query_pairs().find(|(k, _)| k == "url") {
+                Some((_, target)) => match Url::parse(target.trim()) {
+                    Ok(url) if matches!(url.scheme(), "http" | "https") => Ok(url),
+                    _ => Err("Only http and https addresses can be captured"),
+                },
+                None => Err("Capture link has no url parameter"),
+            },
+            _ => Err("Unknown link"),
+        };
+        match request {
+            Ok(url) => {
+                log::info!("[capture] Capture of {url} requested by a link");
+                let _ = app.emit("capture-requested", json!({ "url": url.as_str(), "link": link.as_str() }));
+            }
+            Err(e) => {
+                log::warn!("[capture] {link}: {e}");
+                let _ = app.emit("capture-failed", json!({ "link": link.as_str(), "error": e }));
+            }
+        }
+    }
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+fn encode_png(rgba: &[u8], width: u32, height: u32) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut out, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(rgba)?;
+    }
+    Ok(out)
+}
+
+/// `filename="…"` of a Content-Disposition header.
+fn disposition_filename(header: &str) -> Option<String> {
+    header.split(';')
+        .filter_map(|part| part.trim().strip_prefix("filename="))
+        .map(|name| name.trim_matches('"').to_string())
+        .find(|name| !name.is_empty())
+}
+
+fn extension_for(content_type: &str) -> Option<&'static str> {
+    EXTENSIONS.iter().copied().find(|ext| import::content_type_for(&format!("x.{ext}")) == content_type)
+}
+
+/// A page title (or URL part) usable as a filename.
+fn clean_filename(name: &str) -> String {
+    let name: String = name.chars()
+        .filter(|c| !c.is_control())
+        .map(|c| if matches!(c, '/' | '\\') { '-' } else { c })
+        .take(120)
+        .collect();
+    match name.trim() {
+        "" => "capture".into(),
+        n  => n.to_string(),
+    }
+}
+
+#[derive(Default)]
+struct PageInfo {
+    title:         Option<String>,
+    description:   Option<String>,
+    author:        Option<String>,
+    site_name:     Option<String>,
+    canonical_url: Option<String>,
+}
+
+/// Title and the usual meta tags of an HTML page, read from its head.
+/// Open Graph values win over plain ones.
+fn page_info(html: &str) -> PageInfo {
+    let mut end = html.len().min(HEAD_BYTES);
+    while !html.is_char_boundary(end) {
+        end -= 1;
+    }
+    let head  = &html[..end];
+    // ASCII lowercasing keeps byte offsets, so positions carry over to `head`
+    let lower = head.to_ascii_lowercase();
+    let mut page = PageInfo::default();
+
+    if let Some(open) = lower.find("<title") {
+        if let Some(start) = lower[open..].find('>').map(|i| open + i + 1) {
+            if let Some(close) = lower[start..].find("</title").map(|i| start + i) {
+                page.title = Some(decode_entities(head[start..close].trim())).filter(|t| !t.is_empty());
+            }
+        }
+    }
+
+    for (tag_start, _) in lower.match_indices("<meta").chain(lower.match_indices("<link")) {
+        let Some(tag_end) = lower[tag_start..].find('>').map(|i| tag_start + i) else { continue };
+        let tag = &head[tag_start..tag_end];
+        if tag.len() >= 5 && tag[..5].eq_ignore_ascii_case("<link") {
+            if attr(tag, "rel").is_some_and(|r| r.eq_ignore_ascii_case("canonical")) {
+                page.canonical_url = attr(tag, "href");
+            }
+            continue;
+        }
+        let Some(key) = attr(tag, "property").or_else(|| attr(tag, "name")) else { continue };
+        let Some(content) = attr(tag, "content").filter(|c| !c.is_empty()) else { continue };
+        match key.to_ascii_lowercase().as_str() {
+            "og:title"                   => page.title = Some(content),
+            "og:description"             => page.description = Some(content),
+            "description"                => { page.description.get_or_insert(content); }
+            "author" | "article:author"  => { page.author.get_or_insert(content); }
+            "og:site_name"               => page.site_name = Some(content),
+            _ => {}
+        }
+    }
+    page
+}
+
+/// The value of attribute `name` in an HTML tag.
+fn attr(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(i) = lower[from..].find(name) {
+        let at = from + i;
+        from = at + name.len();
+        // A whole attribute name, not the end of a longer one
+        if !lower[..at].ends_with(char::is_whitespace) {
+            continue;
+        }
+        let Some(rest) = tag[from..].trim_start().strip_prefix('=') else { continue };
+        let rest = rest.trim_start();
+        let value = match rest.chars().next()? {
+            q @ ('"' | '\'') => rest[1..].split(q).next()?,
+            _                => rest.split(|c: char| c.is_whitespace() || c == '>').next()?,
+        };
+        return Some(decode_entities(value.trim()));
+    }
+    None
+}
+
+fn decode_entities(s: &str) -> String {
+    s.replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
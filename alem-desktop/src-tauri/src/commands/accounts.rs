@@ -0,0 +1,132 @@
+// src-tauri/src/commands/accounts.rs
+// Accounts on one or more servers, each signed in on its own (see
+// db/accounts.rs). start_oauth_login or store_oauth_token adds one.
+//
+// A self-hosted server can have its certificate pinned (tls.rs); the pin is
+// shared by every account on the server.
+use crate::{
+    db::{self, accounts, models::{Account, PinKind, ServerCertificate, ServerPin}, pins},
+    oauth, tls, AppState,
+};
+use tauri::{AppHandle, Emitter, State};
+
+/// Every account this install has signed in to, the active one first.
+#[tauri::command]
+pub async fn list_accounts(state: State<'_, AppState>) -> Result<Vec<Account>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let mut list = accounts::list(&conn).await.map_err(|e| e.to_string())?;
+    for account in &mut list {
+        account.signed_in = oauth::has_tokens(&account.id).map_err(|e| e.to_string())?;
+    }
+    Ok(list)
+}
+
+/// Make `account_id` the active account. Its server, identity, tenants and
+/// sync queue come into use and it syncs right away; the others stay signed
+/// in. Emits "account-changed" with the account id.
+#[tauri::command]
+pub async fn switch_account(
+    account_id: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Vec<Account>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    accounts::switch(&conn, &account_id).await.map_err(|e| e.to_string())?;
+    log::info!("[accounts] Switched to {account_id}");
+    let _ = app.emit("account-changed", &account_id);
+
+    let sync_app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = crate::sync::engine::run_once(&sync_app).await {
+            log::warn!("[sync] Sync after account switch failed: {e}");
+        }
+    });
+    list_accounts(state).await
+}
+
+/// The certificate the server at `server_url` (the active account's when
+/// None) presents, and its pin if it has one.
+#[tauri::command]
+pub async fn get_server_certificate(
+    server_url: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<ServerCertificate, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let url = server(&conn, server_url).await?;
+    let presented = tls::inspect(&url).await.map_err(|e| format!("{e:#}"))?;
+    let pin = pins::get(&conn, &presented.host).await.map_err(|e| e.to_string())?;
+    Ok(certificate(presented, pin))
+}
+
+/// Pin the server's certificate: from now on it is trusted by `fingerprint`
+/// alone, the certificate's SHA-256 or its public key's ("sha256/<base64>").
+/// The server has to be presenting that certificate now, so a fingerprint
+/// compared out of band is checked against what is really there. Replaces
+/// an earlier pin.
+#[tauri::command]
+pub async fn trust_server_certificate(
+    fingerprint: String,
+    server_url: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<ServerCertificate, String> {
+    let (kind, fingerprint) = tls::normalize(&fingerprint)
+        .ok_or("Expected a SHA-256 certificate fingerprint or sha256/<base64> public key pin")?;
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let url = server(&conn, server_url).await?;
+    let presented = tls::inspect(&url).await.map_err(|e| format!("{e:#}"))?;
+    let current = match kind {
+        PinKind::Certificate => &presented.certificate_fingerprint,
+        PinKind::PublicKey   => &presented.public_key_fingerprint,
+    };
+    if *current != fingerprint {
+        return Err(format!("{} presents a different certificate ({current})", presented.host));
+    }
+
+    let pin = pins::set(&conn, &presented.host, kind, &fingerprint).await.map_err(|e| e.to_string())?;
+    tls::set_pins(pins::list(&conn).await.map_err(|e| e.to_string())?);
+    log::info!("[tls] Pinned {} to {fingerprint}", presented.host);
+    Ok(certificate(presented, Some(pin)))
+}
+
+/// Stop pinning the server's certificate; it has to chain to a trusted root
+/// again. Returns false when it wasn't pinned.
+#[tauri::command]
+pub async fn untrust_server_certificate(
+    server_url: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let url = server(&conn, server_url).await?;
+    let host = tls::host_key(&url).ok_or_else(|| format!("No host in {url}"))?;
+    let removed = pins::remove(&conn, &host).await.map_err(|e| e.to_string())?;
+    tls::set_pins(pins::list(&conn).await.map_err(|e| e.to_string())?);
+    if removed {
+        log::info!("[tls] Unpinned {host}");
+    }
+    Ok(removed)
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+async fn server(conn: &libsql::Connection, server_url: Option<String>) -> Result<reqwest::Url, String> {
+    let url = match server_url {
+        Some(url) => url,
+        None      => crate::sync::engine::query_server_url(conn).await,
+    };
+    reqwest::Url::parse(url.trim()).map_err(|e| format!("Invalid server URL {url}: {e}"))
+}
+
+fn certificate(presented: tls::Presented, pin: Option<ServerPin>) -> ServerCertificate {
+    let matches_pin = pin.as_ref().is_some_and(|pin| match pin.kind {
+        PinKind::Certificate => pin.fingerprint == presented.certificate_fingerprint,
+        PinKind::PublicKey   => pin.fingerprint == presented.public_key_fingerprint,
+    });
+    ServerCertificate {
+        host:                    presented.host,
+        certificate_fingerprint: presented.certificate_fingerprint,
+        public_key_fingerprint:  presented.public_key_fingerprint,
+        trusted:                 presented.trusted,
+        pin,
+        matches_pin,
+    }
+}
@@ -0,0 +1,50 @@
+// src-tauri/src/commands/activity.rs
+use crate::{
+    db::{self, activity, identity, models::ActivityFeed, tenants},
+    AppState,
+};
+use tauri::{AppHandle, State};
+
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 200;
+
+/// Newest-first activity of `workspace` (a tenant id, the active tenant when
+/// omitted) since `since`: server events such as members joining and
+/// documents being shared, merged with what this device did. Pass the
+/// returned next_cursor to get the following page. The first page fetches new
+/// server events when online; if that fails the page comes from the cache and
+/// refresh_error says why.
+#[tauri::command]
+pub async fn get_activity_feed(
+    workspace: Option<String>,
+    since: Option<String>,
+    limit: Option<i64>,
+    cursor: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ActivityFeed, String> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let tenant_id = match workspace.map(|w| w.trim().to_string()).filter(|w| !w.is_empty()) {
+        Some(w) => w,
+        None    => identity::current(&conn).await.map_err(|e| e.to_string())?.1,
+    };
+    let known = tenants::list(&conn).await.map_err(|e| e.to_string())?;
+    if !known.iter().any(|t| t.id == tenant_id) {
+        return Err(format!("Unknown workspace {tenant_id}"));
+    }
+
+    let mut refresh_error = None;
+    if cursor.is_none() && state.connectivity.is_online() {
+        if let Err(e) = crate::sync::activity::refresh(&app, &tenant_id).await {
+            log::warn!("[activity] Refresh for {tenant_id} failed: {e}");
+            refresh_error = Some(e.to_string());
+        }
+    }
+
+    let mut feed = activity::feed(&conn, &tenant_id, since.as_deref(), limit, cursor.as_deref())
+        .await
+        .map_err(|e| e.to_string())?;
+    feed.refresh_error = refresh_error;
+    Ok(feed)
+}
@@ -0,0 +1,79 @@
+// src-tauri/src/commands/annotations.rs
+use crate::{
+    db::{
+        self,
+        annotations, identity,
+        models::{Annotation, AnnotationAnchor, AnnotationKind},
+    },
+    AppState,
+};
+use tauri::State;
+
+const MAX_BODY_BYTES: usize = 16 * 1024;
+const MAX_QUOTE_CHARS: usize = 1000;
+
+#[tauri::command]
+pub async fn list_annotations(doc_id: String, state: State<'_, AppState>) -> Result<Vec<Annotation>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    annotations::list(&conn, &doc_id).await.map_err(|e| e.to_string())
+}
+
+/// Comment on or highlight part of a document, as this device's DID. The
+/// annotation syncs to the document's other devices and collaborators.
+#[tauri::command]
+pub async fn add_annotation(
+    doc_id: String,
+    kind: AnnotationKind,
+    anchor: Option<AnnotationAnchor>,
+    body: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Annotation, String> {
+    let anchor = anchor.unwrap_or_default();
+    let body = body.unwrap_or_default();
+    check(Some(&anchor), Some(&body))?;
+    if kind == AnnotationKind::Comment && body.trim().is_empty() {
+        return Err("A comment needs some text".into());
+    }
+    let author = super::did::local_did(&state).await?;
+
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    super::links::ensure_live(&conn, &doc_id).await?;
+    let (user_id, _) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+    annotations::add(&conn, &user_id, &author, &doc_id, kind, &anchor, &body).await.map_err(|e| e.to_string())
+}
+
+/// Edit one of this DID's annotations; None leaves a field as it is.
+#[tauri::command]
+pub async fn update_annotation(
+    id: String,
+    anchor: Option<AnnotationAnchor>,
+    body: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Annotation, String> {
+    check(anchor.as_ref(), body.as_deref())?;
+    let author = super::did::local_did(&state).await?;
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let (user_id, _) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+    annotations::update(&conn, &user_id, &author, &id, anchor.as_ref(), body.as_deref())
+        .await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_annotation(id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let author = super::did::local_did(&state).await?;
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let (user_id, _) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+    annotations::delete(&conn, &user_id, &author, &id).await.map_err(|e| e.to_string())
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+fn check(anchor: Option<&AnnotationAnchor>, body: Option<&str>) -> Result<(), String> {
+    if body.is_some_and(|b| b.len() > MAX_BODY_BYTES) {
+        return Err(format!("Annotations are limited to {} KiB", MAX_BODY_BYTES / 1024));
+    }
+    if anchor.and_then(|a| a.quote.as_ref()).is_some_and(|q| q.chars().count() > MAX_QUOTE_CHARS) {
+        return Err(format!("Quoted text is limited to {MAX_QUOTE_CHARS} characters"));
+    }
+    Ok(())
+}
@@ -1,88 +1,130 @@
 // src-tauri/src/commands/auth.rs
-use crate::AppState;
-use keyring::Entry;
+use crate::{db, oauth::{self, OAuthClient, Tokens}, AppState};
 use serde::{Deserialize, Serialize};
-use tauri::State;
+use tauri::{AppHandle, State};
 
-const KEYRING_SERVICE: &str = "alem-desktop";
-const OAUTH_KEY: &str = "oauth_token";
+/// Allowed difference between our clock and the issuer's when checking
+/// exp/nbf.
+const CLOCK_SKEW_SECS: i64 = 60;
 
 #[derive(Serialize, Deserialize)]
 pub struct AuthResult {
     pub authenticated: bool,
     pub server_url: Option<String>,
     pub username: Option<String>,
+    /// RFC 3339 expiry of the access token (its exp claim, or the token
+    /// response's expires_in). None when unknown.
+    pub expires_at: Option<String>,
+    /// The server stopped accepting the tokens and they couldn't be
+    /// refreshed: signed out until the account signs in again (the
+    /// "auth:expired" event said so). server_url and username tell which.
+    pub session_expired: bool,
 }
 
+/// Keep the tokens from a sign-in. With `refresh_token` (and the OAuth
+/// `client` it was issued to, `{client_id, client_secret}`) the token is
+/// refreshed before it expires; see oauth.rs.
 #[tauri::command]
 pub async fn store_oauth_token(
     token: String,
     server_url: String,
     username: String,
+    refresh_token: Option<String>,
+    expires_in: Option<i64>,
+    client: Option<OAuthClient>,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
-    // Token → OS keychain only
-    let entry = Entry::new(KEYRING_SERVICE, OAUTH_KEY).map_err(|e| e.to_string())?;
-    entry.set_password(&token).map_err(|e| e.to_string())?;
+    let conn   = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let tokens = Tokens { access_token: token, refresh_token, expires_in };
+    oauth::signed_in(&conn, &server_url, &username, &tokens, client.as_ref())
+        .await.map_err(|e| e.to_string())?;
+    Ok(())
+}
 
-    // Non-sensitive info → libsql
-    let conn = state.db.connect().map_err(|e| e.to_string())?;
-    conn.execute(
-        "INSERT INTO local_identity (id, server_url, username, updated_at)
-         VALUES ('singleton', ?1, ?2, datetime('now'))
-         ON CONFLICT(id) DO UPDATE SET
-             server_url = excluded.server_url,
-             username   = excluded.username,
-             updated_at = datetime('now')",
-        libsql::params![server_url, username],
-    ).await.map_err(|e| e.to_string())?;
+/// Sign in to `server_url` in the system browser (OAuth with PKCE; see
+/// oauth.rs) and keep the tokens. Resolves once the browser comes back, or
+/// fails after five minutes or when another sign-in starts.
+#[tauri::command]
+pub async fn start_oauth_login(server_url: String, app: AppHandle) -> Result<AuthResult, String> {
+    let (username, expires) = oauth::login(&app, &server_url).await.map_err(|e| format!("{e:#}"))?;
+    Ok(AuthResult {
+        authenticated: true,
+        server_url:    Some(server_url.trim().trim_end_matches('/').to_string()),
+        username:      Some(username),
+        expires_at:    oauth::rfc3339(expires),
+        session_expired: false,
+    })
+}
 
-    Ok(())
+/// The access token, refreshed first when it is about to expire.
+#[tauri::command]
+pub async fn get_oauth_token(app: AppHandle) -> Result<Option<String>, String> {
+    oauth::access_token(&app).await.map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn get_oauth_token() -> Result<Option<String>, String> {
-    let entry = Entry::new(KEYRING_SERVICE, OAUTH_KEY).map_err(|e| e.to_string())?;
-    match entry.get_password() {
-        Ok(t)                          => Ok(Some(t)),
-        Err(keyring::Error::NoEntry)   => Ok(None),
-        Err(e)                         => Err(e.to_string()),
-    }
+pub async fn clear_oauth_token(state: State<'_, AppState>) -> Result<(), String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    oauth::clear(&conn).await.map_err(|e| e.to_string())
 }
 
+/// Refresh the access token now. Returns the new expiry (RFC 3339), when
+/// known; the "token_refreshed" event carries the same.
 #[tauri::command]
-pub async fn clear_oauth_token() -> Result<(), String> {
-    let entry = Entry::new(KEYRING_SERVICE, OAUTH_KEY).map_err(|e| e.to_string())?;
-    entry.delete_password().map_err(|e| e.to_string())?;
-        Ok(())
+pub async fn refresh_oauth_token(app: AppHandle, state: State<'_, AppState>) -> Result<Option<String>, String> {
+    let conn  = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let stale = oauth::stored(&conn).await.map_err(|e| e.to_string())?.ok_or("Not signed in")?;
+    let fresh = oauth::refresh(&app, &stale).await.map_err(|e| format!("{e:#}"))?;
+    let expires = oauth::expires_at(&conn, &fresh).await.map_err(|e| e.to_string())?;
+    Ok(oauth::rfc3339(expires))
 }
 
+/// A stored token counts only while it is valid, after a refresh if one is
+/// due: it is checked against its expiry and nbf claim (within
+/// CLOCK_SKEW_SECS). Tokens of unknown expiry never expire here, and none
+/// counts once the session has expired.
 #[tauri::command]
-pub async fn is_authenticated(state: State<'_, AppState>) -> Result<AuthResult, String> {
-    let token = match get_oauth_token().await? {
-        Some(t) if !t.is_empty() => t,
-        _ => return Ok(AuthResult { authenticated: false, server_url: None, username: None }),
+pub async fn is_authenticated(app: AppHandle, state: State<'_, AppState>) -> Result<AuthResult, String> {
+    let signed_out = AuthResult {
+        authenticated: false, server_url: None, username: None, expires_at: None, session_expired: false,
     };
-    let _ = token; // confirmed present
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let session_expired = oauth::session_expired(&conn).await.map_err(|e| e.to_string())?;
+    let token = match get_oauth_token(app).await? {
+        Some(t) if !t.is_empty() && !session_expired => t,
+        Some(t) if !t.is_empty() => {
+            let (server_url, username) = identity(&conn).await.map_err(|e| e.to_string())?.unwrap_or_default();
+            return Ok(AuthResult { server_url, username, session_expired, ..signed_out });
+        }
+        _ => return Ok(signed_out),
+    };
+    let not_before = oauth::token_validity(&token).0;
+    let expires    = oauth::expires_at(&conn, &token).await.map_err(|e| e.to_string())?;
+    let now = chrono::Utc::now().timestamp();
+    if expires.is_some_and(|exp| now >= exp + CLOCK_SKEW_SECS) || not_before.is_some_and(|nbf| now + CLOCK_SKEW_SECS < nbf) {
+        return Ok(signed_out);
+    }
+    let expires_at = oauth::rfc3339(expires);
 
-    let conn = state.db.connect().map_err(|e| e.to_string())?;
+    let Some((server_url, username)) = identity(&conn).await.map_err(|e| e.to_string())? else {
+        return Ok(signed_out);
+    };
+    Ok(AuthResult { authenticated: true, server_url, username, expires_at, session_expired: false })
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+/// The active account's server and username; None before the first sign-in.
+async fn identity(conn: &libsql::Connection) -> anyhow::Result<Option<(Option<String>, Option<String>)>> {
     let mut rows = conn.query(
         "SELECT server_url, username FROM local_identity WHERE id = 'singleton'",
         (),
-    ).await.map_err(|e| e.to_string())?;
-
-    if let Some(row) = rows.next().await.map_err(|e| e.to_string())? {
-        use libsql::Value;
-        let server_url = match row.get_value(0).ok() {
-            Some(Value::Text(s)) => Some(s),
-            _ => None,
-        };
-        let username = match row.get_value(1).ok() {
-            Some(Value::Text(s)) => Some(s),
-            _ => None,
-        };
-        Ok(AuthResult { authenticated: true, server_url, username })
-    } else {
-        Ok(AuthResult { authenticated: false, server_url: None, username: None })
-    }
-}
\ No newline at end of file
+    ).await?;
+    let Some(row) = rows.next().await? else { return Ok(None) };
+    use libsql::Value;
+    let text = |i| match row.get_value(i).ok() {
+        Some(Value::Text(s)) => Some(s),
+        _ => None,
+    };
+    Ok(Some((text(0), text(1))))
+}
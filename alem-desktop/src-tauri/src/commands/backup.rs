@@ -0,0 +1,59 @@
+// src-tauri/src/commands/backup.rs
+use crate::AppState;
+use std::path::PathBuf;
+use tauri::{AppHandle, State};
+
+/// Back up the database to `dest_path`, as a plain database file or, with
+/// `include_files`, a tar archive that also holds every stored file. With
+/// `base_path` (an earlier archive backup, full or incremental) only what
+/// changed since it is written; restoring needs the base next to it.
+/// Returns a job id; the job result is { path, size, files, incremental }.
+#[tauri::command]
+pub async fn backup_database(
+    dest_path: String,
+    include_files: Option<bool>,
+    base_path: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let job_app = app.clone();
+    Ok(state.jobs.spawn(&app, "backup", move |job| async move {
+        let base = base_path.map(PathBuf::from);
+        crate::backup::create(
+            &job_app,
+            &PathBuf::from(dest_path),
+            include_files.unwrap_or(false),
+            base.as_deref(),
+            &job,
+        ).await
+    }))
+}
+
+/// Check a backup (with its base chain) for completeness without restoring
+/// it. Returns a job id; the job result is a BackupVerification.
+#[tauri::command]
+pub async fn verify_backup(
+    path: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let job_app = app.clone();
+    Ok(state.jobs.spawn(&app, "verify_backup", move |job| async move {
+        let report = crate::backup::verify(&job_app, &PathBuf::from(path), &job).await?;
+        Ok(serde_json::to_value(report)?)
+    }))
+}
+
+/// Verify a backup and stage it; it replaces the current database on the
+/// next launch. Returns a job id; the job result has `restart_required`.
+#[tauri::command]
+pub async fn restore_database(
+    src_path: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let job_app = app.clone();
+    Ok(state.jobs.spawn(&app, "restore", move |job| async move {
+        crate::backup::stage_restore(&job_app, &PathBuf::from(src_path), &job).await
+    }))
+}
@@ -0,0 +1,296 @@
+// src-tauri/src/commands/collections.rs
+// Folder hierarchy for documents. Every change is queued as a sync op:
+// `sync_collection` for the collection row, `update_document` for moved docs,
+// `propagate_acl` for an ACL change (see db/acl.rs).
+use crate::{
+    db::{
+        self, acl, identity,
+        models::{AclChangeSet, AclEntry, Collection},
+        ops,
+    },
+    scan,
+    AppState,
+};
+use libsql::{Connection, Value};
+use serde_json::json;
+use tauri::State;
+use uuid::Uuid;
+
+#[tauri::command]
+pub async fn list_collections(state: State<'_, AppState>) -> Result<Vec<Collection>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let mut rows = conn.query(
+        "SELECT c.id, c.parent_id, c.name, c.created_at, c.updated_at,
+                (SELECT COUNT(*) FROM documents d
+                 WHERE d.collection_id = c.id AND d.status != 'deleted' AND d.trashed_at IS NULL)
+         FROM collections c ORDER BY c.name COLLATE NOCASE",
+        (),
+    ).await.map_err(|e| e.to_string())?;
+
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|e| e.to_string())? {
+        out.push(row_to_collection(&row));
+    }
+    Ok(out)
+}
+
+#[tauri::command]
+pub async fn create_collection(
+    name: String,
+    parent_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Collection, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let name = validate_name(&name)?;
+    if let Some(p) = &parent_id {
+        ensure_exists(&conn, p).await?;
+    }
+
+    let (user_id, tenant_id) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO collections (id, user_id, tenant_id, parent_id, name) VALUES (?1,?2,?3,?4,?5)",
+        libsql::params![id.clone(), user_id.clone(), tenant_id, parent_id, name],
+    ).await.map_err(|e| format!("Insert failed: {e}"))?;
+
+    ops::enqueue(&conn, &user_id, "sync_collection", json!({ "collection_id": id }))
+        .await.map_err(|e| format!("Queue op failed: {e}"))?;
+
+    get_collection(&conn, &id).await
+}
+
+#[tauri::command]
+pub async fn rename_collection(
+    id: String,
+    name: String,
+    state: State<'_, AppState>,
+) -> Result<Collection, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let name = validate_name(&name)?;
+    ensure_exists(&conn, &id).await?;
+
+    conn.execute(
+        "UPDATE collections SET name = ?1, updated_at = datetime('now') WHERE id = ?2",
+        libsql::params![name, id.clone()],
+    ).await.map_err(|e| format!("Update failed: {e}"))?;
+
+    queue_collection_sync(&conn, &id).await?;
+    get_collection(&conn, &id).await
+}
+
+/// Re-parent a collection (None = move to the root). Refuses to create cycles.
+#[tauri::command]
+pub async fn move_collection(
+    id: String,
+    parent_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Collection, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    ensure_exists(&conn, &id).await?;
+
+    if let Some(p) = &parent_id {
+        ensure_exists(&conn, p).await?;
+        // The new parent must not be the collection itself or one of its descendants
+        let mut rows = conn.query(
+            "WITH RECURSIVE sub(id) AS (
+                 SELECT ?1
+                 UNION
+                 SELECT c.id FROM collections c JOIN sub ON c.parent_id = sub.id
+             )
+             SELECT COUNT(*) FROM sub WHERE id = ?2",
+            libsql::params![id.clone(), p.clone()],
+        ).await.map_err(|e| e.to_string())?;
+        if count(&mut rows).await? > 0 {
+            return Err("Cannot move a collection into itself or one of its descendants".into());
+        }
+    }
+
+    conn.execute(
+        "UPDATE collections SET parent_id = ?1, updated_at = datetime('now') WHERE id = ?2",
+        libsql::params![parent_id, id.clone()],
+    ).await.map_err(|e| format!("Update failed: {e}"))?;
+
+    queue_collection_sync(&conn, &id).await?;
+    get_collection(&conn, &id).await
+}
+
+/// Delete a collection. Its documents and child collections move up to the
+/// deleted collection's parent — nothing is lost.
+#[tauri::command]
+pub async fn delete_collection(id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let conn   = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let parent = get_collection(&conn, &id).await?.parent_id;
+    let (user_id, _) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+
+    let children = ids(&conn, "SELECT id FROM collections WHERE parent_id = ?1", &id).await?;
+    let docs     = ids(&conn, "SELECT id FROM documents WHERE collection_id = ?1", &id).await?;
+
+    let tx = conn.transaction().await.map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE collections SET parent_id = ?1, updated_at = datetime('now') WHERE parent_id = ?2",
+        libsql::params![parent.clone(), id.clone()],
+    ).await.map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE documents SET collection_id = ?1, updated_at = datetime('now') WHERE collection_id = ?2",
+        libsql::params![parent, id.clone()],
+    ).await.map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM collections WHERE id = ?1", libsql::params![id.clone()])
+        .await.map_err(|e| e.to_string())?;
+
+    for c in children.iter().chain(std::iter::once(&id)) {
+        ops::enqueue(&tx, &user_id, "sync_collection", json!({ "collection_id": c }))
+            .await.map_err(|e| e.to_string())?;
+    }
+    for d in &docs {
+        ops::enqueue(&tx, &user_id, "update_document", json!({ "doc_id": d }))
+            .await.map_err(|e| e.to_string())?;
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Move documents into a collection (None = root / unfiled).
+#[tauri::command]
+pub async fn move_documents(
+    ids: Vec<String>,
+    collection_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<u64, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    if let Some(c) = &collection_id {
+        ensure_exists(&conn, c).await?;
+    }
+    let (user_id, _) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+
+    let tx = conn.transaction().await.map_err(|e| e.to_string())?;
+    let mut moved = 0;
+    for id in &ids {
+        moved += tx.execute(
+            "UPDATE documents
+             SET collection_id = ?1, local_version = local_version + 1, updated_at = datetime('now')
+             WHERE id = ?2 AND status != 'deleted'",
+            libsql::params![collection_id.clone(), id.clone()],
+        ).await.map_err(|e| e.to_string())?;
+        ops::enqueue(&tx, &user_id, "update_document", json!({ "doc_id": id }))
+            .await.map_err(|e| e.to_string())?;
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(moved)
+}
+
+#[tauri::command]
+pub async fn get_collection_acl(collection_id: String, state: State<'_, AppState>) -> Result<Vec<AclEntry>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    ensure_exists(&conn, &collection_id).await?;
+    acl::get(&conn, &collection_id).await.map_err(|e| e.to_string())
+}
+
+/// Replace a collection's ACL. Its documents (and those of subcollections
+/// without their own ACL) get the change as one queued change-set; follow it
+/// with get_acl_changeset or the "acl-propagation-progress" event.
+#[tauri::command]
+pub async fn set_collection_acl(
+    collection_id: String,
+    entries: Vec<AclEntry>,
+    state: State<'_, AppState>,
+) -> Result<AclChangeSet, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    ensure_exists(&conn, &collection_id).await?;
+    let (user_id, _) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+    if !entries.is_empty() {
+        scan::check_share(&conn, &collection_id).await.map_err(|e| e.to_string())?;
+    }
+
+    let tx = conn.transaction().await.map_err(|e| e.to_string())?;
+    let id = acl::set(&tx, &user_id, &collection_id, &entries).await.map_err(|e| e.to_string())?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    get_changeset(&conn, &id).await
+}
+
+#[tauri::command]
+pub async fn get_acl_changeset(id: String, state: State<'_, AppState>) -> Result<AclChangeSet, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    get_changeset(&conn, &id).await
+}
+
+/// Send the documents the server rejected in a change-set again.
+#[tauri::command]
+pub async fn retry_acl_changeset(id: String, state: State<'_, AppState>) -> Result<AclChangeSet, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let (user_id, _) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+    let tx = conn.transaction().await.map_err(|e| e.to_string())?;
+    acl::retry(&tx, &user_id, &id).await.map_err(|e| e.to_string())?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    get_changeset(&conn, &id).await
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+async fn get_changeset(conn: &Connection, id: &str) -> Result<AclChangeSet, String> {
+    acl::changeset(conn, id).await.map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("ACL change-set {id} not found"))
+}
+
+fn validate_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Collection name cannot be empty".into());
+    }
+    Ok(name.to_string())
+}
+
+async fn queue_collection_sync(conn: &Connection, id: &str) -> Result<(), String> {
+    let (user_id, _) = identity::current(conn).await.map_err(|e| e.to_string())?;
+    ops::enqueue(conn, &user_id, "sync_collection", json!({ "collection_id": id }))
+        .await.map_err(|e| format!("Queue op failed: {e}"))?;
+    Ok(())
+}
+
+pub(crate) async fn ensure_exists(conn: &Connection, id: &str) -> Result<(), String> {
+    get_collection(conn, id).await.map(|_| ())
+}
+
+async fn get_collection(conn: &Connection, id: &str) -> Result<Collection, String> {
+    let mut rows = conn.query(
+        "SELECT c.id, c.parent_id, c.name, c.created_at, c.updated_at,
+                (SELECT COUNT(*) FROM documents d
+                 WHERE d.collection_id = c.id AND d.status != 'deleted' AND d.trashed_at IS NULL)
+         FROM collections c WHERE c.id = ?1",
+        libsql::params![id],
+    ).await.map_err(|e| e.to_string())?;
+
+    match rows.next().await.map_err(|e| e.to_string())? {
+        Some(row) => Ok(row_to_collection(&row)),
+        None      => Err(format!("Collection {id} not found")),
+    }
+}
+
+fn row_to_collection(row: &libsql::Row) -> Collection {
+    let s = |i| match row.get_value(i).ok() { Some(Value::Text(s)) => Some(s), _ => None };
+    Collection {
+        id:             s(0).unwrap_or_default(),
+        parent_id:      s(1),
+        name:           s(2).unwrap_or_default(),
+        created_at:     s(3).unwrap_or_default(),
+        updated_at:     s(4).unwrap_or_default(),
+        document_count: match row.get_value(5).ok() { Some(Value::Integer(n)) => n, _ => 0 },
+    }
+}
+
+async fn ids(conn: &Connection, sql: &str, param: &str) -> Result<Vec<String>, String> {
+    let mut rows = conn.query(sql, libsql::params![param]).await.map_err(|e| e.to_string())?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|e| e.to_string())? {
+        if let Ok(Value::Text(s)) = row.get_value(0) { out.push(s); }
+    }
+    Ok(out)
+}
+
+async fn count(rows: &mut libsql::Rows) -> Result<i64, String> {
+    match rows.next().await.map_err(|e| e.to_string())? {
+        Some(row) => Ok(match row.get_value(0).ok() { Some(Value::Integer(n)) => n, _ => 0 }),
+        None      => Ok(0),
+    }
+}
@@ -0,0 +1,99 @@
+// src-tauri/src/commands/contacts.rs
+use crate::{
+    db::{
+        self,
+        contacts,
+        models::{Contact, ContactVerification},
+    },
+    AppState,
+};
+use tauri::State;
+
+const MAX_LABEL: usize = 100;
+
+#[tauri::command]
+pub async fn list_contacts(
+    include_revoked: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Vec<Contact>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    contacts::list(&conn, include_revoked.unwrap_or(false)).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_contact(did: String, state: State<'_, AppState>) -> Result<Option<Contact>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    contacts::get(&conn, &did).await.map_err(|e| e.to_string())
+}
+
+/// Add a did:key to the contact book as unverified, or relabel a known one.
+/// `did_document` (theirs, e.g. received while pairing) adds its keys to the
+/// contact's key history.
+#[tauri::command]
+pub async fn add_contact(
+    did: String,
+    label: Option<String>,
+    did_document: Option<serde_json::Value>,
+    state: State<'_, AppState>,
+) -> Result<Contact, String> {
+    let label = label.map(|l| l.trim().to_string()).filter(|l| !l.is_empty());
+    if label.as_ref().is_some_and(|l| l.chars().count() > MAX_LABEL) {
+        return Err(format!("Contact labels are limited to {MAX_LABEL} characters"));
+    }
+    if did == super::did::local_did(&state).await.unwrap_or_default() {
+        return Err("That is this device's own DID".into());
+    }
+
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let tx = conn.transaction().await.map_err(|e| e.to_string())?;
+    contacts::add(&tx, &did, label.as_deref()).await.map_err(|e| e.to_string())?;
+    if let Some(document) = &did_document {
+        contacts::add_document_keys(&tx, &did, document).await.map_err(|e| e.to_string())?;
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+    contacts::get(&conn, &did).await.map_err(|e| e.to_string())?.ok_or_else(|| "Contact not stored".into())
+}
+
+/// Safety number and QR code for verifying `did` against this device's DID.
+#[tauri::command]
+pub async fn get_contact_verification(
+    did: String,
+    state: State<'_, AppState>,
+) -> Result<ContactVerification, String> {
+    let local = super::did::local_did(&state).await?;
+    let safety_number = contacts::safety_number(&local, &did).map_err(|e| e.to_string())?;
+    Ok(ContactVerification { qr_payload: contacts::qr_payload(&local, &safety_number), safety_number })
+}
+
+/// Mark `did` verified. With `scanned_code` (the QR code shown by their
+/// device) the code is checked here; without it the user has confirmed that
+/// both devices show the same safety number.
+#[tauri::command]
+pub async fn verify_contact(
+    did: String,
+    scanned_code: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Contact, String> {
+    let via = match scanned_code {
+        Some(code) => {
+            let local = super::did::local_did(&state).await?;
+            let safety_number = contacts::safety_number(&local, &did).map_err(|e| e.to_string())?;
+            contacts::check_qr(code.trim(), &did, &safety_number).map_err(|e| e.to_string())?;
+            "qr"
+        }
+        None => "safety_number",
+    };
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    contacts::mark_verified(&conn, &did, via).await.map_err(|e| e.to_string())?;
+    log::info!("[contacts] Verified {did} by {via}");
+    contacts::get(&conn, &did).await.map_err(|e| e.to_string())?.ok_or_else(|| "Contact not found".into())
+}
+
+/// Stop trusting `did`: its signatures are rejected until it is added again.
+#[tauri::command]
+pub async fn revoke_contact(did: String, state: State<'_, AppState>) -> Result<Contact, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    contacts::revoke(&conn, &did).await.map_err(|e| e.to_string())?;
+    log::info!("[contacts] Revoked {did}");
+    contacts::get(&conn, &did).await.map_err(|e| e.to_string())?.ok_or_else(|| "Contact not found".into())
+}
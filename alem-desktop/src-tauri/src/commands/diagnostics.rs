@@ -0,0 +1,86 @@
+// src-tauri/src/commands/diagnostics.rs
+use crate::{
+    db::{
+        self,
+        models::SlowQuery,
+        plans::{self, PlanIssue},
+        schema::{self, MigrationStep, SchemaVersion},
+        stats::{self, DatabaseStats},
+    },
+    AppState,
+};
+use tauri::{AppHandle, Manager, State};
+
+/// Statements that exceeded the slow-query threshold, slowest first.
+#[tauri::command]
+pub async fn get_slow_queries(
+    limit: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<Vec<SlowQuery>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let mut rows = conn.query(
+        "SELECT id, sql, param_types, duration_ms, created_at
+         FROM slow_queries ORDER BY duration_ms DESC LIMIT ?1",
+        libsql::params![limit.unwrap_or(100)],
+    ).await.map_err(|e| e.to_string())?;
+
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|e| e.to_string())? {
+        use libsql::Value;
+        let s = |i| match row.get_value(i).ok() { Some(Value::Text(s)) => s, _ => String::new() };
+        let n = |i| match row.get_value(i).ok() { Some(Value::Integer(n)) => n, _ => 0 };
+        out.push(SlowQuery {
+            id:          n(0),
+            sql:         s(1),
+            param_types: s(2),
+            duration_ms: n(3),
+            created_at:  s(4),
+        });
+    }
+    Ok(out)
+}
+
+#[tauri::command]
+pub async fn clear_slow_queries(state: State<'_, AppState>) -> Result<u64, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM slow_queries", ()).await.map_err(|e| e.to_string())
+}
+
+/// Run EXPLAIN QUERY PLAN over the hot queries and report any that have
+/// regressed to a full scan or temp sort. Empty means all plans use indexes.
+#[tauri::command]
+pub async fn check_query_plans(state: State<'_, AppState>) -> Result<Vec<PlanIssue>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    plans::check_query_plans(&conn).await.map_err(|e| e.to_string())
+}
+
+/// Applied/pending migrations with their checksum status.
+#[tauri::command]
+pub async fn get_schema_version(state: State<'_, AppState>) -> Result<SchemaVersion, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    schema::schema_version(&conn).await.map_err(|e| e.to_string())
+}
+
+/// Support tool: move the schema to `target` (latest if omitted). With
+/// `dry_run` the steps run against the real data and are rolled back. After a
+/// real downgrade the next app start re-applies the newer migrations.
+#[tauri::command]
+pub async fn migrate_schema(
+    target: Option<i64>,
+    dry_run: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Vec<MigrationStep>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    schema::migrate_to(&conn, target.unwrap_or_else(schema::latest_version), dry_run.unwrap_or(true))
+        .await
+        .map_err(|e| format!("{e:#}"))
+}
+
+/// Row counts, sizes and queue backlog. ANALYZE / integrity_check / vacuum
+/// run as part of run_maintenance.
+#[tauri::command]
+pub async fn get_database_stats(app: AppHandle, state: State<'_, AppState>) -> Result<DatabaseStats, String> {
+    let db_path = app.path().app_data_dir().map_err(|e| e.to_string())?.join("alem.db");
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    stats::collect(&conn, &db_path).await.map_err(|e| e.to_string())
+}
@@ -1,33 +1,34 @@
 // src-tauri/src/commands/did.rs
-use crate::{db::models::DIDResult, AppState};
-use ed25519_dalek::SigningKey;
-use keyring::Entry;
-use rand::rngs::OsRng;
+use crate::{
+    crypto::{self, algorithms::{PublicKey, SecretKey}, base64, keys, multiformats},
+    db::{
+        self,
+        contacts,
+        models::{DIDResult, DidKey, KeyAlgorithm, KeyPurpose, SignedPayload, TrustLevel},
+    },
+    keychain, AppState,
+};
 use tauri::State;
 
-const KEYRING_SERVICE: &str = "alem-desktop";
-
+/// Generate a did:key for this device. `key_type` defaults to Ed25519;
+/// secp256k1 and P-256 are for ecosystems that require them.
 #[tauri::command]
-pub async fn generate_did(state: State<'_, AppState>) -> Result<DIDResult, String> {
-    let mut csprng  = OsRng;
-    let signing_key = SigningKey::generate(&mut csprng);
-    let verifying   = signing_key.verifying_key();
+pub async fn generate_did(
+    key_type: Option<KeyAlgorithm>,
+    state: State<'_, AppState>,
+) -> Result<DIDResult, String> {
+    let signing_key = SecretKey::generate(key_type.unwrap_or_default());
+    let verifying   = signing_key.public();
 
-    // did:key multicodec prefix for Ed25519 = 0xed 0x01
-    let mut prefixed = vec![0xed_u8, 0x01];
-    prefixed.extend_from_slice(verifying.as_bytes());
-    let multibase = format!("z{}", bs58::encode(&prefixed).into_string());
-    let did       = format!("did:key:{multibase}");
+    let multibase = verifying.multibase();
+    let did       = multiformats::did_key(verifying.codec(), &verifying.to_bytes());
 
     // Private key → OS keychain
-    let priv_b64 = base64_simple(signing_key.as_bytes());
-    Entry::new(KEYRING_SERVICE, &format!("did_priv_{did}"))
-        .map_err(|e| e.to_string())?
-        .set_password(&priv_b64)
-        .map_err(|e| e.to_string())?;
+    let priv_b64 = base64::STANDARD.encode(&signing_key.to_bytes());
+    keychain::set(&keychain::did_private_key(&did), &priv_b64).map_err(|e| e.to_string())?;
 
     // DID + public key → libsql
-    let conn = state.db.connect().map_err(|e| e.to_string())?;
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
     conn.execute(
         "UPDATE local_identity
          SET did = ?1, did_public_key = ?2, updated_at = datetime('now')
@@ -35,12 +36,15 @@ pub async fn generate_did(state: State<'_, AppState>) -> Result<DIDResult, Strin
         libsql::params![did.clone(), multibase.clone()],
     ).await.map_err(|e| e.to_string())?;
 
+    // Separate signing / authentication / key agreement keys under the new DID
+    keys::ensure(&conn, &did).await.map_err(|e| e.to_string())?;
+
     Ok(DIDResult { did, public_key_multibase: multibase })
 }
 
 #[tauri::command]
 pub async fn get_stored_did(state: State<'_, AppState>) -> Result<Option<DIDResult>, String> {
-    let conn = state.db.connect().map_err(|e| e.to_string())?;
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
     let mut rows = conn.query(
         "SELECT did, did_public_key FROM local_identity
          WHERE id = 'singleton' AND did IS NOT NULL",
@@ -63,9 +67,10 @@ pub async fn get_stored_did(state: State<'_, AppState>) -> Result<Option<DIDResu
     }
 }
 
+/// Whether `did` is a did:key of a key type this app handles.
 #[tauri::command]
 pub async fn validate_did(did: String) -> Result<bool, String> {
-    Ok(did.starts_with("did:key:z") && did.len() > 12)
+    Ok(multiformats::parse_did_key(&did).is_ok())
 }
 
 
@@ -76,7 +81,7 @@ pub async fn store_server_did(
 ) -> Result<(), String> {
     // Store server-generated DID in local SQLite
     // No private key — server holds the keypair
-    let conn = state.db.connect().map_err(|e| e.to_string())?;
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
     conn.execute(
         "UPDATE local_identity
          SET did = ?1, did_public_key = ?2, updated_at = datetime('now')
@@ -90,20 +95,94 @@ pub async fn store_server_did(
     Ok(())
 }
 
+/// Sign `payload` with the local DID's assertion key. Only DIDs generated on
+/// this device have one; server-issued DIDs are signed for by the server.
+#[tauri::command]
+pub async fn sign_payload(
+    payload: serde_json::Value,
+    state: State<'_, AppState>,
+) -> Result<SignedPayload, String> {
+    let did  = local_did(&state).await?;
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let (key, signing_key) = keys::signing_key(&conn, &did).await.map_err(|e| e.to_string())?;
 
-fn base64_simple(bytes: &[u8]) -> String {
-    const CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
-    let mut out = String::new();
-    for chunk in bytes.chunks(3) {
-        let n = match chunk.len() {
-            3 => ((chunk[0] as u32) << 16) | ((chunk[1] as u32) << 8) | chunk[2] as u32,
-            2 => ((chunk[0] as u32) << 16) | ((chunk[1] as u32) << 8),
-            _ => (chunk[0] as u32) << 16,
-        };
-        out.push(CHARS[((n >> 18) & 63) as usize] as char);
-        out.push(CHARS[((n >> 12) & 63) as usize] as char);
-        out.push(if chunk.len() > 1 { CHARS[((n >>  6) & 63) as usize] as char } else { '=' });
-        out.push(if chunk.len() > 2 { CHARS[( n        & 63) as usize] as char } else { '=' });
+    let signature = crypto::sign_json(&signing_key, &payload).map_err(|e| e.to_string())?;
+    let jws       = crypto::sign_jws(&signing_key, &key.id, &payload).map_err(|e| e.to_string())?;
+    Ok(SignedPayload { did, key_id: key.id, signature, jws })
+}
+
+/// Check a signature against `payload`. With `key_id` (from sign_payload) the
+/// signature must come from that key of this device's DID or from a key in a
+/// contact's key history, rotated-out keys included, since old signatures
+/// stay valid. Without it, `did` must be a did:key and its own key is used.
+/// `signature` may be either the multibase signature or the JWS. Signatures
+/// from revoked contacts never verify.
+#[tauri::command]
+pub async fn verify_payload(
+    did: String,
+    payload: serde_json::Value,
+    signature: String,
+    key_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    if contacts::trust(&conn, &did).await.map_err(|e| e.to_string())? == Some(TrustLevel::Revoked) {
+        return Ok(false);
     }
-    out
+    let key = match key_id {
+        Some(key_id) => match keys::find(&conn, &key_id).await.map_err(|e| e.to_string())? {
+            Some(key) if key.did == did && key.purpose == KeyPurpose::Signing => {
+                keys::verifying_key(&key).map_err(|e| e.to_string())?
+            }
+            Some(_) => return Err(format!("Unknown signing key {key_id}")),
+            // A contact's key, from its key history
+            None => {
+                let multibase = contacts::find_key(&conn, &did, &key_id).await.map_err(|e| e.to_string())?
+                    .ok_or_else(|| format!("Unknown signing key {key_id}"))?;
+                PublicKey::from_multibase(&multibase).map_err(|e| e.to_string())?
+            }
+        },
+        None => crypto::did_key_public(&did).map_err(|e| e.to_string())?,
+    };
+    let verified = if signature.contains('.') {
+        crypto::verify_jws(&key, &payload, &signature)
+    } else {
+        crypto::verify_json(&key, &payload, &signature)
+    };
+    Ok(verified.is_ok())
+}
+
+/// DID document for the local DID: the controller key plus the active
+/// signing, authentication and key agreement keys, signed by the controller.
+#[tauri::command]
+pub async fn get_did_document(state: State<'_, AppState>) -> Result<serde_json::Value, String> {
+    let did  = local_did(&state).await?;
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    keys::ensure(&conn, &did).await.map_err(|e| e.to_string())?;
+    keys::document(&conn, &did).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_did_keys(
+    include_revoked: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Vec<DidKey>, String> {
+    let did  = local_did(&state).await?;
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    keys::list(&conn, &did, include_revoked.unwrap_or(false)).await.map_err(|e| e.to_string())
+}
+
+/// Replace the key for one purpose; the others are untouched.
+#[tauri::command]
+pub async fn rotate_did_key(
+    purpose: KeyPurpose,
+    state: State<'_, AppState>,
+) -> Result<DidKey, String> {
+    let did  = local_did(&state).await?;
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    keys::rotate(&conn, &did, purpose).await.map_err(|e| e.to_string())
+}
+
+pub(crate) async fn local_did(state: &State<'_, AppState>) -> Result<String, String> {
+    Ok(get_stored_did(state.clone()).await?.ok_or("No DID stored")?.did)
 }
\ No newline at end of file
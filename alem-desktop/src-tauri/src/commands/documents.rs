@@ -1,145 +1,569 @@
 // src-tauri/src/commands/documents.rs
-use crate::{db::models::{Document, row_to_document}, AppState};
+use crate::{
+    db::{
+        self,
+        access, bulk,
+        models::{
+            document_columns, row_to_document, row_to_search_hit, row_to_summary, summary_columns,
+            Document, DocumentPage, DocumentSummary, HistoricalDocument, RecentDocument, SearchHit,
+            SearchSettings,
+        },
+        history, identity, metadata_index, metadata_schemas,
+        query::{DocumentFilter, SqlBuilder},
+        search::{self, SearchField, SearchOptions},
+        ops, settings, tags, timing, trash,
+    },
+    extract, ocr, scan,
+    storage::{cas, text},
+    AppState,
+};
 use serde::Deserialize;
-use tauri::State;
+use tauri::{AppHandle, Manager, State};
 use uuid::Uuid;
 
+/// Upper bound for each search weight and the recency boost.
+const MAX_SEARCH_WEIGHT: f64 = 100.0;
+
 #[derive(Debug, Deserialize)]
 pub struct CreateDocumentInput {
-    pub filename:     String,
-    pub content_type: String,
-    pub local_path:   String,
-    pub file_size:    i64,
-    pub content_hash: String,
-    pub text_content: Option<String>,
-    pub metadata:     Option<serde_json::Value>,
-    pub tags:         Option<Vec<String>>,
+    pub filename:       String,
+    pub content_type:   String,
+    pub local_path:     String,
+    pub file_size:      i64,
+    pub content_hash:   String,
+    pub text_content:   Option<String>,
+    pub metadata:       Option<serde_json::Value>,
+    pub tags:           Option<Vec<String>>,
+    /// Set by storage::text::limit when text_content was cut.
+    #[serde(skip)]
+    pub full_text_path: Option<String>,
+}
+
+/// Changes applied to every document by bulk_update_documents. Tags are added
+/// and removed rather than replaced, so each document keeps its other tags.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct BulkDocumentChanges {
+    pub add_tags:         Vec<String>,
+    pub remove_tags:      Vec<String>,
+    /// Move into this collection.
+    pub collection_id:    Option<String>,
+    /// Move to the root (unfiled); ignored if collection_id is set.
+    pub clear_collection: bool,
+    /// true moves to the trash, false restores from it.
+    pub trashed:          Option<bool>,
 }
 
 #[tauri::command]
 pub async fn create_document(
-    input: CreateDocumentInput,
+    mut input: CreateDocumentInput,
+    app: AppHandle,
     state: State<'_, AppState>,
 ) -> Result<Document, String> {
-    let conn = state.db.connect().map_err(|e| e.to_string())?;
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
 
-    let id       = Uuid::new_v4().to_string();
-    let metadata = serde_json::to_string(&input.metadata.unwrap_or(serde_json::json!({}))).unwrap_or_else(|_| "{}".into());
-    let tags     = serde_json::to_string(&input.tags.unwrap_or_default()).unwrap_or_else(|_| "[]".into());
+    let id = Uuid::new_v4().to_string();
 
     // Read identity from libsql
-    let mut id_rows = conn.query(
-        "SELECT COALESCE(user_id,'anonymous'), COALESCE(tenant_id,'default')
-         FROM local_identity WHERE id='singleton'",
-        (),
-    ).await.map_err(|e| e.to_string())?;
+    let (user_id, tenant_id) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+    // The schema and every extractor go by content type, so fix it first
+    extract::sniff::fill(&mut input).await;
+    validate_metadata(&conn, &tenant_id, &input).await.map_err(|e| e.to_string())?;
+    extract::fill(&mut input).await;
+    extract::media::fill(&conn, &mut input).await.map_err(|e| e.to_string())?;
+    // Before the text limit, so the whole text is scanned
+    scan::scan(&conn, &mut input).await.map_err(|e| e.to_string())?;
+    let files_dir = cas::files_dir(&app).map_err(|e| e.to_string())?;
+    text::limit(&files_dir, &conn, &mut input).await.map_err(|e| e.to_string())?;
 
-    let (user_id, tenant_id) = if let Some(row) = id_rows.next().await.map_err(|e| e.to_string())? {
-        use libsql::Value;
-        let uid = match row.get_value(0).ok() { Some(Value::Text(s)) => s, _ => "anonymous".into() };
-        let tid = match row.get_value(1).ok() { Some(Value::Text(s)) => s, _ => "default".into()   };
-        (uid, tid)
-    } else {
-        ("anonymous".into(), "default".into())
-    };
-
-    conn.execute(
-        "INSERT INTO documents (
-             id, user_id, tenant_id, filename, content_type, file_size,
-             content_hash, local_path, text_content, metadata, tags,
-             status, needs_upload
-         ) VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11,'local',1)",
-        libsql::params![
-            id.clone(), user_id.clone(), tenant_id,
-            input.filename, input.content_type, input.file_size,
-            input.content_hash, input.local_path,
-            input.text_content.unwrap_or_default(), metadata, tags,
-        ],
-    ).await.map_err(|e| format!("Insert failed: {e}"))?;
+    insert_document(&conn, &id, &user_id, &tenant_id, &input)
+        .await.map_err(|e| format!("Insert failed: {e}"))?;
+    scan::notify(&app, &id, &input);
+    ocr::queue_if_needed(&conn, &id, &input).await.map_err(|e| e.to_string())?;
 
     // Queue upload operation
-    let op_id   = Uuid::new_v4().to_string();
-    let payload = format!("{{\"doc_id\":\"{id}\"}}");
-    conn.execute(
-        "INSERT INTO offline_operations (id, user_id, op_type, payload)
-         VALUES (?1, ?2, 'upload_document', ?3)",
-        libsql::params![op_id, user_id, payload],
-    ).await.map_err(|e| format!("Queue op failed: {e}"))?;
+    ops::enqueue(&conn, &user_id, "upload_document", serde_json::json!({ "doc_id": id }))
+        .await.map_err(|e| format!("Queue op failed: {e}"))?;
 
-    get_document(id, state).await
+    load_document(&conn, &id).await
 }
 
+/// Bulk import as a background job. Returns the job id; progress comes through
+/// `job-progress`. Rows are inserted in transactions of bulk::BATCH_SIZE with
+/// the FTS triggers suspended, and the index is rebuilt once at the end.
+/// Cancelling keeps the batches already committed.
 #[tauri::command]
-pub async fn get_documents(state: State<'_, AppState>) -> Result<Vec<Document>, String> {
-    let conn = state.db.connect().map_err(|e| e.to_string())?;
-    let mut rows = conn.query(
-        "SELECT id, user_id, tenant_id, filename, content_type, file_size, content_hash,
-                local_path, object_key, text_content, metadata, tags, status,
-                local_version, server_version, is_synced, needs_upload, needs_download,
-                sync_error, last_synced_at, created_at, updated_at
-         FROM documents WHERE status != 'deleted' ORDER BY created_at DESC",
-        (),
+pub async fn import_documents(
+    mut inputs: Vec<CreateDocumentInput>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let job_app = app.clone();
+    Ok(state.jobs.spawn(&app, "import_documents", move |job| async move {
+        let _guard = bulk::lock().await;
+        let conn   = db::connect(&job_app.state::<AppState>().db).await?;
+        let (user_id, tenant_id) = identity::current(&conn).await?;
+
+        for input in &mut inputs {
+            extract::sniff::fill(input).await;
+        }
+        // Reject the whole import up front rather than stopping partway
+        for (i, input) in inputs.iter().enumerate() {
+            validate_metadata(&conn, &tenant_id, input).await
+                .map_err(|e| anyhow::anyhow!("{} (item {i}): {e}", input.filename))?;
+        }
+        let files_dir = cas::files_dir(&job_app)?;
+        for input in &mut inputs {
+            extract::fill(input).await;
+            extract::media::fill(&conn, input).await?;
+            scan::scan(&conn, input).await?;
+            text::limit(&files_dir, &conn, input).await?;
+        }
+
+        job.set_total(inputs.len() as u64);
+        bulk::suspend_fts(&conn).await?;
+
+        let mut ids = Vec::with_capacity(inputs.len());
+        let outcome: anyhow::Result<()> = async {
+            for batch in inputs.chunks(bulk::BATCH_SIZE) {
+                job.check_cancelled()?;
+                let tx = conn.transaction().await?;
+                for input in batch {
+                    let id = Uuid::new_v4().to_string();
+                    insert_document(&tx, &id, &user_id, &tenant_id, input).await?;
+                    ops::enqueue(&tx, &user_id, "upload_document", serde_json::json!({ "doc_id": id })).await?;
+                    ids.push(id);
+                }
+                tx.commit().await?;
+                for (input, id) in batch.iter().zip(&ids[ids.len() - batch.len()..]) {
+                    scan::notify(&job_app, id, input);
+                    ocr::queue_if_needed(&conn, id, input).await?;
+                }
+                job.advance(batch.len() as u64, None);
+            }
+            Ok(())
+        }.await;
+
+        // Always put the triggers back, even if a batch failed or was cancelled
+        job.advance(0, Some("Rebuilding search index".into()));
+        bulk::resume_fts(&conn).await?;
+
+        outcome?;
+        Ok(serde_json::json!({ "imported": ids.len(), "ids": ids }))
+    }))
+}
+
+/// Listing without text_content — use get_document(id) for the full record.
+#[tauri::command]
+pub async fn get_documents(state: State<'_, AppState>) -> Result<Vec<DocumentSummary>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let (_, tenant_id) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+    let mut rows = timing::query(&conn,
+        &format!(
+            "SELECT {} FROM documents WHERE tenant_id = ?1 AND status != 'deleted' AND trashed_at IS NULL
+             ORDER BY created_at DESC, id DESC",
+            summary_columns(""),
+        ),
+        libsql::params![tenant_id],
     ).await.map_err(|e| e.to_string())?;
 
     let mut docs = Vec::new();
     while let Some(row) = rows.next().await.map_err(|e| e.to_string())? {
-        if let Ok(doc) = row_to_document(&row) { docs.push(doc); }
+        if let Ok(doc) = row_to_summary(&row) { docs.push(doc); }
     }
     Ok(docs)
 }
 
+/// Keyset-paginated listing (newest first). The cursor is opaque to callers:
+/// it encodes the (created_at, id) of the last row of the previous page, so
+/// deep pages cost the same as the first one, unlike OFFSET.
 #[tauri::command]
-pub async fn get_document(id: String, state: State<'_, AppState>) -> Result<Document, String> {
-    let conn = state.db.connect().map_err(|e| e.to_string())?;
-    let mut rows = conn.query(
-        "SELECT id, user_id, tenant_id, filename, content_type, file_size, content_hash,
-                local_path, object_key, text_content, metadata, tags, status,
-                local_version, server_version, is_synced, needs_upload, needs_download,
-                sync_error, last_synced_at, created_at, updated_at
-         FROM documents WHERE id = ?1",
+pub async fn get_documents_page(
+    limit: Option<i64>,
+    cursor: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<DocumentPage, String> {
+    let conn  = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let limit = limit.unwrap_or(50).clamp(1, 500);
+    let (_, tenant_id) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+
+    let (after_created, after_id) = match cursor.as_deref().map(decode_cursor) {
+        Some(Some((c, i))) => (c, i),
+        Some(None)         => return Err("Invalid cursor".into()),
+        // Sorts after every real timestamp, so the first page is unbounded
+        None               => ("\u{10FFFF}".to_string(), String::new()),
+    };
+
+    let mut rows = timing::query(&conn,
+        &format!(
+            "SELECT {} FROM documents
+             WHERE tenant_id = ?4 AND status != 'deleted' AND trashed_at IS NULL
+               AND (created_at, id) < (?1, ?2)
+             ORDER BY created_at DESC, id DESC
+             LIMIT ?3",
+            summary_columns(""),
+        ),
+        libsql::params![after_created, after_id, limit + 1, tenant_id],
+    ).await.map_err(|e| e.to_string())?;
+
+    let mut docs = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|e| e.to_string())? {
+        if let Ok(doc) = row_to_summary(&row) { docs.push(doc); }
+    }
+    Ok(paginate(docs, limit))
+}
+
+/// Filtered listing: the filter compiles to bound SQL, so the UI never has to
+/// fetch everything and filter in JS. Paginates like get_documents_page.
+#[tauri::command]
+pub async fn query_documents(
+    filter: DocumentFilter,
+    limit: Option<i64>,
+    cursor: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<DocumentPage, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    filtered_page(&conn, &filter, None, limit, cursor).await
+}
+
+/// Keyset-paginated listing for a filter plus optional full-text search text
+/// (parsed with the default SearchOptions). Shared with saved searches.
+pub(crate) async fn filtered_page(
+    conn: &libsql::Connection,
+    filter: &DocumentFilter,
+    text: Option<&str>,
+    limit: Option<i64>,
+    cursor: Option<String>,
+) -> Result<DocumentPage, String> {
+    let limit = limit.unwrap_or(50).clamp(1, 500);
+    let (_, tenant_id) = identity::current(conn).await.map_err(|e| e.to_string())?;
+
+    let mut b = SqlBuilder::default();
+    let p = b.bind(tenant_id);
+    b.push(format!("d.tenant_id = {p}"));
+    let indexed = metadata_index::keys(conn).await.map_err(|e| e.to_string())?;
+    filter.apply("d", &indexed, &mut b);
+    if let Some(expr) = text.and_then(|t| search::match_expression(t, &SearchOptions::default())) {
+        let p = b.bind(expr);
+        b.push(format!("d.rowid IN (SELECT rowid FROM documents_fts WHERE documents_fts MATCH {p})"));
+    }
+    if let Some(c) = cursor {
+        let (created_at, id) = decode_cursor(&c).ok_or("Invalid cursor")?;
+        let (pc, pi) = (b.bind(created_at), b.bind(id));
+        b.push(format!("(d.created_at, d.id) < ({pc}, {pi})"));
+    }
+    let lim = b.bind(limit + 1);
+
+    let sql = format!(
+        "SELECT {} FROM documents d WHERE {} ORDER BY d.created_at DESC, d.id DESC LIMIT {lim}",
+        summary_columns("d."),
+        b.where_clause(),
+    );
+    let mut rows = timing::query(conn, &sql, b.into_params()).await.map_err(|e| e.to_string())?;
+
+    let mut docs = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|e| e.to_string())? {
+        if let Ok(doc) = row_to_summary(&row) { docs.push(doc); }
+    }
+    Ok(paginate(docs, limit))
+}
+
+/// Shared by create_document, the bulk import and the file import pipeline.
+pub(crate) async fn insert_document(
+    conn: &libsql::Connection,
+    id: &str,
+    user_id: &str,
+    tenant_id: &str,
+    input: &CreateDocumentInput,
+) -> anyhow::Result<()> {
+    let metadata = serde_json::to_string(input.metadata.as_ref().unwrap_or(&serde_json::json!({}))).unwrap_or_else(|_| "{}".into());
+
+    conn.execute(
+        "INSERT INTO documents (
+             id, user_id, tenant_id, filename, content_type, file_size,
+             content_hash, local_path, text_content, metadata, full_text_path,
+             status, needs_upload
+         ) VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11,'local',1)",
+        libsql::params![
+            id, user_id, tenant_id,
+            input.filename.as_str(), input.content_type.as_str(), input.file_size,
+            input.content_hash.as_str(), input.local_path.as_str(),
+            input.text_content.as_deref().unwrap_or_default(), metadata,
+            input.full_text_path.clone(),
+        ],
+    ).await?;
+    tags::set_for_document(conn, id, input.tags.as_deref().unwrap_or_default()).await?;
+    // The blob is claimed now (see storage/cas.rs)
+    conn.execute("DELETE FROM staged_blobs WHERE path = ?1", libsql::params![input.local_path.as_str()]).await?;
+    Ok(())
+}
+
+/// Check an input's metadata (missing = `{}`) against the tenant's schema for
+/// its content type. Only user-supplied documents are checked; file imports
+/// and backup restores keep whatever metadata they carry.
+async fn validate_metadata(conn: &libsql::Connection, tenant_id: &str, input: &CreateDocumentInput) -> anyhow::Result<()> {
+    let empty = serde_json::json!({});
+    metadata_schemas::validate(conn, tenant_id, &input.content_type, input.metadata.as_ref().unwrap_or(&empty)).await
+}
+
+/// The full record, without counting an access.
+async fn load_document(conn: &libsql::Connection, id: &str) -> Result<Document, String> {
+    let mut rows = timing::query(conn,
+        &format!("SELECT {} FROM documents WHERE id = ?1", document_columns("")),
         libsql::params![id],
     ).await.map_err(|e| e.to_string())?;
 
     if let Some(row) = rows.next().await.map_err(|e| e.to_string())? {
         row_to_document(&row).map_err(|e| e.to_string())
     } else {
-        Err(format!("Document not found"))
+        Err("Document not found".to_string())
+    }
+}
+
+/// Trim a `limit + 1` fetch to `limit` rows and derive the next cursor.
+pub(crate) fn paginate(mut docs: Vec<DocumentSummary>, limit: i64) -> DocumentPage {
+    let has_more = docs.len() as i64 > limit;
+    docs.truncate(limit as usize);
+    let next_cursor = has_more
+        .then(|| docs.last().map(|d| format!("{}|{}", d.created_at, d.id)))
+        .flatten();
+    DocumentPage { documents: docs, next_cursor }
+}
+
+pub(crate) fn decode_cursor(cursor: &str) -> Option<(String, String)> {
+    let (created_at, id) = cursor.split_once('|')?;
+    Some((created_at.to_string(), id.to_string()))
+}
+
+/// Opening a document counts as an access (see get_recent_documents). A
+/// synced document whose bytes aren't local yet starts downloading; a
+/// "document-downloaded" event follows once they are.
+#[tauri::command]
+pub async fn get_document(id: String, app: AppHandle, state: State<'_, AppState>) -> Result<Document, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let doc = load_document(&conn, &id).await?;
+    if let Err(e) = access::record(&conn, &id).await {
+        log::warn!("[access] Could not record access to {id}: {e}");
+    }
+    if doc.needs_download && doc.object_key.as_deref().is_some_and(|k| !k.is_empty()) {
+        crate::sync::engine::fetch_in_background(&app, &id);
     }
+    Ok(doc)
 }
 
+/// The whole text of a document. Same as text_content unless that was cut
+/// to the storage text limit (see metadata.text_truncated).
 #[tauri::command]
-pub async fn search_documents(query: String, state: State<'_, AppState>) -> Result<Vec<Document>, String> {
-    let conn = state.db.connect().map_err(|e| e.to_string())?;
+pub async fn get_document_text(id: String, state: State<'_, AppState>) -> Result<String, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
     let mut rows = conn.query(
-        "SELECT d.id, d.user_id, d.tenant_id, d.filename, d.content_type, d.file_size, d.content_hash,
-                d.local_path, d.object_key, d.text_content, d.metadata, d.tags, d.status,
-                d.local_version, d.server_version, d.is_synced, d.needs_upload, d.needs_download,
-                d.sync_error, d.last_synced_at, d.created_at, d.updated_at
-         FROM documents d
-         JOIN documents_fts fts ON d.id = fts.id
-         WHERE d.status != 'deleted' AND documents_fts MATCH ?1
-         ORDER BY rank",
-        libsql::params![query],
+        "SELECT COALESCE(text_content, ''), full_text_path FROM documents WHERE id = ?1",
+        libsql::params![id.as_str()],
     ).await.map_err(|e| e.to_string())?;
+    let row = rows.next().await.map_err(|e| e.to_string())?.ok_or_else(|| format!("Document {id} not found"))?;
+    let text: String = row.get(0).map_err(|e| e.to_string())?;
+    match row.get::<Option<String>>(1).map_err(|e| e.to_string())? {
+        Some(path) => {
+            let bytes = tokio::fs::read(&path).await
+                .map_err(|e| format!("Full text of {id} is unavailable: {e}"))?;
+            Ok(String::from_utf8_lossy(&bytes).into_owned())
+        }
+        None => Ok(text),
+    }
+}
 
-    let mut docs = Vec::new();
+/// Most recently opened documents of the active tenant, with how often each
+/// has been opened.
+#[tauri::command]
+pub async fn get_recent_documents(
+    limit: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<Vec<RecentDocument>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let (_, tenant_id) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+    access::recent(&conn, &tenant_id, limit.unwrap_or(20).clamp(1, 200)).await.map_err(|e| e.to_string())
+}
+
+/// The active tenant's documents as they were at `timestamp`: filename,
+/// type, folder, metadata and tags then, plus what became of each since.
+/// Documents deleted by then are left out. History starts with this version
+/// of the app; earlier changes weren't recorded.
+#[tauri::command]
+pub async fn get_documents_as_of(
+    timestamp: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<HistoricalDocument>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let (_, tenant_id) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+    history::as_of(&conn, &tenant_id, &timestamp).await.map_err(|e| e.to_string())
+}
+
+/// Full-text search over filename and text_content (and annotations, if
+/// weighted), best matches first. Ranking follows the search settings.
+#[tauri::command]
+pub async fn search_documents(
+    query: String,
+    options: Option<SearchOptions>,
+    state: State<'_, AppState>,
+) -> Result<Vec<SearchHit>, String> {
+    let opts = options.unwrap_or_default();
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+
+    let expr = if opts.fuzzy && !opts.raw {
+        search::fuzzy_expression(&conn, &query, &opts).await.map_err(|e| e.to_string())?
+    } else {
+        search::match_expression(&query, &opts)
+    };
+    let Some(expr) = expr else {
+        return Ok(Vec::new());
+    };
+    let (_, tenant_id) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+    let ranking: SearchSettings = settings::get(&conn, settings::SEARCH_SETTINGS).await
+        .map_err(|e| e.to_string())?
+        .unwrap_or_default();
+
+    // Column filters and raw FTS5 syntax only make sense against documents_fts
+    let notes = if ranking.annotations_weight > 0.0 && opts.field == SearchField::All && !opts.raw {
+        "SELECT rowid, bm25(annotations_fts) AS rank FROM annotations_fts WHERE annotations_fts MATCH ?1"
+    } else {
+        "SELECT NULL AS rowid, NULL AS rank WHERE 0"
+    };
+
+    // bm25 is negative, lower is better; row_to_search_hit negates `score`
+    let mut rows = timing::query(&conn,
+        &format!(
+            "WITH doc_hits AS MATERIALIZED (
+                 SELECT rowid, bm25(documents_fts, ?7, ?8)      AS rank,
+                        highlight(documents_fts, 1, ?2, ?3)       AS filename_highlight,
+                        snippet(documents_fts, 2, ?2, ?3, '…', ?4) AS snippet
+                 FROM documents_fts WHERE documents_fts MATCH ?1
+             ),
+             note_matches AS MATERIALIZED ({notes}),
+             note_hits AS (
+                 SELECT a.doc_id, MIN(m.rank) AS rank
+                 FROM note_matches m JOIN annotations a ON a.rowid = m.rowid
+                 GROUP BY a.doc_id
+             ),
+             candidates AS (
+                 SELECT rowid FROM doc_hits
+                 UNION
+                 SELECT d2.rowid FROM note_hits JOIN documents d2 ON d2.id = note_hits.doc_id
+             )
+             SELECT {},
+                    (COALESCE(h.rank, 0) + ?9 * COALESCE(n.rank, 0))
+                      * (1 + ?10 * MAX(0, 1 - (julianday('now') - julianday(d.updated_at)) / ?11)) AS score,
+                    COALESCE(h.filename_highlight, d.filename) AS filename_highlight,
+                    h.snippet                                  AS snippet
+             FROM candidates c
+             JOIN documents d ON d.rowid = c.rowid
+             LEFT JOIN doc_hits h  ON h.rowid = d.rowid
+             LEFT JOIN note_hits n ON n.doc_id = d.id
+             WHERE d.tenant_id = ?6 AND d.status != 'deleted' AND d.trashed_at IS NULL
+             ORDER BY score
+             LIMIT ?5",
+            summary_columns("d."),
+        ),
+        libsql::params![
+            expr,
+            opts.highlight_open,
+            opts.highlight_close,
+            opts.snippet_tokens.clamp(1, 64),
+            opts.limit.clamp(1, 500),
+            tenant_id,
+            ranking.filename_weight,
+            ranking.content_weight,
+            ranking.annotations_weight,
+            ranking.recency_boost,
+            ranking.recency_window_days,
+        ],
+    ).await.map_err(|e| e.to_string())?;
+
+    let mut hits = Vec::new();
     while let Some(row) = rows.next().await.map_err(|e| e.to_string())? {
-        if let Ok(doc) = row_to_document(&row) { docs.push(doc); }
+        if let Ok(hit) = row_to_search_hit(&row) { hits.push(hit); }
     }
-    Ok(docs)
+    Ok(hits)
+}
+
+#[tauri::command]
+pub async fn get_search_settings(state: State<'_, AppState>) -> Result<SearchSettings, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let current = settings::get(&conn, settings::SEARCH_SETTINGS).await.map_err(|e| e.to_string())?;
+    Ok(current.unwrap_or_default())
+}
+
+#[tauri::command]
+pub async fn update_search_settings(
+    settings: SearchSettings,
+    state: State<'_, AppState>,
+) -> Result<SearchSettings, String> {
+    let weights = [
+        ("filename_weight",    settings.filename_weight),
+        ("content_weight",     settings.content_weight),
+        ("annotations_weight", settings.annotations_weight),
+        ("recency_boost",      settings.recency_boost),
+    ];
+    for (name, w) in weights {
+        if !(0.0..=MAX_SEARCH_WEIGHT).contains(&w) {
+            return Err(format!("{name} must be between 0 and {MAX_SEARCH_WEIGHT}"));
+        }
+    }
+    if settings.filename_weight == 0.0 && settings.content_weight == 0.0 {
+        return Err("filename_weight and content_weight cannot both be 0".into());
+    }
+    if !(1.0..=3650.0).contains(&settings.recency_window_days) {
+        return Err("recency_window_days must be between 1 and 3650".into());
+    }
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    settings::set(&conn, settings::SEARCH_SETTINGS, &settings).await.map_err(|e| e.to_string())?;
+    Ok(settings)
 }
 
 #[tauri::command]
 pub async fn update_document(
     id: String,
     filename: Option<String>,
-    _metadata: Option<serde_json::Value>,
-    _tags: Option<Vec<String>>,
+    metadata: Option<serde_json::Value>,
+    tags: Option<Vec<String>>,
     state: State<'_, AppState>,
 ) -> Result<Document, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let (user_id, tenant_id) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+    if let Some(metadata) = &metadata {
+        let mut rows = conn.query("SELECT content_type FROM documents WHERE id = ?1", libsql::params![id.clone()])
+            .await.map_err(|e| e.to_string())?;
+        let content_type: String = match rows.next().await.map_err(|e| e.to_string())? {
+            Some(row) => row.get(0).map_err(|e| e.to_string())?,
+            None      => return Err(format!("Document {id} not found")),
+        };
+        drop(rows);
+        metadata_schemas::validate(&conn, &tenant_id, &content_type, metadata)
+            .await.map_err(|e| e.to_string())?;
+    }
+
+    // All of it or none: a failed tag update leaves the new name unapplied too
+    let tx = conn.transaction().await.map_err(|e| e.to_string())?;
+    if let Some(metadata) = metadata {
+        tx.execute(
+            "UPDATE documents
+             SET metadata = ?1, local_version = local_version + 1,
+                 needs_upload = 1, is_synced = 0, status = 'local',
+                 updated_at = datetime('now')
+             WHERE id = ?2",
+            libsql::params![metadata.to_string(), id.clone()],
+        ).await.map_err(|e| format!("Update failed: {e}"))?;
+    }
+    if let Some(tags) = tags {
+        tags::set_for_document(&tx, &id, &tags).await.map_err(|e| format!("Update failed: {e}"))?;
+        tx.execute(
+            "UPDATE documents SET local_version = local_version + 1, updated_at = datetime('now') WHERE id = ?1",
+            libsql::params![id.clone()],
+        ).await.map_err(|e| format!("Update failed: {e}"))?;
+        ops::enqueue(&tx, &user_id, "update_document", serde_json::json!({ "doc_id": id }))
+            .await.map_err(|e| format!("Queue op failed: {e}"))?;
+    }
     if let Some(name) = filename {
-        let conn = state.db.connect().map_err(|e| e.to_string())?;
-        conn.execute(
+        tx.execute(
             "UPDATE documents
              SET filename = ?1, local_version = local_version + 1,
                  needs_upload = 1, is_synced = 0, status = 'local',
@@ -148,12 +572,13 @@ pub async fn update_document(
             libsql::params![name, id.clone()],
         ).await.map_err(|e| format!("Update failed: {e}"))?;
     }
-    get_document(id, state).await
+    tx.commit().await.map_err(|e| e.to_string())?;
+    load_document(&conn, &id).await
 }
 
 #[tauri::command]
 pub async fn delete_document(id: String, state: State<'_, AppState>) -> Result<(), String> {
-    let conn = state.db.connect().map_err(|e| e.to_string())?;
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
 
     let mut rows = conn.query(
         "SELECT user_id FROM documents WHERE id = ?1",
@@ -174,13 +599,149 @@ pub async fn delete_document(id: String, state: State<'_, AppState>) -> Result<(
         libsql::params![id.clone()],
     ).await.map_err(|e| format!("Delete failed: {e}"))?;
 
-    let op_id   = Uuid::new_v4().to_string();
-    let payload = format!("{{\"doc_id\":\"{id}\"}}");
-    conn.execute(
-        "INSERT INTO offline_operations (id, user_id, op_type, payload)
-         VALUES (?1, ?2, 'delete_document', ?3)",
-        libsql::params![op_id, user_id, payload],
-    ).await.map_err(|e| format!("Queue delete failed: {e}"))?;
+    ops::enqueue(&conn, &user_id, "delete_document", serde_json::json!({ "doc_id": id }))
+        .await.map_err(|e| format!("Queue delete failed: {e}"))?;
+
+    Ok(())
+}
 
+/// Move a document to the trash (undoable with restore_document). Trashed
+/// documents are left out of listings and search; query_documents with
+/// `trashed: true` lists them.
+#[tauri::command]
+pub async fn trash_document(id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let (user_id, _) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+    if !trash::trash(&conn, &user_id, &id).await.map_err(|e| e.to_string())? {
+        return Err(format!("Document {id} not found or already in the trash"));
+    }
     Ok(())
-}
\ No newline at end of file
+}
+
+#[tauri::command]
+pub async fn restore_document(id: String, state: State<'_, AppState>) -> Result<Document, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let (user_id, _) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+    if !trash::restore(&conn, &user_id, &id).await.map_err(|e| e.to_string())? {
+        return Err(format!("Document {id} is not in the trash"));
+    }
+    load_document(&conn, &id).await
+}
+
+/// Permanently delete everything in the trash. Returns the number deleted.
+#[tauri::command]
+pub async fn empty_trash(state: State<'_, AppState>) -> Result<u64, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let purged = trash::purge(&conn, None).await.map_err(|e| e.to_string())?;
+    Ok(purged.len() as u64)
+}
+
+/// Apply `changes` to many documents in one transaction, queueing their sync
+/// ops alongside. Missing and deleted ids are skipped. Returns the number of
+/// documents changed.
+#[tauri::command]
+pub async fn bulk_update_documents(
+    ids: Vec<String>,
+    changes: BulkDocumentChanges,
+    state: State<'_, AppState>,
+) -> Result<u64, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    if let Some(c) = &changes.collection_id {
+        super::collections::ensure_exists(&conn, c).await?;
+    }
+    let (user_id, _) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+
+    let add_tags    = tags::normalize(&changes.add_tags);
+    let remove_tags = tags::normalize(&changes.remove_tags);
+    let move_to     = match (&changes.collection_id, changes.clear_collection) {
+        (Some(c), _)  => Some(Some(c.clone())),
+        (None, true)  => Some(None),
+        (None, false) => None,
+    };
+    let edits = !add_tags.is_empty() || !remove_tags.is_empty() || move_to.is_some();
+
+    let tx = conn.transaction().await.map_err(|e| e.to_string())?;
+    let mut add_ids = Vec::new();
+    for name in &add_tags {
+        add_ids.push(tags::ensure(&tx, name).await.map_err(|e| e.to_string())?);
+    }
+    let mut remove_ids = Vec::new();
+    for name in &remove_tags {
+        if let Some(tag_id) = tags::find(&tx, name).await.map_err(|e| e.to_string())? {
+            remove_ids.push(tag_id);
+        }
+    }
+
+    let mut changed = 0;
+    for id in &ids {
+        let mut touched = false;
+        if edits {
+            let live = tx.execute(
+                "UPDATE documents SET local_version = local_version + 1, updated_at = datetime('now')
+                 WHERE id = ?1 AND status != 'deleted'",
+                libsql::params![id.clone()],
+            ).await.map_err(|e| format!("Update failed: {e}"))? > 0;
+            if !live {
+                continue;
+            }
+            for tag_id in &remove_ids {
+                tx.execute(
+                    "DELETE FROM document_tags WHERE document_id = ?1 AND tag_id = ?2",
+                    libsql::params![id.clone(), *tag_id],
+                ).await.map_err(|e| format!("Update failed: {e}"))?;
+            }
+            for tag_id in &add_ids {
+                tx.execute(
+                    "INSERT OR IGNORE INTO document_tags (document_id, tag_id) VALUES (?1, ?2)",
+                    libsql::params![id.clone(), *tag_id],
+                ).await.map_err(|e| format!("Update failed: {e}"))?;
+            }
+            if let Some(collection_id) = &move_to {
+                tx.execute(
+                    "UPDATE documents SET collection_id = ?1 WHERE id = ?2",
+                    libsql::params![collection_id.clone(), id.clone()],
+                ).await.map_err(|e| format!("Update failed: {e}"))?;
+            }
+            ops::enqueue(&tx, &user_id, "update_document", serde_json::json!({ "doc_id": id }))
+                .await.map_err(|e| format!("Queue op failed: {e}"))?;
+            touched = true;
+        }
+        touched |= match changes.trashed {
+            Some(true)  => trash::trash(&tx, &user_id, id).await,
+            Some(false) => trash::restore(&tx, &user_id, id).await,
+            None        => Ok(false),
+        }.map_err(|e| e.to_string())?;
+        if touched {
+            changed += 1;
+        }
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(changed)
+}
+
+/// Delete many documents in one transaction. Missing and already deleted ids
+/// are skipped. Returns the number deleted.
+#[tauri::command]
+pub async fn bulk_delete_documents(ids: Vec<String>, state: State<'_, AppState>) -> Result<u64, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let (user_id, _) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+
+    let tx = conn.transaction().await.map_err(|e| e.to_string())?;
+    let mut deleted = 0;
+    for id in &ids {
+        let changed = tx.execute(
+            "UPDATE documents SET status = 'deleted', updated_at = datetime('now')
+             WHERE id = ?1 AND status != 'deleted'",
+            libsql::params![id.clone()],
+        ).await.map_err(|e| format!("Delete failed: {e}"))?;
+        if changed > 0 {
+            ops::enqueue(&tx, &user_id, "delete_document", serde_json::json!({ "doc_id": id }))
+                .await.map_err(|e| format!("Queue delete failed: {e}"))?;
+            deleted += 1;
+        }
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(deleted)
+}
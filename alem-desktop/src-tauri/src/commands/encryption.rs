@@ -0,0 +1,56 @@
+// src-tauri/src/commands/encryption.rs
+// Database encryption at rest. Both setters only stage the key — it is applied
+// on the next launch (see db::encryption), which the UI should prompt for.
+//
+// The database is encrypted with SQLite3MultipleCiphers' AES-256-CBC cipher,
+// the only one libsql exposes, not in SQLCipher's format: a copied alem.db
+// opens in Alem (or SQLite3MultipleCiphers with cipher=aes256cbc), not in
+// sqlcipher tools. A passphrase is never stored, only the key derived from it.
+use crate::db::{
+    encryption::{self, DbKey},
+    models::{DatabaseEncryption, KeySource},
+};
+
+#[tauri::command]
+pub async fn get_database_encryption() -> Result<DatabaseEncryption, String> {
+    encryption::status().map_err(|e| e.to_string())
+}
+
+/// Encrypt a plaintext database. Without a passphrase a random key is
+/// generated and kept in the OS keychain.
+#[tauri::command]
+pub async fn set_database_passphrase(passphrase: Option<String>) -> Result<DatabaseEncryption, String> {
+    if encryption::current_key().map_err(|e| e.to_string())?.is_some() {
+        return Err("Database is already encrypted — use change_database_passphrase".into());
+    }
+    stage(passphrase)
+}
+
+/// Re-key an encrypted database. `current_passphrase` is required when the
+/// existing key is a user passphrase; passing no new passphrase switches to a
+/// generated keychain key.
+#[tauri::command]
+pub async fn change_database_passphrase(
+    current_passphrase: Option<String>,
+    new_passphrase: Option<String>,
+) -> Result<DatabaseEncryption, String> {
+    let current = encryption::current_key()
+        .map_err(|e| e.to_string())?
+        .ok_or("Database is not encrypted — use set_database_passphrase")?;
+
+    if current.source == KeySource::Passphrase && !current_passphrase.is_some_and(|p| current.is_passphrase(&p)) {
+        return Err("Current passphrase is incorrect".into());
+    }
+    stage(new_passphrase)
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+fn stage(passphrase: Option<String>) -> Result<DatabaseEncryption, String> {
+    let key = match passphrase {
+        Some(p) => DbKey::passphrase(&p).map_err(|e| e.to_string())?,
+        None    => DbKey::generate(),
+    };
+    encryption::stage(&key).map_err(|e| e.to_string())?;
+    encryption::status().map_err(|e| e.to_string())
+}
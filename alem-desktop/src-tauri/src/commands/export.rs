@@ -0,0 +1,103 @@
+// src-tauri/src/commands/export.rs
+use crate::{db::models::ExportSelection, AppState};
+use std::path::PathBuf;
+use tauri::{AppHandle, State};
+
+/// Export the selected documents (files, metadata, tags, collections) to a
+/// portable archive at `dest`. Returns a job id.
+#[tauri::command]
+pub async fn export_archive(
+    selection: Option<ExportSelection>,
+    dest: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let job_app = app.clone();
+    Ok(state.jobs.spawn(&app, "export_archive", move |job| async move {
+        crate::export::export(&job_app, &selection.unwrap_or_default(), &PathBuf::from(dest), &job).await
+    }))
+}
+
+/// Import an archive from export_archive. `preserve_ids` keeps the original
+/// ids (skipping documents that already exist); by default everything gets
+/// new ids. Returns a job id.
+#[tauri::command]
+pub async fn import_archive(
+    src: String,
+    preserve_ids: Option<bool>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let job_app = app.clone();
+    Ok(state.jobs.spawn(&app, "import_archive", move |job| async move {
+        crate::export::import(&job_app, &PathBuf::from(src), preserve_ids.unwrap_or(false), &job).await
+    }))
+}
+
+/// Export every unsent offline operation, with the documents and collections it
+/// references, for moving to a new device before a final sync. With `hand_off`
+/// (the default) the exported operations stop running on this device.
+/// Returns a job id.
+#[tauri::command]
+pub async fn export_sync_queue(
+    dest: String,
+    hand_off: Option<bool>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let job_app = app.clone();
+    Ok(state.jobs.spawn(&app, "export_sync_queue", move |job| async move {
+        crate::export::export_queue(&job_app, &PathBuf::from(dest), hand_off.unwrap_or(true), &job).await
+    }))
+}
+
+/// Queue the operations from an export_sync_queue archive on this device.
+/// Returns a job id.
+#[tauri::command]
+pub async fn import_sync_queue(
+    src: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let job_app = app.clone();
+    Ok(state.jobs.spawn(&app, "import_sync_queue", move |job| async move {
+        crate::export::import_queue(&job_app, &PathBuf::from(src), &job).await
+    }))
+}
+
+/// Copy every document and folder from another Alem account, read with
+/// `token` from `server_url`, into the current tenant with new ids. Running it
+/// again for the same server continues where an interrupted run stopped.
+/// Returns a job id; the job result is { imported, skipped, missing,
+/// collections }.
+#[tauri::command]
+pub async fn migrate_from_account(
+    server_url: String,
+    token: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    if !server_url.starts_with("http://") && !server_url.starts_with("https://") {
+        return Err("server_url must be an http(s) URL".into());
+    }
+    let job_app = app.clone();
+    Ok(state.jobs.spawn(&app, "migrate_from_account", move |job| async move {
+        crate::sync::migrate::run(&job_app, &server_url, &token, &job).await
+    }))
+}
+
+/// Bundle every document, annotation, activity entry and metadata value
+/// matching `query` (a person's name, e-mail, …) into a signed archive at
+/// `dest`, for a data subject access request. Returns a job id.
+#[tauri::command]
+pub async fn compliance_export(
+    query: String,
+    dest: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let job_app = app.clone();
+    Ok(state.jobs.spawn(&app, "compliance_export", move |job| async move {
+        crate::compliance::export(&job_app, &query, &PathBuf::from(dest), &job).await
+    }))
+}
@@ -1,33 +1,58 @@
 // src-tauri/src/commands/files.rs
-use crate::AppState;
+use crate::{
+    db::{self, access, models::{BucketCredentials, IpfsSettings, StorageSettings, StorageTarget}, settings},
+    extract,
+    sync::bucket,
+    storage::{cas, chunked::{self, ReadManifest}, compress, ipfs, protocol, quota::{self, StorageUsage}, verify},
+    AppState,
+};
+use sha2::{Digest, Sha256};
+use std::path::Path;
 use tauri::{AppHandle, Manager, State};
-use uuid::Uuid;
-use std::path::PathBuf;
 
+const MIN_QUOTA_BYTES: u64 = 64 * 1024 * 1024;
+const MIN_TEXT_BYTES:  u64 = 64 * 1024;
+
+/// Copy a file into the blob store and return its key, the local path with
+/// the local store. Blobs are named by sha256, so the same content is stored
+/// once.
 #[tauri::command]
 pub async fn store_file(
     source_path: String,
     filename: String,
-    app: AppHandle,
-    _state: State<'_, AppState>,
+    state: State<'_, AppState>,
 ) -> Result<String, String> {
-    let data_dir = app.path().app_data_dir()
-        .map_err(|e| e.to_string())?;
-    let files_dir = data_dir.join("files");
-    tokio::fs::create_dir_all(&files_dir).await.map_err(|e| e.to_string())?;
-
-    let ext = PathBuf::from(&filename)
-        .extension()
-        .and_then(|e| e.to_str())
-        .map(|e| format!(".{e}"))
-        .unwrap_or_default();
+    let blob = state.blobs.put(Path::new(&source_path)).await.map_err(|e| e.to_string())?;
+    // Kept from the GC until create_document claims it
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    cas::stage(&conn, &blob.key, &blob.hash, blob.size).await.map_err(|e| e.to_string())?;
 
-    let dest_name = format!("{}{}", Uuid::new_v4(), ext);
-    let dest      = files_dir.join(&dest_name);
+    log::debug!("[files] Stored {filename} as {} in {}", blob.hash, state.blobs.name());
+    Ok(blob.key)
+}
 
-    tokio::fs::copy(&source_path, &dest).await.map_err(|e| e.to_string())?;
+/// The content type to store for a file, from its magic bytes: corrects a
+/// wrong `declared` type and fills in a missing one (then also by extension).
+/// create_document does the same, so calling this first is only needed to
+/// show the type before the document exists.
+#[tauri::command]
+pub async fn detect_content_type(
+    local_path: String,
+    filename: String,
+    declared: Option<String>,
+    _state: State<'_, AppState>,
+) -> Result<String, String> {
+    let path = Path::new(&local_path);
+    if tokio::fs::metadata(path).await.is_err() {
+        return Err("File not found".into());
+    }
+    Ok(extract::sniff::detect(path, declared.as_deref().unwrap_or_default(), &filename).await)
+}
 
-    Ok(dest.to_string_lossy().to_string())
+/// `alemfile://` URL streaming the document's bytes to the webview.
+#[tauri::command]
+pub async fn get_preview_url(id: String, _state: State<'_, AppState>) -> Result<String, String> {
+    Ok(protocol::preview_url(&id))
 }
 
 #[tauri::command]
@@ -35,17 +60,303 @@ pub async fn get_file_path(
     local_path: String,
     _state: State<'_, AppState>,
 ) -> Result<String, String> {
-    if tokio::fs::metadata(&local_path).await.is_ok() {
-        Ok(local_path)
-    } else {
-        Err("File not found".into())
+    if tokio::fs::metadata(&local_path).await.is_err() {
+        return Err("File not found".into());
     }
+    // Other apps can't read compressed blobs
+    let path = compress::plain_path(Path::new(&local_path)).await.map_err(|e| e.to_string())?;
+    Ok(path.to_string_lossy().to_string())
 }
 
 #[tauri::command]
 pub async fn delete_file(
     local_path: String,
-    _state: State<'_, AppState>,
+    state: State<'_, AppState>,
 ) -> Result<(), String> {
-    tokio::fs::remove_file(&local_path).await.map_err(|e| e.to_string())
-}
\ No newline at end of file
+    // Blobs are shared by content — keep the file while a live document uses it
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let mut rows = conn.query(
+        "SELECT COUNT(*) FROM documents WHERE local_path = ?1 AND status != 'deleted'",
+        libsql::params![local_path.clone()],
+    ).await.map_err(|e| e.to_string())?;
+    let in_use = match rows.next().await.map_err(|e| e.to_string())? {
+        Some(row) => matches!(row.get_value(0), Ok(libsql::Value::Integer(n)) if n > 0),
+        None      => false,
+    };
+    if in_use {
+        return Ok(());
+    }
+
+    state.blobs.delete(&local_path).await.map_err(|e| e.to_string())
+}
+
+/// Return a document's bytes, verifying them against content_hash first
+/// (`verify` overrides the storage setting). Missing or corrupt local copies
+/// are re-downloaded via the object_key when the document has been synced.
+#[tauri::command]
+pub async fn get_document_content(
+    id: String,
+    verify: Option<bool>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<tauri::ipc::Response, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+
+    let mut rows = conn.query(
+        "SELECT local_path, content_hash, object_key FROM documents WHERE id = ?1",
+        libsql::params![id.clone()],
+    ).await.map_err(|e| e.to_string())?;
+    let (local_path, content_hash, object_key) = match rows.next().await.map_err(|e| e.to_string())? {
+        Some(row) => {
+            use libsql::Value;
+            let s = |i| match row.get_value(i).ok() { Some(Value::Text(s)) if !s.is_empty() => Some(s), _ => None };
+            (s(0), s(1), s(2))
+        }
+        None => return Err(format!("Document {id} not found")),
+    };
+
+    let verify = match verify {
+        Some(v) => v,
+        None => settings::get::<StorageSettings>(&conn, settings::STORAGE_SETTINGS).await
+            .map_err(|e| e.to_string())?
+            .unwrap_or_default()
+            .verify_on_read,
+    };
+
+    let local = match &local_path {
+        Some(p) => state.blobs.get(p).await.ok(),
+        None    => None,
+    };
+
+    let local = local.filter(|bytes| {
+        let expected = content_hash.as_deref().filter(|h| verify && cas::is_sha256_hex(h));
+        match expected {
+            Some(h) if !cas::hex(&Sha256::digest(bytes)).eq_ignore_ascii_case(h) => {
+                log::warn!("[files] {id}: local content does not match content_hash");
+                false
+            }
+            _ => true,
+        }
+    });
+
+    let bytes = match local {
+        Some(bytes) => bytes,
+        None if object_key.is_some() => {
+            let path = crate::sync::engine::download_document(&app, &id).await.map_err(|e| e.to_string())?;
+            state.blobs.get(&path.to_string_lossy()).await.map_err(|e| e.to_string())?
+        }
+        None => return Err(format!("Content for {id} is missing or corrupt and has not been synced")),
+    };
+
+    if let Err(e) = access::record(&conn, &id).await {
+        log::warn!("[access] Could not record access to {id}: {e}");
+    }
+    Ok(tauri::ipc::Response::new(bytes))
+}
+
+/// Locally cached bytes against the storage quota, by document status.
+#[tauri::command]
+pub async fn get_storage_usage(state: State<'_, AppState>) -> Result<StorageUsage, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    quota::usage(&conn).await.map_err(|e| e.to_string())
+}
+
+/// Set (or with None remove) the soft quota on locally cached files, then
+/// evict right away if usage is over it. Only synced documents are evicted;
+/// their content downloads again when opened.
+#[tauri::command]
+pub async fn set_storage_quota(
+    quota_bytes: Option<u64>,
+    state: State<'_, AppState>,
+) -> Result<StorageUsage, String> {
+    if quota_bytes.is_some_and(|q| q < MIN_QUOTA_BYTES) {
+        return Err(format!("The storage quota must be at least {} MiB", MIN_QUOTA_BYTES / (1024 * 1024)));
+    }
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let mut storage: StorageSettings = settings::get(&conn, settings::STORAGE_SETTINGS).await
+        .map_err(|e| e.to_string())?
+        .unwrap_or_default();
+    storage.quota_bytes = quota_bytes;
+    settings::set(&conn, settings::STORAGE_SETTINGS, &storage).await.map_err(|e| e.to_string())?;
+
+    quota::enforce(&conn).await.map_err(|e| e.to_string())?;
+    quota::usage(&conn).await.map_err(|e| e.to_string())
+}
+
+/// Set the longest text_content kept in the database. Only new documents are
+/// affected; longer text is cut and kept whole on disk (get_document_text).
+#[tauri::command]
+pub async fn set_max_text_bytes(max_bytes: u64, state: State<'_, AppState>) -> Result<StorageSettings, String> {
+    if max_bytes < MIN_TEXT_BYTES {
+        return Err(format!("The text limit must be at least {} KiB", MIN_TEXT_BYTES / 1024));
+    }
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let mut storage: StorageSettings = settings::get(&conn, settings::STORAGE_SETTINGS).await
+        .map_err(|e| e.to_string())?
+        .unwrap_or_default();
+    storage.max_text_bytes = max_bytes;
+    settings::set(&conn, settings::STORAGE_SETTINGS, &storage).await.map_err(|e| e.to_string())?;
+    Ok(storage)
+}
+
+/// Turn zstd compression of cached files on or off. Maintenance compresses
+/// files of compressible types while idle; turning it off leaves files
+/// already compressed as they are (they read the same either way).
+#[tauri::command]
+pub async fn set_storage_compression(
+    enabled: bool,
+    level: Option<i32>,
+    state: State<'_, AppState>,
+) -> Result<StorageSettings, String> {
+    if level.is_some_and(|l| !(1..=19).contains(&l)) {
+        return Err("Compression level must be between 1 and 19".into());
+    }
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let mut storage: StorageSettings = settings::get(&conn, settings::STORAGE_SETTINGS).await
+        .map_err(|e| e.to_string())?
+        .unwrap_or_default();
+    storage.compression = enabled;
+    if let Some(level) = level {
+        storage.compression_level = level;
+    }
+    settings::set(&conn, settings::STORAGE_SETTINGS, &storage).await.map_err(|e| e.to_string())?;
+    Ok(storage)
+}
+
+/// Where uploads go: the server's default bucket, or the user's own.
+#[tauri::command]
+pub async fn get_storage_target(state: State<'_, AppState>) -> Result<StorageTarget, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    bucket::target(&conn).await.map_err(|e| e.to_string())
+}
+
+/// Register the user's own S3-compatible bucket with the server and send
+/// uploads there, once a probe object has been written and read back through
+/// it from this machine. The credentials go to the server only.
+#[tauri::command]
+pub async fn register_storage_bucket(
+    credentials: BucketCredentials,
+    app: AppHandle,
+) -> Result<StorageTarget, String> {
+    for (field, value) in [
+        ("endpoint", &credentials.endpoint),
+        ("bucket", &credentials.bucket),
+        ("access_key_id", &credentials.access_key_id),
+        ("secret_access_key", &credentials.secret_access_key),
+    ] {
+        if value.trim().is_empty() {
+            return Err(format!("{field} is required"));
+        }
+    }
+    if !credentials.endpoint.starts_with("https://") && !credentials.endpoint.starts_with("http://") {
+        return Err("endpoint must be an http(s) URL".into());
+    }
+    bucket::register(&app, &credentials).await.map_err(|e| format!("{e:#}"))
+}
+
+/// Send uploads to the server's default bucket again.
+#[tauri::command]
+pub async fn reset_storage_target(state: State<'_, AppState>) -> Result<StorageTarget, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    bucket::reset(&conn).await.map_err(|e| e.to_string())?;
+    Ok(StorageTarget::default())
+}
+
+#[tauri::command]
+pub async fn get_ipfs_settings(state: State<'_, AppState>) -> Result<IpfsSettings, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let current = settings::get(&conn, settings::IPFS_SETTINGS).await.map_err(|e| e.to_string())?;
+    Ok(current.unwrap_or_default())
+}
+
+/// Pin uploads to an IPFS node (or stop). Turning it on checks that the node
+/// answers first. Documents already uploaded stay where they are.
+#[tauri::command]
+pub async fn update_ipfs_settings(
+    settings: IpfsSettings,
+    state: State<'_, AppState>,
+) -> Result<IpfsSettings, String> {
+    if !settings.api_url.starts_with("http://") && !settings.api_url.starts_with("https://") {
+        return Err("api_url must be an http(s) URL".into());
+    }
+    if settings.enabled {
+        let store = ipfs::IpfsStore::new(&settings.api_url).map_err(|e| e.to_string())?;
+        let version = store.version().await
+            .map_err(|e| format!("IPFS node at {} is not reachable: {e}", settings.api_url))?;
+        log::info!("[files] Uploads go to IPFS node {} (Kubo {version})", settings.api_url);
+    }
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    settings::set(&conn, settings::IPFS_SETTINGS, &settings).await.map_err(|e| e.to_string())?;
+    Ok(settings)
+}
+
+/// Re-hash every locally cached file against its content_hash. With
+/// `repair`, documents whose file is missing or corrupt are queued to
+/// download again from the server. Returns a job id; the job result lists
+/// the missing and corrupted documents.
+#[tauri::command]
+pub async fn verify_documents(
+    repair: Option<bool>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let job_app = app.clone();
+    Ok(state.jobs.spawn(&app, "verify_documents", move |job| async move {
+        let conn   = db::connect(&job_app.state::<AppState>().db).await?;
+        let report = verify::run(&conn, repair.unwrap_or(false), &job).await?;
+        Ok(serde_json::to_value(report)?)
+    }))
+}
+
+// ── Chunked reads ────────────────────────────────────────────────────────────
+
+/// Start a chunked read of a document. The manifest lists every chunk's sha256;
+/// verify each chunk against it and call end_content_read when done.
+#[tauri::command]
+pub async fn begin_content_read(
+    id: String,
+    chunk_size: Option<u64>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ReadManifest, String> {
+    let (path, _) = protocol::locate(&app, &id).await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Document {id} not found"))?;
+    state.reads.open(path, chunk_size).await.map_err(|e| e.to_string())
+}
+
+/// Raw bytes `offset..offset + len` of a document, so players and viewers
+/// can seek without loading the whole file. At most 32 MiB per call; fewer
+/// bytes come back at the end of the file. A missing local copy is
+/// downloaded first.
+#[tauri::command]
+pub async fn get_document_content_range(
+    doc_id: String,
+    offset: u64,
+    len: u64,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<tauri::ipc::Response, String> {
+    let (path, _) = protocol::locate(&app, &doc_id).await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Document {doc_id} not found"))?;
+    let bytes = chunked::read_range(state.blobs.as_ref(), &path.to_string_lossy(), offset, len).await
+        .map_err(|e| e.to_string())?;
+    Ok(tauri::ipc::Response::new(bytes))
+}
+
+/// Raw bytes of one chunk.
+#[tauri::command]
+pub async fn read_content_chunk(
+    session_id: String,
+    index: u64,
+    state: State<'_, AppState>,
+) -> Result<tauri::ipc::Response, String> {
+    let bytes = state.reads.read(&session_id, index).await.map_err(|e| e.to_string())?;
+    Ok(tauri::ipc::Response::new(bytes))
+}
+
+#[tauri::command]
+pub async fn end_content_read(session_id: String, state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(state.reads.close(&session_id))
+}
@@ -0,0 +1,384 @@
+// src-tauri/src/commands/import.rs
+// The standard file import pipeline: copy into the content-addressed store,
+// insert the document row, queue the upload. Every import entry point (native
+// dialog, drag-and-drop paths, watched folders) should go through import_path
+// or import_with.
+use crate::{
+    commands::documents::{insert_document, CreateDocumentInput},
+    db::{self, identity, models::{row_to_summary, summary_columns, DocumentSummary, MediaSettings}, ops, settings},
+    extract, ocr, scan,
+    storage::{cas, text},
+    AppState,
+};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_dialog::DialogExt;
+use tokio::io::AsyncReadExt;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+pub struct FileFilter {
+    pub name:       String,
+    /// Without the dot: ["pdf", "png"].
+    pub extensions: Vec<String>,
+}
+
+/// Applied to every file of an import_files call.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct ImportOptions {
+    /// Put the documents in this collection.
+    pub collection_id:   Option<String>,
+    pub tags:            Vec<String>,
+    /// Leave out files whose content is already in the library.
+    pub skip_duplicates: bool,
+}
+
+/// Open the system file picker and import the chosen files. Returns the created
+/// documents, or an empty list if the user cancelled the dialog.
+#[tauri::command]
+pub async fn import_with_dialog(
+    filters: Option<Vec<FileFilter>>,
+    allow_multiple: Option<bool>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Vec<DocumentSummary>, String> {
+    let mut dialog = app.dialog().file().set_title("Import documents");
+    for f in filters.unwrap_or_default() {
+        let exts: Vec<&str> = f.extensions.iter().map(String::as_str).collect();
+        dialog = dialog.add_filter(f.name, &exts);
+    }
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    if allow_multiple.unwrap_or(true) {
+        dialog.pick_files(move |picked| { let _ = tx.send(picked.unwrap_or_default()); });
+    } else {
+        dialog.pick_file(move |picked| { let _ = tx.send(picked.into_iter().collect()); });
+    }
+    let picked = rx.await.map_err(|_| "File dialog closed unexpectedly".to_string())?;
+
+    let mut paths = Vec::with_capacity(picked.len());
+    for p in picked {
+        paths.push(p.into_path().map_err(|e| e.to_string())?);
+    }
+
+    let mut ids = Vec::with_capacity(paths.len());
+    for path in &paths {
+        ids.push(import_path(&app, path).await.map_err(|e| format!("{}: {e}", path.display()))?);
+    }
+    summaries(&state, &ids).await
+}
+
+/// Import many files at once, e.g. a drag-and-drop or a multi-file pick, as
+/// a background job. Folders among `paths` are imported with everything in
+/// them except hidden files. Returns a job id: each file advances the job's
+/// progress, and the result is { imported: [ids], duplicates, failed:
+/// [{path, error}] }. A file that fails doesn't stop the others.
+#[tauri::command]
+pub async fn import_files(
+    paths: Vec<String>,
+    options: Option<ImportOptions>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+    if let Some(c) = &options.collection_id {
+        let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+        super::collections::ensure_exists(&conn, c).await?;
+    }
+
+    let job_app = app.clone();
+    Ok(state.jobs.spawn(&app, "import_files", move |job| async move {
+        let files = expand(paths.iter().map(PathBuf::from).collect()).await;
+        job.set_total(files.len() as u64);
+        let conn = db::connect(&job_app.state::<AppState>().db).await?;
+        let (_, tenant_id) = identity::current(&conn).await?;
+
+        let (mut imported, mut duplicates, mut failed) = (Vec::new(), 0u64, Vec::new());
+        for path in &files {
+            job.check_cancelled()?;
+            let duplicate = options.skip_duplicates
+                && duplicate_of(&conn, &tenant_id, path).await.ok().flatten().is_some();
+            if duplicate {
+                duplicates += 1;
+            } else {
+                match import_with(&job_app, path, &options).await {
+                    Ok(id) => imported.push(id),
+                    Err(e) => {
+                        log::warn!("[import] {}: {e}", path.display());
+                        failed.push(serde_json::json!({ "path": path.to_string_lossy(), "error": e.to_string() }));
+                    }
+                }
+            }
+            let name = path.file_name().map(|n| n.to_string_lossy().into_owned());
+            job.advance(1, name);
+        }
+
+        log::info!("[import] {} imported, {duplicates} duplicates, {} failed", imported.len(), failed.len());
+        Ok(serde_json::json!({ "imported": imported, "duplicates": duplicates, "failed": failed }))
+    }))
+}
+
+/// A document from the clipboard's text, or failing that its image.
+#[tauri::command]
+pub async fn create_document_from_clipboard(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<DocumentSummary, String> {
+    let id = crate::capture::from_clipboard(&app).await.map_err(|e| format!("{e:#}"))?;
+    summaries(&state, &[id]).await?.pop().ok_or_else(|| "Document not found".to_string())
+}
+
+/// A document from what `url` serves. A web page is kept as HTML, with its
+/// address, title and description in metadata.
+#[tauri::command]
+pub async fn create_document_from_url(
+    url: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<DocumentSummary, String> {
+    let id = crate::capture::from_url(&app, &url).await.map_err(|e| format!("{e:#}"))?;
+    summaries(&state, &[id]).await?.pop().ok_or_else(|| "Document not found".to_string())
+}
+
+#[tauri::command]
+pub async fn get_media_settings(state: State<'_, AppState>) -> Result<MediaSettings, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let current = settings::get(&conn, settings::MEDIA_SETTINGS).await.map_err(|e| e.to_string())?;
+    Ok(current.unwrap_or_default())
+}
+
+/// Applies to files imported from now on.
+#[tauri::command]
+pub async fn update_media_settings(
+    settings: MediaSettings,
+    state: State<'_, AppState>,
+) -> Result<MediaSettings, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    settings::set(&conn, settings::MEDIA_SETTINGS, &settings).await.map_err(|e| e.to_string())?;
+    Ok(settings)
+}
+
+/// Import one file from disk. Returns the new document id.
+pub(crate) async fn import_path(app: &AppHandle, source: &Path) -> anyhow::Result<String> {
+    import_with(app, source, &ImportOptions::default()).await
+}
+
+/// import_path, with the document given `options`' collection and tags.
+/// skip_duplicates is the caller's business.
+pub(crate) async fn import_with(app: &AppHandle, source: &Path, options: &ImportOptions) -> anyhow::Result<String> {
+    let filename = source
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| anyhow::anyhow!("Not a file path"))?;
+    // Metadata syncs, so where the file sat on this disk stays out of it
+    let metadata = serde_json::json!({ "source": "file" });
+    import_file_as(app, source, &filename, metadata, options).await
+}
+
+/// Import the file at `source` named `filename` rather than after the file,
+/// with `metadata` saying where it came from.
+pub(crate) async fn import_file_as(
+    app: &AppHandle,
+    source: &Path,
+    filename: &str,
+    metadata: serde_json::Value,
+    options: &ImportOptions,
+) -> anyhow::Result<String> {
+    let (dest, hash, size) = cas::import_file(&cas::files_dir(app)?, source).await?;
+    let id = ingest(app, (dest, hash, size), filename.to_string(), metadata, options).await?;
+
+    log::info!("[import] {} → {id}", source.display());
+    Ok(id)
+}
+
+/// Import content that isn't a file on disk (clipboard, a download), named
+/// `filename`, with `metadata` saying where it came from.
+pub(crate) async fn import_bytes(
+    app: &AppHandle,
+    filename: &str,
+    bytes: &[u8],
+    metadata: serde_json::Value,
+    options: &ImportOptions,
+) -> anyhow::Result<String> {
+    let (dest, hash) = cas::store_bytes(&cas::files_dir(app)?, bytes).await?;
+    let id = ingest(app, (dest, hash, bytes.len() as u64), filename.to_string(), metadata, options).await?;
+
+    log::info!("[import] {filename} → {id}");
+    Ok(id)
+}
+
+/// Text for a file just stored at `input.local_path`: the file itself for
+/// plain-text formats, the extractors otherwise. text::limit still has to
+/// run before the row is written.
+pub(crate) async fn fill_text(conn: &libsql::Connection, input: &mut CreateDocumentInput) -> anyhow::Result<()> {
+    let max_text = text::max_bytes(conn).await?;
+    let size = input.file_size as u64;
+    input.text_content = read_text(Path::new(&input.local_path), &input.content_type, max_text).await;
+    // A text file is its own full text
+    if let Some(stored) = input.text_content.as_ref().map(String::len).filter(|_| size > max_text) {
+        text::flag(input, stored as u64, size);
+        input.full_text_path = Some(input.local_path.clone());
+    }
+    extract::fill(input).await;
+    Ok(())
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+/// The rest of the pipeline for a blob already in the store: (path, hash,
+/// size).
+async fn ingest(
+    app: &AppHandle,
+    (dest, hash, size): (PathBuf, String, u64),
+    filename: String,
+    metadata: serde_json::Value,
+    options: &ImportOptions,
+) -> anyhow::Result<String> {
+    let conn = db::connect(&app.state::<AppState>().db).await?;
+    let files_dir = cas::files_dir(app)?;
+    let content_type = extract::sniff::detect(&dest, &content_type_for(&filename), &filename).await;
+
+    let mut input = CreateDocumentInput {
+        filename,
+        content_type,
+        local_path:     dest.to_string_lossy().to_string(),
+        file_size:      size as i64,
+        content_hash:   hash,
+        text_content:   None,
+        metadata:       Some(metadata),
+        tags:           Some(options.tags.clone()).filter(|t| !t.is_empty()),
+        full_text_path: None,
+    };
+    fill_text(&conn, &mut input).await?;
+    extract::media::fill(&conn, &mut input).await?;
+    scan::scan(&conn, &mut input).await?;
+    text::limit(&files_dir, &conn, &mut input).await?;
+
+    let (user_id, tenant_id) = identity::current(&conn).await?;
+    let id = Uuid::new_v4().to_string();
+
+    let tx = conn.transaction().await?;
+    insert_document(&tx, &id, &user_id, &tenant_id, &input).await?;
+    ops::enqueue(&tx, &user_id, "upload_document", serde_json::json!({ "doc_id": id })).await?;
+    // The upload creates the document on the server; the folder follows as an update
+    if let Some(collection_id) = &options.collection_id {
+        tx.execute(
+            "UPDATE documents SET collection_id = ?1 WHERE id = ?2",
+            libsql::params![collection_id.as_str(), id.as_str()],
+        ).await?;
+        ops::enqueue(&tx, &user_id, "update_document", serde_json::json!({ "doc_id": id })).await?;
+    }
+    tx.commit().await?;
+    scan::notify(app, &id, &input);
+    ocr::queue_if_needed(&conn, &id, &input).await?;
+    Ok(id)
+}
+
+/// Extension → MIME type (same table as the frontend's guessContentType).
+pub(crate) fn content_type_for(filename: &str) -> String {
+    let ext = Path::new(filename)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "txt"          => "text/plain",
+        "html" | "htm" => "text/html",
+        "md"           => "text/markdown",
+        "csv"          => "text/csv",
+        "json"         => "application/json",
+        "pdf"          => "application/pdf",
+        "jpg" | "jpeg" => "image/jpeg",
+        "png"          => "image/png",
+        "gif"          => "image/gif",
+        "webp"         => "image/webp",
+        "heic"         => "image/heic",
+        "tif" | "tiff" => "image/tiff",
+        "mp4"          => "video/mp4",
+        "mov"          => "video/quicktime",
+        "m4v"          => "video/x-m4v",
+        "mp3"          => "audio/mpeg",
+        "m4a"          => "audio/mp4",
+        "doc"          => "application/msword",
+        "docx"         => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        _              => "application/octet-stream",
+    }.to_string()
+}
+
+/// `paths` with folders replaced by the files in them (recursively, hidden
+/// entries and symlinked folders left out). Paths that can't be read are
+/// kept, so their import fails and is reported.
+async fn expand(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut stack: Vec<PathBuf> = paths.into_iter().rev().collect();
+    while let Some(path) = stack.pop() {
+        let Ok(meta) = tokio::fs::metadata(&path).await else {
+            files.push(path);
+            continue;
+        };
+        if !meta.is_dir() {
+            files.push(path);
+            continue;
+        }
+        let Ok(mut entries) = tokio::fs::read_dir(&path).await else { continue };
+        let mut children = Vec::new();
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            let symlink = entry.file_type().await.map(|t| t.is_symlink()).unwrap_or(true);
+            if hidden || (symlink && tokio::fs::metadata(entry.path()).await.is_ok_and(|m| m.is_dir())) {
+                continue;
+            }
+            children.push(entry.path());
+        }
+        children.sort();
+        stack.extend(children.into_iter().rev());
+    }
+    files
+}
+
+/// A live document of the tenant with the same content as `path`.
+async fn duplicate_of(conn: &libsql::Connection, tenant_id: &str, path: &Path) -> anyhow::Result<Option<String>> {
+    let hash = cas::hash_file(path).await?;
+    let mut rows = conn.query(
+        "SELECT id FROM documents
+         WHERE content_hash = ?1 AND tenant_id = ?2 AND status != 'deleted' AND trashed_at IS NULL
+         LIMIT 1",
+        libsql::params![hash, tenant_id],
+    ).await?;
+    Ok(match rows.next().await? {
+        Some(row) => row.get::<String>(0).ok(),
+        None      => None,
+    })
+}
+
+/// Plain-text formats are indexed as-is, up to `max_bytes`; other formats
+/// are left to extract::fill.
+async fn read_text(path: &Path, content_type: &str, max_bytes: u64) -> Option<String> {
+    if !extract::is_plain_text(content_type) {
+        return None;
+    }
+    let mut bytes = Vec::new();
+    tokio::fs::File::open(path).await.ok()?.take(max_bytes).read_to_end(&mut bytes).await.ok()?;
+    // Cutting at max_bytes may split the last character
+    let end = match std::str::from_utf8(&bytes) {
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        _                                 => bytes.len(),
+    };
+    Some(String::from_utf8_lossy(&bytes[..end]).into_owned())
+}
+
+pub(crate) async fn summaries(state: &State<'_, AppState>, ids: &[String]) -> Result<Vec<DocumentSummary>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let mut docs = Vec::with_capacity(ids.len());
+    for id in ids {
+        let mut rows = conn.query(
+            &format!("SELECT {} FROM documents WHERE id = ?1", summary_columns("")),
+            libsql::params![id.as_str()],
+        ).await.map_err(|e| e.to_string())?;
+        if let Some(row) = rows.next().await.map_err(|e| e.to_string())? {
+            docs.push(row_to_summary(&row).map_err(|e| e.to_string())?);
+        }
+    }
+    Ok(docs)
+}
@@ -0,0 +1,18 @@
+// src-tauri/src/commands/jobs.rs
+use crate::{jobs::JobInfo, AppState};
+use tauri::State;
+
+#[tauri::command]
+pub async fn list_jobs(state: State<'_, AppState>) -> Result<Vec<JobInfo>, String> {
+    Ok(state.jobs.list())
+}
+
+#[tauri::command]
+pub async fn get_job(id: String, state: State<'_, AppState>) -> Result<JobInfo, String> {
+    state.jobs.get(&id).ok_or_else(|| format!("Job {id} not found"))
+}
+
+#[tauri::command]
+pub async fn cancel_job(id: String, state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(state.jobs.cancel(&id))
+}
@@ -0,0 +1,213 @@
+// src-tauri/src/commands/links.rs
+// Typed relations between documents ("references", "attachment-of", …) and the
+// backlinks/graph views built on them. Links are local to this device: the
+// server has nowhere to keep them, so no sync op is queued for them.
+use crate::{
+    db::{
+        self,
+        models::{row_to_summary, summary_columns, Columns, DocumentLink, DocumentSummary, LinkGraph, LinkedDocument},
+    },
+    AppState,
+};
+use libsql::{Connection, Value};
+use std::collections::{HashMap, HashSet};
+use tauri::State;
+
+const MAX_GRAPH_DEPTH: u32 = 3;
+const MAX_GRAPH_NODES: usize = 500;
+
+#[tauri::command]
+pub async fn link_documents(
+    source_id: String,
+    target_id: String,
+    relation: String,
+    state: State<'_, AppState>,
+) -> Result<DocumentLink, String> {
+    let relation = validate_relation(&relation)?;
+    if source_id == target_id {
+        return Err("A document cannot link to itself".into());
+    }
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    ensure_live(&conn, &source_id).await?;
+    ensure_live(&conn, &target_id).await?;
+
+    conn.execute(
+        "INSERT OR IGNORE INTO document_links (source_id, target_id, relation) VALUES (?1, ?2, ?3)",
+        libsql::params![source_id.clone(), target_id.clone(), relation.clone()],
+    ).await.map_err(|e| format!("Link failed: {e}"))?;
+
+    let mut rows = conn.query(
+        "SELECT source_id, target_id, relation, created_at FROM document_links
+         WHERE source_id = ?1 AND target_id = ?2 AND relation = ?3",
+        libsql::params![source_id, target_id, relation],
+    ).await.map_err(|e| e.to_string())?;
+    match rows.next().await.map_err(|e| e.to_string())? {
+        Some(row) => Ok(row_to_link(&row)),
+        None      => Err("Link not found".into()),
+    }
+}
+
+/// Remove a link. Without `relation`, every link from source to target goes.
+/// Returns the number removed.
+#[tauri::command]
+pub async fn unlink_documents(
+    source_id: String,
+    target_id: String,
+    relation: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<u64, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM document_links
+         WHERE source_id = ?1 AND target_id = ?2 AND (?3 IS NULL OR relation = ?3)",
+        libsql::params![source_id, target_id, relation],
+    ).await.map_err(|e| format!("Unlink failed: {e}"))
+}
+
+/// Documents `doc_id` links to.
+#[tauri::command]
+pub async fn get_links(
+    doc_id: String,
+    relation: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<LinkedDocument>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    linked(&conn, "target_id", "source_id", &doc_id, relation).await
+}
+
+/// Documents linking to `doc_id`.
+#[tauri::command]
+pub async fn get_backlinks(
+    doc_id: String,
+    relation: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<LinkedDocument>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    linked(&conn, "source_id", "target_id", &doc_id, relation).await
+}
+
+/// Everything within `depth` links of `doc_id` (either direction; default 1,
+/// at most 3), for the relationship graph. Deleted and trashed documents are
+/// left out.
+#[tauri::command]
+pub async fn get_link_graph(
+    doc_id: String,
+    depth: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<LinkGraph, String> {
+    let conn  = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let depth = depth.unwrap_or(1).clamp(1, MAX_GRAPH_DEPTH);
+
+    let mut seen: HashSet<String> = HashSet::from([doc_id.clone()]);
+    let mut edges: HashMap<(String, String, String), DocumentLink> = HashMap::new();
+    let mut frontier = vec![doc_id.clone()];
+
+    for _ in 0..depth {
+        let mut next = Vec::new();
+        for id in &frontier {
+            let mut rows = conn.query(
+                "SELECT l.source_id, l.target_id, l.relation, l.created_at
+                 FROM document_links l
+                 JOIN documents s ON s.id = l.source_id
+                 JOIN documents t ON t.id = l.target_id
+                 WHERE (l.source_id = ?1 OR l.target_id = ?1)
+                   AND s.status != 'deleted' AND s.trashed_at IS NULL
+                   AND t.status != 'deleted' AND t.trashed_at IS NULL",
+                libsql::params![id.as_str()],
+            ).await.map_err(|e| e.to_string())?;
+            while let Some(row) = rows.next().await.map_err(|e| e.to_string())? {
+                let link  = row_to_link(&row);
+                let other = if link.source_id == *id { &link.target_id } else { &link.source_id };
+                if seen.len() < MAX_GRAPH_NODES && seen.insert(other.clone()) {
+                    next.push(other.clone());
+                }
+                if seen.contains(&link.source_id) && seen.contains(&link.target_id) {
+                    let key = (link.source_id.clone(), link.target_id.clone(), link.relation.clone());
+                    edges.insert(key, link);
+                }
+            }
+        }
+        frontier = next;
+    }
+
+    let ids: Vec<String> = seen.into_iter().collect();
+    Ok(LinkGraph { nodes: summaries(&conn, &ids).await?, edges: edges.into_values().collect() })
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+/// Lowercase letters, digits, '-' and '_'; at most 64 characters.
+fn validate_relation(relation: &str) -> Result<String, String> {
+    let relation = relation.trim().to_lowercase();
+    let valid = !relation.is_empty()
+        && relation.len() <= 64
+        && relation.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(relation)
+    } else {
+        Err("Relation must be 1-64 characters of a-z, 0-9, '-' or '_'".into())
+    }
+}
+
+pub(crate) async fn ensure_live(conn: &Connection, id: &str) -> Result<(), String> {
+    let mut rows = conn.query(
+        "SELECT 1 FROM documents WHERE id = ?1 AND status != 'deleted' AND trashed_at IS NULL",
+        libsql::params![id],
+    ).await.map_err(|e| e.to_string())?;
+    match rows.next().await.map_err(|e| e.to_string())? {
+        Some(_) => Ok(()),
+        None    => Err(format!("Document {id} not found")),
+    }
+}
+
+/// Live documents at the `other` end of links whose `this` end is `doc_id`.
+async fn linked(
+    conn: &Connection,
+    other: &str,
+    this: &str,
+    doc_id: &str,
+    relation: Option<String>,
+) -> Result<Vec<LinkedDocument>, String> {
+    let mut rows = conn.query(
+        &format!(
+            "SELECT {}, l.relation AS link_relation, l.created_at AS linked_at
+             FROM document_links l JOIN documents d ON d.id = l.{other}
+             WHERE l.{this} = ?1 AND (?2 IS NULL OR l.relation = ?2)
+               AND d.status != 'deleted' AND d.trashed_at IS NULL
+             ORDER BY l.created_at DESC",
+            summary_columns("d."),
+        ),
+        libsql::params![doc_id, relation],
+    ).await.map_err(|e| e.to_string())?;
+
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|e| e.to_string())? {
+        let document = row_to_summary(&row).map_err(|e| e.to_string())?;
+        let c = Columns::new(&row);
+        out.push(LinkedDocument {
+            relation:  c.str("link_relation").unwrap_or_default(),
+            linked_at: c.str("linked_at").unwrap_or_default(),
+            document,
+        });
+    }
+    Ok(out)
+}
+
+async fn summaries(conn: &Connection, ids: &[String]) -> Result<Vec<DocumentSummary>, String> {
+    let mut out = Vec::new();
+    for id in ids {
+        let mut rows = conn.query(
+            &format!("SELECT {} FROM documents WHERE id = ?1", summary_columns("")),
+            libsql::params![id.as_str()],
+        ).await.map_err(|e| e.to_string())?;
+        if let Some(row) = rows.next().await.map_err(|e| e.to_string())? {
+            out.push(row_to_summary(&row).map_err(|e| e.to_string())?);
+        }
+    }
+    Ok(out)
+}
+
+fn row_to_link(row: &libsql::Row) -> DocumentLink {
+    let s = |i| match row.get_value(i).ok() { Some(Value::Text(s)) => s, _ => String::new() };
+    DocumentLink { source_id: s(0), target_id: s(1), relation: s(2), created_at: s(3) }
+}
@@ -0,0 +1,33 @@
+// src-tauri/src/commands/maintenance.rs
+use crate::{db::{self, models::MaintenanceSettings, settings}, AppState};
+use tauri::{AppHandle, State};
+
+#[tauri::command]
+pub async fn get_maintenance_settings(state: State<'_, AppState>) -> Result<MaintenanceSettings, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let current = settings::get(&conn, settings::MAINTENANCE_SETTINGS).await.map_err(|e| e.to_string())?;
+    Ok(current.unwrap_or_default())
+}
+
+#[tauri::command]
+pub async fn update_maintenance_settings(
+    settings: MaintenanceSettings,
+    state: State<'_, AppState>,
+) -> Result<MaintenanceSettings, String> {
+    if settings.idle_minutes == 0 {
+        return Err("idle_minutes must be at least 1".into());
+    }
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    settings::set(&conn, settings::MAINTENANCE_SETTINGS, &settings).await.map_err(|e| e.to_string())?;
+    Ok(settings)
+}
+
+/// Run every maintenance task now, regardless of idle state. Returns a job id;
+/// the job result is the per-task report.
+#[tauri::command]
+pub async fn run_maintenance(app: AppHandle, state: State<'_, AppState>) -> Result<String, String> {
+    let job_app = app.clone();
+    Ok(state.jobs.spawn(&app, "maintenance", move |_job| async move {
+        crate::maintenance::run(&job_app, None).await
+    }))
+}
@@ -0,0 +1,31 @@
+// src-tauri/src/commands/metadata_index.rs
+use crate::{
+    db::{self, metadata_index},
+    AppState,
+};
+use tauri::State;
+
+/// Metadata keys that query_documents filters through an index.
+#[tauri::command]
+pub async fn get_indexed_metadata_keys(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    metadata_index::keys(&conn).await.map_err(|e| e.to_string())
+}
+
+/// Replace the indexed metadata keys (e.g. ["project", "client"]). Newly
+/// added keys are indexed for existing documents before this returns.
+#[tauri::command]
+pub async fn set_indexed_metadata_keys(
+    keys: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, String> {
+    let mut keys: Vec<String> = keys.iter().map(|k| k.trim().to_string()).collect();
+    keys.sort();
+    keys.dedup();
+
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let tx = conn.transaction().await.map_err(|e| e.to_string())?;
+    metadata_index::set_keys(&tx, &keys).await.map_err(|e| e.to_string())?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    metadata_index::keys(&conn).await.map_err(|e| e.to_string())
+}
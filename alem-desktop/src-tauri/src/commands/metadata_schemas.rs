@@ -0,0 +1,59 @@
+// src-tauri/src/commands/metadata_schemas.rs
+use crate::{
+    db::{self, identity, metadata_schemas, models::MetadataSchema},
+    AppState,
+};
+use serde_json::{json, Value};
+use tauri::State;
+
+/// The active tenant's metadata schemas.
+#[tauri::command]
+pub async fn get_metadata_schemas(state: State<'_, AppState>) -> Result<Vec<MetadataSchema>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let (_, tenant_id) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+    metadata_schemas::list(&conn, &tenant_id).await.map_err(|e| e.to_string())
+}
+
+/// Define the metadata schema for `content_type` ("application/pdf",
+/// "image/*" or "*") in the active tenant. `required` lists fields every
+/// document of that type must have, added to `schema` if both are given.
+/// Existing documents are not re-checked; create_document and
+/// update_document are.
+#[tauri::command]
+pub async fn set_metadata_schema(
+    content_type: String,
+    schema: Option<Value>,
+    required: Option<Vec<String>>,
+    state: State<'_, AppState>,
+) -> Result<Vec<MetadataSchema>, String> {
+    let mut schema = schema.unwrap_or_else(|| json!({ "type": "object" }));
+    if let Some(fields) = required {
+        let obj = schema.as_object_mut().ok_or("Schema must be a JSON object")?;
+        let list = obj.entry("required").or_insert_with(|| json!([]));
+        let list = list.as_array_mut().ok_or("\"required\" must be an array")?;
+        for field in fields.iter().map(|f| f.trim()).filter(|f| !f.is_empty()) {
+            if !list.iter().any(|r| r == field) {
+                list.push(json!(field));
+            }
+        }
+    }
+
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let (_, tenant_id) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+    metadata_schemas::set(&conn, &tenant_id, content_type.trim(), &schema).await.map_err(|e| e.to_string())?;
+    metadata_schemas::list(&conn, &tenant_id).await.map_err(|e| e.to_string())
+}
+
+/// Remove the schema for exactly `content_type`; wider ones still apply.
+#[tauri::command]
+pub async fn delete_metadata_schema(
+    content_type: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<MetadataSchema>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let (_, tenant_id) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+    if !metadata_schemas::delete(&conn, &tenant_id, &content_type).await.map_err(|e| e.to_string())? {
+        return Err(format!("No metadata schema for {content_type}"));
+    }
+    metadata_schemas::list(&conn, &tenant_id).await.map_err(|e| e.to_string())
+}
@@ -0,0 +1,67 @@
+// src-tauri/src/commands/mirrors.rs
+// Folder mirrors: a collection kept in step with a folder on disk, both ways
+// (see mirror.rs).
+use crate::{
+    db::{self, identity, mirrors, models::FolderMirror},
+    mirror::{self, MirrorPass},
+    AppState,
+};
+use std::path::Path;
+use tauri::{AppHandle, Manager, State};
+
+/// Mirror `collection_id` to the folder at `path` (created if missing) and
+/// run the first pass. Called again for a mirrored collection, it changes
+/// `propagate_deletes` or moves the mirror to another folder.
+#[tauri::command]
+pub async fn mirror_collection(
+    collection_id: String,
+    path: String,
+    propagate_deletes: Option<bool>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<FolderMirror, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    super::collections::ensure_exists(&conn, &collection_id).await?;
+
+    let folder = Path::new(&path);
+    if !folder.is_absolute() {
+        return Err("Mirror folder must be an absolute path".into());
+    }
+    let data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    if folder.starts_with(&data_dir) || data_dir.starts_with(folder) {
+        return Err("Mirror folder can't overlap the app's data directory".into());
+    }
+    if mirrors::get_by_path(&conn, &path).await.map_err(|e| e.to_string())?
+        .is_some_and(|m| m.collection_id != collection_id)
+    {
+        return Err("Another collection is already mirrored to this folder".into());
+    }
+
+    mirrors::set(&conn, &collection_id, &path, propagate_deletes.unwrap_or(false))
+        .await.map_err(|e| e.to_string())?;
+    // A failed first pass is reported in last_error, like any other
+    let _ = mirror::sync(&app, &collection_id).await;
+    mirrors::get(&conn, &collection_id).await.map_err(|e| e.to_string())?
+        .ok_or_else(|| "Mirror was removed".to_string())
+}
+
+/// Stop mirroring `collection_id`. The folder and its files stay as they
+/// are. Returns false when it wasn't mirrored.
+#[tauri::command]
+pub async fn unmirror_collection(collection_id: String, state: State<'_, AppState>) -> Result<bool, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    mirrors::remove(&conn, &collection_id).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_folder_mirrors(state: State<'_, AppState>) -> Result<Vec<FolderMirror>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let (_, tenant_id) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+    mirrors::list(&conn, &tenant_id).await.map_err(|e| e.to_string())
+}
+
+/// Run a pass now rather than waiting for the next one.
+#[tauri::command]
+pub async fn sync_folder_mirror(collection_id: String, app: AppHandle) -> Result<MirrorPass, String> {
+    mirror::sync(&app, &collection_id).await.map_err(|e| format!("{e:#}"))
+}
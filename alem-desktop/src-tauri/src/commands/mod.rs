@@ -1,5 +1,31 @@
+pub mod accounts;
+pub mod activity;
 pub mod auth;
+pub mod backup;
+pub mod collections;
+pub mod contacts;
+pub mod diagnostics;
 pub mod did;
 pub mod documents;
+pub mod encryption;
+pub mod export;
 pub mod files;
-pub mod sync;
\ No newline at end of file
+pub mod import;
+pub mod jobs;
+pub mod links;
+pub mod maintenance;
+pub mod mirrors;
+pub mod private_notes;
+pub mod profile;
+pub mod saved_searches;
+pub mod sharing;
+pub mod sync;
+pub mod tags;
+pub mod tenants;
+pub mod metadata_schemas;
+pub mod annotations;
+pub mod metadata_index;
+pub mod watches;
+pub mod webdav;
+pub mod scan;
+pub mod ocr;
\ No newline at end of file
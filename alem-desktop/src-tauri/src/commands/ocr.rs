@@ -0,0 +1,55 @@
+// src-tauri/src/commands/ocr.rs
+// OCR settings and queue (see ocr.rs).
+use crate::{
+    db::{self, models::{OcrSettings, OcrStatus}, settings},
+    ocr, AppState,
+};
+use tauri::State;
+
+#[tauri::command]
+pub async fn get_ocr_settings(state: State<'_, AppState>) -> Result<OcrSettings, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let current = settings::get(&conn, settings::OCR_SETTINGS).await.map_err(|e| e.to_string())?;
+    Ok(current.unwrap_or_default())
+}
+
+/// Applies to documents imported from now on; use queue_ocr for the rest.
+#[tauri::command]
+pub async fn update_ocr_settings(
+    settings: OcrSettings,
+    state: State<'_, AppState>,
+) -> Result<OcrSettings, String> {
+    if settings.tesseract_path.trim().is_empty() || settings.pdftoppm_path.trim().is_empty() {
+        return Err("tesseract_path and pdftoppm_path must not be empty".into());
+    }
+    if settings.languages.trim().is_empty() {
+        return Err("At least one OCR language is required".into());
+    }
+    if !(72..=1200).contains(&settings.dpi) {
+        return Err("dpi must be between 72 and 1200".into());
+    }
+    if settings.max_pages == 0 {
+        return Err("max_pages must be greater than 0".into());
+    }
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    settings::set(&conn, settings::OCR_SETTINGS, &settings).await.map_err(|e| e.to_string())?;
+    Ok(settings)
+}
+
+/// Queue documents for OCR, again if they were done before. Without `ids`,
+/// every image and text-less PDF not queued yet. Returns how many were queued.
+#[tauri::command]
+pub async fn queue_ocr(ids: Option<Vec<String>>, state: State<'_, AppState>) -> Result<u64, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    match ids {
+        Some(ids) => ocr::enqueue(&conn, &ids).await,
+        None      => ocr::enqueue_all(&conn).await,
+    }.map_err(|e| e.to_string())
+}
+
+/// OCR status of a document; None if it was never queued.
+#[tauri::command]
+pub async fn get_ocr_status(id: String, state: State<'_, AppState>) -> Result<Option<OcrStatus>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    ocr::status(&conn, &id).await.map_err(|e| e.to_string())
+}
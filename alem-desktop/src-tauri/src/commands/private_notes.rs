@@ -0,0 +1,46 @@
+// src-tauri/src/commands/private_notes.rs
+use crate::{
+    crypto::sealed::LocalKey,
+    db::{self, models::NotesTarget, private_notes},
+    AppState,
+};
+use tauri::State;
+
+const MAX_NOTES_BYTES: usize = 64 * 1024;
+
+/// Private notes on a document (by id) or contact (by DID). Only this
+/// device can read them.
+#[tauri::command]
+pub async fn get_private_notes(
+    target: NotesTarget,
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<Option<String>, String> {
+    let key  = local_key(&state).await?;
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    private_notes::get(&conn, &key, target, &id).await.map_err(|e| e.to_string())
+}
+
+/// Replace the private notes on a document or contact; None clears them.
+/// They are never synced or exported.
+#[tauri::command]
+pub async fn set_private_notes(
+    target: NotesTarget,
+    id: String,
+    notes: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    if notes.as_ref().is_some_and(|n| n.len() > MAX_NOTES_BYTES) {
+        return Err(format!("Private notes are limited to {} KiB", MAX_NOTES_BYTES / 1024));
+    }
+    let key  = local_key(&state).await?;
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    private_notes::set(&conn, &key, target, &id, notes.as_deref()).await.map_err(|e| e.to_string())
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+async fn local_key(state: &State<'_, AppState>) -> Result<LocalKey, String> {
+    let did = super::did::local_did(state).await?;
+    LocalKey::for_did(&did, private_notes::KEY_PURPOSE).map_err(|e| e.to_string())
+}
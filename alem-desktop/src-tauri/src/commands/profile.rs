@@ -0,0 +1,67 @@
+// src-tauri/src/commands/profile.rs
+use crate::{
+    crypto::keys,
+    db::{
+        self,
+        identity,
+        models::{Profile, ProfileInput},
+        ops, profiles,
+    },
+    storage::cas,
+    AppState,
+};
+use tauri::State;
+
+const MAX_DISPLAY_NAME: usize = 100;
+
+/// Re-issue the local DID's profile with `input` and queue it for sync so
+/// collaborators pick it up.
+#[tauri::command]
+pub async fn update_profile(input: ProfileInput, state: State<'_, AppState>) -> Result<Profile, String> {
+    let display_name = input.display_name.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+    if display_name.as_ref().is_some_and(|n| n.chars().count() > MAX_DISPLAY_NAME) {
+        return Err(format!("Display name is limited to {MAX_DISPLAY_NAME} characters"));
+    }
+    if input.avatar_hash.as_deref().is_some_and(|h| !cas::is_sha256_hex(h)) {
+        return Err("avatar_hash must be a sha256 hex digest".into());
+    }
+    let contact = input.contact.unwrap_or_else(|| serde_json::json!({}));
+    if !contact.is_object() {
+        return Err("contact must be a JSON object".into());
+    }
+
+    let did  = super::did::local_did(&state).await?;
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let (key, secret) = keys::signing_key(&conn, &did).await
+        .map_err(|e| format!("Profiles can only be signed by a DID generated on this device: {e}"))?;
+
+    let mut profile = Profile {
+        did,
+        display_name,
+        avatar_hash: input.avatar_hash,
+        contact,
+        issued_at:   chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        signature:   String::new(),
+    };
+    profiles::sign(&mut profile, &key.id, &secret).map_err(|e| e.to_string())?;
+
+    let (user_id, _) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+    let tx = conn.transaction().await.map_err(|e| e.to_string())?;
+    profiles::store(&tx, &profile).await.map_err(|e| e.to_string())?;
+    ops::enqueue(&tx, &user_id, "sync_profile", serde_json::json!({ "did": profile.did }))
+        .await.map_err(|e| format!("Queue op failed: {e}"))?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(profile)
+}
+
+/// Stored profile for `did` (the local DID if omitted). Only profiles whose
+/// signature verified are ever stored.
+#[tauri::command]
+pub async fn get_profile(did: Option<String>, state: State<'_, AppState>) -> Result<Option<Profile>, String> {
+    let did = match did {
+        Some(did) => did,
+        None      => super::did::local_did(&state).await?,
+    };
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    profiles::get(&conn, &did).await.map_err(|e| e.to_string())
+}
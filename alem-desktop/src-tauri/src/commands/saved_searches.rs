@@ -0,0 +1,134 @@
+// src-tauri/src/commands/saved_searches.rs
+// Saved searches ("smart collections"): a named search text + DocumentFilter,
+// stored locally and evaluated fresh every time through the same keyset
+// pagination as query_documents.
+use crate::{
+    commands::documents::filtered_page,
+    db::{self, identity, models::{DocumentPage, SavedSearch}, query::DocumentFilter},
+    AppState,
+};
+use libsql::{Connection, Value};
+use tauri::State;
+use uuid::Uuid;
+
+#[tauri::command]
+pub async fn list_saved_searches(state: State<'_, AppState>) -> Result<Vec<SavedSearch>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let mut rows = conn.query(
+        "SELECT id, name, query, filter, created_at, updated_at
+         FROM saved_searches ORDER BY name COLLATE NOCASE",
+        (),
+    ).await.map_err(|e| e.to_string())?;
+
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|e| e.to_string())? {
+        out.push(row_to_saved_search(&row));
+    }
+    Ok(out)
+}
+
+#[tauri::command]
+pub async fn save_search(
+    name: String,
+    query: Option<String>,
+    filter: Option<DocumentFilter>,
+    state: State<'_, AppState>,
+) -> Result<SavedSearch, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let name = validate_name(&name)?;
+    let (user_id, _) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+
+    let id     = Uuid::new_v4().to_string();
+    let filter = serde_json::to_string(&filter.unwrap_or_default()).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO saved_searches (id, user_id, name, query, filter) VALUES (?1,?2,?3,?4,?5)",
+        libsql::params![id.clone(), user_id, name, normalize_query(query), filter],
+    ).await.map_err(|e| format!("Insert failed: {e}"))?;
+
+    get_saved_search(&conn, &id).await
+}
+
+/// Replace the fields that are passed; omitted ones keep their value.
+#[tauri::command]
+pub async fn update_saved_search(
+    id: String,
+    name: Option<String>,
+    query: Option<String>,
+    filter: Option<DocumentFilter>,
+    state: State<'_, AppState>,
+) -> Result<SavedSearch, String> {
+    let conn    = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let current = get_saved_search(&conn, &id).await?;
+
+    let name   = match name { Some(n) => validate_name(&n)?, None => current.name };
+    let query  = if query.is_some() { normalize_query(query) } else { current.query };
+    let filter = serde_json::to_string(&filter.unwrap_or(current.filter)).map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "UPDATE saved_searches SET name = ?1, query = ?2, filter = ?3, updated_at = datetime('now')
+         WHERE id = ?4",
+        libsql::params![name, query, filter, id.clone()],
+    ).await.map_err(|e| format!("Update failed: {e}"))?;
+
+    get_saved_search(&conn, &id).await
+}
+
+#[tauri::command]
+pub async fn delete_saved_search(id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM saved_searches WHERE id = ?1", libsql::params![id])
+        .await.map_err(|e| format!("Delete failed: {e}"))?;
+    Ok(())
+}
+
+/// Evaluate a saved search. Paginates like get_documents_page.
+#[tauri::command]
+pub async fn run_saved_search(
+    id: String,
+    limit: Option<i64>,
+    cursor: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<DocumentPage, String> {
+    let conn  = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let saved = get_saved_search(&conn, &id).await?;
+    filtered_page(&conn, &saved.filter, saved.query.as_deref(), limit, cursor).await
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+fn validate_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Saved search name cannot be empty".into());
+    }
+    Ok(name.to_string())
+}
+
+/// Blank search text means "filter only".
+fn normalize_query(query: Option<String>) -> Option<String> {
+    query.map(|q| q.trim().to_string()).filter(|q| !q.is_empty())
+}
+
+async fn get_saved_search(conn: &Connection, id: &str) -> Result<SavedSearch, String> {
+    let mut rows = conn.query(
+        "SELECT id, name, query, filter, created_at, updated_at FROM saved_searches WHERE id = ?1",
+        libsql::params![id],
+    ).await.map_err(|e| e.to_string())?;
+
+    match rows.next().await.map_err(|e| e.to_string())? {
+        Some(row) => Ok(row_to_saved_search(&row)),
+        None      => Err(format!("Saved search {id} not found")),
+    }
+}
+
+fn row_to_saved_search(row: &libsql::Row) -> SavedSearch {
+    let s = |i| match row.get_value(i).ok() { Some(Value::Text(s)) => Some(s), _ => None };
+    SavedSearch {
+        id:         s(0).unwrap_or_default(),
+        name:       s(1).unwrap_or_default(),
+        query:      s(2),
+        filter:     s(3).and_then(|f| serde_json::from_str(&f).ok()).unwrap_or_default(),
+        created_at: s(4).unwrap_or_default(),
+        updated_at: s(5).unwrap_or_default(),
+    }
+}
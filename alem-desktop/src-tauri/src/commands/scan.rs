@@ -0,0 +1,61 @@
+// src-tauri/src/commands/scan.rs
+// Content scanner settings (see scan/mod.rs).
+use crate::{
+    db::{self, models::{ScanSettings, ScannerConfig}, settings},
+    AppState,
+};
+use tauri::State;
+
+#[tauri::command]
+pub async fn get_scan_settings(state: State<'_, AppState>) -> Result<ScanSettings, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let current = settings::get(&conn, settings::SCAN_SETTINGS).await.map_err(|e| e.to_string())?;
+    Ok(current.unwrap_or_default())
+}
+
+/// Replace the scanner list. Applies to documents imported from now on.
+#[tauri::command]
+pub async fn update_scan_settings(
+    settings: ScanSettings,
+    state: State<'_, AppState>,
+) -> Result<ScanSettings, String> {
+    for scanner in &settings.scanners {
+        match scanner {
+            ScannerConfig::Pii { tag, .. } => {
+                if tag.as_deref().is_some_and(|t| t.trim().is_empty()) {
+                    return Err("PII tag must not be empty".into());
+                }
+            }
+            ScannerConfig::Command { name, program, timeout_secs, .. } => {
+                if name.trim().is_empty() || program.trim().is_empty() {
+                    return Err("Command scanners need a name and a program".into());
+                }
+                check_timeout(name, *timeout_secs)?;
+            }
+            ScannerConfig::Icap { name, url, timeout_secs, .. } => {
+                if name.trim().is_empty() {
+                    return Err("ICAP scanners need a name".into());
+                }
+                if !url.starts_with("icap://") || url.len() <= "icap://".len() {
+                    return Err(format!("{name}: ICAP url must look like icap://host[:port]/service"));
+                }
+                check_timeout(name, *timeout_secs)?;
+            }
+        }
+    }
+    if settings.max_file_bytes == 0 {
+        return Err("max_file_bytes must be greater than 0".into());
+    }
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    settings::set(&conn, settings::SCAN_SETTINGS, &settings).await.map_err(|e| e.to_string())?;
+    Ok(settings)
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+fn check_timeout(name: &str, secs: u64) -> Result<(), String> {
+    if !(1..=600).contains(&secs) {
+        return Err(format!("{name}: timeout_secs must be between 1 and 600"));
+    }
+    Ok(())
+}
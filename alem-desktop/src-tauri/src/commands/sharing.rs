@@ -0,0 +1,107 @@
+// src-tauri/src/commands/sharing.rs
+// Sharing documents beyond the library: share links and shares with other
+// DIDs (see sharing.rs), and posts to the fediverse through the account's
+// Pleroma account (see activitypub.rs).
+use crate::{
+    activitypub,
+    db::{
+        self, did_shares, fediverse,
+        models::{
+            AclRole, DocumentShare, FediversePost, PostVisibility, ShareLink, SharePermission,
+            SharedDocument, SharedDocumentContent,
+        },
+        shares,
+    },
+    sharing,
+    AppState,
+};
+use tauri::{AppHandle, State};
+
+/// A link to `doc_id` granting `permissions`, expiring `expiry` seconds from
+/// now (never, when omitted). The server's capability URL when it can be
+/// asked, a link signed with the local DID's key otherwise.
+#[tauri::command]
+pub async fn create_share_link(
+    doc_id: String,
+    expiry: Option<i64>,
+    permissions: Vec<SharePermission>,
+    app: AppHandle,
+) -> Result<ShareLink, String> {
+    sharing::create_link(&app, &doc_id, expiry, &permissions)
+        .await.map_err(|e| format!("{e:#}"))
+}
+
+/// Share links of `doc_id`, or of every document, newest first. Revoked
+/// and expired ones included.
+#[tauri::command]
+pub async fn list_share_links(doc_id: Option<String>, state: State<'_, AppState>) -> Result<Vec<ShareLink>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    shares::list(&conn, doc_id.as_deref()).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn revoke_share_link(id: String, state: State<'_, AppState>) -> Result<ShareLink, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    sharing::revoke_link(&conn, &id).await.map_err(|e| e.to_string())
+}
+
+/// Share `doc_id` with `did` as viewer or editor. Queued: it reaches the
+/// server on the next sync.
+#[tauri::command]
+pub async fn share_document_with(
+    did: String,
+    doc_id: String,
+    permission: AclRole,
+    state: State<'_, AppState>,
+) -> Result<DocumentShare, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    sharing::share_with(&conn, &did, &doc_id, permission).await.map_err(|e| format!("{e:#}"))
+}
+
+/// DIDs `doc_id` is shared with, newest first. Revoked shares included.
+#[tauri::command]
+pub async fn list_document_shares(doc_id: String, state: State<'_, AppState>) -> Result<Vec<DocumentShare>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    did_shares::list(&conn, &doc_id).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn unshare_document(id: String, state: State<'_, AppState>) -> Result<DocumentShare, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    sharing::unshare(&conn, &id).await.map_err(|e| e.to_string())
+}
+
+/// Documents other DIDs have shared with the active account, newest first.
+#[tauri::command]
+pub async fn list_shared_with_me(state: State<'_, AppState>) -> Result<Vec<SharedDocument>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    sharing::shared_with_me(&conn).await.map_err(|e| e.to_string())
+}
+
+/// The text and a short-lived download URL of a document shared with the
+/// active account. Needs the server.
+#[tauri::command]
+pub async fn open_shared_document(id: String, app: AppHandle) -> Result<SharedDocumentContent, String> {
+    sharing::open_shared(&app, &id).await.map_err(|e| format!("{e:#}"))
+}
+
+/// Post `doc_id` to the linked Pleroma account, the local DID named as its
+/// author. `link` (e.g. a share link) goes into the post when given. Needs
+/// the server: fails offline rather than queueing.
+#[tauri::command]
+pub async fn share_to_fediverse(
+    doc_id: String,
+    visibility: PostVisibility,
+    link: Option<String>,
+    app: AppHandle,
+) -> Result<FediversePost, String> {
+    activitypub::publish(&app, &doc_id, visibility, link.as_deref())
+        .await.map_err(|e| format!("{e:#}"))
+}
+
+/// Where `doc_id` has been posted, newest first.
+#[tauri::command]
+pub async fn list_fediverse_posts(doc_id: String, state: State<'_, AppState>) -> Result<Vec<FediversePost>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    fediverse::list(&conn, &doc_id).await.map_err(|e| e.to_string())
+}
@@ -1,14 +1,16 @@
 // src-tauri/src/commands/sync.rs
-use crate::{db::models::SyncStatus, AppState};
+use crate::{db::{self, conflicts, identity, models::{ApiRequest, ConflictStrategy, NetworkSettings, OfflineOperation, SyncConflict, SyncFilter, SyncFilterState, SyncSettings, SyncStatus}, ops, settings, tags, tenants, timing}, sync::metrics::Direction, AppState};
 use tauri::{AppHandle, State};
 
 #[tauri::command]
 pub async fn get_sync_status(state: State<'_, AppState>) -> Result<SyncStatus, String> {
-    let conn = state.db.connect().map_err(|e| e.to_string())?;
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let (_, tenant_id) = identity::current(&conn).await.map_err(|e| e.to_string())?;
 
     // pending count
     let mut rows = conn.query(
-        "SELECT COUNT(*) FROM offline_operations WHERE status='pending'", ()
+        "SELECT COUNT(*) FROM offline_operations WHERE tenant_id = ?1 AND status='pending'",
+        libsql::params![tenant_id.as_str()],
     ).await.map_err(|e| e.to_string())?;
     let pending: i64 = if let Some(row) = rows.next().await.map_err(|e| e.to_string())? {
         match row.get_value(0).ok() { Some(libsql::Value::Integer(n)) => n, _ => 0 }
@@ -16,7 +18,8 @@ pub async fn get_sync_status(state: State<'_, AppState>) -> Result<SyncStatus, S
 
     // failed count
     let mut rows = conn.query(
-        "SELECT COUNT(*) FROM offline_operations WHERE status='failed'", ()
+        "SELECT COUNT(*) FROM offline_operations WHERE tenant_id = ?1 AND status='failed'",
+        libsql::params![tenant_id.as_str()],
     ).await.map_err(|e| e.to_string())?;
     let failed: i64 = if let Some(row) = rows.next().await.map_err(|e| e.to_string())? {
         match row.get_value(0).ok() { Some(libsql::Value::Integer(n)) => n, _ => 0 }
@@ -24,18 +27,50 @@ pub async fn get_sync_status(state: State<'_, AppState>) -> Result<SyncStatus, S
 
     // last_sync_at
     let mut rows = conn.query(
-        "SELECT last_sync_at FROM local_identity WHERE id='singleton'", ()
+        "SELECT last_sync_at FROM tenants WHERE id = ?1",
+        libsql::params![tenant_id.as_str()],
     ).await.map_err(|e| e.to_string())?;
     let last_sync: Option<String> = if let Some(row) = rows.next().await.map_err(|e| e.to_string())? {
         match row.get_value(0).ok() { Some(libsql::Value::Text(s)) => Some(s), _ => None }
     } else { None };
 
+    // bytes still to upload (queued upload ops) and to download (metadata-only docs)
+    let mut rows = timing::query(&conn,
+        "SELECT
+             (SELECT COALESCE(SUM(d.file_size), 0)
+              FROM offline_operations o
+              JOIN documents d ON d.id = json_extract(o.payload, '$.doc_id')
+              WHERE o.tenant_id = ?1 AND o.status = 'pending' AND o.op_type = 'upload_document'),
+             (SELECT COALESCE(SUM(file_size), 0)
+              FROM documents WHERE needs_download = 1 AND status != 'deleted' AND tenant_id = ?1)",
+        libsql::params![tenant_id.as_str()],
+    ).await.map_err(|e| e.to_string())?;
+    let (up_bytes, down_bytes): (i64, i64) = if let Some(row) = rows.next().await.map_err(|e| e.to_string())? {
+        let n = |i| match row.get_value(i).ok() { Some(libsql::Value::Integer(n)) => n, _ => 0 };
+        (n(0), n(1))
+    } else { (0, 0) };
+
+    let conflict_count = conflicts::count(&conn, &tenant_id).await.map_err(|e| e.to_string())?;
+
+    let metrics = &state.transfer_metrics;
+    let eta = match (metrics.eta(Direction::Upload, up_bytes), metrics.eta(Direction::Download, down_bytes)) {
+        (Some(u), Some(d)) => Some(u + d),
+        _ => None,
+    };
+
     Ok(SyncStatus {
         is_syncing: false,
         last_sync_at: last_sync,
         pending_count: pending,
         failed_count: failed,
-        connection_online: true,
+        connection_online: state.connectivity.is_online(),
+        connection_state: state.connectivity.state(),
+        upload_bytes_per_sec: metrics.throughput(Direction::Upload),
+        download_bytes_per_sec: metrics.throughput(Direction::Download),
+        bytes_pending_upload: up_bytes,
+        bytes_pending_download: down_bytes,
+        eta_seconds: eta,
+        conflict_count,
     })
 }
 
@@ -49,12 +84,19 @@ pub async fn trigger_sync(app: AppHandle) -> Result<String, String> {
     Ok("Sync triggered".to_string())
 }
 
+/// Queued and failed ops, newest first. `include_payload` adds each op's
+/// payload and user_id for support inspection.
 #[tauri::command]
-pub async fn get_pending_operations(state: State<'_, AppState>) -> Result<serde_json::Value, String> {
-    let conn = state.db.connect().map_err(|e| e.to_string())?;
+pub async fn get_pending_operations(
+    include_payload: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<serde_json::Value, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
     let mut rows = conn.query(
-        "SELECT id, op_type, status, retry_count, error_msg, created_at
-         FROM offline_operations WHERE status IN ('pending','failed')
+        "SELECT id, op_type, status, retry_count, error_msg, created_at, payload, user_id
+         FROM offline_operations
+         WHERE tenant_id = (SELECT tenant_id FROM local_identity WHERE id = 'singleton')
+           AND status IN ('pending','failed')
          ORDER BY created_at DESC LIMIT 50",
         (),
     ).await.map_err(|e| e.to_string())?;
@@ -64,26 +106,84 @@ pub async fn get_pending_operations(state: State<'_, AppState>) -> Result<serde_
         use libsql::Value;
         let s = |i| match row.get_value(i).ok() { Some(Value::Text(s)) => s, _ => String::new() };
         let n = |i| match row.get_value(i).ok() { Some(Value::Integer(n)) => n, _ => 0 };
-        ops.push(serde_json::json!({
+        let mut op = serde_json::json!({
             "id":          s(0),
             "op_type":     s(1),
             "status":      s(2),
             "retry_count": n(3),
             "error_msg":   match row.get_value(4).ok() { Some(Value::Text(s)) => serde_json::Value::String(s), _ => serde_json::Value::Null },
             "created_at":  s(5),
-        }));
+        });
+        if include_payload.unwrap_or(false) {
+            op["payload"] = serde_json::from_str(&s(6)).unwrap_or(serde_json::Value::Null);
+            op["user_id"] = s(7).into();
+        }
+        ops.push(op);
     }
 
     let count = ops.len();
     Ok(serde_json::json!({ "operations": ops, "count": count }))
 }
 
+/// One queued op with its full payload, whatever its status.
+#[tauri::command]
+pub async fn get_operation(id: String, state: State<'_, AppState>) -> Result<OfflineOperation, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    ops::get(&conn, &id).await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Operation {id} not found"))
+}
+
+/// Queue a call to the server that can wait until it is reachable, such as a
+/// profile change made offline. It goes out with the next sync (at once when
+/// online, or on reconnect), after requests queued earlier for the same path,
+/// and is replayed until it succeeds; `on_conflict` decides what a 409 or 412
+/// does. Returns the queued op, whose payload has the idempotency key
+/// "api-request-conflict" events carry.
+#[tauri::command]
+pub async fn queue_api_request(
+    request: ApiRequest,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<OfflineOperation, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let (user_id, _) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+    let id = ops::enqueue_api_request(&conn, &user_id, request).await.map_err(|e| e.to_string())?;
+
+    tauri::async_runtime::spawn(async move {
+        let _ = crate::sync::engine::run_once(&app).await;
+    });
+
+    ops::get(&conn, &id).await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Operation {id} not found"))
+}
+
+/// Recovery: replace a malformed op's payload (e.g. a wrong doc_id) and
+/// retry it, without clearing the rest of the queue.
+#[tauri::command]
+pub async fn requeue_operation_with_payload(
+    id: String,
+    new_payload: serde_json::Value,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<OfflineOperation, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let op   = ops::requeue_with_payload(&conn, &id, new_payload).await.map_err(|e| e.to_string())?;
+
+    tauri::async_runtime::spawn(async move {
+        let _ = crate::sync::engine::run_once(&app).await;
+    });
+
+    Ok(op)
+}
+
 #[tauri::command]
 pub async fn retry_failed_operations(
     state: State<'_, AppState>,
     app: AppHandle,
 ) -> Result<u64, String> {
-    let conn = state.db.connect().map_err(|e| e.to_string())?;
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
     let affected = conn.execute(
         "UPDATE offline_operations
          SET status='pending', retry_count=0, error_msg=NULL, updated_at=datetime('now')
@@ -96,4 +196,165 @@ pub async fn retry_failed_operations(
     });
 
     Ok(affected)
-}
\ No newline at end of file
+}
+
+/// Open conflicts of the active tenant, oldest first. New ones also arrive
+/// as "sync-conflict" events while syncing.
+#[tauri::command]
+pub async fn list_sync_conflicts(state: State<'_, AppState>) -> Result<Vec<SyncConflict>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let (_, tenant_id) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+    conflicts::list(&conn, &tenant_id).await.map_err(|e| e.to_string())
+}
+
+/// Resolve one conflict. Returns the id of the copy made by keep_both.
+#[tauri::command]
+pub async fn resolve_conflict(
+    id: String,
+    strategy: ConflictStrategy,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<Option<String>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let (user_id, _) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+    let tx = conn.transaction().await.map_err(|e| e.to_string())?;
+    let copy_id = conflicts::resolve(&tx, &user_id, &id, strategy).await.map_err(|e| e.to_string())?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    tauri::async_runtime::spawn(async move {
+        let _ = crate::sync::engine::run_once(&app).await;
+    });
+
+    Ok(copy_id)
+}
+
+/// Resolve every open conflict of the active tenant with `strategy`, in one
+/// transaction. keep_both falls back to keep_remote for documents with no
+/// local content to copy. Returns how many were resolved.
+#[tauri::command]
+pub async fn resolve_all_conflicts(
+    strategy: ConflictStrategy,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<usize, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let (user_id, tenant_id) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+    let tx = conn.transaction().await.map_err(|e| e.to_string())?;
+    let open = conflicts::list(&tx, &tenant_id).await.map_err(|e| e.to_string())?;
+    for conflict in &open {
+        let strategy = if conflict.strategies.contains(&strategy) { strategy } else { ConflictStrategy::KeepRemote };
+        conflicts::resolve(&tx, &user_id, &conflict.id, strategy).await.map_err(|e| e.to_string())?;
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    tauri::async_runtime::spawn(async move {
+        let _ = crate::sync::engine::run_once(&app).await;
+    });
+
+    Ok(open.len())
+}
+
+#[tauri::command]
+pub async fn get_sync_settings(state: State<'_, AppState>) -> Result<SyncSettings, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let current = settings::get(&conn, settings::SYNC_SETTINGS).await.map_err(|e| e.to_string())?;
+    Ok(current.unwrap_or_default())
+}
+
+#[tauri::command]
+pub async fn update_sync_settings(
+    settings: SyncSettings,
+    state: State<'_, AppState>,
+) -> Result<SyncSettings, String> {
+    if !(1..=16).contains(&settings.upload_concurrency) {
+        return Err("upload_concurrency must be between 1 and 16".into());
+    }
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    settings::set(&conn, settings::SYNC_SETTINGS, &settings).await.map_err(|e| e.to_string())?;
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn get_network_settings(state: State<'_, AppState>) -> Result<NetworkSettings, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let current = settings::get(&conn, settings::NETWORK_SETTINGS).await.map_err(|e| e.to_string())?;
+    Ok(current.unwrap_or_default())
+}
+
+/// Set the proxy and extra root certificates. They are checked first, and
+/// apply from the next request on.
+#[tauri::command]
+pub async fn update_network_settings(
+    settings: NetworkSettings,
+    state: State<'_, AppState>,
+) -> Result<NetworkSettings, String> {
+    let blank = |s: Option<String>| s.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    let settings = NetworkSettings {
+        proxy_url:         blank(settings.proxy_url),
+        proxy_username:    blank(settings.proxy_username),
+        proxy_password:    settings.proxy_password.filter(|p| !p.is_empty()),
+        no_proxy:          blank(settings.no_proxy),
+        root_certificates: settings.root_certificates.into_iter()
+            .map(|pem| pem.trim().to_string())
+            .filter(|pem| !pem.is_empty())
+            .collect(),
+    };
+    if settings.proxy_url.as_deref().is_some_and(|url| !url.contains("://")) {
+        return Err("proxy_url must include a scheme, e.g. http://proxy:3128".into());
+    }
+    crate::http::configure(&settings).map_err(|e| format!("{e:#}"))?;
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    settings::set(&conn, settings::NETWORK_SETTINGS, &settings).await.map_err(|e| e.to_string())?;
+    Ok(settings)
+}
+
+/// The active tenant's sync filter.
+#[tauri::command]
+pub async fn get_sync_filter(state: State<'_, AppState>) -> Result<SyncFilterState, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let (_, tenant_id) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+    tenants::sync_filter(&conn, &tenant_id).await.map_err(|e| e.to_string())
+}
+
+/// Limit which of the active tenant's documents this device receives (None
+/// or an empty filter: all of them). The filter is applied here, on the next
+/// sync, which starts right away and pulls from the beginning. Documents
+/// already here stay.
+#[tauri::command]
+pub async fn set_sync_filter(
+    filter: Option<SyncFilter>,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<SyncFilterState, String> {
+    let filter = filter
+        .map(|f| SyncFilter {
+            tags:           tags::normalize(&f.tags),
+            collection_ids: f.collection_ids.into_iter().filter(|c| !c.trim().is_empty()).collect(),
+            content_types:  f.content_types.into_iter()
+                .map(|t| t.trim().to_ascii_lowercase())
+                .filter(|t| !t.is_empty())
+                .collect(),
+            max_file_size:  f.max_file_size,
+        })
+        .filter(|f| !f.is_empty());
+    if filter.as_ref().and_then(|f| f.max_file_size).is_some_and(|max| max <= 0) {
+        return Err("max_file_size must be positive".into());
+    }
+
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let (_, tenant_id) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+    tenants::set_sync_filter(&conn, &tenant_id, filter.as_ref()).await.map_err(|e| e.to_string())?;
+
+    tauri::async_runtime::spawn(async move {
+        let _ = crate::sync::engine::run_once(&app).await;
+    });
+
+    Ok(SyncFilterState { filter })
+}
+
+/// Request counts, retries and failure rates per host since startup, with
+/// each host's circuit breaker (see http.rs).
+#[tauri::command]
+pub async fn get_http_metrics() -> Result<Vec<crate::http::HostMetrics>, String> {
+    Ok(crate::http::metrics())
+}
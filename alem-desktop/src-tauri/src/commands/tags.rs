@@ -0,0 +1,133 @@
+// src-tauri/src/commands/tags.rs
+// Tag management over the normalized tags / document_tags tables. Each change
+// bumps the affected documents and queues `update_document` so the server
+// receives their new tag lists.
+use crate::{db::{self, identity, models::Tag, ops, tags}, AppState};
+use libsql::{Connection, Value};
+use serde_json::json;
+use tauri::State;
+
+#[tauri::command]
+pub async fn list_tags(state: State<'_, AppState>) -> Result<Vec<Tag>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let mut rows = conn.query(
+        "SELECT t.id, t.name,
+                (SELECT COUNT(*) FROM document_tags dt JOIN documents d ON d.id = dt.document_id
+                 WHERE dt.tag_id = t.id AND d.status != 'deleted' AND d.trashed_at IS NULL)
+         FROM tags t ORDER BY t.name COLLATE NOCASE",
+        (),
+    ).await.map_err(|e| e.to_string())?;
+
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|e| e.to_string())? {
+        let int = |i| match row.get_value(i).ok() { Some(Value::Integer(n)) => n, _ => 0 };
+        out.push(Tag {
+            id:             int(0),
+            name:           match row.get_value(1).ok() { Some(Value::Text(s)) => s, _ => String::new() },
+            document_count: int(2),
+        });
+    }
+    Ok(out)
+}
+
+/// Rename a tag in place. Fails if `new_name` is another existing tag — use
+/// merge_tags for that. Case-only renames ("work" → "Work") are allowed.
+#[tauri::command]
+pub async fn rename_tag(name: String, new_name: String, state: State<'_, AppState>) -> Result<(), String> {
+    let conn     = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let new_name = validate_name(&new_name)?;
+    let tag_id   = require(&conn, &name).await?;
+
+    if let Some(other) = tags::find(&conn, &new_name).await.map_err(|e| e.to_string())? {
+        if other != tag_id {
+            return Err(format!("Tag {new_name} already exists — merge the tags instead"));
+        }
+    }
+
+    let tx = conn.transaction().await.map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE tags SET name = ?1 WHERE id = ?2",
+        libsql::params![new_name, tag_id],
+    ).await.map_err(|e| format!("Rename failed: {e}"))?;
+    touch_documents(&tx, tag_id).await?;
+    tx.commit().await.map_err(|e| e.to_string())
+}
+
+/// Fold every tag in `sources` into `target` (created if missing). Documents
+/// carrying several of them end up with `target` once.
+#[tauri::command]
+pub async fn merge_tags(sources: Vec<String>, target: String, state: State<'_, AppState>) -> Result<(), String> {
+    let conn   = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let target = validate_name(&target)?;
+
+    let tx        = conn.transaction().await.map_err(|e| e.to_string())?;
+    let target_id = tags::ensure(&tx, &target).await.map_err(|e| e.to_string())?;
+
+    for source in &sources {
+        let Some(source_id) = tags::find(&tx, source).await.map_err(|e| e.to_string())? else {
+            continue;
+        };
+        if source_id == target_id {
+            continue;
+        }
+        touch_documents(&tx, source_id).await?;
+        tx.execute(
+            "INSERT OR IGNORE INTO document_tags (document_id, tag_id)
+             SELECT document_id, ?1 FROM document_tags WHERE tag_id = ?2",
+            libsql::params![target_id, source_id],
+        ).await.map_err(|e| format!("Merge failed: {e}"))?;
+        delete(&tx, source_id).await?;
+    }
+    tx.commit().await.map_err(|e| e.to_string())
+}
+
+/// Remove a tag from every document and drop it.
+#[tauri::command]
+pub async fn delete_tag(name: String, state: State<'_, AppState>) -> Result<(), String> {
+    let conn   = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let tag_id = require(&conn, &name).await?;
+
+    let tx = conn.transaction().await.map_err(|e| e.to_string())?;
+    touch_documents(&tx, tag_id).await?;
+    delete(&tx, tag_id).await?;
+    tx.commit().await.map_err(|e| e.to_string())
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+fn validate_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Tag name cannot be empty".into());
+    }
+    Ok(name.to_string())
+}
+
+async fn require(conn: &Connection, name: &str) -> Result<i64, String> {
+    tags::find(conn, name).await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Tag {name} not found"))
+}
+
+async fn delete(conn: &Connection, tag_id: i64) -> Result<(), String> {
+    conn.execute("DELETE FROM document_tags WHERE tag_id = ?1", libsql::params![tag_id])
+        .await.map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM tags WHERE id = ?1", libsql::params![tag_id])
+        .await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Bump every live document carrying `tag_id` and queue it for sync.
+async fn touch_documents(conn: &Connection, tag_id: i64) -> Result<(), String> {
+    let (user_id, _) = identity::current(conn).await.map_err(|e| e.to_string())?;
+    for doc_id in tags::documents_with(conn, tag_id).await.map_err(|e| e.to_string())? {
+        conn.execute(
+            "UPDATE documents SET local_version = local_version + 1, updated_at = datetime('now')
+             WHERE id = ?1",
+            libsql::params![doc_id.clone()],
+        ).await.map_err(|e| e.to_string())?;
+        ops::enqueue(conn, &user_id, "update_document", json!({ "doc_id": doc_id }))
+            .await.map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
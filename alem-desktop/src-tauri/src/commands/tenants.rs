@@ -0,0 +1,45 @@
+// src-tauri/src/commands/tenants.rs
+use crate::{
+    db::{self, models::Tenant, tenants},
+    AppState,
+};
+use tauri::{AppHandle, Emitter, State};
+
+const MAX_TENANT_NAME: usize = 100;
+
+/// Every tenant this install knows, with document and queue counts.
+#[tauri::command]
+pub async fn list_tenants(state: State<'_, AppState>) -> Result<Vec<Tenant>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    tenants::list(&conn).await.map_err(|e| e.to_string())
+}
+
+/// Make `tenant_id` the active tenant (registering it if new). Listings,
+/// search and the sync queue switch to its rows, and it syncs right away.
+/// Emits "tenant-changed" with the tenant id.
+#[tauri::command]
+pub async fn switch_tenant(
+    tenant_id: String,
+    name: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Vec<Tenant>, String> {
+    let tenant_id = tenant_id.trim().to_string();
+    let name = name.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+    if name.as_ref().is_some_and(|n| n.chars().count() > MAX_TENANT_NAME) {
+        return Err(format!("Tenant names are limited to {MAX_TENANT_NAME} characters"));
+    }
+
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    tenants::switch(&conn, &tenant_id, name.as_deref()).await.map_err(|e| e.to_string())?;
+    log::info!("[tenants] Switched to {tenant_id}");
+    let _ = app.emit("tenant-changed", &tenant_id);
+
+    let sync_app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = crate::sync::engine::run_once(&sync_app).await {
+            log::warn!("[sync] Sync after tenant switch failed: {e}");
+        }
+    });
+    tenants::list(&conn).await.map_err(|e| e.to_string())
+}
@@ -0,0 +1,30 @@
+// src-tauri/src/commands/watches.rs
+use crate::{
+    db::{self, identity, models::WatchedDocument, watches},
+    AppState,
+};
+use tauri::State;
+
+/// Get a desktop notification when `doc_id` changes on another device or is
+/// changed by someone else; its new content downloads ahead of the prefetch
+/// queue. Returns false when it was already watched.
+#[tauri::command]
+pub async fn watch_document(doc_id: String, state: State<'_, AppState>) -> Result<bool, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    super::links::ensure_live(&conn, &doc_id).await?;
+    watches::add(&conn, &doc_id).await.map_err(|e| e.to_string())
+}
+
+/// Returns false when `doc_id` wasn't watched.
+#[tauri::command]
+pub async fn unwatch_document(doc_id: String, state: State<'_, AppState>) -> Result<bool, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    watches::remove(&conn, &doc_id).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_watched_documents(state: State<'_, AppState>) -> Result<Vec<WatchedDocument>, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let (_, tenant_id) = identity::current(&conn).await.map_err(|e| e.to_string())?;
+    watches::list(&conn, &tenant_id).await.map_err(|e| e.to_string())
+}
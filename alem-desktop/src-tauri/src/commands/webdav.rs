@@ -0,0 +1,30 @@
+// src-tauri/src/commands/webdav.rs
+// The library shared over WebDAV on localhost (see webdav.rs).
+use crate::{
+    db::{self, models::{WebDavSettings, WebDavStatus}, settings},
+    webdav, AppState,
+};
+use tauri::{AppHandle, State};
+
+#[tauri::command]
+pub async fn get_webdav_settings(state: State<'_, AppState>) -> Result<WebDavSettings, String> {
+    let conn = db::connect(&state.db).await.map_err(|e| e.to_string())?;
+    let current = settings::get(&conn, settings::WEBDAV_SETTINGS).await.map_err(|e| e.to_string())?;
+    Ok(current.unwrap_or_default())
+}
+
+/// Turn the share on or off, or move it to another port. Fails, keeping the
+/// settings as they were, when the port can't be listened on.
+#[tauri::command]
+pub async fn update_webdav_settings(settings: WebDavSettings, app: AppHandle) -> Result<WebDavStatus, String> {
+    if settings.enabled && settings.port != 0 && settings.port < 1024 {
+        return Err("port must be 0 (any free port) or from 1024 up".into());
+    }
+    webdav::configure(&app, settings).await.map_err(|e| format!("{e:#}"))
+}
+
+/// Whether the share is running, and its URL.
+#[tauri::command]
+pub async fn get_webdav_status() -> Result<WebDavStatus, String> {
+    Ok(webdav::status())
+}
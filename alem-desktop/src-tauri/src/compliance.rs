@@ -0,0 +1,266 @@
+// src-tauri/src/compliance.rs
+// Compliance (data subject access request) export: everything the local
+// database holds about a person or term, in one signed bundle.
+//
+// A case-insensitive (ASCII) substring search of the current workspace
+// covers documents (filename, text, the full text kept outside the database,
+// metadata values and tags), annotations (body and quote), and activity (server
+// events and this device's own ops, including those about a matched
+// document). The bundle is a tar file laid out like export.rs archives:
+//
+//     manifest.json            ComplianceManifest
+//     files/<first2>/<sha256>  content of each matched document
+//
+// When this device holds a DID signing key, the manifest carries a `proof`
+// (a SignedPayload) over its JCS canonical form with `proof` left out. Each
+// file entry is pinned by its sha256 in the manifest, so the signature covers
+// the files too.
+use anyhow::{bail, Result};
+use libsql::{Connection, Value};
+use serde::Serialize;
+use serde_json::Value as Json;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::crypto::{self, keys};
+use crate::db::{
+    self, activity, annotations, identity,
+    models::{document_columns, row_to_document, ActivityEvent, Annotation, Columns, Document, SignedPayload},
+};
+use crate::export::{blob_for, write_archive, FILES_PREFIX};
+use crate::jobs::JobHandle;
+
+const KIND:           &str = "alem-compliance-export";
+const FORMAT_VERSION: u64 = 1;
+/// Shorter terms would match most of the database.
+const MIN_QUERY_LEN:  usize = 3;
+
+#[derive(Debug, Serialize)]
+pub struct ComplianceManifest {
+    pub format:          u64,
+    pub kind:            String,
+    pub query:           String,
+    pub tenant_id:       String,
+    pub exported_at:     String,
+    pub documents:       Vec<MatchedDocument>,
+    pub annotations:     Vec<Annotation>,
+    pub activity:        Vec<ActivityEvent>,
+    pub metadata_values: Vec<MetadataMatch>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proof:           Option<SignedPayload>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MatchedDocument {
+    pub id:            String,
+    pub filename:      String,
+    pub content_type:  Option<String>,
+    pub file_size:     Option<i64>,
+    pub content_hash:  Option<String>,
+    /// Where the query matched: "filename", "text", "full_text", "metadata", "tags".
+    pub matched_in:    Vec<&'static str>,
+    pub text_content:  Option<String>,
+    pub metadata:      Json,
+    pub tags:          Vec<String>,
+    pub collection_id: Option<String>,
+    pub created_at:    String,
+    pub updated_at:    String,
+    /// Archive entry holding the content; None if it wasn't available.
+    pub file:          Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MetadataMatch {
+    pub document_id: String,
+    /// JSON path within the document's metadata, e.g. `$.client.email`.
+    pub path:        String,
+    pub value:       Json,
+}
+
+/// Bundle everything matching `query` into `dest`.
+pub async fn export(app: &AppHandle, query: &str, dest: &Path, job: &JobHandle) -> Result<Json> {
+    let query = query.trim();
+    if query.chars().count() < MIN_QUERY_LEN {
+        bail!("Query must be at least {MIN_QUERY_LEN} characters");
+    }
+    let conn = db::connect(&app.state::<crate::AppState>().db).await?;
+    let (_, tenant_id) = identity::current(&conn).await?;
+
+    job.advance(0, Some("Searching".into()));
+    let matches = matching_documents(&conn, &tenant_id, query).await?;
+    let doc_ids: Vec<String> = matches.iter().map(|(d, _)| d.id.clone()).collect();
+    let annotations     = annotations::matching(&conn, &tenant_id, query).await?;
+    let activity        = activity::matching(&conn, &tenant_id, query, &doc_ids).await?;
+    let metadata_values = metadata_values(&conn, &tenant_id, query).await?;
+    job.set_total(matches.len() as u64);
+
+    let mut documents = Vec::with_capacity(matches.len());
+    let mut blobs: Vec<(String, PathBuf)> = Vec::new();
+    let mut seen    = HashSet::new();
+    let mut missing = 0u64;
+    for (doc, matched_in) in matches {
+        job.check_cancelled()?;
+        let file = match blob_for(app, &doc.id, doc.local_path.as_deref(), doc.needs_download).await {
+            Ok((hash, path)) => {
+                let name = format!("{FILES_PREFIX}{}/{hash}", &hash[..2]);
+                if seen.insert(hash) {
+                    blobs.push((name.clone(), path));
+                }
+                Some(name)
+            }
+            Err(e) => {
+                log::warn!("[compliance] No content for {}: {e}", doc.id);
+                missing += 1;
+                None
+            }
+        };
+        documents.push(MatchedDocument {
+            id:            doc.id,
+            filename:      doc.filename,
+            content_type:  doc.content_type,
+            file_size:     doc.file_size,
+            content_hash:  doc.content_hash,
+            matched_in,
+            text_content:  doc.text_content,
+            metadata:      doc.metadata,
+            tags:          doc.tags,
+            collection_id: doc.collection_id,
+            created_at:    doc.created_at,
+            updated_at:    doc.updated_at,
+            file,
+        });
+        job.advance(1, None);
+    }
+
+    let mut manifest = ComplianceManifest {
+        format:      FORMAT_VERSION,
+        kind:        KIND.into(),
+        query:       query.into(),
+        tenant_id,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        documents,
+        annotations,
+        activity,
+        metadata_values,
+        proof:       None,
+    };
+    manifest.proof = sign(&conn, &manifest).await?;
+    write_archive(dest, &manifest, &blobs, job).await?;
+    log::info!(
+        "[compliance] Exported {} documents, {} annotations, {} events for a {}-character query",
+        manifest.documents.len(), manifest.annotations.len(), manifest.activity.len(), query.chars().count(),
+    );
+
+    Ok(serde_json::json!({
+        "path":            dest,
+        "documents":       manifest.documents.len(),
+        "annotations":     manifest.annotations.len(),
+        "activity":        manifest.activity.len(),
+        "metadata_values": manifest.metadata_values.len(),
+        "files":           blobs.len(),
+        "missing_files":   missing,
+        "signed_by":       manifest.proof.as_ref().map(|p| p.key_id.clone()),
+    }))
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+/// Live and trashed documents of `tenant_id` matching `term`, with where.
+async fn matching_documents(conn: &Connection, tenant_id: &str, term: &str) -> Result<Vec<(Document, Vec<&'static str>)>> {
+    let mut rows = conn.query(
+        &format!(
+            "SELECT * FROM (
+                 SELECT {},
+                        d.full_text_path AS full_text_path,
+                        instr(lower(d.filename), lower(?2)) > 0 AS in_filename,
+                        instr(lower(COALESCE(d.text_content, '')), lower(?2)) > 0 AS in_text,
+                        EXISTS (
+                            SELECT 1 FROM json_tree(CASE WHEN json_valid(d.metadata) THEN d.metadata ELSE '{{}}' END) j
+                            WHERE j.atom IS NOT NULL AND instr(lower(CAST(j.atom AS TEXT)), lower(?2)) > 0
+                        ) AS in_metadata,
+                        EXISTS (
+                            SELECT 1 FROM document_tags dt JOIN tags t ON t.id = dt.tag_id
+                            WHERE dt.document_id = d.id AND instr(lower(t.name), lower(?2)) > 0
+                        ) AS in_tags
+                 FROM documents d
+                 WHERE d.tenant_id = ?1 AND d.status != 'deleted'
+             )
+             WHERE in_filename OR in_text OR in_metadata OR in_tags OR full_text_path IS NOT NULL
+             ORDER BY created_at, id",
+            document_columns("d."),
+        ),
+        libsql::params![tenant_id, term],
+    ).await?;
+
+    let needle = term.to_ascii_lowercase();
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let c = Columns::new(&row);
+        let mut matched_in: Vec<&'static str> = ["filename", "text", "metadata", "tags"]
+            .into_iter()
+            .filter(|k| c.i64(&format!("in_{k}")) == Some(1))
+            .collect();
+        // Text cut by storage::text is searched in full
+        if let Some(path) = c.str("full_text_path") {
+            match tokio::fs::read(&path).await {
+                Ok(bytes) if String::from_utf8_lossy(&bytes).to_ascii_lowercase().contains(&needle) => {
+                    matched_in.push("full_text");
+                }
+                Ok(_)  => {}
+                Err(e) => log::warn!("[compliance] Cannot read full text {path}: {e}"),
+            }
+        }
+        if !matched_in.is_empty() {
+            out.push((row_to_document(&row)?, matched_in));
+        }
+    }
+    Ok(out)
+}
+
+/// Every scalar metadata value of `tenant_id`'s documents containing `term`.
+async fn metadata_values(conn: &Connection, tenant_id: &str, term: &str) -> Result<Vec<MetadataMatch>> {
+    let mut rows = conn.query(
+        "SELECT d.id, j.fullkey, json_quote(j.atom)
+         FROM documents d, json_tree(CASE WHEN json_valid(d.metadata) THEN d.metadata ELSE '{}' END) j
+         WHERE d.tenant_id = ?1 AND d.status != 'deleted'
+           AND j.atom IS NOT NULL AND instr(lower(CAST(j.atom AS TEXT)), lower(?2)) > 0
+         ORDER BY d.id, j.fullkey",
+        libsql::params![tenant_id, term],
+    ).await?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let s = |i| match row.get_value(i).ok() { Some(Value::Text(s)) => s, _ => String::new() };
+        out.push(MetadataMatch {
+            document_id: s(0),
+            path:        s(1),
+            value:       serde_json::from_str(&s(2)).unwrap_or(Json::Null),
+        });
+    }
+    Ok(out)
+}
+
+/// Sign the manifest with the local DID's signing key, if this device has one.
+async fn sign(conn: &Connection, manifest: &ComplianceManifest) -> Result<Option<SignedPayload>> {
+    let mut rows = conn.query(
+        "SELECT did FROM local_identity WHERE id = 'singleton' AND did IS NOT NULL",
+        (),
+    ).await?;
+    let Some(Value::Text(did)) = rows.next().await?.and_then(|r| r.get_value(0).ok()) else {
+        log::warn!("[compliance] No DID: manifest left unsigned");
+        return Ok(None);
+    };
+    let (key, secret) = match keys::signing_key(conn, &did).await {
+        Ok(k)  => k,
+        Err(e) => {
+            log::warn!("[compliance] No signing key for {did} ({e}): manifest left unsigned");
+            return Ok(None);
+        }
+    };
+    Ok(Some(SignedPayload {
+        signature: crypto::sign_json(&secret, manifest)?,
+        jws:       crypto::sign_jws(&secret, &key.id, manifest)?,
+        key_id:    key.id,
+        did,
+    }))
+}
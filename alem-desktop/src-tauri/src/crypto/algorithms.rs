@@ -0,0 +1,159 @@
+// src-tauri/src/crypto/algorithms.rs
+// Signature algorithms a DID can use. Ed25519 is the default; secp256k1 and
+// P-256 are for ecosystems that require them (ES256K / ES256 in JOSE terms).
+//
+// All three have 32-byte secrets, which is what the keychain stores. ECDSA
+// signatures are the 64-byte r‖s form over SHA-256 (what JWS uses, not DER),
+// and EC public keys are compressed SEC1 points, as multicodec expects.
+use anyhow::{anyhow, bail, Result};
+use ed25519_dalek::{Signer, Verifier};
+use rand::rngs::OsRng;
+
+use super::multiformats::{self, Codec};
+use crate::db::models::KeyAlgorithm;
+
+pub const ED25519_TYPE:  &str = "Ed25519VerificationKey2020";
+/// Generic publicKeyMultibase method type for the EC curves.
+pub const MULTIKEY_TYPE: &str = "Multikey";
+
+impl KeyAlgorithm {
+    pub fn jws_alg(self) -> &'static str {
+        match self {
+            KeyAlgorithm::Ed25519   => "EdDSA",
+            KeyAlgorithm::Secp256k1 => "ES256K",
+            KeyAlgorithm::P256      => "ES256",
+        }
+    }
+
+    /// DID document verification method type.
+    pub fn method_type(self) -> &'static str {
+        match self {
+            KeyAlgorithm::Ed25519                        => ED25519_TYPE,
+            KeyAlgorithm::Secp256k1 | KeyAlgorithm::P256 => MULTIKEY_TYPE,
+        }
+    }
+}
+
+pub enum SecretKey {
+    Ed25519(ed25519_dalek::SigningKey),
+    Secp256k1(k256::ecdsa::SigningKey),
+    P256(p256::ecdsa::SigningKey),
+}
+
+#[derive(Clone)]
+pub enum PublicKey {
+    Ed25519(ed25519_dalek::VerifyingKey),
+    Secp256k1(k256::ecdsa::VerifyingKey),
+    P256(p256::ecdsa::VerifyingKey),
+}
+
+impl SecretKey {
+    pub fn generate(algorithm: KeyAlgorithm) -> Self {
+        match algorithm {
+            KeyAlgorithm::Ed25519   => SecretKey::Ed25519(ed25519_dalek::SigningKey::generate(&mut OsRng)),
+            KeyAlgorithm::Secp256k1 => SecretKey::Secp256k1(k256::ecdsa::SigningKey::random(&mut OsRng)),
+            KeyAlgorithm::P256      => SecretKey::P256(p256::ecdsa::SigningKey::random(&mut OsRng)),
+        }
+    }
+
+    pub fn from_bytes(algorithm: KeyAlgorithm, bytes: &[u8]) -> Result<Self> {
+        let bad = || anyhow!("Bad {algorithm:?} secret key");
+        Ok(match algorithm {
+            KeyAlgorithm::Ed25519 => {
+                SecretKey::Ed25519(ed25519_dalek::SigningKey::from_bytes(bytes.try_into().map_err(|_| bad())?))
+            }
+            KeyAlgorithm::Secp256k1 => {
+                SecretKey::Secp256k1(k256::ecdsa::SigningKey::from_slice(bytes).map_err(|_| bad())?)
+            }
+            KeyAlgorithm::P256 => SecretKey::P256(p256::ecdsa::SigningKey::from_slice(bytes).map_err(|_| bad())?),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            SecretKey::Ed25519(k)   => k.to_bytes().to_vec(),
+            SecretKey::Secp256k1(k) => k.to_bytes().to_vec(),
+            SecretKey::P256(k)      => k.to_bytes().to_vec(),
+        }
+    }
+
+    pub fn public(&self) -> PublicKey {
+        match self {
+            SecretKey::Ed25519(k)   => PublicKey::Ed25519(k.verifying_key()),
+            SecretKey::Secp256k1(k) => PublicKey::Secp256k1(*k.verifying_key()),
+            SecretKey::P256(k)      => PublicKey::P256(*k.verifying_key()),
+        }
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Vec<u8> {
+        match self {
+            SecretKey::Ed25519(k) => k.sign(message).to_bytes().to_vec(),
+            SecretKey::Secp256k1(k) => {
+                let signature: k256::ecdsa::Signature = k.sign(message);
+                signature.to_bytes().to_vec()
+            }
+            SecretKey::P256(k) => {
+                let signature: p256::ecdsa::Signature = k.sign(message);
+                signature.to_bytes().to_vec()
+            }
+        }
+    }
+}
+
+impl PublicKey {
+    /// From a multicodec key; X25519 is not a signature key and is rejected.
+    pub fn from_codec(codec: Codec, bytes: &[u8]) -> Result<Self> {
+        let bad = || anyhow!("Bad {} key", codec.name());
+        Ok(match codec {
+            Codec::Ed25519 => PublicKey::Ed25519(
+                ed25519_dalek::VerifyingKey::from_bytes(bytes.try_into().map_err(|_| bad())?).map_err(|_| bad())?,
+            ),
+            Codec::Secp256k1 => PublicKey::Secp256k1(k256::ecdsa::VerifyingKey::from_sec1_bytes(bytes).map_err(|_| bad())?),
+            Codec::P256      => PublicKey::P256(p256::ecdsa::VerifyingKey::from_sec1_bytes(bytes).map_err(|_| bad())?),
+            Codec::X25519    => bail!("{} is not a signature key", codec.name()),
+        })
+    }
+
+    pub fn from_multibase(multibase: &str) -> Result<Self> {
+        let (codec, bytes) = multiformats::decode_key(multibase)?;
+        Self::from_codec(codec, &bytes)
+    }
+
+    pub fn algorithm(&self) -> KeyAlgorithm {
+        match self {
+            PublicKey::Ed25519(_)   => KeyAlgorithm::Ed25519,
+            PublicKey::Secp256k1(_) => KeyAlgorithm::Secp256k1,
+            PublicKey::P256(_)      => KeyAlgorithm::P256,
+        }
+    }
+
+    pub fn codec(&self) -> Codec {
+        match self {
+            PublicKey::Ed25519(_)   => Codec::Ed25519,
+            PublicKey::Secp256k1(_) => Codec::Secp256k1,
+            PublicKey::P256(_)      => Codec::P256,
+        }
+    }
+
+    /// Raw Ed25519 key or compressed SEC1 point.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            PublicKey::Ed25519(k)   => k.as_bytes().to_vec(),
+            PublicKey::Secp256k1(k) => k.to_encoded_point(true).as_bytes().to_vec(),
+            PublicKey::P256(k)      => k.to_encoded_point(true).as_bytes().to_vec(),
+        }
+    }
+
+    pub fn multibase(&self) -> String {
+        multiformats::encode_key(self.codec(), &self.to_bytes())
+    }
+
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> Result<()> {
+        match self {
+            PublicKey::Ed25519(k) => k.verify(message, &ed25519_dalek::Signature::from_slice(signature)?)?,
+            PublicKey::Secp256k1(k) => k.verify(message, &k256::ecdsa::Signature::from_slice(signature)?)?,
+            PublicKey::P256(k) => k.verify(message, &p256::ecdsa::Signature::from_slice(signature)?)?,
+        }
+        Ok(())
+    }
+}
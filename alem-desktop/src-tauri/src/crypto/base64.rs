@@ -0,0 +1,141 @@
+// src-tauri/src/crypto/base64.rs
+// Base64 (RFC 4648) in the standard and URL-safe alphabets.
+//
+// STANDARD is what the DID controller key has always been stored as in the
+// keychain; URL_SAFE_NO_PAD is the JOSE encoding (JWS segments). Decoding
+// is strict: characters outside the alphabet, padding where the engine has
+// none (or a wrong amount where it does) and non-zero trailing bits are all
+// errors, so every encoded string has exactly one decoding.
+use anyhow::{bail, Result};
+
+pub struct Engine {
+    alphabet: &'static [u8; 64],
+    pad:      bool,
+}
+
+const STANDARD_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const URL_SAFE_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+pub const STANDARD:        Engine = Engine { alphabet: STANDARD_ALPHABET, pad: true };
+pub const URL_SAFE_NO_PAD: Engine = Engine { alphabet: URL_SAFE_ALPHABET, pad: false };
+
+impl Engine {
+    pub fn encode(&self, bytes: &[u8]) -> String {
+        let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+        for chunk in bytes.chunks(3) {
+            let n = chunk.iter().enumerate().fold(0u32, |n, (i, b)| n | ((*b as u32) << (16 - 8 * i)));
+            // 1 byte → 2 chars, 2 → 3, 3 → 4
+            for i in 0..=chunk.len() {
+                out.push(self.alphabet[((n >> (18 - 6 * i)) & 63) as usize] as char);
+            }
+            if self.pad {
+                for _ in chunk.len()..3 {
+                    out.push('=');
+                }
+            }
+        }
+        out
+    }
+
+    pub fn decode(&self, encoded: &str) -> Result<Vec<u8>> {
+        let bytes = encoded.as_bytes();
+        let data  = if self.pad {
+            if !bytes.len().is_multiple_of(4) {
+                bail!("Base64 length must be a multiple of 4");
+            }
+            let padding = bytes.iter().rev().take(2).take_while(|b| **b == b'=').count();
+            &bytes[..bytes.len() - padding]
+        } else {
+            bytes
+        };
+        if data.len() % 4 == 1 {
+            bail!("Truncated base64");
+        }
+
+        let mut out = Vec::with_capacity(data.len() * 3 / 4);
+        for chunk in data.chunks(4) {
+            let mut n = 0u32;
+            for (i, c) in chunk.iter().enumerate() {
+                n |= self.value(*c)? << (18 - 6 * i);
+            }
+            let len = chunk.len() - 1;
+            // Bits below the last whole byte must be zero
+            if n & (0xff_ffff >> (8 * len)) != 0 {
+                bail!("Non-canonical base64");
+            }
+            out.extend_from_slice(&n.to_be_bytes()[1..=len]);
+        }
+        Ok(out)
+    }
+
+    fn value(&self, c: u8) -> Result<u32> {
+        match self.alphabet.iter().position(|a| *a == c) {
+            Some(v) => Ok(v as u32),
+            None    => bail!("Invalid base64 character {:?}", c as char),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// RFC 4648 §10.
+    const VECTORS: &[(&str, &str)] = &[
+        ("",       ""),
+        ("f",      "Zg=="),
+        ("fo",     "Zm8="),
+        ("foo",    "Zm9v"),
+        ("foob",   "Zm9vYg=="),
+        ("fooba",  "Zm9vYmE="),
+        ("foobar", "Zm9vYmFy"),
+    ];
+
+    #[test]
+    fn rfc4648_vectors() {
+        for (plain, encoded) in VECTORS {
+            let unpadded = encoded.trim_end_matches('=');
+            assert_eq!(STANDARD.encode(plain.as_bytes()), *encoded);
+            assert_eq!(STANDARD.decode(encoded).unwrap(), plain.as_bytes());
+            assert_eq!(URL_SAFE_NO_PAD.encode(plain.as_bytes()), unpadded);
+            assert_eq!(URL_SAFE_NO_PAD.decode(unpadded).unwrap(), plain.as_bytes());
+        }
+    }
+
+    #[test]
+    fn alphabets_differ_in_the_last_two_characters() {
+        assert_eq!(STANDARD.encode(&[0xfb, 0xff]), "+/8=");
+        assert_eq!(URL_SAFE_NO_PAD.encode(&[0xfb, 0xff]), "-_8");
+        assert_eq!(URL_SAFE_NO_PAD.decode("-_8").unwrap(), [0xfb, 0xff]);
+    }
+
+    #[test]
+    fn rejects_bad_padding() {
+        for bad in ["Zg", "Zg=", "Zg===", "Z===", "Zm9v=", "=Zg="] {
+            assert!(STANDARD.decode(bad).is_err(), "{bad:?}");
+        }
+        for bad in ["Zg==", "Zm8=", "Z"] {
+            assert!(URL_SAFE_NO_PAD.decode(bad).is_err(), "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn rejects_characters_outside_the_alphabet() {
+        for bad in ["Zm9-", "Zm9_", "Zm 9", "Zm9\n", "Zm9é"] {
+            assert!(STANDARD.decode(bad).is_err(), "{bad:?}");
+        }
+        for bad in ["Zm9+", "Zm9/", "Zm.v"] {
+            assert!(URL_SAFE_NO_PAD.decode(bad).is_err(), "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn rejects_non_zero_trailing_bits() {
+        for bad in ["Zh==", "Zm9="] {
+            assert!(STANDARD.decode(bad).is_err(), "{bad:?}");
+        }
+        for bad in ["Zh", "Zm9"] {
+            assert!(URL_SAFE_NO_PAD.decode(bad).is_err(), "{bad:?}");
+        }
+    }
+}
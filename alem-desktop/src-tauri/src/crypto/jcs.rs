@@ -0,0 +1,230 @@
+// src-tauri/src/crypto/jcs.rs
+// JSON Canonicalization Scheme (RFC 8785).
+//
+// Two serializations of the same JSON value must give the same bytes before
+// they are signed or verified, whatever key order or number formatting the
+// producer used. JCS fixes all of it:
+//
+//   - object members sorted by the UTF-16 code units of their names
+//   - no insignificant whitespace
+//   - strings escaped minimally: only `"`, `\` and control characters, the
+//     latter as \b \t \n \f \r or \u00xx
+//   - numbers as IEEE-754 doubles printed the way ECMAScript's
+//     Number.prototype.toString does (shortest round-trip digits, exponent
+//     form outside 1e-6 ≤ |x| < 1e21)
+//
+// Integers are treated as doubles too, as the RFC requires, so values beyond
+// 2^53 lose precision — payloads that need them must carry them as strings.
+// serde_json is built with float_roundtrip: its default parser can land one
+// ulp off the nearest double, which would canonicalize differently.
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Canonical form of `value`.
+pub fn to_string(value: &Value) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value);
+    out
+}
+
+/// Canonical UTF-8 bytes of any serializable value — what gets signed.
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    Ok(to_string(&serde_json::to_value(value)?).into_bytes())
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+fn write_value(out: &mut String, value: &Value) {
+    match value {
+        Value::Null      => out.push_str("null"),
+        Value::Bool(b)   => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => out.push_str(&number(n.as_f64().unwrap_or(0.0))),
+        Value::String(s) => write_string(out, s),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(out, item);
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut members: Vec<(Vec<u16>, &String, &Value)> = map.iter()
+                .map(|(k, v)| (k.encode_utf16().collect(), k, v))
+                .collect();
+            members.sort_by(|a, b| a.0.cmp(&b.0));
+
+            out.push('{');
+            for (i, (_, key, value)) in members.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(out, key);
+                out.push(':');
+                write_value(out, value);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"'      => out.push_str("\\\""),
+            '\\'     => out.push_str("\\\\"),
+            '\u{08}' => out.push_str("\\b"),
+            '\t'     => out.push_str("\\t"),
+            '\n'     => out.push_str("\\n"),
+            '\u{0c}' => out.push_str("\\f"),
+            '\r'     => out.push_str("\\r"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// ECMAScript Number.prototype.toString for a finite double (serde_json
+/// never holds NaN or infinities).
+fn number(x: f64) -> String {
+    if x == 0.0 {
+        return "0".into(); // also -0
+    }
+    // `{:e}` gives the shortest digits that round-trip, e.g. "1.2345e-7"
+    let (mut digits, exp) = split_exp(&format!("{:e}", x.abs()));
+    let k = digits.len() as i32;
+    let n = exp + 1; // decimal point position
+
+    // When x lies exactly halfway between two shortest candidates ECMAScript
+    // takes the even one; Rust rounds up. x's exact expansion then has just
+    // one more digit, a 5.
+    let (exact, exact_exp) = split_exp(&format!("{:.1100e}", x.abs()));
+    let exact = exact.trim_end_matches('0');
+    if exact.len() == digits.len() + 1 && exact.ends_with('5') && exact_exp == exp {
+        let lower: u64 = exact[..digits.len()].parse().unwrap_or(0);
+        digits = if lower.is_multiple_of(2) { lower } else { lower + 1 }.to_string();
+    }
+
+    let body = if k <= n && n <= 21 {
+        format!("{digits}{}", "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        format!("{}.{}", &digits[..n as usize], &digits[n as usize..])
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{digits}", "0".repeat(-n as usize))
+    } else {
+        let e = n - 1;
+        let sign = if e < 0 { '-' } else { '+' };
+        if k == 1 {
+            format!("{digits}e{sign}{}", e.abs())
+        } else {
+            format!("{}.{}e{sign}{}", &digits[..1], &digits[1..], e.abs())
+        }
+    };
+    if x < 0.0 { format!("-{body}") } else { body }
+}
+
+/// "d.ddde±x" → ("dddd", x).
+fn split_exp(sci: &str) -> (String, i32) {
+    let (mantissa, exp) = sci.split_once('e').unwrap_or((sci, "0"));
+    (mantissa.replace('.', ""), exp.parse().unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// RFC 8785 Appendix B: IEEE-754 bit patterns and their canonical form.
+    const NUMBERS: &[(u64, &str)] = &[
+        (0x0000000000000000, "0"),
+        (0x8000000000000000, "0"),
+        (0x0000000000000001, "5e-324"),
+        (0x8000000000000001, "-5e-324"),
+        (0x7fefffffffffffff, "1.7976931348623157e+308"),
+        (0xffefffffffffffff, "-1.7976931348623157e+308"),
+        (0x4340000000000000, "9007199254740992"),
+        (0xc340000000000000, "-9007199254740992"),
+        (0x4430000000000000, "295147905179352830000"),
+        (0x44b52d02c7e14af5, "9.999999999999997e+22"),
+        (0x44b52d02c7e14af6, "1e+23"),
+        (0x44b52d02c7e14af7, "1.0000000000000001e+23"),
+        (0x444b1ae4d6e2ef4e, "999999999999999700000"),
+        (0x444b1ae4d6e2ef4f, "999999999999999900000"),
+        (0x444b1ae4d6e2ef50, "1e+21"),
+        (0x3eb0c6f7a0b5ed8c, "9.999999999999997e-7"),
+        (0x3eb0c6f7a0b5ed8d, "0.000001"),
+        (0x41b3de4355555553, "333333333.3333332"),
+        (0x41b3de4355555554, "333333333.33333325"),
+        (0x41b3de4355555555, "333333333.3333333"),
+        (0x41b3de4355555556, "333333333.3333334"),
+        (0x41b3de4355555557, "333333333.33333343"),
+        (0xbecbf647612f3696, "-0.0000033333333333333333"),
+        (0x43143ff3c1cb0959, "1424953923781206.2"),
+    ];
+
+    #[test]
+    fn numbers_match_appendix_b() {
+        for &(bits, expected) in NUMBERS {
+            assert_eq!(number(f64::from_bits(bits)), expected, "{bits:016x}");
+        }
+    }
+
+    #[test]
+    fn exponent_boundaries() {
+        assert_eq!(number(1e21), "1e+21");
+        assert_eq!(number(1e20), "100000000000000000000");
+        assert_eq!(number(1e-6), "0.000001");
+        assert_eq!(number(1e-7), "1e-7");
+        assert_eq!(number(-0.0), "0");
+    }
+
+    /// RFC 8785 section 3.2.2.
+    #[test]
+    fn canonicalizes_the_rfc_example() {
+        let value: Value = serde_json::from_str(
+            r#"{
+                "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+                "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+                "literals": [null, true, false]
+            }"#,
+        ).unwrap();
+        assert_eq!(
+            to_string(&value),
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#,
+        );
+    }
+
+    /// RFC 8785 section 3.2.3: members sort by UTF-16 code units, so the
+    /// emoji (a surrogate pair, 0xd83d…) comes before U+FB33.
+    #[test]
+    fn sorts_members_by_utf16_code_units() {
+        let value = json!({
+            "\u{20ac}":  "Euro Sign",
+            "\r":        "Carriage Return",
+            "\u{fb33}":  "Hebrew Letter Dalet With Dagesh",
+            "1":         "One",
+            "\u{1f600}": "Emoji: Grinning Face",
+            "\u{80}":    "Control",
+            "\u{f6}":    "Latin Small Letter O With Diaeresis",
+        });
+        assert_eq!(
+            to_string(&value),
+            "{\"\\r\":\"Carriage Return\",\"1\":\"One\",\"\u{80}\":\"Control\",\
+             \"\u{f6}\":\"Latin Small Letter O With Diaeresis\",\"\u{20ac}\":\"Euro Sign\",\
+             \"\u{1f600}\":\"Emoji: Grinning Face\",\"\u{fb33}\":\"Hebrew Letter Dalet With Dagesh\"}",
+        );
+    }
+
+    #[test]
+    fn to_vec_ignores_serializer_key_order() {
+        let a: Value = serde_json::from_str(r#"{"b":1,"a":{"d":[1.0,2],"c":null}}"#).unwrap();
+        let b: Value = serde_json::from_str(r#"{ "a": { "c": null, "d": [1, 2.0] }, "b": 1e0 }"#).unwrap();
+        assert_eq!(to_vec(&a).unwrap(), to_vec(&b).unwrap());
+        assert_eq!(to_string(&a), r#"{"a":{"c":null,"d":[1,2]},"b":1}"#);
+    }
+}
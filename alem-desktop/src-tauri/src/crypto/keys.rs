@@ -0,0 +1,278 @@
+// src-tauri/src/crypto/keys.rs
+// Per-purpose keys under the local DID.
+//
+// The did:key's own Ed25519 key is the DID's controller key: it stays in the
+// keychain and is used only for capabilityInvocation / capabilityDelegation.
+// Everyday use gets a separate key per verification relationship, so a leaked
+// signing key can't also log in or read what was encrypted to the DID:
+//
+//     signing         Ed25519*  assertionMethod
+//     authentication  Ed25519*  authentication
+//     key_agreement   X25519    keyAgreement
+//
+// * or the DID's own algorithm, for secp256k1 / P-256 DIDs.
+//
+// Public halves live in did_keys (rotated-out rows are kept, marked revoked),
+// private halves in the keychain under the verification method id. Each
+// purpose rotates on its own; the DID document lists only active keys.
+use anyhow::{Context, Result};
+use libsql::{Connection, Value};
+use rand::rngs::OsRng;
+use serde_json::json;
+
+use super::{
+    algorithms::{PublicKey, SecretKey},
+    base64,
+    multiformats::{self, Codec},
+};
+use crate::db::models::{DidKey, KeyPurpose};
+use crate::keychain;
+
+const X25519_TYPE: &str = "X25519KeyAgreementKey2020";
+const PROOF_TYPE:  &str = "JsonWebSignature2020";
+const PURPOSES: [KeyPurpose; 3] = [KeyPurpose::Signing, KeyPurpose::Authentication, KeyPurpose::KeyAgreement];
+
+impl KeyPurpose {
+    fn as_str(self) -> &'static str {
+        match self {
+            KeyPurpose::Signing        => "signing",
+            KeyPurpose::Authentication => "authentication",
+            KeyPurpose::KeyAgreement   => "key_agreement",
+        }
+    }
+
+    /// DID document verification relationship.
+    fn relationship(self) -> &'static str {
+        match self {
+            KeyPurpose::Signing        => "assertionMethod",
+            KeyPurpose::Authentication => "authentication",
+            KeyPurpose::KeyAgreement   => "keyAgreement",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        PURPOSES.into_iter().find(|p| p.as_str() == s)
+    }
+}
+
+/// Create any purpose key `did` doesn't have yet. Only DIDs generated on this
+/// device can have them (the controller key must be in the keychain).
+pub async fn ensure(conn: &Connection, did: &str) -> Result<()> {
+    let Some(controller) = controller_key(did)? else {
+        anyhow::bail!("This DID's keys are held by the server");
+    };
+    let active = list(conn, did, false).await?;
+    for purpose in PURPOSES {
+        if !active.iter().any(|k| k.purpose == purpose) {
+            create(conn, did, purpose, &controller).await?;
+        }
+    }
+    Ok(())
+}
+
+/// The did:key's own private key, if it was generated on this device. Fails
+/// if the stored key doesn't belong to `did`.
+pub fn controller_key(did: &str) -> Result<Option<SecretKey>> {
+    let Some(secret) = keychain::get(&keychain::did_private_key(did))? else {
+        return Ok(None);
+    };
+    let public = super::did_key_public(did)?;
+    let key = SecretKey::from_bytes(public.algorithm(), &base64::STANDARD.decode(&secret)?)
+        .with_context(|| format!("Stored controller key for {did} is corrupt"))?;
+    if key.public().to_bytes() != public.to_bytes() {
+        anyhow::bail!("Stored controller key does not match {did}");
+    }
+    Ok(Some(key))
+}
+
+/// Replace the active key for `purpose` with a fresh one. The old key is
+/// marked revoked and its private half deleted.
+pub async fn rotate(conn: &Connection, did: &str, purpose: KeyPurpose) -> Result<DidKey> {
+    ensure(conn, did).await?;
+    let controller = controller_key(did)?.context("This DID's keys are held by the server")?;
+    let old = active(conn, did, purpose).await?;
+
+    // New private key first: a crash after this leaves an unused keychain entry,
+    // never a did_keys row without its secret
+    let tx = conn.transaction().await?;
+    if let Some(old) = &old {
+        tx.execute(
+            "UPDATE did_keys SET revoked_at = datetime('now') WHERE id = ?1",
+            libsql::params![old.id.as_str()],
+        ).await?;
+    }
+    let key = create(&tx, did, purpose, &controller).await?;
+    tx.commit().await?;
+
+    if let Some(old) = old {
+        keychain::delete(&private_entry(&old.id))?;
+    }
+    log::info!("[did] Rotated {} key for {did}", purpose.as_str());
+    Ok(key)
+}
+
+pub async fn list(conn: &Connection, did: &str, include_revoked: bool) -> Result<Vec<DidKey>> {
+    let mut rows = conn.query(
+        "SELECT id, did, purpose, key_type, public_key_multibase, created_at, revoked_at
+         FROM did_keys WHERE did = ?1 AND (?2 OR revoked_at IS NULL)
+         ORDER BY created_at",
+        libsql::params![did, include_revoked],
+    ).await?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await? {
+        if let Some(key) = row_to_did_key(&row) {
+            out.push(key);
+        }
+    }
+    Ok(out)
+}
+
+/// Any key ever issued under this id, revoked or not.
+pub async fn find(conn: &Connection, key_id: &str) -> Result<Option<DidKey>> {
+    let mut rows = conn.query(
+        "SELECT id, did, purpose, key_type, public_key_multibase, created_at, revoked_at
+         FROM did_keys WHERE id = ?1",
+        libsql::params![key_id],
+    ).await?;
+    Ok(rows.next().await?.as_ref().and_then(row_to_did_key))
+}
+
+/// The active assertion key, for signing payloads.
+pub async fn signing_key(conn: &Connection, did: &str) -> Result<(DidKey, SecretKey)> {
+    ensure(conn, did).await?;
+    let key = active(conn, did, KeyPurpose::Signing).await?.context("No signing key")?;
+    let secret = keychain::get(&private_entry(&key.id))?
+        .with_context(|| format!("Private key for {} is missing from the keychain", key.id))?;
+    let secret = SecretKey::from_bytes(verifying_key(&key)?.algorithm(), &bs58::decode(secret).into_vec()?)
+        .with_context(|| format!("Stored key for {} is corrupt", key.id))?;
+    Ok((key, secret))
+}
+
+/// Public key of a signing or authentication key.
+pub fn verifying_key(key: &DidKey) -> Result<PublicKey> {
+    PublicKey::from_multibase(&key.public_key_multibase)
+        .with_context(|| format!("{} is not a signature key", key.id))
+}
+
+/// DID document for `did` with the controller key and every active purpose key
+/// under its verification relationship. When the controller key is on this
+/// device the document carries a `proof` by it, which is what lets others
+/// trust the purpose keys it lists (verify_document).
+pub async fn document(conn: &Connection, did: &str) -> Result<serde_json::Value> {
+    let public  = super::did_key_public(did)?;
+    let root    = public.multibase();
+    let root_id = format!("{did}#{root}");
+    let keys = list(conn, did, false).await?;
+
+    let mut methods = vec![json!({
+        "id":                 root_id,
+        "type":               public.algorithm().method_type(),
+        "controller":         did,
+        "publicKeyMultibase": root,
+    })];
+    let mut doc = json!({
+        "@context": [
+            "https://www.w3.org/ns/did/v1",
+            "https://w3id.org/security/suites/ed25519-2020/v1",
+            "https://w3id.org/security/suites/x25519-2020/v1",
+            "https://w3id.org/security/multikey/v1",
+        ],
+        "id":                   did,
+        "capabilityInvocation": [root_id],
+        "capabilityDelegation": [root_id],
+    });
+    for purpose in PURPOSES {
+        doc[purpose.relationship()] = json!([]);
+    }
+    for key in keys {
+        methods.push(json!({
+            "id":                 key.id,
+            "type":               key.key_type,
+            "controller":         did,
+            "publicKeyMultibase": key.public_key_multibase,
+        }));
+        if let Some(list) = doc[key.purpose.relationship()].as_array_mut() {
+            list.push(json!(key.id));
+        }
+    }
+    doc["verificationMethod"] = json!(methods);
+    if let Some(controller) = controller_key(did)? {
+        let jws = super::sign_jws(&controller, &root_id, &doc)?;
+        doc["proof"] = json!({
+            "type":               PROOF_TYPE,
+            "verificationMethod": root_id,
+            "proofPurpose":       "capabilityInvocation",
+            "jws":                jws,
+        });
+    }
+    Ok(doc)
+}
+
+/// Check a DID document's `proof`: a JWS over the rest of the document by
+/// the did:key's own key. Anyone can write a document listing keys under a
+/// DID; only the controller can sign one.
+pub fn verify_document(document: &serde_json::Value) -> Result<()> {
+    let did     = document["id"].as_str().context("DID document has no id")?;
+    let public  = super::did_key_public(did)?;
+    let root_id = format!("{did}#{}", public.multibase());
+    let proof   = &document["proof"];
+    if proof.is_null() {
+        anyhow::bail!("DID document for {did} is not signed");
+    }
+    if proof["verificationMethod"] != root_id.as_str() {
+        anyhow::bail!("DID document for {did} is not signed by its controller key");
+    }
+    let jws = proof["jws"].as_str().context("DID document proof has no jws")?;
+    let mut unsigned = document.clone();
+    unsigned.as_object_mut().context("DID document is not an object")?.remove("proof");
+    super::verify_jws(&public, &unsigned, jws)
+        .with_context(|| format!("DID document proof for {did} does not verify"))
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+/// Signing and authentication keys use the controller's algorithm.
+async fn create(conn: &Connection, did: &str, purpose: KeyPurpose, controller: &SecretKey) -> Result<DidKey> {
+    let (secret, public, key_type) = match purpose {
+        KeyPurpose::Signing | KeyPurpose::Authentication => {
+            let algorithm = controller.public().algorithm();
+            let key = SecretKey::generate(algorithm);
+            (key.to_bytes(), key.public().multibase(), algorithm.method_type())
+        }
+        KeyPurpose::KeyAgreement => {
+            let key = x25519_dalek::StaticSecret::random_from_rng(OsRng);
+            let public = x25519_dalek::PublicKey::from(&key);
+            (key.to_bytes().to_vec(), multiformats::encode_key(Codec::X25519, public.as_bytes()), X25519_TYPE)
+        }
+    };
+    let id = format!("{did}#{public}");
+    keychain::set(&private_entry(&id), &bs58::encode(secret).into_string())?;
+
+    conn.execute(
+        "INSERT INTO did_keys (id, did, purpose, key_type, public_key_multibase) VALUES (?1,?2,?3,?4,?5)",
+        libsql::params![id.as_str(), did, purpose.as_str(), key_type, public.as_str()],
+    ).await?;
+    find(conn, &id).await?.context("Key not stored")
+}
+
+async fn active(conn: &Connection, did: &str, purpose: KeyPurpose) -> Result<Option<DidKey>> {
+    Ok(list(conn, did, false).await?.into_iter().find(|k| k.purpose == purpose))
+}
+
+/// Keychain entry for a purpose key's private half.
+fn private_entry(key_id: &str) -> String {
+    format!("did_priv_{key_id}")
+}
+
+fn row_to_did_key(row: &libsql::Row) -> Option<DidKey> {
+    let s = |i| match row.get_value(i).ok() { Some(Value::Text(s)) => Some(s), _ => None };
+    Some(DidKey {
+        id:                   s(0)?,
+        did:                  s(1)?,
+        purpose:              KeyPurpose::parse(&s(2)?)?,
+        key_type:             s(3)?,
+        public_key_multibase: s(4)?,
+        created_at:           s(5).unwrap_or_default(),
+        revoked_at:           s(6),
+    })
+}
@@ -0,0 +1,82 @@
+// src-tauri/src/crypto/mod.rs
+// Signing and verification of JSON payloads (sync changes, manifests, receipts).
+//
+// Signatures are always computed over the JCS canonical bytes of the payload
+// (jcs.rs), never over whatever serialization happened to be sent, so key order
+// and number formatting on the other side can't break verification. Keys are
+// Ed25519 by default, or secp256k1 / P-256 (algorithms.rs); signatures are
+// multibase base58btc ("z…"), like did:key itself.
+pub mod algorithms;
+pub mod base64;
+pub mod jcs;
+pub mod keys;
+pub mod multiformats;
+pub mod sealed;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use self::algorithms::{PublicKey, SecretKey};
+use self::base64::URL_SAFE_NO_PAD;
+use self::multiformats::Base;
+
+pub fn sign_json<T: Serialize + ?Sized>(key: &SecretKey, payload: &T) -> Result<String> {
+    let signature = key.sign(&jcs::to_vec(payload)?);
+    Ok(multiformats::encode(Base::Base58Btc, &signature))
+}
+
+pub fn verify_json<T: Serialize + ?Sized>(key: &PublicKey, payload: &T, signature: &str) -> Result<()> {
+    let (_, bytes) = multiformats::decode(signature).context("Signature is not multibase")?;
+    key.verify(&jcs::to_vec(payload)?, &bytes)
+        .context("Signature does not match payload")
+}
+
+/// The same signature as a compact JWS (RFC 7515; EdDSA, ES256K or ES256)
+/// for consumers that speak JOSE. The payload segment is the JCS canonical
+/// payload.
+pub fn sign_jws<T: Serialize + ?Sized>(key: &SecretKey, kid: &str, payload: &T) -> Result<String> {
+    let header = serde_json::json!({ "alg": key.public().algorithm().jws_alg(), "kid": kid });
+    let signing_input = format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(&jcs::to_vec(&header)?),
+        URL_SAFE_NO_PAD.encode(&jcs::to_vec(payload)?),
+    );
+    let signature = key.sign(signing_input.as_bytes());
+    Ok(format!("{signing_input}.{}", URL_SAFE_NO_PAD.encode(&signature)))
+}
+
+/// Check a compact JWS and that it signs exactly `payload` (compared in
+/// canonical form, so the JWS may come from any JSON serializer).
+pub fn verify_jws<T: Serialize + ?Sized>(key: &PublicKey, payload: &T, jws: &str) -> Result<()> {
+    let mut parts = jws.split('.');
+    let (Some(header), Some(body), Some(signature), None) = (parts.next(), parts.next(), parts.next(), parts.next()) else {
+        anyhow::bail!("Not a compact JWS");
+    };
+    let header: serde_json::Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(header)?)?;
+    let alg = key.algorithm().jws_alg();
+    if header["alg"] != alg {
+        anyhow::bail!("JWS algorithm {} does not match the {alg} key", header["alg"]);
+    }
+    let signing_input = &jws[..jws.len() - signature.len() - 1];
+    key.verify(signing_input.as_bytes(), &URL_SAFE_NO_PAD.decode(signature)?)
+        .context("Signature does not match payload")?;
+
+    let signed: serde_json::Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(body)?)?;
+    if jcs::to_vec(&signed)? != jcs::to_vec(payload)? {
+        anyhow::bail!("JWS signs a different payload");
+    }
+    Ok(())
+}
+
+/// The `kid` a compact JWS's header names, unchecked.
+pub fn jws_kid(jws: &str) -> Result<String> {
+    let header = jws.split('.').next().context("Not a compact JWS")?;
+    let header: serde_json::Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(header)?)?;
+    header["kid"].as_str().map(str::to_string).context("JWS header has no kid")
+}
+
+/// Public key of a did:key (Ed25519, secp256k1 or P-256).
+pub fn did_key_public(did: &str) -> Result<PublicKey> {
+    let (codec, key) = multiformats::parse_did_key(did)?;
+    PublicKey::from_codec(codec, &key)
+}
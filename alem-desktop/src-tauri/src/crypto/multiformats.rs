@@ -0,0 +1,233 @@
+// src-tauri/src/crypto/multiformats.rs
+// Multibase and multicodec, the self-describing encodings inside did:key
+// identifiers and publicKeyMultibase values.
+//
+//     did:key:z6Mk…   =  "did:key:" + multibase(varint(codec) + raw key)
+//
+// Multibase puts a one-character base prefix in front ("z" base58btc,
+// "u" base64url); multicodec puts an unsigned-LEB128 type code in front of
+// the bytes (0xed → ed 01 for an Ed25519 public key, 0x1200 → 80 24 for
+// P-256). Only the key codecs this app handles are in the registry.
+use anyhow::{anyhow, bail, Context, Result};
+
+use super::base64::URL_SAFE_NO_PAD;
+
+const DID_KEY_PREFIX: &str = "did:key:";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Base {
+    Base58Btc,
+    Base64Url,
+}
+
+impl Base {
+    fn prefix(self) -> char {
+        match self {
+            Base::Base58Btc => 'z',
+            Base::Base64Url => 'u',
+        }
+    }
+}
+
+/// Public key codecs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    Ed25519,
+    X25519,
+    /// Compressed SEC1 point.
+    Secp256k1,
+    /// Compressed SEC1 point.
+    P256,
+}
+
+const CODECS: [Codec; 4] = [Codec::Ed25519, Codec::X25519, Codec::Secp256k1, Codec::P256];
+
+impl Codec {
+    pub fn code(self) -> u64 {
+        match self {
+            Codec::Ed25519   => 0xed,
+            Codec::X25519    => 0xec,
+            Codec::Secp256k1 => 0xe7,
+            Codec::P256      => 0x1200,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Codec::Ed25519   => "ed25519-pub",
+            Codec::X25519    => "x25519-pub",
+            Codec::Secp256k1 => "secp256k1-pub",
+            Codec::P256      => "p256-pub",
+        }
+    }
+
+    pub fn key_len(self) -> usize {
+        match self {
+            Codec::Ed25519 | Codec::X25519 => 32,
+            Codec::Secp256k1 | Codec::P256 => 33,
+        }
+    }
+
+    fn from_code(code: u64) -> Option<Self> {
+        CODECS.into_iter().find(|c| c.code() == code)
+    }
+}
+
+pub fn encode(base: Base, bytes: &[u8]) -> String {
+    let body = match base {
+        Base::Base58Btc => bs58::encode(bytes).into_string(),
+        Base::Base64Url => URL_SAFE_NO_PAD.encode(bytes),
+    };
+    format!("{}{body}", base.prefix())
+}
+
+pub fn decode(multibase: &str) -> Result<(Base, Vec<u8>)> {
+    let mut chars = multibase.chars();
+    let (prefix, body) = (chars.next().context("Empty multibase string")?, chars.as_str());
+    match prefix {
+        'z' => Ok((Base::Base58Btc, bs58::decode(body).into_vec()?)),
+        'u' => Ok((Base::Base64Url, URL_SAFE_NO_PAD.decode(body)?)),
+        _   => bail!("Unsupported multibase prefix {prefix:?}"),
+    }
+}
+
+/// base58btc(varint(codec) + key), as used in did:key and publicKeyMultibase.
+pub fn encode_key(codec: Codec, key: &[u8]) -> String {
+    let mut bytes = varint(codec.code());
+    bytes.extend_from_slice(key);
+    encode(Base::Base58Btc, &bytes)
+}
+
+/// Key type and raw key bytes of a multibase multicodec key. Fails on codecs
+/// outside the registry and on keys of the wrong length.
+pub fn decode_key(multibase: &str) -> Result<(Codec, Vec<u8>)> {
+    let (_, bytes) = decode(multibase)?;
+    let (code, key) = read_varint(&bytes).context("Truncated multicodec prefix")?;
+    let codec = Codec::from_code(code).ok_or_else(|| anyhow!("Unsupported key type 0x{code:x}"))?;
+    if key.len() != codec.key_len() {
+        bail!("Bad {} key length in {multibase}", codec.name());
+    }
+    Ok((codec, key.to_vec()))
+}
+
+pub fn did_key(codec: Codec, key: &[u8]) -> String {
+    format!("{DID_KEY_PREFIX}{}", encode_key(codec, key))
+}
+
+/// Key type and bytes of a did:key (any fragment is ignored).
+pub fn parse_did_key(did: &str) -> Result<(Codec, Vec<u8>)> {
+    let id = did.strip_prefix(DID_KEY_PREFIX).context("Not a did:key")?;
+    decode_key(id.split('#').next().unwrap_or(id))
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+/// Unsigned LEB128.
+fn varint(mut n: u64) -> Vec<u8> {
+    let mut out = Vec::new();
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            out.push(byte);
+            return out;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+/// (value, rest). None if the bytes end mid-varint or it overflows; the
+/// multiformats spec caps varints at 9 bytes.
+fn read_varint(bytes: &[u8]) -> Option<(u64, &[u8])> {
+    let mut n = 0u64;
+    for (i, b) in bytes.iter().enumerate().take(9) {
+        n |= ((b & 0x7f) as u64) << (7 * i);
+        if b & 0x80 == 0 {
+            return Some((n, &bytes[i + 1..]));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// From the did:key method spec's test vectors.
+    const VECTORS: &[(Codec, &str)] = &[
+        (Codec::Ed25519,   "did:key:z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp"),
+        (Codec::Ed25519,   "did:key:z6MkjchhfUsD6mmvni8mCdXHw216Xrm9bQe2mBH1P5RDjVJG"),
+        (Codec::Ed25519,   "did:key:z6MknGc3ocHs3zdPiJbnaaqDi58NGb4pk1Sp9WxWufuXSdxf"),
+        (Codec::X25519,    "did:key:z6LSeu9HkTHSfLLeUs2nnzUSNedgDUevfNQgQjQC23ZCit6F"),
+        (Codec::X25519,    "did:key:z6LStiZsmxiK4odS4Sb6JmdRFuJ6e1SYP157gtiCyJKfrYha"),
+        (Codec::X25519,    "did:key:z6LSoMdmJz2Djah2P4L9taDmtqeJ6wwd2HhKZvNChdfmE7nh"),
+        (Codec::Secp256k1, "did:key:zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme"),
+        (Codec::Secp256k1, "did:key:zQ3shtxV1FrJfhqE1dvxYRcCknWNjHc3c5X1y3ZSoPDi2aur2"),
+        (Codec::Secp256k1, "did:key:zQ3shZc2QzApp2oymGvQbzP8eKheVshBHbU4ZYjeXqwSKEn6N"),
+        (Codec::P256,      "did:key:zDnaerDaTF5BXEavCrfRZEk316dpbLsfPDZ3WJ5hRTPFU2169"),
+        (Codec::P256,      "did:key:zDnaerx9CtbPJ1q36T5Ln5wYt3MQYeGRG5ehnPAmxcf5mDZpv"),
+    ];
+
+    #[test]
+    fn spec_vectors_round_trip() {
+        for &(codec, did) in VECTORS {
+            let (parsed, key) = parse_did_key(did).unwrap();
+            assert_eq!(parsed, codec, "{did}");
+            assert_eq!(key.len(), codec.key_len(), "{did}");
+            assert_eq!(did_key(codec, &key), did);
+        }
+    }
+
+    #[test]
+    fn encoded_keys_parse_back() {
+        for codec in CODECS {
+            let key: Vec<u8> = (0..codec.key_len() as u8).collect();
+            let did = did_key(codec, &key);
+            assert_eq!(parse_did_key(&did).unwrap(), (codec, key.clone()));
+            assert_eq!(parse_did_key(&format!("{did}#keys-1")).unwrap(), (codec, key));
+        }
+    }
+
+    #[test]
+    fn base64url_multibase_round_trips() {
+        let bytes = [0u8, 1, 2, 250, 251, 252];
+        let encoded = encode(Base::Base64Url, &bytes);
+        assert!(encoded.starts_with('u'));
+        assert_eq!(decode(&encoded).unwrap(), (Base::Base64Url, bytes.to_vec()));
+    }
+
+    #[test]
+    fn rejects_bad_multibase_prefix() {
+        assert!(decode("x6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp").is_err());
+        assert!(decode("").is_err());
+        assert!(parse_did_key("did:key:m6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp").is_err());
+        assert!(parse_did_key("did:web:example.com").is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_codec() {
+        // 0x12 is sha2-256, not a key
+        let mut bytes = varint(0x12);
+        bytes.extend_from_slice(&[7; 32]);
+        let did = format!("{DID_KEY_PREFIX}{}", encode(Base::Base58Btc, &bytes));
+        assert!(parse_did_key(&did).is_err());
+    }
+
+    #[test]
+    fn rejects_wrong_key_length() {
+        for codec in CODECS {
+            for len in [codec.key_len() - 1, codec.key_len() + 1] {
+                let mut bytes = varint(codec.code());
+                bytes.resize(bytes.len() + len, 7);
+                let did = format!("{DID_KEY_PREFIX}{}", encode(Base::Base58Btc, &bytes));
+                assert!(parse_did_key(&did).is_err(), "{} key of {len} bytes", codec.name());
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_varint() {
+        let did = format!("{DID_KEY_PREFIX}{}", encode(Base::Base58Btc, &[0x80]));
+        assert!(parse_did_key(&did).is_err());
+    }
+}
@@ -0,0 +1,155 @@
+// src-tauri/src/crypto/sealed.rs
+// Encryption for data that stays on this device (private notes).
+//
+// The key is derived (HKDF-SHA256) from a random secret kept in the keychain
+// for the DID, never from one of the DID's own keys, so only this device can
+// read what it sealed and rotating or exporting DID keys doesn't touch it.
+// Sealed values are
+//
+//     "v2:" + base64(nonce[12] ‖ AES-256-GCM ciphertext ‖ tag[16])
+//
+// with a caller-supplied context string, e.g. the row the value belongs to,
+// as associated data, so a sealed value copied to another row no longer opens.
+//
+// Values sealed before v2 ("v1:", AES-256-CTR + HMAC-SHA256 under a key
+// derived from the DID's controller key) still open; nothing seals v1 anymore.
+use std::sync::Mutex;
+
+use aes::cipher::BlockEncrypt;
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm, Nonce,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use rand::{rngs::OsRng, RngCore};
+use sha2::Sha256;
+
+use super::{base64, keys};
+use crate::keychain;
+
+const VERSION:    &str  = "v2:";
+const NONCE_LEN:  usize = 12;
+const TAG_LEN:    usize = 16;
+const SECRET_LEN: usize = 32;
+
+const V1:         &str  = "v1:";
+const V1_IV_LEN:  usize = 16;
+const V1_TAG_LEN: usize = 32;
+
+/// Serialises creating a DID's local secret, so two first uses can't each
+/// store one and leave a value sealed under the loser.
+static SECRET: Mutex<()> = Mutex::new(());
+
+pub struct LocalKey {
+    key:     [u8; 32],
+    did:     String,
+    purpose: String,
+}
+
+impl LocalKey {
+    /// `purpose` separates keys for unrelated data under the same DID.
+    pub fn for_did(did: &str, purpose: &str) -> Result<Self> {
+        let secret = local_secret(did)?;
+        let mut key = [0u8; 32];
+        Hkdf::<Sha256>::new(Some(b"alem-local-key-v2"), &secret)
+            .expand(purpose.as_bytes(), &mut key)
+            .map_err(|_| anyhow!("HKDF output too long"))?;
+        Ok(Self { key, did: did.to_string(), purpose: purpose.to_string() })
+    }
+
+    pub fn seal(&self, plaintext: &str, context: &str) -> String {
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+        let ciphertext = self.cipher()
+            .encrypt(Nonce::from_slice(&nonce), Payload { msg: plaintext.as_bytes(), aad: context.as_bytes() })
+            .expect("AES-GCM encrypts any plaintext under 64 GiB");
+        let mut out = nonce.to_vec();
+        out.extend_from_slice(&ciphertext);
+        format!("{VERSION}{}", base64::STANDARD.encode(&out))
+    }
+
+    pub fn open(&self, sealed: &str, context: &str) -> Result<String> {
+        if let Some(body) = sealed.strip_prefix(V1) {
+            return self.open_v1(body, context);
+        }
+        let body = sealed.strip_prefix(VERSION).context("Unknown sealed value format")?;
+        let bytes = base64::STANDARD.decode(body)?;
+        if bytes.len() < NONCE_LEN + TAG_LEN {
+            bail!("Sealed value is truncated");
+        }
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+        let plaintext = self.cipher()
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: context.as_bytes() })
+            .map_err(|_| anyhow!("Sealed value was tampered with or belongs elsewhere"))?;
+        Ok(String::from_utf8(plaintext)?)
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(&self.key.into())
+    }
+
+    // ── v1 (open only) ───────────────────────────────────────────────────────
+
+    fn open_v1(&self, body: &str, context: &str) -> Result<String> {
+        let (enc, mac_key) = self.v1_keys()?;
+        let bytes = base64::STANDARD.decode(body)?;
+        if bytes.len() < V1_IV_LEN + V1_TAG_LEN {
+            bail!("Sealed value is truncated");
+        }
+        let (data, tag) = bytes.split_at(bytes.len() - V1_TAG_LEN);
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&mac_key).expect("HMAC takes any key length");
+        mac.update(V1.as_bytes());
+        mac.update(&(context.len() as u64).to_be_bytes());
+        mac.update(context.as_bytes());
+        mac.update(data);
+        mac.verify_slice(tag)
+            .map_err(|_| anyhow!("Sealed value was tampered with or belongs elsewhere"))?;
+        let (iv, ciphertext) = data.split_at(V1_IV_LEN);
+        Ok(String::from_utf8(ctr(&enc, iv.try_into()?, ciphertext))?)
+    }
+
+    /// v1 encryption and MAC keys, from the controller key.
+    fn v1_keys(&self) -> Result<([u8; 32], [u8; 32])> {
+        let controller = keys::controller_key(&self.did)?
+            .context("These notes were sealed with a DID key that is not on this device")?;
+        let mut okm = [0u8; 64];
+        Hkdf::<Sha256>::new(Some(b"alem-local-key"), &controller.to_bytes())
+            .expand(self.purpose.as_bytes(), &mut okm)
+            .map_err(|_| anyhow!("HKDF output too long"))?;
+        let (enc, mac) = okm.split_at(32);
+        Ok((enc.try_into()?, mac.try_into()?))
+    }
+}
+
+/// The DID's local secret, created on first use.
+fn local_secret(did: &str) -> Result<Vec<u8>> {
+    let _guard = SECRET.lock().unwrap_or_else(|e| e.into_inner());
+    let entry = keychain::local_secret(did);
+    if let Some(stored) = keychain::get(&entry)? {
+        let secret = base64::STANDARD.decode(&stored)?;
+        if secret.len() != SECRET_LEN {
+            bail!("Stored local secret for {did} is corrupt");
+        }
+        return Ok(secret);
+    }
+    let mut secret = vec![0u8; SECRET_LEN];
+    OsRng.fill_bytes(&mut secret);
+    keychain::set(&entry, &base64::STANDARD.encode(&secret))?;
+    Ok(secret)
+}
+
+/// AES-256-CTR; the IV is the initial 128-bit big-endian counter.
+fn ctr(key: &[u8; 32], iv: &[u8; V1_IV_LEN], data: &[u8]) -> Vec<u8> {
+    let cipher = aes::Aes256::new(key.into());
+    let mut counter = u128::from_be_bytes(*iv);
+    let mut out = Vec::with_capacity(data.len());
+    for chunk in data.chunks(16) {
+        let mut block = counter.to_be_bytes().into();
+        cipher.encrypt_block(&mut block);
+        out.extend(chunk.iter().zip(block.iter()).map(|(d, k)| d ^ k));
+        counter = counter.wrapping_add(1);
+    }
+    out
+}
@@ -0,0 +1,42 @@
+// src-tauri/src/db/access.rs
+// Document access tracking for the "Recent" view and for choosing which
+// local copies to evict first. Only opening a document counts: get_document
+// and get_document_content, not listings or search.
+use anyhow::Result;
+use libsql::Connection;
+
+use super::models::{row_to_recent_document, summary_columns, RecentDocument};
+
+/// Count an access to `doc_id`. Failing to record one never fails the read,
+/// so callers log and carry on.
+pub async fn record(conn: &Connection, doc_id: &str) -> Result<()> {
+    conn.execute(
+        "INSERT INTO document_access (document_id, last_accessed_at, access_count)
+         VALUES (?1, strftime('%Y-%m-%d %H:%M:%f', 'now'), 1)
+         ON CONFLICT(document_id) DO UPDATE SET
+             last_accessed_at = excluded.last_accessed_at,
+             access_count     = access_count + 1",
+        libsql::params![doc_id],
+    ).await?;
+    Ok(())
+}
+
+/// Most recently opened live documents of `tenant_id`.
+pub async fn recent(conn: &Connection, tenant_id: &str, limit: i64) -> Result<Vec<RecentDocument>> {
+    let mut rows = conn.query(
+        &format!(
+            "SELECT {}, a.last_accessed_at, a.access_count
+             FROM document_access a JOIN documents d ON d.id = a.document_id
+             WHERE d.tenant_id = ?1 AND d.status != 'deleted' AND d.trashed_at IS NULL
+             ORDER BY a.last_accessed_at DESC
+             LIMIT ?2",
+            summary_columns("d."),
+        ),
+        libsql::params![tenant_id, limit],
+    ).await?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await? {
+        out.push(row_to_recent_document(&row)?);
+    }
+    Ok(out)
+}
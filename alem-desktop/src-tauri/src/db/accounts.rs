@@ -0,0 +1,177 @@
+// src-tauri/src/db/accounts.rs
+// Several accounts (server and user) signed in at once.
+//
+// local_identity is the active account, so everything that asks "who am I"
+// keeps reading it. Inactive accounts keep their identity (server, user,
+// DID, active tenant, token expiry) in their accounts row; switching parks
+// the active one there and loads the other. Each account has its own tenants
+// (db/tenants.rs), so documents, search and the sync queue follow the
+// account. Tokens are kept in the keychain per account (oauth.rs), so every
+// account stays signed in while another is active.
+use anyhow::{bail, Result};
+use libsql::Connection;
+
+use super::models::{Account, Columns};
+use super::tenants;
+
+/// What an account carries, named alike in local_identity and accounts.
+const IDENTITY: &str = "server_url, username, email, user_id, did, did_public_key, \
+                        pleroma_account_id, tenant_id, token_expires_at, session_expired_at";
+
+/// Every account, the active one first. `signed_in` is left false; the
+/// keychain knows (oauth::has_tokens).
+pub async fn list(conn: &Connection) -> Result<Vec<Account>> {
+    // The active account's row is parked data; local_identity is current
+    let mut rows = conn.query(
+        "SELECT a.id, a.created_at, a.last_used_at, l.id IS NOT NULL AS active,
+                COALESCE(l.server_url, a.server_url) AS server_url,
+                COALESCE(l.username, a.username)     AS username,
+                CASE WHEN l.id IS NULL THEN a.did ELSE l.did END AS did,
+                COALESCE(l.tenant_id, a.tenant_id)   AS tenant_id,
+                CASE WHEN l.id IS NULL THEN a.session_expired_at ELSE l.session_expired_at END
+                    IS NOT NULL AS session_expired
+         FROM accounts a
+         LEFT JOIN local_identity l ON l.id = 'singleton' AND l.account_id = a.id
+         ORDER BY active DESC, a.last_used_at DESC",
+        (),
+    ).await?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let c = Columns::new(&row);
+        out.push(Account {
+            id:              c.required("id")?,
+            server_url:      c.str("server_url").unwrap_or_default(),
+            username:        c.str("username").unwrap_or_default(),
+            did:             c.str("did"),
+            tenant_id:       c.str("tenant_id").unwrap_or_else(|| "default".into()),
+            active:          c.bool("active"),
+            signed_in:       false,
+            session_expired: c.bool("session_expired"),
+            created_at:      c.str("created_at").unwrap_or_default(),
+            last_used_at:    c.str("last_used_at").unwrap_or_default(),
+        });
+    }
+    Ok(out)
+}
+
+/// The active account; None before the first sign-in.
+pub async fn active_id(conn: &Connection) -> Result<Option<String>> {
+    let mut rows = conn.query(
+        "SELECT account_id FROM local_identity WHERE id = 'singleton'",
+        (),
+    ).await?;
+    Ok(match rows.next().await? {
+        Some(row) => row.get::<Option<String>>(0)?,
+        None      => None,
+    })
+}
+
+/// Make the account of `username` on `server_url` active, creating it if
+/// new, and return its id. The first sign-in adopts whatever the install
+/// already holds (documents, tenants, DID); a later one for another account
+/// starts it afresh in its server's default tenant.
+pub async fn sign_in(conn: &Connection, server_url: &str, username: &str) -> Result<String> {
+    let tx = conn.transaction().await?;
+    tx.execute("INSERT OR IGNORE INTO local_identity (id) VALUES ('singleton')", ()).await?;
+    let active = active_id(&tx).await?;
+    let existing = {
+        let mut rows = tx.query(
+            "SELECT id FROM accounts WHERE server_url = ?1 AND username = ?2",
+            libsql::params![server_url, username],
+        ).await?;
+        match rows.next().await? {
+            Some(row) => Some(row.get::<String>(0)?),
+            None      => None,
+        }
+    };
+
+    let id = match (active, existing) {
+        (Some(active), Some(existing)) if active == existing => active,
+        (None, None) => {
+            let id = uuid::Uuid::new_v4().to_string();
+            tx.execute(
+                "UPDATE local_identity SET account_id = ?1, server_url = ?2, username = ?3 WHERE id = 'singleton'",
+                libsql::params![id.as_str(), server_url, username],
+            ).await?;
+            tx.execute(&format!(
+                "INSERT INTO accounts (id, {IDENTITY}) SELECT ?1, {IDENTITY} FROM local_identity WHERE id = 'singleton'"),
+                libsql::params![id.as_str()],
+            ).await?;
+            tx.execute("UPDATE tenants SET account_id = ?1 WHERE account_id IS NULL", libsql::params![id.as_str()]).await?;
+            id
+        }
+        (active, existing) => {
+            if active.is_some() {
+                park(&tx).await?;
+            }
+            let id = match existing {
+                Some(id) => id,
+                None => {
+                    let id = uuid::Uuid::new_v4().to_string();
+                    let tenant = tenants::claim(&tx, Some(&id), "default").await?;
+                    tx.execute(
+                        "INSERT INTO accounts (id, server_url, username, tenant_id) VALUES (?1, ?2, ?3, ?4)",
+                        libsql::params![id.as_str(), server_url, username, tenant],
+                    ).await?;
+                    id
+                }
+            };
+            load(&tx, &id).await?;
+            id
+        }
+    };
+    tx.execute(
+        "UPDATE accounts SET last_used_at = datetime('now') WHERE id = ?1",
+        libsql::params![id.as_str()],
+    ).await?;
+    tx.commit().await?;
+    Ok(id)
+}
+
+/// Make `id` the active account.
+pub async fn switch(conn: &Connection, id: &str) -> Result<()> {
+    let tx = conn.transaction().await?;
+    let mut rows = tx.query("SELECT 1 FROM accounts WHERE id = ?1", libsql::params![id]).await?;
+    if rows.next().await?.is_none() {
+        bail!("Account {id} not found");
+    }
+    drop(rows);
+    if active_id(&tx).await?.as_deref() == Some(id) {
+        return Ok(());
+    }
+    park(&tx).await?;
+    load(&tx, id).await?;
+    tx.execute(
+        "UPDATE accounts SET last_used_at = datetime('now') WHERE id = ?1",
+        libsql::params![id],
+    ).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+/// Save local_identity into the active account's row.
+async fn park(conn: &Connection) -> Result<()> {
+    conn.execute(
+        &format!(
+            "UPDATE accounts SET ({IDENTITY}) = (SELECT {IDENTITY} FROM local_identity WHERE id = 'singleton')
+             WHERE id = (SELECT account_id FROM local_identity WHERE id = 'singleton')"
+        ),
+        (),
+    ).await?;
+    Ok(())
+}
+
+/// Replace local_identity with account `id`.
+async fn load(conn: &Connection, id: &str) -> Result<()> {
+    conn.execute(
+        &format!(
+            "UPDATE local_identity
+             SET ({IDENTITY}, account_id, updated_at) = (SELECT {IDENTITY}, id, datetime('now') FROM accounts WHERE id = ?1)
+             WHERE id = 'singleton'"
+        ),
+        libsql::params![id],
+    ).await?;
+    Ok(())
+}
@@ -0,0 +1,196 @@
+// src-tauri/src/db/acl.rs
+// Collection access control and its propagation to documents.
+//
+// Setting a collection's ACL snapshots every document under it into a
+// change-set and queues a single propagate_acl op, instead of one op per
+// document. The engine sends the change-set's documents to the server's batch
+// endpoint BATCH_SIZE at a time and records the result per document, so
+// progress survives a restart, a failed request only resends what wasn't
+// applied, and documents the server rejected are reported instead of being
+// retried forever (retry() queues them again).
+//
+// Documents in a subcollection that has its own ACL keep that one.
+use anyhow::{bail, Result};
+use libsql::{Connection, Value};
+use serde_json::json;
+use uuid::Uuid;
+
+use super::{
+    models::{AclChangeSet, AclEntry, AclFailure, AclRole},
+    ops,
+};
+
+/// Documents per batch request.
+pub const BATCH_SIZE: i64 = 500;
+
+impl AclRole {
+    pub(super) fn as_str(self) -> &'static str {
+        match self {
+            AclRole::Viewer => "viewer",
+            AclRole::Editor => "editor",
+            AclRole::Owner  => "owner",
+        }
+    }
+
+    pub(super) fn parse(s: &str) -> Option<Self> {
+        [AclRole::Viewer, AclRole::Editor, AclRole::Owner].into_iter().find(|r| r.as_str() == s)
+    }
+}
+
+pub async fn get(conn: &Connection, collection_id: &str) -> Result<Vec<AclEntry>> {
+    let mut rows = conn.query(
+        "SELECT grantee, role FROM collection_acl WHERE collection_id = ?1 ORDER BY grantee",
+        libsql::params![collection_id],
+    ).await?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await? {
+        if let (Ok(Value::Text(grantee)), Ok(Value::Text(role))) = (row.get_value(0), row.get_value(1)) {
+            if let Some(role) = AclRole::parse(&role) {
+                out.push(AclEntry { grantee, role });
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Replace `collection_id`'s ACL and queue its propagation. Run inside a
+/// transaction; returns the change-set id.
+pub async fn set(conn: &Connection, user_id: &str, collection_id: &str, entries: &[AclEntry]) -> Result<String> {
+    for (i, entry) in entries.iter().enumerate() {
+        if entry.grantee.trim().is_empty() {
+            bail!("ACL grantee must not be empty");
+        }
+        if entries[..i].iter().any(|e| e.grantee == entry.grantee) {
+            bail!("{} is listed more than once", entry.grantee);
+        }
+    }
+
+    conn.execute("DELETE FROM collection_acl WHERE collection_id = ?1", libsql::params![collection_id]).await?;
+    for entry in entries {
+        conn.execute(
+            "INSERT INTO collection_acl (collection_id, grantee, role) VALUES (?1, ?2, ?3)",
+            libsql::params![collection_id, entry.grantee.as_str(), entry.role.as_str()],
+        ).await?;
+    }
+
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO acl_changesets (id, collection_id, acl) VALUES (?1, ?2, ?3)",
+        libsql::params![id.as_str(), collection_id, serde_json::to_string(entries)?],
+    ).await?;
+    // The collection and every subcollection that inherits from it
+    conn.execute(
+        "WITH RECURSIVE sub(id) AS (
+             SELECT ?2
+             UNION
+             SELECT c.id FROM collections c JOIN sub ON c.parent_id = sub.id
+             WHERE NOT EXISTS (SELECT 1 FROM collection_acl a WHERE a.collection_id = c.id)
+         )
+         INSERT INTO acl_changeset_documents (changeset_id, document_id)
+         SELECT ?1, d.id FROM documents d
+         WHERE d.collection_id IN (SELECT id FROM sub) AND d.status != 'deleted'",
+        libsql::params![id.as_str(), collection_id],
+    ).await?;
+    ops::enqueue(conn, user_id, "propagate_acl", json!({ "changeset_id": id })).await?;
+    Ok(id)
+}
+
+pub async fn changeset(conn: &Connection, id: &str) -> Result<Option<AclChangeSet>> {
+    let mut rows = conn.query(
+        "SELECT collection_id, acl, created_at, updated_at,
+                (SELECT COUNT(*) FROM acl_changeset_documents WHERE changeset_id = ?1),
+                (SELECT COUNT(*) FROM acl_changeset_documents WHERE changeset_id = ?1 AND status = 'applied'),
+                (SELECT COUNT(*) FROM acl_changeset_documents WHERE changeset_id = ?1 AND status = 'pending')
+         FROM acl_changesets WHERE id = ?1",
+        libsql::params![id],
+    ).await?;
+    let Some(row) = rows.next().await? else { return Ok(None) };
+    let s = |i| match row.get_value(i).ok() { Some(Value::Text(s)) => s, _ => String::new() };
+    let n = |i| match row.get_value(i).ok() { Some(Value::Integer(n)) => n, _ => 0 };
+    let (collection_id, acl, created_at, updated_at) = (s(0), s(1), s(2), s(3));
+    let (total, applied, pending) = (n(4), n(5), n(6));
+    drop(rows);
+
+    let failed = failures(conn, id).await?;
+    let status = match (pending, failed.is_empty()) {
+        (0, true)  => "done",
+        (0, false) => "partial",
+        _          => "pending",
+    };
+    Ok(Some(AclChangeSet {
+        id: id.to_string(),
+        collection_id,
+        acl: serde_json::from_str(&acl).unwrap_or_default(),
+        status: status.into(),
+        total,
+        applied,
+        failed,
+        created_at,
+        updated_at,
+    }))
+}
+
+/// The next documents of `id` still to send.
+pub async fn pending_batch(conn: &Connection, id: &str) -> Result<Vec<String>> {
+    let mut rows = conn.query(
+        "SELECT document_id FROM acl_changeset_documents
+         WHERE changeset_id = ?1 AND status = 'pending' ORDER BY document_id LIMIT ?2",
+        libsql::params![id, BATCH_SIZE],
+    ).await?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await? {
+        if let Ok(Value::Text(doc)) = row.get_value(0) {
+            out.push(doc);
+        }
+    }
+    Ok(out)
+}
+
+/// Record a batch response: everything in `sent` not in `failed` was applied.
+pub async fn record_batch(conn: &Connection, id: &str, sent: &[String], failed: &[AclFailure]) -> Result<()> {
+    let tx = conn.transaction().await?;
+    for doc in sent {
+        let error = failed.iter().find(|f| f.document_id == *doc).map(|f| f.error.as_str());
+        tx.execute(
+            "UPDATE acl_changeset_documents SET status = ?3, error = ?4
+             WHERE changeset_id = ?1 AND document_id = ?2",
+            libsql::params![id, doc.as_str(), if error.is_some() { "failed" } else { "applied" }, error],
+        ).await?;
+    }
+    tx.execute(
+        "UPDATE acl_changesets SET updated_at = datetime('now') WHERE id = ?1",
+        libsql::params![id],
+    ).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Queue the failed documents of `id` again. Returns how many.
+pub async fn retry(conn: &Connection, user_id: &str, id: &str) -> Result<u64> {
+    let n = conn.execute(
+        "UPDATE acl_changeset_documents SET status = 'pending', error = NULL
+         WHERE changeset_id = ?1 AND status = 'failed'",
+        libsql::params![id],
+    ).await?;
+    if n > 0 {
+        ops::enqueue(conn, user_id, "propagate_acl", json!({ "changeset_id": id })).await?;
+    }
+    Ok(n)
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+async fn failures(conn: &Connection, id: &str) -> Result<Vec<AclFailure>> {
+    let mut rows = conn.query(
+        "SELECT document_id, COALESCE(error, '') FROM acl_changeset_documents
+         WHERE changeset_id = ?1 AND status = 'failed' ORDER BY document_id",
+        libsql::params![id],
+    ).await?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await? {
+        if let (Ok(Value::Text(document_id)), Ok(Value::Text(error))) = (row.get_value(0), row.get_value(1)) {
+            out.push(AclFailure { document_id, error });
+        }
+    }
+    Ok(out)
+}
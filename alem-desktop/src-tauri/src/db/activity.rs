@@ -0,0 +1,186 @@
+// src-tauri/src/db/activity.rs
+// Workspace activity feed: server events merged with this device's own.
+//
+// Server events (member added, document shared, …) are fetched by
+// sync::activity and cached in activity_events, so the feed still reads
+// offline. Local events are not stored twice: they are the tenant's queued
+// ops, named by LOCAL_KINDS, with the op's sync status in `data`. Both are
+// normalized to datetime() form and paged newest first on (occurred_at, id).
+use anyhow::{bail, Result};
+use libsql::{Connection, Value};
+use serde_json::Value as Json;
+
+use super::models::{ActivityEvent, ActivityFeed, ActivitySource};
+
+/// Feed kind for each op type; other op types show under their own name.
+const LOCAL_KINDS: &[(&str, &str)] = &[
+    ("upload_document",  "document_created"),
+    ("update_document",  "document_updated"),
+    ("trash_document",   "document_trashed"),
+    ("restore_document", "document_restored"),
+    ("delete_document",  "document_deleted"),
+    ("sync_collection",  "collection_updated"),
+    ("sync_annotation",  "annotation_updated"),
+    ("propagate_acl",    "collection_shared"),
+    ("sync_profile",     "profile_updated"),
+];
+
+/// Store events fetched from the server; an event already cached is replaced.
+/// Events without an id or a parseable time are skipped. Returns how many
+/// were stored.
+pub async fn store(conn: &Connection, tenant_id: &str, events: &[Json]) -> Result<usize> {
+    let mut stored = 0;
+    for event in events {
+        let (Some(id), Some(occurred_at)) = (text(&event["id"]), event["occurred_at"].as_str()) else { continue };
+        let Some(kind) = event["type"].as_str().or(event["kind"].as_str()) else { continue };
+        let n = conn.execute(
+            "INSERT OR REPLACE INTO activity_events (id, tenant_id, kind, actor, document_id, data, occurred_at)
+             SELECT ?1, ?2, ?3, ?4, ?5, ?6, datetime(?7) WHERE datetime(?7) IS NOT NULL",
+            libsql::params![
+                id,
+                tenant_id,
+                kind,
+                text(&event["actor"]),
+                text(&event["document_id"]),
+                event.get("data").filter(|d| d.is_object()).cloned().unwrap_or_else(|| serde_json::json!({})).to_string(),
+                occurred_at,
+            ],
+        ).await?;
+        stored += n as usize;
+    }
+    Ok(stored)
+}
+
+/// Where the next server fetch for `tenant_id` resumes.
+pub async fn cursor(conn: &Connection, tenant_id: &str) -> Result<Option<String>> {
+    let mut rows = conn.query(
+        "SELECT activity_cursor FROM tenants WHERE id = ?1",
+        libsql::params![tenant_id],
+    ).await?;
+    Ok(match rows.next().await? {
+        Some(row) => match row.get_value(0)? { Value::Text(s) => Some(s), _ => None },
+        None      => None,
+    })
+}
+
+pub async fn set_cursor(conn: &Connection, tenant_id: &str, cursor: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE tenants SET activity_cursor = ?2 WHERE id = ?1",
+        libsql::params![tenant_id, cursor],
+    ).await?;
+    Ok(())
+}
+
+/// Up to `limit` events of `tenant_id` newer than `since`, starting after
+/// `cursor` (the previous page's next_cursor).
+pub async fn feed(
+    conn: &Connection,
+    tenant_id: &str,
+    since: Option<&str>,
+    limit: i64,
+    cursor: Option<&str>,
+) -> Result<ActivityFeed> {
+    let after = match cursor {
+        Some(c) => {
+            let Some((at, id)) = c.split_once('|') else { bail!("Invalid cursor") };
+            Some((at.to_string(), id.to_string()))
+        }
+        None => None,
+    };
+    let mut rows = conn.query(
+        &format!(
+            "SELECT id, source, kind, actor, document_id, data, occurred_at FROM ({})
+             WHERE (?2 IS NULL OR occurred_at > datetime(?2))
+               AND (?3 IS NULL OR occurred_at < ?3 OR (occurred_at = ?3 AND id < ?4))
+             ORDER BY occurred_at DESC, id DESC
+             LIMIT ?5",
+            events(),
+        ),
+        libsql::params![
+            tenant_id,
+            since,
+            after.as_ref().map(|(at, _)| at.as_str()),
+            after.as_ref().map(|(_, id)| id.as_str()),
+            limit + 1,
+        ],
+    ).await?;
+
+    let mut events = Vec::new();
+    while let Some(row) = rows.next().await? {
+        if let Some(event) = row_to_event(&row) {
+            events.push(event);
+        }
+    }
+    let next_cursor = if events.len() as i64 > limit {
+        events.truncate(limit as usize);
+        events.last().map(|e| format!("{}|{}", e.occurred_at, e.id))
+    } else {
+        None
+    };
+    Ok(ActivityFeed { events, next_cursor, refresh_error: None })
+}
+
+/// Every event of `tenant_id` mentioning `term` (ASCII case-insensitive) in
+/// its actor, kind or data, or about one of `document_ids`. Oldest first.
+pub async fn matching(conn: &Connection, tenant_id: &str, term: &str, document_ids: &[String]) -> Result<Vec<ActivityEvent>> {
+    let mut rows = conn.query(
+        &format!(
+            "SELECT id, source, kind, actor, document_id, data, occurred_at FROM ({})
+             WHERE instr(lower(COALESCE(actor, '') || ' ' || kind || ' ' || data), lower(?2)) > 0
+                OR document_id IN (SELECT value FROM json_each(?3))
+             ORDER BY occurred_at, id",
+            events(),
+        ),
+        libsql::params![tenant_id, term, serde_json::to_string(document_ids)?],
+    ).await?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await? {
+        if let Some(event) = row_to_event(&row) {
+            out.push(event);
+        }
+    }
+    Ok(out)
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+/// Server and local events of tenant ?1 as one table: id, source, kind,
+/// actor, document_id, data, occurred_at.
+fn events() -> String {
+    let kind = LOCAL_KINDS.iter()
+        .map(|(op, kind)| format!("WHEN '{op}' THEN '{kind}'"))
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!(
+        "SELECT id, 'server' AS source, kind, actor, document_id, data, occurred_at
+         FROM activity_events WHERE tenant_id = ?1
+         UNION ALL
+         SELECT 'local:' || o.id, 'local', CASE o.op_type {kind} ELSE o.op_type END, o.user_id,
+                json_extract(o.payload, '$.doc_id'),
+                json_object('op_type', o.op_type, 'status', o.status, 'payload', json(o.payload)),
+                o.created_at
+         FROM offline_operations o WHERE o.tenant_id = ?1 AND json_valid(o.payload)"
+    )
+}
+
+/// Ids may come as strings or numbers.
+fn text(value: &Json) -> Option<String> {
+    match value {
+        Json::String(s) if !s.is_empty() => Some(s.clone()),
+        Json::Number(n)                  => Some(n.to_string()),
+        _                                => None,
+    }
+}
+
+fn row_to_event(row: &libsql::Row) -> Option<ActivityEvent> {
+    let s = |i| match row.get_value(i).ok() { Some(Value::Text(s)) => Some(s), _ => None };
+    Some(ActivityEvent {
+        id:          s(0)?,
+        source:      if s(1)? == "local" { ActivitySource::Local } else { ActivitySource::Server },
+        kind:        s(2)?,
+        actor:       s(3),
+        document_id: s(4),
+        data:        s(5).and_then(|d| serde_json::from_str(&d).ok()).unwrap_or(Json::Null),
+        occurred_at: s(6)?,
+    })
+}
@@ -0,0 +1,199 @@
+// src-tauri/src/db/annotations.rs
+// Comments and highlights attached to documents.
+//
+// Each annotation syncs on its own (a sync_annotation op) rather than with
+// its document, since several people may annotate one document. Deleting
+// leaves a tombstone so the delete syncs too. Edits from the server are
+// applied last-writer-wins on updated_at, and a tombstone is never revived.
+use anyhow::{bail, Context, Result};
+use libsql::{Connection, Value};
+use serde_json::json;
+use uuid::Uuid;
+
+use super::{
+    models::{Annotation, AnnotationAnchor, AnnotationKind},
+    ops,
+};
+
+const COLUMNS: &str = "id, doc_id, kind, anchor, body, author_did, created_at, updated_at, deleted_at";
+
+impl AnnotationKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            AnnotationKind::Comment   => "comment",
+            AnnotationKind::Highlight => "highlight",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        [AnnotationKind::Comment, AnnotationKind::Highlight].into_iter().find(|k| k.as_str() == s)
+    }
+}
+
+/// Live annotations on `doc_id`, oldest first.
+pub async fn list(conn: &Connection, doc_id: &str) -> Result<Vec<Annotation>> {
+    let mut rows = conn.query(
+        &format!(
+            "SELECT {COLUMNS} FROM annotations WHERE doc_id = ?1 AND deleted_at IS NULL
+             ORDER BY created_at, id"
+        ),
+        libsql::params![doc_id],
+    ).await?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await? {
+        if let Some(annotation) = row_to_annotation(&row) {
+            out.push(annotation);
+        }
+    }
+    Ok(out)
+}
+
+/// Live annotations on `tenant_id`'s documents whose body or anchor mentions
+/// `term` (ASCII case-insensitive), oldest first.
+pub async fn matching(conn: &Connection, tenant_id: &str, term: &str) -> Result<Vec<Annotation>> {
+    let mut rows = conn.query(
+        &format!(
+            "SELECT {COLUMNS} FROM annotations
+             WHERE deleted_at IS NULL
+               AND doc_id IN (SELECT id FROM documents WHERE tenant_id = ?1)
+               AND instr(lower(body || ' ' || anchor), lower(?2)) > 0
+             ORDER BY created_at, id"
+        ),
+        libsql::params![tenant_id, term],
+    ).await?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await? {
+        if let Some(annotation) = row_to_annotation(&row) {
+            out.push(annotation);
+        }
+    }
+    Ok(out)
+}
+
+/// Including tombstones.
+pub async fn get(conn: &Connection, id: &str) -> Result<Option<Annotation>> {
+    let mut rows = conn.query(
+        &format!("SELECT {COLUMNS} FROM annotations WHERE id = ?1"),
+        libsql::params![id],
+    ).await?;
+    Ok(rows.next().await?.as_ref().and_then(row_to_annotation))
+}
+
+pub async fn add(
+    conn: &Connection,
+    user_id: &str,
+    author_did: &str,
+    doc_id: &str,
+    kind: AnnotationKind,
+    anchor: &AnnotationAnchor,
+    body: &str,
+) -> Result<Annotation> {
+    check_anchor(anchor)?;
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO annotations (id, doc_id, kind, anchor, body, author_did) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        libsql::params![id.as_str(), doc_id, kind.as_str(), serde_json::to_string(anchor)?, body, author_did],
+    ).await?;
+    ops::enqueue(conn, user_id, "sync_annotation", json!({ "annotation_id": id })).await?;
+    get(conn, &id).await?.context("Annotation not stored")
+}
+
+/// Change the body and/or anchor of one of `author_did`'s annotations.
+pub async fn update(
+    conn: &Connection,
+    user_id: &str,
+    author_did: &str,
+    id: &str,
+    anchor: Option<&AnnotationAnchor>,
+    body: Option<&str>,
+) -> Result<Annotation> {
+    if let Some(anchor) = anchor {
+        check_anchor(anchor)?;
+    }
+    own(conn, author_did, id).await?;
+    conn.execute(
+        "UPDATE annotations SET
+             anchor     = COALESCE(?2, anchor),
+             body       = COALESCE(?3, body),
+             updated_at = datetime('now')
+         WHERE id = ?1",
+        libsql::params![id, anchor.map(serde_json::to_string).transpose()?, body],
+    ).await?;
+    ops::enqueue(conn, user_id, "sync_annotation", json!({ "annotation_id": id })).await?;
+    get(conn, id).await?.context("Annotation not found")
+}
+
+pub async fn delete(conn: &Connection, user_id: &str, author_did: &str, id: &str) -> Result<()> {
+    own(conn, author_did, id).await?;
+    conn.execute(
+        "UPDATE annotations SET deleted_at = datetime('now'), updated_at = datetime('now') WHERE id = ?1",
+        libsql::params![id],
+    ).await?;
+    ops::enqueue(conn, user_id, "sync_annotation", json!({ "annotation_id": id })).await?;
+    Ok(())
+}
+
+/// Store an annotation pulled from the server.
+pub async fn apply_remote(conn: &Connection, annotation: &Annotation) -> Result<()> {
+    conn.execute(
+        "INSERT INTO annotations (id, doc_id, kind, anchor, body, author_did, created_at, updated_at, deleted_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+         ON CONFLICT(id) DO UPDATE SET
+             anchor     = excluded.anchor,
+             body       = excluded.body,
+             updated_at = excluded.updated_at,
+             deleted_at = COALESCE(annotations.deleted_at, excluded.deleted_at)
+         WHERE annotations.deleted_at IS NULL
+           AND (excluded.updated_at >= annotations.updated_at OR excluded.deleted_at IS NOT NULL)",
+        libsql::params![
+            annotation.id.as_str(),
+            annotation.doc_id.as_str(),
+            annotation.kind.as_str(),
+            serde_json::to_string(&annotation.anchor)?,
+            annotation.body.as_str(),
+            annotation.author_did.as_str(),
+            annotation.created_at.as_str(),
+            annotation.updated_at.as_str(),
+            annotation.deleted_at.clone(),
+        ],
+    ).await?;
+    Ok(())
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+/// Only the author edits or deletes an annotation.
+async fn own(conn: &Connection, author_did: &str, id: &str) -> Result<()> {
+    match get(conn, id).await? {
+        Some(a) if a.deleted_at.is_some() => bail!("Annotation {id} was deleted"),
+        Some(a) if a.author_did != author_did => bail!("Only {} can change this annotation", a.author_did),
+        Some(_) => Ok(()),
+        None    => bail!("Annotation {id} not found"),
+    }
+}
+
+fn check_anchor(anchor: &AnnotationAnchor) -> Result<()> {
+    if anchor.page.is_some_and(|p| p < 1) {
+        bail!("Pages are numbered from 1");
+    }
+    match (anchor.start, anchor.end) {
+        (Some(start), Some(end)) if start < 0 || end < start => bail!("Invalid range {start}..{end}"),
+        (Some(_), None) | (None, Some(_)) => bail!("A range needs both start and end"),
+        _ => Ok(()),
+    }
+}
+
+fn row_to_annotation(row: &libsql::Row) -> Option<Annotation> {
+    let s = |i| match row.get_value(i).ok() { Some(Value::Text(s)) => Some(s), _ => None };
+    Some(Annotation {
+        id:         s(0)?,
+        doc_id:     s(1)?,
+        kind:       AnnotationKind::parse(&s(2)?)?,
+        anchor:     s(3).and_then(|a| serde_json::from_str(&a).ok()).unwrap_or_default(),
+        body:       s(4).unwrap_or_default(),
+        author_did: s(5).unwrap_or_default(),
+        created_at: s(6).unwrap_or_default(),
+        updated_at: s(7).unwrap_or_default(),
+        deleted_at: s(8),
+    })
+}
@@ -0,0 +1,77 @@
+// src-tauri/src/db/bulk.rs
+// Bulk-import support: run large inserts without the per-row FTS triggers.
+//
+// SQLite has no "disable trigger", so suspend_fts drops the three documents_fts
+// triggers and resume_fts recreates them and rebuilds the index from the
+// documents table in one pass. A settings flag records the suspended state, so
+// if the app dies mid-import the triggers are restored on the next open.
+use anyhow::Result;
+use libsql::Connection;
+use tokio::sync::{Mutex, MutexGuard};
+
+use super::settings;
+
+/// Rows per insert transaction.
+pub const BATCH_SIZE: usize = 500;
+
+const FTS_SUSPENDED: &str = "fts_suspended";
+
+/// Must stay identical to the trigger definitions in migration v1.
+const FTS_TRIGGERS: &str = "
+    CREATE TRIGGER IF NOT EXISTS docs_fts_insert AFTER INSERT ON documents BEGIN
+        INSERT INTO documents_fts(id, filename, text_content)
+        VALUES (new.id, new.filename, new.text_content);
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS docs_fts_update AFTER UPDATE ON documents BEGIN
+        UPDATE documents_fts
+        SET    filename     = new.filename,
+               text_content = new.text_content
+        WHERE  id = new.id;
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS docs_fts_delete AFTER DELETE ON documents BEGIN
+        DELETE FROM documents_fts WHERE id = old.id;
+    END;
+";
+
+/// One bulk import at a time — a second one finishing first would
+/// re-enable the triggers under the other.
+static BULK_LOCK: Mutex<()> = Mutex::const_new(());
+
+pub async fn lock() -> MutexGuard<'static, ()> {
+    BULK_LOCK.lock().await
+}
+
+/// Drop the FTS triggers. Writes from other connections (sync pulls, edits)
+/// during the import are picked up by the rebuild in resume_fts.
+pub async fn suspend_fts(conn: &Connection) -> Result<()> {
+    settings::set(conn, FTS_SUSPENDED, &true).await?;
+    conn.execute_batch("
+        DROP TRIGGER IF EXISTS docs_fts_insert;
+        DROP TRIGGER IF EXISTS docs_fts_update;
+        DROP TRIGGER IF EXISTS docs_fts_delete;
+    ").await?;
+    log::debug!("[db] FTS triggers suspended for bulk import");
+    Ok(())
+}
+
+/// Recreate the triggers and rebuild documents_fts from scratch.
+pub async fn resume_fts(conn: &Connection) -> Result<()> {
+    let started = std::time::Instant::now();
+    conn.execute_batch(&format!(
+        "{FTS_TRIGGERS}\nINSERT INTO documents_fts(documents_fts) VALUES('rebuild');"
+    )).await?;
+    settings::set(conn, FTS_SUSPENDED, &false).await?;
+    log::info!("[db] FTS triggers restored, index rebuilt in {:?}", started.elapsed());
+    Ok(())
+}
+
+/// Called on open: finish the job of an import that never got to resume_fts.
+pub async fn recover_fts(conn: &Connection) -> Result<()> {
+    if settings::get::<bool>(conn, FTS_SUSPENDED).await?.unwrap_or(false) {
+        log::warn!("[db] Previous bulk import did not finish — restoring FTS triggers");
+        resume_fts(conn).await?;
+    }
+    Ok(())
+}
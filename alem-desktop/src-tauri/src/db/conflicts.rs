@@ -0,0 +1,309 @@
+// src-tauri/src/db/conflicts.rs
+// Sync conflicts. A server change pulled for a document whose own local
+// change hasn't been uploaded yet is held here instead of overwriting it, and
+// the document's queued upload/update ops wait (see process_pending_ops)
+// until the user picks a ConflictStrategy.
+use anyhow::{bail, Context, Result};
+use libsql::Connection;
+use serde_json::{json, Value as Json};
+use uuid::Uuid;
+
+use super::{
+    models::{Columns, ConflictStrategy, ConflictVersion, SyncConflict},
+    ops, tags,
+};
+
+/// Op types that carry a document's state to the server; these are held
+/// while it is in conflict and dropped when the server version is taken.
+pub const HELD_OPS: &str = "('upload_document', 'update_document')";
+
+impl ConflictStrategy {
+    fn as_str(self) -> &'static str {
+        match self {
+            ConflictStrategy::KeepLocal  => "keep_local",
+            ConflictStrategy::KeepRemote => "keep_remote",
+            ConflictStrategy::KeepBoth   => "keep_both",
+        }
+    }
+}
+
+/// Conflict columns joined with the local side (`d`), for row_to_conflict.
+fn columns() -> String {
+    format!(
+        "c.id AS id, c.document_id AS document_id, c.remote AS remote,
+         c.created_at AS created_at, c.updated_at AS updated_at,
+         d.filename AS filename, d.content_type AS content_type, d.content_hash AS content_hash,
+         d.file_size AS file_size, d.collection_id AS collection_id, {} AS tags,
+         d.updated_at AS local_updated_at, d.local_version AS local_version,
+         d.local_path AS local_path, d.object_key AS object_key",
+        tags::json_expr("d.id"),
+    )
+}
+
+/// Whether `remote` (a pulled document change's data) would overwrite an
+/// unsent local change to `doc_id`. A change that agrees with the local copy
+/// on everything it sends, such as the echo of our own update, doesn't.
+pub async fn conflicts_with_local(conn: &Connection, doc_id: &str, remote: &Json) -> Result<bool> {
+    let mut rows = conn.query(
+        "SELECT filename, object_key, collection_id FROM documents
+         WHERE id = ?1 AND needs_upload = 1 AND status != 'deleted'",
+        libsql::params![doc_id],
+    ).await?;
+    let Some(row) = rows.next().await? else { return Ok(false) };
+    let c = Columns::new(&row);
+
+    let differs = |field: &str, local: Option<String>| match remote.get(field) {
+        Some(Json::String(s)) => !s.is_empty() && local.as_deref() != Some(s),
+        Some(Json::Null)      => field == "collection_id" && local.is_some(),
+        _                     => false,
+    };
+    Ok(differs("filename", c.str("filename"))
+        || differs("object_key", c.str("object_key").filter(|k| !k.is_empty()))
+        || differs("collection_id", c.str("collection_id")))
+}
+
+/// Hold `remote` against `doc_id`. A newer change to a document that is
+/// already in conflict replaces the held one.
+pub async fn record(conn: &Connection, doc_id: &str, remote: &Json) -> Result<SyncConflict> {
+    conn.execute(
+        "INSERT INTO sync_conflicts (id, document_id, remote) VALUES (?1, ?2, ?3)
+         ON CONFLICT(document_id) WHERE resolved_at IS NULL DO UPDATE SET
+             remote     = excluded.remote,
+             updated_at = datetime('now')",
+        libsql::params![Uuid::new_v4().to_string(), doc_id, remote.to_string()],
+    ).await?;
+
+    let mut rows = conn.query(
+        &format!(
+            "SELECT {} FROM sync_conflicts c JOIN documents d ON d.id = c.document_id
+             WHERE c.document_id = ?1 AND c.resolved_at IS NULL",
+            columns(),
+        ),
+        libsql::params![doc_id],
+    ).await?;
+    let row = rows.next().await?.with_context(|| format!("Conflict on {doc_id} vanished"))?;
+    row_to_conflict(&row)
+}
+
+/// Open conflicts on `tenant_id`'s documents, oldest first.
+pub async fn list(conn: &Connection, tenant_id: &str) -> Result<Vec<SyncConflict>> {
+    let mut rows = conn.query(
+        &format!(
+            "SELECT {} FROM sync_conflicts c JOIN documents d ON d.id = c.document_id
+             WHERE c.resolved_at IS NULL AND d.tenant_id = ?1
+             ORDER BY c.created_at, c.id",
+            columns(),
+        ),
+        libsql::params![tenant_id],
+    ).await?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await? {
+        out.push(row_to_conflict(&row)?);
+    }
+    Ok(out)
+}
+
+pub async fn count(conn: &Connection, tenant_id: &str) -> Result<i64> {
+    let mut rows = conn.query(
+        "SELECT COUNT(*) FROM sync_conflicts c JOIN documents d ON d.id = c.document_id
+         WHERE c.resolved_at IS NULL AND d.tenant_id = ?1",
+        libsql::params![tenant_id],
+    ).await?;
+    Ok(match rows.next().await? {
+        Some(row) => row.get(0).unwrap_or(0),
+        None      => 0,
+    })
+}
+
+/// Apply `strategy` to open conflict `id`. Returns the id of the copy made
+/// by KeepBoth.
+pub async fn resolve(
+    conn: &Connection,
+    user_id: &str,
+    id: &str,
+    strategy: ConflictStrategy,
+) -> Result<Option<String>> {
+    let mut rows = conn.query(
+        &format!(
+            "SELECT {} FROM sync_conflicts c JOIN documents d ON d.id = c.document_id
+             WHERE c.id = ?1 AND c.resolved_at IS NULL",
+            columns(),
+        ),
+        libsql::params![id],
+    ).await?;
+    let row = rows.next().await?.with_context(|| format!("No open conflict {id}"))?;
+    let conflict = row_to_conflict(&row)?;
+    if !conflict.strategies.contains(&strategy) {
+        bail!("{} is not available for conflict {id}", strategy.as_str());
+    }
+    let c          = Columns::new(&row);
+    let remote     = c.str("remote").and_then(|r| serde_json::from_str::<Json>(&r).ok()).unwrap_or(Json::Null);
+    let object_key = c.str("object_key").filter(|k| !k.is_empty());
+    let doc_id     = conflict.document_id.as_str();
+    drop(rows);
+
+    let mut copy_id = None;
+    match strategy {
+        ConflictStrategy::KeepLocal => {
+            // New content goes up as an upload; otherwise the current state as an update
+            let remote_key = remote["object_key"].as_str().filter(|k| !k.is_empty());
+            let op_type = if object_key.is_none() || (remote_key.is_some() && remote_key != object_key.as_deref()) {
+                "upload_document"
+            } else {
+                "update_document"
+            };
+            ensure_queued(conn, user_id, doc_id, op_type).await?;
+        }
+        ConflictStrategy::KeepRemote => take_remote(conn, doc_id, &remote).await?,
+        ConflictStrategy::KeepBoth => {
+            copy_id = Some(copy_local(conn, user_id, doc_id).await?);
+            take_remote(conn, doc_id, &remote).await?;
+        }
+    }
+
+    conn.execute(
+        "UPDATE sync_conflicts SET resolution = ?1, resolved_at = datetime('now') WHERE id = ?2",
+        libsql::params![strategy.as_str(), id],
+    ).await?;
+    log::info!("[sync] Conflict on {doc_id} resolved: {}", strategy.as_str());
+    Ok(copy_id)
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+fn row_to_conflict(row: &libsql::Row) -> Result<SyncConflict> {
+    let c           = Columns::new(row);
+    let document_id = c.required("document_id")?;
+    let remote: Json = c.str("remote").and_then(|r| serde_json::from_str(&r).ok()).unwrap_or(Json::Null);
+
+    let mut strategies = vec![ConflictStrategy::KeepLocal, ConflictStrategy::KeepRemote];
+    // A copy needs local content to upload
+    if c.str("local_path").is_some_and(|p| !p.is_empty()) {
+        strategies.push(ConflictStrategy::KeepBoth);
+    }
+
+    Ok(SyncConflict {
+        id: c.required("id")?,
+        local: ConflictVersion {
+            filename:      c.str("filename"),
+            content_type:  c.str("content_type"),
+            content_hash:  c.str("content_hash"),
+            file_size:     c.i64("file_size"),
+            collection_id: c.str("collection_id"),
+            tags:          c.str("tags").and_then(|t| serde_json::from_str(&t).ok()),
+            updated_at:    c.str("local_updated_at"),
+            version:       c.i64("local_version"),
+            changed_by:    None,
+        },
+        remote: remote_version(&remote),
+        strategies,
+        created_at: c.str("created_at").unwrap_or_default(),
+        updated_at: c.str("updated_at").unwrap_or_default(),
+        document_id,
+    })
+}
+
+fn remote_version(data: &Json) -> ConflictVersion {
+    let s = |k: &str| data[k].as_str().map(str::to_string);
+    ConflictVersion {
+        filename:      s("filename"),
+        content_type:  s("content_type"),
+        content_hash:  s("content_hash"),
+        file_size:     data["file_size"].as_i64(),
+        collection_id: s("collection_id"),
+        tags:          serde_json::from_value(data["tags"].clone()).ok(),
+        updated_at:    s("updated_at"),
+        version:       data["version"].as_i64().or_else(|| data["server_version"].as_i64()),
+        changed_by:    s("updated_by").or_else(|| s("user_id")),
+    }
+}
+
+/// Queue `op_type` for `doc_id` unless one is already waiting.
+async fn ensure_queued(conn: &Connection, user_id: &str, doc_id: &str, op_type: &str) -> Result<()> {
+    let mut rows = conn.query(
+        "SELECT 1 FROM offline_operations
+         WHERE op_type = ?1 AND json_extract(payload, '$.doc_id') = ?2 AND status IN ('pending', 'failed')",
+        libsql::params![op_type, doc_id],
+    ).await?;
+    if rows.next().await?.is_none() {
+        ops::enqueue(conn, user_id, op_type, json!({ "doc_id": doc_id })).await?;
+    }
+    Ok(())
+}
+
+/// Overwrite the local document with the server's version and drop its
+/// unsent upload/update ops. New content is left for prefetch to download.
+async fn take_remote(conn: &Connection, doc_id: &str, remote: &Json) -> Result<()> {
+    let metadata = remote.get("metadata").filter(|m| m.is_object()).map(Json::to_string);
+    conn.execute(
+        "UPDATE documents SET
+             filename       = COALESCE(?1, filename),
+             content_type   = COALESCE(?2, content_type),
+             collection_id  = CASE WHEN ?3 THEN ?4 ELSE collection_id END,
+             metadata       = COALESCE(?5, metadata),
+             needs_download = CASE WHEN ?6 != '' AND ?6 IS NOT object_key THEN 1 ELSE needs_download END,
+             object_key     = COALESCE(NULLIF(?6, ''), object_key),
+             status = 'synced', is_synced = 1, needs_upload = 0,
+             last_synced_at = datetime('now'), updated_at = datetime('now')
+         WHERE id = ?7",
+        libsql::params![
+            remote["filename"].as_str().filter(|f| !f.is_empty()),
+            remote["content_type"].as_str().filter(|t| !t.is_empty()),
+            remote.get("collection_id").is_some() as i64,
+            remote["collection_id"].as_str(),
+            metadata,
+            remote["object_key"].as_str().unwrap_or(""),
+            doc_id,
+        ],
+    ).await?;
+    if let Ok(remote_tags) = serde_json::from_value::<Vec<String>>(remote["tags"].clone()) {
+        tags::set_for_document(conn, doc_id, &remote_tags).await?;
+    }
+    conn.execute(
+        &format!(
+            "DELETE FROM offline_operations
+             WHERE op_type IN {HELD_OPS} AND json_extract(payload, '$.doc_id') = ?1
+               AND status IN ('pending', 'failed')"
+        ),
+        libsql::params![doc_id],
+    ).await?;
+    Ok(())
+}
+
+/// Copy `doc_id` as it is locally into a new document queued for upload.
+async fn copy_local(conn: &Connection, user_id: &str, doc_id: &str) -> Result<String> {
+    let mut rows = conn.query("SELECT filename FROM documents WHERE id = ?1", libsql::params![doc_id]).await?;
+    let filename: String = rows.next().await?
+        .with_context(|| format!("Document {doc_id} not found"))?
+        .get(0)?;
+    drop(rows);
+
+    let copy_id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO documents (
+             id, user_id, tenant_id, filename, content_type, file_size, content_hash,
+             local_path, compression, text_content, full_text_path, metadata, collection_id,
+             is_cached_locally, status, needs_upload
+         )
+         SELECT ?1, user_id, tenant_id, ?2, content_type, file_size, content_hash,
+                local_path, compression, text_content, full_text_path, metadata, collection_id,
+                is_cached_locally, 'local', 1
+         FROM documents WHERE id = ?3",
+        libsql::params![copy_id.as_str(), conflicted_name(&filename), doc_id],
+    ).await?;
+    conn.execute(
+        "INSERT INTO document_tags (document_id, tag_id)
+         SELECT ?1, tag_id FROM document_tags WHERE document_id = ?2",
+        libsql::params![copy_id.as_str(), doc_id],
+    ).await?;
+    ops::enqueue(conn, user_id, "upload_document", json!({ "doc_id": copy_id })).await?;
+    Ok(copy_id)
+}
+
+/// "report.pdf" → "report (conflicted copy).pdf"
+fn conflicted_name(filename: &str) -> String {
+    match filename.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{stem} (conflicted copy).{ext}"),
+        _ => format!("{filename} (conflicted copy)"),
+    }
+}
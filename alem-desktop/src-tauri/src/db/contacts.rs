@@ -0,0 +1,239 @@
+// src-tauri/src/db/contacts.rs
+// Contact book: DIDs we know about and how far we trust them.
+//
+// A DID lands here unverified, either added by the user or first seen on a
+// synced profile. It becomes verified once both sides have compared the
+// safety number (or one scanned the other's QR code), and revoked when the
+// user withdraws trust; signatures from revoked DIDs are rejected. Every key
+// seen for a contact is kept, so a signature can be checked against the key
+// it names even after the contact has rotated it.
+use anyhow::{anyhow, bail, Context, Result};
+use libsql::{Connection, Value};
+use sha2::{Digest, Sha256};
+
+use super::models::{Contact, ContactKey, TrustLevel};
+use crate::crypto::{self, multiformats};
+
+const QR_PREFIX: &str = "alem-verify:v1:";
+/// 5-digit groups per side; two sides make a 60-digit safety number.
+const FINGERPRINT_GROUPS: usize = 6;
+
+impl TrustLevel {
+    fn as_str(self) -> &'static str {
+        match self {
+            TrustLevel::Unverified => "unverified",
+            TrustLevel::Verified   => "verified",
+            TrustLevel::Revoked    => "revoked",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        [TrustLevel::Unverified, TrustLevel::Verified, TrustLevel::Revoked]
+            .into_iter()
+            .find(|t| t.as_str() == s)
+    }
+}
+
+/// Add `did` (or update its label). Re-adding a revoked contact starts it
+/// over as unverified.
+pub async fn add(conn: &Connection, did: &str, label: Option<&str>) -> Result<Contact> {
+    seen(conn, did).await?;
+    conn.execute(
+        "UPDATE contacts SET
+             label        = COALESCE(?2, label),
+             trust        = CASE trust WHEN 'revoked' THEN 'unverified' ELSE trust END,
+             revoked_at   = NULL,
+             verified_at  = CASE trust WHEN 'revoked' THEN NULL ELSE verified_at END,
+             verified_via = CASE trust WHEN 'revoked' THEN NULL ELSE verified_via END,
+             updated_at   = datetime('now')
+         WHERE did = ?1",
+        libsql::params![did, label],
+    ).await?;
+    get(conn, did).await?.context("Contact not stored")
+}
+
+/// Record `did` as seen, unverified, unless it is already known.
+pub async fn seen(conn: &Connection, did: &str) -> Result<()> {
+    let root = crypto::did_key_public(did)
+        .with_context(|| format!("Contacts need a did:key, got {did}"))?
+        .multibase();
+    conn.execute("INSERT OR IGNORE INTO contacts (did) VALUES (?1)", libsql::params![did]).await?;
+    add_key(conn, did, &format!("{did}#{root}"), &root).await
+}
+
+/// Add the verification methods of `did`'s DID document to its key history.
+/// The document must carry a proof by the did:key's own key; methods
+/// controlled by another DID are ignored.
+pub async fn add_document_keys(conn: &Connection, did: &str, document: &serde_json::Value) -> Result<usize> {
+    if document["id"] != did {
+        bail!("DID document is for {}, not {did}", document["id"]);
+    }
+    crypto::keys::verify_document(document)?;
+    let mut added = 0;
+    for method in document["verificationMethod"].as_array().into_iter().flatten() {
+        let (Some(id), Some(multibase)) = (method["id"].as_str(), method["publicKeyMultibase"].as_str()) else {
+            continue;
+        };
+        if method["controller"] != did || !id.starts_with(&format!("{did}#")) {
+            continue;
+        }
+        multiformats::decode_key(multibase).with_context(|| format!("Bad key in {id}"))?;
+        add_key(conn, did, id, multibase).await?;
+        added += 1;
+    }
+    Ok(added)
+}
+
+pub async fn get(conn: &Connection, did: &str) -> Result<Option<Contact>> {
+    let mut rows = conn.query(
+        "SELECT did, label, trust, first_seen_at, verified_at, verified_via, revoked_at
+         FROM contacts WHERE did = ?1",
+        libsql::params![did],
+    ).await?;
+    let Some(mut contact) = rows.next().await?.as_ref().and_then(row_to_contact) else {
+        return Ok(None);
+    };
+    contact.keys = keys(conn, did).await?;
+    Ok(Some(contact))
+}
+
+pub async fn list(conn: &Connection, include_revoked: bool) -> Result<Vec<Contact>> {
+    let mut rows = conn.query(
+        "SELECT did, label, trust, first_seen_at, verified_at, verified_via, revoked_at
+         FROM contacts WHERE ?1 OR trust != 'revoked'
+         ORDER BY COALESCE(label, did) COLLATE NOCASE",
+        libsql::params![include_revoked],
+    ).await?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await? {
+        if let Some(contact) = row_to_contact(&row) {
+            out.push(contact);
+        }
+    }
+    for contact in &mut out {
+        contact.keys = keys(conn, &contact.did).await?;
+    }
+    Ok(out)
+}
+
+/// Trust level of `did`, None if it isn't a contact.
+pub async fn trust(conn: &Connection, did: &str) -> Result<Option<TrustLevel>> {
+    let mut rows = conn.query("SELECT trust FROM contacts WHERE did = ?1", libsql::params![did]).await?;
+    Ok(match rows.next().await? {
+        Some(row) => match row.get_value(0)? { Value::Text(s) => TrustLevel::parse(&s), _ => None },
+        None      => None,
+    })
+}
+
+/// Public key (multibase) of a key in `did`'s history.
+pub async fn find_key(conn: &Connection, did: &str, key_id: &str) -> Result<Option<String>> {
+    let mut rows = conn.query(
+        "SELECT public_key_multibase FROM contact_keys WHERE id = ?1 AND did = ?2",
+        libsql::params![key_id, did],
+    ).await?;
+    Ok(match rows.next().await? {
+        Some(row) => match row.get_value(0)? { Value::Text(s) => Some(s), _ => None },
+        None      => None,
+    })
+}
+
+/// `via` is "qr" or "safety_number". Revoked contacts must be re-added first.
+pub async fn mark_verified(conn: &Connection, did: &str, via: &str) -> Result<()> {
+    let changed = conn.execute(
+        "UPDATE contacts SET trust = 'verified', verified_at = datetime('now'), verified_via = ?2,
+                             updated_at = datetime('now')
+         WHERE did = ?1 AND trust != 'revoked'",
+        libsql::params![did, via],
+    ).await?;
+    if changed == 0 {
+        bail!("{did} is not an active contact");
+    }
+    Ok(())
+}
+
+pub async fn revoke(conn: &Connection, did: &str) -> Result<()> {
+    let changed = conn.execute(
+        "UPDATE contacts SET trust = 'revoked', revoked_at = datetime('now'), updated_at = datetime('now')
+         WHERE did = ?1",
+        libsql::params![did],
+    ).await?;
+    if changed == 0 {
+        bail!("{did} is not a contact");
+    }
+    Ok(())
+}
+
+/// Safety number for the pair: each side's 30-digit key fingerprint, in a
+/// fixed order so both devices show the same 12 groups.
+pub fn safety_number(local_did: &str, contact_did: &str) -> Result<String> {
+    let mut halves = [fingerprint(local_did)?, fingerprint(contact_did)?];
+    halves.sort();
+    Ok(halves.join(" "))
+}
+
+/// What this device shows as a QR code for the contact to scan.
+pub fn qr_payload(local_did: &str, safety_number: &str) -> String {
+    format!("{QR_PREFIX}{local_did}:{}", safety_number.replace(' ', ""))
+}
+
+/// Check a QR code scanned from `contact_did`'s device against our own
+/// safety number for the pair.
+pub fn check_qr(scanned: &str, contact_did: &str, safety_number: &str) -> Result<()> {
+    let rest = scanned.strip_prefix(QR_PREFIX).ok_or_else(|| anyhow!("Not a contact verification code"))?;
+    let (did, digits) = rest.rsplit_once(':').ok_or_else(|| anyhow!("Malformed verification code"))?;
+    if did != contact_did {
+        bail!("This code belongs to {did}, not {contact_did}");
+    }
+    if digits != safety_number.replace(' ', "") {
+        bail!("Safety numbers do not match");
+    }
+    Ok(())
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+async fn add_key(conn: &Connection, did: &str, id: &str, multibase: &str) -> Result<()> {
+    conn.execute(
+        "INSERT OR IGNORE INTO contact_keys (id, did, public_key_multibase) VALUES (?1, ?2, ?3)",
+        libsql::params![id, did, multibase],
+    ).await?;
+    Ok(())
+}
+
+async fn keys(conn: &Connection, did: &str) -> Result<Vec<ContactKey>> {
+    let mut rows = conn.query(
+        "SELECT id, public_key_multibase, first_seen_at FROM contact_keys WHERE did = ?1
+         ORDER BY first_seen_at, rowid",
+        libsql::params![did],
+    ).await?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let s = |i| match row.get_value(i).ok() { Some(Value::Text(s)) => s, _ => String::new() };
+        out.push(ContactKey { id: s(0), public_key_multibase: s(1), first_seen_at: s(2) });
+    }
+    Ok(out)
+}
+
+/// 30 digits from a hash of the did:key's public key, in groups of five.
+fn fingerprint(did: &str) -> Result<String> {
+    let key  = crypto::did_key_public(did)?;
+    let hash = Sha256::digest([b"alem-safety-number".as_slice(), &key.to_bytes()].concat());
+    Ok(hash.chunks(5).take(FINGERPRINT_GROUPS).map(|chunk| {
+        let n = chunk.iter().fold(0u64, |n, b| (n << 8) | *b as u64);
+        format!("{:05}", n % 100_000)
+    }).collect::<Vec<_>>().join(" "))
+}
+
+fn row_to_contact(row: &libsql::Row) -> Option<Contact> {
+    let s = |i| match row.get_value(i).ok() { Some(Value::Text(s)) => Some(s), _ => None };
+    Some(Contact {
+        did:           s(0)?,
+        label:         s(1),
+        trust:         TrustLevel::parse(&s(2)?)?,
+        first_seen_at: s(3).unwrap_or_default(),
+        verified_at:   s(4),
+        verified_via:  s(5),
+        revoked_at:    s(6),
+        keys:          Vec::new(),
+    })
+}
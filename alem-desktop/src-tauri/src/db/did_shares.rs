@@ -0,0 +1,185 @@
+// src-tauri/src/db/did_shares.rs
+// Documents shared between DIDs (sharing.rs): the ones this account shared
+// with others, in document_shares, and the ones others shared with this
+// account, in shared_with_me, as sync/shares.rs receives them. Both are
+// copies of the server's share records; the server decides who may open what.
+use anyhow::{anyhow, Result};
+use libsql::Connection;
+use serde_json::Value as Json;
+
+use super::models::{AclRole, Columns, DocumentShare, SharedDocument};
+
+const OUTGOING: &str = "id, doc_id, recipient_did, role, revoked_at, created_at";
+const INCOMING: &str = "id, doc_id, owner_did, role, filename, content_type, metadata, shared_at, received_at";
+
+// ── Shared by this account ───────────────────────────────────────────────────
+
+/// DIDs `doc_id` is shared with, newest first. Revoked shares included.
+pub async fn list(conn: &Connection, doc_id: &str) -> Result<Vec<DocumentShare>> {
+    let mut rows = conn.query(
+        &format!("SELECT {OUTGOING} FROM document_shares WHERE doc_id = ?1 ORDER BY created_at DESC"),
+        libsql::params![doc_id],
+    ).await?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await? {
+        out.push(row_to_share(&row)?);
+    }
+    Ok(out)
+}
+
+pub async fn get(conn: &Connection, id: &str) -> Result<Option<DocumentShare>> {
+    let mut rows = conn.query(
+        &format!("SELECT {OUTGOING} FROM document_shares WHERE id = ?1"),
+        libsql::params![id],
+    ).await?;
+    rows.next().await?.map(|row| row_to_share(&row)).transpose()
+}
+
+/// The share of `doc_id` with `did` still in force, if any.
+pub async fn active(conn: &Connection, doc_id: &str, did: &str) -> Result<Option<DocumentShare>> {
+    let mut rows = conn.query(
+        &format!("SELECT {OUTGOING} FROM document_shares
+                  WHERE doc_id = ?1 AND recipient_did = ?2 AND revoked_at IS NULL"),
+        libsql::params![doc_id, did],
+    ).await?;
+    rows.next().await?.map(|row| row_to_share(&row)).transpose()
+}
+
+/// Keep `share`; its created_at is set here.
+pub async fn insert(conn: &Connection, share: &DocumentShare) -> Result<DocumentShare> {
+    conn.execute(
+        &format!("INSERT INTO document_shares ({OUTGOING}) VALUES (?1, ?2, ?3, ?4, NULL, datetime('now'))"),
+        libsql::params![
+            share.id.as_str(),
+            share.doc_id.as_str(),
+            share.recipient_did.as_str(),
+            share.role.as_str(),
+        ],
+    ).await?;
+    get(conn, &share.id).await?.ok_or_else(|| anyhow!("Share {} not stored", share.id))
+}
+
+/// Mark `id` revoked. Returns false when it already was.
+pub async fn revoke(conn: &Connection, id: &str) -> Result<bool> {
+    let n = conn.execute(
+        "UPDATE document_shares SET revoked_at = datetime('now') WHERE id = ?1 AND revoked_at IS NULL",
+        libsql::params![id],
+    ).await?;
+    Ok(n > 0)
+}
+
+// ── Shared with this account ─────────────────────────────────────────────────
+
+/// Documents shared with `account_id` and not revoked, newest first.
+pub async fn list_incoming(conn: &Connection, account_id: &str) -> Result<Vec<SharedDocument>> {
+    let mut rows = conn.query(
+        &format!("SELECT {INCOMING} FROM shared_with_me
+                  WHERE account_id = ?1 AND revoked_at IS NULL ORDER BY shared_at DESC"),
+        libsql::params![account_id],
+    ).await?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await? {
+        out.push(row_to_shared(&row)?);
+    }
+    Ok(out)
+}
+
+pub async fn get_incoming(conn: &Connection, id: &str) -> Result<Option<SharedDocument>> {
+    let mut rows = conn.query(
+        &format!("SELECT {INCOMING} FROM shared_with_me WHERE id = ?1 AND revoked_at IS NULL"),
+        libsql::params![id],
+    ).await?;
+    rows.next().await?.map(|row| row_to_shared(&row)).transpose()
+}
+
+/// Where the next fetch of `account_id`'s incoming shares starts: the
+/// server's updated_at of the latest one received.
+pub async fn incoming_cursor(conn: &Connection, account_id: &str) -> Result<Option<String>> {
+    let mut rows = conn.query(
+        "SELECT MAX(updated_at) FROM shared_with_me WHERE account_id = ?1",
+        libsql::params![account_id],
+    ).await?;
+    Ok(match rows.next().await? {
+        Some(row) => match row.get_value(0)? { libsql::Value::Text(s) => Some(s), _ => None },
+        None      => None,
+    })
+}
+
+/// Upsert the server's share records for `account_id`. Returns the ones
+/// that are new and in force, to announce.
+pub async fn store_incoming(conn: &Connection, account_id: &str, records: &[Json]) -> Result<Vec<SharedDocument>> {
+    let mut received = Vec::new();
+    for record in records {
+        let text = |key: &str| record[key].as_str().map(str::to_string);
+        let (Some(id), Some(doc_id), Some(updated_at)) = (text("id"), text("doc_id"), text("updated_at")) else {
+            log::warn!("[shares] Skipping an incoming share without id, doc_id or updated_at");
+            continue;
+        };
+        let role = text("role").and_then(|r| AclRole::parse(&r)).unwrap_or(AclRole::Viewer);
+        let existed = {
+            let mut rows = conn.query("SELECT 1 FROM shared_with_me WHERE id = ?1", libsql::params![id.as_str()]).await?;
+            rows.next().await?.is_some()
+        };
+        conn.execute(
+            "INSERT INTO shared_with_me
+               (id, account_id, doc_id, owner_did, role, filename, content_type, metadata, shared_at, updated_at, revoked_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+             ON CONFLICT(id) DO UPDATE SET
+               role = excluded.role, filename = excluded.filename, content_type = excluded.content_type,
+               metadata = excluded.metadata, updated_at = excluded.updated_at, revoked_at = excluded.revoked_at",
+            libsql::params![
+                id.as_str(),
+                account_id,
+                doc_id,
+                text("owner_did"),
+                role.as_str(),
+                text("filename").unwrap_or_default(),
+                text("content_type"),
+                serde_json::to_string(&record["metadata"].as_object().cloned().unwrap_or_default())?,
+                text("created_at").unwrap_or_else(|| updated_at.clone()),
+                updated_at.as_str(),
+                text("revoked_at"),
+            ],
+        ).await?;
+        if !existed && record["revoked_at"].is_null() {
+            if let Some(shared) = get_incoming(conn, &id).await? {
+                received.push(shared);
+            }
+        }
+    }
+    Ok(received)
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+fn role(c: &Columns) -> Result<AclRole> {
+    let role: String = c.required("role")?;
+    AclRole::parse(&role).ok_or_else(|| anyhow!("Unknown role {role}"))
+}
+
+fn row_to_share(row: &libsql::Row) -> Result<DocumentShare> {
+    let c = Columns::new(row);
+    Ok(DocumentShare {
+        id:            c.required("id")?,
+        doc_id:        c.required("doc_id")?,
+        recipient_did: c.required("recipient_did")?,
+        role:          role(&c)?,
+        revoked_at:    c.str("revoked_at"),
+        created_at:    c.str("created_at").unwrap_or_default(),
+    })
+}
+
+fn row_to_shared(row: &libsql::Row) -> Result<SharedDocument> {
+    let c = Columns::new(row);
+    Ok(SharedDocument {
+        id:           c.required("id")?,
+        doc_id:       c.required("doc_id")?,
+        owner_did:    c.str("owner_did"),
+        role:         role(&c)?,
+        filename:     c.str("filename").unwrap_or_default(),
+        content_type: c.str("content_type"),
+        metadata:     serde_json::from_str(&c.str("metadata").unwrap_or_default()).unwrap_or_default(),
+        shared_at:    c.str("shared_at").unwrap_or_default(),
+        received_at:  c.str("received_at").unwrap_or_default(),
+    })
+}
@@ -0,0 +1,227 @@
+// src-tauri/src/db/encryption.rs
+// Encryption at rest for alem.db (libsql `encryption` feature, AES-256-CBC via
+// SQLite3MultipleCiphers).
+//
+// Not SQLCipher's format: libsql's EncryptionConfig offers only the
+// aes256cbc cipher, so an encrypted alem.db opens with SQLite3MultipleCiphers
+// (cipher=aes256cbc) and the key, not with sqlcipher tools.
+//
+// The key is kept in the OS keychain: either a random secret generated for this
+// install, or one derived from a passphrase the user picked (PBKDF2-HMAC-SHA256
+// with a fixed salt, so the same passphrase opens a copied database file
+// elsewhere). The passphrase itself is never stored; older versions stored it,
+// and open_local() re-keys those databases to the derived key. Every
+// connection a libsql Database hands out applies the key it was built with, so
+// the key cannot change under a running app. Instead the passphrase commands
+// stage the new key, and open_local() applies it with PRAGMA rekey on the next
+// launch before anything else connects. The same path encrypts an existing
+// plaintext database in place.
+//
+// Building with the `encryption` feature compiles SQLite3MultipleCiphers, which
+// needs cmake (see the desktop README).
+use anyhow::{Context, Result};
+use hmac::{Hmac, Mac};
+use libsql::{Builder, Cipher, Connection, Database, EncryptionConfig};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use super::models::{DatabaseEncryption, KeySource};
+use crate::keychain;
+
+const CURRENT_KEY: &str = "db_key";
+const PENDING_KEY: &str = "db_key_pending";
+
+pub const MIN_PASSPHRASE_LEN: usize = 8;
+
+/// PBKDF2 parameters for passphrase keys. Changing them changes every
+/// passphrase's key.
+const KDF_SALT:       &[u8] = b"alem-db-passphrase";
+const KDF_ITERATIONS: u32   = 600_000;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DbKey {
+    pub secret: String,
+    pub source: KeySource,
+    /// For passphrase keys, whether `secret` is the derived key (always, for
+    /// keys stored by this version) rather than the passphrase itself.
+    #[serde(default)]
+    pub derived: bool,
+}
+
+impl DbKey {
+    /// 256-bit random secret, hex encoded.
+    pub fn generate() -> Self {
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        Self { secret: crate::storage::cas::hex(&bytes), source: KeySource::Keychain, derived: false }
+    }
+
+    pub fn passphrase(passphrase: &str) -> Result<Self> {
+        if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+            anyhow::bail!("Passphrase must be at least {MIN_PASSPHRASE_LEN} characters");
+        }
+        Ok(Self::derive(passphrase))
+    }
+
+    /// Whether this is the key of `passphrase`.
+    pub fn is_passphrase(&self, passphrase: &str) -> bool {
+        let secret = if self.derived { Self::derive(passphrase).secret } else { passphrase.to_string() };
+        self.source == KeySource::Passphrase && secret == self.secret
+    }
+
+    fn derive(passphrase: &str) -> Self {
+        let key = pbkdf2_sha256(passphrase.as_bytes(), KDF_SALT, KDF_ITERATIONS);
+        Self { secret: crate::storage::cas::hex(&key), source: KeySource::Passphrase, derived: true }
+    }
+
+    fn config(&self) -> EncryptionConfig {
+        EncryptionConfig::new(Cipher::Aes256Cbc, self.secret.clone().into())
+    }
+}
+
+/// Open the local database with the keychain key, applying a staged key
+/// change first. Without any key this is a plain local database, as before.
+pub async fn open_local(path: &str) -> Result<Database> {
+    let current = load(CURRENT_KEY)?;
+    let pending = match load(PENDING_KEY)? {
+        Some(next) => Some(next),
+        // A passphrase stored by an older version: swap it for its derived key
+        None => current.as_ref()
+            .filter(|k| k.source == KeySource::Passphrase && !k.derived)
+            .map(|k| DbKey::derive(&k.secret)),
+    };
+    let Some(next) = pending else {
+        return try_open(path, current.as_ref()).await;
+    };
+
+    match try_open(path, current.as_ref()).await {
+        Ok(db) => {
+            let conn = db.connect()?;
+            rekey(&conn, &next).await?;
+        }
+        // A previous launch re-keyed the file but died before updating the keychain
+        Err(_) if try_open(path, Some(&next)).await.is_ok() => {}
+        Err(e) => return Err(e.context("The database key in the keychain does not open the database")),
+    }
+
+    store(CURRENT_KEY, &next)?;
+    keychain::delete(PENDING_KEY)?;
+    log::info!("[db] Database re-keyed ({:?} key)", next.source);
+    try_open(path, Some(&next)).await
+}
+
+/// Open another copy of the database (backup snapshot, restore candidate)
+/// with the current key, encrypting it in place first if it is plaintext.
+pub async fn open_copy(path: &str) -> Result<Database> {
+    let Some(key) = load(CURRENT_KEY)? else {
+        return try_open(path, None).await
+            .context("Database copy is encrypted but this install has no database key");
+    };
+    if let Ok(db) = try_open(path, Some(&key)).await {
+        return Ok(db);
+    }
+    let plain = try_open(path, None).await
+        .context("Database copy is encrypted with a different key")?;
+    rekey(&plain.connect()?, &key).await?;
+    drop(plain);
+    try_open(path, Some(&key)).await
+}
+
+pub fn status() -> Result<DatabaseEncryption> {
+    let current = load(CURRENT_KEY)?;
+    Ok(DatabaseEncryption {
+        encrypted:  current.is_some(),
+        key_source: current.map(|k| k.source),
+        pending:    load(PENDING_KEY)?.map(|k| k.source),
+    })
+}
+
+pub fn current_key() -> Result<Option<DbKey>> {
+    load(CURRENT_KEY)
+}
+
+/// Stage `key` to be applied on the next launch (replaces any staged key).
+pub fn stage(key: &DbKey) -> Result<()> {
+    store(PENDING_KEY, key)
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+async fn try_open(path: &str, key: Option<&DbKey>) -> Result<Database> {
+    let mut builder = Builder::new_local(path);
+    if let Some(key) = key {
+        builder = builder.encryption_config(key.config());
+    }
+    let db = builder.build().await?;
+
+    // A wrong key only shows up on first read ("file is not a database")
+    let conn = db.connect()?;
+    conn.query("SELECT count(*) FROM sqlite_master", ()).await
+        .context("Could not read the database with the configured key")?;
+    Ok(db)
+}
+
+async fn rekey(conn: &Connection, key: &DbKey) -> Result<()> {
+    // SQLite3MultipleCiphers can't rekey a WAL database, and a plaintext
+    // connection would otherwise encrypt with its default cipher (ChaCha20)
+    // rather than the AES-256-CBC the builder opens with.
+    drain(conn, "PRAGMA journal_mode = DELETE").await?;
+    drain(conn, "PRAGMA cipher = 'aes256cbc'").await?;
+    drain(conn, &format!("PRAGMA rekey = '{}'", key.secret.replace('\'', "''"))).await
+        .context("Re-keying the database failed")?;
+    Ok(())
+}
+
+/// Run a PRAGMA that may answer with rows.
+async fn drain(conn: &Connection, sql: &str) -> Result<()> {
+    let mut rows = conn.query(sql, ()).await?;
+    while rows.next().await?.is_some() {}
+    Ok(())
+}
+
+fn load(name: &str) -> Result<Option<DbKey>> {
+    match keychain::get(name)? {
+        Some(json) => Ok(Some(serde_json::from_str(&json)?)),
+        None       => Ok(None),
+    }
+}
+
+fn store(name: &str, key: &DbKey) -> Result<()> {
+    keychain::set(name, &serde_json::to_string(key)?)
+}
+
+/// PBKDF2-HMAC-SHA256 (RFC 8018) with a 32-byte output, a single block.
+fn pbkdf2_sha256(password: &[u8], salt: &[u8], iterations: u32) -> [u8; 32] {
+    let prf = <Hmac<Sha256> as Mac>::new_from_slice(password).expect("HMAC takes any key length");
+    let mut u: [u8; 32] = {
+        let mut mac = prf.clone();
+        mac.update(salt);
+        mac.update(&1u32.to_be_bytes());
+        mac.finalize().into_bytes().into()
+    };
+    let mut out = u;
+    for _ in 1..iterations {
+        let mut mac = prf.clone();
+        mac.update(&u);
+        u = mac.finalize().into_bytes().into();
+        out.iter_mut().zip(u).for_each(|(o, b)| *o ^= b);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pbkdf2_matches_known_vectors() {
+        for (iterations, expected) in [
+            (1,    "120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b"),
+            (2,    "ae4d0c95af6b46d32d0adff928f06dd02a303f8ef3c251dfd6e2d85a95474c43"),
+            (4096, "c5e478d59288c841aa530db6845c4c8d962893a001ce4e11a4963873aa98134a"),
+        ] {
+            assert_eq!(crate::storage::cas::hex(&pbkdf2_sha256(b"password", b"salt", iterations)), expected);
+        }
+    }
+}
@@ -0,0 +1,82 @@
+// src-tauri/src/db/fediverse.rs
+// Documents published as posts to the fediverse (activitypub.rs). The post
+// itself lives on Pleroma; this keeps which status a document became, so the
+// document can show where it was shared.
+use anyhow::{anyhow, Result};
+use libsql::Connection;
+
+use super::models::{Columns, FediversePost, PostVisibility};
+
+impl PostVisibility {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PostVisibility::Public   => "public",
+            PostVisibility::Unlisted => "unlisted",
+            PostVisibility::Private  => "private",
+            PostVisibility::Direct   => "direct",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        [PostVisibility::Public, PostVisibility::Unlisted, PostVisibility::Private, PostVisibility::Direct]
+            .into_iter()
+            .find(|v| v.as_str() == s)
+    }
+}
+
+const COLUMNS: &str = "id, doc_id, status_id, url, visibility, pleroma_account_id, author_did, link, created_at";
+
+/// Posts of `doc_id`, newest first.
+pub async fn list(conn: &Connection, doc_id: &str) -> Result<Vec<FediversePost>> {
+    let mut rows = conn.query(
+        &format!("SELECT {COLUMNS} FROM fediverse_posts WHERE doc_id = ?1 ORDER BY created_at DESC"),
+        libsql::params![doc_id],
+    ).await?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await? {
+        out.push(row_to_post(&row)?);
+    }
+    Ok(out)
+}
+
+/// Keep `post`; its created_at is set here.
+pub async fn record(conn: &Connection, post: &FediversePost) -> Result<FediversePost> {
+    conn.execute(
+        &format!("INSERT INTO fediverse_posts ({COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, datetime('now'))"),
+        libsql::params![
+            post.id.as_str(),
+            post.doc_id.as_str(),
+            post.status_id.as_str(),
+            post.url.clone(),
+            post.visibility.as_str(),
+            post.pleroma_account_id.as_str(),
+            post.author_did.clone(),
+            post.link.clone(),
+        ],
+    ).await?;
+    let mut rows = conn.query(
+        &format!("SELECT {COLUMNS} FROM fediverse_posts WHERE id = ?1"),
+        libsql::params![post.id.as_str()],
+    ).await?;
+    let row = rows.next().await?.ok_or_else(|| anyhow!("Post {} not stored", post.id))?;
+    row_to_post(&row)
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+fn row_to_post(row: &libsql::Row) -> Result<FediversePost> {
+    let c = Columns::new(row);
+    let visibility: String = c.required("visibility")?;
+    Ok(FediversePost {
+        id:                 c.required("id")?,
+        doc_id:             c.required("doc_id")?,
+        status_id:          c.required("status_id")?,
+        url:                c.str("url"),
+        visibility:         PostVisibility::parse(&visibility)
+            .ok_or_else(|| anyhow!("Unknown visibility {visibility}"))?,
+        pleroma_account_id: c.required("pleroma_account_id")?,
+        author_did:         c.str("author_did"),
+        link:               c.str("link"),
+        created_at:         c.str("created_at").unwrap_or_default(),
+    })
+}
@@ -0,0 +1,95 @@
+// src-tauri/src/db/history.rs
+// The library as it was at a past time. Triggers keep the history (schema
+// migration 32):
+// - document_versions holds each superseded state of a document. Deletion
+//   tombstones and trashing count as changes.
+// - document_tag_history holds when each tag was on each document.
+//
+// A document's state at time T is its first version replaced after T. With no
+// such version, it is the current row. Documents created after T, and ones
+// that were deleted at T, are left out.
+use anyhow::{bail, Result};
+use libsql::{Connection, Value};
+
+use super::{
+    models::{Columns, HistoricalDocument},
+    tags,
+};
+
+/// Documents of `tenant_id` as they were at `at` (anything SQLite's
+/// datetime() reads), oldest first.
+pub async fn as_of(conn: &Connection, tenant_id: &str, at: &str) -> Result<Vec<HistoricalDocument>> {
+    let at = normalize(conn, at).await?;
+    // v is the first version replaced after ?2: the state the document had then
+    let pick = |field: &str| format!("CASE WHEN v.id IS NULL THEN d.{field} ELSE v.{field} END AS {field}");
+    let mut rows = conn.query(
+        &format!(
+            "SELECT d.id AS id, d.created_at AS created_at, d.status AS status,
+                    d.trashed_at AS current_trashed_at, v.id AS version_id,
+                    {}, {}, {}, {}, {}, {}, {},
+                    CASE WHEN v.id IS NULL THEN d.status = 'deleted' ELSE v.deleted END AS deleted,
+                    (SELECT json_group_array(name) FROM (
+                         SELECT t.name FROM document_tag_history h JOIN tags t ON t.id = h.tag_id
+                         WHERE h.document_id = d.id AND h.added_at <= ?2
+                           AND (h.removed_at IS NULL OR h.removed_at > ?2)
+                         GROUP BY t.id ORDER BY t.name COLLATE NOCASE
+                     )) AS tags,
+                    {} AS current_tags
+             FROM documents d
+             LEFT JOIN document_versions v ON v.id = (
+                 SELECT id FROM document_versions
+                 WHERE document_id = d.id AND replaced_at > ?2
+                 ORDER BY replaced_at, id LIMIT 1
+             )
+             WHERE d.tenant_id = ?1 AND datetime(d.created_at) <= ?2
+             ORDER BY d.created_at, d.id",
+            pick("filename"), pick("content_type"), pick("file_size"), pick("content_hash"),
+            pick("collection_id"), pick("metadata"), pick("trashed_at"),
+            tags::json_expr("d.id"),
+        ),
+        libsql::params![tenant_id, at.as_str()],
+    ).await?;
+
+    let mut docs = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let c = Columns::new(&row);
+        if c.bool("deleted") {
+            continue;
+        }
+        let tags: Vec<String> = c.str("tags").and_then(|t| serde_json::from_str(&t).ok()).unwrap_or_default();
+        let current_tags: Vec<String> = c.str("current_tags").and_then(|t| serde_json::from_str(&t).ok()).unwrap_or_default();
+        let current_state = if c.str("status").as_deref() == Some("deleted") {
+            "deleted"
+        } else if c.str("current_trashed_at").is_some() {
+            "trashed"
+        } else {
+            "live"
+        };
+        docs.push(HistoricalDocument {
+            id:            c.required("id")?,
+            filename:      c.str("filename").unwrap_or_default(),
+            content_type:  c.str("content_type"),
+            file_size:     c.i64("file_size"),
+            content_hash:  c.str("content_hash"),
+            collection_id: c.str("collection_id"),
+            metadata:      c.str("metadata").and_then(|m| serde_json::from_str(&m).ok()).unwrap_or_else(|| serde_json::json!({})),
+            changed_since: c.i64("version_id").is_some() || tags != current_tags,
+            tags,
+            trashed_at:    c.str("trashed_at"),
+            created_at:    c.str("created_at").unwrap_or_default(),
+            current_state: current_state.to_string(),
+        });
+    }
+    Ok(docs)
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+/// `at` in the datetime() form the history columns use.
+async fn normalize(conn: &Connection, at: &str) -> Result<String> {
+    let mut rows = conn.query("SELECT datetime(?1)", libsql::params![at]).await?;
+    match rows.next().await?.map(|row| row.get_value(0)) {
+        Some(Ok(Value::Text(s))) => Ok(s),
+        _ => bail!("Invalid timestamp: {at}"),
+    }
+}
@@ -0,0 +1,33 @@
+// src-tauri/src/db/identity.rs
+use anyhow::Result;
+use libsql::{Connection, Value};
+
+/// (user_id, tenant_id) of the local identity, with the same fallbacks
+/// create_document has always used before login.
+pub async fn current(conn: &Connection) -> Result<(String, String)> {
+    let mut rows = conn.query(
+        "SELECT COALESCE(user_id,'anonymous'), COALESCE(tenant_id,'default')
+         FROM local_identity WHERE id='singleton'",
+        (),
+    ).await?;
+
+    Ok(if let Some(row) = rows.next().await? {
+        let uid = match row.get_value(0).ok() { Some(Value::Text(s)) => s, _ => "anonymous".into() };
+        let tid = match row.get_value(1).ok() { Some(Value::Text(s)) => s, _ => "default".into()   };
+        (uid, tid)
+    } else {
+        ("anonymous".into(), "default".into())
+    })
+}
+
+/// The local DID, once one is stored.
+pub async fn did(conn: &Connection) -> Result<Option<String>> {
+    let mut rows = conn.query(
+        "SELECT did FROM local_identity WHERE id = 'singleton' AND did IS NOT NULL",
+        (),
+    ).await?;
+    Ok(match rows.next().await?.and_then(|r| r.get_value(0).ok()) {
+        Some(Value::Text(did)) => Some(did),
+        _ => None,
+    })
+}
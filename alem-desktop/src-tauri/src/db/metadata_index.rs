@@ -0,0 +1,58 @@
+// src-tauri/src/db/metadata_index.rs
+// Metadata keys promoted to document_metadata_kv for fast filtering.
+//
+// Filtering on a metadata key otherwise means json_each over every row.
+// Promoted keys have their scalar values copied into an indexed table by
+// triggers on documents (see migration 19), and DocumentFilter turns a filter
+// on such a key into an index lookup. Promoting a key indexes the documents
+// that already have it.
+use anyhow::{bail, Result};
+use libsql::{Connection, Value};
+
+/// More keys make every metadata write slower.
+pub const MAX_KEYS: usize = 32;
+
+/// How a JSON scalar from json_each (aliased `j`) is stored and compared.
+pub const NORMALIZED_VALUE: &str =
+    "CASE j.type WHEN 'true' THEN 'true' WHEN 'false' THEN 'false' ELSE CAST(j.value AS TEXT) END";
+
+pub async fn keys(conn: &Connection) -> Result<Vec<String>> {
+    let mut rows = conn.query("SELECT key FROM metadata_index_keys ORDER BY key", ()).await?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await? {
+        if let Ok(Value::Text(key)) = row.get_value(0) {
+            out.push(key);
+        }
+    }
+    Ok(out)
+}
+
+/// Replace the promoted keys, indexing new ones and dropping the values of
+/// removed ones. Run inside a transaction.
+pub async fn set_keys(conn: &Connection, keys: &[String]) -> Result<()> {
+    if keys.len() > MAX_KEYS {
+        bail!("At most {MAX_KEYS} metadata keys can be indexed");
+    }
+    if keys.iter().any(|k| k.trim().is_empty()) {
+        bail!("Metadata keys must not be empty");
+    }
+    let current = self::keys(conn).await?;
+
+    for key in current.iter().filter(|k| !keys.contains(k)) {
+        conn.execute("DELETE FROM metadata_index_keys WHERE key = ?1", libsql::params![key.as_str()]).await?;
+        conn.execute("DELETE FROM document_metadata_kv WHERE key = ?1", libsql::params![key.as_str()]).await?;
+    }
+    for key in keys.iter().filter(|k| !current.contains(k)) {
+        conn.execute("INSERT OR IGNORE INTO metadata_index_keys (key) VALUES (?1)", libsql::params![key.as_str()]).await?;
+        conn.execute(
+            &format!(
+                "INSERT OR IGNORE INTO document_metadata_kv (key, value, document_id)
+                 SELECT j.key, {NORMALIZED_VALUE}, d.id
+                 FROM documents d, json_each(CASE WHEN json_valid(d.metadata) THEN d.metadata ELSE '{{}}' END) j
+                 WHERE j.key = ?1 AND j.type NOT IN ('object', 'array', 'null')"
+            ),
+            libsql::params![key.as_str()],
+        ).await?;
+    }
+    Ok(())
+}
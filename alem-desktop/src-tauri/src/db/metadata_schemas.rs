@@ -0,0 +1,247 @@
+// src-tauri/src/db/metadata_schemas.rs
+// Per-tenant schemas for document metadata.
+//
+// Metadata is free-form JSON unless the active tenant has a schema for the
+// document's content type. The most specific schema applies: "image/png",
+// then "image/*", then "*". Schemas are JSON Schema, limited to the keywords
+// in KEYWORDS; anything else is rejected when the schema is defined, so a
+// schema never silently checks less than it says.
+use anyhow::{bail, Result};
+use libsql::{Connection, Value};
+use serde_json::{Map, Value as Json};
+
+use super::models::MetadataSchema;
+
+/// Supported JSON Schema keywords. Annotations are accepted and ignored.
+const KEYWORDS: &[&str] = &[
+    "type", "enum", "const", "required", "properties", "additionalProperties",
+    "items", "minItems", "maxItems", "minLength", "maxLength", "minimum", "maximum",
+    "$schema", "title", "description",
+];
+const TYPES: &[&str] = &["object", "array", "string", "number", "integer", "boolean", "null"];
+
+pub async fn list(conn: &Connection, tenant_id: &str) -> Result<Vec<MetadataSchema>> {
+    let mut rows = conn.query(
+        "SELECT content_type, schema, updated_at FROM metadata_schemas
+         WHERE tenant_id = ?1 ORDER BY content_type",
+        libsql::params![tenant_id],
+    ).await?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let s = |i| match row.get_value(i).ok() { Some(Value::Text(s)) => s, _ => String::new() };
+        out.push(MetadataSchema {
+            content_type: s(0),
+            schema:       serde_json::from_str(&s(1)).unwrap_or(Json::Null),
+            updated_at:   s(2),
+        });
+    }
+    Ok(out)
+}
+
+/// Define (or replace) the schema for `content_type`.
+pub async fn set(conn: &Connection, tenant_id: &str, content_type: &str, schema: &Json) -> Result<()> {
+    check_pattern(content_type)?;
+    check_schema(schema, "")?;
+    conn.execute(
+        "INSERT INTO metadata_schemas (tenant_id, content_type, schema) VALUES (?1, ?2, ?3)
+         ON CONFLICT(tenant_id, content_type) DO UPDATE SET
+             schema     = excluded.schema,
+             updated_at = datetime('now')",
+        libsql::params![tenant_id, content_type, schema.to_string()],
+    ).await?;
+    Ok(())
+}
+
+pub async fn delete(conn: &Connection, tenant_id: &str, content_type: &str) -> Result<bool> {
+    let n = conn.execute(
+        "DELETE FROM metadata_schemas WHERE tenant_id = ?1 AND content_type = ?2",
+        libsql::params![tenant_id, content_type],
+    ).await?;
+    Ok(n > 0)
+}
+
+/// Check `metadata` against the schema that applies to `content_type`, if
+/// any. The error lists every violation.
+pub async fn validate(conn: &Connection, tenant_id: &str, content_type: &str, metadata: &Json) -> Result<()> {
+    let Some(schema) = schema_for(conn, tenant_id, content_type).await? else { return Ok(()) };
+    let mut errors = Vec::new();
+    violations(&schema, metadata, "", &mut errors);
+    if !errors.is_empty() {
+        bail!("Metadata does not match the schema for {content_type}: {}", errors.join("; "));
+    }
+    Ok(())
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+async fn schema_for(conn: &Connection, tenant_id: &str, content_type: &str) -> Result<Option<Json>> {
+    let family = format!("{}/*", content_type.split('/').next().unwrap_or_default());
+    let mut rows = conn.query(
+        "SELECT schema FROM metadata_schemas
+         WHERE tenant_id = ?1 AND content_type IN (?2, ?3, '*')
+         ORDER BY CASE content_type WHEN ?2 THEN 0 WHEN ?3 THEN 1 ELSE 2 END
+         LIMIT 1",
+        libsql::params![tenant_id, content_type, family],
+    ).await?;
+    Ok(match rows.next().await? {
+        Some(row) => match row.get_value(0)? {
+            Value::Text(s) => Some(serde_json::from_str(&s)?),
+            _              => None,
+        },
+        None => None,
+    })
+}
+
+fn check_pattern(content_type: &str) -> Result<()> {
+    let valid = content_type == "*"
+        || content_type.split_once('/').is_some_and(|(kind, sub)| {
+            !kind.is_empty() && kind != "*" && !sub.is_empty() && !kind.contains('*') && (sub == "*" || !sub.contains('*'))
+        });
+    if !valid {
+        bail!("Expected a content type like \"image/png\", \"image/*\" or \"*\", got {content_type:?}");
+    }
+    Ok(())
+}
+
+/// Reject schemas using keywords or forms this validator doesn't implement.
+fn check_schema(schema: &Json, path: &str) -> Result<()> {
+    let Some(obj) = schema.as_object() else {
+        bail!("Schema at {} must be an object", display(path));
+    };
+    for (key, value) in obj {
+        if !KEYWORDS.contains(&key.as_str()) {
+            bail!("Unsupported schema keyword {key:?} at {}", display(path));
+        }
+        let ok = match key.as_str() {
+            "type" => match value {
+                Json::String(t) => TYPES.contains(&t.as_str()),
+                Json::Array(ts) => ts.iter().all(|t| t.as_str().is_some_and(|t| TYPES.contains(&t))),
+                _               => false,
+            },
+            "enum"     => value.is_array(),
+            "required" => value.as_array().is_some_and(|r| r.iter().all(Json::is_string)),
+            "properties" => {
+                let Some(props) = value.as_object() else { bail!("\"properties\" at {} must be an object", display(path)) };
+                for (name, sub) in props {
+                    check_schema(sub, &format!("{path}.{name}"))?;
+                }
+                true
+            }
+            "additionalProperties" => {
+                if !value.is_boolean() { check_schema(value, &format!("{path}.*"))?; }
+                true
+            }
+            "items" => {
+                check_schema(value, &format!("{path}[]"))?;
+                true
+            }
+            "minItems" | "maxItems" | "minLength" | "maxLength" => value.is_u64(),
+            "minimum" | "maximum" => value.is_number(),
+            "title" | "description" | "$schema" => value.is_string(),
+            _ => true,
+        };
+        if !ok {
+            bail!("Invalid value for {key:?} at {}", display(path));
+        }
+    }
+    Ok(())
+}
+
+fn violations(schema: &Json, value: &Json, path: &str, out: &mut Vec<String>) {
+    let Some(schema) = schema.as_object() else { return };
+    let at = display(path);
+
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Json::Array(ts) => ts.iter().filter_map(Json::as_str).collect(),
+            t               => t.as_str().into_iter().collect(),
+        };
+        if !types.iter().any(|t| is_type(value, t)) {
+            out.push(format!("{at} must be {}", types.join(" or ")));
+            return;
+        }
+    }
+    if let Some(options) = schema.get("enum").and_then(Json::as_array) {
+        if !options.contains(value) {
+            out.push(format!("{at} must be one of {}", Json::Array(options.clone())));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if value != expected {
+            out.push(format!("{at} must be {expected}"));
+        }
+    }
+
+    match value {
+        Json::Object(obj) => object_violations(schema, obj, path, out),
+        Json::Array(items) => {
+            let len = items.len() as u64;
+            if let Some(min) = schema.get("minItems").and_then(Json::as_u64).filter(|&min| len < min) {
+                out.push(format!("{at} needs at least {min} items"));
+            }
+            if let Some(max) = schema.get("maxItems").and_then(Json::as_u64).filter(|&max| len > max) {
+                out.push(format!("{at} allows at most {max} items"));
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    violations(item_schema, item, &format!("{path}[{i}]"), out);
+                }
+            }
+        }
+        Json::String(s) => {
+            let len = s.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(Json::as_u64).filter(|&min| len < min) {
+                out.push(format!("{at} must be at least {min} characters"));
+            }
+            if let Some(max) = schema.get("maxLength").and_then(Json::as_u64).filter(|&max| len > max) {
+                out.push(format!("{at} must be at most {max} characters"));
+            }
+        }
+        Json::Number(n) => {
+            let n = n.as_f64().unwrap_or_default();
+            if let Some(min) = schema.get("minimum").and_then(Json::as_f64).filter(|&min| n < min) {
+                out.push(format!("{at} must be at least {min}"));
+            }
+            if let Some(max) = schema.get("maximum").and_then(Json::as_f64).filter(|&max| n > max) {
+                out.push(format!("{at} must be at most {max}"));
+            }
+        }
+        _ => {}
+    }
+}
+
+fn object_violations(schema: &Map<String, Json>, obj: &Map<String, Json>, path: &str, out: &mut Vec<String>) {
+    for name in schema.get("required").and_then(Json::as_array).into_iter().flatten().filter_map(Json::as_str) {
+        if !obj.contains_key(name) {
+            out.push(format!("{}.{name} is required", display(path)));
+        }
+    }
+    let props = schema.get("properties").and_then(Json::as_object);
+    for (name, value) in obj {
+        let child = format!("{path}.{name}");
+        match (props.and_then(|p| p.get(name)), schema.get("additionalProperties")) {
+            (Some(sub), _)                       => violations(sub, value, &child, out),
+            (None, Some(Json::Bool(false)))      => out.push(format!("{} is not allowed", display(&child))),
+            (None, Some(sub)) if sub.is_object() => violations(sub, value, &child, out),
+            _                                    => {}
+        }
+    }
+}
+
+fn is_type(value: &Json, t: &str) -> bool {
+    match t {
+        "object"  => value.is_object(),
+        "array"   => value.is_array(),
+        "string"  => value.is_string(),
+        "number"  => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0),
+        "boolean" => value.is_boolean(),
+        "null"    => value.is_null(),
+        _         => false,
+    }
+}
+
+/// "metadata" for the root, "metadata.camera.model" below it.
+fn display(path: &str) -> String {
+    format!("metadata{path}")
+}
@@ -0,0 +1,149 @@
+// src-tauri/src/db/mirrors.rs
+// Folder mirrors and the files they have written (schema migration 34). The
+// syncing itself is mirror.rs.
+use anyhow::Result;
+use libsql::Connection;
+use std::collections::HashMap;
+
+use super::models::{Columns, FolderMirror};
+
+/// The file a document has in its mirror folder, as the last pass saw it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MirrorFile {
+    pub name:         String,
+    pub content_hash: String,
+    pub size:         i64,
+    pub mtime_ms:     i64,
+}
+
+const MIRROR_COLUMNS: &str =
+    "m.collection_id, m.path, m.propagate_deletes, m.created_at, m.last_synced_at, m.last_error";
+
+/// Mirror `collection_id` to `path`, or change the settings of its mirror.
+/// Moving a mirror to another folder starts it over there.
+pub async fn set(conn: &Connection, collection_id: &str, path: &str, propagate_deletes: bool) -> Result<()> {
+    let moved = conn.execute(
+        "DELETE FROM folder_mirrors WHERE collection_id = ?1 AND path != ?2",
+        libsql::params![collection_id, path],
+    ).await?;
+    if moved > 0 {
+        log::info!("[mirror] {collection_id} moves to {path}");
+    }
+    conn.execute(
+        "INSERT INTO folder_mirrors (collection_id, path, propagate_deletes) VALUES (?1, ?2, ?3)
+         ON CONFLICT(collection_id) DO UPDATE SET propagate_deletes = excluded.propagate_deletes",
+        libsql::params![collection_id, path, propagate_deletes as i64],
+    ).await?;
+    Ok(())
+}
+
+/// Returns false when `collection_id` wasn't mirrored. The folder is left as it is.
+pub async fn remove(conn: &Connection, collection_id: &str) -> Result<bool> {
+    let n = conn.execute(
+        "DELETE FROM folder_mirrors WHERE collection_id = ?1",
+        libsql::params![collection_id],
+    ).await?;
+    Ok(n > 0)
+}
+
+pub async fn get(conn: &Connection, collection_id: &str) -> Result<Option<FolderMirror>> {
+    let mut rows = conn.query(
+        &format!("SELECT {MIRROR_COLUMNS} FROM folder_mirrors m WHERE m.collection_id = ?1"),
+        libsql::params![collection_id],
+    ).await?;
+    match rows.next().await? {
+        Some(row) => Ok(Some(row_to_mirror(&row)?)),
+        None      => Ok(None),
+    }
+}
+
+pub async fn get_by_path(conn: &Connection, path: &str) -> Result<Option<FolderMirror>> {
+    let mut rows = conn.query(
+        &format!("SELECT {MIRROR_COLUMNS} FROM folder_mirrors m WHERE m.path = ?1"),
+        libsql::params![path],
+    ).await?;
+    match rows.next().await? {
+        Some(row) => Ok(Some(row_to_mirror(&row)?)),
+        None      => Ok(None),
+    }
+}
+
+/// Mirrors of `tenant_id`'s collections.
+pub async fn list(conn: &Connection, tenant_id: &str) -> Result<Vec<FolderMirror>> {
+    let mut rows = conn.query(
+        &format!(
+            "SELECT {MIRROR_COLUMNS} FROM folder_mirrors m
+             JOIN collections c ON c.id = m.collection_id
+             WHERE c.tenant_id = ?1
+             ORDER BY m.path"
+        ),
+        libsql::params![tenant_id],
+    ).await?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await? {
+        out.push(row_to_mirror(&row)?);
+    }
+    Ok(out)
+}
+
+/// Record the outcome of a pass.
+pub async fn finished(conn: &Connection, collection_id: &str, error: Option<&str>) -> Result<()> {
+    conn.execute(
+        "UPDATE folder_mirrors
+         SET last_synced_at = CASE WHEN ?2 IS NULL THEN datetime('now') ELSE last_synced_at END,
+             last_error = ?2
+         WHERE collection_id = ?1",
+        libsql::params![collection_id, error],
+    ).await?;
+    Ok(())
+}
+
+/// Document id → its file, for every file the mirror has written or taken in.
+pub async fn files(conn: &Connection, collection_id: &str) -> Result<HashMap<String, MirrorFile>> {
+    let mut rows = conn.query(
+        "SELECT document_id, name, content_hash, size, mtime_ms FROM mirror_files WHERE collection_id = ?1",
+        libsql::params![collection_id],
+    ).await?;
+    let mut out = HashMap::new();
+    while let Some(row) = rows.next().await? {
+        let c = Columns::new(&row);
+        out.insert(c.required("document_id")?, MirrorFile {
+            name:         c.required("name")?,
+            content_hash: c.str("content_hash").unwrap_or_default(),
+            size:         c.i64("size").unwrap_or(0),
+            mtime_ms:     c.i64("mtime_ms").unwrap_or(0),
+        });
+    }
+    Ok(out)
+}
+
+pub async fn remember(conn: &Connection, collection_id: &str, doc_id: &str, file: &MirrorFile) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO mirror_files (collection_id, document_id, name, content_hash, size, mtime_ms)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        libsql::params![collection_id, doc_id, file.name.as_str(), file.content_hash.as_str(), file.size, file.mtime_ms],
+    ).await?;
+    Ok(())
+}
+
+pub async fn forget(conn: &Connection, collection_id: &str, doc_id: &str) -> Result<()> {
+    conn.execute(
+        "DELETE FROM mirror_files WHERE collection_id = ?1 AND document_id = ?2",
+        libsql::params![collection_id, doc_id],
+    ).await?;
+    Ok(())
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+fn row_to_mirror(row: &libsql::Row) -> Result<FolderMirror> {
+    let c = Columns::new(row);
+    Ok(FolderMirror {
+        collection_id:     c.required("collection_id")?,
+        path:              c.required("path")?,
+        propagate_deletes: c.bool("propagate_deletes"),
+        created_at:        c.str("created_at").unwrap_or_default(),
+        last_synced_at:    c.str("last_synced_at"),
+        last_error:        c.str("last_error"),
+    })
+}
@@ -1,19 +1,62 @@
 // src-tauri/src/db/mod.rs
+pub mod access;
+pub mod accounts;
+pub mod acl;
+pub mod activity;
+pub mod annotations;
+pub mod bulk;
+pub mod conflicts;
+pub mod contacts;
+pub mod did_shares;
+pub mod encryption;
+pub mod fediverse;
+pub mod history;
+pub mod identity;
+pub mod metadata_index;
+pub mod metadata_schemas;
+pub mod mirrors;
 pub mod models;
+pub mod ops;
+pub mod pins;
+pub mod plans;
+pub mod private_notes;
+pub mod profiles;
+pub mod query;
 pub mod schema;
+pub mod search;
+pub mod settings;
+pub mod shares;
+pub mod stats;
+pub mod tags;
+pub mod tenants;
+pub mod timing;
+pub mod trash;
+pub mod watches;
 
 use anyhow::Result;
-use libsql::{Builder, Database};
+use libsql::{Builder, Connection, Database};
+use std::time::Duration;
+
+/// How long a connection waits on another's lock before SQLITE_BUSY.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+/// Page cache per connection, in KiB.
+const CACHE_SIZE_KIB: i64 = 16 * 1024;
 
 /// Open (or create) a local embedded libsql database.
 /// This is the standard local-only mode — SQLite-compatible, no network.
+/// Encrypted with the keychain key once set_database_passphrase has been used
+/// (see encryption.rs).
 /// To enable Turso embedded replica sync, use open_with_replica() below instead.
 pub async fn open(path: &str) -> Result<Database> {
-    let db = Builder::new_local(path).build().await?;
+    let db = encryption::open_local(path).await?;
+
+    // Only takes effect on a new, empty file; maintenance switches older ones
+    let conn = connect(&db).await?;
+    conn.execute("PRAGMA auto_vacuum = INCREMENTAL", ()).await?;
 
     // Run schema migrations once on open
-    let conn = db.connect()?;
     schema::run_migrations(&conn).await?;
+    bulk::recover_fts(&conn).await?;
 
     Ok(db)
 }
@@ -36,8 +79,36 @@ pub async fn open_with_replica(local_path: &str, remote_url: &str, auth_token: &
         .build()
         .await?;
 
-    let conn = db.connect()?;
+    let conn = connect(&db).await?;
     schema::run_migrations(&conn).await?;
+    bulk::recover_fts(&conn).await?;
 
     Ok(db)
+}
+
+/// A connection to `db` with configure() applied. Use this rather than
+/// Database::connect so every connection gets the same settings.
+pub async fn connect(db: &Database) -> Result<Connection> {
+    let conn = db.connect()?;
+    configure(&conn).await?;
+    Ok(conn)
+}
+
+/// Settings for the UI and background sync sharing the file:
+/// - WAL, so readers don't block the writer or each other
+/// - synchronous = NORMAL, safe in WAL mode (an OS crash can lose the last
+///   commits, never corrupt the file) and far fewer fsyncs than FULL
+/// - a busy timeout, so a second writer waits instead of failing with SQLITE_BUSY
+/// - a larger page cache than SQLite's 2 MiB default
+///
+/// journal_mode sticks to the file; the rest is per connection.
+pub async fn configure(conn: &Connection) -> Result<()> {
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    // Answers with the resulting mode
+    let mut rows = conn.query("PRAGMA journal_mode = WAL", ()).await?;
+    while rows.next().await?.is_some() {}
+    drop(rows);
+    conn.execute("PRAGMA synchronous = NORMAL", ()).await?;
+    conn.execute(&format!("PRAGMA cache_size = -{CACHE_SIZE_KIB}"), ()).await?;
+    Ok(())
 }
\ No newline at end of file
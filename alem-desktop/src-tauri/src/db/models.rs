@@ -26,6 +26,167 @@ pub struct Document {
     pub last_synced_at: Option<String>,
     pub created_at: String,
     pub updated_at: String,
+    pub collection_id: Option<String>,
+    /// Set while the document is in the trash.
+    pub trashed_at: Option<String>,
+}
+
+/// Listing/search row: everything in Document except text_content, which can
+/// be megabytes per row. Fetch the full record with get_document(id).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentSummary {
+    pub id: String,
+    pub user_id: String,
+    pub tenant_id: String,
+    pub filename: String,
+    pub content_type: Option<String>,
+    pub file_size: Option<i64>,
+    pub content_hash: Option<String>,
+    pub local_path: Option<String>,
+    pub object_key: Option<String>,
+    pub metadata: serde_json::Value,
+    pub tags: Vec<String>,
+    pub status: String,
+    pub local_version: i32,
+    pub server_version: i32,
+    pub is_synced: bool,
+    pub needs_upload: bool,
+    pub needs_download: bool,
+    pub sync_error: Option<String>,
+    pub last_synced_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub collection_id: Option<String>,
+    pub trashed_at: Option<String>,
+}
+
+const SUMMARY_COLUMNS: &[&str] = &[
+    "id", "user_id", "tenant_id", "filename", "content_type", "file_size", "content_hash",
+    "local_path", "object_key", "metadata", "tags", "status",
+    "local_version", "server_version", "is_synced", "needs_upload", "needs_download",
+    "sync_error", "last_synced_at", "created_at", "updated_at", "collection_id", "trashed_at",
+];
+
+/// Column list for row_to_summary (prefix with a table alias via
+/// `summary_columns("d.")` when joining). `tags` is computed from document_tags.
+pub fn summary_columns(prefix: &str) -> String {
+    select_list(prefix, SUMMARY_COLUMNS)
+}
+
+/// Column list for row_to_document: the summary columns plus text_content.
+pub fn document_columns(prefix: &str) -> String {
+    format!("{}, {prefix}text_content", select_list(prefix, SUMMARY_COLUMNS))
+}
+
+fn select_list(prefix: &str, columns: &[&str]) -> String {
+    let id_col = if prefix.is_empty() { "documents.id".to_string() } else { format!("{prefix}id") };
+    columns.iter().map(|&c| match c {
+        "tags" => format!("{} AS tags", super::tags::json_expr(&id_col)),
+        c      => format!("{prefix}{c}"),
+    }).collect::<Vec<_>>().join(", ")
+}
+
+/// One page of a keyset-paginated listing. Pass `next_cursor` back to get
+/// the following page; None means this was the last one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentPage {
+    pub documents: Vec<DocumentSummary>,
+    pub next_cursor: Option<String>,
+}
+
+/// One search result: the summary fields plus FTS5 ranking and match context.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    #[serde(flatten)]
+    pub document: DocumentSummary,
+    /// Relevance, higher is better (negated FTS5 bm25).
+    pub score: f64,
+    /// Filename with matched terms wrapped in the highlight markers.
+    pub filename_highlight: String,
+    /// Fragment of text_content around the best match; None if no content.
+    pub snippet: Option<String>,
+}
+
+/// A document from the "Recent" view.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentDocument {
+    #[serde(flatten)]
+    pub document: DocumentSummary,
+    pub last_accessed_at: String,
+    pub access_count: i64,
+}
+
+/// A document on the watch list.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchedDocument {
+    #[serde(flatten)]
+    pub document: DocumentSummary,
+    pub watched_since: String,
+    /// Last change pulled from the server since watching began.
+    pub last_changed_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Collection {
+    pub id: String,
+    pub parent_id: Option<String>,
+    pub name: String,
+    /// Documents directly in this collection (not descendants), excluding
+    /// deleted and trashed ones.
+    pub document_count: i64,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentLink {
+    pub source_id: String,
+    pub target_id: String,
+    pub relation: String,
+    pub created_at: String,
+}
+
+/// The document at the other end of a link, for get_links / get_backlinks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkedDocument {
+    pub relation: String,
+    pub linked_at: String,
+    pub document: DocumentSummary,
+}
+
+/// Documents reachable from a starting document and the links between them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkGraph {
+    pub nodes: Vec<DocumentSummary>,
+    pub edges: Vec<DocumentLink>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub id: String,
+    pub name: String,
+    /// Full-text search text; None = filter only.
+    pub query: Option<String>,
+    pub filter: super::query::DocumentFilter,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Which documents export_archive writes: the listed ids if any, narrowed by
+/// `filter` (default: every non-deleted document).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportSelection {
+    pub document_ids: Vec<String>,
+    pub filter: Option<super::query::DocumentFilter>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tag {
+    pub id: i64,
+    pub name: String,
+    /// Documents carrying this tag, excluding deleted and trashed ones.
+    pub document_count: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,6 +202,132 @@ pub struct LocalIdentity {
     pub last_sync_at: Option<String>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AclRole {
+    Viewer,
+    Editor,
+    Owner,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AclEntry {
+    /// DID or user id.
+    pub grantee: String,
+    pub role: AclRole,
+}
+
+/// Progress of one collection ACL change across its documents.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AclChangeSet {
+    pub id: String,
+    pub collection_id: String,
+    pub acl: Vec<AclEntry>,
+    /// "pending", "done" (every document applied) or "partial" (some failed).
+    pub status: String,
+    pub total: i64,
+    pub applied: i64,
+    pub failed: Vec<AclFailure>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AclFailure {
+    pub document_id: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tenant {
+    pub id: String,
+    pub name: Option<String>,
+    /// The tenant whose documents and sync queue are in use.
+    pub active: bool,
+    /// Pull cursor: when this tenant last synced.
+    pub last_sync_at: Option<String>,
+    pub document_count: i64,
+    pub pending_ops: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationKind {
+    Comment,
+    Highlight,
+}
+
+/// Where an annotation points. All optional: a comment on the whole
+/// document has an empty anchor.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AnnotationAnchor {
+    /// 1-based page, for paged formats.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<i64>,
+    /// Character range in the page (or the text content), end exclusive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<i64>,
+    /// The highlighted text, to re-find the range if the content changes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quote: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Annotation {
+    pub id: String,
+    pub doc_id: String,
+    pub kind: AnnotationKind,
+    pub anchor: AnnotationAnchor,
+    pub body: String,
+    pub author_did: String,
+    pub created_at: String,
+    pub updated_at: String,
+    /// Set on tombstones, which are only seen by the sync engine.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetadataSchema {
+    /// Exact type, a "type/*" wildcard or "*".
+    pub content_type: String,
+    pub schema: serde_json::Value,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivitySource {
+    /// Reported by the server: other members' actions, shares, membership.
+    Server,
+    /// Done on this device (from the op queue), synced or not yet.
+    Local,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityEvent {
+    pub id: String,
+    pub source: ActivitySource,
+    /// e.g. "member_added", "document_shared", "document_created".
+    pub kind: String,
+    /// DID or user id of whoever did it.
+    pub actor: Option<String>,
+    pub document_id: Option<String>,
+    pub data: serde_json::Value,
+    pub occurred_at: String,
+}
+
+/// One page of the activity feed, newest first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityFeed {
+    pub events: Vec<ActivityEvent>,
+    pub next_cursor: Option<String>,
+    /// Set when fetching new server events failed; the page is from the cache.
+    pub refresh_error: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OfflineOperation {
     pub id: String,
@@ -53,6 +340,37 @@ pub struct OfflineOperation {
     pub created_at: String,
 }
 
+/// Payload of an "api_request" op: a call to the server queued until it can
+/// go out (db/ops.rs). It may be sent more than once, so it must be
+/// idempotent; a server that honours Idempotency-Key drops repeats.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiRequest {
+    /// POST, PUT, PATCH or DELETE.
+    pub method: String,
+    /// e.g. "/api/v1/namespaces/account".
+    pub path: String,
+    #[serde(default)]
+    pub body: Option<serde_json::Value>,
+    /// Filled in when queued.
+    #[serde(default)]
+    pub idempotency_key: String,
+    #[serde(default)]
+    pub on_conflict: RequestConflict,
+}
+
+/// What a queued request does when the server answers 409 or 412.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestConflict {
+    /// The op fails and stays in the queue to be requeued or cleared.
+    #[default]
+    Fail,
+    /// The server's state stands and the request is dropped.
+    Discard,
+    /// Fail, and emit "api-request-conflict" with the server's answer.
+    Notify,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DIDResult {
     pub did: String,
@@ -60,6 +378,123 @@ pub struct DIDResult {
     // private key is NEVER returned — stored only in OS keychain
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SignedPayload {
+    pub did: String,
+    /// Verification method that signed: the DID's current assertion key.
+    pub key_id: String,
+    /// Ed25519 over the JCS canonical payload, multibase base58btc.
+    pub signature: String,
+    /// The same signature as a compact JWS (EdDSA, kid = key_id).
+    pub jws: String,
+}
+
+/// Self-issued profile. `signature` covers every other field (JCS) and is
+/// made with the DID's signing key (db/profiles.rs).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub did: String,
+    pub display_name: Option<String>,
+    /// sha256 of the avatar image in the blob store.
+    pub avatar_hash: Option<String>,
+    /// Public contact details (email, url, …) as a JSON object.
+    pub contact: serde_json::Value,
+    pub issued_at: String,
+    pub signature: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProfileInput {
+    pub display_name: Option<String>,
+    pub avatar_hash: Option<String>,
+    pub contact: Option<serde_json::Value>,
+}
+
+/// How far a contact's DID is trusted. Only `verified` contacts have been
+/// checked out of band (QR scan or safety number comparison).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrustLevel {
+    Unverified,
+    Verified,
+    /// Signatures from this DID are no longer accepted.
+    Revoked,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Contact {
+    pub did: String,
+    pub label: Option<String>,
+    pub trust: TrustLevel,
+    pub first_seen_at: String,
+    pub verified_at: Option<String>,
+    /// "qr" or "safety_number".
+    pub verified_via: Option<String>,
+    pub revoked_at: Option<String>,
+    /// Key history, oldest first; the did:key's own key is always first.
+    pub keys: Vec<ContactKey>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContactKey {
+    /// Verification method id, "<did>#<multibase>".
+    pub id: String,
+    pub public_key_multibase: String,
+    pub first_seen_at: String,
+}
+
+/// What the two sides compare to verify each other: the safety number read
+/// aloud or side by side, or the QR code scanned by the other device.
+#[derive(Debug, Clone, Serialize)]
+pub struct ContactVerification {
+    pub safety_number: String,
+    pub qr_payload: String,
+}
+
+/// Rows that can carry private notes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotesTarget {
+    Document,
+    Contact,
+}
+
+/// Signature algorithm of a DID (its did:key and its signing / authentication
+/// keys). Key agreement is X25519 regardless.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyAlgorithm {
+    #[default]
+    Ed25519,
+    Secp256k1,
+    P256,
+}
+
+/// What a DID key may be used for; one active key per purpose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyPurpose {
+    /// assertionMethod — signing payloads.
+    Signing,
+    /// authentication — proving control of the DID when logging in.
+    Authentication,
+    /// keyAgreement — X25519, deriving encryption keys with other parties.
+    KeyAgreement,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DidKey {
+    /// Verification method id, "<did>#<multibase>".
+    pub id: String,
+    pub did: String,
+    pub purpose: KeyPurpose,
+    pub key_type: String,
+    pub public_key_multibase: String,
+    pub created_at: String,
+    /// Set once rotated out; revoked keys are not in the DID document.
+    pub revoked_at: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SyncStatus {
     pub is_syncing: bool,
@@ -67,57 +502,777 @@ pub struct SyncStatus {
     pub pending_count: i64,
     pub failed_count: i64,
     pub connection_online: bool,
+    pub connection_state: crate::sync::connectivity::ConnectionState,
+    /// Rolling average over recent transfers; None until one completes.
+    pub upload_bytes_per_sec: Option<f64>,
+    pub download_bytes_per_sec: Option<f64>,
+    pub bytes_pending_upload: i64,
+    pub bytes_pending_download: i64,
+    /// Estimated seconds to drain both queues at the current rates.
+    pub eta_seconds: Option<u64>,
+    /// Open sync conflicts waiting for the user (see list_sync_conflicts).
+    pub conflict_count: i64,
 }
 
-/// Helper: convert libsql::Row columns to a Document.
-/// libsql::Row uses column index + Value enum, not typed closures.
-pub fn row_to_document(row: &libsql::Row) -> anyhow::Result<Document> {
-    use libsql::Value;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictStrategy {
+    /// Send the local version over the server's.
+    KeepLocal,
+    /// Take the server's version and drop the unsent local change.
+    KeepRemote,
+    /// Keep the local version as a new "conflicted copy" document and take
+    /// the server's version for the original.
+    KeepBoth,
+}
+
+/// One side of a sync conflict, enough to tell the two apart in a dialog.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConflictVersion {
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    pub content_hash: Option<String>,
+    pub file_size: Option<i64>,
+    pub collection_id: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub updated_at: Option<String>,
+    /// local_version locally; the server's version, when it sends one.
+    pub version: Option<i64>,
+    /// Who made the server change; None on the local side.
+    pub changed_by: Option<String>,
+}
+
+/// A server change pulled for a document with a local change that wasn't
+/// uploaded yet. Neither side is applied until it is resolved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncConflict {
+    pub id: String,
+    pub document_id: String,
+    pub local: ConflictVersion,
+    pub remote: ConflictVersion,
+    /// What resolve_conflict accepts for this one.
+    pub strategies: Vec<ConflictStrategy>,
+    pub created_at: String,
+    /// Bumped when a newer server change replaces the held one.
+    pub updated_at: String,
+}
+
+/// A document's metadata as it was at some past time (get_documents_as_of).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoricalDocument {
+    pub id: String,
+    pub filename: String,
+    pub content_type: Option<String>,
+    pub file_size: Option<i64>,
+    pub content_hash: Option<String>,
+    pub collection_id: Option<String>,
+    pub metadata: serde_json::Value,
+    pub tags: Vec<String>,
+    /// Set when the document was in the trash at that time.
+    pub trashed_at: Option<String>,
+    pub created_at: String,
+    /// The document now: "live", "trashed" or "deleted".
+    pub current_state: String,
+    /// Whether any of the fields above changed since.
+    pub changed_since: bool,
+}
 
-    fn get_str(row: &libsql::Row, idx: i32) -> Option<String> {
-        match row.get_value(idx).ok()? {
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlowQuery {
+    pub id: i64,
+    pub sql: String,
+    pub param_types: String,
+    pub duration_ms: i64,
+    pub created_at: String,
+}
+
+/// Persisted under settings key "sync".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncSettings {
+    /// Number of operations processed in parallel per sync cycle.
+    pub upload_concurrency: usize,
+    /// Aggregate upload cap across all workers, in kilobits per second;
+    /// None = unlimited.
+    pub bandwidth_limit_kbps: Option<u64>,
+}
+
+impl Default for SyncSettings {
+    fn default() -> Self {
+        Self { upload_concurrency: 3, bandwidth_limit_kbps: None }
+    }
+}
+
+/// Which of a tenant's documents this device receives (sync/filter.rs).
+/// A document must pass every non-empty rule; an empty filter passes all.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncFilter {
+    /// Has at least one of these tags.
+    pub tags: Vec<String>,
+    /// Is directly in one of these folders.
+    pub collection_ids: Vec<String>,
+    /// Has one of these content types; "image/" matches every image type.
+    pub content_types: Vec<String>,
+    pub max_file_size: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncFilterState {
+    pub filter: Option<SyncFilter>,
+}
+
+/// An S3-compatible bucket of the user's own. Sent to the server once by
+/// register_storage_bucket and never stored locally.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BucketCredentials {
+    /// e.g. "https://s3.eu-central-1.amazonaws.com" or a MinIO URL.
+    pub endpoint: String,
+    pub region: String,
+    pub bucket: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// Path-style addressing, which most self-hosted stores need.
+    #[serde(default)]
+    pub path_style: bool,
+}
+
+/// Persisted under settings key "storage_target". Uploads go to the
+/// server's default bucket while bucket_id is None.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StorageTarget {
+    /// The server's id for the registered bucket.
+    pub bucket_id: Option<String>,
+    pub bucket: Option<String>,
+    pub endpoint: Option<String>,
+    /// When the probe last made the round trip from this machine.
+    pub validated_at: Option<String>,
+}
+
+/// Persisted under settings key "storage".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StorageSettings {
+    /// Re-hash local bytes against content_hash before returning them.
+    pub verify_on_read: bool,
+    /// Soft limit on locally cached file bytes; None = unlimited. Above it,
+    /// local copies of synced documents are evicted, least recently used first.
+    pub quota_bytes: Option<u64>,
+    /// Longest text_content kept in the database (and search index). Longer
+    /// text is cut to this size and kept whole in a blob (full_text_path).
+    pub max_text_bytes: u64,
+    /// zstd-compress cached files whose content type compresses well.
+    pub compression: bool,
+    pub compression_level: i32,
+}
+
+impl Default for StorageSettings {
+    fn default() -> Self {
+        Self {
+            verify_on_read:    true,
+            quota_bytes:       None,
+            max_text_bytes:    10 * 1024 * 1024,
+            compression:       false,
+            compression_level: 3,
+        }
+    }
+}
+
+/// A collection kept in step with a folder on disk (mirror.rs).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderMirror {
+    pub collection_id: String,
+    pub path: String,
+    /// A file deleted from the folder trashes its document. Otherwise the
+    /// file is written again on the next pass.
+    pub propagate_deletes: bool,
+    pub created_at: String,
+    pub last_synced_at: Option<String>,
+    /// Why the last pass failed; None after a good one.
+    pub last_error: Option<String>,
+}
+
+/// A server and user this install has signed in to (db/accounts.rs).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Account {
+    pub id: String,
+    pub server_url: String,
+    pub username: String,
+    pub did: Option<String>,
+    /// The tenant it works in while active.
+    pub tenant_id: String,
+    /// The account whose documents, tenants and sync queue are in use.
+    pub active: bool,
+    /// Its tokens are in the keychain; false once signed out.
+    pub signed_in: bool,
+    /// The server stopped accepting its tokens; it has to sign in again.
+    pub session_expired: bool,
+    pub created_at: String,
+    pub last_used_at: String,
+}
+
+/// Persisted under settings key "ipfs" (storage/ipfs.rs).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IpfsSettings {
+    /// Pin uploads to the IPFS node instead of sending them to the bucket.
+    pub enabled: bool,
+    /// The node's RPC API. It also serves `ipfs://` downloads when uploads
+    /// are off.
+    pub api_url: String,
+}
+
+impl Default for IpfsSettings {
+    fn default() -> Self {
+        Self { enabled: false, api_url: "http://127.0.0.1:5001".into() }
+    }
+}
+
+/// What a server's pin fixes (tls.rs).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PinKind {
+    /// The exact certificate; a renewed one has to be trusted again.
+    Certificate,
+    /// The certificate's public key, which survives renewals that keep it.
+    PublicKey,
+}
+
+/// A server whose TLS certificate is pinned: it is trusted by fingerprint
+/// alone (self-signed is fine) and nothing else is accepted for its host.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerPin {
+    pub host: String,
+    pub kind: PinKind,
+    /// SHA-256 as colon-separated hex for a certificate, "sha256/<base64>"
+    /// of the SubjectPublicKeyInfo for a public key.
+    pub fingerprint: String,
+    pub created_at: String,
+}
+
+/// The certificate a server presents, for deciding whether to pin it.
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerCertificate {
+    pub host: String,
+    pub certificate_fingerprint: String,
+    pub public_key_fingerprint: String,
+    /// Whether it chains to a trusted root (the system's or the network
+    /// settings'), i.e. would be accepted without a pin.
+    pub trusted: bool,
+    pub pin: Option<ServerPin>,
+    /// Whether the current pin, if any, accepts it.
+    pub matches_pin: bool,
+}
+
+/// Who sees a post on the fediverse (activitypub.rs), as Mastodon's API
+/// names it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PostVisibility {
+    /// Public timelines and followers.
+    Public,
+    /// Anyone with the link and followers, but no public timeline.
+    Unlisted,
+    /// Followers only.
+    Private,
+    /// Only the accounts it mentions.
+    Direct,
+}
+
+/// A document published as a post to the account's Pleroma account.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FediversePost {
+    pub id: String,
+    pub doc_id: String,
+    /// The status as Pleroma knows it.
+    pub status_id: String,
+    /// Where the post can be seen, when Pleroma said.
+    pub url: Option<String>,
+    pub visibility: PostVisibility,
+    pub pleroma_account_id: String,
+    /// The DID named as the author.
+    pub author_did: Option<String>,
+    /// The link the post carries, if it was given one.
+    pub link: Option<String>,
+    pub created_at: String,
+}
+
+/// What a share link lets its holder do (sharing.rs).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SharePermission {
+    /// See the document: its metadata and text.
+    Read,
+    /// Download the file.
+    Download,
+}
+
+/// Who vouches for a share link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShareIssuer {
+    /// A capability URL the server hands out and checks; revoking it takes
+    /// effect there.
+    Server,
+    /// A token signed with the local DID's key, made when the server
+    /// couldn't be asked. Whoever checks it has only its signature and
+    /// expiry to go by, so revoking it is recorded here alone.
+    Local,
+}
+
+/// A link to one document that works without an account, within its
+/// permissions and until it expires or is revoked.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareLink {
+    pub id: String,
+    pub doc_id: String,
+    pub issuer: ShareIssuer,
+    /// What to hand out: the server's capability URL, or
+    /// alem://share?token=<JWS> for a local token.
+    pub url: String,
+    pub permissions: Vec<SharePermission>,
+    /// RFC 3339; None: never.
+    pub expires_at: Option<String>,
+    pub revoked_at: Option<String>,
+    pub created_at: String,
+}
+
+/// A document shared with another DID (sharing.rs).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentShare {
+    pub id: String,
+    pub doc_id: String,
+    pub recipient_did: String,
+    /// Viewer or editor.
+    pub role: AclRole,
+    pub revoked_at: Option<String>,
+    pub created_at: String,
+}
+
+/// A document another DID shared with this account, for "Shared with me".
+/// What it holds is the server's copy as of the share; open it with
+/// open_shared_document.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SharedDocument {
+    /// The share's id.
+    pub id: String,
+    pub doc_id: String,
+    pub owner_did: Option<String>,
+    pub role: AclRole,
+    pub filename: String,
+    pub content_type: Option<String>,
+    pub metadata: serde_json::Value,
+    pub shared_at: String,
+    pub received_at: String,
+}
+
+/// An opened shared document.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SharedDocumentContent {
+    pub text_content: Option<String>,
+    /// Short-lived.
+    pub download_url: Option<String>,
+}
+
+/// Persisted under settings key "network"; applied to every HTTP client
+/// (http.rs).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkSettings {
+    /// e.g. "http://proxy.corp.example:3128". None: the HTTP_PROXY,
+    /// HTTPS_PROXY and ALL_PROXY environment variables, if set.
+    pub proxy_url: Option<String>,
+    pub proxy_username: Option<String>,
+    pub proxy_password: Option<String>,
+    /// Hosts reached without the proxy, comma-separated as in NO_PROXY.
+    pub no_proxy: Option<String>,
+    /// PEM certificates trusted besides the system's, for a TLS-inspecting
+    /// proxy or a server with a private CA. An entry may hold a bundle.
+    pub root_certificates: Vec<String>,
+}
+
+/// Persisted under settings key "webdav" (webdav.rs).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebDavSettings {
+    pub enabled: bool,
+    /// 0 picks a free port at each start; set one to keep the URL stable.
+    pub port: u16,
+    /// The share's secret first path segment, made when it's first turned
+    /// on. Not taken from update_webdav_settings.
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WebDavStatus {
+    pub running: bool,
+    /// What to connect a file manager or office suite to.
+    pub url: Option<String>,
+}
+
+/// Persisted under settings key "search". Weights scale each source's bm25
+/// score; the defaults rank exactly as FTS5 does out of the box.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchSettings {
+    pub filename_weight: f64,
+    pub content_weight: f64,
+    /// Matches in a document's annotations; 0 leaves annotations out of search.
+    pub annotations_weight: f64,
+    /// Extra score for recently updated documents: 1.0 doubles the score of
+    /// one updated just now, fading linearly to nothing over the window.
+    pub recency_boost: f64,
+    pub recency_window_days: f64,
+}
+
+impl Default for SearchSettings {
+    fn default() -> Self {
+        Self {
+            filename_weight:     1.0,
+            content_weight:      1.0,
+            annotations_weight:  0.0,
+            recency_boost:       0.0,
+            recency_window_days: 30.0,
+        }
+    }
+}
+
+/// Persisted under settings key "maintenance".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MaintenanceSettings {
+    pub enabled: bool,
+    /// Minutes without user commands before maintenance may start.
+    pub idle_minutes: u64,
+    /// Minimum gap between completed runs.
+    pub interval_hours: u64,
+    /// Trashed documents older than this are deleted for good; 0 keeps them.
+    pub trash_retention_days: u64,
+}
+
+impl Default for MaintenanceSettings {
+    fn default() -> Self {
+        Self { enabled: true, idle_minutes: 5, interval_hours: 6, trash_retention_days: 30 }
+    }
+}
+
+impl MaintenanceSettings {
+    pub fn idle_threshold(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.idle_minutes * 60)
+    }
+}
+
+/// What a content scanner's finding does to the document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanAction {
+    /// Import goes ahead; the UI is told through the "content-flagged" event.
+    Warn,
+    /// Also refuse to share the document (collection ACLs).
+    BlockShare,
+}
+
+/// One configured scanner, run in order on every imported document.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ScannerConfig {
+    /// Built-in detector of e-mail addresses and card-like numbers in the
+    /// document's text. Flagged documents also get `tag`.
+    Pii {
+        action: ScanAction,
+        tag:    Option<String>,
+    },
+    /// External program given the file path (appended, or in place of a
+    /// `{path}` argument). Exit 0 is clean and 1 a finding, as with clamscan;
+    /// the last line of output is the detail.
+    Command {
+        name:         String,
+        program:      String,
+        #[serde(default)]
+        args:         Vec<String>,
+        action:       ScanAction,
+        #[serde(default = "default_scan_timeout")]
+        timeout_secs: u64,
+    },
+    /// ICAP server (RESPMOD), e.g. icap://av.example.com:1344/avscan.
+    Icap {
+        name:         String,
+        url:          String,
+        action:       ScanAction,
+        #[serde(default = "default_scan_timeout")]
+        timeout_secs: u64,
+    },
+}
+
+fn default_scan_timeout() -> u64 { 60 }
+
+/// Persisted under settings key "scan".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScanSettings {
+    pub enabled: bool,
+    pub scanners: Vec<ScannerConfig>,
+    /// Larger files are not sent to external scanners (noted in the result).
+    pub max_file_bytes: u64,
+}
+
+impl Default for ScanSettings {
+    fn default() -> Self {
+        Self {
+            enabled:        true,
+            scanners:       vec![ScannerConfig::Pii { action: ScanAction::Warn, tag: Some("pii".into()) }],
+            max_file_bytes: 100 * 1024 * 1024,
+        }
+    }
+}
+
+/// Persisted under settings key "media". See extract/media.rs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MediaSettings {
+    pub enabled: bool,
+    /// Keep GPS coordinates from photos. Off by default: they sync with the
+    /// document and go wherever it is shared.
+    pub include_gps: bool,
+}
+
+impl Default for MediaSettings {
+    fn default() -> Self {
+        Self { enabled: true, include_gps: false }
+    }
+}
+
+/// Persisted under settings key "ocr". OCR runs the tesseract CLI (and
+/// pdftoppm for PDFs); both must be installed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OcrSettings {
+    pub enabled: bool,
+    pub tesseract_path: String,
+    pub pdftoppm_path: String,
+    /// tesseract -l, e.g. "eng+deu".
+    pub languages: String,
+    /// Resolution PDF pages are rendered at.
+    pub dpi: u32,
+    /// Pages of a PDF recognized; later pages are left out.
+    pub max_pages: u32,
+}
+
+impl Default for OcrSettings {
+    fn default() -> Self {
+        Self {
+            enabled:        false,
+            tesseract_path: "tesseract".into(),
+            pdftoppm_path:  "pdftoppm".into(),
+            languages:      "eng".into(),
+            dpi:            300,
+            max_pages:      50,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OcrStatus {
+    pub document_id: String,
+    /// pending | running | done | failed | skipped
+    pub status: String,
+    pub attempts: i64,
+    /// Characters of recognized text, once done.
+    pub chars: Option<i64>,
+    pub error: Option<String>,
+    pub updated_at: String,
+}
+
+/// Where the database key came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeySource {
+    /// Random secret generated for this install; the user never sees it.
+    Keychain,
+    /// Passphrase chosen by the user; its derived key (not the passphrase) is
+    /// kept in the keychain for unattended start.
+    Passphrase,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DatabaseEncryption {
+    pub encrypted:  bool,
+    pub key_source: Option<KeySource>,
+    /// Key change staged by set/change_database_passphrase; applied on next launch.
+    pub pending:    Option<KeySource>,
+}
+
+use libsql::Value;
+
+/// Column name → index for one result row, so the mappers below read fields
+/// by name and a column added to (or moved in) a SELECT list can't shift the
+/// others. Computed columns must be aliased (`… AS tags`) to be found; with
+/// duplicate names the first column wins.
+pub struct Columns<'a> {
+    row:   &'a libsql::Row,
+    index: std::collections::HashMap<&'a str, i32>,
+}
+
+impl<'a> Columns<'a> {
+    pub fn new(row: &'a libsql::Row) -> Self {
+        let mut index = std::collections::HashMap::new();
+        for i in 0..row.column_count() {
+            if let Some(name) = row.column_name(i) {
+                index.entry(name).or_insert(i);
+            }
+        }
+        Self { row, index }
+    }
+
+    fn value(&self, name: &str) -> Option<Value> {
+        self.row.get_value(*self.index.get(name)?).ok()
+    }
+
+    pub fn str(&self, name: &str) -> Option<String> {
+        match self.value(name)? {
             Value::Text(s) => Some(s),
             _ => None,
         }
     }
-    fn get_i64(row: &libsql::Row, idx: i32) -> Option<i64> {
-        match row.get_value(idx).ok()? {
+
+    pub fn i64(&self, name: &str) -> Option<i64> {
+        match self.value(name)? {
             Value::Integer(i) => Some(i),
             _ => None,
         }
     }
-    fn get_i32(row: &libsql::Row, idx: i32) -> i32 {
-        get_i64(row, idx).unwrap_or(0) as i32
+
+    pub fn f64(&self, name: &str) -> Option<f64> {
+        match self.value(name)? {
+            Value::Real(r)    => Some(r),
+            Value::Integer(i) => Some(i as f64),
+            _ => None,
+        }
+    }
+
+    pub fn i32(&self, name: &str) -> i32 {
+        self.i64(name).unwrap_or(0) as i32
+    }
+
+    pub fn bool(&self, name: &str) -> bool {
+        self.i64(name).unwrap_or(0) != 0
     }
-    fn get_bool(row: &libsql::Row, idx: i32) -> bool {
-        get_i64(row, idx).unwrap_or(0) != 0
+
+    /// Like str, but the column must be present and non-NULL.
+    pub fn required(&self, name: &str) -> anyhow::Result<String> {
+        self.str(name).ok_or_else(|| anyhow::anyhow!("Row has no {name} column"))
     }
 
-    let metadata_str = get_str(row, 10).unwrap_or_else(|| "{}".into());
-    let tags_str     = get_str(row, 11).unwrap_or_else(|| "[]".into());
+    fn json<T: serde::de::DeserializeOwned>(&self, name: &str) -> Option<T> {
+        serde_json::from_str(&self.str(name)?).ok()
+    }
+}
 
+/// Helper: convert a row selected with document_columns() to a Document.
+pub fn row_to_document(row: &libsql::Row) -> anyhow::Result<Document> {
+    let c = Columns::new(row);
     Ok(Document {
-        id:             get_str(row, 0).unwrap_or_default(),
-        user_id:        get_str(row, 1).unwrap_or_default(),
-        tenant_id:      get_str(row, 2).unwrap_or_default(),
-        filename:       get_str(row, 3).unwrap_or_default(),
-        content_type:   get_str(row, 4),
-        file_size:      get_i64(row, 5),
-        content_hash:   get_str(row, 6),
-        local_path:     get_str(row, 7),
-        object_key:     get_str(row, 8),
-        text_content:   get_str(row, 9),
-        metadata:       serde_json::from_str(&metadata_str).unwrap_or(serde_json::json!({})),
-        tags:           serde_json::from_str(&tags_str).unwrap_or_default(),
-        status:         get_str(row, 12).unwrap_or_else(|| "local".into()),
-        local_version:  get_i32(row, 13),
-        server_version: get_i32(row, 14),
-        is_synced:      get_bool(row, 15),
-        needs_upload:   get_bool(row, 16),
-        needs_download: get_bool(row, 17),
-        sync_error:     get_str(row, 18),
-        last_synced_at: get_str(row, 19),
-        created_at:     get_str(row, 20).unwrap_or_default(),
-        updated_at:     get_str(row, 21).unwrap_or_default(),
+        id:             c.required("id")?,
+        user_id:        c.str("user_id").unwrap_or_default(),
+        tenant_id:      c.str("tenant_id").unwrap_or_default(),
+        filename:       c.str("filename").unwrap_or_default(),
+        content_type:   c.str("content_type"),
+        file_size:      c.i64("file_size"),
+        content_hash:   c.str("content_hash"),
+        local_path:     c.str("local_path"),
+        object_key:     c.str("object_key"),
+        text_content:   c.str("text_content"),
+        metadata:       c.json("metadata").unwrap_or(serde_json::json!({})),
+        tags:           c.json("tags").unwrap_or_default(),
+        status:         c.str("status").unwrap_or_else(|| "local".into()),
+        local_version:  c.i32("local_version"),
+        server_version: c.i32("server_version"),
+        is_synced:      c.bool("is_synced"),
+        needs_upload:   c.bool("needs_upload"),
+        needs_download: c.bool("needs_download"),
+        sync_error:     c.str("sync_error"),
+        last_synced_at: c.str("last_synced_at"),
+        created_at:     c.str("created_at").unwrap_or_default(),
+        updated_at:     c.str("updated_at").unwrap_or_default(),
+        collection_id:  c.str("collection_id"),
+        trashed_at:     c.str("trashed_at"),
+    })
+}
+
+/// Helper: convert a row selected with summary_columns() to a DocumentSummary.
+pub fn row_to_summary(row: &libsql::Row) -> anyhow::Result<DocumentSummary> {
+    let c = Columns::new(row);
+    Ok(DocumentSummary {
+        id:             c.required("id")?,
+        user_id:        c.str("user_id").unwrap_or_default(),
+        tenant_id:      c.str("tenant_id").unwrap_or_default(),
+        filename:       c.str("filename").unwrap_or_default(),
+        content_type:   c.str("content_type"),
+        file_size:      c.i64("file_size"),
+        content_hash:   c.str("content_hash"),
+        local_path:     c.str("local_path"),
+        object_key:     c.str("object_key"),
+        metadata:       c.json("metadata").unwrap_or(serde_json::json!({})),
+        tags:           c.json("tags").unwrap_or_default(),
+        status:         c.str("status").unwrap_or_else(|| "local".into()),
+        local_version:  c.i32("local_version"),
+        server_version: c.i32("server_version"),
+        is_synced:      c.bool("is_synced"),
+        needs_upload:   c.bool("needs_upload"),
+        needs_download: c.bool("needs_download"),
+        sync_error:     c.str("sync_error"),
+        last_synced_at: c.str("last_synced_at"),
+        created_at:     c.str("created_at").unwrap_or_default(),
+        updated_at:     c.str("updated_at").unwrap_or_default(),
+        collection_id:  c.str("collection_id"),
+        trashed_at:     c.str("trashed_at"),
+    })
+}
+
+/// Helper: summary columns plus `score` (bm25), `filename_highlight` and `snippet`.
+pub fn row_to_search_hit(row: &libsql::Row) -> anyhow::Result<SearchHit> {
+    let document = row_to_summary(row)?;
+    let c = Columns::new(row);
+    Ok(SearchHit {
+        filename_highlight: c.str("filename_highlight").unwrap_or_else(|| document.filename.clone()),
+        snippet:            c.str("snippet").filter(|s| !s.is_empty()),
+        score:              c.f64("score").map(|r| -r).unwrap_or(0.0),
+        document,
+    })
+}
+
+pub fn row_to_recent_document(row: &libsql::Row) -> anyhow::Result<RecentDocument> {
+    let document = row_to_summary(row)?;
+    let c = Columns::new(row);
+    Ok(RecentDocument {
+        last_accessed_at: c.str("last_accessed_at").unwrap_or_default(),
+        access_count:     c.i64("access_count").unwrap_or(0),
+        document,
     })
-}
\ No newline at end of file
+}
+
+pub fn row_to_watched_document(row: &libsql::Row) -> anyhow::Result<WatchedDocument> {
+    let document = row_to_summary(row)?;
+    let c = Columns::new(row);
+    Ok(WatchedDocument {
+        watched_since:   c.str("watched_since").unwrap_or_default(),
+        last_changed_at: c.str("last_changed_at"),
+        document,
+    })
+}
+
+/// Helper: id, user_id, op_type, payload, status, retry_count, error_msg, created_at.
+pub fn row_to_offline_operation(row: &libsql::Row) -> OfflineOperation {
+    let c = Columns::new(row);
+    OfflineOperation {
+        id:          c.str("id").unwrap_or_default(),
+        user_id:     c.str("user_id").unwrap_or_default(),
+        op_type:     c.str("op_type").unwrap_or_default(),
+        payload:     c.json("payload").unwrap_or(serde_json::Value::Null),
+        status:      c.str("status").unwrap_or_default(),
+        retry_count: c.i32("retry_count"),
+        error_msg:   c.str("error_msg"),
+        created_at:  c.str("created_at").unwrap_or_default(),
+    }
+}
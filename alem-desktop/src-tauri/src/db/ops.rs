@@ -0,0 +1,116 @@
+// src-tauri/src/db/ops.rs
+// Offline operation queue. The sync engine drains these in
+// process_pending_ops; payloads carry ids only and the engine reads current
+// row state at send time. Ops are queued under the active tenant.
+//
+// The exception is api_request: a server call queued whole (ApiRequest), for
+// changes that have no local row to sync, and replayed as it is.
+use anyhow::{Context, Result};
+use libsql::Connection;
+use uuid::Uuid;
+
+use super::models::{row_to_offline_operation, ApiRequest, OfflineOperation};
+
+const OP_COLUMNS: &str = "id, user_id, op_type, payload, status, retry_count, error_msg, created_at";
+
+pub async fn enqueue(
+    conn: &Connection,
+    user_id: &str,
+    op_type: &str,
+    payload: serde_json::Value,
+) -> Result<String> {
+    let op_id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO offline_operations (id, user_id, tenant_id, op_type, payload)
+         VALUES (?1, ?2, COALESCE((SELECT tenant_id FROM local_identity WHERE id = 'singleton'), 'default'), ?3, ?4)",
+        libsql::params![op_id.clone(), user_id, op_type, payload.to_string()],
+    ).await?;
+    Ok(op_id)
+}
+
+/// Queue `request` for the server, with an idempotency key of its own.
+pub async fn enqueue_api_request(conn: &Connection, user_id: &str, mut request: ApiRequest) -> Result<String> {
+    request.method = request.method.trim().to_ascii_uppercase();
+    check_api_request(&request)?;
+    if request.idempotency_key.is_empty() {
+        request.idempotency_key = Uuid::new_v4().to_string();
+    }
+    enqueue(conn, user_id, "api_request", serde_json::to_value(&request)?).await
+}
+
+pub async fn get(conn: &Connection, id: &str) -> Result<Option<OfflineOperation>> {
+    let mut rows = conn.query(
+        &format!("SELECT {OP_COLUMNS} FROM offline_operations WHERE id = ?1"),
+        libsql::params![id],
+    ).await?;
+    Ok(rows.next().await?.as_ref().map(row_to_offline_operation))
+}
+
+/// Replace the payload of a pending or failed op and put it back in the queue
+/// with a fresh retry budget. For support fixing a malformed op by hand, so
+/// the payload is checked against what the engine reads for that op type.
+pub async fn requeue_with_payload(conn: &Connection, id: &str, payload: serde_json::Value) -> Result<OfflineOperation> {
+    let op = get(conn, id).await?.with_context(|| format!("Operation {id} not found"))?;
+    if !matches!(op.status.as_str(), "pending" | "failed") {
+        anyhow::bail!("Operation {id} is {} — only pending or failed operations can be edited", op.status);
+    }
+    validate_payload(conn, &op.op_type, &payload).await?;
+
+    conn.execute(
+        "UPDATE offline_operations
+         SET payload = ?1, status = 'pending', retry_count = 0, error_msg = NULL,
+             updated_at = datetime('now')
+         WHERE id = ?2",
+        libsql::params![payload.to_string(), id],
+    ).await?;
+    log::info!("[ops] {id} ({}) requeued: {} -> {payload}", op.op_type, op.payload);
+
+    get(conn, id).await?.with_context(|| format!("Operation {id} not found"))
+}
+
+/// The id each op type needs, and the table it must exist in.
+fn target(op_type: &str) -> Option<(&'static str, &'static str)> {
+    match op_type {
+        "upload_document" | "delete_document" | "update_document"
+        | "trash_document" | "restore_document" => Some(("doc_id", "documents")),
+        "sync_collection" => Some(("collection_id", "collections")),
+        "sync_profile"    => Some(("did", "profiles")),
+        "propagate_acl"   => Some(("changeset_id", "acl_changesets")),
+        "sync_annotation" => Some(("annotation_id", "annotations")),
+        _ => None,
+    }
+}
+
+async fn validate_payload(conn: &Connection, op_type: &str, payload: &serde_json::Value) -> Result<()> {
+    if !payload.is_object() {
+        anyhow::bail!("Payload must be a JSON object");
+    }
+    if op_type == "api_request" {
+        let request = serde_json::from_value(payload.clone()).context("Not an api_request payload")?;
+        return check_api_request(&request);
+    }
+    let Some((key, table)) = target(op_type) else {
+        anyhow::bail!("Unknown operation type {op_type}");
+    };
+    let target_id = payload[key].as_str().filter(|s| !s.is_empty())
+        .with_context(|| format!("{op_type} payload needs a \"{key}\" string"))?;
+
+    let mut rows = conn.query(
+        &format!("SELECT 1 FROM {table} WHERE id = ?1"),
+        libsql::params![target_id],
+    ).await?;
+    if rows.next().await?.is_none() {
+        anyhow::bail!("{key} {target_id} does not exist locally");
+    }
+    Ok(())
+}
+
+fn check_api_request(request: &ApiRequest) -> Result<()> {
+    if !["POST", "PUT", "PATCH", "DELETE"].contains(&request.method.as_str()) {
+        anyhow::bail!("Only POST, PUT, PATCH and DELETE requests are queued, not {}", request.method);
+    }
+    if !request.path.starts_with("/api/") || request.path.contains("..") {
+        anyhow::bail!("Queued requests go to the server's /api/ paths, not {}", request.path);
+    }
+    Ok(())
+}
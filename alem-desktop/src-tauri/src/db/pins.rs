@@ -0,0 +1,70 @@
+// src-tauri/src/db/pins.rs
+// Pinned server certificates. tls.rs keeps a copy in memory for the TLS
+// handshake; whoever changes a pin here reloads it there (tls::set_pins).
+use anyhow::{anyhow, Result};
+use libsql::Connection;
+
+use super::models::{Columns, PinKind, ServerPin};
+
+impl PinKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            PinKind::Certificate => "certificate",
+            PinKind::PublicKey   => "public_key",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        [PinKind::Certificate, PinKind::PublicKey].into_iter().find(|k| k.as_str() == s)
+    }
+}
+
+pub async fn list(conn: &Connection) -> Result<Vec<ServerPin>> {
+    let mut rows = conn.query(
+        "SELECT host, kind, fingerprint, created_at FROM server_pins ORDER BY host",
+        (),
+    ).await?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await? {
+        out.push(row_to_pin(&row)?);
+    }
+    Ok(out)
+}
+
+pub async fn get(conn: &Connection, host: &str) -> Result<Option<ServerPin>> {
+    let mut rows = conn.query(
+        "SELECT host, kind, fingerprint, created_at FROM server_pins WHERE host = ?1",
+        libsql::params![host],
+    ).await?;
+    rows.next().await?.map(|row| row_to_pin(&row)).transpose()
+}
+
+/// Pin `host`, replacing any earlier pin.
+pub async fn set(conn: &Connection, host: &str, kind: PinKind, fingerprint: &str) -> Result<ServerPin> {
+    conn.execute(
+        "INSERT INTO server_pins (host, kind, fingerprint) VALUES (?1, ?2, ?3)
+         ON CONFLICT(host) DO UPDATE SET kind = excluded.kind, fingerprint = excluded.fingerprint,
+                                         created_at = datetime('now')",
+        libsql::params![host, kind.as_str(), fingerprint],
+    ).await?;
+    get(conn, host).await?.ok_or_else(|| anyhow!("Pin for {host} not stored"))
+}
+
+/// Returns false when `host` wasn't pinned.
+pub async fn remove(conn: &Connection, host: &str) -> Result<bool> {
+    let n = conn.execute("DELETE FROM server_pins WHERE host = ?1", libsql::params![host]).await?;
+    Ok(n > 0)
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+fn row_to_pin(row: &libsql::Row) -> Result<ServerPin> {
+    let c = Columns::new(row);
+    let kind: String = c.required("kind")?;
+    Ok(ServerPin {
+        host:        c.required("host")?,
+        kind:        PinKind::parse(&kind).ok_or_else(|| anyhow!("Unknown pin kind {kind}"))?,
+        fingerprint: c.required("fingerprint")?,
+        created_at:  c.str("created_at").unwrap_or_default(),
+    })
+}
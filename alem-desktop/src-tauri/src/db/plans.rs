@@ -0,0 +1,86 @@
+// src-tauri/src/db/plans.rs
+// EXPLAIN QUERY PLAN regression check for the hot queries. Any full table scan
+// or temp B-tree sort in these plans means an index stopped being used —
+// at 100k+ documents that is the difference between 2 ms and 2 s.
+use anyhow::Result;
+use libsql::{Connection, Value};
+use serde::Serialize;
+
+/// (name, SQL, number of ?N bind params). Keep in sync with the real queries.
+const HOT_QUERIES: &[(&str, &str, usize)] = &[
+    ("list_documents",
+     "SELECT id FROM documents WHERE tenant_id = ?1 AND status != 'deleted' AND trashed_at IS NULL
+      ORDER BY created_at DESC, id DESC LIMIT 50", 1),
+    ("list_documents_after_cursor",
+     "SELECT id FROM documents WHERE tenant_id = ?3 AND status != 'deleted' AND trashed_at IS NULL
+      AND (created_at, id) < (?1, ?2)
+      ORDER BY created_at DESC, id DESC LIMIT 50", 3),
+    ("pending_ops_snapshot",
+     "SELECT id FROM offline_operations WHERE tenant_id = ?1 AND status = 'pending' AND retry_count < 5
+      ORDER BY created_at ASC LIMIT 20", 1),
+    ("pending_ops_count",
+     "SELECT COUNT(*) FROM offline_operations WHERE tenant_id = ?1 AND status = 'pending'", 1),
+    ("download_backlog",
+     "SELECT COALESCE(SUM(file_size), 0) FROM documents
+      WHERE needs_download = 1 AND status != 'deleted'", 0),
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanIssue {
+    pub query:  String,
+    pub detail: String,
+}
+
+pub async fn check_query_plans(conn: &Connection) -> Result<Vec<PlanIssue>> {
+    let mut issues = Vec::new();
+    for (name, sql, n_params) in HOT_QUERIES {
+        for detail in plan(conn, sql, *n_params).await? {
+            let full_scan = detail.starts_with("SCAN ") && !detail.contains(" USING ");
+            if full_scan || detail.contains("USE TEMP B-TREE") {
+                issues.push(PlanIssue { query: name.to_string(), detail });
+            }
+        }
+    }
+    Ok(issues)
+}
+
+/// The detail column of each EXPLAIN QUERY PLAN row, with empty strings bound.
+async fn plan(conn: &Connection, sql: &str, n_params: usize) -> Result<Vec<String>> {
+    let params: Vec<String> = (0..n_params).map(|_| String::new()).collect();
+    let mut rows = conn.query(&format!("EXPLAIN QUERY PLAN {sql}"), params).await?;
+    let mut details = Vec::new();
+    while let Some(row) = rows.next().await? {
+        if let Ok(Value::Text(detail)) = row.get_value(3) {
+            details.push(detail);
+        }
+    }
+    Ok(details)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn hot_queries_use_their_indexes() {
+        let db   = libsql::Builder::new_local(":memory:").build().await.unwrap();
+        let conn = db.connect().unwrap();
+        crate::db::schema::run_migrations(&conn).await.unwrap();
+
+        let issues = check_query_plans(&conn).await.unwrap();
+        assert!(issues.is_empty(), "{issues:?}");
+
+        for (name, index) in [
+            ("list_documents",              "idx_docs_tenant_live_created"),
+            ("list_documents_after_cursor", "idx_docs_tenant_live_created"),
+            ("pending_ops_snapshot",        "idx_ops_tenant_status_created"),
+            ("pending_ops_count",           "idx_ops_tenant_status_created"),
+            ("download_backlog",            "idx_docs_download"),
+        ] {
+            let (_, sql, n_params) = HOT_QUERIES.iter().find(|(n, ..)| *n == name).unwrap();
+            let details = plan(&conn, sql, *n_params).await.unwrap();
+            assert!(details.iter().any(|d| d.contains(index)), "{name} does not use {index}: {details:?}");
+            assert!(!details.iter().any(|d| d.starts_with("SCAN documents")), "{name} scans documents: {details:?}");
+        }
+    }
+}
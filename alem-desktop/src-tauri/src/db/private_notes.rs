@@ -0,0 +1,60 @@
+// src-tauri/src/db/private_notes.rs
+// Personal notes on documents and contacts, kept only on this device.
+//
+// Stored sealed (crypto/sealed.rs) in a private_notes column that no sync
+// payload, export or DID-signed change ever reads, and setting one doesn't
+// touch updated_at or local_version, so it never causes a sync either.
+use anyhow::{bail, Result};
+use libsql::{Connection, Value};
+
+use super::models::NotesTarget;
+use crate::crypto::sealed::LocalKey;
+
+/// Key purpose for LocalKey::for_did.
+pub const KEY_PURPOSE: &str = "private-notes";
+
+impl NotesTarget {
+    fn table(self) -> &'static str {
+        match self {
+            NotesTarget::Document => "documents",
+            NotesTarget::Contact  => "contacts",
+        }
+    }
+
+    fn id_column(self) -> &'static str {
+        match self {
+            NotesTarget::Document => "id",
+            NotesTarget::Contact  => "did",
+        }
+    }
+
+    /// Sealing context, so notes can't be moved to another row.
+    fn context(self, id: &str) -> String {
+        format!("{}:{id}", self.table())
+    }
+}
+
+pub async fn get(conn: &Connection, key: &LocalKey, target: NotesTarget, id: &str) -> Result<Option<String>> {
+    let mut rows = conn.query(
+        &format!("SELECT private_notes FROM {} WHERE {} = ?1", target.table(), target.id_column()),
+        libsql::params![id],
+    ).await?;
+    let Some(row) = rows.next().await? else { bail!("No {:?} {id}", target) };
+    match row.get_value(0)? {
+        Value::Text(sealed) => Ok(Some(key.open(&sealed, &target.context(id))?)),
+        _                   => Ok(None),
+    }
+}
+
+/// Replace the notes; None or blank clears them.
+pub async fn set(conn: &Connection, key: &LocalKey, target: NotesTarget, id: &str, notes: Option<&str>) -> Result<()> {
+    let sealed = notes.filter(|n| !n.trim().is_empty()).map(|n| key.seal(n, &target.context(id)));
+    let changed = conn.execute(
+        &format!("UPDATE {} SET private_notes = ?2 WHERE {} = ?1", target.table(), target.id_column()),
+        libsql::params![id, sealed],
+    ).await?;
+    if changed == 0 {
+        bail!("No {:?} {id}", target);
+    }
+    Ok(())
+}
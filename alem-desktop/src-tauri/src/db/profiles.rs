@@ -0,0 +1,112 @@
+// src-tauri/src/db/profiles.rs
+// Self-issued profiles (display name, avatar, public contact info).
+//
+// Ours is signed here and pushed as an op; collaborators' arrive through sync.
+// Profiles are a compact JWS by the DID's assertion (signing) key, the kid
+// naming it, so the controller key stays reserved for capabilities. A
+// collaborator's profile verifies once that key is in their key history
+// (contacts.rs), which the controller-signed DID document synced along with
+// the profile puts there; profiles signed before that, with the did:key's own
+// key and no kid, still verify against the DID alone. Only profiles whose signature
+// checks out are stored, and an older profile never replaces a newer one.
+use anyhow::{Context, Result};
+use libsql::{Connection, Value};
+use serde_json::json;
+
+use super::{
+    contacts,
+    models::{KeyPurpose, Profile},
+};
+use crate::crypto::{
+    self,
+    algorithms::{PublicKey, SecretKey},
+    keys,
+};
+
+/// The signed claims: every field but the signature.
+fn claims(profile: &Profile) -> serde_json::Value {
+    json!({
+        "type":         "AlemProfile",
+        "did":          profile.did,
+        "display_name": profile.display_name,
+        "avatar_hash":  profile.avatar_hash,
+        "contact":      profile.contact,
+        "issued_at":    profile.issued_at,
+    })
+}
+
+/// Sign with the signing key `kid`.
+pub fn sign(profile: &mut Profile, kid: &str, key: &SecretKey) -> Result<()> {
+    profile.signature = crypto::sign_jws(key, kid, &claims(profile))?;
+    Ok(())
+}
+
+pub async fn verify(conn: &Connection, profile: &Profile) -> Result<()> {
+    if !profile.signature.contains('.') {
+        let key = crypto::did_key_public(&profile.did)
+            .with_context(|| format!("Profiles without a kid need a did:key, got {}", profile.did))?;
+        return crypto::verify_json(&key, &claims(profile), &profile.signature);
+    }
+    let kid = crypto::jws_kid(&profile.signature)?;
+    let key = signing_key(conn, &profile.did, &kid).await?
+        .with_context(|| format!("Unknown signing key {kid}"))?;
+    crypto::verify_jws(&key, &claims(profile), &profile.signature)
+}
+
+/// Verify and store `profile` unless a newer one is already stored. Returns
+/// false if it was older.
+pub async fn store(conn: &Connection, profile: &Profile) -> Result<bool> {
+    verify(conn, profile).await?;
+    let changed = conn.execute(
+        "INSERT INTO profiles (id, display_name, avatar_hash, contact, issued_at, signature)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT(id) DO UPDATE SET
+             display_name = excluded.display_name,
+             avatar_hash  = excluded.avatar_hash,
+             contact      = excluded.contact,
+             issued_at    = excluded.issued_at,
+             signature    = excluded.signature,
+             received_at  = datetime('now')
+         WHERE excluded.issued_at > profiles.issued_at",
+        libsql::params![
+            profile.did.as_str(),
+            profile.display_name.clone(),
+            profile.avatar_hash.clone(),
+            profile.contact.to_string(),
+            profile.issued_at.as_str(),
+            profile.signature.as_str(),
+        ],
+    ).await?;
+    Ok(changed > 0)
+}
+
+pub async fn get(conn: &Connection, did: &str) -> Result<Option<Profile>> {
+    let mut rows = conn.query(
+        "SELECT id, display_name, avatar_hash, contact, issued_at, signature FROM profiles WHERE id = ?1",
+        libsql::params![did],
+    ).await?;
+    let Some(row) = rows.next().await? else { return Ok(None) };
+    let s = |i| match row.get_value(i).ok() { Some(Value::Text(s)) => Some(s), _ => None };
+    Ok(Some(Profile {
+        did:          s(0).unwrap_or_default(),
+        display_name: s(1),
+        avatar_hash:  s(2),
+        contact:      s(3).and_then(|c| serde_json::from_str(&c).ok()).unwrap_or_else(|| json!({})),
+        issued_at:    s(4).unwrap_or_default(),
+        signature:    s(5).unwrap_or_default(),
+    }))
+}
+
+/// Public half of `did`'s signing key `kid`: one of this device's keys, or
+/// one in a contact's key history.
+async fn signing_key(conn: &Connection, did: &str, kid: &str) -> Result<Option<PublicKey>> {
+    if let Some(key) = keys::find(conn, kid).await? {
+        if key.did != did || key.purpose != KeyPurpose::Signing {
+            return Ok(None);
+        }
+        return Ok(Some(keys::verifying_key(&key)?));
+    }
+    contacts::find_key(conn, did, kid).await?
+        .map(|multibase| PublicKey::from_multibase(&multibase))
+        .transpose()
+}
@@ -0,0 +1,176 @@
+// src-tauri/src/db/query.rs
+// Structured document filter → parameterised SQL WHERE clause.
+//
+// Every user-supplied value is bound as a positional parameter; only fixed SQL
+// fragments are ever concatenated.
+use libsql::Value;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::metadata_index::NORMALIZED_VALUE;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DocumentFilter {
+    /// Match documents carrying at least one of these tags.
+    pub tags_any: Vec<String>,
+    /// Match documents carrying every one of these tags.
+    pub tags_all: Vec<String>,
+    /// e.g. "image/" or "application/pdf".
+    pub content_type_prefix: Option<String>,
+    /// Empty = every status except 'deleted'.
+    pub status: Vec<String>,
+    /// Only documents in the trash; by default trashed documents are excluded.
+    pub trashed: bool,
+    pub created_after: Option<String>,
+    pub created_before: Option<String>,
+    pub updated_after: Option<String>,
+    pub updated_before: Option<String>,
+    pub min_size: Option<i64>,
+    pub max_size: Option<i64>,
+    /// Capture date of photos / recordings (metadata.media.captured_at).
+    pub captured_after: Option<String>,
+    pub captured_before: Option<String>,
+    /// Pixels, for images and video.
+    pub min_width: Option<i64>,
+    pub min_height: Option<i64>,
+    /// Seconds, for audio and video.
+    pub min_duration: Option<f64>,
+    pub max_duration: Option<f64>,
+    /// Documents directly in this collection.
+    pub collection_id: Option<String>,
+    /// Top-level metadata key → value, compared as text (booleans as
+    /// "true"/"false"). Every pair must match.
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// Accumulates WHERE conditions and their bind values with ?N numbering.
+#[derive(Default)]
+pub struct SqlBuilder {
+    conditions: Vec<String>,
+    params:     Vec<Value>,
+}
+
+impl SqlBuilder {
+    /// Bind a value and return its placeholder.
+    pub fn bind(&mut self, value: impl Into<Value>) -> String {
+        self.params.push(value.into());
+        format!("?{}", self.params.len())
+    }
+
+    pub fn push(&mut self, condition: String) {
+        self.conditions.push(condition);
+    }
+
+    /// `(?1, ?2, …)` for an IN list.
+    pub fn bind_list(&mut self, values: &[String]) -> String {
+        let placeholders: Vec<String> = values.iter().map(|v| self.bind(v.clone())).collect();
+        format!("({})", placeholders.join(", "))
+    }
+
+    pub fn where_clause(&self) -> String {
+        if self.conditions.is_empty() {
+            "1 = 1".into()
+        } else {
+            self.conditions.join(" AND ")
+        }
+    }
+
+    pub fn into_params(self) -> Vec<Value> {
+        self.params
+    }
+}
+
+impl DocumentFilter {
+    /// Append this filter's conditions for a `documents` table aliased as
+    /// `alias`. Metadata filters on `indexed_keys` (metadata_index::keys) use
+    /// document_metadata_kv; others scan the metadata JSON.
+    pub fn apply(&self, alias: &str, indexed_keys: &[String], b: &mut SqlBuilder) {
+        let col = |c: &str| format!("{alias}.{c}");
+
+        if self.status.is_empty() {
+            b.push(format!("{} != 'deleted'", col("status")));
+        } else {
+            let list = b.bind_list(&self.status);
+            b.push(format!("{} IN {list}", col("status")));
+        }
+        b.push(format!("{} IS {}NULL", col("trashed_at"), if self.trashed { "NOT " } else { "" }));
+
+        if !self.tags_any.is_empty() {
+            let list = b.bind_list(&self.tags_any);
+            b.push(format!(
+                "EXISTS (SELECT 1 FROM document_tags dt JOIN tags t ON t.id = dt.tag_id
+                         WHERE dt.document_id = {} AND t.name IN {list})", col("id")
+            ));
+        }
+        for tag in &self.tags_all {
+            let p = b.bind(tag.clone());
+            b.push(format!(
+                "EXISTS (SELECT 1 FROM document_tags dt JOIN tags t ON t.id = dt.tag_id
+                         WHERE dt.document_id = {} AND t.name = {p})", col("id")
+            ));
+        }
+
+        if let Some(prefix) = self.content_type_prefix.as_deref().filter(|p| !p.is_empty()) {
+            let p = b.bind(format!("{}%", escape_like(prefix)));
+            b.push(format!("{} LIKE {p} ESCAPE '\\'", col("content_type")));
+        }
+
+        let ranges = [
+            ("created_at", ">=", &self.created_after),
+            ("created_at", "<",  &self.created_before),
+            ("updated_at", ">=", &self.updated_after),
+            ("updated_at", "<",  &self.updated_before),
+            ("media_captured_at", ">=", &self.captured_after),
+            ("media_captured_at", "<",  &self.captured_before),
+        ];
+        for (c, op, value) in ranges {
+            if let Some(v) = value {
+                let p = b.bind(v.clone());
+                b.push(format!("{} {op} {p}", col(c)));
+            }
+        }
+
+        if let Some(min) = self.min_size {
+            let p = b.bind(min);
+            b.push(format!("{} >= {p}", col("file_size")));
+        }
+        if let Some(max) = self.max_size {
+            let p = b.bind(max);
+            b.push(format!("{} <= {p}", col("file_size")));
+        }
+        let media = [
+            ("media_width",    ">=", self.min_width.map(Value::Integer)),
+            ("media_height",   ">=", self.min_height.map(Value::Integer)),
+            ("media_duration", ">=", self.min_duration.map(Value::Real)),
+            ("media_duration", "<=", self.max_duration.map(Value::Real)),
+        ];
+        for (c, op, value) in media {
+            if let Some(v) = value {
+                let p = b.bind(v);
+                b.push(format!("{} {op} {p}", col(c)));
+            }
+        }
+        if let Some(collection) = &self.collection_id {
+            let p = b.bind(collection.clone());
+            b.push(format!("{} = {p}", col("collection_id")));
+        }
+        for (key, value) in &self.metadata {
+            let (k, v) = (b.bind(key.clone()), b.bind(value.clone()));
+            if indexed_keys.contains(key) {
+                b.push(format!(
+                    "{} IN (SELECT document_id FROM document_metadata_kv WHERE key = {k} AND value = {v})", col("id")
+                ));
+            } else {
+                b.push(format!(
+                    "EXISTS (SELECT 1 FROM json_each(CASE WHEN json_valid({m}) THEN {m} ELSE '{{}}' END) j
+                             WHERE j.key = {k} AND {NORMALIZED_VALUE} = {v})", m = col("metadata")
+                ));
+            }
+        }
+    }
+}
+
+fn escape_like(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
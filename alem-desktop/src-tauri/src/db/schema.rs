@@ -1,9 +1,1338 @@
 // src-tauri/src/db/schema.rs
-use anyhow::Result;
-use libsql::Connection;
+use anyhow::{bail, Context, Result};
+use libsql::{Connection, Value};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// One schema step. `up` is applied in a transaction together with its
+/// schema_migrations row; `down` (None = irreversible) undoes it.
+pub struct Migration {
+    pub version: i64,
+    pub name:    &'static str,
+    pub up:      &'static str,
+    pub down:    Option<&'static str>,
+}
+
+/// Ordered schema migrations. Append only — never edit a shipped `up`; add a
+/// new version instead. Applied checksums are compared on every migration run.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name:    "initial",
+        up:      "
+        CREATE TABLE IF NOT EXISTS local_identity (
+            id                  TEXT PRIMARY KEY DEFAULT 'singleton',
+            user_id             TEXT,
+            tenant_id           TEXT NOT NULL DEFAULT 'default',
+            username            TEXT,
+            email               TEXT,
+            did                 TEXT UNIQUE,
+            did_public_key      TEXT,
+            pleroma_account_id  TEXT,
+            server_url          TEXT NOT NULL DEFAULT 'http://localhost:4000',
+            last_sync_at        TEXT,
+            created_at          TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at          TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE TABLE IF NOT EXISTS documents (
+            id              TEXT PRIMARY KEY,
+            user_id         TEXT NOT NULL,
+            tenant_id       TEXT NOT NULL DEFAULT 'default',
+            filename        TEXT NOT NULL,
+            content_type    TEXT,
+            file_size       INTEGER,
+            content_hash    TEXT,
+            local_path      TEXT,
+            object_key      TEXT,
+            text_content    TEXT,
+            metadata        TEXT NOT NULL DEFAULT '{}',
+            tags            TEXT NOT NULL DEFAULT '[]',
+            status          TEXT NOT NULL DEFAULT 'local',
+            local_version   INTEGER NOT NULL DEFAULT 1,
+            server_version  INTEGER NOT NULL DEFAULT 0,
+            is_synced       INTEGER NOT NULL DEFAULT 0,
+            needs_upload    INTEGER NOT NULL DEFAULT 1,
+            needs_download  INTEGER NOT NULL DEFAULT 0,
+            sync_error      TEXT,
+            last_synced_at  TEXT,
+            created_at      TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at      TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_docs_user   ON documents(user_id);
+        CREATE INDEX IF NOT EXISTS idx_docs_status ON documents(status);
+        CREATE INDEX IF NOT EXISTS idx_docs_upload ON documents(needs_upload)
+            WHERE needs_upload = 1;
+
+        CREATE VIRTUAL TABLE IF NOT EXISTS documents_fts USING fts5(
+            id            UNINDEXED,
+            filename,
+            text_content,
+            content       = 'documents',
+            content_rowid = 'rowid'
+        );
+
+        CREATE TRIGGER IF NOT EXISTS docs_fts_insert AFTER INSERT ON documents BEGIN
+            INSERT INTO documents_fts(id, filename, text_content)
+            VALUES (new.id, new.filename, new.text_content);
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS docs_fts_update AFTER UPDATE ON documents BEGIN
+            UPDATE documents_fts
+            SET    filename     = new.filename,
+                   text_content = new.text_content
+            WHERE  id = new.id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS docs_fts_delete AFTER DELETE ON documents BEGIN
+            DELETE FROM documents_fts WHERE id = old.id;
+        END;
+
+        CREATE TABLE IF NOT EXISTS offline_operations (
+            id          TEXT PRIMARY KEY,
+            user_id     TEXT NOT NULL,
+            op_type     TEXT NOT NULL,
+            payload     TEXT NOT NULL DEFAULT '{}',
+            status      TEXT NOT NULL DEFAULT 'pending',
+            retry_count INTEGER NOT NULL DEFAULT 0,
+            error_msg   TEXT,
+            created_at  TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at  TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_ops_status
+            ON offline_operations(user_id, status);
+    ",
+        down:    None,
+    },
+    Migration {
+        version: 2,
+        name:    "settings",
+        up:      "
+        CREATE TABLE IF NOT EXISTS settings (
+            key         TEXT PRIMARY KEY,
+            value       TEXT NOT NULL,
+            updated_at  TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+    ",
+        down:    Some("
+        DROP TABLE IF EXISTS settings;
+    "),
+    },
+    Migration {
+        version: 3,
+        name:    "slow_queries",
+        up:      "
+        CREATE TABLE IF NOT EXISTS slow_queries (
+            id           INTEGER PRIMARY KEY AUTOINCREMENT,
+            sql          TEXT NOT NULL,
+            param_types  TEXT NOT NULL DEFAULT '',
+            duration_ms  INTEGER NOT NULL,
+            created_at   TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+    ",
+        down:    Some("
+        DROP TABLE IF EXISTS slow_queries;
+    "),
+    },
+    Migration {
+        version: 4,
+        name:    "listing_indexes",
+        up:      "
+        -- Listing / keyset pagination: partial index matches `status != 'deleted'`
+        CREATE INDEX IF NOT EXISTS idx_docs_live_created
+            ON documents(created_at DESC, id DESC) WHERE status != 'deleted';
+        CREATE INDEX IF NOT EXISTS idx_docs_download ON documents(needs_download)
+            WHERE needs_download = 1;
+
+        -- Queue snapshot (status + FIFO order) and status counts
+        CREATE INDEX IF NOT EXISTS idx_ops_status_created
+            ON offline_operations(status, created_at);
+    ",
+        down:    Some("
+        DROP INDEX IF EXISTS idx_docs_live_created;
+        DROP INDEX IF EXISTS idx_docs_download;
+        DROP INDEX IF EXISTS idx_ops_status_created;
+    "),
+    },
+    Migration {
+        version: 5,
+        name:    "collections",
+        up:      "
+        CREATE TABLE IF NOT EXISTS collections (
+            id          TEXT PRIMARY KEY,
+            user_id     TEXT NOT NULL,
+            tenant_id   TEXT NOT NULL DEFAULT 'default',
+            parent_id   TEXT,
+            name        TEXT NOT NULL,
+            created_at  TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at  TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_collections_parent ON collections(parent_id);
+
+        ALTER TABLE documents ADD COLUMN collection_id TEXT;
+        CREATE INDEX IF NOT EXISTS idx_docs_collection ON documents(collection_id);
+    ",
+        down:    Some("
+        DROP INDEX IF EXISTS idx_docs_collection;
+        ALTER TABLE documents DROP COLUMN collection_id;
+        DROP INDEX IF EXISTS idx_collections_parent;
+        DROP TABLE IF EXISTS collections;
+    "),
+    },
+    Migration {
+        version: 6,
+        name:    "normalized_tags",
+        up:      "
+        -- Tags move out of the documents.tags JSON blob (left in place, no longer read)
+        CREATE TABLE IF NOT EXISTS tags (
+            id         INTEGER PRIMARY KEY,
+            name       TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE TABLE IF NOT EXISTS document_tags (
+            document_id TEXT    NOT NULL,
+            tag_id      INTEGER NOT NULL,
+            PRIMARY KEY (document_id, tag_id)
+        ) WITHOUT ROWID;
+        CREATE INDEX IF NOT EXISTS idx_document_tags_tag ON document_tags(tag_id);
+
+        CREATE TRIGGER IF NOT EXISTS docs_tags_delete AFTER DELETE ON documents BEGIN
+            DELETE FROM document_tags WHERE document_id = old.id;
+        END;
+
+        INSERT OR IGNORE INTO tags (name)
+            SELECT trim(j.value) FROM documents d, json_each(d.tags) j
+            WHERE json_valid(d.tags) AND j.type = 'text' AND trim(j.value) != '';
+        INSERT OR IGNORE INTO document_tags (document_id, tag_id)
+            SELECT d.id, t.id FROM documents d, json_each(d.tags) j
+            JOIN tags t ON t.name = trim(j.value)
+            WHERE json_valid(d.tags) AND j.type = 'text';
+    ",
+        down:    Some("
+        -- Write the current tag sets back into the JSON column before dropping
+        UPDATE documents SET tags = (
+            SELECT json_group_array(t.name) FROM document_tags dt
+            JOIN tags t ON t.id = dt.tag_id WHERE dt.document_id = documents.id
+        );
+        DROP TRIGGER IF EXISTS docs_tags_delete;
+        DROP INDEX IF EXISTS idx_document_tags_tag;
+        DROP TABLE IF EXISTS document_tags;
+        DROP TABLE IF EXISTS tags;
+    "),
+    },
+    Migration {
+        version: 7,
+        name:    "fts_vocab",
+        up:      "
+        -- Read-only view of the FTS term list, used for fuzzy search candidates
+        CREATE VIRTUAL TABLE IF NOT EXISTS documents_fts_vocab
+            USING fts5vocab(documents_fts, 'row');
+    ",
+        down:    Some("
+        DROP TABLE IF EXISTS documents_fts_vocab;
+    "),
+    },
+    Migration {
+        version: 8,
+        name:    "saved_searches",
+        up:      "
+        -- Smart collections: a named query + filter, re-evaluated when opened
+        CREATE TABLE IF NOT EXISTS saved_searches (
+            id          TEXT PRIMARY KEY,
+            user_id     TEXT NOT NULL,
+            name        TEXT NOT NULL,
+            query       TEXT,
+            filter      TEXT NOT NULL DEFAULT '{}',
+            created_at  TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at  TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+    ",
+        down:    Some("
+        DROP TABLE IF EXISTS saved_searches;
+    "),
+    },
+    Migration {
+        version: 9,
+        name:    "trash",
+        up:      "
+        -- Trashed documents keep their sync status; NULL = not in the trash
+        ALTER TABLE documents ADD COLUMN trashed_at TEXT;
+        CREATE INDEX IF NOT EXISTS idx_docs_trashed ON documents(trashed_at)
+            WHERE trashed_at IS NOT NULL;
+    ",
+        down:    Some("
+        DROP INDEX IF EXISTS idx_docs_trashed;
+        ALTER TABLE documents DROP COLUMN trashed_at;
+    "),
+    },
+    Migration {
+        version: 10,
+        name:    "document_links",
+        up:      "
+        -- Typed, directed relations between documents ('references', 'attachment-of', …)
+        CREATE TABLE IF NOT EXISTS document_links (
+            source_id  TEXT NOT NULL,
+            target_id  TEXT NOT NULL,
+            relation   TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            PRIMARY KEY (source_id, target_id, relation)
+        ) WITHOUT ROWID;
+        CREATE INDEX IF NOT EXISTS idx_document_links_target ON document_links(target_id);
+
+        CREATE TRIGGER IF NOT EXISTS docs_links_delete AFTER DELETE ON documents BEGIN
+            DELETE FROM document_links WHERE source_id = old.id OR target_id = old.id;
+        END;
+    ",
+        down:    Some("
+        DROP TRIGGER IF EXISTS docs_links_delete;
+        DROP INDEX IF EXISTS idx_document_links_target;
+        DROP TABLE IF EXISTS document_links;
+    "),
+    },
+    Migration {
+        version: 11,
+        name:    "did_keys",
+        up:      "
+        -- Per-purpose keys under the local DID; private halves are in the keychain
+        CREATE TABLE IF NOT EXISTS did_keys (
+            id                   TEXT PRIMARY KEY,   -- verification method id, <did>#<multibase>
+            did                  TEXT NOT NULL,
+            purpose              TEXT NOT NULL,
+            key_type             TEXT NOT NULL,
+            public_key_multibase TEXT NOT NULL,
+            created_at           TEXT NOT NULL DEFAULT (datetime('now')),
+            revoked_at           TEXT
+        );
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_did_keys_active ON did_keys(did, purpose)
+            WHERE revoked_at IS NULL;
+    ",
+        down:    Some("
+        DROP INDEX IF EXISTS idx_did_keys_active;
+        DROP TABLE IF EXISTS did_keys;
+    "),
+    },
+    Migration {
+        version: 12,
+        name:    "profiles",
+        up:      "
+        -- Self-issued profiles: ours and collaborators', each signed by its DID
+        CREATE TABLE IF NOT EXISTS profiles (
+            id           TEXT PRIMARY KEY,   -- the DID
+            display_name TEXT,
+            avatar_hash  TEXT,
+            contact      TEXT NOT NULL DEFAULT '{}',
+            issued_at    TEXT NOT NULL,
+            signature    TEXT NOT NULL,
+            received_at  TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+    ",
+        down:    Some("
+        DROP TABLE IF EXISTS profiles;
+    "),
+    },
+    Migration {
+        version: 13,
+        name:    "contacts",
+        up:      "
+        -- Known DIDs and how far we trust them
+        CREATE TABLE IF NOT EXISTS contacts (
+            did           TEXT PRIMARY KEY,
+            label         TEXT,
+            trust         TEXT NOT NULL DEFAULT 'unverified',   -- unverified | verified | revoked
+            first_seen_at TEXT NOT NULL DEFAULT (datetime('now')),
+            verified_at   TEXT,
+            verified_via  TEXT,                                 -- qr | safety_number
+            revoked_at    TEXT,
+            updated_at    TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        -- Every key seen for a contact, the did:key's own key included
+        CREATE TABLE IF NOT EXISTS contact_keys (
+            id                   TEXT PRIMARY KEY,   -- verification method id, <did>#<multibase>
+            did                  TEXT NOT NULL,
+            public_key_multibase TEXT NOT NULL,
+            first_seen_at        TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE INDEX IF NOT EXISTS idx_contact_keys_did ON contact_keys(did);
+    ",
+        down:    Some("
+        DROP INDEX IF EXISTS idx_contact_keys_did;
+        DROP TABLE IF EXISTS contact_keys;
+        DROP TABLE IF EXISTS contacts;
+    "),
+    },
+    Migration {
+        version: 14,
+        name:    "private_notes",
+        up:      "
+        -- Sealed with a key derived from the DID key; local only, never synced
+        ALTER TABLE documents ADD COLUMN private_notes TEXT;
+        ALTER TABLE contacts ADD COLUMN private_notes TEXT;
+    ",
+        down:    Some("
+        ALTER TABLE contacts DROP COLUMN private_notes;
+        ALTER TABLE documents DROP COLUMN private_notes;
+    "),
+    },
+    Migration {
+        version: 15,
+        name:    "tenants",
+        up:      "
+        -- Organizations this install works in; local_identity.tenant_id is the
+        -- active one. Each keeps its own pull cursor.
+        CREATE TABLE IF NOT EXISTS tenants (
+            id           TEXT PRIMARY KEY,
+            name         TEXT,
+            last_sync_at TEXT,
+            created_at   TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        INSERT OR IGNORE INTO tenants (id, last_sync_at)
+            SELECT tenant_id, last_sync_at FROM local_identity WHERE id = 'singleton';
+        INSERT OR IGNORE INTO tenants (id) SELECT DISTINCT tenant_id FROM documents;
+
+        -- Queued ops belong to the tenant that was active when they were queued
+        ALTER TABLE offline_operations ADD COLUMN tenant_id TEXT NOT NULL DEFAULT 'default';
+        UPDATE offline_operations
+            SET tenant_id = COALESCE((SELECT tenant_id FROM local_identity WHERE id = 'singleton'), 'default');
+
+        CREATE INDEX IF NOT EXISTS idx_docs_tenant_live_created
+            ON documents(tenant_id, created_at DESC, id DESC) WHERE status != 'deleted';
+        CREATE INDEX IF NOT EXISTS idx_ops_tenant_status_created
+            ON offline_operations(tenant_id, status, created_at);
+    ",
+        down:    Some("
+        DROP INDEX IF EXISTS idx_ops_tenant_status_created;
+        DROP INDEX IF EXISTS idx_docs_tenant_live_created;
+        ALTER TABLE offline_operations DROP COLUMN tenant_id;
+        DROP TABLE IF EXISTS tenants;
+    "),
+    },
+    Migration {
+        version: 16,
+        name:    "collection_acl",
+        up:      "
+        -- Who may see or edit a collection; documents inherit their collection's
+        CREATE TABLE IF NOT EXISTS collection_acl (
+            collection_id TEXT NOT NULL,
+            grantee       TEXT NOT NULL,   -- DID or user id
+            role          TEXT NOT NULL,   -- viewer | editor | owner
+            PRIMARY KEY (collection_id, grantee)
+        );
+
+        -- One ACL change pushed to every contained document as a single batch
+        CREATE TABLE IF NOT EXISTS acl_changesets (
+            id            TEXT PRIMARY KEY,
+            collection_id TEXT NOT NULL,
+            acl           TEXT NOT NULL,   -- JSON [{grantee, role}] at the time of the change
+            created_at    TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at    TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE TABLE IF NOT EXISTS acl_changeset_documents (
+            changeset_id TEXT NOT NULL,
+            document_id  TEXT NOT NULL,
+            status       TEXT NOT NULL DEFAULT 'pending',   -- pending | applied | failed
+            error        TEXT,
+            PRIMARY KEY (changeset_id, document_id)
+        );
+    ",
+        down:    Some("
+        DROP TABLE IF EXISTS acl_changeset_documents;
+        DROP TABLE IF EXISTS acl_changesets;
+        DROP TABLE IF EXISTS collection_acl;
+    "),
+    },
+    Migration {
+        version: 17,
+        name:    "metadata_schemas",
+        up:      "
+        -- JSON Schema a document's metadata must satisfy, per tenant and content
+        -- type ('image/png', 'image/*' or '*')
+        CREATE TABLE IF NOT EXISTS metadata_schemas (
+            tenant_id    TEXT NOT NULL,
+            content_type TEXT NOT NULL,
+            schema       TEXT NOT NULL,
+            updated_at   TEXT NOT NULL DEFAULT (datetime('now')),
+            PRIMARY KEY (tenant_id, content_type)
+        );
+    ",
+        down:    Some("
+        DROP TABLE IF EXISTS metadata_schemas;
+    "),
+    },
+    Migration {
+        version: 18,
+        name:    "annotations",
+        up:      "
+        -- Comments and highlights on documents; synced, so deletes are tombstones
+        CREATE TABLE IF NOT EXISTS annotations (
+            id         TEXT PRIMARY KEY,
+            doc_id     TEXT NOT NULL,
+            kind       TEXT NOT NULL,   -- comment | highlight
+            anchor     TEXT NOT NULL DEFAULT '{}',   -- JSON {page, start, end, quote}
+            body       TEXT NOT NULL DEFAULT '',
+            author_did TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            deleted_at TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_annotations_doc
+            ON annotations(doc_id, created_at) WHERE deleted_at IS NULL;
+    ",
+        down:    Some("
+        DROP INDEX IF EXISTS idx_annotations_doc;
+        DROP TABLE IF EXISTS annotations;
+    "),
+    },
+    Migration {
+        version: 19,
+        name:    "document_metadata_kv",
+        up:      "
+        -- Metadata keys promoted to an index, and their values per document
+        -- (scalars only, as text; booleans as 'true'/'false')
+        CREATE TABLE IF NOT EXISTS metadata_index_keys (
+            key        TEXT PRIMARY KEY,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE TABLE IF NOT EXISTS document_metadata_kv (
+            key         TEXT NOT NULL,
+            value       TEXT NOT NULL,
+            document_id TEXT NOT NULL,
+            PRIMARY KEY (key, value, document_id)
+        ) WITHOUT ROWID;
+        CREATE INDEX IF NOT EXISTS idx_document_metadata_kv_doc ON document_metadata_kv(document_id);
+
+        CREATE TRIGGER IF NOT EXISTS docs_metadata_kv_insert AFTER INSERT ON documents BEGIN
+            INSERT OR IGNORE INTO document_metadata_kv (key, value, document_id)
+                SELECT j.key, CASE j.type WHEN 'true' THEN 'true' WHEN 'false' THEN 'false' ELSE CAST(j.value AS TEXT) END, new.id
+                FROM json_each(CASE WHEN json_valid(new.metadata) THEN new.metadata ELSE '{}' END) j
+                WHERE j.key IN (SELECT key FROM metadata_index_keys) AND j.type NOT IN ('object', 'array', 'null');
+        END;
+        CREATE TRIGGER IF NOT EXISTS docs_metadata_kv_update AFTER UPDATE OF metadata ON documents BEGIN
+            DELETE FROM document_metadata_kv WHERE document_id = old.id;
+            INSERT OR IGNORE INTO document_metadata_kv (key, value, document_id)
+                SELECT j.key, CASE j.type WHEN 'true' THEN 'true' WHEN 'false' THEN 'false' ELSE CAST(j.value AS TEXT) END, new.id
+                FROM json_each(CASE WHEN json_valid(new.metadata) THEN new.metadata ELSE '{}' END) j
+                WHERE j.key IN (SELECT key FROM metadata_index_keys) AND j.type NOT IN ('object', 'array', 'null');
+        END;
+        CREATE TRIGGER IF NOT EXISTS docs_metadata_kv_delete AFTER DELETE ON documents BEGIN
+            DELETE FROM document_metadata_kv WHERE document_id = old.id;
+        END;
+    ",
+        down:    Some("
+        DROP TRIGGER IF EXISTS docs_metadata_kv_delete;
+        DROP TRIGGER IF EXISTS docs_metadata_kv_update;
+        DROP TRIGGER IF EXISTS docs_metadata_kv_insert;
+        DROP INDEX IF EXISTS idx_document_metadata_kv_doc;
+        DROP TABLE IF EXISTS document_metadata_kv;
+        DROP TABLE IF EXISTS metadata_index_keys;
+    "),
+    },
+    Migration {
+        version: 20,
+        name:    "document_access",
+        up:      "
+        -- When each document was last opened and how often. Kept out of
+        -- documents so a read doesn't fire its update triggers.
+        CREATE TABLE IF NOT EXISTS document_access (
+            document_id      TEXT PRIMARY KEY,
+            last_accessed_at TEXT NOT NULL,
+            access_count     INTEGER NOT NULL DEFAULT 0
+        );
+        CREATE INDEX IF NOT EXISTS idx_document_access_recent ON document_access(last_accessed_at DESC);
+
+        CREATE TRIGGER IF NOT EXISTS docs_access_delete AFTER DELETE ON documents BEGIN
+            DELETE FROM document_access WHERE document_id = old.id;
+        END;
+    ",
+        down:    Some("
+        DROP TRIGGER IF EXISTS docs_access_delete;
+        DROP INDEX IF EXISTS idx_document_access_recent;
+        DROP TABLE IF EXISTS document_access;
+    "),
+    },
+    Migration {
+        version: 21,
+        name:    "annotations_fts",
+        up:      "
+        -- Full-text index over live annotation bodies, so search can rank
+        -- documents by their comments too. Tombstones are left out.
+        CREATE VIRTUAL TABLE IF NOT EXISTS annotations_fts USING fts5(
+            body,
+            content       = 'annotations',
+            content_rowid = 'rowid'
+        );
+
+        CREATE TRIGGER IF NOT EXISTS annotations_fts_insert AFTER INSERT ON annotations BEGIN
+            INSERT INTO annotations_fts(rowid, body) SELECT new.rowid, new.body WHERE new.deleted_at IS NULL;
+        END;
+        CREATE TRIGGER IF NOT EXISTS annotations_fts_update AFTER UPDATE ON annotations BEGIN
+            INSERT INTO annotations_fts(annotations_fts, rowid, body)
+                SELECT 'delete', old.rowid, old.body WHERE old.deleted_at IS NULL;
+            INSERT INTO annotations_fts(rowid, body) SELECT new.rowid, new.body WHERE new.deleted_at IS NULL;
+        END;
+        CREATE TRIGGER IF NOT EXISTS annotations_fts_delete AFTER DELETE ON annotations BEGIN
+            INSERT INTO annotations_fts(annotations_fts, rowid, body)
+                SELECT 'delete', old.rowid, old.body WHERE old.deleted_at IS NULL;
+        END;
+
+        INSERT INTO annotations_fts(rowid, body) SELECT rowid, body FROM annotations WHERE deleted_at IS NULL;
+    ",
+        down:    Some("
+        DROP TRIGGER IF EXISTS annotations_fts_delete;
+        DROP TRIGGER IF EXISTS annotations_fts_update;
+        DROP TRIGGER IF EXISTS annotations_fts_insert;
+        DROP TABLE IF EXISTS annotations_fts;
+    "),
+    },
+    Migration {
+        version: 22,
+        name:    "is_cached_locally",
+        up:      "
+        -- 0 once the local copy has been evicted to stay under the storage
+        -- quota (or was never downloaded); the row and metadata stay
+        ALTER TABLE documents ADD COLUMN is_cached_locally INTEGER NOT NULL DEFAULT 1;
+        UPDATE documents SET is_cached_locally = 0 WHERE COALESCE(local_path, '') = '';
+    ",
+        down:    Some("
+        ALTER TABLE documents DROP COLUMN is_cached_locally;
+    "),
+    },
+    Migration {
+        version: 23,
+        name:    "activity_events",
+        up:      "
+        -- Cached workspace events from the server (member added, document
+        -- shared, …); local events come from offline_operations
+        CREATE TABLE IF NOT EXISTS activity_events (
+            id          TEXT PRIMARY KEY,
+            tenant_id   TEXT NOT NULL,
+            kind        TEXT NOT NULL,
+            actor       TEXT,
+            document_id TEXT,
+            data        TEXT NOT NULL DEFAULT '{}',
+            occurred_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_activity_tenant_time
+            ON activity_events(tenant_id, occurred_at DESC, id DESC);
+
+        -- Where the next activity fetch for each tenant resumes
+        ALTER TABLE tenants ADD COLUMN activity_cursor TEXT;
+    ",
+        down:    Some("
+        ALTER TABLE tenants DROP COLUMN activity_cursor;
+        DROP INDEX IF EXISTS idx_activity_tenant_time;
+        DROP TABLE IF EXISTS activity_events;
+    "),
+    },
+    Migration {
+        version: 24,
+        name:    "watches",
+        up:      "
+        -- Documents the user wants to hear about when they change elsewhere
+        CREATE TABLE IF NOT EXISTS watches (
+            document_id     TEXT PRIMARY KEY,
+            created_at      TEXT NOT NULL DEFAULT (datetime('now')),
+            last_changed_at TEXT
+        );
+
+        CREATE TRIGGER IF NOT EXISTS docs_watches_delete AFTER DELETE ON documents BEGIN
+            DELETE FROM watches WHERE document_id = old.id;
+        END;
+    ",
+        down:    Some("
+        DROP TRIGGER IF EXISTS docs_watches_delete;
+        DROP TABLE IF EXISTS watches;
+    "),
+    },
+    Migration {
+        version: 25,
+        name:    "full_text_path",
+        up:      "
+        -- Blob holding the whole extracted text when text_content was cut
+        -- down to StorageSettings::max_text_bytes
+        ALTER TABLE documents ADD COLUMN full_text_path TEXT;
+    ",
+        down:    Some("
+        ALTER TABLE documents DROP COLUMN full_text_path;
+    "),
+    },
+    Migration {
+        version: 26,
+        name:    "blobs",
+        up:      "
+        -- One row per distinct content: how many documents (of any status or
+        -- tenant) hold it. Kept by triggers; blob files are shared by hash.
+        CREATE TABLE IF NOT EXISTS blobs (
+            content_hash TEXT PRIMARY KEY,
+            size         INTEGER,
+            ref_count    INTEGER NOT NULL DEFAULT 0,
+            created_at   TEXT NOT NULL DEFAULT (datetime('now'))
+        ) WITHOUT ROWID;
+
+        INSERT OR IGNORE INTO blobs (content_hash, size, ref_count)
+            SELECT content_hash, MAX(file_size), COUNT(*) FROM documents
+            WHERE COALESCE(content_hash, '') != '' GROUP BY content_hash;
+
+        CREATE TRIGGER IF NOT EXISTS docs_blobs_insert AFTER INSERT ON documents
+        WHEN COALESCE(new.content_hash, '') != '' BEGIN
+            INSERT OR IGNORE INTO blobs (content_hash, size) VALUES (new.content_hash, new.file_size);
+            UPDATE blobs SET ref_count = ref_count + 1 WHERE content_hash = new.content_hash;
+        END;
+        CREATE TRIGGER IF NOT EXISTS docs_blobs_delete AFTER DELETE ON documents
+        WHEN COALESCE(old.content_hash, '') != '' BEGIN
+            UPDATE blobs SET ref_count = ref_count - 1 WHERE content_hash = old.content_hash;
+        END;
+        CREATE TRIGGER IF NOT EXISTS docs_blobs_update AFTER UPDATE OF content_hash ON documents
+        WHEN COALESCE(old.content_hash, '') IS NOT COALESCE(new.content_hash, '') BEGIN
+            UPDATE blobs SET ref_count = ref_count - 1 WHERE content_hash = old.content_hash;
+            INSERT OR IGNORE INTO blobs (content_hash, size)
+                SELECT new.content_hash, new.file_size WHERE COALESCE(new.content_hash, '') != '';
+            UPDATE blobs SET ref_count = ref_count + 1 WHERE content_hash = new.content_hash;
+        END;
+
+        -- Finding a copy the server already holds before uploading
+        CREATE INDEX IF NOT EXISTS idx_docs_tenant_hash ON documents(tenant_id, content_hash);
+    ",
+        down:    Some("
+        DROP INDEX IF EXISTS idx_docs_tenant_hash;
+        DROP TRIGGER IF EXISTS docs_blobs_update;
+        DROP TRIGGER IF EXISTS docs_blobs_delete;
+        DROP TRIGGER IF EXISTS docs_blobs_insert;
+        DROP TABLE IF EXISTS blobs;
+    "),
+    },
+    Migration {
+        version: 27,
+        name:    "compression",
+        up:      "
+        -- How the local copy at local_path is stored: 'zstd', or as is when
+        -- NULL or 'none' (compressing it didn't pay off)
+        ALTER TABLE documents ADD COLUMN compression TEXT;
+    ",
+        down:    Some("
+        ALTER TABLE documents DROP COLUMN compression;
+    "),
+    },
+    Migration {
+        version: 28,
+        name:    "ocr_queue",
+        up:      "
+        -- Documents waiting for (or done with) OCR; see ocr.rs
+        CREATE TABLE IF NOT EXISTS ocr_queue (
+            document_id TEXT PRIMARY KEY,
+            status      TEXT NOT NULL DEFAULT 'pending',   -- pending | running | done | failed | skipped
+            attempts    INTEGER NOT NULL DEFAULT 0,
+            chars       INTEGER,
+            error       TEXT,
+            created_at  TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at  TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE INDEX IF NOT EXISTS idx_ocr_queue_status ON ocr_queue(status, created_at);
+
+        CREATE TRIGGER IF NOT EXISTS docs_ocr_queue_delete AFTER DELETE ON documents BEGIN
+            DELETE FROM ocr_queue WHERE document_id = old.id;
+        END;
+    ",
+        down:    Some("
+        DROP TRIGGER IF EXISTS docs_ocr_queue_delete;
+        DROP INDEX IF EXISTS idx_ocr_queue_status;
+        DROP TABLE IF EXISTS ocr_queue;
+    "),
+    },
+    Migration {
+        version: 29,
+        name:    "media_columns",
+        up:      "
+        -- Common fields of metadata.media (extract/media.rs), for filtering
+        ALTER TABLE documents ADD COLUMN media_width INTEGER GENERATED ALWAYS AS
+            (CASE WHEN json_valid(metadata) THEN json_extract(metadata, '$.media.width') END) VIRTUAL;
+        ALTER TABLE documents ADD COLUMN media_height INTEGER GENERATED ALWAYS AS
+            (CASE WHEN json_valid(metadata) THEN json_extract(metadata, '$.media.height') END) VIRTUAL;
+        ALTER TABLE documents ADD COLUMN media_duration REAL GENERATED ALWAYS AS
+            (CASE WHEN json_valid(metadata) THEN json_extract(metadata, '$.media.duration_secs') END) VIRTUAL;
+        ALTER TABLE documents ADD COLUMN media_captured_at TEXT GENERATED ALWAYS AS
+            (CASE WHEN json_valid(metadata) THEN json_extract(metadata, '$.media.captured_at') END) VIRTUAL;
+        CREATE INDEX IF NOT EXISTS idx_documents_media_captured
+            ON documents(media_captured_at) WHERE media_captured_at IS NOT NULL;
+    ",
+        down:    Some("
+        DROP INDEX IF EXISTS idx_documents_media_captured;
+        ALTER TABLE documents DROP COLUMN media_captured_at;
+        ALTER TABLE documents DROP COLUMN media_duration;
+        ALTER TABLE documents DROP COLUMN media_height;
+        ALTER TABLE documents DROP COLUMN media_width;
+    "),
+    },
+    Migration {
+        version: 30,
+        name:    "sync_conflicts",
+        up:      "
+        -- Server changes held back because the local copy had unsent changes;
+        -- see db/conflicts.rs. At most one open conflict per document.
+        CREATE TABLE IF NOT EXISTS sync_conflicts (
+            id          TEXT PRIMARY KEY,
+            document_id TEXT NOT NULL,
+            remote      TEXT NOT NULL DEFAULT '{}',    -- the pulled change's data
+            resolution  TEXT,                          -- keep_local | keep_remote | keep_both
+            created_at  TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at  TEXT NOT NULL DEFAULT (datetime('now')),
+            resolved_at TEXT
+        );
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_sync_conflicts_open
+            ON sync_conflicts(document_id) WHERE resolved_at IS NULL;
+
+        CREATE TRIGGER IF NOT EXISTS docs_sync_conflicts_delete AFTER DELETE ON documents BEGIN
+            DELETE FROM sync_conflicts WHERE document_id = old.id;
+        END;
+    ",
+        down:    Some("
+        DROP TRIGGER IF EXISTS docs_sync_conflicts_delete;
+        DROP INDEX IF EXISTS idx_sync_conflicts_open;
+        DROP TABLE IF EXISTS sync_conflicts;
+    "),
+    },
+    Migration {
+        version: 31,
+        name:    "account_imports",
+        up:      "
+        -- What migrate_from_account has copied so far, so an interrupted run
+        -- resumes; see sync/migrate.rs.
+        CREATE TABLE IF NOT EXISTS account_imports (
+            source      TEXT NOT NULL,                 -- server URL copied from
+            remote_id   TEXT NOT NULL,                 -- id on that server
+            kind        TEXT NOT NULL,                 -- document | collection
+            local_id    TEXT NOT NULL,
+            imported_at TEXT NOT NULL DEFAULT (datetime('now')),
+            PRIMARY KEY (source, remote_id)
+        );
+    ",
+        down:    Some("
+        DROP TABLE IF EXISTS account_imports;
+    "),
+    },
+    Migration {
+        version: 32,
+        name:    "document_history",
+        up:      "
+        -- Superseded document states, for get_documents_as_of (db/history.rs).
+        -- Each row is a document as it was until replaced_at. Sync bookkeeping
+        -- doesn't make a version; only the fields below and deletion do.
+        CREATE TABLE IF NOT EXISTS document_versions (
+            id            INTEGER PRIMARY KEY AUTOINCREMENT,
+            document_id   TEXT NOT NULL,
+            filename      TEXT NOT NULL,
+            content_type  TEXT,
+            file_size     INTEGER,
+            content_hash  TEXT,
+            collection_id TEXT,
+            metadata      TEXT NOT NULL DEFAULT '{}',
+            trashed_at    TEXT,
+            deleted       INTEGER NOT NULL DEFAULT 0,
+            replaced_at   TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE INDEX IF NOT EXISTS idx_document_versions_doc
+            ON document_versions(document_id, replaced_at);
+
+        CREATE TRIGGER IF NOT EXISTS docs_versions_update
+        AFTER UPDATE OF filename, content_type, file_size, content_hash, collection_id,
+                        metadata, trashed_at, status ON documents
+        WHEN old.filename      IS NOT new.filename
+          OR old.content_type  IS NOT new.content_type
+          OR old.file_size     IS NOT new.file_size
+          OR old.content_hash  IS NOT new.content_hash
+          OR old.collection_id IS NOT new.collection_id
+          OR old.metadata      IS NOT new.metadata
+          OR old.trashed_at    IS NOT new.trashed_at
+          OR (old.status = 'deleted') IS NOT (new.status = 'deleted')
+        BEGIN
+            INSERT INTO document_versions
+                (document_id, filename, content_type, file_size, content_hash,
+                 collection_id, metadata, trashed_at, deleted)
+            VALUES (old.id, old.filename, old.content_type, old.file_size, old.content_hash,
+                    old.collection_id, old.metadata, old.trashed_at, old.status = 'deleted');
+        END;
+
+        -- When each tag was on each document. Tags already set count from the
+        -- document's creation.
+        CREATE TABLE IF NOT EXISTS document_tag_history (
+            document_id TEXT NOT NULL,
+            tag_id      INTEGER NOT NULL,
+            added_at    TEXT NOT NULL DEFAULT (datetime('now')),
+            removed_at  TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_document_tag_history_doc
+            ON document_tag_history(document_id, added_at);
+        INSERT INTO document_tag_history (document_id, tag_id, added_at)
+            SELECT dt.document_id, dt.tag_id, d.created_at
+            FROM document_tags dt JOIN documents d ON d.id = dt.document_id;
+
+        CREATE TRIGGER IF NOT EXISTS doc_tags_history_insert AFTER INSERT ON document_tags BEGIN
+            INSERT INTO document_tag_history (document_id, tag_id) VALUES (new.document_id, new.tag_id);
+        END;
+        CREATE TRIGGER IF NOT EXISTS doc_tags_history_delete AFTER DELETE ON document_tags BEGIN
+            UPDATE document_tag_history SET removed_at = datetime('now')
+            WHERE document_id = old.document_id AND tag_id = old.tag_id AND removed_at IS NULL;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS docs_history_delete AFTER DELETE ON documents BEGIN
+            DELETE FROM document_versions    WHERE document_id = old.id;
+            DELETE FROM document_tag_history WHERE document_id = old.id;
+        END;
+    ",
+        down:    Some("
+        DROP TRIGGER IF EXISTS docs_history_delete;
+        DROP TRIGGER IF EXISTS doc_tags_history_delete;
+        DROP TRIGGER IF EXISTS doc_tags_history_insert;
+        DROP INDEX IF EXISTS idx_document_tag_history_doc;
+        DROP TABLE IF EXISTS document_tag_history;
+        DROP TRIGGER IF EXISTS docs_versions_update;
+        DROP INDEX IF EXISTS idx_document_versions_doc;
+        DROP TABLE IF EXISTS document_versions;
+    "),
+    },
+    Migration {
+        version: 33,
+        name:    "sync_filters",
+        up:      "
+        -- Each tenant's SyncFilter (JSON) and the id the server gave it
+        ALTER TABLE tenants ADD COLUMN sync_filter TEXT;
+        ALTER TABLE tenants ADD COLUMN sync_filter_id TEXT;
+    ",
+        down:    Some("
+        ALTER TABLE tenants DROP COLUMN sync_filter_id;
+        ALTER TABLE tenants DROP COLUMN sync_filter;
+    "),
+    },
+    Migration {
+        version: 34,
+        name:    "folder_mirrors",
+        up:      "
+        -- Collections mirrored to a folder on disk; see mirror.rs
+        CREATE TABLE IF NOT EXISTS folder_mirrors (
+            collection_id     TEXT PRIMARY KEY,
+            path              TEXT NOT NULL UNIQUE,
+            propagate_deletes INTEGER NOT NULL DEFAULT 0,
+            created_at        TEXT NOT NULL DEFAULT (datetime('now')),
+            last_synced_at    TEXT,
+            last_error        TEXT
+        );
+
+        -- The file each mirrored document has in its folder, as last seen
+        CREATE TABLE IF NOT EXISTS mirror_files (
+            collection_id TEXT NOT NULL,
+            document_id   TEXT NOT NULL,
+            name          TEXT NOT NULL,               -- file name in the folder
+            content_hash  TEXT NOT NULL,
+            size          INTEGER NOT NULL,
+            mtime_ms      INTEGER NOT NULL,
+            PRIMARY KEY (collection_id, document_id)
+        );
+
+        CREATE TRIGGER IF NOT EXISTS collections_mirror_delete AFTER DELETE ON collections BEGIN
+            DELETE FROM folder_mirrors WHERE collection_id = old.id;
+        END;
+        CREATE TRIGGER IF NOT EXISTS folder_mirrors_delete AFTER DELETE ON folder_mirrors BEGIN
+            DELETE FROM mirror_files WHERE collection_id = old.collection_id;
+        END;
+    ",
+        down:    Some("
+        DROP TRIGGER IF EXISTS folder_mirrors_delete;
+        DROP TRIGGER IF EXISTS collections_mirror_delete;
+        DROP TABLE IF EXISTS mirror_files;
+        DROP TABLE IF EXISTS folder_mirrors;
+    "),
+    },
+    Migration {
+        version: 35,
+        name:    "staged_blobs",
+        up:      "
+        -- Blobs stored by store_file that no document points at yet; see
+        -- storage/cas.rs
+        CREATE TABLE IF NOT EXISTS staged_blobs (
+            path      TEXT PRIMARY KEY,
+            hash      TEXT NOT NULL,
+            size      INTEGER NOT NULL,
+            staged_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+    ",
+        down:    Some("
+        DROP TABLE IF EXISTS staged_blobs;
+    "),
+    },
+    Migration {
+        version: 36,
+        name:    "token_expiry",
+        up:      "
+        -- Unix seconds; for opaque access tokens, which carry no exp claim
+        -- (see oauth.rs)
+        ALTER TABLE local_identity ADD COLUMN token_expires_at INTEGER;
+    ",
+        down:    Some("
+        ALTER TABLE local_identity DROP COLUMN token_expires_at;
+    "),
+    },
+    Migration {
+        version: 37,
+        name:    "accounts",
+        up:      "
+        -- Every server/user this install has signed in to (see db/accounts.rs).
+        -- local_identity holds the active one; the others keep their identity
+        -- here while inactive.
+        CREATE TABLE IF NOT EXISTS accounts (
+            id                 TEXT PRIMARY KEY,
+            server_url         TEXT NOT NULL,
+            username           TEXT NOT NULL,
+            email              TEXT,
+            user_id            TEXT,
+            did                TEXT,
+            did_public_key     TEXT,
+            pleroma_account_id TEXT,
+            tenant_id          TEXT NOT NULL DEFAULT 'default',
+            token_expires_at   INTEGER,
+            created_at         TEXT NOT NULL DEFAULT (datetime('now')),
+            last_used_at       TEXT NOT NULL DEFAULT (datetime('now')),
+            UNIQUE(server_url, username)
+        );
+        ALTER TABLE local_identity ADD COLUMN account_id TEXT;
+
+        -- A tenant belongs to one account. remote_id is the server's id for
+        -- it when the local id had to differ (two servers' 'default')
+        ALTER TABLE tenants ADD COLUMN account_id TEXT;
+        ALTER TABLE tenants ADD COLUMN remote_id TEXT;
+
+        -- The signed-in identity becomes the first account, with every tenant
+        INSERT OR IGNORE INTO accounts
+            (id, server_url, username, email, user_id, did, did_public_key,
+             pleroma_account_id, tenant_id, token_expires_at)
+            SELECT lower(hex(randomblob(16))), server_url, username, email, user_id, did,
+                   did_public_key, pleroma_account_id, tenant_id, token_expires_at
+            FROM local_identity WHERE id = 'singleton' AND username IS NOT NULL;
+        UPDATE local_identity SET account_id = (SELECT id FROM accounts) WHERE id = 'singleton';
+        UPDATE tenants SET account_id = (SELECT id FROM accounts);
+    ",
+        down:    Some("
+        ALTER TABLE tenants DROP COLUMN remote_id;
+        ALTER TABLE tenants DROP COLUMN account_id;
+        ALTER TABLE local_identity DROP COLUMN account_id;
+        DROP TABLE IF EXISTS accounts;
+    "),
+    },
+    Migration {
+        version: 38,
+        name:    "changes_etag",
+        up:      "
+        -- The ETag of the last change set a tenant pulled and applied, sent
+        -- back as If-None-Match so an unchanged set isn't downloaded again
+        ALTER TABLE tenants ADD COLUMN changes_etag TEXT;
+    ",
+        down:    Some("
+        ALTER TABLE tenants DROP COLUMN changes_etag;
+    "),
+    },
+    Migration {
+        version: 39,
+        name:    "server_pins",
+        up:      "
+        -- Pinned TLS certificates (see tls.rs), by host so every account on
+        -- a server shares its pin
+        CREATE TABLE IF NOT EXISTS server_pins (
+            host        TEXT PRIMARY KEY,
+            kind        TEXT NOT NULL CHECK (kind IN ('certificate', 'public_key')),
+            fingerprint TEXT NOT NULL,
+            created_at  TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+    ",
+        down:    Some("
+        DROP TABLE IF EXISTS server_pins;
+    "),
+    },
+    Migration {
+        version: 40,
+        name:    "session_expiry",
+        up:      "
+        -- When the server turned the account's token away and it couldn't be
+        -- refreshed (see oauth.rs); cleared once new tokens are stored
+        ALTER TABLE local_identity ADD COLUMN session_expired_at TEXT;
+        ALTER TABLE accounts ADD COLUMN session_expired_at TEXT;
+    ",
+        down:    Some("
+        ALTER TABLE accounts DROP COLUMN session_expired_at;
+        ALTER TABLE local_identity DROP COLUMN session_expired_at;
+    "),
+    },
+    Migration {
+        version: 41,
+        name:    "fediverse_posts",
+        up:      "
+        -- Documents published to the fediverse (see activitypub.rs)
+        CREATE TABLE IF NOT EXISTS fediverse_posts (
+            id                 TEXT PRIMARY KEY,
+            doc_id             TEXT NOT NULL,
+            status_id          TEXT NOT NULL,
+            url                TEXT,
+            visibility         TEXT NOT NULL CHECK (visibility IN ('public', 'unlisted', 'private', 'direct')),
+            pleroma_account_id TEXT NOT NULL,
+            author_did         TEXT,
+            link               TEXT,
+            created_at         TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE INDEX IF NOT EXISTS idx_fediverse_posts_doc ON fediverse_posts(doc_id, created_at);
+    ",
+        down:    Some("
+        DROP TABLE IF EXISTS fediverse_posts;
+    "),
+    },
+    Migration {
+        version: 42,
+        name:    "shares",
+        up:      "
+        -- Share links handed out (see sharing.rs); permissions is a JSON array
+        CREATE TABLE IF NOT EXISTS shares (
+            id          TEXT PRIMARY KEY,
+            doc_id      TEXT NOT NULL,
+            issuer      TEXT NOT NULL CHECK (issuer IN ('server', 'local')),
+            url         TEXT NOT NULL,
+            permissions TEXT NOT NULL DEFAULT '[]',
+            expires_at  TEXT,
+            revoked_at  TEXT,
+            created_at  TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE INDEX IF NOT EXISTS idx_shares_doc ON shares(doc_id, created_at);
+    ",
+        down:    Some("
+        DROP TABLE IF EXISTS shares;
+    "),
+    },
+    Migration {
+        version: 43,
+        name:    "did_shares",
+        up:      "
+        -- Documents shared with other DIDs, and shared with this account by
+        -- them (see sharing.rs and sync/shares.rs)
+        CREATE TABLE IF NOT EXISTS document_shares (
+            id            TEXT PRIMARY KEY,
+            doc_id        TEXT NOT NULL,
+            recipient_did TEXT NOT NULL,
+            role          TEXT NOT NULL CHECK (role IN ('viewer', 'editor')),
+            revoked_at    TEXT,
+            created_at    TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE INDEX IF NOT EXISTS idx_document_shares_doc ON document_shares(doc_id, recipient_did);
+
+        -- updated_at is the server's, and the cursor for the next fetch
+        CREATE TABLE IF NOT EXISTS shared_with_me (
+            id           TEXT PRIMARY KEY,
+            account_id   TEXT NOT NULL,
+            doc_id       TEXT NOT NULL,
+            owner_did    TEXT,
+            role         TEXT NOT NULL,
+            filename     TEXT NOT NULL,
+            content_type TEXT,
+            metadata     TEXT NOT NULL DEFAULT '{}',
+            shared_at    TEXT NOT NULL,
+            updated_at   TEXT NOT NULL,
+            revoked_at   TEXT,
+            received_at  TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE INDEX IF NOT EXISTS idx_shared_with_me_account ON shared_with_me(account_id, updated_at);
+    ",
+        down:    Some("
+        DROP TABLE IF EXISTS shared_with_me;
+        DROP TABLE IF EXISTS document_shares;
+    "),
+    },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationStep {
+    pub version:   i64,
+    pub name:      String,
+    pub direction: Direction,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationStatus {
+    pub version:     i64,
+    pub name:        String,
+    pub applied_at:  Option<String>,
+    /// None for pending migrations; false means the shipped `up` was edited
+    /// after this database applied it.
+    pub checksum_ok: Option<bool>,
+    pub reversible:  bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SchemaVersion {
+    pub current:    i64,
+    pub latest:     i64,
+    pub migrations: Vec<MigrationStatus>,
+}
+
+/// Checksum of every migration, stored in the DB header (PRAGMA user_version)
+/// once migrations have been applied. Matching on open means the schema is
+/// current and the migration machinery can be skipped entirely.
+fn schema_fingerprint() -> i64 {
+    let mut hasher = Sha256::new();
+    for m in MIGRATIONS {
+        hasher.update(m.version.to_le_bytes());
+        hasher.update(m.name.as_bytes());
+        hasher.update(m.up.as_bytes());
+        hasher.update(m.down.unwrap_or_default().as_bytes());
+    }
+    let digest = hasher.finalize();
+    // user_version is a signed 32-bit int; keep it positive and non-zero
+    (i64::from(u32::from_le_bytes([digest[0], digest[1], digest[2], digest[3]]) & 0x7fff_ffff)).max(1)
+}
+
+/// Per-migration checksum recorded in schema_migrations.
+fn checksum(m: &Migration) -> String {
+    crate::storage::cas::hex(&Sha256::digest(m.up.as_bytes()))
+}
+
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+async fn stored_fingerprint(conn: &Connection) -> Result<i64> {
+    let mut rows = conn.query("PRAGMA user_version", ()).await?;
+    Ok(match rows.next().await? {
+        Some(row) => row.get(0).unwrap_or(0),
+        None      => 0,
+    })
+}
 
 pub async fn run_migrations(conn: &Connection) -> Result<()> {
-    // schema_migrations table
+    let started     = std::time::Instant::now();
+    let fingerprint = schema_fingerprint();
+    if stored_fingerprint(conn).await? == fingerprint {
+        log::debug!("[db] Schema fingerprint matches — skipped migrations in {:?}", started.elapsed());
+        return Ok(());
+    }
+
+    ensure_bookkeeping(conn).await?;
+    verify_checksums(conn).await?;
+    migrate_to(conn, latest_version(), false).await?;
+
+    conn.execute(&format!("PRAGMA user_version = {fingerprint}"), ()).await?;
+    log::debug!("[db] Migrations checked in {:?}", started.elapsed());
+
+    Ok(())
+}
+
+/// Move the schema to `target`, applying `up`s or `down`s in order. Each step
+/// runs in its own transaction. With `dry_run` every step runs inside a single
+/// transaction that is rolled back, which validates the SQL against the real
+/// data without changing anything.
+pub async fn migrate_to(conn: &Connection, target: i64, dry_run: bool) -> Result<Vec<MigrationStep>> {
+    ensure_bookkeeping(conn).await?;
+    let current = current_version(conn).await?;
+    let steps   = plan(current, target)?;
+
+    if dry_run {
+        let tx = conn.transaction().await?;
+        for (m, dir) in &steps {
+            apply(&tx, m, *dir).await
+                .with_context(|| format!("Dry run failed at {} {:?}", m.version, dir))?;
+        }
+        tx.rollback().await?;
+    } else {
+        for (m, dir) in &steps {
+            let tx = conn.transaction().await?;
+            apply(&tx, m, *dir).await
+                .with_context(|| format!("Migration {} ({}) {:?} failed", m.version, m.name, dir))?;
+            tx.commit().await?;
+            log::info!("[db] Migration {} ({}) {:?}", m.version, m.name, dir);
+        }
+        if steps.iter().any(|(_, d)| *d == Direction::Down) {
+            // Invalidate the fingerprint so the next open re-applies what was rolled back
+            conn.execute("PRAGMA user_version = 0", ()).await?;
+        }
+    }
+
+    Ok(steps.iter().map(|(m, dir)| MigrationStep {
+        version:   m.version,
+        name:      m.name.to_string(),
+        direction: *dir,
+    }).collect())
+}
+
+pub async fn schema_version(conn: &Connection) -> Result<SchemaVersion> {
+    ensure_bookkeeping(conn).await?;
+    let applied = applied(conn).await?;
+
+    let migrations = MIGRATIONS.iter().map(|m| {
+        let row = applied.iter().find(|(v, _, _)| *v == m.version);
+        MigrationStatus {
+            version:     m.version,
+            name:        m.name.to_string(),
+            applied_at:  row.map(|(_, _, at)| at.clone()),
+            checksum_ok: row.map(|(_, sum, _)| sum.as_deref().is_none_or(|s| s == checksum(m))),
+            reversible:  m.down.is_some(),
+        }
+    }).collect();
+
+    Ok(SchemaVersion {
+        current: current_version(conn).await?,
+        latest:  latest_version(),
+        migrations,
+    })
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+fn plan(current: i64, target: i64) -> Result<Vec<(&'static Migration, Direction)>> {
+    if target > latest_version() || target < 0 {
+        bail!("Unknown schema version {target} (latest is {})", latest_version());
+    }
+    if target >= current {
+        return Ok(MIGRATIONS.iter()
+            .filter(|m| m.version > current && m.version <= target)
+            .map(|m| (m, Direction::Up))
+            .collect());
+    }
+
+    let steps: Vec<_> = MIGRATIONS.iter().rev()
+        .filter(|m| m.version <= current && m.version > target)
+        .map(|m| (m, Direction::Down))
+        .collect();
+    if let Some((m, _)) = steps.iter().find(|(m, _)| m.down.is_none()) {
+        bail!("Migration {} ({}) cannot be reverted", m.version, m.name);
+    }
+    Ok(steps)
+}
+
+async fn apply(conn: &Connection, m: &Migration, dir: Direction) -> Result<()> {
+    match dir {
+        Direction::Up => {
+            conn.execute_batch(m.up).await?;
+            conn.execute(
+                "INSERT INTO schema_migrations (version, name, checksum) VALUES (?1, ?2, ?3)",
+                libsql::params![m.version, m.name, checksum(m)],
+            ).await?;
+        }
+        Direction::Down => {
+            conn.execute_batch(m.down.context("Irreversible migration")?).await?;
+            conn.execute(
+                "DELETE FROM schema_migrations WHERE version = ?1",
+                libsql::params![m.version],
+            ).await?;
+        }
+    }
+    Ok(())
+}
+
+/// schema_migrations gained name + checksum columns after v8 shipped; older
+/// databases get them added here.
+async fn ensure_bookkeeping(conn: &Connection) -> Result<()> {
     conn.execute_batch("
         CREATE TABLE IF NOT EXISTS schema_migrations (
             version    INTEGER PRIMARY KEY,
@@ -11,106 +1340,62 @@ pub async fn run_migrations(conn: &Connection) -> Result<()> {
         );
     ").await?;
 
-    let mut rows = conn.query(
-        "SELECT COALESCE(MAX(version), 0) FROM schema_migrations", ()
-    ).await?;
-
-    let version: i64 = if let Some(row) = rows.next().await? {
-        row.get(0).unwrap_or(0)
-    } else {
-        0
-    };
-
-    if version < 1 {
-        // libsql execute_batch runs the whole string as a transaction
-        conn.execute_batch("
-            CREATE TABLE IF NOT EXISTS local_identity (
-                id                  TEXT PRIMARY KEY DEFAULT 'singleton',
-                user_id             TEXT,
-                tenant_id           TEXT NOT NULL DEFAULT 'default',
-                username            TEXT,
-                email               TEXT,
-                did                 TEXT UNIQUE,
-                did_public_key      TEXT,
-                pleroma_account_id  TEXT,
-                server_url          TEXT NOT NULL DEFAULT 'http://localhost:4000',
-                last_sync_at        TEXT,
-                created_at          TEXT NOT NULL DEFAULT (datetime('now')),
-                updated_at          TEXT NOT NULL DEFAULT (datetime('now'))
-            );
-
-            CREATE TABLE IF NOT EXISTS documents (
-                id              TEXT PRIMARY KEY,
-                user_id         TEXT NOT NULL,
-                tenant_id       TEXT NOT NULL DEFAULT 'default',
-                filename        TEXT NOT NULL,
-                content_type    TEXT,
-                file_size       INTEGER,
-                content_hash    TEXT,
-                local_path      TEXT,
-                object_key      TEXT,
-                text_content    TEXT,
-                metadata        TEXT NOT NULL DEFAULT '{}',
-                tags            TEXT NOT NULL DEFAULT '[]',
-                status          TEXT NOT NULL DEFAULT 'local',
-                local_version   INTEGER NOT NULL DEFAULT 1,
-                server_version  INTEGER NOT NULL DEFAULT 0,
-                is_synced       INTEGER NOT NULL DEFAULT 0,
-                needs_upload    INTEGER NOT NULL DEFAULT 1,
-                needs_download  INTEGER NOT NULL DEFAULT 0,
-                sync_error      TEXT,
-                last_synced_at  TEXT,
-                created_at      TEXT NOT NULL DEFAULT (datetime('now')),
-                updated_at      TEXT NOT NULL DEFAULT (datetime('now'))
-            );
-
-            CREATE INDEX IF NOT EXISTS idx_docs_user   ON documents(user_id);
-            CREATE INDEX IF NOT EXISTS idx_docs_status ON documents(status);
-            CREATE INDEX IF NOT EXISTS idx_docs_upload ON documents(needs_upload)
-                WHERE needs_upload = 1;
-
-            CREATE VIRTUAL TABLE IF NOT EXISTS documents_fts USING fts5(
-                id            UNINDEXED,
-                filename,
-                text_content,
-                content       = 'documents',
-                content_rowid = 'rowid'
-            );
-
-            CREATE TRIGGER IF NOT EXISTS docs_fts_insert AFTER INSERT ON documents BEGIN
-                INSERT INTO documents_fts(id, filename, text_content)
-                VALUES (new.id, new.filename, new.text_content);
-            END;
-
-            CREATE TRIGGER IF NOT EXISTS docs_fts_update AFTER UPDATE ON documents BEGIN
-                UPDATE documents_fts
-                SET    filename     = new.filename,
-                       text_content = new.text_content
-                WHERE  id = new.id;
-            END;
-
-            CREATE TRIGGER IF NOT EXISTS docs_fts_delete AFTER DELETE ON documents BEGIN
-                DELETE FROM documents_fts WHERE id = old.id;
-            END;
-
-            CREATE TABLE IF NOT EXISTS offline_operations (
-                id          TEXT PRIMARY KEY,
-                user_id     TEXT NOT NULL,
-                op_type     TEXT NOT NULL,
-                payload     TEXT NOT NULL DEFAULT '{}',
-                status      TEXT NOT NULL DEFAULT 'pending',
-                retry_count INTEGER NOT NULL DEFAULT 0,
-                error_msg   TEXT,
-                created_at  TEXT NOT NULL DEFAULT (datetime('now')),
-                updated_at  TEXT NOT NULL DEFAULT (datetime('now'))
-            );
-
-            CREATE INDEX IF NOT EXISTS idx_ops_status
-                ON offline_operations(user_id, status);
-
-            INSERT INTO schema_migrations(version) VALUES (1);
-        ").await?;
+    let mut columns = Vec::new();
+    let mut rows = conn.query("PRAGMA table_info(schema_migrations)", ()).await?;
+    while let Some(row) = rows.next().await? {
+        if let Ok(Value::Text(name)) = row.get_value(1) { columns.push(name); }
+    }
+    for col in ["name", "checksum"] {
+        if !columns.iter().any(|c| c == col) {
+            conn.execute(&format!("ALTER TABLE schema_migrations ADD COLUMN {col} TEXT"), ()).await?;
+        }
     }
+    Ok(())
+}
 
+/// Fill in checksums for rows recorded before they existed, and warn about
+/// shipped migrations whose `up` has changed since they were applied.
+async fn verify_checksums(conn: &Connection) -> Result<()> {
+    for (version, stored, _) in applied(conn).await? {
+        let Some(m) = MIGRATIONS.iter().find(|m| m.version == version) else {
+            log::warn!("[db] Database has migration {version}, which this build does not know");
+            continue;
+        };
+        match stored {
+            None => {
+                conn.execute(
+                    "UPDATE schema_migrations SET name = ?1, checksum = ?2 WHERE version = ?3",
+                    libsql::params![m.name, checksum(m), version],
+                ).await?;
+            }
+            Some(sum) if sum != checksum(m) => {
+                log::warn!("[db] Migration {version} ({}) changed after it was applied", m.name);
+            }
+            Some(_) => {}
+        }
+    }
     Ok(())
-}
\ No newline at end of file
+}
+
+pub async fn current_version(conn: &Connection) -> Result<i64> {
+    let mut rows = conn.query("SELECT COALESCE(MAX(version), 0) FROM schema_migrations", ()).await?;
+    Ok(match rows.next().await? {
+        Some(row) => row.get(0).unwrap_or(0),
+        None      => 0,
+    })
+}
+
+/// (version, checksum, applied_at) for every applied migration.
+async fn applied(conn: &Connection) -> Result<Vec<(i64, Option<String>, String)>> {
+    let mut rows = conn.query(
+        "SELECT version, checksum, applied_at FROM schema_migrations ORDER BY version", ()
+    ).await?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let version  = row.get::<i64>(0)?;
+        let checksum = match row.get_value(1)? { Value::Text(s) => Some(s), _ => None };
+        let at       = match row.get_value(2)? { Value::Text(s) => s, _ => String::new() };
+        out.push((version, checksum, at));
+    }
+    Ok(out)
+}
@@ -0,0 +1,169 @@
+// src-tauri/src/db/search.rs
+// User search text → FTS5 MATCH expression.
+//
+// Input is split on whitespace and every term is quoted, so punctuation and
+// FTS5 operators typed by the user are matched literally instead of raising
+// syntax errors. `raw` skips this for callers that build FTS5 queries themselves.
+//
+// Fuzzy mode expands each term with close spellings taken from the index
+// vocabulary (documents_fts_vocab), spellfix-style: "recipt" becomes
+// ("recipt"* OR "receipt" OR "recipe").
+use anyhow::Result;
+use libsql::{Connection, Value};
+use serde::{Deserialize, Serialize};
+
+/// Spelling candidates kept per query term in fuzzy mode.
+const FUZZY_CANDIDATES: usize = 8;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchField {
+    #[default]
+    All,
+    Filename,
+    Content,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchOptions {
+    /// Treat every term as a prefix ("repo" matches "report").
+    pub prefix: bool,
+    pub field: SearchField,
+    /// Pass the query to FTS5 untouched (AND/OR/NEAR, column filters, …).
+    pub raw: bool,
+    /// Also match terms within a small edit distance of each query term.
+    pub fuzzy: bool,
+    pub limit: i64,
+    /// Inserted around matches in `snippet` and `filename_highlight`. The
+    /// surrounding text is not escaped — render it as text, not HTML.
+    pub highlight_open: String,
+    pub highlight_close: String,
+    /// Approximate snippet length in tokens (FTS5 caps this at 64).
+    pub snippet_tokens: i64,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            prefix:          true,
+            field:           SearchField::All,
+            raw:             false,
+            fuzzy:           false,
+            limit:           50,
+            highlight_open:  "<mark>".into(),
+            highlight_close: "</mark>".into(),
+            snippet_tokens:  16,
+        }
+    }
+}
+
+/// Build the MATCH expression, or None if the input has nothing searchable.
+pub fn match_expression(input: &str, opts: &SearchOptions) -> Option<String> {
+    if opts.raw {
+        let q = input.trim();
+        return (!q.is_empty()).then(|| q.to_string());
+    }
+
+    let terms: Vec<String> = input
+        .split_whitespace()
+        .filter(|t| t.chars().any(char::is_alphanumeric))
+        .map(|t| term(t, opts.prefix))
+        .collect();
+    restrict(terms, opts.field)
+}
+
+/// Like match_expression, but every term is OR-ed with its closest spellings
+/// from the index vocabulary.
+pub async fn fuzzy_expression(conn: &Connection, input: &str, opts: &SearchOptions) -> Result<Option<String>> {
+    // Split the way the unicode61 tokenizer does, so terms line up with the vocabulary
+    let words: Vec<String> = input
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+
+    let mut groups = Vec::with_capacity(words.len());
+    for word in &words {
+        let mut alternatives = vec![term(word, opts.prefix)];
+        for candidate in spelling_candidates(conn, word).await? {
+            alternatives.push(term(&candidate, false));
+        }
+        groups.push(if alternatives.len() == 1 {
+            alternatives.remove(0)
+        } else {
+            format!("({})", alternatives.join(" OR "))
+        });
+    }
+    Ok(restrict(groups, opts.field))
+}
+
+fn term(t: &str, prefix: bool) -> String {
+    let quoted = format!("\"{}\"", t.replace('"', "\"\""));
+    if prefix { format!("{quoted}*") } else { quoted }
+}
+
+fn restrict(terms: Vec<String>, field: SearchField) -> Option<String> {
+    if terms.is_empty() {
+        return None;
+    }
+    // Explicit AND: FTS5 rejects implicit AND after a parenthesised group
+    let terms = terms.join(" AND ");
+    Some(match field {
+        SearchField::All      => terms,
+        SearchField::Filename => format!("filename : ({terms})"),
+        SearchField::Content  => format!("text_content : ({terms})"),
+    })
+}
+
+/// Allowed typos grow with word length; very short words must match exactly.
+fn max_edits(len: usize) -> usize {
+    match len {
+        0..=3 => 0,
+        4..=6 => 1,
+        _     => 2,
+    }
+}
+
+/// Vocabulary terms within max_edits of `word`, nearest and most common first.
+async fn spelling_candidates(conn: &Connection, word: &str) -> Result<Vec<String>> {
+    let len   = word.chars().count();
+    let edits = max_edits(len);
+    if edits == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut rows = conn.query(
+        "SELECT term, doc FROM documents_fts_vocab
+         WHERE length(term) BETWEEN ?1 AND ?2 AND term != ?3",
+        libsql::params![(len - edits) as i64, (len + edits) as i64, word],
+    ).await?;
+
+    let mut found: Vec<(usize, i64, String)> = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let Value::Text(candidate) = row.get_value(0)? else { continue };
+        let docs = match row.get_value(1)? { Value::Integer(n) => n, _ => 0 };
+        let distance = edit_distance(word, &candidate);
+        if distance <= edits {
+            found.push((distance, -docs, candidate));
+        }
+    }
+    found.sort();
+    found.truncate(FUZZY_CANDIDATES);
+    Ok(found.into_iter().map(|(_, _, t)| t).collect())
+}
+
+/// Levenshtein distance over chars.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
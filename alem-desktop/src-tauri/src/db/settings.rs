@@ -0,0 +1,44 @@
+// src-tauri/src/db/settings.rs
+// Key → JSON value store for user-tunable settings (sync, storage, …).
+use anyhow::Result;
+use libsql::{Connection, Value};
+use serde::{de::DeserializeOwned, Serialize};
+
+pub const SYNC_SETTINGS:        &str = "sync";
+pub const STORAGE_SETTINGS:     &str = "storage";
+pub const MAINTENANCE_SETTINGS: &str = "maintenance";
+pub const SEARCH_SETTINGS:      &str = "search";
+pub const SCAN_SETTINGS:        &str = "scan";
+pub const OCR_SETTINGS:         &str = "ocr";
+pub const MEDIA_SETTINGS:       &str = "media";
+pub const STORAGE_TARGET:       &str = "storage_target";
+pub const IPFS_SETTINGS:        &str = "ipfs";
+pub const NETWORK_SETTINGS:     &str = "network";
+pub const WEBDAV_SETTINGS:      &str = "webdav";
+
+pub async fn get<T: DeserializeOwned>(conn: &Connection, key: &str) -> Result<Option<T>> {
+    let mut rows = conn.query(
+        "SELECT value FROM settings WHERE key = ?1",
+        libsql::params![key],
+    ).await?;
+
+    match rows.next().await? {
+        Some(row) => match row.get_value(0)? {
+            Value::Text(s) => Ok(Some(serde_json::from_str(&s)?)),
+            _ => Ok(None),
+        },
+        None => Ok(None),
+    }
+}
+
+pub async fn set<T: Serialize>(conn: &Connection, key: &str, value: &T) -> Result<()> {
+    conn.execute(
+        "INSERT INTO settings (key, value, updated_at)
+         VALUES (?1, ?2, datetime('now'))
+         ON CONFLICT(key) DO UPDATE SET
+             value      = excluded.value,
+             updated_at = datetime('now')",
+        libsql::params![key, serde_json::to_string(value)?],
+    ).await?;
+    Ok(())
+}
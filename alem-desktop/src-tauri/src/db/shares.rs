@@ -0,0 +1,85 @@
+// src-tauri/src/db/shares.rs
+// Share links handed out (sharing.rs). A server link is checked by the
+// server; this is the list of what was handed out, to show and revoke.
+use anyhow::{anyhow, Result};
+use libsql::Connection;
+
+use super::models::{Columns, ShareIssuer, ShareLink};
+
+impl ShareIssuer {
+    fn as_str(self) -> &'static str {
+        match self {
+            ShareIssuer::Server => "server",
+            ShareIssuer::Local  => "local",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        [ShareIssuer::Server, ShareIssuer::Local].into_iter().find(|i| i.as_str() == s)
+    }
+}
+
+const COLUMNS: &str = "id, doc_id, issuer, url, permissions, expires_at, revoked_at, created_at";
+
+/// Share links of `doc_id`, or of every document, newest first.
+pub async fn list(conn: &Connection, doc_id: Option<&str>) -> Result<Vec<ShareLink>> {
+    let mut rows = conn.query(
+        &format!("SELECT {COLUMNS} FROM shares WHERE ?1 IS NULL OR doc_id = ?1 ORDER BY created_at DESC"),
+        libsql::params![doc_id.map(str::to_string)],
+    ).await?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await? {
+        out.push(row_to_share(&row)?);
+    }
+    Ok(out)
+}
+
+pub async fn get(conn: &Connection, id: &str) -> Result<Option<ShareLink>> {
+    let mut rows = conn.query(
+        &format!("SELECT {COLUMNS} FROM shares WHERE id = ?1"),
+        libsql::params![id],
+    ).await?;
+    rows.next().await?.map(|row| row_to_share(&row)).transpose()
+}
+
+/// Keep `share`; its created_at is set here.
+pub async fn insert(conn: &Connection, share: &ShareLink) -> Result<ShareLink> {
+    conn.execute(
+        &format!("INSERT INTO shares ({COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, NULL, datetime('now'))"),
+        libsql::params![
+            share.id.as_str(),
+            share.doc_id.as_str(),
+            share.issuer.as_str(),
+            share.url.as_str(),
+            serde_json::to_string(&share.permissions)?,
+            share.expires_at.clone(),
+        ],
+    ).await?;
+    get(conn, &share.id).await?.ok_or_else(|| anyhow!("Share {} not stored", share.id))
+}
+
+/// Mark `id` revoked. Returns false when it already was.
+pub async fn revoke(conn: &Connection, id: &str) -> Result<bool> {
+    let n = conn.execute(
+        "UPDATE shares SET revoked_at = datetime('now') WHERE id = ?1 AND revoked_at IS NULL",
+        libsql::params![id],
+    ).await?;
+    Ok(n > 0)
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+fn row_to_share(row: &libsql::Row) -> Result<ShareLink> {
+    let c = Columns::new(row);
+    let issuer: String = c.required("issuer")?;
+    Ok(ShareLink {
+        id:          c.required("id")?,
+        doc_id:      c.required("doc_id")?,
+        issuer:      ShareIssuer::parse(&issuer).ok_or_else(|| anyhow!("Unknown share issuer {issuer}"))?,
+        url:         c.required("url")?,
+        permissions: serde_json::from_str(&c.str("permissions").unwrap_or_default()).unwrap_or_default(),
+        expires_at:  c.str("expires_at"),
+        revoked_at:  c.str("revoked_at"),
+        created_at:  c.str("created_at").unwrap_or_default(),
+    })
+}
@@ -0,0 +1,99 @@
+// src-tauri/src/db/stats.rs
+// Size and health numbers for the diagnostics view, plus the integrity check
+// shared by maintenance and backup verification.
+use anyhow::Result;
+use libsql::{Connection, Value};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DatabaseStats {
+    /// Document rows by sync status ("local", "synced", "deleted", …).
+    pub documents_by_status:        BTreeMap<String, i64>,
+    pub trashed_documents:          i64,
+    /// Database file plus its WAL.
+    pub bytes_on_disk:              u64,
+    /// Free pages inside the file, reclaimable by maintenance.
+    pub free_bytes:                 u64,
+    /// Full-text index segments.
+    pub fts_index_bytes:            u64,
+    /// Queue entries not yet done, by op type.
+    pub queued_ops_by_type:         BTreeMap<String, i64>,
+    pub oldest_pending_op_age_secs: Option<i64>,
+}
+
+/// `db_path` is the main database file; its -wal sits next to it.
+pub async fn collect(conn: &Connection, db_path: &Path) -> Result<DatabaseStats> {
+    let documents_by_status = counts(conn, "SELECT status, COUNT(*) FROM documents GROUP BY status").await?;
+    let queued_ops_by_type  = counts(
+        conn,
+        "SELECT op_type, COUNT(*) FROM offline_operations WHERE status != 'done' GROUP BY op_type",
+    ).await?;
+
+    let trashed_documents = scalar(
+        conn, "SELECT COUNT(*) FROM documents WHERE trashed_at IS NOT NULL AND status != 'deleted'",
+    ).await?.unwrap_or(0);
+    let fts_index_bytes = scalar(
+        conn, "SELECT COALESCE(SUM(length(block)), 0) FROM documents_fts_data",
+    ).await?.unwrap_or(0);
+    let oldest_pending_op_age_secs = scalar(
+        conn,
+        "SELECT CAST(strftime('%s', 'now') - strftime('%s', MIN(created_at)) AS INTEGER)
+         FROM offline_operations WHERE status = 'pending'",
+    ).await?;
+
+    let page_size = scalar(conn, "PRAGMA page_size").await?.unwrap_or(0);
+    let free      = scalar(conn, "PRAGMA freelist_count").await?.unwrap_or(0);
+    let wal_path  = format!("{}-wal", db_path.display());
+    let mut bytes_on_disk = 0;
+    for path in [db_path, Path::new(&wal_path)] {
+        bytes_on_disk += tokio::fs::metadata(path).await.map(|m| m.len()).unwrap_or(0);
+    }
+
+    Ok(DatabaseStats {
+        documents_by_status,
+        trashed_documents,
+        bytes_on_disk,
+        free_bytes:      (free * page_size) as u64,
+        fts_index_bytes: fts_index_bytes as u64,
+        queued_ops_by_type,
+        oldest_pending_op_age_secs,
+    })
+}
+
+/// PRAGMA integrity_check. Empty means the database is sound.
+pub async fn integrity_problems(conn: &Connection) -> Result<Vec<String>> {
+    let mut rows = conn.query("PRAGMA integrity_check", ()).await?;
+    let mut problems = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let line: String = row.get(0)?;
+        if line != "ok" {
+            problems.push(line);
+        }
+    }
+    Ok(problems)
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+/// (text, count) rows into a map.
+async fn counts(conn: &Connection, sql: &str) -> Result<BTreeMap<String, i64>> {
+    let mut rows = conn.query(sql, ()).await?;
+    let mut out = BTreeMap::new();
+    while let Some(row) = rows.next().await? {
+        if let (Ok(Value::Text(key)), Ok(Value::Integer(n))) = (row.get_value(0), row.get_value(1)) {
+            out.insert(key, n);
+        }
+    }
+    Ok(out)
+}
+
+/// First column of the first row, if it is an integer.
+async fn scalar(conn: &Connection, sql: &str) -> Result<Option<i64>> {
+    let mut rows = conn.query(sql, ()).await?;
+    Ok(match rows.next().await? {
+        Some(row) => match row.get_value(0)? { Value::Integer(n) => Some(n), _ => None },
+        None      => None,
+    })
+}
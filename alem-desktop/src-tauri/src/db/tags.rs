@@ -0,0 +1,73 @@
+// src-tauri/src/db/tags.rs
+// Normalized tags: `tags` holds one row per name (case-insensitive) and
+// `document_tags` links them to documents. Document rows expose their tags as
+// a JSON array computed from the join, so a rename touches a single row.
+use anyhow::Result;
+use libsql::{Connection, Value};
+
+/// SQL expression yielding a document's tags as a JSON array, sorted by name.
+/// `doc_id` is the qualified id column of the outer query, e.g. `d.id`.
+pub fn json_expr(doc_id: &str) -> String {
+    format!(
+        "(SELECT json_group_array(name) FROM (
+             SELECT t.name FROM document_tags dt JOIN tags t ON t.id = dt.tag_id
+             WHERE dt.document_id = {doc_id} ORDER BY t.name COLLATE NOCASE
+         ))"
+    )
+}
+
+/// Trim, drop empties and case-insensitive duplicates, keeping first spelling.
+pub fn normalize(tags: &[String]) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for t in tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        if !out.iter().any(|o| o.eq_ignore_ascii_case(t)) {
+            out.push(t.to_string());
+        }
+    }
+    out
+}
+
+/// Id of the tag called `name`, creating it if needed.
+pub async fn ensure(conn: &Connection, name: &str) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO tags (name) VALUES (?1) ON CONFLICT(name) DO NOTHING",
+        libsql::params![name],
+    ).await?;
+    find(conn, name).await?.ok_or_else(|| anyhow::anyhow!("Tag {name} vanished"))
+}
+
+pub async fn find(conn: &Connection, name: &str) -> Result<Option<i64>> {
+    let mut rows = conn.query("SELECT id FROM tags WHERE name = ?1", libsql::params![name]).await?;
+    Ok(match rows.next().await? {
+        Some(row) => match row.get_value(0)? { Value::Integer(n) => Some(n), _ => None },
+        None      => None,
+    })
+}
+
+/// Replace a document's tag set.
+pub async fn set_for_document(conn: &Connection, doc_id: &str, tags: &[String]) -> Result<()> {
+    conn.execute("DELETE FROM document_tags WHERE document_id = ?1", libsql::params![doc_id]).await?;
+    for name in normalize(tags) {
+        let tag_id = ensure(conn, &name).await?;
+        conn.execute(
+            "INSERT OR IGNORE INTO document_tags (document_id, tag_id) VALUES (?1, ?2)",
+            libsql::params![doc_id, tag_id],
+        ).await?;
+    }
+    Ok(())
+}
+
+/// Ids of live documents carrying tag `tag_id`.
+pub async fn documents_with(conn: &Connection, tag_id: i64) -> Result<Vec<String>> {
+    let mut rows = conn.query(
+        "SELECT dt.document_id FROM document_tags dt
+         JOIN documents d ON d.id = dt.document_id
+         WHERE dt.tag_id = ?1 AND d.status != 'deleted'",
+        libsql::params![tag_id],
+    ).await?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await? {
+        if let Value::Text(s) = row.get_value(0)? { out.push(s); }
+    }
+    Ok(out)
+}
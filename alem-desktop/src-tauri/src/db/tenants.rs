@@ -0,0 +1,173 @@
+// src-tauri/src/db/tenants.rs
+// Several organizations in one install.
+//
+// Every document and queued op carries a tenant_id; the active tenant is
+// local_identity.tenant_id (what identity::current returns), and listings,
+// search and the sync queue only ever see that tenant's rows. Each tenant
+// keeps its own pull cursor, so switching back and forth never re-pulls or
+// skips changes. Ops queued under another tenant wait until it is active.
+//
+// Tenants belong to an account (db/accounts.rs) and only the active
+// account's are listed. Tenant ids come from the server, so two servers can
+// both have a 'default': the second gets a local id of its own and keeps the
+// server's in remote_id, which is what sync sends.
+use anyhow::{bail, Result};
+use libsql::{Connection, Value};
+
+use super::models::{SyncFilter, SyncFilterState, Tenant};
+
+pub const EPOCH: &str = "2000-01-01T00:00:00Z";
+
+pub async fn list(conn: &Connection) -> Result<Vec<Tenant>> {
+    let mut rows = conn.query(
+        "SELECT t.id, t.name, t.last_sync_at,
+                t.id = COALESCE((SELECT tenant_id FROM local_identity WHERE id = 'singleton'), 'default'),
+                (SELECT COUNT(*) FROM documents d WHERE d.tenant_id = t.id AND d.status != 'deleted'),
+                (SELECT COUNT(*) FROM offline_operations o WHERE o.tenant_id = t.id AND o.status = 'pending')
+         FROM tenants t
+         WHERE t.account_id IS (SELECT account_id FROM local_identity WHERE id = 'singleton')
+         ORDER BY COALESCE(t.name, t.id) COLLATE NOCASE",
+        (),
+    ).await?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let s = |i| match row.get_value(i).ok() { Some(Value::Text(s)) => Some(s), _ => None };
+        let n = |i| match row.get_value(i).ok() { Some(Value::Integer(n)) => n, _ => 0 };
+        out.push(Tenant {
+            id:             s(0).unwrap_or_default(),
+            name:           s(1),
+            last_sync_at:   s(2),
+            active:         n(3) != 0,
+            document_count: n(4),
+            pending_ops:    n(5),
+        });
+    }
+    Ok(out)
+}
+
+/// Make `id` (the server's tenant id, or a local one from list) the active
+/// account's active tenant, registering it if new. `name` (if given) renames
+/// it.
+pub async fn switch(conn: &Connection, id: &str, name: Option<&str>) -> Result<()> {
+    if id.trim().is_empty() {
+        bail!("Tenant id must not be empty");
+    }
+    let tx = conn.transaction().await?;
+    let account = super::accounts::active_id(&tx).await?;
+    let local = claim(&tx, account.as_deref(), id).await?;
+    if let Some(name) = name {
+        tx.execute(
+            "UPDATE tenants SET name = ?2 WHERE id = ?1",
+            libsql::params![local.as_str(), name],
+        ).await?;
+    }
+    tx.execute(
+        "UPDATE local_identity SET tenant_id = ?1, updated_at = datetime('now') WHERE id = 'singleton'",
+        libsql::params![local],
+    ).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// The local id of `account`'s tenant `id`, registering the tenant for it
+/// if new. When another account has a tenant by that id already, this one
+/// gets `<id>@<account>`.
+pub async fn claim(conn: &Connection, account: Option<&str>, id: &str) -> Result<String> {
+    let mut rows = conn.query(
+        "SELECT id FROM tenants WHERE account_id IS ?1 AND (id = ?2 OR remote_id = ?2)",
+        libsql::params![account, id],
+    ).await?;
+    if let Some(row) = rows.next().await? {
+        return Ok(row.get(0)?);
+    }
+    let claimed = conn.execute(
+        "INSERT INTO tenants (id, account_id) VALUES (?1, ?2)
+         ON CONFLICT(id) DO UPDATE SET account_id = excluded.account_id WHERE tenants.account_id IS NULL",
+        libsql::params![id, account],
+    ).await?;
+    if claimed > 0 {
+        return Ok(id.to_string());
+    }
+    let local = format!("{id}@{}", account.unwrap_or("local"));
+    conn.execute(
+        "INSERT OR IGNORE INTO tenants (id, account_id, remote_id) VALUES (?1, ?2, ?3)",
+        libsql::params![local.as_str(), account, id],
+    ).await?;
+    Ok(local)
+}
+
+/// The server's id for local tenant `tenant`.
+pub async fn remote_id(conn: &Connection, tenant: &str) -> Result<String> {
+    let mut rows = conn.query(
+        "SELECT COALESCE(remote_id, id) FROM tenants WHERE id = ?1",
+        libsql::params![tenant],
+    ).await?;
+    Ok(match rows.next().await? {
+        Some(row) => match row.get_value(0)? { Value::Text(s) => s, _ => tenant.into() },
+        None      => tenant.into(),
+    })
+}
+
+/// When `tenant` last pulled changes (the epoch if never).
+pub async fn sync_cursor(conn: &Connection, tenant: &str) -> Result<String> {
+    let mut rows = conn.query(
+        "SELECT last_sync_at FROM tenants WHERE id = ?1",
+        libsql::params![tenant],
+    ).await?;
+    Ok(match rows.next().await? {
+        Some(row) => match row.get_value(0)? { Value::Text(s) => s, _ => EPOCH.into() },
+        None      => EPOCH.into(),
+    })
+}
+
+/// The ETag of the change set `tenant` last pulled, if the server sent one.
+pub async fn changes_etag(conn: &Connection, tenant: &str) -> Result<Option<String>> {
+    let mut rows = conn.query(
+        "SELECT changes_etag FROM tenants WHERE id = ?1",
+        libsql::params![tenant],
+    ).await?;
+    Ok(match rows.next().await? {
+        Some(row) => match row.get_value(0)? { Value::Text(s) => Some(s), _ => None },
+        None      => None,
+    })
+}
+
+/// Record a pull: the cursor moves to now and `etag` is what the pulled
+/// change set was tagged with.
+pub async fn advance_sync_cursor(conn: &Connection, tenant: &str, etag: Option<&str>) -> Result<()> {
+    conn.execute(
+        "INSERT INTO tenants (id, last_sync_at, changes_etag) VALUES (?1, datetime('now'), ?2)
+         ON CONFLICT(id) DO UPDATE SET last_sync_at = excluded.last_sync_at,
+                                       changes_etag = excluded.changes_etag",
+        libsql::params![tenant, etag],
+    ).await?;
+    Ok(())
+}
+
+pub async fn sync_filter(conn: &Connection, tenant: &str) -> Result<SyncFilterState> {
+    let mut rows = conn.query(
+        "SELECT sync_filter FROM tenants WHERE id = ?1",
+        libsql::params![tenant],
+    ).await?;
+    let Some(row) = rows.next().await? else {
+        return Ok(SyncFilterState { filter: None });
+    };
+    let s = |i| match row.get_value(i).ok() { Some(Value::Text(s)) => Some(s), _ => None };
+    Ok(SyncFilterState {
+        filter: s(0).map(|f| serde_json::from_str(&f)).transpose()?,
+    })
+}
+
+/// Replace `tenant`'s filter. The next pull starts over from the epoch (with
+/// no ETag, since a change set applied through the old filter may have
+/// skipped documents) so documents the old filter kept out arrive.
+pub async fn set_sync_filter(conn: &Connection, tenant: &str, filter: Option<&SyncFilter>) -> Result<()> {
+    let json = filter.map(serde_json::to_string).transpose()?;
+    conn.execute(
+        "INSERT INTO tenants (id, sync_filter) VALUES (?1, ?2)
+         ON CONFLICT(id) DO UPDATE SET sync_filter = excluded.sync_filter,
+                                       last_sync_at = NULL, changes_etag = NULL",
+        libsql::params![tenant, json],
+    ).await?;
+    Ok(())
+}
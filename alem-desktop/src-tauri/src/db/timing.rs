@@ -0,0 +1,94 @@
+// src-tauri/src/db/timing.rs
+// Drop-in timed wrappers for Connection::query / execute. Statements slower than
+// SLOW_QUERY_MS are written to the slow_queries table (SQL with string literals
+// redacted, bind parameters reduced to their types) for the diagnostics view.
+use libsql::{params::{IntoParams, Params}, Connection, Rows, Value};
+use std::time::{Duration, Instant};
+
+pub const SLOW_QUERY_MS: u128 = 100;
+/// Rows kept in slow_queries; older entries are pruned on insert.
+const KEEP: i64 = 500;
+
+pub async fn query(conn: &Connection, sql: &str, params: impl IntoParams) -> libsql::Result<Rows> {
+    let params  = params.into_params()?;
+    let shape   = param_types(&params);
+    let started = Instant::now();
+    let rows    = conn.query(sql, params).await;
+    record_if_slow(conn, sql, &shape, started.elapsed()).await;
+    rows
+}
+
+pub async fn execute(conn: &Connection, sql: &str, params: impl IntoParams) -> libsql::Result<u64> {
+    let params  = params.into_params()?;
+    let shape   = param_types(&params);
+    let started = Instant::now();
+    let result  = conn.execute(sql, params).await;
+    record_if_slow(conn, sql, &shape, started.elapsed()).await;
+    result
+}
+
+async fn record_if_slow(conn: &Connection, sql: &str, param_types: &str, elapsed: Duration) {
+    if elapsed.as_millis() < SLOW_QUERY_MS {
+        return;
+    }
+    let redacted = redact(sql);
+    log::warn!("[db] Slow query ({} ms): {redacted}", elapsed.as_millis());
+
+    let result = conn.execute(
+        "INSERT INTO slow_queries (sql, param_types, duration_ms) VALUES (?1, ?2, ?3)",
+        libsql::params![redacted, param_types, elapsed.as_millis() as i64],
+    ).await;
+    let result = match result {
+        Ok(_) => conn.execute(
+            "DELETE FROM slow_queries
+             WHERE id NOT IN (SELECT id FROM slow_queries ORDER BY id DESC LIMIT ?1)",
+            libsql::params![KEEP],
+        ).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        log::debug!("[db] Could not record slow query: {e}");
+    }
+}
+
+fn param_types(params: &Params) -> String {
+    let kind = |v: &Value| match v {
+        Value::Null       => "null",
+        Value::Integer(_) => "integer",
+        Value::Real(_)    => "real",
+        Value::Text(_)    => "text",
+        Value::Blob(_)    => "blob",
+    };
+    match params {
+        Params::None            => String::new(),
+        Params::Positional(vs)  => vs.iter().map(kind).collect::<Vec<_>>().join(","),
+        Params::Named(vs)       => vs.iter().map(|(n, v)| format!("{n}:{}", kind(v))).collect::<Vec<_>>().join(","),
+    }
+}
+
+/// Collapse whitespace and replace quoted string literals with '?', so inlined
+/// values never reach the diagnostics table.
+fn redact(sql: &str) -> String {
+    let mut out       = String::with_capacity(sql.len());
+    let mut in_string = false;
+    let mut last_ws   = false;
+    let mut chars     = sql.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            if c == '\'' {
+                if chars.peek() == Some(&'\'') { chars.next(); } else { in_string = false; }
+            }
+            continue;
+        }
+        match c {
+            '\'' => { in_string = true; out.push_str("'?'"); last_ws = false; }
+            c if c.is_whitespace() => {
+                if !last_ws && !out.is_empty() { out.push(' '); }
+                last_ws = true;
+            }
+            c => { out.push(c); last_ws = false; }
+        }
+    }
+    out.trim_end().to_string()
+}
@@ -0,0 +1,68 @@
+// src-tauri/src/db/trash.rs
+// Trash: a document is trashed by setting trashed_at, which hides it from
+// listings, search and counts but keeps its row, blob and sync status, so
+// restoring is just clearing the column. Both directions are queued as ops so
+// other devices follow. Purging turns trashed documents into ordinary deletes.
+use anyhow::Result;
+use libsql::{Connection, Value};
+
+use super::ops;
+
+/// Move a document to the trash. Returns false if it is missing, deleted or
+/// already trashed.
+pub async fn trash(conn: &Connection, user_id: &str, id: &str) -> Result<bool> {
+    let changed = conn.execute(
+        "UPDATE documents SET trashed_at = datetime('now'), updated_at = datetime('now')
+         WHERE id = ?1 AND status != 'deleted' AND trashed_at IS NULL",
+        libsql::params![id],
+    ).await? > 0;
+    if changed {
+        ops::enqueue(conn, user_id, "trash_document", serde_json::json!({ "doc_id": id })).await?;
+    }
+    Ok(changed)
+}
+
+/// Take a document back out of the trash. Returns false if it wasn't there.
+pub async fn restore(conn: &Connection, user_id: &str, id: &str) -> Result<bool> {
+    let changed = conn.execute(
+        "UPDATE documents SET trashed_at = NULL, updated_at = datetime('now')
+         WHERE id = ?1 AND status != 'deleted' AND trashed_at IS NOT NULL",
+        libsql::params![id],
+    ).await? > 0;
+    if changed {
+        ops::enqueue(conn, user_id, "restore_document", serde_json::json!({ "doc_id": id })).await?;
+    }
+    Ok(changed)
+}
+
+/// Delete trashed documents for good — all of them, or only those trashed
+/// more than `older_than_days` ago. Returns the purged ids.
+pub async fn purge(conn: &Connection, older_than_days: Option<u64>) -> Result<Vec<String>> {
+    let cutoff = match older_than_days {
+        Some(days) => format!("-{days} days"),
+        None       => "+0 days".into(),
+    };
+    let mut rows = conn.query(
+        "SELECT id, user_id FROM documents
+         WHERE trashed_at IS NOT NULL AND status != 'deleted'
+           AND trashed_at <= datetime('now', ?1)",
+        libsql::params![cutoff],
+    ).await?;
+    let mut docs = Vec::new();
+    while let Some(row) = rows.next().await? {
+        if let (Ok(Value::Text(id)), Ok(Value::Text(user_id))) = (row.get_value(0), row.get_value(1)) {
+            docs.push((id, user_id));
+        }
+    }
+
+    let tx = conn.transaction().await?;
+    for (id, user_id) in &docs {
+        tx.execute(
+            "UPDATE documents SET status = 'deleted', updated_at = datetime('now') WHERE id = ?1",
+            libsql::params![id.as_str()],
+        ).await?;
+        ops::enqueue(&tx, user_id, "delete_document", serde_json::json!({ "doc_id": id })).await?;
+    }
+    tx.commit().await?;
+    Ok(docs.into_iter().map(|(id, _)| id).collect())
+}
@@ -0,0 +1,51 @@
+// src-tauri/src/db/watches.rs
+// Documents the user is watching. When the sync engine pulls a change to one
+// from the server it records it here, downloads the new content ahead of the
+// prefetch queue and notifies the user.
+use anyhow::Result;
+use libsql::Connection;
+
+use super::models::{row_to_watched_document, summary_columns, WatchedDocument};
+
+/// Returns false when `doc_id` was already watched.
+pub async fn add(conn: &Connection, doc_id: &str) -> Result<bool> {
+    let n = conn.execute(
+        "INSERT OR IGNORE INTO watches (document_id) VALUES (?1)",
+        libsql::params![doc_id],
+    ).await?;
+    Ok(n > 0)
+}
+
+/// Returns false when `doc_id` wasn't watched.
+pub async fn remove(conn: &Connection, doc_id: &str) -> Result<bool> {
+    let n = conn.execute("DELETE FROM watches WHERE document_id = ?1", libsql::params![doc_id]).await?;
+    Ok(n > 0)
+}
+
+/// Watched documents of `tenant_id`, most recently changed first.
+pub async fn list(conn: &Connection, tenant_id: &str) -> Result<Vec<WatchedDocument>> {
+    let mut rows = conn.query(
+        &format!(
+            "SELECT {}, w.created_at AS watched_since, w.last_changed_at
+             FROM watches w JOIN documents d ON d.id = w.document_id
+             WHERE d.tenant_id = ?1 AND d.status != 'deleted'
+             ORDER BY COALESCE(w.last_changed_at, w.created_at) DESC",
+            summary_columns("d."),
+        ),
+        libsql::params![tenant_id],
+    ).await?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await? {
+        out.push(row_to_watched_document(&row)?);
+    }
+    Ok(out)
+}
+
+/// Record a change pulled for `doc_id`. Returns whether it is watched.
+pub async fn changed(conn: &Connection, doc_id: &str) -> Result<bool> {
+    let n = conn.execute(
+        "UPDATE watches SET last_changed_at = datetime('now') WHERE document_id = ?1",
+        libsql::params![doc_id],
+    ).await?;
+    Ok(n > 0)
+}
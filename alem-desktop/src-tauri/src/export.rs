@@ -0,0 +1,613 @@
+// src-tauri/src/export.rs
+// Portable document archives: move documents between devices without a server.
+//
+// An archive is a tar file (storage::archive) laid out as
+//
+//     manifest.json            ArchiveManifest — metadata, tags, collections
+//     files/<first2>/<sha256>  one entry per distinct blob
+//
+// The manifest comes first so an import knows what it's reading before any
+// bytes land. Blobs are checked against their hash on the way in. Imported
+// documents are queued for upload like freshly created ones.
+//
+// The same format carries the sync queue between devices (kind
+// "alem-sync-queue"): unsent offline_operations plus the documents and
+// collections they point at, with ids and sync state preserved so the ops can
+// run unchanged on the new machine.
+use anyhow::{Context, Result};
+use libsql::{Connection, Value};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use uuid::Uuid;
+
+use crate::commands::documents::{insert_document, CreateDocumentInput};
+use crate::db::{
+    self,
+    bulk, identity, metadata_index,
+    models::{document_columns, row_to_document, row_to_offline_operation, Document, ExportSelection, OfflineOperation},
+    ops, query::SqlBuilder, tags,
+};
+use crate::jobs::JobHandle;
+use crate::storage::{archive::{TarReader, TarWriter, MAX_MANIFEST}, cas};
+
+pub const MANIFEST_ENTRY: &str = "manifest.json";
+pub const FILES_PREFIX:   &str = "files/";
+const FORMAT_VERSION:     u64 = 1;
+const DOCUMENTS_KIND:     &str = "alem-documents";
+const QUEUE_KIND:         &str = "alem-sync-queue";
+
+/// Status given to ops handed off by export_queue; the engine only runs 'pending'.
+const EXPORTED_STATUS: &str = "exported";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    pub format:      u64,
+    pub kind:        String,
+    pub exported_at: String,
+    pub documents:   Vec<ArchivedDocument>,
+    pub collections: Vec<ArchivedCollection>,
+    /// Sync queue archives only.
+    #[serde(default)]
+    pub operations:  Vec<OfflineOperation>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchivedDocument {
+    pub id:            String,
+    pub filename:      String,
+    pub content_type:  Option<String>,
+    pub file_size:     Option<i64>,
+    pub content_hash:  Option<String>,
+    pub text_content:  Option<String>,
+    pub metadata:      serde_json::Value,
+    pub tags:          Vec<String>,
+    pub collection_id: Option<String>,
+    pub created_at:    String,
+    pub updated_at:    String,
+    /// Archive entry holding the bytes; None if they weren't available at export.
+    pub file:          Option<String>,
+    /// Sync queue archives only: server-side state to carry over.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync:          Option<ArchivedSyncState>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchivedSyncState {
+    pub status:         String,
+    pub object_key:     Option<String>,
+    pub server_version: i32,
+    pub is_synced:      bool,
+    pub needs_upload:   bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchivedCollection {
+    pub id:        String,
+    pub parent_id: Option<String>,
+    pub name:      String,
+}
+
+/// Write the selected documents, their blobs and their collections to `dest`.
+pub async fn export(app: &AppHandle, selection: &ExportSelection, dest: &Path, job: &JobHandle) -> Result<serde_json::Value> {
+    let conn = db::connect(&app.state::<crate::AppState>().db).await?;
+    let docs = select_documents(&conn, selection).await?;
+    job.set_total(docs.len() as u64);
+
+    let (documents, blobs, missing) = archive_documents(app, docs, false, job).await?;
+    let manifest = build_manifest(&conn, DOCUMENTS_KIND, documents).await?;
+    write_archive(dest, &manifest, &blobs, job).await?;
+
+    Ok(serde_json::json!({
+        "path":          dest,
+        "documents":     manifest.documents.len(),
+        "files":         blobs.len(),
+        "missing_files": missing,
+    }))
+}
+
+/// Write every unsent (pending or failed) op and what it references to `dest`.
+/// With `hand_off` the exported ops are parked as 'exported' here so the two
+/// devices don't both send them.
+pub async fn export_queue(app: &AppHandle, dest: &Path, hand_off: bool, job: &JobHandle) -> Result<serde_json::Value> {
+    let conn = db::connect(&app.state::<crate::AppState>().db).await?;
+    let mut rows = conn.query(
+        "SELECT id, user_id, op_type, payload, status, retry_count, error_msg, created_at
+         FROM offline_operations WHERE status IN ('pending','failed') ORDER BY created_at",
+        (),
+    ).await?;
+    let mut operations = Vec::new();
+    while let Some(row) = rows.next().await? {
+        operations.push(row_to_offline_operation(&row));
+    }
+
+    let doc_ids: Vec<String> = operations.iter()
+        .filter_map(|op| op.payload["doc_id"].as_str().map(str::to_string))
+        .collect::<HashSet<_>>().into_iter().collect();
+    let mut b = SqlBuilder::default();
+    let list = b.bind_list(&doc_ids);
+    b.push(format!("d.id IN {list}"));
+    let docs = if doc_ids.is_empty() { Vec::new() } else { load_documents(&conn, b).await? };
+    job.set_total(docs.len() as u64);
+
+    let (documents, blobs, missing) = archive_documents(app, docs, true, job).await?;
+    let mut manifest = build_manifest(&conn, QUEUE_KIND, documents).await?;
+
+    // Collections touched only by sync_collection ops
+    let extra: HashSet<String> = operations.iter()
+        .filter_map(|op| op.payload["collection_id"].as_str().map(str::to_string))
+        .filter(|id| !manifest.collections.iter().any(|c| c.id == *id))
+        .collect();
+    let known: HashSet<String> = manifest.collections.iter().map(|c| c.id.clone()).collect();
+    for c in collections_with_ancestors(&conn, extra).await? {
+        if !known.contains(&c.id) {
+            manifest.collections.push(c);
+        }
+    }
+    manifest.operations = operations;
+
+    write_archive(dest, &manifest, &blobs, job).await?;
+
+    if hand_off && !manifest.operations.is_empty() {
+        let ids: Vec<String> = manifest.operations.iter().map(|op| op.id.clone()).collect();
+        let mut b = SqlBuilder::default();
+        let list = b.bind_list(&ids);
+        conn.execute(
+            &format!(
+                "UPDATE offline_operations SET status = '{EXPORTED_STATUS}', updated_at = datetime('now')
+                 WHERE id IN {list}"
+            ),
+            b.into_params(),
+        ).await?;
+    }
+
+    Ok(serde_json::json!({
+        "path":          dest,
+        "operations":    manifest.operations.len(),
+        "documents":     manifest.documents.len(),
+        "files":         blobs.len(),
+        "missing_files": missing,
+        "handed_off":    hand_off,
+    }))
+}
+
+/// Import an archive written by export(). With `preserve_ids` documents and
+/// collections keep their ids and ones that already exist here are skipped;
+/// otherwise everything gets fresh ids (safe to import the same archive twice).
+pub async fn import(app: &AppHandle, src: &Path, preserve_ids: bool, job: &JobHandle) -> Result<serde_json::Value> {
+    let files_dir = cas::files_dir(app)?;
+    let mut tar = TarReader::new(tokio::io::BufReader::new(
+        tokio::fs::File::open(src).await.with_context(|| format!("Opening {}", src.display()))?,
+    ));
+
+    let manifest = read_manifest(&mut tar, DOCUMENTS_KIND).await?;
+    job.set_total(manifest.documents.len() as u64);
+    let present = extract_blobs(&files_dir, &mut tar, job).await?;
+
+    job.advance(0, Some("Importing documents".into()));
+    let _guard = bulk::lock().await;
+    let conn   = db::connect(&app.state::<crate::AppState>().db).await?;
+    let (user_id, tenant_id) = identity::current(&conn).await?;
+
+    let tx = conn.transaction().await?;
+    let collection_map = import_collections(&tx, &manifest.collections, preserve_ids, &user_id, &tenant_id).await?;
+    tx.commit().await?;
+
+    let (mut imported, mut skipped, mut missing) = (Vec::new(), 0u64, 0u64);
+    for batch in manifest.documents.chunks(bulk::BATCH_SIZE) {
+        job.check_cancelled()?;
+        let tx = conn.transaction().await?;
+        for doc in batch {
+            let Some(hash) = doc.file.as_ref().filter(|f| present.contains(*f))
+                .and_then(|f| f.rsplit('/').next()) else {
+                missing += 1;
+                continue;
+            };
+            if preserve_ids && exists(&tx, "documents", &doc.id).await? {
+                skipped += 1;
+                continue;
+            }
+
+            let id = if preserve_ids { doc.id.clone() } else { Uuid::new_v4().to_string() };
+            let path = cas::blob_path(&files_dir, hash);
+            let input = CreateDocumentInput {
+                filename:       doc.filename.clone(),
+                content_type:   doc.content_type.clone().unwrap_or_else(|| "application/octet-stream".into()),
+                local_path:     path.to_string_lossy().to_string(),
+                file_size:      doc.file_size.unwrap_or(0),
+                content_hash:   hash.to_string(),
+                text_content:   doc.text_content.clone(),
+                metadata:       Some(doc.metadata.clone()),
+                tags:           Some(doc.tags.clone()),
+                full_text_path: None,
+            };
+            insert_document(&tx, &id, &user_id, &tenant_id, &input).await?;
+
+            let collection = doc.collection_id.as_ref().and_then(|c| collection_map.get(c));
+            tx.execute(
+                "UPDATE documents SET collection_id = ?1, created_at = ?2 WHERE id = ?3",
+                libsql::params![collection.cloned(), doc.created_at.as_str(), id.as_str()],
+            ).await?;
+            ops::enqueue(&tx, &user_id, "upload_document", serde_json::json!({ "doc_id": id })).await?;
+            imported.push(id);
+        }
+        tx.commit().await?;
+        job.advance(batch.len() as u64, None);
+    }
+
+    Ok(serde_json::json!({
+        "imported":      imported.len(),
+        "ids":           imported,
+        "skipped":       skipped,
+        "missing_files": missing,
+        "collections":   collection_map.len(),
+    }))
+}
+
+/// Import a queue written by export_queue: documents and collections keep
+/// their ids (existing rows are updated only if the archived copy is newer),
+/// then the ops are queued again under their original ids.
+pub async fn import_queue(app: &AppHandle, src: &Path, job: &JobHandle) -> Result<serde_json::Value> {
+    let files_dir = cas::files_dir(app)?;
+    let mut tar = TarReader::new(tokio::io::BufReader::new(
+        tokio::fs::File::open(src).await.with_context(|| format!("Opening {}", src.display()))?,
+    ));
+
+    let manifest = read_manifest(&mut tar, QUEUE_KIND).await?;
+    job.set_total(manifest.documents.len() as u64);
+    let present = extract_blobs(&files_dir, &mut tar, job).await?;
+
+    job.advance(0, Some("Importing queue".into()));
+    let _guard = bulk::lock().await;
+    let conn   = db::connect(&app.state::<crate::AppState>().db).await?;
+    let (user_id, tenant_id) = identity::current(&conn).await?;
+
+    let tx = conn.transaction().await?;
+    import_collections(&tx, &manifest.collections, true, &user_id, &tenant_id).await?;
+
+    let (mut inserted, mut updated, mut missing) = (0u64, 0u64, 0u64);
+    for doc in &manifest.documents {
+        job.check_cancelled()?;
+        if exists(&tx, "documents", &doc.id).await? {
+            updated += u64::from(update_if_newer(&tx, doc).await?);
+        } else {
+            let hash = doc.file.as_ref().filter(|f| present.contains(*f)).and_then(|f| f.rsplit('/').next());
+            // A deleted document only needs its id for the delete op
+            let deleted = doc.sync.as_ref().is_some_and(|s| s.status == "deleted");
+            match hash {
+                Some(hash) => {
+                    insert_archived(&tx, doc, &cas::blob_path(&files_dir, hash), &user_id, &tenant_id).await?;
+                    inserted += 1;
+                }
+                None if deleted => {}
+                None => missing += 1,
+            }
+        }
+        job.advance(1, None);
+    }
+
+    let mut queued = 0u64;
+    for op in &manifest.operations {
+        queued += tx.execute(
+            "INSERT OR IGNORE INTO offline_operations (id, user_id, tenant_id, op_type, payload, status, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, 'pending', ?6)",
+            libsql::params![
+                op.id.as_str(), user_id.as_str(), tenant_id.as_str(), op.op_type.as_str(),
+                op.payload.to_string(), op.created_at.as_str(),
+            ],
+        ).await?;
+    }
+    tx.commit().await?;
+
+    Ok(serde_json::json!({
+        "operations":    queued,
+        "inserted":      inserted,
+        "updated":       updated,
+        "missing_files": missing,
+    }))
+}
+
+/// The manifest must be the first entry, and of the expected kind.
+pub async fn read_manifest<R>(tar: &mut TarReader<R>, kind: &str) -> Result<ArchiveManifest>
+where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    match tar.next_entry().await? {
+        Some(e) if e.name == MANIFEST_ENTRY => {}
+        _ => anyhow::bail!("Not an ALEM archive (no {MANIFEST_ENTRY})"),
+    }
+    let manifest: ArchiveManifest = serde_json::from_slice(&tar.read_entry(MAX_MANIFEST).await?)
+        .context("Unreadable archive manifest")?;
+    if manifest.kind != kind {
+        anyhow::bail!("Expected a {kind} archive, got {}", manifest.kind);
+    }
+    if manifest.format > FORMAT_VERSION {
+        anyhow::bail!("Archive was made by a newer version of the app");
+    }
+    Ok(manifest)
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+async fn select_documents(conn: &Connection, selection: &ExportSelection) -> Result<Vec<Document>> {
+    let mut b = SqlBuilder::default();
+    let indexed = metadata_index::keys(conn).await?;
+    selection.filter.clone().unwrap_or_default().apply("d", &indexed, &mut b);
+    if !selection.document_ids.is_empty() {
+        let list = b.bind_list(&selection.document_ids);
+        b.push(format!("d.id IN {list}"));
+    }
+    load_documents(conn, b).await
+}
+
+/// Full rows for the documents matching `b` (alias `d`).
+async fn load_documents(conn: &Connection, b: SqlBuilder) -> Result<Vec<Document>> {
+    let sql = format!(
+        "SELECT {} FROM documents d WHERE {} ORDER BY d.created_at",
+        document_columns("d."),
+        b.where_clause(),
+    );
+    let mut rows = conn.query(&sql, b.into_params()).await?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await? {
+        out.push(row_to_document(&row)?);
+    }
+    Ok(out)
+}
+
+/// Manifest entries for `docs` plus the distinct blobs to write. Bytes that
+/// can't be found locally are downloaded; ones that still can't be had are
+/// counted as missing. `with_sync` keeps each row's sync state.
+async fn archive_documents(
+    app: &AppHandle,
+    docs: Vec<Document>,
+    with_sync: bool,
+    job: &JobHandle,
+) -> Result<(Vec<ArchivedDocument>, Vec<(String, PathBuf)>, u64)> {
+    let mut documents = Vec::with_capacity(docs.len());
+    let mut blobs: Vec<(String, PathBuf)> = Vec::new();
+    let mut seen  = HashSet::new();
+    let mut missing = 0u64;
+
+    for doc in docs {
+        job.check_cancelled()?;
+        let file = if doc.status == "deleted" {
+            None
+        } else {
+            match blob_for(app, &doc.id, doc.local_path.as_deref(), doc.needs_download).await {
+                Ok((hash, path)) => {
+                    let name = format!("{FILES_PREFIX}{}/{hash}", &hash[..2]);
+                    if seen.insert(hash) {
+                        blobs.push((name.clone(), path));
+                    }
+                    Some(name)
+                }
+                Err(e) => {
+                    log::warn!("[export] No content for {}: {e}", doc.id);
+                    missing += 1;
+                    None
+                }
+            }
+        };
+        let sync = with_sync.then(|| ArchivedSyncState {
+            status:         doc.status.clone(),
+            object_key:     doc.object_key.clone(),
+            server_version: doc.server_version,
+            is_synced:      doc.is_synced,
+            needs_upload:   doc.needs_upload,
+        });
+        documents.push(ArchivedDocument {
+            id:            doc.id,
+            filename:      doc.filename,
+            content_type:  doc.content_type,
+            file_size:     doc.file_size,
+            content_hash:  doc.content_hash,
+            text_content:  doc.text_content,
+            metadata:      doc.metadata,
+            tags:          doc.tags,
+            collection_id: doc.collection_id,
+            created_at:    doc.created_at,
+            updated_at:    doc.updated_at,
+            file,
+            sync,
+        });
+        job.advance(1, None);
+    }
+    Ok((documents, blobs, missing))
+}
+
+async fn build_manifest(conn: &Connection, kind: &str, documents: Vec<ArchivedDocument>) -> Result<ArchiveManifest> {
+    let collection_ids: HashSet<String> = documents.iter().filter_map(|d| d.collection_id.clone()).collect();
+    Ok(ArchiveManifest {
+        format:      FORMAT_VERSION,
+        kind:        kind.into(),
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        collections: collections_with_ancestors(conn, collection_ids).await?,
+        documents,
+        operations:  Vec::new(),
+    })
+}
+
+/// Manifest first, then blobs; written to a .partial file and renamed into place.
+pub(crate) async fn write_archive(dest: &Path, manifest: &impl Serialize, blobs: &[(String, PathBuf)], job: &JobHandle) -> Result<()> {
+    job.advance(0, Some("Writing archive".into()));
+    let partial = PathBuf::from(format!("{}.partial", dest.display()));
+    let result = async {
+        let out = tokio::fs::File::create(&partial).await?;
+        let mut tar = TarWriter::new(tokio::io::BufWriter::new(out));
+        tar.append_bytes(MANIFEST_ENTRY, &serde_json::to_vec_pretty(manifest)?).await?;
+        for (name, path) in blobs {
+            job.check_cancelled()?;
+            tar.append_file(name, path).await?;
+        }
+        tar.finish().await?.into_inner().sync_all().await?;
+        tokio::fs::rename(&partial, dest).await?;
+        Ok(())
+    }.await;
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&partial).await;
+    }
+    result
+}
+
+/// Verify and store every blob in the rest of the archive. Blobs go in before
+/// any row so a document never points at bytes that aren't there. Returns the
+/// archive names seen.
+async fn extract_blobs<R>(files_dir: &Path, tar: &mut TarReader<R>, job: &JobHandle) -> Result<HashSet<String>>
+where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    job.advance(0, Some("Extracting files".into()));
+    let mut present = HashSet::new();
+    while let Some(entry) = tar.next_entry().await? {
+        job.check_cancelled()?;
+        if let Some(name) = entry.name.strip_prefix(FILES_PREFIX) {
+            crate::backup::restore_blob(files_dir, name, tar).await?;
+            present.insert(entry.name);
+        }
+    }
+    Ok(present)
+}
+
+/// Insert a queue-archive document under its original id and sync state.
+async fn insert_archived(conn: &Connection, doc: &ArchivedDocument, path: &Path, user_id: &str, tenant_id: &str) -> Result<()> {
+    let input = CreateDocumentInput {
+        filename:       doc.filename.clone(),
+        content_type:   doc.content_type.clone().unwrap_or_else(|| "application/octet-stream".into()),
+        local_path:     path.to_string_lossy().to_string(),
+        file_size:      doc.file_size.unwrap_or(0),
+        content_hash:   doc.content_hash.clone().unwrap_or_default(),
+        text_content:   doc.text_content.clone(),
+        metadata:       Some(doc.metadata.clone()),
+        tags:           Some(doc.tags.clone()),
+        full_text_path: None,
+    };
+    insert_document(conn, &doc.id, user_id, tenant_id, &input).await?;
+    conn.execute(
+        "UPDATE documents SET collection_id = ?1, created_at = ?2, updated_at = ?3 WHERE id = ?4",
+        libsql::params![doc.collection_id.clone(), doc.created_at.as_str(), doc.updated_at.as_str(), doc.id.as_str()],
+    ).await?;
+    if let Some(sync) = &doc.sync {
+        conn.execute(
+            "UPDATE documents SET status = ?1, object_key = ?2, server_version = ?3, is_synced = ?4, needs_upload = ?5
+             WHERE id = ?6",
+            libsql::params![
+                sync.status.as_str(), sync.object_key.clone(), sync.server_version,
+                sync.is_synced as i64, sync.needs_upload as i64, doc.id.as_str(),
+            ],
+        ).await?;
+    }
+    Ok(())
+}
+
+/// Bring an existing row up to the archived metadata if the archive's copy was
+/// edited later. Returns true if it changed.
+async fn update_if_newer(conn: &Connection, doc: &ArchivedDocument) -> Result<bool> {
+    let changed = conn.execute(
+        "UPDATE documents
+         SET filename = ?1, text_content = ?2, metadata = ?3, collection_id = ?4, updated_at = ?5
+         WHERE id = ?6 AND updated_at < ?5",
+        libsql::params![
+            doc.filename.as_str(), doc.text_content.clone(), doc.metadata.to_string(),
+            doc.collection_id.clone(), doc.updated_at.as_str(), doc.id.as_str(),
+        ],
+    ).await? > 0;
+    if changed {
+        tags::set_for_document(conn, &doc.id, &doc.tags).await?;
+    }
+    Ok(changed)
+}
+
+/// (sha256, blob path) for a document, downloading it first if needed.
+pub(crate) async fn blob_for(app: &AppHandle, id: &str, local_path: Option<&str>, needs_download: bool) -> Result<(String, PathBuf)> {
+    let path = match local_path.map(PathBuf::from).filter(|p| !needs_download && p.exists()) {
+        Some(p) => p,
+        None    => crate::sync::engine::download_document(app, id).await?,
+    };
+    // Archives hold plain content, named by hash; pre-CAS leftovers are hashed
+    let plain = crate::storage::compress::plain_path(&path).await?;
+    let hash = match path.file_name().and_then(|n| n.to_str()).map(cas::blob_hash) {
+        Some(n) if cas::is_sha256_hex(n) => n.to_string(),
+        _ => cas::hash_file(&plain).await?,
+    };
+    Ok((hash, plain))
+}
+
+/// The given collections plus every ancestor, parents before children.
+async fn collections_with_ancestors(conn: &Connection, mut pending: HashSet<String>) -> Result<Vec<ArchivedCollection>> {
+    let mut found: HashMap<String, ArchivedCollection> = HashMap::new();
+    while let Some(id) = pending.iter().next().cloned() {
+        pending.remove(&id);
+        if found.contains_key(&id) {
+            continue;
+        }
+        let mut rows = conn.query(
+            "SELECT id, parent_id, name FROM collections WHERE id = ?1",
+            libsql::params![id.as_str()],
+        ).await?;
+        if let Some(row) = rows.next().await? {
+            let text = |i| match row.get_value(i).ok() { Some(Value::Text(s)) => Some(s), _ => None };
+            let c = ArchivedCollection {
+                id:        text(0).unwrap_or_default(),
+                parent_id: text(1),
+                name:      text(2).unwrap_or_default(),
+            };
+            if let Some(p) = &c.parent_id {
+                pending.insert(p.clone());
+            }
+            found.insert(id, c);
+        }
+    }
+
+    // Order so every parent precedes its children
+    let mut ordered = Vec::with_capacity(found.len());
+    let mut placed  = HashSet::new();
+    while !found.is_empty() {
+        let ready: Vec<String> = found.values()
+            .filter(|c| c.parent_id.as_ref().is_none_or(|p| placed.contains(p) || !found.contains_key(p)))
+            .map(|c| c.id.clone())
+            .collect();
+        if ready.is_empty() {
+            anyhow::bail!("Collection hierarchy has a cycle");
+        }
+        for id in ready {
+            if let Some(c) = found.remove(&id) {
+                placed.insert(id);
+                ordered.push(c);
+            }
+        }
+    }
+    Ok(ordered)
+}
+
+/// Create the archive's collections. Returns archive id → local id.
+async fn import_collections(
+    conn: &Connection,
+    collections: &[ArchivedCollection],
+    preserve_ids: bool,
+    user_id: &str,
+    tenant_id: &str,
+) -> Result<HashMap<String, String>> {
+    let mut map = HashMap::new();
+    for c in collections {
+        if preserve_ids && exists(conn, "collections", &c.id).await? {
+            map.insert(c.id.clone(), c.id.clone());
+            continue;
+        }
+        let id = if preserve_ids { c.id.clone() } else { Uuid::new_v4().to_string() };
+        let parent = c.parent_id.as_ref().and_then(|p| map.get(p)).cloned();
+        conn.execute(
+            "INSERT INTO collections (id, user_id, tenant_id, parent_id, name) VALUES (?1,?2,?3,?4,?5)",
+            libsql::params![id.as_str(), user_id, tenant_id, parent, c.name.as_str()],
+        ).await?;
+        ops::enqueue(conn, user_id, "sync_collection", serde_json::json!({ "collection_id": id })).await?;
+        map.insert(c.id.clone(), id);
+    }
+    Ok(map)
+}
+
+async fn exists(conn: &Connection, table: &str, id: &str) -> Result<bool> {
+    let mut rows = conn.query(&format!("SELECT 1 FROM {table} WHERE id = ?1"), libsql::params![id]).await?;
+    Ok(rows.next().await?.is_some())
+}
@@ -0,0 +1,76 @@
+// src-tauri/src/extract/docx.rs
+// Text of a Word (OOXML) document: the runs of word/document.xml.
+//
+// Only text (<w:t>), tabs and breaks are needed, so the XML is scanned
+// rather than parsed; paragraphs end in a newline. Headers, footers and
+// comments live in other parts and are left out.
+use anyhow::{Context, Result};
+use std::io::{Cursor, Read};
+
+pub const CONTENT_TYPE: &str = "application/vnd.openxmlformats-officedocument.wordprocessingml.document";
+
+pub fn text(bytes: &[u8]) -> Result<String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).context("Not a DOCX file")?;
+    let mut xml = String::new();
+    archive.by_name("word/document.xml").context("DOCX file has no word/document.xml")?
+        .read_to_string(&mut xml)?;
+    Ok(xml_text(&xml))
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+fn xml_text(xml: &str) -> String {
+    let mut out     = String::new();
+    let mut rest    = xml;
+    let mut in_text = false;
+    while let Some(lt) = rest.find('<') {
+        if in_text {
+            unescape_into(&rest[..lt], &mut out);
+        }
+        let Some(gt) = rest[lt..].find('>') else { break };
+        let tag = &rest[lt + 1..lt + gt];
+        let closing      = tag.starts_with('/');
+        let self_closing = tag.ends_with('/');
+        let name = tag.trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default();
+        match name {
+            "w:t"                           => in_text = !closing && !self_closing,
+            "w:tab" if !closing             => out.push('\t'),
+            "w:br" | "w:cr" if !closing     => out.push('\n'),
+            "w:p" if closing || self_closing => out.push('\n'),
+            _                               => {}
+        }
+        rest = &rest[lt + gt + 1..];
+    }
+    out
+}
+
+/// Append `text` with its XML entities decoded.
+fn unescape_into(text: &str, out: &mut String) {
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest.find(';') else { break };
+        let entity = &rest[1..semi];
+        let decoded = match entity {
+            "amp"  => Some('&'),
+            "lt"   => Some('<'),
+            "gt"   => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity.strip_prefix("#x").map(|h| u32::from_str_radix(h, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => out.push(c),
+            None    => out.push_str(&rest[..=semi]),
+        }
+        rest = &rest[semi + 1..];
+    }
+    out.push_str(rest);
+}
@@ -0,0 +1,255 @@
+// src-tauri/src/extract/media.rs
+// Media metadata read into metadata.media on import: EXIF (and pixel size)
+// for images, ID3 for MP3, the movie header for MP4 / QuickTime.
+//
+// The common fields (width, height, duration_secs, captured_at) have
+// generated columns on documents (migration 29) so DocumentFilter can use
+// them. GPS coordinates are only kept with MediaSettings::include_gps.
+use anyhow::{bail, Result};
+use chrono::{DateTime, NaiveDateTime};
+use libsql::Connection;
+use serde_json::{json, Map, Value};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::commands::documents::CreateDocumentInput;
+use crate::db::{models::MediaSettings, settings};
+use crate::storage::compress;
+
+const MP4_TYPES: &[&str] = &["video/mp4", "video/quicktime", "video/x-m4v", "audio/mp4", "audio/x-m4a"];
+const MP3_TYPES: &[&str] = &["audio/mpeg", "audio/mp3"];
+/// Larger moov boxes (hours of fragmented video) are not read.
+const MAX_MOOV_BYTES: u64 = 16 * 1024 * 1024;
+/// MP4 times count from 1904-01-01.
+const MP4_EPOCH_OFFSET: i64 = 2_082_844_800;
+
+pub fn handles(content_type: &str) -> bool {
+    (content_type.starts_with("image/") && content_type != "image/svg+xml")
+        || MP4_TYPES.contains(&content_type)
+        || MP3_TYPES.contains(&content_type)
+}
+
+/// Fill in `input.metadata.media` from its file. A file that can't be read
+/// is imported without it.
+pub async fn fill(conn: &Connection, input: &mut CreateDocumentInput) -> Result<()> {
+    if input.local_path.is_empty() || !handles(&input.content_type) {
+        return Ok(());
+    }
+    let config: MediaSettings = settings::get(conn, settings::MEDIA_SETTINGS).await?.unwrap_or_default();
+    if !config.enabled {
+        return Ok(());
+    }
+    let path = compress::plain_path(Path::new(&input.local_path)).await?;
+    let content_type = input.content_type.clone();
+    let read = tokio::task::spawn_blocking(move || read(&path, &content_type, config.include_gps)).await?;
+    match read {
+        Ok(media) if !media.is_empty() => {
+            let metadata = input.metadata.get_or_insert_with(|| json!({}));
+            if let Some(obj) = metadata.as_object_mut() {
+                obj.insert("media".into(), Value::Object(media));
+            }
+        }
+        Ok(_)  => {}
+        Err(e) => log::info!("[media] No metadata read from {}: {e}", input.filename),
+    }
+    Ok(())
+}
+
+/// The media fields of the file at `path`; empty if it has none.
+pub fn read(path: &Path, content_type: &str, include_gps: bool) -> Result<Map<String, Value>> {
+    let mut out = Map::new();
+    if MP4_TYPES.contains(&content_type) {
+        mp4(path, &mut out)?;
+    } else if MP3_TYPES.contains(&content_type) {
+        mp3(path, &mut out);
+    } else {
+        if let Ok(size) = imagesize::size(path) {
+            out.insert("width".into(), json!(size.width));
+            out.insert("height".into(), json!(size.height));
+        }
+        exif(path, include_gps, &mut out);
+    }
+    Ok(out)
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+fn exif(path: &Path, include_gps: bool, out: &mut Map<String, Value>) {
+    use exif::{In, Tag};
+
+    let Ok(file) = File::open(path) else { return };
+    let Ok(data) = exif::Reader::new().read_from_container(&mut BufReader::new(file)) else { return };
+    let ascii = |tag| match data.get_field(tag, In::PRIMARY).map(|f| &f.value) {
+        Some(exif::Value::Ascii(v)) => v.first()
+            .map(|s| String::from_utf8_lossy(s).trim().to_string())
+            .filter(|s| !s.is_empty()),
+        _ => None,
+    };
+
+    let captured = ascii(Tag::DateTimeOriginal).or_else(|| ascii(Tag::DateTime));
+    if let Some(at) = captured.and_then(|s| NaiveDateTime::parse_from_str(&s, "%Y:%m:%d %H:%M:%S").ok()) {
+        out.insert("captured_at".into(), json!(at.format("%Y-%m-%dT%H:%M:%S").to_string()));
+    }
+    if let Some(make) = ascii(Tag::Make) {
+        out.insert("camera_make".into(), json!(make));
+    }
+    if let Some(model) = ascii(Tag::Model) {
+        out.insert("camera_model".into(), json!(model));
+    }
+    if let Some(o) = data.get_field(Tag::Orientation, In::PRIMARY).and_then(|f| f.value.get_uint(0)) {
+        out.insert("orientation".into(), json!(o));
+    }
+    // Without a container size (e.g. plain TIFF) fall back to the EXIF one
+    if !out.contains_key("width") {
+        let dim = |tag| data.get_field(tag, In::PRIMARY).and_then(|f| f.value.get_uint(0));
+        if let (Some(w), Some(h)) = (dim(Tag::PixelXDimension), dim(Tag::PixelYDimension)) {
+            out.insert("width".into(), json!(w));
+            out.insert("height".into(), json!(h));
+        }
+    }
+
+    if include_gps {
+        let coord = |tag, ref_tag, negative: &str| {
+            let exif::Value::Rational(v) = &data.get_field(tag, In::PRIMARY)?.value else { return None };
+            if v.len() < 3 {
+                return None;
+            }
+            let degrees = v[0].to_f64() + v[1].to_f64() / 60.0 + v[2].to_f64() / 3600.0;
+            Some(if ascii(ref_tag).as_deref() == Some(negative) { -degrees } else { degrees })
+        };
+        if let (Some(lat), Some(lon)) = (
+            coord(Tag::GPSLatitude, Tag::GPSLatitudeRef, "S"),
+            coord(Tag::GPSLongitude, Tag::GPSLongitudeRef, "W"),
+        ) {
+            out.insert("gps".into(), json!({ "latitude": lat, "longitude": lon }));
+        }
+    }
+}
+
+fn mp3(path: &Path, out: &mut Map<String, Value>) {
+    use id3::TagLike;
+
+    let Ok(tag) = id3::Tag::read_from_path(path) else { return };
+    let fields = [("title", tag.title()), ("artist", tag.artist()), ("album", tag.album())];
+    for (key, value) in fields {
+        if let Some(v) = value.map(str::trim).filter(|v| !v.is_empty()) {
+            out.insert(key.into(), json!(v));
+        }
+    }
+    if let Some(year) = tag.year() {
+        out.insert("year".into(), json!(year));
+    }
+    if let Some(ms) = tag.duration() {
+        out.insert("duration_secs".into(), json!(ms as f64 / 1000.0));
+    }
+    if let Some(at) = tag.date_recorded() {
+        out.insert("captured_at".into(), json!(at.to_string()));
+    }
+}
+
+/// Duration, creation time and the first video track's size from the moov
+/// box of an ISO base media file.
+fn mp4(path: &Path, out: &mut Map<String, Value>) -> Result<()> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let Some((start, size)) = find_box(&mut file, 0, len, b"moov")? else { bail!("No moov box") };
+    if size > MAX_MOOV_BYTES {
+        bail!("moov box too large");
+    }
+    let mut moov = vec![0u8; size as usize];
+    file.seek(SeekFrom::Start(start))?;
+    file.read_exact(&mut moov)?;
+
+    for (kind, body) in boxes(&moov) {
+        match &kind {
+            b"mvhd" => {
+                let Some((created, timescale, duration)) = mvhd(body) else { continue };
+                if timescale > 0 {
+                    out.insert("duration_secs".into(), json!(duration as f64 / timescale as f64));
+                }
+                let unix = created as i64 - MP4_EPOCH_OFFSET;
+                if let Some(at) = DateTime::from_timestamp(unix, 0).filter(|_| created > 0 && unix > 0) {
+                    out.insert("captured_at".into(), json!(at.format("%Y-%m-%dT%H:%M:%S").to_string()));
+                }
+            }
+            b"trak" if !out.contains_key("width") => {
+                let size = boxes(body).into_iter()
+                    .find(|(k, _)| k == b"tkhd")
+                    .and_then(|(_, tkhd)| tkhd_size(tkhd))
+                    .filter(|&(w, h)| w > 0 && h > 0);
+                if let Some((w, h)) = size {
+                    out.insert("width".into(), json!(w));
+                    out.insert("height".into(), json!(h));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Body offset and size of the first top-level box of `kind`.
+fn find_box(file: &mut File, mut pos: u64, end: u64, kind: &[u8; 4]) -> Result<Option<(u64, u64)>> {
+    while pos + 8 <= end {
+        file.seek(SeekFrom::Start(pos))?;
+        let mut header = [0u8; 16];
+        file.read_exact(&mut header[..8])?;
+        let mut size = u32::from_be_bytes(header[..4].try_into()?) as u64;
+        let mut header_len = 8;
+        if size == 1 {
+            file.read_exact(&mut header[8..])?;
+            size = u64::from_be_bytes(header[8..].try_into()?);
+            header_len = 16;
+        } else if size == 0 {
+            size = end - pos;
+        }
+        if size < header_len {
+            bail!("Malformed box");
+        }
+        if &header[4..8] == kind {
+            return Ok(Some((pos + header_len, size - header_len)));
+        }
+        pos += size;
+    }
+    Ok(None)
+}
+
+/// The child boxes in `data` as (type, body).
+fn boxes(mut data: &[u8]) -> Vec<([u8; 4], &[u8])> {
+    let mut out = Vec::new();
+    while data.len() >= 8 {
+        let size = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+        let kind = [data[4], data[5], data[6], data[7]];
+        let size = if size == 0 { data.len() } else { size };
+        if size < 8 || size > data.len() {
+            break;
+        }
+        out.push((kind, &data[8..size]));
+        data = &data[size..];
+    }
+    out
+}
+
+/// (creation time, timescale, duration) of an mvhd body.
+fn mvhd(b: &[u8]) -> Option<(u64, u32, u64)> {
+    let u32_at = |i: usize| b.get(i..i + 4).and_then(|s| s.try_into().ok()).map(u32::from_be_bytes);
+    let u64_at = |i: usize| b.get(i..i + 8).and_then(|s| s.try_into().ok()).map(u64::from_be_bytes);
+    match b.first()? {
+        0 => Some((u32_at(4)? as u64, u32_at(12)?, u32_at(16)? as u64)),
+        1 => Some((u64_at(4)?, u32_at(20)?, u64_at(24)?)),
+        _ => None,
+    }
+}
+
+/// Width and height (16.16 fixed point) at the end of a tkhd body.
+fn tkhd_size(b: &[u8]) -> Option<(u32, u32)> {
+    let offset = match b.first()? {
+        0 => 76,
+        1 => 88,
+        _ => return None,
+    };
+    let w = u32::from_be_bytes(b.get(offset..offset + 4)?.try_into().ok()?);
+    let h = u32::from_be_bytes(b.get(offset + 4..offset + 8)?.try_into().ok()?);
+    Some((w >> 16, h >> 16))
+}
@@ -0,0 +1,87 @@
+// src-tauri/src/extract/mod.rs
+// Text extraction, so search covers more than plain-text files.
+//
+// EXTRACTORS maps content types to a function from the file's bytes to its
+// text; supporting a new format means adding a row. Extraction runs on the
+// blocking pool, and a failure only costs the document its text_content.
+// The document is still imported, and metadata.text_extractor records which
+// extractor produced the text. Plain text (text/* without an extractor, and
+// JSON) is read as-is by the import pipeline instead. Media files get
+// metadata rather than text (media.rs).
+mod docx;
+pub mod media;
+pub mod sniff;
+
+use anyhow::{anyhow, Result};
+use serde_json::json;
+use std::path::Path;
+
+use crate::commands::documents::CreateDocumentInput;
+use crate::storage::compress;
+
+/// Larger files are imported without extracted text.
+const MAX_SOURCE_BYTES: i64 = 200 * 1024 * 1024;
+/// html2text wraps at this width; wide enough not to split sentences.
+const HTML_WIDTH: usize = 1000;
+
+pub struct Extractor {
+    pub name:          &'static str,
+    pub content_types: &'static [&'static str],
+    pub extract:       fn(&[u8]) -> Result<String>,
+}
+
+pub const EXTRACTORS: &[Extractor] = &[
+    Extractor { name: "pdf",  content_types: &["application/pdf"],                    extract: pdf },
+    Extractor { name: "docx", content_types: &[docx::CONTENT_TYPE],                   extract: docx::text },
+    Extractor { name: "html", content_types: &["text/html", "application/xhtml+xml"], extract: html },
+];
+
+pub fn find(content_type: &str) -> Option<&'static Extractor> {
+    EXTRACTORS.iter().find(|e| e.content_types.contains(&content_type))
+}
+
+/// Content types whose bytes are their text.
+pub fn is_plain_text(content_type: &str) -> bool {
+    (content_type.starts_with("text/") || content_type == "application/json") && find(content_type).is_none()
+}
+
+/// Fill in `input.text_content` from its file if it has none and an
+/// extractor handles its content type.
+pub async fn fill(input: &mut CreateDocumentInput) {
+    if input.text_content.is_some() || input.local_path.is_empty() {
+        return;
+    }
+    let Some(extractor) = find(&input.content_type) else { return };
+    if input.file_size > MAX_SOURCE_BYTES {
+        log::info!("[extract] {} is too large to extract text from", input.filename);
+        return;
+    }
+    match run(extractor, Path::new(&input.local_path)).await {
+        Ok(text) if !text.trim().is_empty() => {
+            input.text_content = Some(text);
+            let metadata = input.metadata.get_or_insert_with(|| json!({}));
+            if let Some(obj) = metadata.as_object_mut() {
+                obj.insert("text_extractor".into(), json!(extractor.name));
+            }
+        }
+        Ok(_)  => log::info!("[extract] No text in {}", input.filename),
+        Err(e) => log::warn!("[extract] {} extraction failed for {}: {e}", extractor.name, input.filename),
+    }
+}
+
+/// Text of the file at `path` (plain or compressed blob).
+pub async fn run(extractor: &Extractor, path: &Path) -> Result<String> {
+    let bytes = compress::read(path).await?;
+    let extract = extractor.extract;
+    tokio::task::spawn_blocking(move || extract(&bytes)).await?
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+fn pdf(bytes: &[u8]) -> Result<String> {
+    pdf_extract::extract_text_from_mem(bytes).map_err(|e| anyhow!("{e}"))
+}
+
+fn html(bytes: &[u8]) -> Result<String> {
+    Ok(html2text::from_read(bytes, HTML_WIDTH))
+}
@@ -0,0 +1,89 @@
+// src-tauri/src/extract/sniff.rs
+// Content-type sniffing. The type a caller declares (or the file extension
+// implies) decides which extractor, scanner and media reader runs, so it is
+// checked against the file's magic bytes before the record is created.
+// Magic bytes win over a specific but wrong declared type; a missing or
+// application/octet-stream one is filled in. Sniffed types that are too
+// generic to pick a format (zip containers, text) never replace a declared
+// type.
+use std::path::Path;
+use tokio::io::AsyncReadExt;
+
+use crate::commands::documents::CreateDocumentInput;
+use crate::commands::import::content_type_for;
+use crate::storage::compress;
+
+/// Enough for infer to look past a zip's first entries (OOXML).
+const HEAD_BYTES: u64 = 32 * 1024;
+const UNKNOWN: &str = "application/octet-stream";
+
+/// Different names for the same format: (declared, sniffed).
+const ALIASES: &[(&str, &str)] = &[
+    ("image/jpg",   "image/jpeg"),
+    ("image/heic",  "image/heif"),
+    ("audio/mp3",   "audio/mpeg"),
+    ("audio/mp4",   "audio/m4a"),
+    ("audio/x-m4a", "audio/m4a"),
+    ("video/x-m4v", "video/mp4"),
+];
+
+/// Correct or fill in `input.content_type` from its file.
+pub async fn fill(input: &mut CreateDocumentInput) {
+    let sniffed = if input.local_path.is_empty() {
+        None
+    } else {
+        sniff(Path::new(&input.local_path)).await
+    };
+    let resolved = resolve(&input.content_type, sniffed, &input.filename);
+    if resolved != input.content_type {
+        log::info!(
+            "[sniff] {}: content type {:?} → {resolved}",
+            input.filename, input.content_type,
+        );
+        input.content_type = resolved;
+    }
+}
+
+/// The content type to store for the file at `path`, given the `declared` one.
+pub async fn detect(path: &Path, declared: &str, filename: &str) -> String {
+    resolve(declared, sniff(path).await, filename)
+}
+
+/// infer's guess from the first HEAD_BYTES of a blob (plain or compressed).
+async fn sniff(path: &Path) -> Option<&'static str> {
+    let head = if compress::is_compressed(path) {
+        let mut bytes = compress::read(path).await.ok()?;
+        bytes.truncate(HEAD_BYTES as usize);
+        bytes
+    } else {
+        let mut bytes = Vec::new();
+        tokio::fs::File::open(path).await.ok()?.take(HEAD_BYTES).read_to_end(&mut bytes).await.ok()?;
+        bytes
+    };
+    infer::get(&head).map(|t| t.mime_type())
+}
+
+fn resolve(declared: &str, sniffed: Option<&str>, filename: &str) -> String {
+    // Parameters (charset=…) don't change the format
+    let base = declared.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+    let specific = sniffed.filter(|s| !is_generic(s));
+
+    if base.is_empty() || base == UNKNOWN {
+        let by_extension = content_type_for(filename);
+        return match specific {
+            Some(s)                        => s.to_string(),
+            None if by_extension != UNKNOWN => by_extension,
+            None                           => sniffed.unwrap_or(UNKNOWN).to_string(),
+        };
+    }
+    match specific {
+        Some(s) if s != base && !ALIASES.contains(&(base.as_str(), s)) => s.to_string(),
+        _ => declared.to_string(),
+    }
+}
+
+/// Containers many formats share, and text, which magic bytes can't tell
+/// apart reliably.
+fn is_generic(sniffed: &str) -> bool {
+    sniffed.starts_with("text/") || matches!(sniffed, "application/zip" | "application/x-ole-storage")
+}
@@ -0,0 +1,440 @@
+// src-tauri/src/http.rs
+// Outgoing HTTP: the Alem server, the object store URLs it hands out, IPFS
+// and captured pages. Clients come from client() and requests go out through
+// send() (or .send_retrying()), which adds the same failure handling
+// everywhere:
+//
+// - Retries with exponential backoff (and jitter) for timeouts, dropped
+//   connections and 5xx answers. Requests that may not be repeated safely
+//   (POST, PATCH) are retried only when the connection failed, i.e. the
+//   server never saw them. Streamed bodies, which can't be replayed, are
+//   sent once.
+// - A circuit breaker per host. After BREAKER_THRESHOLD failures in a row the
+//   host is left alone for BREAKER_COOLDOWN; requests fail at once with
+//   CircuitOpen instead of queueing up timeouts. Then one request tries it
+//   again, and it either closes the breaker or opens it for another cooldown.
+// - Rate limits. A 429's Retry-After, or X-RateLimit-Reset once
+//   X-RateLimit-Remaining reaches 0, pauses the host: requests fail at once
+//   with RateLimited until then (the sync engine checks paused_for() and
+//   holds its queue). A 429 asking for a short wait is waited out and sent
+//   again.
+// - Per-host counts and a rolling failure rate, for get_http_metrics.
+//
+// Clients also go through the proxy and trust the extra root certificates of
+// the network settings, which configure() installs at startup and whenever
+// they change. TLS, certificate pins included, is tls.rs.
+use anyhow::{Context, Result};
+use rand::Rng;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::{LazyLock, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::db::models::NetworkSettings;
+use crate::tls;
+
+const MAX_ATTEMPTS: u32 = 4;
+const BACKOFF_BASE: Duration = Duration::from_millis(500);
+const BACKOFF_MAX:  Duration = Duration::from_secs(8);
+/// Failures in a row that open a host's breaker.
+const BREAKER_THRESHOLD: u32 = 5;
+const BREAKER_COOLDOWN:  Duration = Duration::from_secs(30);
+/// Outcomes kept per host for the failure rate.
+const WINDOW: usize = 100;
+/// Pause after a 429 that doesn't say for how long.
+const DEFAULT_RATE_LIMIT_WAIT: Duration = Duration::from_secs(30);
+/// Longer rate-limit waits fail the request rather than hold it.
+const MAX_INLINE_WAIT: Duration = Duration::from_secs(5);
+
+static HOSTS: LazyLock<Mutex<HashMap<String, HostState>>> = LazyLock::new(Default::default);
+static NETWORK: LazyLock<RwLock<Network>> = LazyLock::new(Default::default);
+
+/// A request refused because its host's breaker is open.
+#[derive(Debug)]
+pub struct CircuitOpen {
+    pub host:     String,
+    pub retry_in: Duration,
+}
+
+impl std::fmt::Display for CircuitOpen {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} is failing; not retried for another {}s", self.host, self.retry_in.as_secs().max(1))
+    }
+}
+
+impl std::error::Error for CircuitOpen {}
+
+/// A request held back because its host asked for a pause.
+#[derive(Debug)]
+pub struct RateLimited {
+    pub host:     String,
+    pub retry_in: Duration,
+}
+
+impl std::fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} is rate limiting; resuming in {}s", self.host, self.retry_in.as_secs().max(1))
+    }
+}
+
+impl std::error::Error for RateLimited {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    Closed,
+    /// Failing; requests are refused until the cooldown ends.
+    Open,
+    /// Cooldown over; the next request finds out whether the host is back.
+    HalfOpen,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HostMetrics {
+    pub host: String,
+    pub requests: u64,
+    pub failures: u64,
+    pub retries: u64,
+    /// Requests refused while the breaker was open.
+    pub short_circuited: u64,
+    /// 429 answers.
+    pub rate_limited: u64,
+    /// Seconds until the host's rate-limit pause ends, while it lasts.
+    pub paused_for_secs: Option<u64>,
+    /// Share of the last WINDOW attempts that failed.
+    pub failure_rate: f64,
+    pub breaker: BreakerState,
+}
+
+/// A client for requests that give up after `timeout`.
+pub fn client(timeout: Duration) -> Result<reqwest::Client> {
+    client_with_tls(timeout, tls::config()?)
+}
+
+/// client() with TLS set up by the caller.
+pub fn client_with_tls(timeout: Duration, tls: rustls::ClientConfig) -> Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder()
+        .timeout(timeout)
+        .user_agent(concat!("alem-desktop/", env!("CARGO_PKG_VERSION")))
+        .use_preconfigured_tls(tls);
+    if let Some(proxy) = NETWORK.read().unwrap().proxy.clone() {
+        builder = builder.proxy(proxy);
+    }
+    Ok(builder.build()?)
+}
+
+/// Check `settings` and use them for every client made from now on.
+pub fn configure(settings: &NetworkSettings) -> Result<()> {
+    let mut network = Network::default();
+    if let Some(url) = settings.proxy_url.as_deref() {
+        let mut proxy = reqwest::Proxy::all(url).with_context(|| format!("Invalid proxy URL {url}"))?;
+        if let Some(username) = settings.proxy_username.as_deref() {
+            proxy = proxy.basic_auth(username, settings.proxy_password.as_deref().unwrap_or(""));
+        }
+        let no_proxy = settings.no_proxy.as_deref().unwrap_or("");
+        network.proxy     = Some(proxy.no_proxy(reqwest::NoProxy::from_string(no_proxy)));
+        network.proxy_url = Some(reqwest::Url::parse(url).with_context(|| format!("Invalid proxy URL {url}"))?);
+        network.no_proxy  = no_proxy.split(',')
+            .map(|h| h.trim().trim_start_matches("*.").trim_start_matches('.').to_ascii_lowercase())
+            .filter(|h| !h.is_empty())
+            .collect();
+    }
+    let mut roots = Vec::new();
+    for (i, pem) in settings.root_certificates.iter().enumerate() {
+        roots.extend(tls::parse_pem(pem).with_context(|| format!("Root certificate {}", i + 1))?);
+    }
+    tls::set_roots(&roots)?;
+    *NETWORK.write().unwrap() = network;
+    Ok(())
+}
+
+/// The proxy requests to `url` go through, if one is set and `url`'s host
+/// isn't exempt.
+pub fn proxy_for(url: &reqwest::Url) -> Option<reqwest::Url> {
+    let network = NETWORK.read().unwrap();
+    let proxy = network.proxy_url.clone()?;
+    let host = url.host_str()?.to_ascii_lowercase();
+    let exempt = network.no_proxy.iter().any(|h| {
+        h == "*" || host == *h || host.strip_suffix(h.as_str()).is_some_and(|rest| rest.ends_with('.'))
+    });
+    (!exempt).then_some(proxy)
+}
+
+/// Send `request`, retrying and short-circuiting as described above. Like
+/// RequestBuilder::send, a 4xx or 5xx answer is still Ok; callers check the
+/// status.
+pub async fn send(request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+    let (client, request) = request.build_split();
+    let mut request = request?;
+    let host = host_of(request.url());
+    let repeatable = matches!(
+        *request.method(),
+        reqwest::Method::GET | reqwest::Method::HEAD | reqwest::Method::PUT
+            | reqwest::Method::DELETE | reqwest::Method::OPTIONS
+    );
+
+    let mut attempt = 1;
+    loop {
+        admit(&host)?;
+        let retry = (attempt < MAX_ATTEMPTS).then(|| request.try_clone()).flatten();
+        let outcome = client.execute(request).await;
+        let (failed, retryable) = match &outcome {
+            Ok(resp) => (resp.status().is_server_error(), repeatable),
+            // A pin mismatch won't go away by trying again
+            Err(e)   => (
+                e.is_timeout() || e.is_connect(),
+                (repeatable || e.is_connect()) && !tls::is_pin_mismatch(e),
+            ),
+        };
+        record(&host, failed);
+
+        // A 429 wasn't processed, so any request may go again after the wait
+        let mut delay = None;
+        if let Ok(resp) = &outcome {
+            let throttled = resp.status() == reqwest::StatusCode::TOO_MANY_REQUESTS;
+            if let Some(wait) = rate_limit(resp) {
+                pause(&host, wait, throttled);
+                if throttled && wait <= MAX_INLINE_WAIT {
+                    delay = Some(wait);
+                }
+            }
+        }
+        if failed && retryable && allowed(&host) {
+            delay = Some(backoff(attempt));
+        }
+
+        match retry.zip(delay) {
+            Some((next, delay)) => {
+                log::debug!("[http] {host}: attempt {attempt} failed, retrying in {delay:?}");
+                note_retry(&host);
+                tokio::time::sleep(delay).await;
+                request = next;
+                attempt += 1;
+            }
+            None => return Ok(outcome?),
+        }
+    }
+}
+
+/// How much longer requests to `url`'s host are paused for a rate limit.
+pub fn paused_for(url: &str) -> Option<Duration> {
+    let host = host_of(&reqwest::Url::parse(url).ok()?);
+    let hosts = HOSTS.lock().unwrap();
+    hosts.get(&host)?.paused_until
+        .map(|until| until.saturating_duration_since(Instant::now()))
+        .filter(|left| !left.is_zero())
+}
+
+/// Whether `e` is a host asking to be left alone for now (rate limited, or
+/// failing behind an open breaker) rather than a problem with the request.
+pub fn is_throttled(e: &anyhow::Error) -> bool {
+    e.chain().any(|cause| {
+        cause.is::<RateLimited>()
+            || cause.is::<CircuitOpen>()
+            || cause.downcast_ref::<reqwest::Error>()
+                .is_some_and(|r| r.status() == Some(reqwest::StatusCode::TOO_MANY_REQUESTS))
+    })
+}
+
+/// send() as a RequestBuilder method, so call chains read as they would
+/// with RequestBuilder::send.
+pub trait SendRetrying {
+    fn send_retrying(self) -> impl Future<Output = Result<reqwest::Response>> + Send;
+}
+
+impl SendRetrying for reqwest::RequestBuilder {
+    fn send_retrying(self) -> impl Future<Output = Result<reqwest::Response>> + Send {
+        send(self)
+    }
+}
+
+/// Every host talked to since startup, busiest first.
+pub fn metrics() -> Vec<HostMetrics> {
+    let hosts = HOSTS.lock().unwrap();
+    let mut out: Vec<HostMetrics> = hosts.iter().map(|(host, s)| HostMetrics {
+        host:            host.clone(),
+        requests:        s.requests,
+        failures:        s.failures,
+        retries:         s.retries,
+        short_circuited: s.short_circuited,
+        rate_limited:    s.rate_limited,
+        paused_for_secs: s.paused_until
+            .map(|until| until.saturating_duration_since(Instant::now()))
+            .filter(|left| !left.is_zero())
+            .map(|left| left.as_secs().max(1)),
+        failure_rate:    match s.recent.len() {
+            0 => 0.0,
+            n => s.recent.iter().filter(|f| **f).count() as f64 / n as f64,
+        },
+        breaker:         s.breaker(),
+    }).collect();
+    out.sort_by(|a, b| b.requests.cmp(&a.requests).then_with(|| a.host.cmp(&b.host)));
+    out
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+/// The network settings' proxy, ready for client().
+#[derive(Default)]
+struct Network {
+    proxy:     Option<reqwest::Proxy>,
+    proxy_url: Option<reqwest::Url>,
+    /// Hosts (and their subdomains) reached directly.
+    no_proxy:  Vec<String>,
+}
+
+#[derive(Default)]
+struct HostState {
+    requests:        u64,
+    failures:        u64,
+    retries:         u64,
+    short_circuited: u64,
+    rate_limited:    u64,
+    recent:          VecDeque<bool>,
+    failing_streak:  u32,
+    /// Set while the breaker is open (or half-open once this has passed).
+    open_until:      Option<Instant>,
+    /// Rate-limit pause.
+    paused_until:    Option<Instant>,
+}
+
+impl HostState {
+    fn breaker(&self) -> BreakerState {
+        match self.open_until {
+            None                                  => BreakerState::Closed,
+            Some(until) if Instant::now() < until => BreakerState::Open,
+            Some(_)                               => BreakerState::HalfOpen,
+        }
+    }
+}
+
+fn host_of(url: &reqwest::Url) -> String {
+    match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{host}:{port}"),
+        (Some(host), None)       => host.to_string(),
+        (None, _)                => url.scheme().to_string(),
+    }
+}
+
+/// Let a request to `host` through, or refuse it while the host is paused
+/// or its breaker is open.
+fn admit(host: &str) -> Result<()> {
+    let mut hosts = HOSTS.lock().unwrap();
+    let state = hosts.entry(host.to_string()).or_default();
+    if let Some(retry_in) = state.paused_until.map(|until| until.saturating_duration_since(Instant::now())) {
+        if !retry_in.is_zero() {
+            return Err(RateLimited { host: host.to_string(), retry_in }.into());
+        }
+        state.paused_until = None;
+    }
+    match state.breaker() {
+        BreakerState::Closed => Ok(()),
+        BreakerState::HalfOpen => {
+            // The trial request; others wait for its outcome, or for another
+            // cooldown should it never finish
+            state.open_until = Some(Instant::now() + BREAKER_COOLDOWN);
+            Ok(())
+        }
+        _ => {
+            state.short_circuited += 1;
+            let retry_in = state.open_until
+                .map(|until| until.saturating_duration_since(Instant::now()))
+                .unwrap_or(BREAKER_COOLDOWN);
+            Err(CircuitOpen { host: host.to_string(), retry_in }.into())
+        }
+    }
+}
+
+/// Whether another attempt on `host` would be let through.
+fn allowed(host: &str) -> bool {
+    HOSTS.lock().unwrap().get(host).is_none_or(|s| s.breaker() == BreakerState::Closed)
+}
+
+fn record(host: &str, failed: bool) {
+    let mut hosts = HOSTS.lock().unwrap();
+    let state = hosts.entry(host.to_string()).or_default();
+    state.requests += 1;
+    if state.recent.len() == WINDOW {
+        state.recent.pop_front();
+    }
+    state.recent.push_back(failed);
+
+    if !failed {
+        if state.open_until.take().is_some() {
+            log::info!("[http] {host} is answering again");
+        }
+        state.failing_streak = 0;
+        return;
+    }
+    state.failures += 1;
+    state.failing_streak += 1;
+    // A failed trial reopens at once; otherwise the streak decides
+    if state.open_until.is_some() || state.failing_streak >= BREAKER_THRESHOLD {
+        if state.open_until.is_none() {
+            log::warn!("[http] {host} failed {} times in a row; pausing requests", state.failing_streak);
+        }
+        state.open_until = Some(Instant::now() + BREAKER_COOLDOWN);
+    }
+}
+
+/// Hold requests to `host` for `wait`. `throttled`: it came with a 429.
+fn pause(host: &str, wait: Duration, throttled: bool) {
+    let mut hosts = HOSTS.lock().unwrap();
+    let state = hosts.entry(host.to_string()).or_default();
+    if throttled {
+        state.rate_limited += 1;
+    }
+    let until = Instant::now() + wait;
+    if state.paused_until.is_none_or(|current| current < until) {
+        if wait > MAX_INLINE_WAIT {
+            log::warn!("[http] {host} is rate limiting; pausing for {}s", wait.as_secs());
+        }
+        state.paused_until = Some(until);
+    }
+}
+
+/// The pause a response asks for: a 429's Retry-After (DEFAULT_RATE_LIMIT_WAIT
+/// without one), or X-RateLimit-Reset once X-RateLimit-Remaining is 0.
+fn rate_limit(resp: &reqwest::Response) -> Option<Duration> {
+    let header = |name: &str| resp.headers().get(name).and_then(|v| v.to_str().ok()).map(str::trim);
+    let now = chrono::Utc::now();
+    let until_epoch = |secs: i64| Duration::from_secs((secs - now.timestamp()).max(0) as u64);
+
+    if resp.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = header("retry-after").and_then(|v| match v.parse::<u64>() {
+            Ok(secs) => Some(Duration::from_secs(secs)),
+            // An HTTP date
+            Err(_) => chrono::DateTime::parse_from_rfc2822(v).ok().map(|at| until_epoch(at.timestamp())),
+        });
+        return Some(retry_after.or_else(|| reset(header("x-ratelimit-reset")?)).unwrap_or(DEFAULT_RATE_LIMIT_WAIT));
+    }
+    if header("x-ratelimit-remaining").and_then(|v| v.parse::<f64>().ok()) == Some(0.0) {
+        return reset(header("x-ratelimit-reset")?);
+    }
+    None
+}
+
+/// X-RateLimit-Reset: seconds from now, or (as some servers send it) a Unix
+/// time.
+fn reset(value: &str) -> Option<Duration> {
+    let secs = value.parse::<f64>().ok()?.max(0.0) as i64;
+    Some(if secs > 1_000_000_000 {
+        Duration::from_secs((secs - chrono::Utc::now().timestamp()).max(0) as u64)
+    } else {
+        Duration::from_secs(secs as u64)
+    })
+}
+
+fn note_retry(host: &str) {
+    if let Some(state) = HOSTS.lock().unwrap().get_mut(host) {
+        state.retries += 1;
+    }
+}
+
+/// BACKOFF_BASE doubled per attempt up to BACKOFF_MAX, less up to a quarter
+/// of jitter so clients that failed together don't retry together.
+fn backoff(attempt: u32) -> Duration {
+    let delay = BACKOFF_BASE.saturating_mul(1 << (attempt - 1).min(16)).min(BACKOFF_MAX);
+    delay.mul_f64(1.0 - rand::thread_rng().gen_range(0.0..0.25))
+}
@@ -0,0 +1,194 @@
+// src-tauri/src/jobs.rs
+// Generic handle for long-running maintenance jobs (backup, import, export, …).
+//
+// A job is spawned on the async runtime and the starting command returns its id
+// right away. Progress is pushed to the webview as `job-progress` events and the
+// final state (with result or error) as `job-finished`; `cancel_job(id)` flips a
+// flag the job body polls between units of work.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter};
+use uuid::Uuid;
+
+/// Finished jobs kept around for `get_job` polling.
+const MAX_FINISHED: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobInfo {
+    pub id:          String,
+    pub kind:        String,
+    pub status:      JobStatus,
+    pub done:        u64,
+    pub total:       u64,
+    pub message:     Option<String>,
+    pub result:      Option<serde_json::Value>,
+    pub error:       Option<String>,
+    pub started_at:  String,
+    pub finished_at: Option<String>,
+}
+
+pub struct JobHandle {
+    app:       AppHandle,
+    cancelled: AtomicBool,
+    info:      Mutex<JobInfo>,
+}
+
+impl JobHandle {
+    pub fn id(&self) -> String {
+        self.info.lock().unwrap().id.clone()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Call between units of work; bails out with a cancellation error.
+    pub fn check_cancelled(&self) -> anyhow::Result<()> {
+        if self.is_cancelled() {
+            anyhow::bail!("Job cancelled");
+        }
+        Ok(())
+    }
+
+    pub fn set_total(&self, total: u64) {
+        self.info.lock().unwrap().total = total;
+        self.emit_progress();
+    }
+
+    /// Advance by `n` units, optionally replacing the status message.
+    pub fn advance(&self, n: u64, message: Option<String>) {
+        {
+            let mut info = self.info.lock().unwrap();
+            info.done += n;
+            if message.is_some() {
+                info.message = message;
+            }
+        }
+        self.emit_progress();
+    }
+
+    pub fn snapshot(&self) -> JobInfo {
+        self.info.lock().unwrap().clone()
+    }
+
+    fn emit_progress(&self) {
+        let _ = self.app.emit("job-progress", self.snapshot());
+    }
+
+    fn finish(&self, outcome: anyhow::Result<serde_json::Value>) {
+        {
+            let mut info = self.info.lock().unwrap();
+            info.finished_at = Some(chrono::Utc::now().to_rfc3339());
+            match outcome {
+                Ok(v) => {
+                    info.status = JobStatus::Completed;
+                    info.result = Some(v);
+                }
+                Err(_) if self.is_cancelled() => info.status = JobStatus::Cancelled,
+                Err(e) => {
+                    info.status = JobStatus::Failed;
+                    info.error  = Some(e.to_string());
+                }
+            }
+        }
+        let _ = self.app.emit("job-finished", self.snapshot());
+    }
+}
+
+#[derive(Default)]
+pub struct JobRegistry {
+    jobs: Mutex<HashMap<String, Arc<JobHandle>>>,
+}
+
+impl JobRegistry {
+    /// Register a job and run `body` in the background. Returns the job id.
+    pub fn spawn<F, Fut>(&self, app: &AppHandle, kind: &str, body: F) -> String
+    where
+        F:   FnOnce(Arc<JobHandle>) -> Fut + Send + 'static,
+        Fut: Future<Output = anyhow::Result<serde_json::Value>> + Send + 'static,
+    {
+        let id  = Uuid::new_v4().to_string();
+        let job = Arc::new(JobHandle {
+            app:       app.clone(),
+            cancelled: AtomicBool::new(false),
+            info:      Mutex::new(JobInfo {
+                id:          id.clone(),
+                kind:        kind.to_string(),
+                status:      JobStatus::Running,
+                done:        0,
+                total:       0,
+                message:     None,
+                result:      None,
+                error:       None,
+                started_at:  chrono::Utc::now().to_rfc3339(),
+                finished_at: None,
+            }),
+        });
+
+        self.prune();
+        self.jobs.lock().unwrap().insert(id.clone(), Arc::clone(&job));
+        job.emit_progress();
+
+        tauri::async_runtime::spawn(async move {
+            let outcome = body(Arc::clone(&job)).await;
+            if let Err(e) = &outcome {
+                log::warn!("[jobs] {} {} ended: {e}", job.snapshot().kind, job.id());
+            }
+            job.finish(outcome);
+        });
+
+        id
+    }
+
+    pub fn get(&self, id: &str) -> Option<JobInfo> {
+        self.jobs.lock().unwrap().get(id).map(|j| j.snapshot())
+    }
+
+    pub fn list(&self) -> Vec<JobInfo> {
+        let mut v: Vec<JobInfo> = self.jobs.lock().unwrap().values().map(|j| j.snapshot()).collect();
+        v.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+        v
+    }
+
+    /// Request cancellation. Returns false if the job is unknown or already finished.
+    pub fn cancel(&self, id: &str) -> bool {
+        match self.jobs.lock().unwrap().get(id) {
+            Some(job) if job.snapshot().status == JobStatus::Running => {
+                job.cancelled.store(true, Ordering::Relaxed);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn prune(&self) {
+        let mut jobs = self.jobs.lock().unwrap();
+        let mut finished: Vec<(String, String)> = jobs.iter()
+            .map(|(id, j)| (id.clone(), j.snapshot()))
+            .filter(|(_, info)| info.status != JobStatus::Running)
+            .map(|(id, info)| (info.finished_at.unwrap_or_default(), id))
+            .collect();
+
+        if finished.len() <= MAX_FINISHED {
+            return;
+        }
+        finished.sort();
+        let excess = finished.len() - MAX_FINISHED;
+        for (_, id) in finished.into_iter().take(excess) {
+            jobs.remove(&id);
+        }
+    }
+}
@@ -0,0 +1,139 @@
+// src-tauri/src/keychain.rs
+// OS keychain access, namespaced per profile.
+//
+// Entries used to live under the fixed service "alem-desktop", so two installs
+// (or two accounts on one machine) overwrote each other's OAuth token, DID key
+// and database key. Each app data directory now gets a profile id, and entries
+// are stored under "alem-desktop:<profile id>". Processes sharing a data dir
+// share the profile, so they still see the same entries.
+use anyhow::{Context, Result};
+use keyring::Entry;
+use std::io::Write;
+use std::path::Path;
+use std::sync::OnceLock;
+
+const SERVICE:         &str = "alem-desktop";
+const PROFILE_ID_FILE: &str = "profile_id";
+
+/// Fixed-name entries moved out of the shared namespace by init(). DID keys
+/// are named after the DID, so they're migrated once the database is open.
+const LEGACY_ENTRIES: &[&str] = &["oauth_token", "db_key", "db_key_pending"];
+
+static PROFILE: OnceLock<String> = OnceLock::new();
+
+/// Resolve the profile for `data_dir` and migrate legacy entries into it.
+/// Must run before anything touches the keychain (the database key lives here).
+pub fn init(data_dir: &Path) -> Result<()> {
+    let profile = load_or_create_profile_id(data_dir)?;
+    let _ = PROFILE.set(profile);
+    for name in LEGACY_ENTRIES {
+        if let Err(e) = migrate_legacy(name) {
+            log::warn!("[keychain] Could not migrate {name}: {e}");
+        }
+    }
+    Ok(())
+}
+
+pub fn profile_id() -> &'static str {
+    PROFILE.get().map(String::as_str).expect("keychain::init not called")
+}
+
+pub fn get(name: &str) -> Result<Option<String>> {
+    match entry(name)?.get_password() {
+        Ok(v)                        => Ok(Some(v)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e)                       => Err(e.into()),
+    }
+}
+
+pub fn set(name: &str, value: &str) -> Result<()> {
+    entry(name)?.set_password(value)?;
+    Ok(())
+}
+
+/// Deleting a missing entry is not an error.
+pub fn delete(name: &str) -> Result<()> {
+    match entry(name)?.delete_password() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Entry name for a locally generated DID's private key.
+pub fn did_private_key(did: &str) -> String {
+    format!("did_priv_{did}")
+}
+
+/// Entry name for the random secret local encryption keys under `did` are
+/// derived from (crypto/sealed.rs).
+pub fn local_secret(did: &str) -> String {
+    format!("local_secret_{did}")
+}
+
+/// Migrate the private key of the DID stored in local_identity, if any.
+pub async fn migrate_did_key(conn: &libsql::Connection) -> Result<()> {
+    let mut rows = conn.query(
+        "SELECT did FROM local_identity WHERE id = 'singleton' AND did IS NOT NULL",
+        (),
+    ).await?;
+    if let Some(row) = rows.next().await? {
+        let did: String = row.get(0)?;
+        migrate_legacy(&did_private_key(&did))?;
+    }
+    Ok(())
+}
+
+/// Move `name` from the shared pre-profile service into this profile.
+/// An entry already present in the profile wins; the legacy copy is removed
+/// either way so the next install can't pick it up. Returns true if moved.
+pub fn migrate_legacy(name: &str) -> Result<bool> {
+    let legacy = Entry::new(SERVICE, name)?;
+    let value = match legacy.get_password() {
+        Ok(v)                        => v,
+        Err(keyring::Error::NoEntry) => return Ok(false),
+        Err(e)                       => return Err(e.into()),
+    };
+
+    let moved = get(name)?.is_none();
+    if moved {
+        set(name, &value)?;
+        log::info!("[keychain] Moved {name} into profile {}", profile_id());
+    }
+    match legacy.delete_password() {
+        // Another process sharing the data dir got there first
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(moved),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+fn entry(name: &str) -> Result<Entry> {
+    Ok(Entry::new(&format!("{SERVICE}:{}", profile_id()), name)?)
+}
+
+/// Read <data_dir>/profile_id, creating it atomically on first run so two
+/// processes starting together agree on one id.
+fn load_or_create_profile_id(data_dir: &Path) -> Result<String> {
+    let path = data_dir.join(PROFILE_ID_FILE);
+    match std::fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+        Ok(mut file) => {
+            let id = uuid::Uuid::new_v4().to_string();
+            file.write_all(id.as_bytes())?;
+            file.sync_all()?;
+            Ok(id)
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+            // The creator may still be writing; wait briefly for the id
+            for _ in 0..50 {
+                let id = std::fs::read_to_string(&path)?.trim().to_string();
+                if !id.is_empty() {
+                    return Ok(id);
+                }
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+            anyhow::bail!("{} is empty", path.display())
+        }
+        Err(e) => Err(e).with_context(|| format!("Creating {}", path.display())),
+    }
+}
@@ -1,34 +1,289 @@
 // src-tauri/src/lib.rs
+mod activitypub;
+mod backup;
+mod capture;
 mod commands;
+mod compliance;
+mod crypto;
 mod db;
+mod export;
+mod extract;
+mod http;
+mod jobs;
+mod keychain;
+mod maintenance;
+mod mirror;
+mod oauth;
+mod ocr;
+mod scan;
+mod sharing;
+mod storage;
 mod sync;
+mod tls;
+mod webdav;
 
 use std::sync::Arc;
 use tauri::Manager;
+use tauri_plugin_deep_link::DeepLinkExt;
 
 /// AppState now holds an Arc<libsql::Database> instead of a rusqlite::Connection.
 /// libsql::Database is cheaply clonable (Arc internally) and its connections are
 /// async — no Mutex needed for multi-access safety.
 pub struct AppState {
     pub db: Arc<libsql::Database>,
+    /// Shared with the sync engine, which wakes early on reconnect.
+    pub connectivity: Arc<sync::connectivity::ConnectivityMonitor>,
+    /// Long-running jobs (backup, import, export) with progress + cancel.
+    pub jobs: jobs::JobRegistry,
+    /// Rolling upload/download throughput for SyncStatus.
+    pub transfer_metrics: sync::metrics::TransferMetrics,
+    /// Open chunked content reads (begin/read/end_content_read).
+    pub reads: storage::chunked::ReadSessions,
+    /// Where document bytes are stored; the file commands go through it.
+    pub blobs: Arc<dyn storage::blob::BlobStore>,
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let handler: fn(tauri::ipc::Invoke) -> bool = tauri::generate_handler![
+        // Auth
+        commands::auth::store_oauth_token,
+        commands::auth::start_oauth_login,
+        commands::auth::get_oauth_token,
+        commands::auth::refresh_oauth_token,
+        commands::auth::clear_oauth_token,
+        commands::auth::is_authenticated,
+        // Accounts
+        commands::accounts::list_accounts,
+        commands::accounts::switch_account,
+        commands::accounts::get_server_certificate,
+        commands::accounts::trust_server_certificate,
+        commands::accounts::untrust_server_certificate,
+        // DID
+        commands::did::generate_did,
+        commands::did::get_stored_did,
+        commands::did::validate_did,
+        commands::did::store_server_did,
+        commands::did::sign_payload,
+        commands::did::verify_payload,
+        commands::did::get_did_document,
+        commands::did::list_did_keys,
+        commands::did::rotate_did_key,
+        // Tenants
+        commands::tenants::list_tenants,
+        commands::tenants::switch_tenant,
+        // Activity
+        commands::activity::get_activity_feed,
+        // Profile
+        commands::profile::update_profile,
+        commands::profile::get_profile,
+        // Contacts
+        commands::contacts::list_contacts,
+        commands::contacts::get_contact,
+        commands::contacts::add_contact,
+        commands::contacts::get_contact_verification,
+        commands::contacts::verify_contact,
+        commands::contacts::revoke_contact,
+        // Private notes
+        commands::private_notes::get_private_notes,
+        commands::private_notes::set_private_notes,
+        // Documents
+        commands::documents::create_document,
+        commands::documents::import_documents,
+        commands::documents::get_documents,
+        commands::documents::get_documents_page,
+        commands::documents::query_documents,
+        commands::documents::get_document,
+        commands::documents::get_document_text,
+        commands::documents::get_recent_documents,
+        commands::documents::get_documents_as_of,
+        commands::documents::update_document,
+        commands::documents::delete_document,
+        commands::documents::trash_document,
+        commands::documents::restore_document,
+        commands::documents::empty_trash,
+        commands::documents::bulk_update_documents,
+        commands::documents::bulk_delete_documents,
+        commands::documents::search_documents,
+        commands::documents::get_search_settings,
+        commands::documents::update_search_settings,
+        // Metadata index
+        commands::metadata_index::get_indexed_metadata_keys,
+        commands::metadata_index::set_indexed_metadata_keys,
+        // Annotations
+        commands::annotations::list_annotations,
+        commands::annotations::add_annotation,
+        commands::annotations::update_annotation,
+        commands::annotations::delete_annotation,
+        // Watches
+        commands::watches::watch_document,
+        commands::watches::unwatch_document,
+        commands::watches::list_watched_documents,
+        // Folder mirrors
+        commands::mirrors::mirror_collection,
+        commands::mirrors::unmirror_collection,
+        commands::mirrors::list_folder_mirrors,
+        commands::mirrors::sync_folder_mirror,
+        // WebDAV share
+        commands::webdav::get_webdav_settings,
+        commands::webdav::update_webdav_settings,
+        commands::webdav::get_webdav_status,
+        // Metadata schemas
+        commands::metadata_schemas::get_metadata_schemas,
+        commands::metadata_schemas::set_metadata_schema,
+        commands::metadata_schemas::delete_metadata_schema,
+        // Collections
+        commands::collections::list_collections,
+        commands::collections::create_collection,
+        commands::collections::rename_collection,
+        commands::collections::move_collection,
+        commands::collections::delete_collection,
+        commands::collections::move_documents,
+        commands::collections::get_collection_acl,
+        commands::collections::set_collection_acl,
+        commands::collections::get_acl_changeset,
+        commands::collections::retry_acl_changeset,
+        // Links
+        commands::links::link_documents,
+        commands::links::unlink_documents,
+        commands::links::get_links,
+        commands::links::get_backlinks,
+        commands::links::get_link_graph,
+        // Sharing
+        commands::sharing::create_share_link,
+        commands::sharing::list_share_links,
+        commands::sharing::revoke_share_link,
+        commands::sharing::share_document_with,
+        commands::sharing::list_document_shares,
+        commands::sharing::unshare_document,
+        commands::sharing::list_shared_with_me,
+        commands::sharing::open_shared_document,
+        commands::sharing::share_to_fediverse,
+        commands::sharing::list_fediverse_posts,
+        // Saved searches
+        commands::saved_searches::list_saved_searches,
+        commands::saved_searches::save_search,
+        commands::saved_searches::update_saved_search,
+        commands::saved_searches::delete_saved_search,
+        commands::saved_searches::run_saved_search,
+        // Tags
+        commands::tags::list_tags,
+        commands::tags::rename_tag,
+        commands::tags::merge_tags,
+        commands::tags::delete_tag,
+        // Content scanning
+        commands::scan::get_scan_settings,
+        commands::scan::update_scan_settings,
+        // OCR
+        commands::ocr::get_ocr_settings,
+        commands::ocr::update_ocr_settings,
+        commands::ocr::queue_ocr,
+        commands::ocr::get_ocr_status,
+        // Import
+        commands::import::import_with_dialog,
+        commands::import::import_files,
+        commands::import::create_document_from_clipboard,
+        commands::import::create_document_from_url,
+        commands::import::get_media_settings,
+        commands::import::update_media_settings,
+        // Files
+        commands::files::store_file,
+        commands::files::detect_content_type,
+        commands::files::get_file_path,
+        commands::files::get_preview_url,
+        commands::files::delete_file,
+        commands::files::get_document_content,
+        commands::files::get_document_content_range,
+        commands::files::get_storage_usage,
+        commands::files::set_storage_quota,
+        commands::files::set_max_text_bytes,
+        commands::files::set_storage_compression,
+        commands::files::get_storage_target,
+        commands::files::register_storage_bucket,
+        commands::files::get_ipfs_settings,
+        commands::files::update_ipfs_settings,
+        commands::files::reset_storage_target,
+        commands::files::verify_documents,
+        commands::files::begin_content_read,
+        commands::files::read_content_chunk,
+        commands::files::end_content_read,
+        // Sync
+        commands::sync::get_sync_status,
+        commands::sync::get_http_metrics,
+        commands::sync::get_sync_filter,
+        commands::sync::set_sync_filter,
+        commands::sync::trigger_sync,
+        commands::sync::get_pending_operations,
+        commands::sync::get_operation,
+        commands::sync::queue_api_request,
+        commands::sync::requeue_operation_with_payload,
+        commands::sync::retry_failed_operations,
+        commands::sync::list_sync_conflicts,
+        commands::sync::resolve_conflict,
+        commands::sync::resolve_all_conflicts,
+        commands::sync::get_sync_settings,
+        commands::sync::update_sync_settings,
+        commands::sync::get_network_settings,
+        commands::sync::update_network_settings,
+        // Diagnostics
+        commands::diagnostics::get_slow_queries,
+        commands::diagnostics::clear_slow_queries,
+        commands::diagnostics::check_query_plans,
+        commands::diagnostics::get_schema_version,
+        commands::diagnostics::migrate_schema,
+        commands::diagnostics::get_database_stats,
+        // Encryption
+        commands::encryption::get_database_encryption,
+        commands::encryption::set_database_passphrase,
+        commands::encryption::change_database_passphrase,
+        // Backup
+        commands::backup::backup_database,
+        commands::backup::restore_database,
+        commands::backup::verify_backup,
+        commands::export::export_archive,
+        commands::export::import_archive,
+        commands::export::export_sync_queue,
+        commands::export::import_sync_queue,
+        commands::export::migrate_from_account,
+        commands::export::compliance_export,
+        // Jobs
+        commands::jobs::list_jobs,
+        commands::jobs::get_job,
+        commands::jobs::cancel_job,
+        // Maintenance
+        commands::maintenance::get_maintenance_settings,
+        commands::maintenance::update_maintenance_settings,
+        commands::maintenance::run_maintenance,
+    ];
+
     tauri::Builder::default()
+        // First, so a second launch (e.g. by an alem:// link) hands over to this one
+        .plugin(tauri_plugin_single_instance::init(|_app, _argv, _cwd| {}))
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_http::init())
         .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_store::Builder::default().build())
+        .register_asynchronous_uri_scheme_protocol(storage::protocol::SCHEME, storage::protocol::handle)
         .setup(|app| {
             let data_dir = app.path().app_data_dir()
                 .expect("Failed to resolve app data dir");
             std::fs::create_dir_all(&data_dir)?;
 
+            // Before the database: its key (if any) lives in the keychain
+            keychain::init(&data_dir)?;
+
             let db_path = data_dir.join("alem.db");
 
+            // A restore staged by restore_database takes effect here
+            if let Err(e) = backup::apply_pending_restore(&db_path) {
+                log::warn!("[backup] Could not apply staged restore: {e}");
+            }
+
             // Build the libsql Database on the tokio runtime that Tauri already runs
             let database = tauri::async_runtime::block_on(async {
                 db::open(db_path.to_str().expect("Invalid path"))
@@ -36,45 +291,129 @@ pub fn run() {
                     .expect("Failed to open libsql database")
             });
 
+            // DID key entries are named after the DID, which only the database knows
+            tauri::async_runtime::block_on(async {
+                let result = match db::connect(&database).await {
+                    Ok(conn) => keychain::migrate_did_key(&conn).await,
+                    Err(e)   => Err(e),
+                };
+                if let Err(e) = result {
+                    log::warn!("[keychain] DID key migration failed: {e}");
+                }
+            });
+
+            // Tokens from before accounts move under the account the migration made
+            tauri::async_runtime::block_on(async {
+                let result = match db::connect(&database).await {
+                    Ok(conn) => oauth::adopt_unscoped(&conn).await,
+                    Err(e)   => Err(e),
+                };
+                if let Err(e) = result {
+                    log::warn!("[oauth] Token migration failed: {e}");
+                }
+            });
+
+            // Proxy, extra root certificates and pins, before anything goes out
+            tauri::async_runtime::block_on(async {
+                let result = async {
+                    let conn = db::connect(&database).await?;
+                    tls::set_pins(db::pins::list(&conn).await?);
+                    let settings = db::settings::get(&conn, db::settings::NETWORK_SETTINGS).await?;
+                    http::configure(&settings.unwrap_or_default())
+                }.await;
+                if let Err(e) = result {
+                    log::warn!("[http] Network settings not applied: {e:#}");
+                }
+            });
+
+            // Relocate pre-CAS files (uuid names) into files/<first2>/<hash>
+            tauri::async_runtime::block_on(async {
+                let files_dir = data_dir.join("files");
+                let result = match db::connect(&database).await {
+                    Ok(conn) => storage::cas::migrate_legacy_layout(&files_dir, &conn).await,
+                    Err(e)   => Err(e),
+                };
+                if let Err(e) = result {
+                    log::warn!("[cas] Legacy file migration failed: {e}");
+                }
+            });
+
+            // Dev builds: fail loudly in the log if a hot query lost its index
+            #[cfg(debug_assertions)]
+            tauri::async_runtime::block_on(async {
+                if let Ok(conn) = db::connect(&database).await {
+                    match db::plans::check_query_plans(&conn).await {
+                        Ok(issues) => for i in issues {
+                            log::warn!("[db] Query plan regression in {}: {}", i.query, i.detail);
+                        },
+                        Err(e) => log::warn!("[db] Query plan check failed: {e}"),
+                    }
+                }
+            });
+
             let db = Arc::new(database);
-            app.manage(AppState { db: Arc::clone(&db) });
+            app.manage(AppState {
+                db:           Arc::clone(&db),
+                connectivity: Arc::new(sync::connectivity::ConnectivityMonitor::new()),
+                jobs:         jobs::JobRegistry::default(),
+                transfer_metrics: sync::metrics::TransferMetrics::default(),
+                reads:        storage::chunked::ReadSessions::default(),
+                blobs:        Arc::new(storage::blob::LocalStore::new(data_dir.join("files"))),
+            });
+
+            // alem:// capture links (capture.rs); Linux and Windows learn the scheme at runtime
+            #[cfg(any(target_os = "linux", windows))]
+            if let Err(e) = app.deep_link().register_all() {
+                log::warn!("[capture] Could not register the alem:// scheme: {e}");
+            }
+            let app_handle = app.handle().clone();
+            app.deep_link().on_open_url(move |event| capture::handle_deep_links(&app_handle, event.urls()));
+            if let Ok(Some(urls)) = app.deep_link().get_current() {
+                capture::handle_deep_links(app.handle(), urls);
+            }
+
+            // Spawn connectivity monitor + background sync engine
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                sync::connectivity::watch(app_handle).await;
+            });
 
-            // Spawn background sync engine
             let app_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
                 sync::engine::start(app_handle).await;
             });
 
+            // Housekeeping once the user goes idle
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                maintenance::watch(app_handle).await;
+            });
+
+            // OCR queue (idle unless enabled in OcrSettings)
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                ocr::watch(app_handle).await;
+            });
+
+            // Collections mirrored to folders on disk
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                mirror::watch(app_handle).await;
+            });
+
+            // The library over WebDAV on localhost, when turned on
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                webdav::start(app_handle).await;
+            });
+
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![
-            // Auth
-            commands::auth::store_oauth_token,
-            commands::auth::get_oauth_token,
-            commands::auth::clear_oauth_token,
-            commands::auth::is_authenticated,
-            // DID
-            commands::did::generate_did,
-            commands::did::get_stored_did,
-            commands::did::validate_did,
-            commands::did::store_server_did,
-            // Documents
-            commands::documents::create_document,
-            commands::documents::get_documents,
-            commands::documents::get_document,
-            commands::documents::update_document,
-            commands::documents::delete_document,
-            commands::documents::search_documents,
-            // Files
-            commands::files::store_file,
-            commands::files::get_file_path,
-            commands::files::delete_file,
-            // Sync
-            commands::sync::get_sync_status,
-            commands::sync::trigger_sync,
-            commands::sync::get_pending_operations,
-            commands::sync::retry_failed_operations,
-        ])
+        // Every IPC call goes through here so idle-time maintenance knows the user is around
+        .invoke_handler(move |invoke| {
+            maintenance::note_command(invoke.message.command());
+            handler(invoke)
+        })
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
\ No newline at end of file
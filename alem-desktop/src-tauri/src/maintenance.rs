@@ -0,0 +1,268 @@
+// src-tauri/src/maintenance.rs
+// Idle-time maintenance: heavy housekeeping runs only once the user has left
+// the app alone for a while, so it never competes with interactive use.
+//
+// "Idle" means no IPC command for `idle_minutes`. The UI's background polling
+// (PASSIVE_COMMANDS) doesn't count as activity. Each task checks that the user
+// is still idle before it starts; the first command from the UI ends the run.
+
+use anyhow::Result;
+use libsql::{Connection, Value};
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+use crate::db::{self, models::MaintenanceSettings, settings, stats, trash};
+use crate::storage::{cas, compress, quota};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+const LAST_RUN_KEY:   &str = "maintenance_last_run";
+
+/// Commands the UI issues on timers rather than in response to the user.
+const PASSIVE_COMMANDS: &[&str] = &[
+    "get_documents", "get_sync_status", "is_authenticated", "get_pending_operations",
+    "list_jobs", "get_job",
+];
+
+/// Unreferenced blobs and temp files younger than this are left alone — an
+/// import may be between writing the blob and inserting its row.
+const BLOB_GRACE: Duration = Duration::from_secs(24 * 60 * 60);
+/// Full VACUUM (to switch on incremental auto-vacuum) once this share of the
+/// file is free pages.
+const VACUUM_FREE_RATIO: f64 = 0.2;
+const AUTO_VACUUM_INCREMENTAL: i64 = 2;
+/// Completed queue entries kept for the pending-operations view.
+const DONE_OPS_RETENTION_DAYS: i64 = 30;
+const PREFETCH_PER_RUN: i64 = 10;
+
+static LAST_ACTIVITY: AtomicU64 = AtomicU64::new(0);
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Record user activity (called for every IPC command).
+pub fn note_command(command: &str) {
+    if !PASSIVE_COMMANDS.contains(&command) {
+        LAST_ACTIVITY.store(now_secs(), Ordering::Relaxed);
+    }
+}
+
+pub fn idle_for() -> Duration {
+    Duration::from_secs(now_secs().saturating_sub(LAST_ACTIVITY.load(Ordering::Relaxed)))
+}
+
+/// Background task: wait for the user to go idle, then run maintenance at
+/// most once per `interval_hours`.
+pub async fn watch(app: AppHandle) {
+    LAST_ACTIVITY.store(now_secs(), Ordering::Relaxed);
+    loop {
+        tokio::time::sleep(CHECK_INTERVAL).await;
+        if let Err(e) = tick(&app).await {
+            log::warn!("[maintenance] {e}");
+        }
+    }
+}
+
+async fn tick(app: &AppHandle) -> Result<()> {
+    let conn = db::connect(&app.state::<crate::AppState>().db).await?;
+    let cfg: MaintenanceSettings = settings::get(&conn, settings::MAINTENANCE_SETTINGS).await?.unwrap_or_default();
+    if !cfg.enabled || idle_for() < cfg.idle_threshold() {
+        return Ok(());
+    }
+
+    let last_run: u64 = settings::get(&conn, LAST_RUN_KEY).await?.unwrap_or(0);
+    if now_secs().saturating_sub(last_run) < cfg.interval_hours * 3600 {
+        return Ok(());
+    }
+
+    let report = run(app, Some(cfg.idle_threshold())).await?;
+    log::info!("[maintenance] Idle run finished: {report}");
+    Ok(())
+}
+
+/// Run every task in order. With `idle_threshold` set, stop as soon as the user
+/// is active again; None runs everything (explicit run_maintenance).
+pub async fn run(app: &AppHandle, idle_threshold: Option<Duration>) -> Result<serde_json::Value> {
+    let conn  = db::connect(&app.state::<crate::AppState>().db).await?;
+    let cfg: MaintenanceSettings = settings::get(&conn, settings::MAINTENANCE_SETTINGS).await?.unwrap_or_default();
+    let still_idle = || idle_threshold.is_none_or(|t| idle_for() >= t);
+    let mut report = serde_json::Map::new();
+
+    if still_idle() {
+        optimize_fts(&conn).await?;
+        report.insert("fts_optimized".into(), true.into());
+    }
+    if still_idle() {
+        report.insert("ops_pruned".into(), prune_done_ops(&conn).await?.into());
+    }
+    if still_idle() && cfg.trash_retention_days > 0 {
+        let purged = trash::purge(&conn, Some(cfg.trash_retention_days)).await?;
+        report.insert("trash_purged".into(), purged.len().into());
+    }
+    if still_idle() {
+        report.insert("blobs_removed".into(), gc_blobs(&cas::files_dir(app)?, &conn).await?.into());
+    }
+    if still_idle() {
+        report.insert("pages_reclaimed".into(), reclaim_free_pages(&conn).await?.into());
+    }
+    if still_idle() {
+        conn.execute("ANALYZE", ()).await?;
+        report.insert("analyzed".into(), true.into());
+    }
+    if still_idle() {
+        let problems = stats::integrity_problems(&conn).await?;
+        if !problems.is_empty() {
+            log::error!("[maintenance] Integrity check failed: {}", problems.join("; "));
+        }
+        report.insert("integrity_problems".into(), problems.into());
+    }
+    if still_idle() {
+        report.insert("evicted".into(), serde_json::to_value(quota::enforce(&conn).await?)?);
+    }
+    if still_idle() {
+        report.insert("compressed_bytes_saved".into(), compress::compress_cached(&cas::files_dir(app)?, &conn).await?.into());
+    }
+    if still_idle() {
+        report.insert("prefetched".into(), prefetch(app, &conn).await?.into());
+    }
+
+    let completed = still_idle();
+    if completed {
+        settings::set(&conn, LAST_RUN_KEY, &now_secs()).await?;
+    } else {
+        log::debug!("[maintenance] User active again — stopping early");
+    }
+    report.insert("completed".into(), completed.into());
+    Ok(report.into())
+}
+
+// ── Tasks ────────────────────────────────────────────────────────────────────
+
+/// Merge FTS5 b-tree segments so queries touch fewer pages.
+async fn optimize_fts(conn: &Connection) -> Result<()> {
+    conn.execute("INSERT INTO documents_fts(documents_fts) VALUES('optimize')", ()).await?;
+    Ok(())
+}
+
+async fn prune_done_ops(conn: &Connection) -> Result<u64> {
+    Ok(conn.execute(
+        "DELETE FROM offline_operations
+         WHERE status = 'done' AND updated_at < datetime('now', ?1)",
+        libsql::params![format!("-{DONE_OPS_RETENTION_DAYS} days")],
+    ).await?)
+}
+
+/// Delete blobs no document row points at (any status — deleted documents may
+/// still be restored), either as content or as full text, and abandoned
+/// import temp files. Blobs staged by store_file are spared until they
+/// expire. Drops blobs rows no document counts any more.
+async fn gc_blobs(files_dir: &Path, conn: &Connection) -> Result<u64> {
+    let staged = cas::staged(conn, BLOB_GRACE).await?;
+    let mut referenced = HashSet::new();
+    let mut rows = conn.query(
+        "SELECT local_path FROM documents WHERE local_path IS NOT NULL
+         UNION
+         SELECT full_text_path FROM documents WHERE full_text_path IS NOT NULL",
+        (),
+    ).await?;
+    while let Some(row) = rows.next().await? {
+        if let Value::Text(p) = row.get_value(0)? {
+            referenced.insert(std::path::PathBuf::from(p));
+        }
+    }
+
+    let mut removed = 0;
+    let Ok(mut shards) = tokio::fs::read_dir(files_dir).await else { return Ok(0) };
+    while let Some(entry) = shards.next_entry().await? {
+        let path = entry.path();
+        if entry.file_type().await?.is_dir() {
+            let mut blobs = tokio::fs::read_dir(&path).await?;
+            while let Some(blob) = blobs.next_entry().await? {
+                let blob = blob.path();
+                if cas::is_blob_path(files_dir, &blob)
+                    && !referenced.contains(&blob)
+                    && !staged.contains(&blob)
+                    && older_than_grace(&blob).await
+                {
+                    tokio::fs::remove_file(&blob).await?;
+                    removed += 1;
+                }
+            }
+        } else if entry.file_name().to_string_lossy().starts_with(".tmp-") && older_than_grace(&path).await {
+            tokio::fs::remove_file(&path).await?;
+            removed += 1;
+        }
+    }
+    conn.execute("DELETE FROM blobs WHERE ref_count <= 0", ()).await?;
+    cas::reconcile_staged(conn, BLOB_GRACE).await?;
+    Ok(removed)
+}
+
+async fn older_than_grace(path: &Path) -> bool {
+    match tokio::fs::metadata(path).await.and_then(|m| m.modified()) {
+        Ok(modified) => modified.elapsed().is_ok_and(|age| age > BLOB_GRACE),
+        Err(_)       => false,
+    }
+}
+
+/// Return free pages to the filesystem. In incremental auto-vacuum mode that
+/// is a cheap incremental_vacuum; databases created before the mode was set
+/// need one full VACUUM to switch, done only once enough space is wasted.
+/// Returns the number of pages freed.
+async fn reclaim_free_pages(conn: &Connection) -> Result<i64> {
+    let free = pragma_i64(conn, "freelist_count").await?;
+    if free == 0 {
+        return Ok(0);
+    }
+    if pragma_i64(conn, "auto_vacuum").await? == AUTO_VACUUM_INCREMENTAL {
+        let mut rows = conn.query("PRAGMA incremental_vacuum", ()).await?;
+        while rows.next().await?.is_some() {}
+    } else {
+        let pages = pragma_i64(conn, "page_count").await?;
+        if (free as f64 / pages as f64) < VACUUM_FREE_RATIO {
+            return Ok(0);
+        }
+        conn.execute_batch("PRAGMA auto_vacuum = INCREMENTAL; VACUUM;").await?;
+    }
+    Ok(free - pragma_i64(conn, "freelist_count").await?)
+}
+
+async fn pragma_i64(conn: &Connection, name: &str) -> Result<i64> {
+    let mut rows = conn.query(&format!("PRAGMA {name}"), ()).await?;
+    Ok(match rows.next().await? {
+        Some(row) => row.get(0).unwrap_or(0),
+        None      => 0,
+    })
+}
+
+/// Pull down a few synced-but-not-local documents while nobody is waiting.
+async fn prefetch(app: &AppHandle, conn: &Connection) -> Result<u64> {
+    if !app.state::<crate::AppState>().connectivity.is_online() {
+        return Ok(0);
+    }
+    let mut rows = conn.query(
+        "SELECT id FROM documents
+         WHERE needs_download = 1 AND status != 'deleted' AND trashed_at IS NULL LIMIT ?1",
+        libsql::params![PREFETCH_PER_RUN],
+    ).await?;
+    let mut ids = Vec::new();
+    while let Some(row) = rows.next().await? {
+        if let Value::Text(id) = row.get_value(0)? { ids.push(id); }
+    }
+
+    let mut fetched = 0;
+    for id in ids {
+        // Prefetching is a nicety; don't fill the quota with it
+        if !quota::has_room(conn).await? {
+            break;
+        }
+        match crate::sync::engine::download_document(app, &id).await {
+            Ok(_)  => fetched += 1,
+            Err(e) => log::debug!("[maintenance] Prefetch of {id} failed: {e}"),
+        }
+    }
+    Ok(fetched)
+}
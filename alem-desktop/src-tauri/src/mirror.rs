@@ -0,0 +1,484 @@
+// src-tauri/src/mirror.rs
+// Two-way folder sync: a collection mirrored to a folder on disk, so its
+// documents can be opened and edited as ordinary files. Every
+// MIRROR_INTERVAL, and when asked (sync_folder_mirror), each mirror of the
+// current tenant gets a pass:
+//
+// - Documents directly in the collection are written to the folder under
+//   their filename. A new version in the library rewrites the file, a rename
+//   renames it, and a document leaving the collection (moved, trashed,
+//   deleted) takes its file with it.
+// - A file edited in the folder becomes its document's new content: a new
+//   local_version, queued for upload like any other edit. A new file is
+//   imported into the collection. A deleted file trashes its document when
+//   the mirror propagates deletes, and is written again otherwise.
+//
+// mirror_files keeps each file's size, mtime and hash as of the last pass,
+// which is how a pass tells which side changed; when both did, the file
+// wins. Subfolders, hidden files and files still being written are left
+// alone. A file renamed in the folder counts as deleted plus new.
+use anyhow::{bail, Context, Result};
+use libsql::Connection;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+use uuid::Uuid;
+
+use crate::commands::{documents::CreateDocumentInput, import};
+use crate::db::{self, identity, mirrors::{self, MirrorFile}, models::{Columns, FolderMirror}, ops, trash};
+use crate::storage::{cas, compress, text};
+use crate::{extract, ocr};
+
+const MIRROR_INTERVAL: Duration = Duration::from_secs(30);
+/// A file modified more recently than this may still be being written; it
+/// waits for the next pass.
+const SETTLE_MS: i64 = 3_000;
+/// Where the mirror writes a file before renaming it into place.
+const TEMP_PREFIX: &str = ".alem-tmp-";
+/// Downloads and saves in progress, left for their program to finish.
+const PARTIAL_SUFFIXES: &[&str] = &[".tmp", ".part", ".crdownload", ".download"];
+
+/// One pass at a time, so a pass on demand can't race the timer.
+static PASS: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// What a pass did to one mirror.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct MirrorPass {
+    pub collection_id: String,
+    /// Files written or renamed from the library.
+    pub written:  u64,
+    /// Documents given new content from an edited file.
+    pub updated:  u64,
+    /// New files imported into the collection.
+    pub imported: u64,
+    /// Files removed because their document left the collection.
+    pub removed:  u64,
+    /// Documents trashed because their file was deleted.
+    pub trashed:  u64,
+}
+
+impl MirrorPass {
+    fn changed(&self) -> bool {
+        self.written + self.updated + self.imported + self.removed + self.trashed > 0
+    }
+}
+
+/// A live document of the mirrored collection.
+struct Doc {
+    filename:       String,
+    content_hash:   String,
+    local_path:     Option<String>,
+    needs_download: bool,
+}
+
+/// (size, mtime in ms) of a file in the folder.
+type Stat = (i64, i64);
+
+/// Background task: a pass over every mirror of the current tenant each
+/// MIRROR_INTERVAL.
+pub async fn watch(app: AppHandle) {
+    loop {
+        tokio::time::sleep(MIRROR_INTERVAL).await;
+        if let Err(e) = tick(&app).await {
+            log::warn!("[mirror] {e}");
+        }
+    }
+}
+
+async fn tick(app: &AppHandle) -> Result<()> {
+    let conn = db::connect(&app.state::<crate::AppState>().db).await?;
+    let (_, tenant_id) = identity::current(&conn).await?;
+    for mirror in mirrors::list(&conn, &tenant_id).await? {
+        // A failed pass is recorded on its mirror; the others go ahead
+        let _ = sync(app, &mirror.collection_id).await;
+    }
+    Ok(())
+}
+
+/// One pass over the mirror of `collection_id`. The outcome is recorded on
+/// the mirror, and a pass that changed anything is announced with a
+/// "folder-mirror-synced" event.
+pub async fn sync(app: &AppHandle, collection_id: &str) -> Result<MirrorPass> {
+    let _pass = PASS.lock().await;
+    let conn = db::connect(&app.state::<crate::AppState>().db).await?;
+    // Unmirrored while waiting for the lock
+    let Some(mirror) = mirrors::get(&conn, collection_id).await? else {
+        return Ok(MirrorPass { collection_id: collection_id.to_string(), ..Default::default() });
+    };
+
+    let result = pass(app, &conn, &mirror).await;
+    let error = result.as_ref().err().map(|e| format!("{e:#}"));
+    mirrors::finished(&conn, collection_id, error.as_deref()).await?;
+    match &result {
+        Ok(done) if done.changed() => {
+            log::info!(
+                "[mirror] {}: {} written, {} updated, {} imported, {} removed, {} trashed",
+                mirror.path, done.written, done.updated, done.imported, done.removed, done.trashed,
+            );
+            let _ = app.emit("folder-mirror-synced", done);
+        }
+        Ok(_) => {}
+        Err(e) => log::warn!("[mirror] {}: {e:#}", mirror.path),
+    }
+    result
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+async fn pass(app: &AppHandle, conn: &Connection, mirror: &FolderMirror) -> Result<MirrorPass> {
+    let collection = mirror.collection_id.as_str();
+    let dir   = PathBuf::from(&mirror.path);
+    let known = mirrors::files(conn, collection).await?;
+
+    // An unmounted drive must not read as every file deleted
+    if tokio::fs::metadata(&dir).await.is_err() {
+        if !known.is_empty() {
+            bail!("Folder {} is missing", dir.display());
+        }
+        tokio::fs::create_dir_all(&dir).await
+            .with_context(|| format!("Cannot create {}", dir.display()))?;
+    }
+    let (disk, busy) = scan(&dir).await?;
+    // Nor an emptied or swapped folder as a reason to trash the collection
+    let all_gone = known.values().all(|f| !disk.contains_key(&f.name) && !busy.contains(&f.name));
+    if mirror.propagate_deletes && known.len() > 1 && all_gone {
+        bail!("Every mirrored file is gone from {}; unmirror and mirror the collection again to start over", dir.display());
+    }
+
+    let (user_id, _) = identity::current(conn).await?;
+    let files_dir = cas::files_dir(app)?;
+    let docs      = documents(conn, collection).await?;
+    let mut out   = MirrorPass { collection_id: collection.to_string(), ..Default::default() };
+
+    // Names in use in the folder, and those that belong to a document
+    let mut taken: HashSet<String> = disk.keys().chain(&busy).cloned()
+        .chain(known.values().map(|f| f.name.clone()))
+        .collect();
+    let mut claimed = HashSet::new();
+    let mut placed  = HashSet::new();
+
+    for (doc_id, file) in &known {
+        if busy.contains(&file.name) {
+            claimed.insert(file.name.clone());
+            placed.insert(doc_id.clone());
+            continue;
+        }
+        let path = dir.join(&file.name);
+        let seen = disk.get(&file.name).copied();
+        let unchanged = seen == Some((file.size, file.mtime_ms));
+
+        let Some(doc) = docs.get(doc_id) else {
+            // Left the collection. A file edited since stays, and comes back
+            // in as a new document below.
+            if unchanged {
+                remove(&path).await?;
+                taken.remove(&file.name);
+                out.removed += 1;
+            }
+            mirrors::forget(conn, collection, doc_id).await?;
+            continue;
+        };
+
+        match seen {
+            None => {
+                if mirror.propagate_deletes {
+                    trash::trash(conn, &user_id, doc_id).await?;
+                    out.trashed += 1;
+                } else {
+                    // Written again below
+                    taken.remove(&file.name);
+                }
+                mirrors::forget(conn, collection, doc_id).await?;
+            }
+            Some((size, mtime_ms)) if !unchanged => {
+                claimed.insert(file.name.clone());
+                placed.insert(doc_id.clone());
+                let hash = cas::hash_file(&path).await?;
+                if hash != file.content_hash {
+                    take_in(conn, &files_dir, &user_id, doc_id, &doc.filename, &path).await
+                        .with_context(|| format!("Taking in {}", path.display()))?;
+                    out.updated += 1;
+                }
+                let file = MirrorFile { name: file.name.clone(), content_hash: hash, size, mtime_ms };
+                mirrors::remember(conn, collection, doc_id, &file).await?;
+            }
+            Some(_) => {
+                placed.insert(doc_id.clone());
+                let name = if named_after(&file.name, &doc.filename) {
+                    file.name.clone()
+                } else {
+                    taken.remove(&file.name);
+                    unique_name(&doc.filename, &mut taken)
+                };
+                claimed.insert(name.clone());
+                if doc.content_hash != file.content_hash {
+                    match write(app, &dir, doc_id, doc, &name).await {
+                        Ok(written) => {
+                            if name != file.name {
+                                remove(&path).await?;
+                            }
+                            mirrors::remember(conn, collection, doc_id, &written).await?;
+                            out.written += 1;
+                        }
+                        Err(e) => log::warn!("[mirror] Could not write {doc_id}: {e:#}"),
+                    }
+                } else if name != file.name {
+                    let target = dir.join(&name);
+                    tokio::fs::rename(&path, &target).await?;
+                    let (size, mtime_ms) = stat(&tokio::fs::metadata(&target).await?);
+                    let renamed = MirrorFile { name, content_hash: file.content_hash.clone(), size, mtime_ms };
+                    mirrors::remember(conn, collection, doc_id, &renamed).await?;
+                    out.written += 1;
+                }
+            }
+        }
+    }
+
+    // Documents with no file yet
+    for (doc_id, doc) in &docs {
+        // (or whose file was deleted and who went to the trash for it)
+        let trashed = mirror.propagate_deletes && known.get(doc_id).is_some_and(|f| !disk.contains_key(&f.name));
+        if placed.contains(doc_id) || trashed {
+            continue;
+        }
+        // A file already there with the same name and content is this
+        // document's, e.g. when a folder is mirrored again
+        let name = file_name(&doc.filename);
+        if let Some(&(size, mtime_ms)) = disk.get(&name).filter(|_| !claimed.contains(&name)) {
+            if cas::hash_file(&dir.join(&name)).await? == doc.content_hash {
+                claimed.insert(name.clone());
+                let file = MirrorFile { name, content_hash: doc.content_hash.clone(), size, mtime_ms };
+                mirrors::remember(conn, collection, doc_id, &file).await?;
+                continue;
+            }
+        }
+        let name = unique_name(&doc.filename, &mut taken);
+        match write(app, &dir, doc_id, doc, &name).await {
+            Ok(written) => {
+                claimed.insert(name);
+                mirrors::remember(conn, collection, doc_id, &written).await?;
+                out.written += 1;
+            }
+            Err(e) => log::warn!("[mirror] Could not write {doc_id}: {e:#}"),
+        }
+    }
+
+    // Files with no document yet
+    for (name, &(size, mtime_ms)) in &disk {
+        if claimed.contains(name) {
+            continue;
+        }
+        let path = dir.join(name);
+        let hash = cas::hash_file(&path).await?;
+        let options = import::ImportOptions { collection_id: Some(collection.to_string()), ..Default::default() };
+        let doc_id = import::import_with(app, &path, &options).await
+            .with_context(|| format!("Importing {}", path.display()))?;
+        let file = MirrorFile { name: name.clone(), content_hash: hash, size, mtime_ms };
+        mirrors::remember(conn, collection, &doc_id, &file).await?;
+        out.imported += 1;
+    }
+
+    Ok(out)
+}
+
+/// Live documents directly in `collection_id`, by id.
+async fn documents(conn: &Connection, collection_id: &str) -> Result<HashMap<String, Doc>> {
+    let mut rows = conn.query(
+        "SELECT id, filename, content_hash, local_path, needs_download FROM documents
+         WHERE collection_id = ?1 AND status != 'deleted' AND trashed_at IS NULL",
+        libsql::params![collection_id],
+    ).await?;
+    let mut out = HashMap::new();
+    while let Some(row) = rows.next().await? {
+        let c = Columns::new(&row);
+        out.insert(c.required("id")?, Doc {
+            filename:       c.str("filename").unwrap_or_default(),
+            content_hash:   c.str("content_hash").unwrap_or_default(),
+            local_path:     c.str("local_path").filter(|p| !p.is_empty()),
+            needs_download: c.bool("needs_download"),
+        });
+    }
+    Ok(out)
+}
+
+/// Plain files directly in `dir` (name → stat), and the names of those
+/// modified too recently to trust.
+async fn scan(dir: &Path) -> Result<(HashMap<String, Stat>, HashSet<String>)> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or(0);
+    let mut files = HashMap::new();
+    let mut busy  = HashSet::new();
+    let mut entries = tokio::fs::read_dir(dir).await
+        .with_context(|| format!("Cannot read {}", dir.display()))?;
+    while let Some(entry) = entries.next_entry().await? {
+        let Ok(name) = entry.file_name().into_string() else { continue };
+        let meta = entry.metadata().await?;
+        if !meta.is_file() || ignored(&name) {
+            continue;
+        }
+        let stat = stat(&meta);
+        if now - stat.1 < SETTLE_MS {
+            busy.insert(name);
+        } else {
+            files.insert(name, stat);
+        }
+    }
+    Ok((files, busy))
+}
+
+fn stat(meta: &std::fs::Metadata) -> Stat {
+    let mtime_ms = meta.modified().ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0);
+    (meta.len() as i64, mtime_ms)
+}
+
+/// Hidden files (the mirror's own temp files among them), Office lock files
+/// and partial downloads.
+pub(crate) fn ignored(name: &str) -> bool {
+    let lower = name.to_lowercase();
+    name.starts_with('.') || name.starts_with("~$") || PARTIAL_SUFFIXES.iter().any(|s| lower.ends_with(s))
+}
+
+/// `filename` made safe to use as a file name on every platform.
+fn file_name(filename: &str) -> String {
+    let cleaned: String = filename
+        .chars()
+        .map(|c| if c.is_control() || "/\\:*?\"<>|".contains(c) { '_' } else { c })
+        .collect();
+    let cleaned = cleaned.trim().trim_start_matches('.');
+    if cleaned.is_empty() { "untitled".into() } else { cleaned.to_string() }
+}
+
+/// `stem (n).ext`, the name a document gets when its own is taken.
+fn numbered(name: &str, n: u32) -> String {
+    let path = Path::new(name);
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    match path.extension() {
+        Some(ext) => format!("{stem} ({n}).{}", ext.to_string_lossy()),
+        None      => format!("{stem} ({n})"),
+    }
+}
+
+/// The document's file name, numbered if another file has it. The name is
+/// added to `taken`.
+pub(crate) fn unique_name(filename: &str, taken: &mut HashSet<String>) -> String {
+    let base = file_name(filename);
+    let name = if taken.contains(&base) {
+        (2..).map(|n| numbered(&base, n)).find(|n| !taken.contains(n)).unwrap_or_default()
+    } else {
+        base
+    };
+    taken.insert(name.clone());
+    name
+}
+
+/// Whether `name` is what unique_name could have given `filename`.
+fn named_after(name: &str, filename: &str) -> bool {
+    let base = file_name(filename);
+    if name == base {
+        return true;
+    }
+    // "report (3).pdf" for "report.pdf"
+    let Some(open) = name.rfind(" (") else { return false };
+    let digits: String = name[open + 2..].chars().take_while(char::is_ascii_digit).collect();
+    digits.parse().is_ok_and(|n: u32| numbered(&base, n) == name)
+}
+
+/// Write a document's content to `dir/name`, through a temp file so nothing
+/// sees it half-written. Content not stored here is downloaded first.
+async fn write(app: &AppHandle, dir: &Path, doc_id: &str, doc: &Doc, name: &str) -> Result<MirrorFile> {
+    let local = match doc.local_path.as_deref().filter(|_| !doc.needs_download) {
+        Some(path) => compress::read(Path::new(path)).await.ok(),
+        None       => None,
+    };
+    let bytes = match local {
+        Some(bytes) => bytes,
+        None => {
+            let path = crate::sync::engine::download_document(app, doc_id).await?;
+            compress::read(&path).await?
+        }
+    };
+
+    let tmp    = dir.join(format!("{TEMP_PREFIX}{}", Uuid::new_v4()));
+    let target = dir.join(name);
+    let result = async {
+        tokio::fs::write(&tmp, &bytes).await?;
+        tokio::fs::rename(&tmp, &target).await
+    }.await;
+    if let Err(e) = result {
+        let _ = tokio::fs::remove_file(&tmp).await;
+        return Err(e).with_context(|| format!("Cannot write {}", target.display()));
+    }
+    let (size, mtime_ms) = stat(&tokio::fs::metadata(&target).await?);
+    Ok(MirrorFile { name: name.to_string(), content_hash: doc.content_hash.clone(), size, mtime_ms })
+}
+
+async fn remove(path: &Path) -> Result<()> {
+    match tokio::fs::remove_file(path).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Make the edited file at `path` the new content of document `doc_id`
+/// (named `filename`), through the same extraction as an import, and queue
+/// the upload. The WebDAV share (webdav.rs) saves through here too.
+pub(crate) async fn take_in(
+    conn: &Connection,
+    files_dir: &Path,
+    user_id: &str,
+    doc_id: &str,
+    filename: &str,
+    path: &Path,
+) -> Result<()> {
+    let (blob, hash, size) = cas::import_file(files_dir, path).await?;
+    let mut input = CreateDocumentInput {
+        content_type:   extract::sniff::detect(&blob, &import::content_type_for(filename), filename).await,
+        filename:       filename.to_string(),
+        local_path:     blob.to_string_lossy().to_string(),
+        file_size:      size as i64,
+        content_hash:   hash,
+        text_content:   None,
+        metadata:       None,
+        tags:           None,
+        full_text_path: None,
+    };
+    import::fill_text(conn, &mut input).await?;
+    text::limit(files_dir, conn, &mut input).await?;
+
+    // Merged into the document's metadata; a truncation note from the old
+    // content goes unless the new text was cut too
+    let mut patch = input.metadata.clone().filter(|m| m.is_object()).unwrap_or_else(|| serde_json::json!({}));
+    if patch.get("text_truncated").is_none() {
+        patch["text_truncated"] = serde_json::Value::Null;
+    }
+
+    let tx = conn.transaction().await?;
+    tx.execute(
+        "UPDATE documents
+         SET content_type = ?2, local_path = ?3, file_size = ?4, content_hash = ?5,
+             text_content = ?6, full_text_path = ?7,
+             metadata = json_patch(COALESCE(metadata, '{}'), ?8),
+             compression = NULL, is_cached_locally = 1, needs_download = 0,
+             local_version = local_version + 1, needs_upload = 1, is_synced = 0,
+             status = 'local', updated_at = datetime('now')
+         WHERE id = ?1",
+        libsql::params![
+            doc_id,
+            input.content_type.as_str(),
+            input.local_path.as_str(),
+            input.file_size,
+            input.content_hash.as_str(),
+            input.text_content.clone(),
+            input.full_text_path.clone(),
+            patch.to_string(),
+        ],
+    ).await?;
+    ops::enqueue(&tx, user_id, "upload_document", serde_json::json!({ "doc_id": doc_id })).await?;
+    tx.commit().await?;
+    ocr::queue_if_needed(conn, doc_id, &input).await?;
+    Ok(())
+}
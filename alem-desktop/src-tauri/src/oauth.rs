@@ -0,0 +1,507 @@
+// src-tauri/src/oauth.rs
+// OAuth tokens for the Alem server. The access token, the refresh token and
+// the OAuth client's credentials live in the keychain, per account
+// (db/accounts.rs); the access token's expiry (for opaque tokens, which carry
+// none) with the account's identity. Only the active account's tokens are
+// used.
+//
+// Everything that talks to the server gets its token from access_token(),
+// which first refreshes a token that is about to expire. A refresh is the
+// standard grant against the server's token endpoint (proxied to Pleroma):
+//
+// POST /api/v1/oauth/token  grant_type=refresh_token, refresh_token,
+//                           client_id, client_secret
+//                           → {access_token, refresh_token?, expires_in?}
+//
+// The sync engine also refreshes after a 401 (unauthorized()). Each refresh
+// emits "token_refreshed" with the new expiry. A refresh that fails in
+// transit leaves the tokens as they are; one the server refuses, or a 401
+// with no refresh token to try, means the session has expired: it is marked
+// so (local_identity.session_expired_at), sync pauses and "auth:expired"
+// tells the frontend to sign in again. Storing new tokens (save()) ends it
+// and wakes the sync engine.
+//
+// login() signs in without the frontend doing any OAuth: it registers an app
+// whose redirect is a loopback listener (RFC 8252), opens the browser at the
+// server's authorization page and exchanges the code it gets back, with PKCE
+// (RFC 7636):
+//
+// POST /api/v1/apps             {client_name, redirect_uris, scopes} → {client_id, client_secret}
+// GET  /api/v1/oauth/authorize  (browser) → redirect to http://127.0.0.1:<port>/callback?code&state
+// POST /api/v1/oauth/token      grant_type=authorization_code, code, code_verifier, …
+// GET  /api/v1/namespaces/account → {account: {username}}
+use anyhow::{anyhow, bail, Context, Result};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as Json;
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, Url};
+use tauri_plugin_opener::OpenerExt;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+use crate::crypto::base64::URL_SAFE_NO_PAD;
+use crate::db::{self, accounts};
+use crate::http::{self, SendRetrying};
+use crate::keychain;
+
+const ACCESS_TOKEN:  &str = "oauth_token";
+const REFRESH_TOKEN: &str = "oauth_refresh_token";
+const CLIENT:        &str = "oauth_client";
+/// A token this close to expiry is refreshed before use.
+const REFRESH_AHEAD_SECS: i64 = 5 * 60;
+/// How long a sign-in waits for the browser to come back.
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+const SCOPES: &str = "read write follow push";
+
+/// One refresh at a time: a refresh token may be single-use.
+static REFRESHING: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+/// Ends a sign-in still waiting on the browser when another one starts.
+static LOGIN_SUPERSEDED: tokio::sync::Notify = tokio::sync::Notify::const_new();
+/// Wakes the sync engine when an expired session gets new tokens.
+static SESSION_RESTORED: tokio::sync::Notify = tokio::sync::Notify::const_new();
+
+/// The app registration tokens were issued to, needed to refresh them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthClient {
+    pub client_id:     String,
+    pub client_secret: Option<String>,
+}
+
+/// What a token endpoint returned.
+#[derive(Debug, Clone)]
+pub struct Tokens {
+    pub access_token:  String,
+    pub refresh_token: Option<String>,
+    /// Seconds from now.
+    pub expires_in:    Option<i64>,
+}
+
+impl Tokens {
+    pub fn from_response(resp: &Json) -> Result<Self> {
+        Ok(Self {
+            access_token:  resp["access_token"].as_str().filter(|t| !t.is_empty())
+                .context("Token response has no access_token")?.to_string(),
+            refresh_token: resp["refresh_token"].as_str().filter(|t| !t.is_empty()).map(str::to_string),
+            expires_in:    resp["expires_in"].as_i64(),
+        })
+    }
+}
+
+/// Record who signed in where (making that account active) and keep their
+/// tokens. Returns the access token's expiry in Unix seconds, when known.
+pub async fn signed_in(
+    conn: &libsql::Connection,
+    server_url: &str,
+    username: &str,
+    tokens: &Tokens,
+    client: Option<&OAuthClient>,
+) -> Result<Option<i64>> {
+    // Non-sensitive info → libsql
+    let account = accounts::sign_in(conn, server_url, username).await?;
+    // Tokens → OS keychain only
+    save(conn, &account, tokens, client).await
+}
+
+/// Forget the active account's tokens and client. The account stays, signed
+/// out.
+pub async fn clear(conn: &libsql::Connection) -> Result<()> {
+    let Some(account) = accounts::active_id(conn).await? else { return Ok(()) };
+    for name in [ACCESS_TOKEN, REFRESH_TOKEN, CLIENT] {
+        keychain::delete(&entry(name, &account))?;
+    }
+    conn.execute(
+        "UPDATE local_identity SET token_expires_at = NULL, session_expired_at = NULL WHERE id = 'singleton'",
+        (),
+    ).await?;
+    Ok(())
+}
+
+/// The active account's access token as stored, without a refresh. None
+/// when signed out.
+pub async fn stored(conn: &libsql::Connection) -> Result<Option<String>> {
+    let Some(account) = accounts::active_id(conn).await? else { return Ok(None) };
+    Ok(keychain::get(&entry(ACCESS_TOKEN, &account))?.filter(|t| !t.is_empty()))
+}
+
+/// Whether `account` has tokens, i.e. is signed in.
+pub fn has_tokens(account: &str) -> Result<bool> {
+    Ok(keychain::get(&entry(ACCESS_TOKEN, account))?.is_some_and(|t| !t.is_empty()))
+}
+
+/// Move tokens kept before there were accounts under the active account.
+pub async fn adopt_unscoped(conn: &libsql::Connection) -> Result<()> {
+    let Some(account) = accounts::active_id(conn).await? else { return Ok(()) };
+    for name in [ACCESS_TOKEN, REFRESH_TOKEN, CLIENT] {
+        let Some(value) = keychain::get(name)? else { continue };
+        if keychain::get(&entry(name, &account))?.is_none() {
+            keychain::set(&entry(name, &account), &value)?;
+            log::info!("[oauth] Moved {name} to account {account}");
+        }
+        keychain::delete(name)?;
+    }
+    Ok(())
+}
+
+/// The access token to send, refreshed first when it expires within
+/// REFRESH_AHEAD_SECS and a refresh token is at hand. None when signed out.
+pub async fn access_token(app: &AppHandle) -> Result<Option<String>> {
+    let conn = db::connect(&app.state::<crate::AppState>().db).await?;
+    let Some(token) = stored(&conn).await? else { return Ok(None) };
+    let Some(account) = accounts::active_id(&conn).await? else { return Ok(None) };
+    let expires = expires_at(&conn, &token).await?;
+    let due = expires.is_some_and(|exp| exp - chrono::Utc::now().timestamp() <= REFRESH_AHEAD_SECS);
+    if due && keychain::get(&entry(REFRESH_TOKEN, &account))?.is_some() {
+        match refresh(app, &token).await {
+            Ok(fresh) => return Ok(Some(fresh)),
+            Err(e)    => log::warn!("[oauth] Refresh failed: {e:#}"),
+        }
+    }
+    Ok(Some(token))
+}
+
+/// Trade the active account's refresh token for a new access token, unless
+/// `stale` has been replaced already (by a refresh that ran while this one
+/// waited).
+pub async fn refresh(app: &AppHandle, stale: &str) -> Result<String> {
+    let _refreshing = REFRESHING.lock().await;
+    let conn = db::connect(&app.state::<crate::AppState>().db).await?;
+    let account = accounts::active_id(&conn).await?.context("Not signed in")?;
+    if let Some(current) = stored(&conn).await?.filter(|t| t != stale) {
+        return Ok(current);
+    }
+    let refresh_token = keychain::get(&entry(REFRESH_TOKEN, &account))?.context("No refresh token")?;
+    let client: Option<OAuthClient> = keychain::get(&entry(CLIENT, &account))?
+        .and_then(|c| serde_json::from_str(&c).ok());
+
+    let server_url = crate::sync::engine::query_server_url(&conn).await;
+    let mut form = vec![("grant_type", "refresh_token".to_string()), ("refresh_token", refresh_token)];
+    if let Some(client) = client {
+        form.push(("client_id", client.client_id));
+        if let Some(secret) = client.client_secret {
+            form.push(("client_secret", secret));
+        }
+    }
+    let resp: Json = http::client(Duration::from_secs(15))?
+        .post(format!("{server_url}/api/v1/oauth/token"))
+        .form(&form)
+        .send_retrying().await?
+        .error_for_status()?
+        .json().await?;
+
+    let tokens  = Tokens::from_response(&resp)?;
+    let expires = save(&conn, &account, &tokens, None).await?;
+    log::info!("[oauth] Access token refreshed");
+    let _ = app.emit("token_refreshed", serde_json::json!({
+        "account_id": account,
+        "expires_at": rfc3339(expires),
+    }));
+    Ok(tokens.access_token)
+}
+
+/// The server turned `stale` away: refresh it or, when the server refuses
+/// that or there is no refresh token, mark the active account's session
+/// expired. A refresh that fails otherwise (offline, a 5xx) is returned and
+/// the session left as it is, for the next 401 to try again.
+pub async fn unauthorized(app: &AppHandle, stale: &str) -> Result<()> {
+    let conn = db::connect(&app.state::<crate::AppState>().db).await?;
+    let Some(account) = accounts::active_id(&conn).await? else { return Ok(()) };
+    if session_expired(&conn).await? {
+        return Ok(());
+    }
+    if keychain::get(&entry(REFRESH_TOKEN, &account))?.is_some() {
+        match refresh(app, stale).await {
+            Ok(_) => return Ok(()),
+            Err(e) if !is_refused(&e) => return Err(e),
+            Err(e) => log::warn!("[oauth] Refresh refused: {e:#}"),
+        }
+    }
+    expire(app, &conn, &account).await
+}
+
+/// Whether the active account's session has expired (unauthorized()) and
+/// waits for it to sign in again.
+pub async fn session_expired(conn: &libsql::Connection) -> Result<bool> {
+    let mut rows = conn.query(
+        "SELECT session_expired_at IS NOT NULL FROM local_identity WHERE id = 'singleton'",
+        (),
+    ).await?;
+    Ok(match rows.next().await? {
+        Some(row) => row.get::<i64>(0)? != 0,
+        None      => false,
+    })
+}
+
+/// Resolves when new tokens end an expired session.
+pub async fn session_restored() {
+    SESSION_RESTORED.notified().await
+}
+
+// ── Sign-in ──────────────────────────────────────────────────────────────────
+
+/// Sign in to `server_url` in the browser and keep the tokens. Returns the
+/// username and the access token's expiry (Unix seconds), when known.
+pub async fn login(app: &AppHandle, server_url: &str) -> Result<(String, Option<i64>)> {
+    let server_url = server_url.trim().trim_end_matches('/').to_string();
+    let parsed = Url::parse(&server_url).context("Not a valid server URL")?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        bail!("Server URL must be http or https");
+    }
+    LOGIN_SUPERSEDED.notify_waiters();
+
+    let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+    let redirect_uri = format!("http://127.0.0.1:{}/callback", listener.local_addr()?.port());
+    let web = http::client(Duration::from_secs(15))?;
+
+    // Each sign-in registers its own app: the redirect's port changes
+    let app_resp: Json = web
+        .post(format!("{server_url}/api/v1/apps"))
+        .json(&serde_json::json!({
+            "client_name":   "Alem Desktop",
+            "redirect_uris": redirect_uri,
+            "scopes":        SCOPES,
+        }))
+        .send_retrying().await?
+        .error_for_status()?
+        .json().await?;
+    let client = OAuthClient {
+        client_id:     app_resp["client_id"].as_str().context("App registration has no client_id")?.to_string(),
+        client_secret: app_resp["client_secret"].as_str().map(str::to_string),
+    };
+
+    let verifier  = URL_SAFE_NO_PAD.encode(&random_bytes::<32>());
+    let challenge = URL_SAFE_NO_PAD.encode(&Sha256::digest(verifier.as_bytes()));
+    let state     = URL_SAFE_NO_PAD.encode(&random_bytes::<16>());
+    let mut authorize = Url::parse(&format!("{server_url}/api/v1/oauth/authorize"))?;
+    authorize.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &client.client_id)
+        .append_pair("redirect_uri", &redirect_uri)
+        .append_pair("scope", SCOPES)
+        .append_pair("state", &state)
+        .append_pair("code_challenge", &challenge)
+        .append_pair("code_challenge_method", "S256");
+    app.opener().open_url(authorize.as_str(), None::<&str>)
+        .map_err(|e| anyhow!("Could not open the browser: {e}"))?;
+
+    let code = tokio::select! {
+        code = callback(&listener, &state) => code?,
+        _ = tokio::time::sleep(LOGIN_TIMEOUT) => bail!("Sign-in timed out"),
+        _ = LOGIN_SUPERSEDED.notified() => bail!("Sign-in was superseded by another"),
+    };
+    drop(listener);
+
+    let mut form = vec![
+        ("grant_type", "authorization_code".to_string()),
+        ("code", code),
+        ("redirect_uri", redirect_uri),
+        ("code_verifier", verifier),
+        ("client_id", client.client_id.clone()),
+    ];
+    if let Some(secret) = &client.client_secret {
+        form.push(("client_secret", secret.clone()));
+    }
+    let resp: Json = web
+        .post(format!("{server_url}/api/v1/oauth/token"))
+        .form(&form)
+        .send_retrying().await?
+        .error_for_status()?
+        .json().await?;
+    let tokens = Tokens::from_response(&resp)?;
+
+    let account: Json = web
+        .get(format!("{server_url}/api/v1/namespaces/account"))
+        .bearer_auth(&tokens.access_token)
+        .send_retrying().await?
+        .error_for_status()?
+        .json().await?;
+    let username = account["account"]["username"].as_str()
+        .or_else(|| account["account"]["acct"].as_str())
+        .context("Account has no username")?
+        .to_string();
+
+    let conn = db::connect(&app.state::<crate::AppState>().db).await?;
+    let expires = signed_in(&conn, &server_url, &username, &tokens, Some(&client)).await?;
+    log::info!("[oauth] Signed in to {server_url} as {username}");
+    Ok((username, expires))
+}
+
+/// Serve the loopback redirect until it brings the authorization code for
+/// `state`. Other requests (a favicon, a stray visit) get a 404.
+async fn callback(listener: &TcpListener, state: &str) -> Result<String> {
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        let mut buf = vec![0u8; 8192];
+        let n = match tokio::time::timeout(Duration::from_secs(10), stream.read(&mut buf)).await {
+            Ok(Ok(n)) => n,
+            _         => continue,
+        };
+        let request = String::from_utf8_lossy(&buf[..n]);
+        let target = request.lines().next()
+            .and_then(|line| line.strip_prefix("GET "))
+            .and_then(|rest| rest.split(' ').next())
+            .and_then(|path| Url::parse(&format!("http://127.0.0.1{path}")).ok());
+        let Some(target) = target.filter(|t| t.path() == "/callback") else {
+            let _ = respond(&mut stream, "404 Not Found", "Not found").await;
+            continue;
+        };
+
+        let param = |name: &str| target.query_pairs().find(|(k, _)| k == name).map(|(_, v)| v.into_owned());
+        if param("state").as_deref() != Some(state) {
+            let _ = respond(&mut stream, "400 Bad Request", "This sign-in link is out of date. Start again from Alem.").await;
+            continue;
+        }
+        if let Some(error) = param("error") {
+            let _ = respond(&mut stream, "200 OK", "Sign-in was cancelled. You can close this tab.").await;
+            bail!("Authorization refused: {}", param("error_description").unwrap_or(error));
+        }
+        let code = param("code").filter(|c| !c.is_empty()).context("Redirect has no authorization code")?;
+        let _ = respond(&mut stream, "200 OK", "Signed in to Alem. You can close this tab.").await;
+        return Ok(code);
+    }
+}
+
+async fn respond(stream: &mut tokio::net::TcpStream, status: &str, message: &str) -> Result<()> {
+    let body = format!(
+        "<!doctype html><meta charset=\"utf-8\"><title>Alem</title>\
+         <p style=\"font-family:sans-serif;margin:4em;text-align:center\">{message}</p>"
+    );
+    let head = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len(),
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+// ── Token inspection ─────────────────────────────────────────────────────────
+
+/// Whether `e` is the server turning a token away.
+pub fn is_unauthorized(e: &anyhow::Error) -> bool {
+    e.chain()
+        .filter_map(|cause| cause.downcast_ref::<reqwest::Error>())
+        .any(|r| r.status() == Some(reqwest::StatusCode::UNAUTHORIZED))
+}
+
+/// Whether `e` is the token endpoint refusing a refresh (the refresh token
+/// revoked or expired: 400 invalid_grant, or 401), as opposed to not being
+/// reached or asking to be retried later (429).
+fn is_refused(e: &anyhow::Error) -> bool {
+    !http::is_throttled(e)
+        && e.chain()
+            .filter_map(|cause| cause.downcast_ref::<reqwest::Error>())
+            .any(|r| matches!(r.status(), Some(reqwest::StatusCode::BAD_REQUEST | reqwest::StatusCode::UNAUTHORIZED)))
+}
+
+/// Expiry of `token` in Unix seconds: its exp claim, or what the token
+/// response said.
+pub async fn expires_at(conn: &libsql::Connection, token: &str) -> Result<Option<i64>> {
+    if let Some(exp) = token_validity(token).1 {
+        return Ok(Some(exp));
+    }
+    let mut rows = conn.query("SELECT token_expires_at FROM local_identity WHERE id = 'singleton'", ()).await?;
+    Ok(match rows.next().await? {
+        Some(row) => row.get::<Option<i64>>(0).ok().flatten(),
+        None      => None,
+    })
+}
+
+pub fn rfc3339(unix: Option<i64>) -> Option<String> {
+    unix.and_then(|t| chrono::DateTime::from_timestamp(t, 0)).map(|t| t.to_rfc3339())
+}
+
+/// (nbf, exp) claims of a JWT, in Unix seconds. The signature isn't checked:
+/// the server does that, this only tells when to stop trying.
+pub fn token_validity(token: &str) -> (Option<i64>, Option<i64>) {
+    let parts: Vec<&str> = token.split('.').collect();
+    let claims = match parts.as_slice() {
+        [_, payload, _] => URL_SAFE_NO_PAD.decode(payload).ok()
+            .and_then(|bytes| serde_json::from_slice::<Json>(&bytes).ok()),
+        _ => None,
+    };
+    let Some(claims) = claims else { return (None, None) };
+    let time = |claim: &str| claims[claim].as_i64().or_else(|| claims[claim].as_f64().map(|t| t as i64));
+    (time("nbf"), time("exp"))
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+/// Keychain entry `name` of `account`.
+fn entry(name: &str, account: &str) -> String {
+    format!("{name}:{account}")
+}
+
+/// Keep `account`'s `tokens` (and the client they came from, when given). A
+/// response without a refresh token keeps the current one. Returns the
+/// access token's expiry in Unix seconds, when known.
+async fn save(
+    conn: &libsql::Connection,
+    account: &str,
+    tokens: &Tokens,
+    client: Option<&OAuthClient>,
+) -> Result<Option<i64>> {
+    keychain::set(&entry(ACCESS_TOKEN, account), &tokens.access_token)?;
+    if let Some(refresh) = &tokens.refresh_token {
+        keychain::set(&entry(REFRESH_TOKEN, account), refresh)?;
+    }
+    if let Some(client) = client {
+        keychain::set(&entry(CLIENT, account), &serde_json::to_string(client)?)?;
+    }
+    let expires = token_validity(&tokens.access_token).1
+        .or_else(|| tokens.expires_in.map(|s| chrono::Utc::now().timestamp() + s));
+    // The account may have been switched away from meanwhile
+    let restored = conn.execute(
+        "UPDATE local_identity SET session_expired_at = NULL
+         WHERE id = 'singleton' AND account_id = ?1 AND session_expired_at IS NOT NULL",
+        libsql::params![account],
+    ).await? > 0;
+    conn.execute(
+        "UPDATE local_identity SET token_expires_at = ?1 WHERE id = 'singleton' AND account_id = ?2",
+        libsql::params![expires, account],
+    ).await?;
+    conn.execute(
+        "UPDATE accounts SET token_expires_at = ?1, session_expired_at = NULL WHERE id = ?2",
+        libsql::params![expires, account],
+    ).await?;
+    if restored {
+        log::info!("[oauth] Session of account {account} restored");
+        SESSION_RESTORED.notify_waiters();
+    }
+    Ok(expires)
+}
+
+/// Mark `account`'s session expired and tell the frontend, once.
+async fn expire(app: &AppHandle, conn: &libsql::Connection, account: &str) -> Result<()> {
+    let changed = conn.execute(
+        "UPDATE local_identity SET session_expired_at = datetime('now')
+         WHERE id = 'singleton' AND account_id = ?1 AND session_expired_at IS NULL",
+        libsql::params![account],
+    ).await?;
+    if changed == 0 {
+        return Ok(());
+    }
+    let mut rows = conn.query(
+        "SELECT server_url, username FROM local_identity WHERE id = 'singleton'",
+        (),
+    ).await?;
+    let (server_url, username) = match rows.next().await? {
+        Some(row) => (row.get::<Option<String>>(0)?, row.get::<Option<String>>(1)?),
+        None      => (None, None),
+    };
+    log::warn!("[oauth] Session of account {account} expired; sync is paused until it signs in again");
+    let _ = app.emit("auth:expired", serde_json::json!({
+        "account_id": account,
+        "server_url": server_url,
+        "username":   username,
+    }));
+    Ok(())
+}
+
+fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0u8; N];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes
+}
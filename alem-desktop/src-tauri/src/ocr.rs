@@ -0,0 +1,308 @@
+// src-tauri/src/ocr.rs
+// Optional OCR of images and scanned PDFs, in a background queue.
+//
+// With OcrSettings::enabled, imported images and PDFs that text extraction
+// got (next to) nothing out of are queued in ocr_queue. The worker takes one
+// document at a time: images go straight to the tesseract CLI, PDFs are
+// rendered page by page with pdftoppm first. The text becomes the
+// document's text_content (the FTS triggers re-index it) with
+// metadata.text_extractor = "ocr", and syncs like any other edit. Both
+// programs are external, so nothing is linked in; a document that fails is
+// retried up to MAX_ATTEMPTS times.
+
+use anyhow::{anyhow, bail, Context, Result};
+use libsql::{Connection, Value};
+use std::path::Path;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Notify;
+
+use crate::commands::documents::CreateDocumentInput;
+use crate::db::{self, models::{OcrSettings, OcrStatus}, settings};
+use crate::storage::{cas, text};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+const MAX_ATTEMPTS:  i64 = 3;
+/// A failed document waits this long before it is tried again.
+const RETRY_DELAY:   &str = "-10 minutes";
+/// Per tesseract / pdftoppm run.
+const STEP_TIMEOUT:  Duration = Duration::from_secs(300);
+/// A PDF with less extracted text than this is taken to be a scan.
+const MIN_PDF_TEXT:  usize = 32;
+
+/// Documents worth recognizing: raster images and PDFs without a text layer
+/// (the SQL form of `wants`).
+const ELIGIBLE_SQL: &str = "
+    ((content_type LIKE 'image/%' AND content_type != 'image/svg+xml')
+     OR (content_type = 'application/pdf' AND length(trim(COALESCE(text_content, ''))) < 32))";
+
+static WAKE: Notify = Notify::const_new();
+
+/// Whether a document with this type and extracted text should be OCR'd.
+pub fn wants(content_type: &str, text: Option<&str>) -> bool {
+    match content_type {
+        "image/svg+xml"   => false,
+        "application/pdf" => text.is_none_or(|t| t.trim().len() < MIN_PDF_TEXT),
+        t                 => t.starts_with("image/"),
+    }
+}
+
+/// Queue a just-imported document when OCR is on and it needs it.
+pub async fn queue_if_needed(conn: &Connection, doc_id: &str, input: &CreateDocumentInput) -> Result<()> {
+    if !wants(&input.content_type, input.text_content.as_deref()) {
+        return Ok(());
+    }
+    let cfg: OcrSettings = settings::get(conn, settings::OCR_SETTINGS).await?.unwrap_or_default();
+    if cfg.enabled {
+        enqueue(conn, &[doc_id.to_string()]).await?;
+    }
+    Ok(())
+}
+
+/// Queue the given documents, again if they were done before. Returns how
+/// many were queued.
+pub async fn enqueue(conn: &Connection, ids: &[String]) -> Result<u64> {
+    let n = conn.execute(
+        "INSERT INTO ocr_queue (document_id)
+         SELECT id FROM documents WHERE id IN (SELECT value FROM json_each(?1)) AND status != 'deleted'
+         ON CONFLICT(document_id) DO UPDATE SET
+             status = 'pending', attempts = 0, error = NULL, chars = NULL, updated_at = datetime('now')",
+        libsql::params![serde_json::to_string(ids)?],
+    ).await?;
+    WAKE.notify_one();
+    Ok(n)
+}
+
+/// Queue every eligible document that isn't queued yet.
+pub async fn enqueue_all(conn: &Connection) -> Result<u64> {
+    let n = conn.execute(
+        &format!(
+            "INSERT OR IGNORE INTO ocr_queue (document_id)
+             SELECT id FROM documents WHERE status != 'deleted' AND {ELIGIBLE_SQL}"
+        ),
+        (),
+    ).await?;
+    WAKE.notify_one();
+    Ok(n)
+}
+
+pub async fn status(conn: &Connection, doc_id: &str) -> Result<Option<OcrStatus>> {
+    let mut rows = conn.query(
+        "SELECT document_id, status, attempts, chars, error, updated_at FROM ocr_queue WHERE document_id = ?1",
+        libsql::params![doc_id],
+    ).await?;
+    let Some(row) = rows.next().await? else { return Ok(None) };
+    Ok(Some(OcrStatus {
+        document_id: row.get(0)?,
+        status:      row.get(1)?,
+        attempts:    row.get(2)?,
+        chars:       row.get(3)?,
+        error:       row.get(4)?,
+        updated_at:  row.get(5)?,
+    }))
+}
+
+/// Background task: work through the queue whenever something is queued,
+/// and every POLL_INTERVAL for retries.
+pub async fn watch(app: AppHandle) {
+    // A document left running by the last session starts over
+    if let Err(e) = reset_running(&app).await {
+        log::warn!("[ocr] {e}");
+    }
+    loop {
+        loop {
+            match next(&app).await {
+                Ok(true)  => continue,
+                Ok(false) => break,
+                Err(e) => {
+                    log::warn!("[ocr] {e}");
+                    break;
+                }
+            }
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            _ = WAKE.notified() => {}
+        }
+    }
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+async fn reset_running(app: &AppHandle) -> Result<()> {
+    let conn = db::connect(&app.state::<crate::AppState>().db).await?;
+    conn.execute("UPDATE ocr_queue SET status = 'pending' WHERE status = 'running'", ()).await?;
+    Ok(())
+}
+
+/// OCR the oldest pending document. Returns false when there was none.
+async fn next(app: &AppHandle) -> Result<bool> {
+    let conn = db::connect(&app.state::<crate::AppState>().db).await?;
+    let cfg: OcrSettings = settings::get(&conn, settings::OCR_SETTINGS).await?.unwrap_or_default();
+    if !cfg.enabled {
+        return Ok(false);
+    }
+
+    let mut rows = conn.query(
+        "SELECT q.document_id, q.attempts, d.content_type, d.local_path, d.needs_download, d.status
+         FROM ocr_queue q LEFT JOIN documents d ON d.id = q.document_id
+         WHERE q.status = 'pending' AND (q.attempts = 0 OR q.updated_at <= datetime('now', ?1))
+         ORDER BY q.attempts, q.created_at LIMIT 1",
+        libsql::params![RETRY_DELAY],
+    ).await?;
+    let Some(row) = rows.next().await? else { return Ok(false) };
+    let id: String = row.get(0)?;
+    let attempts: i64 = row.get(1)?;
+    let content_type: Option<String> = row.get(2)?;
+    let local_path = match row.get_value(3)? { Value::Text(s) => Some(s), _ => None };
+    let needs_download = row.get::<Option<i64>>(4)?.unwrap_or(0) != 0;
+    let doc_status: Option<String> = row.get(5)?;
+    drop(rows);
+
+    let Some(content_type) = content_type.filter(|_| doc_status.as_deref() != Some("deleted")) else {
+        finish(&conn, &id, "skipped", None, Some("Document was deleted")).await?;
+        return Ok(true);
+    };
+    conn.execute(
+        "UPDATE ocr_queue SET status = 'running', attempts = attempts + 1, updated_at = datetime('now')
+         WHERE document_id = ?1",
+        libsql::params![id.as_str()],
+    ).await?;
+
+    let outcome = async {
+        let (_, path) = crate::export::blob_for(app, &id, local_path.as_deref(), needs_download).await?;
+        let text = if content_type == "application/pdf" {
+            recognize_pdf(&cas::files_dir(app)?, &cfg, &path).await?
+        } else {
+            tesseract(&cfg, &path).await?
+        };
+        store(app, &conn, &id, text).await
+    }.await;
+
+    match outcome {
+        Ok(chars) => {
+            log::info!("[ocr] {id}: {chars} characters");
+            finish(&conn, &id, "done", Some(chars as i64), None).await?;
+        }
+        Err(e) => {
+            log::warn!("[ocr] {id}: {e}");
+            let status = if attempts + 1 >= MAX_ATTEMPTS { "failed" } else { "pending" };
+            finish(&conn, &id, status, None, Some(&e.to_string())).await?;
+        }
+    }
+    Ok(true)
+}
+
+async fn finish(conn: &Connection, id: &str, status: &str, chars: Option<i64>, error: Option<&str>) -> Result<()> {
+    conn.execute(
+        "UPDATE ocr_queue SET status = ?2, chars = ?3, error = ?4, updated_at = datetime('now')
+         WHERE document_id = ?1",
+        libsql::params![id, status, chars, error],
+    ).await?;
+    Ok(())
+}
+
+/// Render the pages of a PDF and recognize each in turn.
+async fn recognize_pdf(files_dir: &Path, cfg: &OcrSettings, path: &Path) -> Result<String> {
+    let dir = files_dir.join(format!(".tmp-ocr-{}", uuid::Uuid::new_v4()));
+    tokio::fs::create_dir_all(&dir).await?;
+    let result = async {
+        run(&cfg.pdftoppm_path, &[
+            "-r".into(), cfg.dpi.to_string(),
+            "-l".into(), cfg.max_pages.to_string(),
+            "-png".into(),
+            path.to_string_lossy().into_owned(),
+            dir.join("page").to_string_lossy().into_owned(),
+        ]).await?;
+
+        // page-1.png, page-2.png, … (zero-padded for 10+ pages, so sort works)
+        let mut pages = Vec::new();
+        let mut entries = tokio::fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            pages.push(entry.path());
+        }
+        pages.sort();
+        if pages.is_empty() {
+            bail!("{} rendered no pages", cfg.pdftoppm_path);
+        }
+
+        let mut text = String::new();
+        for page in &pages {
+            let page_text = tesseract(cfg, page).await?;
+            if !text.is_empty() {
+                text.push_str("\n\n");
+            }
+            text.push_str(page_text.trim_end());
+        }
+        Ok(text)
+    }.await;
+    let _ = tokio::fs::remove_dir_all(&dir).await;
+    result
+}
+
+async fn tesseract(cfg: &OcrSettings, image: &Path) -> Result<String> {
+    run(&cfg.tesseract_path, &[
+        image.to_string_lossy().into_owned(),
+        "stdout".into(),
+        "-l".into(), cfg.languages.clone(),
+    ]).await
+}
+
+async fn run(program: &str, args: &[String]) -> Result<String> {
+    let output = tokio::time::timeout(
+        STEP_TIMEOUT,
+        tokio::process::Command::new(program).args(args).kill_on_drop(true).output(),
+    ).await
+        .map_err(|_| anyhow!("{program} timed out after {}s", STEP_TIMEOUT.as_secs()))?
+        .with_context(|| format!("Cannot run {program}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("{program} failed: {}", stderr.lines().rfind(|l| !l.trim().is_empty()).unwrap_or("no output"));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Save recognized text as the document's text_content. Returns its length.
+async fn store(app: &AppHandle, conn: &Connection, id: &str, mut text: String) -> Result<usize> {
+    let chars = text.trim().chars().count();
+    if chars == 0 {
+        return Ok(0);
+    }
+
+    let mut rows = conn.query("SELECT metadata FROM documents WHERE id = ?1", libsql::params![id]).await?;
+    let Some(row) = rows.next().await? else { bail!("Document {id} not found") };
+    let raw = match row.get_value(0)? { Value::Text(s) => Some(s), _ => None };
+    drop(rows);
+    let mut metadata = raw.as_deref()
+        .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+        .filter(|m| m.is_object())
+        .unwrap_or_else(|| serde_json::json!({}));
+
+    let full_bytes = text.len();
+    let full_text = text::cut(&cas::files_dir(app)?, conn, &mut text).await?;
+    metadata["text_extractor"] = "ocr".into();
+    match &full_text {
+        Some(_) => {
+            metadata["text_truncated"] = serde_json::json!({ "stored_bytes": text.len(), "full_bytes": full_bytes });
+        }
+        None => {
+            if let Some(m) = metadata.as_object_mut() {
+                m.remove("text_truncated");
+            }
+        }
+    }
+
+    conn.execute(
+        "UPDATE documents SET text_content = ?2, full_text_path = ?3, metadata = ?4,
+             local_version = local_version + 1, needs_upload = 1, is_synced = 0,
+             status = 'local', updated_at = datetime('now')
+         WHERE id = ?1",
+        libsql::params![
+            id,
+            text,
+            full_text.map(|(p, _)| p.to_string_lossy().into_owned()),
+            metadata.to_string(),
+        ],
+    ).await?;
+    Ok(chars)
+}
@@ -0,0 +1,124 @@
+// src-tauri/src/scan/external.rs
+// External scanners: a local program (clamscan-style) or an ICAP server.
+//
+// Both answer Ok(None) for clean, Ok(Some(detail)) for a finding, and an
+// error when the scanner itself failed or timed out.
+use anyhow::{anyhow, bail, Context, Result};
+use std::path::Path;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+const ICAP_PORT: u16 = 1344;
+/// Longest ICAP response header read.
+const MAX_ICAP_HEADER: usize = 64 * 1024;
+/// Response headers that name what an ICAP server found.
+const ICAP_FINDING_HEADERS: &[&str] = &["x-infection-found", "x-violations-found", "x-virus-id"];
+
+/// Run `program` on `path`. `{path}` in `args` is replaced by the path;
+/// without one the path is appended.
+pub async fn command(program: &str, args: &[String], path: &Path, timeout: Duration) -> Result<Option<String>> {
+    let path = path.to_string_lossy();
+    let placeholder = args.iter().any(|a| a.contains("{path}"));
+    let mut args: Vec<String> = args.iter().map(|a| a.replace("{path}", &path)).collect();
+    if !placeholder {
+        args.push(path.to_string());
+    }
+
+    let output = tokio::time::timeout(
+        timeout,
+        tokio::process::Command::new(program).args(&args).kill_on_drop(true).output(),
+    ).await
+        .map_err(|_| anyhow!("{program} timed out after {}s", timeout.as_secs()))?
+        .with_context(|| format!("Cannot run {program}"))?;
+
+    let detail = last_line(&output.stdout).or_else(|| last_line(&output.stderr));
+    match output.status.code() {
+        Some(0) => Ok(None),
+        Some(1) => Ok(Some(detail.unwrap_or_else(|| format!("Flagged by {program}")))),
+        code    => bail!(
+            "{program} failed ({}){}",
+            code.map_or("killed".to_string(), |c| format!("exit {c}")),
+            detail.map(|d| format!(": {d}")).unwrap_or_default(),
+        ),
+    }
+}
+
+/// Send `path` to an ICAP server as the body of an HTTP response (RESPMOD).
+/// 204 is clean; 200 means the server blocked or rewrote the content.
+pub async fn icap(url: &str, filename: &str, content_type: &str, path: &Path, timeout: Duration) -> Result<Option<String>> {
+    let rest = url.strip_prefix("icap://").context("ICAP url must start with icap://")?;
+    let (authority, service) = rest.split_once('/').unwrap_or((rest, ""));
+    let (host, addr) = match authority.rsplit_once(':') {
+        Some((host, _)) => (host, authority.to_string()),
+        None            => (authority, format!("{authority}:{ICAP_PORT}")),
+    };
+    let body = tokio::fs::read(path).await.with_context(|| format!("Cannot read {}", path.display()))?;
+
+    let req_hdr = format!("GET /{} HTTP/1.1\r\nHost: localhost\r\n\r\n", percent_encode(filename));
+    let res_hdr = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\r\n",
+        body.len(),
+    );
+    let head = format!(
+        "RESPMOD icap://{authority}/{service} ICAP/1.0\r\nHost: {host}\r\nAllow: 204\r\n\
+         Encapsulated: req-hdr=0, res-hdr={}, res-body={}\r\n\r\n{req_hdr}{res_hdr}",
+        req_hdr.len(),
+        req_hdr.len() + res_hdr.len(),
+    );
+
+    let exchange = async {
+        let mut stream = tokio::net::TcpStream::connect(&addr).await?;
+        stream.write_all(head.as_bytes()).await?;
+        if !body.is_empty() {
+            stream.write_all(format!("{:x}\r\n", body.len()).as_bytes()).await?;
+            stream.write_all(&body).await?;
+            stream.write_all(b"\r\n").await?;
+        }
+        stream.write_all(b"0\r\n\r\n").await?;
+
+        let mut response = Vec::new();
+        let mut buf = [0u8; 4096];
+        while !response.windows(4).any(|w| w == b"\r\n\r\n") && response.len() < MAX_ICAP_HEADER {
+            let n = stream.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            response.extend_from_slice(&buf[..n]);
+        }
+        anyhow::Ok(response)
+    };
+    let response = tokio::time::timeout(timeout, exchange).await
+        .map_err(|_| anyhow!("ICAP server {addr} timed out after {}s", timeout.as_secs()))??;
+
+    let response = String::from_utf8_lossy(&response);
+    let mut lines = response.split("\r\n");
+    let status = lines.next().unwrap_or_default();
+    let code = status.split_whitespace().nth(1).and_then(|c| c.parse::<u16>().ok());
+    let finding = lines
+        .take_while(|l| !l.is_empty())
+        .filter_map(|l| l.split_once(':'))
+        .find(|(name, _)| ICAP_FINDING_HEADERS.contains(&name.trim().to_ascii_lowercase().as_str()))
+        .map(|(_, value)| value.trim().to_string());
+    match code {
+        Some(204) => Ok(None),
+        Some(200) => Ok(Some(finding.unwrap_or_else(|| "Content blocked by the ICAP server".into()))),
+        _         => bail!("ICAP server {addr} answered {status:?}"),
+    }
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+fn last_line(output: &[u8]) -> Option<String> {
+    String::from_utf8_lossy(output)
+        .lines()
+        .map(str::trim)
+        .rfind(|l| !l.is_empty())
+        .map(|l| l.chars().take(200).collect())
+}
+
+fn percent_encode(s: &str) -> String {
+    s.bytes().map(|b| match b {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'.' | b'-' | b'_' | b'~' => (b as char).to_string(),
+        _ => format!("%{b:02X}"),
+    }).collect()
+}
@@ -0,0 +1,161 @@
+// src-tauri/src/scan/mod.rs
+// Content scanners, run on import and checked before sharing.
+//
+// ScanSettings lists the scanners to run, in order: the built-in PII
+// detector (pii.rs) and external ones (external.rs). The result goes into
+// the document's metadata.scan ({ action, findings, errors, scanned_at }),
+// so it syncs with the document. The strongest action of any finding wins.
+// Warn only reports, through the "content-flagged" event. BlockShare also
+// makes check_share refuse collection ACLs that would share the document.
+// A scanner that fails is listed in errors and doesn't stop the import.
+mod external;
+mod pii;
+
+use anyhow::{bail, Result};
+use libsql::{Connection, Value};
+use serde::Serialize;
+use serde_json::json;
+use std::{path::Path, time::Duration};
+use tauri::{AppHandle, Emitter};
+
+use crate::commands::documents::CreateDocumentInput;
+use crate::db::{
+    models::{ScanAction, ScanSettings, ScannerConfig},
+    settings,
+};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub scanner: String,
+    /// "email", "card_number", or "malware" for external scanners.
+    pub kind:    String,
+    pub count:   u64,
+    pub detail:  Option<String>,
+    pub action:  ScanAction,
+}
+
+/// Run the configured scanners on `input` before it is inserted, recording
+/// the result in its metadata (and the PII tag in its tags).
+pub async fn scan(conn: &Connection, input: &mut CreateDocumentInput) -> Result<()> {
+    let config: ScanSettings = settings::get(conn, settings::SCAN_SETTINGS).await?.unwrap_or_default();
+    if !config.enabled || config.scanners.is_empty() {
+        return Ok(());
+    }
+    let path = Some(Path::new(&input.local_path)).filter(|p| !input.local_path.is_empty() && p.is_file());
+
+    let mut findings = Vec::new();
+    let mut errors   = Vec::new();
+    for scanner in &config.scanners {
+        match scanner {
+            ScannerConfig::Pii { action, tag } => {
+                let Some(text) = input.text_content.as_deref() else { continue };
+                let found = pii::detect(text);
+                if let (Some(tag), false) = (tag, found.is_empty()) {
+                    input.tags.get_or_insert_with(Vec::new).push(tag.clone());
+                }
+                findings.extend(found.into_iter().map(|(kind, count)| Finding {
+                    scanner: "pii".into(),
+                    kind:    kind.into(),
+                    count,
+                    detail:  None,
+                    action:  *action,
+                }));
+            }
+            ScannerConfig::Command { name, action, .. } | ScannerConfig::Icap { name, action, .. } => {
+                let Some(path) = path else { continue };
+                if input.file_size as u64 > config.max_file_bytes {
+                    errors.push(format!("{name}: file larger than {} bytes not scanned", config.max_file_bytes));
+                    continue;
+                }
+                match run_external(scanner, input, path).await {
+                    Ok(None)         => {}
+                    Ok(Some(detail)) => findings.push(Finding {
+                        scanner: name.clone(),
+                        kind:    "malware".into(),
+                        count:   1,
+                        detail:  Some(detail),
+                        action:  *action,
+                    }),
+                    Err(e) => {
+                        log::warn!("[scan] {name} failed on {}: {e}", input.filename);
+                        errors.push(format!("{name}: {e}"));
+                    }
+                }
+            }
+        }
+    }
+
+    let action = findings.iter().map(|f| f.action).max();
+    if !findings.is_empty() {
+        log::info!("[scan] {}: {} findings, action {action:?}", input.filename, findings.len());
+    }
+    let metadata = input.metadata.get_or_insert_with(|| json!({}));
+    if let Some(obj) = metadata.as_object_mut() {
+        obj.insert("scan".into(), json!({
+            "action":     action,
+            "findings":   findings,
+            "errors":     errors,
+            "scanned_at": chrono::Utc::now().to_rfc3339(),
+        }));
+    }
+    Ok(())
+}
+
+/// Tell the UI about an inserted document the scan flagged.
+pub fn notify(app: &AppHandle, doc_id: &str, input: &CreateDocumentInput) {
+    let Some(scan) = input.metadata.as_ref().map(|m| &m["scan"]) else { return };
+    if scan["action"].is_null() {
+        return;
+    }
+    let _ = app.emit("content-flagged", json!({
+        "doc_id":   doc_id,
+        "filename": input.filename,
+        "action":   scan["action"],
+        "findings": scan["findings"],
+    }));
+}
+
+/// Refuse to share `collection_id` while a document it would share is
+/// blocked by a scan. Covers subcollections that inherit its ACL, as acl::set
+/// does.
+pub async fn check_share(conn: &Connection, collection_id: &str) -> Result<()> {
+    let mut rows = conn.query(
+        "WITH RECURSIVE sub(id) AS (
+             SELECT ?1
+             UNION
+             SELECT c.id FROM collections c JOIN sub ON c.parent_id = sub.id
+             WHERE NOT EXISTS (SELECT 1 FROM collection_acl a WHERE a.collection_id = c.id)
+         )
+         SELECT d.filename FROM documents d
+         WHERE d.collection_id IN (SELECT id FROM sub) AND d.status != 'deleted' AND json_valid(d.metadata)
+           AND json_extract(d.metadata, '$.scan.action') = 'block_share'
+         ORDER BY d.filename",
+        libsql::params![collection_id],
+    ).await?;
+    let mut blocked = Vec::new();
+    while let Some(row) = rows.next().await? {
+        if let Ok(Value::Text(filename)) = row.get_value(0) {
+            blocked.push(filename);
+        }
+    }
+    if blocked.is_empty() {
+        return Ok(());
+    }
+    let more = if blocked.len() > 5 { format!(" and {} more", blocked.len() - 5) } else { String::new() };
+    blocked.truncate(5);
+    bail!("Sharing blocked by a content scan: {}{more}", blocked.join(", "))
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+async fn run_external(scanner: &ScannerConfig, input: &CreateDocumentInput, path: &Path) -> Result<Option<String>> {
+    match scanner {
+        ScannerConfig::Command { program, args, timeout_secs, .. } => {
+            external::command(program, args, path, Duration::from_secs(*timeout_secs)).await
+        }
+        ScannerConfig::Icap { url, timeout_secs, .. } => {
+            external::icap(url, &input.filename, &input.content_type, path, Duration::from_secs(*timeout_secs)).await
+        }
+        ScannerConfig::Pii { .. } => Ok(None),
+    }
+}
@@ -0,0 +1,75 @@
+// src-tauri/src/scan/pii.rs
+// Built-in PII detector: e-mail addresses and card-like numbers.
+//
+// Deliberately simple and conservative: a card number is 13–19 digits,
+// optionally grouped by single spaces or dashes, passing the Luhn check.
+// Only counts are reported, so the matched text never reaches metadata.
+
+/// (kind, count) for each kind found in `text`.
+pub fn detect(text: &str) -> Vec<(&'static str, u64)> {
+    [("email", emails(text)), ("card_number", card_numbers(text))]
+        .into_iter()
+        .filter(|(_, n)| *n > 0)
+        .collect()
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+fn emails(text: &str) -> u64 {
+    let is_email_char = |c: char| c.is_ascii_alphanumeric() || "._%+-@".contains(c);
+    text.split(|c: char| !is_email_char(c))
+        .map(|token| token.trim_matches('.'))
+        .filter(|token| is_email(token))
+        .count() as u64
+}
+
+fn is_email(token: &str) -> bool {
+    let Some((local, domain)) = token.split_once('@') else { return false };
+    if local.is_empty() || domain.contains('@') {
+        return false;
+    }
+    let labels: Vec<&str> = domain.split('.').collect();
+    let tld = labels.last().copied().unwrap_or_default();
+    labels.len() >= 2
+        && labels.iter().all(|l| !l.is_empty() && l.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+        && tld.len() >= 2
+        && tld.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+fn card_numbers(text: &str) -> u64 {
+    let mut count  = 0;
+    let mut digits = Vec::new();
+    // A separator is only part of the number if a digit follows it
+    let mut separator = false;
+    for c in text.chars().chain(std::iter::once('\n')) {
+        match c {
+            '0'..='9' => {
+                digits.push(c as u8 - b'0');
+                separator = false;
+            }
+            ' ' | '-' if !digits.is_empty() && !separator => separator = true,
+            _ => {
+                if is_card_number(&digits) {
+                    count += 1;
+                }
+                digits.clear();
+                separator = false;
+            }
+        }
+    }
+    count
+}
+
+fn is_card_number(digits: &[u8]) -> bool {
+    (13..=19).contains(&digits.len())
+        && digits.iter().any(|&d| d != digits[0])
+        && luhn(digits)
+}
+
+fn luhn(digits: &[u8]) -> bool {
+    let sum: u32 = digits.iter().rev().enumerate().map(|(i, &d)| {
+        let d = d as u32;
+        if i % 2 == 1 { if d * 2 > 9 { d * 2 - 9 } else { d * 2 } } else { d }
+    }).sum();
+    sum.is_multiple_of(10)
+}
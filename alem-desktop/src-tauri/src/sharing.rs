@@ -0,0 +1,331 @@
+// src-tauri/src/sharing.rs
+// Share links: a URL to one document that works without an account, scoped
+// to what its holder may do (read, download) and how long. The server hands
+// them out and checks them:
+//
+// POST   /api/v1/shares      {id, doc_id, permissions, expires_at?} → {url, token, …}
+// DELETE /api/v1/shares/:id  revoke: the link stops working at once
+// GET    /api/v1/s/:token    the link → the document, as far as its permissions go
+//
+// A server link needs the server and the document on it. Offline, signed
+// out, with the document not synced yet, or against a server without share
+// links, the link is a local token instead: a JWS signed with the local DID's
+// key, handed out as alem://share?token=<JWS>:
+//
+//   {typ: "alem-share", jti: <share id>, iss: <DID>, sub: <doc id>,
+//    cap: [<permission>, …], iat, exp?}
+//
+// Whoever checks a local token resolves the DID and has only its signature
+// and exp to go by; revoking one is recorded in shares and nowhere else.
+// Revoking a server link queues the DELETE (an api_request op, db/ops.rs), so
+// it reaches the server whenever it can.
+//
+// A document can also be shared with one DID, as viewer or editor. That share
+// is a server record too, sent through the same queue, so sharing works
+// offline and reaches the server with the document's own upload:
+//
+// POST   /api/v1/shares                {id, doc_id, recipient_did, role}
+// DELETE /api/v1/shares/:id            unshare
+// GET    /api/v1/shares/incoming?since shares with this account's DID (sync/shares.rs)
+// GET    /api/v1/shares/incoming/:id   one of them → the document
+//
+// Documents here aren't encrypted for their readers, so there is no document
+// key to hand over: the server opens a shared document to the DID it names.
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use libsql::Connection;
+use serde_json::{json, Value as Json};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::capture::DEEP_LINK_SCHEME;
+use crate::crypto::{self, keys};
+use crate::db::{
+    self, accounts, contacts, did_shares, identity,
+    models::{
+        AclRole, ApiRequest, DocumentShare, RequestConflict, ShareIssuer, ShareLink, SharePermission,
+        SharedDocument, SharedDocumentContent, TrustLevel,
+    },
+    ops, shares,
+};
+use crate::http::{self, SendRetrying};
+use crate::oauth;
+use crate::sync::{connectivity::ConnectionState, engine};
+
+const TIMEOUT: Duration = Duration::from_secs(15);
+/// The longest a link may be given; a link meant to last has no expiry.
+const MAX_EXPIRY_SECS: i64 = 366 * 24 * 60 * 60;
+
+/// Make a link to `doc_id` granting `permissions`, expiring `expires_in`
+/// seconds from now (never, when None): the server's when it can be asked,
+/// a local token otherwise.
+pub async fn create_link(
+    app: &AppHandle,
+    doc_id: &str,
+    expires_in: Option<i64>,
+    permissions: &[SharePermission],
+) -> Result<ShareLink> {
+    let permissions: Vec<SharePermission> = [SharePermission::Read, SharePermission::Download]
+        .into_iter()
+        .filter(|p| permissions.contains(p))
+        .collect();
+    if permissions.is_empty() {
+        bail!("A share link needs at least one permission");
+    }
+    let expires_at = match expires_in {
+        Some(secs) if secs <= 0 || secs > MAX_EXPIRY_SECS => {
+            bail!("A share link can expire at most {} days from now", MAX_EXPIRY_SECS / 86_400)
+        }
+        Some(secs) => Some(Utc::now() + chrono::Duration::seconds(secs)),
+        None => None,
+    };
+
+    let conn = db::connect(&app.state::<crate::AppState>().db).await?;
+    ensure_live(&conn, doc_id).await?;
+    let id = uuid::Uuid::new_v4().to_string();
+    let (issuer, url) = match server_link(app, &conn, &id, doc_id, &permissions, expires_at).await? {
+        Some(url) => (ShareIssuer::Server, url),
+        None      => (ShareIssuer::Local, local_link(&conn, &id, doc_id, &permissions, expires_at).await?),
+    };
+    log::info!("[sharing] Share {id} of {doc_id} created ({issuer:?})");
+    shares::insert(&conn, &ShareLink {
+        id,
+        doc_id: doc_id.to_string(),
+        issuer,
+        url,
+        permissions,
+        expires_at: expires_at.map(rfc3339),
+        revoked_at: None,
+        created_at: String::new(),
+    }).await
+}
+
+/// Revoke share `id`. A server link is revoked there as soon as the queue
+/// gets through.
+pub async fn revoke_link(conn: &Connection, id: &str) -> Result<ShareLink> {
+    let share = shares::get(conn, id).await?.with_context(|| format!("Share {id} not found"))?;
+    if share.revoked_at.is_some() {
+        return Ok(share);
+    }
+    let tx = conn.transaction().await?;
+    shares::revoke(&tx, id).await?;
+    if share.issuer == ShareIssuer::Server {
+        let (user_id, _) = identity::current(&tx).await?;
+        ops::enqueue_api_request(&tx, &user_id, ApiRequest {
+            method:          "DELETE".into(),
+            path:            format!("/api/v1/shares/{id}"),
+            body:            None,
+            idempotency_key: String::new(),
+            on_conflict:     RequestConflict::Discard,
+        }).await?;
+    }
+    tx.commit().await?;
+    log::info!("[sharing] Share {id} revoked");
+    shares::get(conn, id).await?.with_context(|| format!("Share {id} not found"))
+}
+
+/// Share `doc_id` with `did` as `role` (viewer or editor). Sharing it again
+/// with the same role returns the share in force.
+pub async fn share_with(conn: &Connection, did: &str, doc_id: &str, role: AclRole) -> Result<DocumentShare> {
+    let did = did.trim();
+    if !is_did(did) {
+        bail!("{did} is not a DID");
+    }
+    if role == AclRole::Owner {
+        bail!("A document can be shared as viewer or editor, not owner");
+    }
+    if identity::did(conn).await?.as_deref() == Some(did) {
+        bail!("That's this device's own DID");
+    }
+    if contacts::trust(conn, did).await? == Some(TrustLevel::Revoked) {
+        bail!("{did} has been revoked");
+    }
+    ensure_live(conn, doc_id).await?;
+    if let Some(share) = did_shares::active(conn, doc_id, did).await? {
+        if share.role == role {
+            return Ok(share);
+        }
+        bail!("{doc_id} is already shared with {did} as {:?}; unshare it first to change the role", share.role);
+    }
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let tx = conn.transaction().await?;
+    let share = did_shares::insert(&tx, &DocumentShare {
+        id: id.clone(),
+        doc_id: doc_id.to_string(),
+        recipient_did: did.to_string(),
+        role,
+        revoked_at: None,
+        created_at: String::new(),
+    }).await?;
+    let (user_id, _) = identity::current(&tx).await?;
+    ops::enqueue_api_request(&tx, &user_id, ApiRequest {
+        method:          "POST".into(),
+        path:            "/api/v1/shares".into(),
+        body:            Some(json!({
+            "id":            id,
+            "doc_id":        doc_id,
+            "recipient_did": did,
+            "role":          role,
+        })),
+        idempotency_key: String::new(),
+        on_conflict:     RequestConflict::Discard,
+    }).await?;
+    tx.commit().await?;
+    log::info!("[sharing] {doc_id} shared with {did} ({role:?})");
+    Ok(share)
+}
+
+/// Stop sharing: revoke DID share `id`, on the server as soon as the queue
+/// gets through.
+pub async fn unshare(conn: &Connection, id: &str) -> Result<DocumentShare> {
+    let share = did_shares::get(conn, id).await?.with_context(|| format!("Share {id} not found"))?;
+    if share.revoked_at.is_some() {
+        return Ok(share);
+    }
+    let tx = conn.transaction().await?;
+    did_shares::revoke(&tx, id).await?;
+    let (user_id, _) = identity::current(&tx).await?;
+    ops::enqueue_api_request(&tx, &user_id, ApiRequest {
+        method:          "DELETE".into(),
+        path:            format!("/api/v1/shares/{id}"),
+        body:            None,
+        idempotency_key: String::new(),
+        on_conflict:     RequestConflict::Discard,
+    }).await?;
+    tx.commit().await?;
+    log::info!("[sharing] Share {id} of {} with {} revoked", share.doc_id, share.recipient_did);
+    did_shares::get(conn, id).await?.with_context(|| format!("Share {id} not found"))
+}
+
+/// Open a document shared with this account. Needs the server, which checks
+/// the share is still in force.
+pub async fn open_shared(app: &AppHandle, id: &str) -> Result<SharedDocumentContent> {
+    let state = app.state::<crate::AppState>();
+    if state.connectivity.state() == ConnectionState::Offline {
+        bail!("Offline: shared documents are opened from the server");
+    }
+    let conn = db::connect(&state.db).await?;
+    if oauth::session_expired(&conn).await? {
+        bail!("The session has expired; sign in again to open shared documents");
+    }
+    did_shares::get_incoming(&conn, id).await?.with_context(|| format!("Shared document {id} not found"))?;
+    let token = oauth::access_token(app).await?.context("Not signed in")?;
+    let server_url = engine::query_server_url(&conn).await;
+
+    let resp = http::client(TIMEOUT)?
+        .get(format!("{server_url}/api/v1/shares/incoming/{id}"))
+        .bearer_auth(&token)
+        .send_retrying().await?;
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        bail!("This document is no longer shared with you");
+    }
+    let body: Json = resp.error_for_status()?.json().await?;
+    Ok(SharedDocumentContent {
+        text_content: body["text_content"].as_str().map(str::to_string),
+        download_url: body["download_url"].as_str().map(str::to_string),
+    })
+}
+
+/// Documents shared with the active account.
+pub async fn shared_with_me(conn: &Connection) -> Result<Vec<SharedDocument>> {
+    match accounts::active_id(conn).await? {
+        Some(account_id) => did_shares::list_incoming(conn, &account_id).await,
+        None             => Ok(Vec::new()),
+    }
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+async fn ensure_live(conn: &Connection, doc_id: &str) -> Result<()> {
+    let mut rows = conn.query(
+        "SELECT 1 FROM documents WHERE id = ?1 AND status != 'deleted' AND trashed_at IS NULL",
+        libsql::params![doc_id],
+    ).await?;
+    if rows.next().await?.is_none() {
+        bail!("Document {doc_id} not found");
+    }
+    Ok(())
+}
+
+/// The server's capability URL for the share, or None when the server
+/// can't be asked for one (see the top of the file).
+async fn server_link(
+    app: &AppHandle,
+    conn: &Connection,
+    id: &str,
+    doc_id: &str,
+    permissions: &[SharePermission],
+    expires_at: Option<DateTime<Utc>>,
+) -> Result<Option<String>> {
+    let state = app.state::<crate::AppState>();
+    if state.connectivity.state() == ConnectionState::Offline || oauth::session_expired(conn).await? {
+        return Ok(None);
+    }
+    let Some(token) = oauth::access_token(app).await? else { return Ok(None) };
+    let server_url = engine::query_server_url(conn).await;
+
+    let sent = http::client(TIMEOUT)?
+        .post(format!("{server_url}/api/v1/shares"))
+        .bearer_auth(&token)
+        .json(&json!({
+            "id":          id,
+            "doc_id":      doc_id,
+            "permissions": permissions,
+            "expires_at":  expires_at.map(rfc3339),
+        }))
+        .send_retrying().await;
+    let resp = match sent {
+        Ok(resp) => resp,
+        Err(e) => {
+            log::info!("[sharing] Server not reached ({e:#}); signing the link locally");
+            return Ok(None);
+        }
+    };
+    let status = resp.status();
+    if matches!(status.as_u16(), 404 | 405 | 501) {
+        log::info!("[sharing] Server can't share {doc_id} ({status}); signing the link locally");
+        return Ok(None);
+    }
+    let answer: Json = resp.error_for_status()?.json().await?;
+    let url = answer["url"].as_str().context("The server's answer has no url")?;
+    Ok(Some(url.to_string()))
+}
+
+/// An alem:// link carrying a token signed with the local DID's key.
+async fn local_link(
+    conn: &Connection,
+    id: &str,
+    doc_id: &str,
+    permissions: &[SharePermission],
+    expires_at: Option<DateTime<Utc>>,
+) -> Result<String> {
+    let did = identity::did(conn).await?
+        .context("The server can't be asked for a link and there's no DID to sign one with")?;
+    let (key, secret) = keys::signing_key(conn, &did).await?;
+    let mut claims = json!({
+        "typ": "alem-share",
+        "jti": id,
+        "iss": did,
+        "sub": doc_id,
+        "cap": permissions,
+        "iat": Utc::now().timestamp(),
+    });
+    if let Some(exp) = expires_at {
+        claims["exp"] = exp.timestamp().into();
+    }
+    let jws = crypto::sign_jws(&secret, &key.id, &claims)?;
+    Ok(format!("{DEEP_LINK_SCHEME}://share?token={jws}"))
+}
+
+/// did:<method>:<id>, as the server takes them.
+fn is_did(s: &str) -> bool {
+    let mut parts = s.splitn(3, ':');
+    parts.next() == Some("did")
+        && parts.next().is_some_and(|m| !m.is_empty() && m.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()))
+        && parts.next().is_some_and(|id| !id.is_empty())
+}
+
+fn rfc3339(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
@@ -0,0 +1,303 @@
+// src-tauri/src/storage/archive.rs
+// Minimal streaming ustar (POSIX tar) reader/writer for backups and exports.
+//
+// Only regular files are written or read back — enough for "database + blobs +
+// manifest" bundles — so there's no need for a full tar dependency. Entries are
+// streamed, never buffered whole, since blobs can be large.
+use anyhow::{Context, Result};
+use std::path::{Component, Path};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+const BLOCK: usize = 512;
+/// 11 octal digits in the size field.
+const MAX_SIZE: u64 = 1 << 33;
+
+/// Largest manifest read_entry() is asked to hold.
+pub const MAX_MANIFEST: u64 = 16 * 1024 * 1024;
+
+/// True if `header` (the first 512 bytes of a file) looks like a ustar archive.
+pub fn is_tar(header: &[u8]) -> bool {
+    header.len() >= 263 && &header[257..262] == b"ustar"
+}
+
+pub struct TarWriter<W> {
+    out: W,
+}
+
+impl<W: AsyncWrite + Unpin> TarWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    pub async fn append_bytes(&mut self, name: &str, data: &[u8]) -> Result<()> {
+        self.out.write_all(&header(name, data.len() as u64)?).await?;
+        self.out.write_all(data).await?;
+        self.pad(data.len() as u64).await
+    }
+
+    pub async fn append_file(&mut self, name: &str, path: &Path) -> Result<u64> {
+        let mut file = tokio::fs::File::open(path).await
+            .with_context(|| format!("Opening {}", path.display()))?;
+        let size = file.metadata().await?.len();
+        self.out.write_all(&header(name, size)?).await?;
+
+        // Copy exactly `size` bytes so a file growing underneath can't corrupt the stream
+        let copied = tokio::io::copy(&mut (&mut file).take(size), &mut self.out).await?;
+        if copied != size {
+            anyhow::bail!("{} shrank while being archived", path.display());
+        }
+        self.pad(size).await?;
+        Ok(size)
+    }
+
+    /// Write the end-of-archive marker and flush. Returns the inner writer.
+    pub async fn finish(mut self) -> Result<W> {
+        self.out.write_all(&[0u8; BLOCK * 2]).await?;
+        self.out.flush().await?;
+        Ok(self.out)
+    }
+
+    async fn pad(&mut self, size: u64) -> Result<()> {
+        let rem = (size % BLOCK as u64) as usize;
+        if rem != 0 {
+            self.out.write_all(&[0u8; BLOCK][..BLOCK - rem]).await?;
+        }
+        Ok(())
+    }
+}
+
+pub struct TarEntry {
+    pub name: String,
+}
+
+pub struct TarReader<R> {
+    input: R,
+    /// Unread data + padding left in the current entry.
+    remaining: u64,
+    padding:   u64,
+}
+
+impl<R: AsyncRead + Unpin> TarReader<R> {
+    pub fn new(input: R) -> Self {
+        Self { input, remaining: 0, padding: 0 }
+    }
+
+    /// Advance to the next regular file, skipping whatever of the current
+    /// entry was not read. None at end of archive.
+    pub async fn next_entry(&mut self) -> Result<Option<TarEntry>> {
+        loop {
+            self.skip_rest().await?;
+
+            let mut block = [0u8; BLOCK];
+            self.input.read_exact(&mut block).await.context("Truncated archive")?;
+            if block.iter().all(|b| *b == 0) {
+                return Ok(None);
+            }
+            verify_checksum(&block)?;
+
+            let size = parse_octal(&block[124..136]).context("Bad entry size")?;
+            self.remaining = size;
+            self.padding   = (BLOCK as u64 - size % BLOCK as u64) % BLOCK as u64;
+
+            // '0' / NUL = regular file; directories, links, pax headers are skipped
+            if matches!(block[156], b'0' | 0) {
+                let name   = field_str(&block[0..100]);
+                let prefix = field_str(&block[345..500]);
+                let name   = if prefix.is_empty() { name } else { format!("{prefix}/{name}") };
+                return Ok(Some(TarEntry { name }));
+            }
+        }
+    }
+
+    /// Whole current entry in memory — for small entries like manifests.
+    /// Fails without reading it if it's over `max` bytes.
+    pub async fn read_entry(&mut self, max: u64) -> Result<Vec<u8>> {
+        if self.remaining > max {
+            anyhow::bail!("Archive entry of {} bytes is over the {max}-byte limit", self.remaining);
+        }
+        let mut buf = vec![0u8; self.remaining as usize];
+        self.input.read_exact(&mut buf).await?;
+        self.remaining = 0;
+        Ok(buf)
+    }
+
+    /// Stream the current entry into `out`.
+    pub async fn copy_entry<W: AsyncWrite + Unpin>(&mut self, out: &mut W) -> Result<u64> {
+        let n = tokio::io::copy(&mut (&mut self.input).take(self.remaining), out).await?;
+        if n != self.remaining {
+            anyhow::bail!("Truncated archive");
+        }
+        self.remaining = 0;
+        Ok(n)
+    }
+
+    async fn skip_rest(&mut self) -> Result<()> {
+        let n = self.remaining + self.padding;
+        if n > 0 {
+            tokio::io::copy(&mut (&mut self.input).take(n), &mut tokio::io::sink()).await?;
+        }
+        self.remaining = 0;
+        self.padding   = 0;
+        Ok(())
+    }
+}
+
+/// Reject names that would land outside the extraction directory.
+pub fn safe_relative_path(name: &str) -> Result<&Path> {
+    let path = Path::new(name);
+    if path.components().all(|c| matches!(c, Component::Normal(_))) {
+        Ok(path)
+    } else {
+        anyhow::bail!("Unsafe path in archive: {name}")
+    }
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+fn header(name: &str, size: u64) -> Result<[u8; BLOCK]> {
+    if size >= MAX_SIZE {
+        anyhow::bail!("{name} is too large for a tar entry");
+    }
+    let mut h = [0u8; BLOCK];
+
+    // Names over 100 bytes go in the 155-byte prefix field, split at a '/'
+    let (prefix, name) = if name.len() <= 100 {
+        ("", name)
+    } else {
+        name.char_indices()
+            .filter(|(i, c)| *c == '/' && *i <= 155 && name.len() - i - 1 <= 100)
+            .map(|(i, _)| (&name[..i], &name[i + 1..]))
+            .next()
+            .with_context(|| format!("Archive path too long: {name}"))?
+    };
+    h[..name.len()].copy_from_slice(name.as_bytes());
+    h[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+
+    write_octal(&mut h[100..108], 0o644);
+    write_octal(&mut h[108..116], 0);
+    write_octal(&mut h[116..124], 0);
+    write_octal(&mut h[124..136], size);
+    write_octal(&mut h[136..148], chrono::Utc::now().timestamp().max(0) as u64);
+    h[156] = b'0';
+    h[257..263].copy_from_slice(b"ustar\0");
+    h[263..265].copy_from_slice(b"00");
+
+    h[148..156].fill(b' ');
+    let sum: u64 = h.iter().map(|b| *b as u64).sum();
+    write_octal(&mut h[148..155], sum);
+    Ok(h)
+}
+
+/// Zero-padded octal, NUL terminated, filling the field.
+fn write_octal(field: &mut [u8], value: u64) {
+    let width  = field.len() - 1;
+    let digits = format!("{value:0width$o}");
+    field[..width].copy_from_slice(digits.as_bytes());
+    field[width] = 0;
+}
+
+fn parse_octal(field: &[u8]) -> Option<u64> {
+    let s = std::str::from_utf8(field).ok()?.trim_matches(|c: char| c == '\0' || c == ' ');
+    if s.is_empty() { Some(0) } else { u64::from_str_radix(s, 8).ok() }
+}
+
+fn field_str(field: &[u8]) -> String {
+    let end = field.iter().position(|b| *b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+fn verify_checksum(block: &[u8; BLOCK]) -> Result<()> {
+    let stored = parse_octal(&block[148..156]).context("Bad header checksum")?;
+    let actual: u64 = block.iter().enumerate()
+        .map(|(i, b)| if (148..156).contains(&i) { b' ' as u64 } else { *b as u64 })
+        .sum();
+    if stored != actual {
+        anyhow::bail!("Corrupt archive header");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Recompute a hand-edited header's checksum.
+    fn reseal(block: &mut [u8]) {
+        block[148..156].fill(b' ');
+        let sum: u64 = block[..BLOCK].iter().map(|b| *b as u64).sum();
+        write_octal(&mut block[148..155], sum);
+    }
+
+    #[tokio::test]
+    async fn round_trip() {
+        let path = std::env::temp_dir().join(format!("alem-archive-test-{}", std::process::id()));
+        tokio::fs::write(&path, vec![7u8; 1000]).await.unwrap();
+        let long_name = format!("blobs/{}/{}", "d".repeat(60), "f".repeat(90));
+
+        let mut tar = TarWriter::new(Vec::new());
+        tar.append_bytes("manifest.json", b"{\"version\":1}").await.unwrap();
+        assert_eq!(tar.append_file(&long_name, &path).await.unwrap(), 1000);
+        tar.append_bytes("skipped", &[1u8; 600]).await.unwrap();
+        tar.append_bytes("last", b"end").await.unwrap();
+        let bytes = tar.finish().await.unwrap();
+        tokio::fs::remove_file(&path).await.unwrap();
+        assert!(is_tar(&bytes));
+        assert_eq!(bytes.len() % BLOCK, 0);
+
+        let mut reader = TarReader::new(bytes.as_slice());
+        assert_eq!(reader.next_entry().await.unwrap().unwrap().name, "manifest.json");
+        assert_eq!(reader.read_entry(MAX_MANIFEST).await.unwrap(), b"{\"version\":1}");
+
+        assert_eq!(reader.next_entry().await.unwrap().unwrap().name, long_name);
+        let mut out = Vec::new();
+        assert_eq!(reader.copy_entry(&mut out).await.unwrap(), 1000);
+        assert_eq!(out, vec![7u8; 1000]);
+
+        // Left unread: next_entry skips it and its padding
+        assert_eq!(reader.next_entry().await.unwrap().unwrap().name, "skipped");
+        assert_eq!(reader.next_entry().await.unwrap().unwrap().name, "last");
+        assert_eq!(reader.read_entry(MAX_MANIFEST).await.unwrap(), b"end");
+        assert!(reader.next_entry().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn corrupt_checksum_is_rejected() {
+        let mut tar = TarWriter::new(Vec::new());
+        tar.append_bytes("manifest.json", b"{}").await.unwrap();
+        let mut bytes = tar.finish().await.unwrap();
+        bytes[0] = b'n';
+
+        let err = TarReader::new(bytes.as_slice()).next_entry().await.err().unwrap();
+        assert!(err.to_string().contains("Corrupt archive header"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn over_long_size_field() {
+        let mut tar = TarWriter::new(Vec::new());
+        tar.append_bytes("manifest.json", b"{}").await.unwrap();
+        let mut bytes = tar.finish().await.unwrap();
+
+        // The largest size the field holds, with none of the data behind it
+        write_octal(&mut bytes[124..136], MAX_SIZE - 1);
+        reseal(&mut bytes);
+        let mut reader = TarReader::new(bytes.as_slice());
+        reader.next_entry().await.unwrap().unwrap();
+        assert!(reader.read_entry(MAX_MANIFEST).await.is_err());
+        assert!(reader.copy_entry(&mut tokio::io::sink()).await.is_err());
+
+        // Digits that aren't octal
+        bytes[124..135].copy_from_slice(b"99999999999");
+        reseal(&mut bytes);
+        assert!(TarReader::new(bytes.as_slice()).next_entry().await.is_err());
+
+        assert!(header("huge", MAX_SIZE).is_err());
+    }
+
+    #[test]
+    fn unsafe_paths_are_rejected() {
+        assert!(safe_relative_path("blobs/ab/cdef").is_ok());
+        for bad in ["../escape", "blobs/../../escape", "/etc/passwd", "./manifest.json"] {
+            assert!(safe_relative_path(bad).is_err(), "{bad}");
+        }
+    }
+}
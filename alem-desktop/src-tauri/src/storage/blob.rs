@@ -0,0 +1,115 @@
+// src-tauri/src/storage/blob.rs
+// BlobStore: where document bytes live. The file commands reach content
+// only through this trait, so another backend (a bucket written to
+// directly, an IPFS node, …) plugs in by implementing it and being
+// installed as AppState::blobs.
+//
+// A blob is addressed by the key its store handed back from put(). The key
+// is opaque to callers. For LocalStore it is the file path kept in
+// documents.local_path. Readers always get plain bytes, whatever the store
+// does at rest (LocalStore may hold a blob zstd-compressed, see compress.rs).
+use anyhow::Result;
+use std::future::Future;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use tokio::io::{AsyncRead, AsyncSeekExt};
+
+use super::{cas, compress};
+
+/// Boxed so BlobStore stays object-safe.
+pub type BlobFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+pub type BlobReader = Pin<Box<dyn AsyncRead + Send>>;
+
+#[derive(Debug, Clone)]
+pub struct StoredBlob {
+    pub key:  String,
+    /// sha256 hex of the plain content.
+    pub hash: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct BlobStat {
+    /// Bytes the blob takes in the store (compressed size if compressed).
+    pub size:       u64,
+    pub compressed: bool,
+}
+
+pub trait BlobStore: Send + Sync {
+    /// Short name for logs.
+    fn name(&self) -> &'static str;
+
+    /// Copy the file at `source` into the store. Storing the same content
+    /// twice may return the same key.
+    fn put<'a>(&'a self, source: &'a Path) -> BlobFuture<'a, StoredBlob>;
+
+    /// The whole blob.
+    fn get<'a>(&'a self, key: &'a str) -> BlobFuture<'a, Vec<u8>>;
+
+    /// Remove a blob. A blob that is already gone is not an error.
+    fn delete<'a>(&'a self, key: &'a str) -> BlobFuture<'a, ()>;
+
+    /// None when the store has no blob under `key`.
+    fn stat<'a>(&'a self, key: &'a str) -> BlobFuture<'a, Option<BlobStat>>;
+
+    /// The blob from byte `offset` on, without reading what comes before.
+    fn stream<'a>(&'a self, key: &'a str, offset: u64) -> BlobFuture<'a, BlobReader>;
+}
+
+/// Content-addressed files under the app's files directory (cas.rs).
+pub struct LocalStore {
+    files_dir: PathBuf,
+}
+
+impl LocalStore {
+    pub fn new(files_dir: PathBuf) -> Self {
+        Self { files_dir }
+    }
+}
+
+impl BlobStore for LocalStore {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn put<'a>(&'a self, source: &'a Path) -> BlobFuture<'a, StoredBlob> {
+        Box::pin(async move {
+            let (path, hash, size) = cas::import_file(&self.files_dir, source).await?;
+            Ok(StoredBlob { key: path.to_string_lossy().to_string(), hash, size })
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> BlobFuture<'a, Vec<u8>> {
+        Box::pin(compress::read(Path::new(key)))
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> BlobFuture<'a, ()> {
+        Box::pin(async move {
+            match tokio::fs::remove_file(key).await {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            }
+        })
+    }
+
+    fn stat<'a>(&'a self, key: &'a str) -> BlobFuture<'a, Option<BlobStat>> {
+        Box::pin(async move {
+            let path = Path::new(key);
+            Ok(tokio::fs::metadata(path).await.ok().map(|m| BlobStat {
+                size:       m.len(),
+                compressed: compress::is_compressed(path),
+            }))
+        })
+    }
+
+    fn stream<'a>(&'a self, key: &'a str, offset: u64) -> BlobFuture<'a, BlobReader> {
+        Box::pin(async move {
+            // Seeking needs the plain file
+            let path = compress::plain_path(Path::new(key)).await?;
+            let mut file = tokio::fs::File::open(&path).await?;
+            file.seek(SeekFrom::Start(offset)).await?;
+            Ok(Box::pin(file) as BlobReader)
+        })
+    }
+}
@@ -0,0 +1,243 @@
+// src-tauri/src/storage/cas.rs
+// Content-addressable layout for the local files directory:
+//
+//     files/<first 2 hex chars>/<sha256 hex>
+//
+// Identical content maps to one path (free dedupe), a blob's name is its own
+// checksum (integrity check by listing), and blobs never change once written,
+// which keeps rsync-style backups cheap. A blob compressed by compress.rs is
+// `<sha256 hex>.zst`, still named after its plain content.
+//
+// A blob is written to a `.tmp-` file, fsynced, hashed on the way, and only
+// then renamed into its slot, so a crash leaves at worst a temp file (removed
+// by maintenance) and never a half-written blob under a valid name. Blobs
+// handed out by store_file wait in staged_blobs until a document claims them.
+// The GC spares staged blobs and clears entries that were claimed or have
+// expired.
+
+use anyhow::{Context, Result};
+use libsql::{Connection, Value};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use uuid::Uuid;
+
+const CHUNK: usize = 64 * 1024;
+const MIGRATED_FLAG: &str = "cas_layout_migrated";
+pub const COMPRESSED_SUFFIX: &str = ".zst";
+
+pub fn files_dir(app: &AppHandle) -> Result<PathBuf> {
+    Ok(app.path().app_data_dir()?.join("files"))
+}
+
+pub fn blob_path(files_dir: &Path, hash: &str) -> PathBuf {
+    files_dir.join(&hash[..2]).join(hash)
+}
+
+pub fn compressed_blob_path(files_dir: &Path, hash: &str) -> PathBuf {
+    files_dir.join(&hash[..2]).join(format!("{hash}{COMPRESSED_SUFFIX}"))
+}
+
+/// True if `path` already follows the `<first2>/<hash>[.zst]` layout under
+/// `files_dir`.
+pub fn is_blob_path(files_dir: &Path, path: &Path) -> bool {
+    let Ok(rel) = path.strip_prefix(files_dir) else { return false };
+    let parts: Vec<_> = rel.iter().filter_map(|p| p.to_str()).collect();
+    matches!(parts.as_slice(), [dir, name]
+        if is_sha256_hex(blob_hash(name)) && name.starts_with(dir))
+}
+
+/// The content hash a blob file name stands for.
+pub fn blob_hash(name: &str) -> &str {
+    name.strip_suffix(COMPRESSED_SUFFIX).unwrap_or(name)
+}
+
+pub async fn hash_file(path: &Path) -> Result<String> {
+    let mut file   = tokio::fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buf    = vec![0u8; CHUNK];
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 { break; }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex(&hasher.finalize()))
+}
+
+/// Copy `source` into the store, hashing while copying.
+/// Returns (blob path, sha256 hex, size in bytes).
+pub async fn import_file(files_dir: &Path, source: &Path) -> Result<(PathBuf, String, u64)> {
+    tokio::fs::create_dir_all(files_dir).await?;
+    let tmp = files_dir.join(format!(".tmp-{}", Uuid::new_v4()));
+
+    let result = async {
+        let mut src    = tokio::fs::File::open(source).await
+            .with_context(|| format!("Cannot read {}", source.display()))?;
+        let mut dst    = tokio::fs::File::create(&tmp).await?;
+        let mut hasher = Sha256::new();
+        let mut buf    = vec![0u8; CHUNK];
+        let mut size   = 0u64;
+        loop {
+            let n = src.read(&mut buf).await?;
+            if n == 0 { break; }
+            hasher.update(&buf[..n]);
+            dst.write_all(&buf[..n]).await?;
+            size += n as u64;
+        }
+        dst.flush().await?;
+        dst.sync_all().await?;
+        Ok::<_, anyhow::Error>((hex(&hasher.finalize()), size))
+    }.await;
+
+    let (hash, size) = match result {
+        Ok(v)  => v,
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&tmp).await;
+            return Err(e);
+        }
+    };
+
+    let dest = place(files_dir, &tmp, &hash).await?;
+    Ok((dest, hash, size))
+}
+
+/// Store an in-memory buffer (e.g. a downloaded object). Returns (blob path, sha256 hex).
+pub async fn store_bytes(files_dir: &Path, bytes: &[u8]) -> Result<(PathBuf, String)> {
+    tokio::fs::create_dir_all(files_dir).await?;
+    let hash = hex(&Sha256::digest(bytes));
+    let tmp  = files_dir.join(format!(".tmp-{}", Uuid::new_v4()));
+    let written = async {
+        let mut file = tokio::fs::File::create(&tmp).await?;
+        file.write_all(bytes).await?;
+        file.sync_all().await
+    }.await;
+    if let Err(e) = written {
+        let _ = tokio::fs::remove_file(&tmp).await;
+        return Err(e.into());
+    }
+    let dest = place(files_dir, &tmp, &hash).await?;
+    Ok((dest, hash))
+}
+
+/// True if `hash` looks like a sha256 hex digest we can verify against.
+pub fn is_sha256_hex(hash: &str) -> bool {
+    hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Move an already-hashed file into its blob slot (dropping it if the blob exists).
+pub async fn place(files_dir: &Path, from: &Path, hash: &str) -> Result<PathBuf> {
+    let dest = blob_path(files_dir, hash);
+    let parent = dest.parent().expect("blob has parent").to_path_buf();
+    if tokio::fs::metadata(&dest).await.is_ok() {
+        tokio::fs::remove_file(from).await?;
+        // Reused now: the GC's grace period starts over
+        let touched = dest.clone();
+        let _ = tokio::task::spawn_blocking(move || {
+            std::fs::File::options().write(true).open(&touched)?.set_modified(std::time::SystemTime::now())
+        }).await;
+    } else {
+        tokio::fs::create_dir_all(&parent).await?;
+        tokio::fs::rename(from, &dest).await?;
+        sync_dir(&parent).await;
+    }
+    Ok(dest)
+}
+
+/// Record a blob handed out before any document points at it. Removed by
+/// insert_document, or by the GC once it expires.
+pub async fn stage(conn: &Connection, path: &str, hash: &str, size: u64) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO staged_blobs (path, hash, size) VALUES (?1, ?2, ?3)",
+        libsql::params![path, hash, size as i64],
+    ).await?;
+    Ok(())
+}
+
+/// Paths staged less than `max_age` ago.
+pub async fn staged(conn: &Connection, max_age: std::time::Duration) -> Result<std::collections::HashSet<PathBuf>> {
+    let mut rows = conn.query(
+        "SELECT path FROM staged_blobs WHERE staged_at >= datetime('now', ?1)",
+        libsql::params![format!("-{} seconds", max_age.as_secs())],
+    ).await?;
+    let mut out = std::collections::HashSet::new();
+    while let Some(row) = rows.next().await? {
+        if let Value::Text(p) = row.get_value(0)? {
+            out.insert(PathBuf::from(p));
+        }
+    }
+    Ok(out)
+}
+
+/// Drop staging entries a document has claimed and those older than
+/// `max_age`. Returns how many went.
+pub async fn reconcile_staged(conn: &Connection, max_age: std::time::Duration) -> Result<u64> {
+    Ok(conn.execute(
+        "DELETE FROM staged_blobs
+         WHERE staged_at < datetime('now', ?1)
+            OR path IN (SELECT local_path FROM documents WHERE local_path IS NOT NULL)",
+        libsql::params![format!("-{} seconds", max_age.as_secs())],
+    ).await?)
+}
+
+/// Make a rename durable. On Unix the directory entry needs its own fsync;
+/// elsewhere this does nothing.
+async fn sync_dir(dir: &Path) {
+    #[cfg(unix)]
+    if let Ok(d) = tokio::fs::File::open(dir).await {
+        let _ = d.sync_all().await;
+    }
+    #[cfg(not(unix))]
+    let _ = dir;
+}
+
+/// One-time relocation of legacy `files/<uuid>.<ext>` blobs into the CAS layout,
+/// updating documents.local_path (and filling content_hash where missing).
+pub async fn migrate_legacy_layout(files_dir: &Path, conn: &Connection) -> Result<usize> {
+    if crate::db::settings::get::<bool>(conn, MIGRATED_FLAG).await?.unwrap_or(false) {
+        return Ok(0);
+    }
+
+    let mut rows = conn.query(
+        "SELECT id, local_path FROM documents WHERE local_path IS NOT NULL",
+        (),
+    ).await?;
+    // Keyed by path: several rows may share one legacy file
+    let mut docs = std::collections::BTreeMap::new();
+    while let Some(row) = rows.next().await? {
+        if let (Ok(Value::Text(id)), Ok(Value::Text(path))) = (row.get_value(0), row.get_value(1)) {
+            docs.insert(PathBuf::from(path), id);
+        }
+    }
+
+    let mut moved = 0;
+    for (path, id) in docs {
+        if !path.starts_with(files_dir) || is_blob_path(files_dir, &path) {
+            continue;
+        }
+        if tokio::fs::metadata(&path).await.is_err() {
+            log::warn!("[cas] {id}: {} missing — leaving as is", path.display());
+            continue;
+        }
+
+        let hash = hash_file(&path).await?;
+        let dest = place(files_dir, &path, &hash).await?;
+        conn.execute(
+            "UPDATE documents
+             SET local_path = ?1, content_hash = COALESCE(NULLIF(content_hash, ''), ?2)
+             WHERE local_path = ?3",
+            libsql::params![dest.to_string_lossy().to_string(), hash, path.to_string_lossy().to_string()],
+        ).await?;
+        moved += 1;
+    }
+
+    crate::db::settings::set(conn, MIGRATED_FLAG, &true).await?;
+    if moved > 0 {
+        log::info!("[cas] Relocated {moved} files into content-addressed layout");
+    }
+    Ok(moved)
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
@@ -0,0 +1,151 @@
+// src-tauri/src/storage/chunked.rs
+// Chunked content reads over IPC, for callers that cannot use alemfile://.
+//
+// begin_content_read hashes the file once and hands back a manifest with the
+// sha256 of every chunk; read_content_chunk returns raw chunk bytes (no base64)
+// that the caller checks against the manifest, so a corrupted or truncated
+// transfer is caught per chunk rather than after hundreds of megabytes.
+// read_range serves one byte range without a session or hashing.
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::SeekFrom;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use uuid::Uuid;
+
+use super::blob::BlobStore;
+use super::cas;
+
+pub const DEFAULT_CHUNK_SIZE: u64 = 4 * 1024 * 1024;
+const MIN_CHUNK_SIZE: u64 = 64 * 1024;
+const MAX_CHUNK_SIZE: u64 = 32 * 1024 * 1024;
+
+/// Sessions untouched for this long are dropped.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+const MAX_SESSIONS: usize = 16;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadManifest {
+    pub session_id:   String,
+    pub size:         u64,
+    pub chunk_size:   u64,
+    pub chunk_count:  u64,
+    /// Hex sha256 of each chunk, in order.
+    pub chunk_hashes: Vec<String>,
+    /// Hex sha256 of the whole file.
+    pub sha256:       String,
+}
+
+struct ReadSession {
+    path:       PathBuf,
+    size:       u64,
+    chunk_size: u64,
+    last_used:  Instant,
+}
+
+#[derive(Default)]
+pub struct ReadSessions {
+    sessions: Mutex<HashMap<String, ReadSession>>,
+}
+
+impl ReadSessions {
+    /// Hash `path` chunk by chunk and register a session for it.
+    pub async fn open(&self, path: PathBuf, chunk_size: Option<u64>) -> Result<ReadManifest> {
+        let chunk_size = chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE).clamp(MIN_CHUNK_SIZE, MAX_CHUNK_SIZE);
+
+        let mut file  = tokio::fs::File::open(&path).await?;
+        let size      = file.metadata().await?.len();
+        let mut whole = Sha256::new();
+        let mut chunk_hashes = Vec::new();
+        let mut buf   = vec![0u8; chunk_size as usize];
+
+        loop {
+            let n = read_full(&mut file, &mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            whole.update(&buf[..n]);
+            chunk_hashes.push(cas::hex(&Sha256::digest(&buf[..n])));
+        }
+
+        let session_id = Uuid::new_v4().to_string();
+        {
+            let mut sessions = self.sessions.lock().unwrap();
+            sessions.retain(|_, s| s.last_used.elapsed() < IDLE_TIMEOUT);
+            if sessions.len() >= MAX_SESSIONS {
+                anyhow::bail!("Too many open content reads — end some first");
+            }
+            sessions.insert(session_id.clone(), ReadSession {
+                path,
+                size,
+                chunk_size,
+                last_used: Instant::now(),
+            });
+        }
+
+        Ok(ReadManifest {
+            session_id,
+            size,
+            chunk_size,
+            chunk_count: chunk_hashes.len() as u64,
+            chunk_hashes,
+            sha256: cas::hex(&whole.finalize()),
+        })
+    }
+
+    /// Bytes of chunk `index` (the last chunk may be short).
+    pub async fn read(&self, session_id: &str, index: u64) -> Result<Vec<u8>> {
+        let (path, size, chunk_size) = {
+            let mut sessions = self.sessions.lock().unwrap();
+            let s = sessions.get_mut(session_id).context("Unknown or expired content read")?;
+            s.last_used = Instant::now();
+            (s.path.clone(), s.size, s.chunk_size)
+        };
+
+        let offset = index.checked_mul(chunk_size).filter(|o| *o < size)
+            .with_context(|| format!("Chunk {index} is out of range"))?;
+        let len = chunk_size.min(size - offset) as usize;
+
+        let mut file = tokio::fs::File::open(&path).await?;
+        file.seek(SeekFrom::Start(offset)).await?;
+        let mut buf = vec![0u8; len];
+        file.read_exact(&mut buf).await?;
+        Ok(buf)
+    }
+
+    /// Returns false if the session was unknown or had already expired.
+    pub fn close(&self, session_id: &str) -> bool {
+        self.sessions.lock().unwrap().remove(session_id).is_some()
+    }
+}
+
+/// Up to `len` bytes of blob `key` from `offset`, without reading the rest
+/// of it (for seeking players and viewers). Short at the end of the blob;
+/// `len` is capped at MAX_CHUNK_SIZE.
+pub async fn read_range(store: &dyn BlobStore, key: &str, offset: u64, len: u64) -> Result<Vec<u8>> {
+    let stat = store.stat(key).await?.context("Content not found")?;
+    if !stat.compressed && offset > stat.size {
+        anyhow::bail!("Offset {offset} is past the end of the content ({} bytes)", stat.size);
+    }
+    let len = len.min(MAX_CHUNK_SIZE);
+    let mut buf = Vec::new();
+    store.stream(key, offset).await?.take(len).read_to_end(&mut buf).await?;
+    Ok(buf)
+}
+
+/// Fill `buf` unless EOF comes first; returns the number of bytes read.
+async fn read_full(file: &mut tokio::fs::File, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let n = file.read(&mut buf[filled..]).await?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+    Ok(filled)
+}
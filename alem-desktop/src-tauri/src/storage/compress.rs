@@ -0,0 +1,135 @@
+// src-tauri/src/storage/compress.rs
+// Optional zstd compression of locally cached files.
+//
+// With StorageSettings::compression on, maintenance compresses cached blobs
+// whose content type compresses well into `<hash>.zst` (cas.rs) and points
+// their documents at it with compression = 'zstd'; a blob that shrinks by
+// less than MIN_SAVING stays plain. Readers get plain bytes from read().
+// Callers that need a plain file (range requests, chunked reads, archives,
+// opening in another app) use plain_path(), which decompresses into a temp
+// file in the files directory that blob GC removes after its grace period.
+use anyhow::{Context, Result};
+use libsql::{Connection, Value};
+use std::path::{Path, PathBuf};
+
+use super::cas;
+use crate::db::{models::StorageSettings, settings};
+
+pub const ZSTD: &str = "zstd";
+
+/// Smaller files aren't worth a frame header.
+const MIN_SIZE: i64 = 4 * 1024;
+/// Keep the compressed copy only if it is at most this share of the original.
+const MIN_SAVING: f64 = 0.9;
+const PER_RUN: i64 = 20;
+
+/// Content types stored compressed. Images, video, archives and office
+/// formats are compressed already.
+pub fn compressible(content_type: &str) -> bool {
+    content_type.starts_with("text/")
+        || matches!(
+            content_type,
+            "application/json" | "application/xml" | "application/javascript" | "image/svg+xml"
+        )
+}
+
+pub fn is_compressed(path: &Path) -> bool {
+    path.to_str().is_some_and(|p| p.ends_with(cas::COMPRESSED_SUFFIX))
+}
+
+/// The plain bytes of a blob.
+pub async fn read(path: &Path) -> Result<Vec<u8>> {
+    let bytes = tokio::fs::read(path).await.with_context(|| format!("Cannot read {}", path.display()))?;
+    if !is_compressed(path) {
+        return Ok(bytes);
+    }
+    Ok(tokio::task::spawn_blocking(move || zstd::decode_all(bytes.as_slice())).await??)
+}
+
+/// A plain file with the blob's content: the blob itself unless compressed.
+pub async fn plain_path(path: &Path) -> Result<PathBuf> {
+    if !is_compressed(path) {
+        return Ok(path.to_path_buf());
+    }
+    let name = path.file_name().and_then(|n| n.to_str()).context("Not a blob path")?;
+    let files_dir = path.parent().and_then(Path::parent).context("Not a blob path")?;
+    let plain = files_dir.join(format!(".tmp-plain-{}", cas::blob_hash(name)));
+    if tokio::fs::metadata(&plain).await.is_ok() {
+        return Ok(plain);
+    }
+    // Through a unique temp name, so a concurrent reader never sees half a file
+    let tmp = files_dir.join(format!(".tmp-{}", uuid::Uuid::new_v4()));
+    tokio::fs::write(&tmp, read(path).await?).await?;
+    tokio::fs::rename(&tmp, &plain).await?;
+    Ok(plain)
+}
+
+/// Compress up to PER_RUN cached blobs (maintenance task). Blobs serving as
+/// some document's full text stay plain. Returns bytes saved.
+pub async fn compress_cached(files_dir: &Path, conn: &Connection) -> Result<u64> {
+    let storage: StorageSettings = settings::get(conn, settings::STORAGE_SETTINGS).await?.unwrap_or_default();
+    if !storage.compression {
+        return Ok(0);
+    }
+
+    let mut rows = conn.query(
+        "SELECT local_path, MAX(content_type), MAX(content_hash) FROM documents
+         WHERE is_cached_locally = 1 AND compression IS NULL AND COALESCE(local_path, '') != ''
+           AND COALESCE(file_size, 0) >= ?1
+           AND local_path NOT IN (SELECT full_text_path FROM documents WHERE full_text_path IS NOT NULL)
+         GROUP BY local_path
+         LIMIT ?2",
+        libsql::params![MIN_SIZE, PER_RUN * 4],
+    ).await?;
+    let mut candidates = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let s = |i| match row.get_value(i).ok() { Some(Value::Text(s)) => Some(s), _ => None };
+        if let (Some(path), Some(content_type), Some(hash)) = (s(0), s(1), s(2)) {
+            if compressible(&content_type) && cas::is_sha256_hex(&hash) {
+                candidates.push((PathBuf::from(path), hash));
+            }
+        }
+    }
+    drop(rows);
+
+    let mut saved = 0;
+    for (path, hash) in candidates.into_iter().take(PER_RUN as usize) {
+        let plain = match tokio::fs::read(&path).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                log::warn!("[compress] Cannot read {}: {e}", path.display());
+                continue;
+            }
+        };
+        let size  = plain.len();
+        let level = storage.compression_level;
+        let packed = tokio::task::spawn_blocking(move || zstd::encode_all(plain.as_slice(), level)).await??;
+
+        // Not worth it: mark the rows so the blob isn't tried again
+        if packed.len() as f64 > size as f64 * MIN_SAVING {
+            conn.execute(
+                "UPDATE documents SET compression = 'none' WHERE local_path = ?1",
+                libsql::params![path.to_string_lossy().to_string()],
+            ).await?;
+            continue;
+        }
+
+        let dest = cas::compressed_blob_path(files_dir, &hash);
+        let tmp  = files_dir.join(format!(".tmp-{}", uuid::Uuid::new_v4()));
+        tokio::fs::write(&tmp, &packed).await?;
+        tokio::fs::rename(&tmp, &dest).await?;
+        // Rows first: the plain file is only removed once nothing points at it
+        conn.execute(
+            "UPDATE documents SET local_path = ?1, compression = ?2 WHERE local_path = ?3",
+            libsql::params![dest.to_string_lossy().to_string(), ZSTD, path.to_string_lossy().to_string()],
+        ).await?;
+        if let Err(e) = tokio::fs::remove_file(&path).await {
+            log::warn!("[compress] Could not remove {}: {e}", path.display());
+        }
+        saved += (size - packed.len()) as u64;
+    }
+    if saved > 0 {
+        log::info!("[compress] Saved {saved} bytes");
+    }
+    Ok(saved)
+}
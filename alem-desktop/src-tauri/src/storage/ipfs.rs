@@ -0,0 +1,135 @@
+// src-tauri/src/storage/ipfs.rs
+// IPFS as a BlobStore, through the RPC API of a Kubo node (local or remote).
+// The node keeps each blob pinned; the key is the blob's CID (v1).
+//
+// With IpfsSettings::enabled, uploads pin the document to the node rather
+// than sending it to the server's bucket. object_key is then
+// `ipfs://<cid>`, so other devices and the server can tell it apart from a
+// bucket key. Downloads of such keys come from the node too. The server
+// only ever sees the CID.
+use anyhow::{bail, Context, Result};
+use serde_json::Value as Json;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::time::Duration;
+
+use super::blob::{BlobFuture, BlobReader, BlobStat, BlobStore, StoredBlob};
+use super::cas;
+use crate::db::{models::IpfsSettings, settings};
+use crate::http::{self, SendRetrying};
+
+pub const OBJECT_KEY_PREFIX: &str = "ipfs://";
+
+pub struct IpfsStore {
+    api_url: String,
+    client:  reqwest::Client,
+}
+
+impl IpfsStore {
+    pub fn new(api_url: &str) -> Result<Self> {
+        Ok(Self {
+            api_url: api_url.trim_end_matches('/').to_string(),
+            client:  http::client(Duration::from_secs(300))?,
+        })
+    }
+
+    /// The node's version; fails when it can't be reached.
+    pub async fn version(&self) -> Result<String> {
+        let resp: Json = self.call("version", &[]).await?.json().await?;
+        resp["Version"].as_str().map(str::to_string).context("Not an IPFS node")
+    }
+
+    async fn call(&self, command: &str, query: &[(&str, &str)]) -> Result<reqwest::Response> {
+        // Kubo's RPC API takes POST only
+        Ok(self.client
+            .post(format!("{}/api/v0/{command}", self.api_url))
+            .query(query)
+            .send_retrying().await?
+            .error_for_status()?)
+    }
+}
+
+/// The store named by the IPFS settings (the local node by default).
+pub async fn store(conn: &libsql::Connection) -> Result<IpfsStore> {
+    let config: IpfsSettings = settings::get(conn, settings::IPFS_SETTINGS).await?.unwrap_or_default();
+    IpfsStore::new(&config.api_url)
+}
+
+pub fn object_key(cid: &str) -> String {
+    format!("{OBJECT_KEY_PREFIX}{cid}")
+}
+
+/// The CID of an `ipfs://` object key.
+pub fn cid(object_key: &str) -> Option<&str> {
+    object_key.strip_prefix(OBJECT_KEY_PREFIX).filter(|c| !c.is_empty())
+}
+
+impl BlobStore for IpfsStore {
+    fn name(&self) -> &'static str {
+        "ipfs"
+    }
+
+    fn put<'a>(&'a self, source: &'a Path) -> BlobFuture<'a, StoredBlob> {
+        Box::pin(async move {
+            let bytes = tokio::fs::read(source).await
+                .with_context(|| format!("Cannot read {}", source.display()))?;
+            let hash  = cas::hex(&Sha256::digest(&bytes));
+            let size  = bytes.len() as u64;
+            let form  = reqwest::multipart::Form::new()
+                .part("file", reqwest::multipart::Part::bytes(bytes).file_name(hash.clone()));
+            let resp: Json = self.client
+                .post(format!("{}/api/v0/add", self.api_url))
+                .query(&[("pin", "true"), ("cid-version", "1"), ("quieter", "true")])
+                .multipart(form)
+                .send_retrying().await?
+                .error_for_status()?
+                .json().await?;
+            let cid = resp["Hash"].as_str().context("IPFS node returned no CID")?;
+            Ok(StoredBlob { key: cid.to_string(), hash, size })
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> BlobFuture<'a, Vec<u8>> {
+        Box::pin(async move {
+            Ok(self.call("cat", &[("arg", key)]).await?.bytes().await?.to_vec())
+        })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> BlobFuture<'a, ()> {
+        // Unpinned blocks go at the node's next garbage collection
+        Box::pin(async move {
+            let resp = self.client
+                .post(format!("{}/api/v0/pin/rm", self.api_url))
+                .query(&[("arg", key)])
+                .send_retrying().await?;
+            if resp.status().is_success() {
+                return Ok(());
+            }
+            let body = resp.text().await.unwrap_or_default();
+            if body.contains("not pinned") {
+                return Ok(());
+            }
+            bail!("Could not unpin {key}: {body}")
+        })
+    }
+
+    fn stat<'a>(&'a self, key: &'a str) -> BlobFuture<'a, Option<BlobStat>> {
+        Box::pin(async move {
+            let path = format!("/ipfs/{key}");
+            let Ok(resp) = self.call("files/stat", &[("arg", path.as_str())]).await else {
+                return Ok(None);
+            };
+            let resp: Json = resp.json().await?;
+            Ok(resp["Size"].as_u64().map(|size| BlobStat { size, compressed: false }))
+        })
+    }
+
+    fn stream<'a>(&'a self, key: &'a str, offset: u64) -> BlobFuture<'a, BlobReader> {
+        // reqwest is built without body streaming, so the rest is buffered
+        Box::pin(async move {
+            let offset = offset.to_string();
+            let bytes  = self.call("cat", &[("arg", key), ("offset", offset.as_str())]).await?.bytes().await?;
+            Ok(Box::pin(std::io::Cursor::new(bytes.to_vec())) as BlobReader)
+        })
+    }
+}
@@ -0,0 +1,10 @@
+pub mod archive;
+pub mod blob;
+pub mod cas;
+pub mod chunked;
+pub mod compress;
+pub mod ipfs;
+pub mod protocol;
+pub mod quota;
+pub mod text;
+pub mod verify;
\ No newline at end of file
@@ -0,0 +1,187 @@
+// src-tauri/src/storage/protocol.rs
+// `alemfile://` URI scheme: serves document bytes straight to the webview so
+// <img>, <video> and the PDF viewer can load them without a base64 IPC round trip.
+//
+// URLs come from preview_url(id) and carry a per-launch token; requests
+// without it, from another webview, or for a deleted document are refused.
+// Range requests are honoured so media can seek without reading the whole file.
+use anyhow::Result;
+use std::io::SeekFrom;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+use tauri::http::{header, Request, Response, StatusCode};
+use tauri::{AppHandle, Manager, UriSchemeContext, UriSchemeResponder};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use crate::{db, AppState};
+
+pub const SCHEME: &str = "alemfile";
+
+/// Only the main window may load documents.
+const ALLOWED_WEBVIEW: &str = "main";
+
+/// Largest ranged response body; longer ranges are cut short and the
+/// webview asks again for the rest.
+const MAX_CHUNK: u64 = 8 * 1024 * 1024;
+
+static TOKEN: LazyLock<String> = LazyLock::new(|| uuid::Uuid::new_v4().simple().to_string());
+
+/// URL the webview can use as an <img>/<video>/<iframe> src for a document.
+pub fn preview_url(id: &str) -> String {
+    // WebView2 on Windows maps custom schemes to http://<scheme>.localhost
+    let base = if cfg!(windows) {
+        format!("http://{SCHEME}.localhost")
+    } else {
+        format!("{SCHEME}://localhost")
+    };
+    format!("{base}/{id}?token={}", *TOKEN)
+}
+
+/// Handler for Builder::register_asynchronous_uri_scheme_protocol.
+pub fn handle(ctx: UriSchemeContext<'_, tauri::Wry>, request: Request<Vec<u8>>, responder: UriSchemeResponder) {
+    let app = ctx.app_handle().clone();
+    if ctx.webview_label() != ALLOWED_WEBVIEW {
+        responder.respond(status(StatusCode::FORBIDDEN));
+        return;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let response = match serve(&app, &request).await {
+            Ok(r)  => r,
+            Err(e) => {
+                log::warn!("[preview] {}: {e}", request.uri());
+                status(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+        };
+        responder.respond(response);
+    });
+}
+
+async fn serve(app: &AppHandle, request: &Request<Vec<u8>>) -> Result<Response<Vec<u8>>> {
+    let uri = request.uri();
+    let token_ok = uri.query()
+        .and_then(|q| q.split('&').find_map(|kv| kv.strip_prefix("token=")))
+        .is_some_and(|t| t == TOKEN.as_str());
+    if !token_ok {
+        return Ok(status(StatusCode::FORBIDDEN));
+    }
+
+    // alemfile://localhost/<id> (preferred) or alemfile://<id>
+    let id = match uri.path().trim_matches('/') {
+        ""   => uri.host().unwrap_or_default().to_string(),
+        path => path.rsplit('/').next().unwrap_or_default().to_string(),
+    };
+    if id.is_empty() {
+        return Ok(status(StatusCode::BAD_REQUEST));
+    }
+
+    let Some((path, content_type)) = locate(app, &id).await? else {
+        return Ok(status(StatusCode::NOT_FOUND));
+    };
+
+    let mut file = tokio::fs::File::open(&path).await?;
+    let size     = file.metadata().await?.len();
+    let range    = request.headers()
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| parse_range(v, size));
+
+    let builder = Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::CACHE_CONTROL, "no-store");
+
+    match range {
+        None => {
+            let mut body = Vec::with_capacity(size as usize);
+            file.read_to_end(&mut body).await?;
+            Ok(builder.status(StatusCode::OK).body(body)?)
+        }
+        Some(None) => Ok(Response::builder()
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(header::CONTENT_RANGE, format!("bytes */{size}"))
+            .body(Vec::new())?),
+        Some(Some((start, end))) => {
+            let len = end - start + 1;
+            file.seek(SeekFrom::Start(start)).await?;
+            let mut body = vec![0; len as usize];
+            file.read_exact(&mut body).await?;
+            Ok(builder
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(header::CONTENT_RANGE, format!("bytes {start}-{end}/{size}"))
+                .body(body)?)
+        }
+    }
+}
+
+/// (file path, content_type) of a live document belonging to the signed-in
+/// user (or created before login). A missing local copy is downloaded first.
+pub(crate) async fn locate(app: &AppHandle, id: &str) -> Result<Option<(PathBuf, String)>> {
+    let conn = db::connect(&app.state::<AppState>().db).await?;
+    let (user_id, _) = crate::db::identity::current(&conn).await?;
+    let mut rows = conn.query(
+        "SELECT local_path, content_type FROM documents
+         WHERE id = ?1 AND user_id IN (?2, 'anonymous') AND status != 'deleted'",
+        libsql::params![id, user_id],
+    ).await?;
+
+    let (local_path, content_type) = match rows.next().await? {
+        Some(row) => {
+            use libsql::Value;
+            let s = |i| match row.get_value(i).ok() { Some(Value::Text(s)) if !s.is_empty() => Some(s), _ => None };
+            (s(0), s(1).unwrap_or_else(|| "application/octet-stream".into()))
+        }
+        None => return Ok(None),
+    };
+
+    let path = match local_path.map(PathBuf::from).filter(|p| p.exists()) {
+        Some(p) => super::compress::plain_path(&p).await?,
+        None    => crate::sync::engine::download_document(app, id).await?,
+    };
+    Ok(Some((path, content_type)))
+}
+
+/// Single `bytes=` range → inclusive (start, end). None = unsatisfiable.
+/// Multi-range requests are answered with their first range.
+fn parse_range(header: &str, size: u64) -> Option<(u64, u64)> {
+    let spec = header.strip_prefix("bytes=")?.split(',').next()?.trim();
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let n: u64 = suffix.parse().ok()?;
+            (size.checked_sub(n.min(size))?, size.checked_sub(1)?)
+        }
+        (s, "") => {
+            let s: u64 = s.parse().ok()?;
+            (s, size.checked_sub(1)?.min(s.saturating_add(MAX_CHUNK - 1)))
+        }
+        (s, e) => {
+            let s: u64 = s.parse().ok()?;
+            (s, e.parse::<u64>().ok()?.min(size.checked_sub(1)?).min(s.saturating_add(MAX_CHUNK - 1)))
+        }
+    };
+    (start <= end && start < size).then_some((start, end))
+}
+
+fn status(code: StatusCode) -> Response<Vec<u8>> {
+    Response::builder().status(code).body(Vec::new()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges() {
+        assert_eq!(parse_range("bytes=0-99", 1000), Some((0, 99)));
+        assert_eq!(parse_range("bytes=900-", 1000), Some((900, 999)));
+        assert_eq!(parse_range("bytes=-100", 1000), Some((900, 999)));
+        assert_eq!(parse_range("bytes=0-", MAX_CHUNK * 2), Some((0, MAX_CHUNK - 1)));
+        assert_eq!(parse_range("bytes=1000-", 1000), None);
+        assert_eq!(parse_range("bytes=5-2", 1000), None);
+        assert_eq!(parse_range("items=0-1", 1000), None);
+        // A start near u64::MAX is out of range, not an overflow
+        assert_eq!(parse_range(&format!("bytes={}-", u64::MAX), 1000), None);
+        assert_eq!(parse_range(&format!("bytes={}-{}", u64::MAX - 1, u64::MAX), 1000), None);
+    }
+}
@@ -0,0 +1,175 @@
+// src-tauri/src/storage/quota.rs
+// Soft quota on locally cached file bytes.
+//
+// Usage is counted per blob, not per document: blobs are shared by content
+// (cas.rs), so each local_path counts once at its document's file_size.
+// Over the quota, blobs are evicted least recently used first (document_access,
+// falling back to updated_at). A blob is only evictable when every document
+// using it is synced with nothing to upload, so the server holds a copy, and
+// it isn't some document's full text (text.rs); evicting keeps the rows and
+// sets is_cached_locally = 0, and the next read downloads the content again.
+use anyhow::Result;
+use libsql::{Connection, Value};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::db::{models::StorageSettings, settings};
+
+/// Documents with a local copy.
+const CACHED: &str =
+    "d.is_cached_locally = 1 AND COALESCE(d.local_path, '') != ''";
+/// Documents whose content the server already holds.
+const ON_SERVER: &str =
+    "d.status = 'synced' AND d.needs_upload = 0 AND COALESCE(d.object_key, '') != ''";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageUsage {
+    /// Bytes of locally cached files.
+    pub used_bytes: u64,
+    pub quota_bytes: Option<u64>,
+    /// Cached bytes by document status. A blob shared by documents of
+    /// different statuses counts under each, so these can add up to more
+    /// than used_bytes.
+    pub bytes_by_status: BTreeMap<String, u64>,
+    /// Part of used_bytes that eviction may free.
+    pub evictable_bytes: u64,
+    pub cached_documents: i64,
+    /// Documents whose content is only on the server.
+    pub remote_only_documents: i64,
+    /// Bytes not stored because documents share identical content.
+    pub deduplicated_bytes: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Eviction {
+    pub blobs: u64,
+    pub documents: u64,
+    pub bytes: u64,
+}
+
+pub async fn usage(conn: &Connection) -> Result<StorageUsage> {
+    let quota_bytes = quota(conn).await?;
+    let used_bytes = scalar(conn, &format!(
+        "SELECT COALESCE(SUM(size), 0) FROM (
+             SELECT MAX(COALESCE(d.file_size, 0)) AS size FROM documents d
+             WHERE {CACHED} GROUP BY d.local_path
+         )"
+    )).await? as u64;
+    let evictable_bytes = evictable(conn).await?.iter().map(|(_, size)| size).sum();
+
+    let mut bytes_by_status = BTreeMap::new();
+    let mut rows = conn.query(&format!(
+        "SELECT status, COALESCE(SUM(size), 0) FROM (
+             SELECT d.status, MAX(COALESCE(d.file_size, 0)) AS size FROM documents d
+             WHERE {CACHED} GROUP BY d.status, d.local_path
+         ) GROUP BY status"
+    ), ()).await?;
+    while let Some(row) = rows.next().await? {
+        if let (Ok(Value::Text(status)), Ok(Value::Integer(bytes))) = (row.get_value(0), row.get_value(1)) {
+            bytes_by_status.insert(status, bytes as u64);
+        }
+    }
+
+    Ok(StorageUsage {
+        used_bytes,
+        quota_bytes,
+        bytes_by_status,
+        evictable_bytes,
+        cached_documents: scalar(
+            conn, &format!("SELECT COUNT(*) FROM documents d WHERE {CACHED}"),
+        ).await?,
+        remote_only_documents: scalar(
+            conn,
+            "SELECT COUNT(*) FROM documents WHERE is_cached_locally = 0 AND status != 'deleted'",
+        ).await?,
+        deduplicated_bytes: scalar(
+            conn,
+            "SELECT COALESCE(SUM((ref_count - 1) * COALESCE(size, 0)), 0) FROM blobs WHERE ref_count > 1",
+        ).await? as u64,
+    })
+}
+
+/// Evict until usage is within the quota (or nothing evictable is left).
+pub async fn enforce(conn: &Connection) -> Result<Eviction> {
+    let mut report = Eviction::default();
+    let Some(quota) = quota(conn).await? else { return Ok(report) };
+    let mut used = usage(conn).await?.used_bytes;
+    if used <= quota {
+        return Ok(report);
+    }
+
+    for (path, size) in evictable(conn).await? {
+        if used <= quota {
+            break;
+        }
+        // Rows first: if removing the file fails, maintenance collects it later
+        let n = conn.execute(
+            "UPDATE documents SET is_cached_locally = 0, local_path = NULL, compression = NULL WHERE local_path = ?1",
+            libsql::params![path.as_str()],
+        ).await?;
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => log::warn!("[quota] Could not remove {path}: {e}"),
+        }
+        used = used.saturating_sub(size);
+        report.blobs     += 1;
+        report.documents += n;
+        report.bytes     += size;
+    }
+
+    if report.blobs > 0 {
+        log::info!(
+            "[quota] Evicted {} documents ({} bytes); {used} of {quota} bytes in use",
+            report.documents, report.bytes,
+        );
+    }
+    if used > quota {
+        log::warn!("[quota] Still {used} bytes in use, over the {quota} byte quota: nothing else is evictable");
+    }
+    Ok(report)
+}
+
+/// True while there is room left under the quota (always without one).
+pub async fn has_room(conn: &Connection) -> Result<bool> {
+    Ok(match quota(conn).await? {
+        Some(quota) => usage(conn).await?.used_bytes < quota,
+        None        => true,
+    })
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+async fn quota(conn: &Connection) -> Result<Option<u64>> {
+    let storage: StorageSettings = settings::get(conn, settings::STORAGE_SETTINGS).await?.unwrap_or_default();
+    Ok(storage.quota_bytes)
+}
+
+/// (local_path, size) of evictable blobs, least recently used first.
+async fn evictable(conn: &Connection) -> Result<Vec<(String, u64)>> {
+    let mut rows = conn.query(&format!(
+        "SELECT d.local_path, MAX(COALESCE(d.file_size, 0)),
+                MAX(COALESCE(a.last_accessed_at, d.updated_at)) AS last_used
+         FROM documents d LEFT JOIN document_access a ON a.document_id = d.id
+         WHERE {CACHED}
+           AND d.local_path NOT IN (SELECT full_text_path FROM documents WHERE full_text_path IS NOT NULL)
+         GROUP BY d.local_path
+         HAVING SUM(CASE WHEN {ON_SERVER} THEN 0 ELSE 1 END) = 0
+         ORDER BY last_used"
+    ), ()).await?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await? {
+        if let (Ok(Value::Text(path)), Ok(Value::Integer(size))) = (row.get_value(0), row.get_value(1)) {
+            out.push((path, size as u64));
+        }
+    }
+    Ok(out)
+}
+
+async fn scalar(conn: &Connection, sql: &str) -> Result<i64> {
+    let mut rows = conn.query(sql, ()).await?;
+    Ok(match rows.next().await? {
+        Some(row) => row.get(0).unwrap_or(0),
+        None      => 0,
+    })
+}
@@ -0,0 +1,62 @@
+// src-tauri/src/storage/text.rs
+// Soft limit on text_content.
+//
+// Extracted text past StorageSettings::max_text_bytes bloats the database
+// and the FTS index for little search benefit. Such text is cut at the limit
+// (on a character boundary) and the whole text goes to a content-addressed
+// blob referenced by documents.full_text_path; metadata.text_truncated
+// records both sizes. Search covers the part that was kept.
+use anyhow::Result;
+use libsql::Connection;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+use super::cas;
+use crate::commands::documents::CreateDocumentInput;
+use crate::db::{models::StorageSettings, settings};
+
+pub async fn max_bytes(conn: &Connection) -> Result<u64> {
+    let storage: StorageSettings = settings::get(conn, settings::STORAGE_SETTINGS).await?.unwrap_or_default();
+    Ok(storage.max_text_bytes)
+}
+
+/// Apply the limit to `input` before it is inserted. Returns whether the text
+/// was cut.
+pub async fn limit(files_dir: &Path, conn: &Connection, input: &mut CreateDocumentInput) -> Result<bool> {
+    let Some(text) = input.text_content.as_mut() else { return Ok(false) };
+    let Some((path, full_bytes)) = cut(files_dir, conn, text).await? else { return Ok(false) };
+    let stored_bytes = text.len();
+
+    flag(input, stored_bytes as u64, full_bytes as u64);
+    input.full_text_path = Some(path.to_string_lossy().to_string());
+    log::info!("[text] Text of {} cut from {full_bytes} to {stored_bytes} bytes", input.filename);
+    Ok(true)
+}
+
+/// Cut `text` to the limit, keeping the whole of it in a blob. Returns the
+/// blob and the full size, or None if it was within the limit.
+pub async fn cut(files_dir: &Path, conn: &Connection, text: &mut String) -> Result<Option<(PathBuf, usize)>> {
+    let max = max_bytes(conn).await? as usize;
+    if text.len() <= max {
+        return Ok(None);
+    }
+    let (path, _) = cas::store_bytes(files_dir, text.as_bytes()).await?;
+    let full_bytes = text.len();
+    text.truncate(floor_char_boundary(text, max));
+    Ok(Some((path, full_bytes)))
+}
+
+/// Record in `input`'s metadata that its text was cut.
+pub fn flag(input: &mut CreateDocumentInput, stored_bytes: u64, full_bytes: u64) {
+    let metadata = input.metadata.get_or_insert_with(|| json!({}));
+    if let Some(obj) = metadata.as_object_mut() {
+        obj.insert("text_truncated".into(), json!({ "stored_bytes": stored_bytes, "full_bytes": full_bytes }));
+    }
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+/// Largest index <= `max` that starts a character.
+fn floor_char_boundary(text: &str, max: usize) -> usize {
+    (0..=max.min(text.len())).rev().find(|&i| text.is_char_boundary(i)).unwrap_or(0)
+}
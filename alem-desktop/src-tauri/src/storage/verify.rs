@@ -0,0 +1,120 @@
+// src-tauri/src/storage/verify.rs
+// Integrity check of the local file store.
+//
+// Every cached blob is re-hashed (after decompressing, see compress.rs) and
+// compared with its documents' content_hash. With `repair`, documents whose
+// file is missing or corrupt drop their local copy and are marked
+// needs_download, and a corrupt file is deleted so the download can store the
+// right bytes under the same name. Documents never uploaded can't be fetched
+// again and are only reported.
+use anyhow::Result;
+use libsql::{Connection, Value};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+use super::{cas, compress};
+use crate::jobs::JobHandle;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyProblem {
+    pub document_id: String,
+    pub filename:    String,
+    pub local_path:  String,
+    /// Has a server copy to download again.
+    pub recoverable: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VerifyReport {
+    /// Files hashed.
+    pub checked:   u64,
+    pub missing:   Vec<VerifyProblem>,
+    pub corrupted: Vec<VerifyProblem>,
+    /// Documents marked needs_download.
+    pub requeued:  u64,
+}
+
+pub async fn run(conn: &Connection, repair: bool, job: &JobHandle) -> Result<VerifyReport> {
+    // Shared blobs are checked once, against the hash their documents expect
+    let mut rows = conn.query(
+        "SELECT local_path, content_hash, group_concat(id, char(31)), group_concat(filename, char(31)),
+                MAX(COALESCE(object_key, '') != '')
+         FROM documents
+         WHERE is_cached_locally = 1 AND COALESCE(local_path, '') != ''
+         GROUP BY local_path, content_hash
+         ORDER BY local_path",
+        (),
+    ).await?;
+    let mut blobs = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let s = |i| match row.get_value(i).ok() { Some(Value::Text(s)) => s, _ => String::new() };
+        let recoverable = matches!(row.get_value(4), Ok(Value::Integer(1)));
+        blobs.push((s(0), s(1), s(2), s(3), recoverable));
+    }
+    drop(rows);
+    job.set_total(blobs.len() as u64);
+
+    let mut report = VerifyReport::default();
+    for (path, expected, ids, filenames, recoverable) in blobs {
+        job.check_cancelled()?;
+        let problem = |list: &mut Vec<VerifyProblem>| {
+            for (id, filename) in ids.split('\u{1f}').zip(filenames.split('\u{1f}')) {
+                list.push(VerifyProblem {
+                    document_id: id.to_string(),
+                    filename:    filename.to_string(),
+                    local_path:  path.clone(),
+                    recoverable,
+                });
+            }
+        };
+
+        let file = Path::new(&path);
+        let found = if tokio::fs::metadata(file).await.is_err() {
+            problem(&mut report.missing);
+            false
+        } else {
+            report.checked += 1;
+            let ok = !cas::is_sha256_hex(&expected) || hash(file).await?.eq_ignore_ascii_case(&expected);
+            if ok {
+                job.advance(1, None);
+                continue;
+            }
+            log::warn!("[verify] {path} does not match content_hash {expected}");
+            problem(&mut report.corrupted);
+            true
+        };
+
+        if repair && recoverable {
+            report.requeued += conn.execute(
+                "UPDATE documents
+                 SET local_path = NULL, compression = NULL, is_cached_locally = 0, needs_download = 1
+                 WHERE local_path = ?1 AND COALESCE(object_key, '') != ''",
+                libsql::params![path.as_str()],
+            ).await?;
+            if found {
+                if let Err(e) = tokio::fs::remove_file(file).await {
+                    log::warn!("[verify] Could not remove {path}: {e}");
+                }
+            }
+        }
+        job.advance(1, None);
+    }
+
+    log::info!(
+        "[verify] {} files checked: {} missing, {} corrupt documents, {} queued for download",
+        report.checked, report.missing.len(), report.corrupted.len(), report.requeued,
+    );
+    Ok(report)
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+/// sha256 of the plain content.
+async fn hash(path: &Path) -> Result<String> {
+    if compress::is_compressed(path) {
+        Ok(cas::hex(&Sha256::digest(compress::read(path).await?)))
+    } else {
+        cas::hash_file(path).await
+    }
+}
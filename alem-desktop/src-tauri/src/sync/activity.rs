@@ -0,0 +1,59 @@
+// src-tauri/src/sync/activity.rs
+// Fetches new workspace events from the server into the activity cache.
+//
+// GET /api/v1/activity?workspace=&since= returns
+// { "events": [...], "cursor": "...", "has_more": bool }; the cursor is kept
+// per tenant (tenants.activity_cursor) so each refresh only asks for what is
+// new. A long backlog is fetched MAX_PAGES at a time; the rest comes with the
+// next refresh.
+use anyhow::Result;
+use serde_json::Value as Json;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use super::engine::query_server_url;
+use crate::db::{self, activity};
+use crate::http::{self, SendRetrying};
+
+const MAX_PAGES: usize = 5;
+
+/// Fetch events for `tenant_id` newer than its cursor. Returns how many were
+/// stored; nothing is fetched when signed out.
+pub async fn refresh(app: &AppHandle, tenant_id: &str) -> Result<usize> {
+    let Some(token) = crate::oauth::access_token(app).await.ok().flatten() else { return Ok(0) };
+    let state = app.state::<crate::AppState>();
+    let conn  = db::connect(&state.db).await?;
+    let server_url = query_server_url(&conn).await;
+
+    let client = http::client(Duration::from_secs(8))?;
+
+    let workspace = db::tenants::remote_id(&conn, tenant_id).await?;
+    let mut stored = 0;
+    for _ in 0..MAX_PAGES {
+        let mut query = vec![("workspace", workspace.clone())];
+        if let Some(since) = activity::cursor(&conn, tenant_id).await? {
+            query.push(("since", since));
+        }
+        let resp: Json = client
+            .get(format!("{server_url}/api/v1/activity"))
+            .bearer_auth(&token)
+            .query(&query)
+            .send_retrying().await?
+            .error_for_status()?
+            .json().await?;
+
+        let events = resp["events"].as_array().map(Vec::as_slice).unwrap_or_default();
+        stored += activity::store(&conn, tenant_id, events).await?;
+        match resp["cursor"].as_str() {
+            Some(cursor) => activity::set_cursor(&conn, tenant_id, cursor).await?,
+            None         => break,
+        }
+        if !resp["has_more"].as_bool().unwrap_or(false) {
+            break;
+        }
+    }
+    if stored > 0 {
+        log::info!("[activity] {stored} new events for {tenant_id}");
+    }
+    Ok(stored)
+}
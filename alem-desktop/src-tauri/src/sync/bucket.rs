@@ -0,0 +1,106 @@
+// src-tauri/src/sync/bucket.rs
+// Bring-your-own storage. The user can register their own S3-compatible
+// bucket with the server. The server then presigns upload URLs against that
+// bucket instead of its default one.
+//
+// POST /api/v1/storage/buckets           {credentials} → {"bucket_id"}
+// POST /api/v1/storage/buckets/:id/probe → {"put_url", "get_url", "delete_url"?}
+// POST /api/v1/storage/buckets/:id/activate
+//
+// Objects in the user's bucket get object keys of the form byo/<bucket_id>/…,
+// which the server presigns downloads against.
+//
+// Uploads only switch once a probe object has made the round trip through
+// the presigned URLs from this machine: PUT, then GET back byte-identical. A
+// bucket that fails the probe stays registered on the server but unused.
+use anyhow::{bail, Context, Result};
+use serde_json::Value as Json;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use super::engine::query_server_url;
+use crate::db::{self, models::{BucketCredentials, StorageTarget}, settings};
+use crate::http::{self, SendRetrying};
+
+const PROBE_BYTES: usize = 1024;
+
+pub async fn target(conn: &libsql::Connection) -> Result<StorageTarget> {
+    Ok(settings::get(conn, settings::STORAGE_TARGET).await?.unwrap_or_default())
+}
+
+/// Register `credentials` with the server, probe the bucket and switch
+/// uploads to it. On any failure uploads keep their current target.
+pub async fn register(app: &AppHandle, credentials: &BucketCredentials) -> Result<StorageTarget> {
+    let token  = crate::oauth::access_token(app).await?.context("Not authenticated")?;
+    let conn   = db::connect(&app.state::<crate::AppState>().db).await?;
+    let server = query_server_url(&conn).await;
+    let client = http::client(Duration::from_secs(30))?;
+
+    let resp: Json = client
+        .post(format!("{server}/api/v1/storage/buckets"))
+        .bearer_auth(&token)
+        .json(credentials)
+        .send_retrying().await?
+        .error_for_status()
+        .context("The server rejected the bucket")?
+        .json().await?;
+    let bucket_id = resp["bucket_id"].as_str().context("No bucket_id")?.to_string();
+
+    probe(&client, &server, &token, &bucket_id).await
+        .with_context(|| format!("Bucket {} failed the connectivity check", credentials.bucket))?;
+
+    client
+        .post(format!("{server}/api/v1/storage/buckets/{bucket_id}/activate"))
+        .bearer_auth(&token)
+        .send_retrying().await?
+        .error_for_status()?;
+
+    let target = StorageTarget {
+        bucket_id:    Some(bucket_id),
+        bucket:       Some(credentials.bucket.clone()),
+        endpoint:     Some(credentials.endpoint.clone()),
+        validated_at: Some(chrono::Utc::now().to_rfc3339()),
+    };
+    settings::set(&conn, settings::STORAGE_TARGET, &target).await?;
+    log::info!("[bucket] Uploads now go to {} at {}", credentials.bucket, credentials.endpoint);
+    Ok(target)
+}
+
+/// Send uploads back to the server's default bucket. Documents already in
+/// the user's bucket stay there.
+pub async fn reset(conn: &libsql::Connection) -> Result<()> {
+    settings::set(conn, settings::STORAGE_TARGET, &StorageTarget::default()).await
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+/// PUT a random object through the bucket's presigned URLs and read it back.
+async fn probe(client: &reqwest::Client, server: &str, token: &str, bucket_id: &str) -> Result<()> {
+    let urls: Json = client
+        .post(format!("{server}/api/v1/storage/buckets/{bucket_id}/probe"))
+        .bearer_auth(token)
+        .send_retrying().await?
+        .error_for_status()?
+        .json().await?;
+    let put_url = urls["put_url"].as_str().context("No put_url")?;
+    let get_url = urls["get_url"].as_str().context("No get_url")?;
+
+    let body: Vec<u8> = (0..PROBE_BYTES).map(|_| rand::random()).collect();
+    client.put(put_url).body(body.clone())
+        .send_retrying().await.context("Probe upload failed")?
+        .error_for_status().context("Probe upload was refused")?;
+    let back = client.get(get_url)
+        .send_retrying().await.context("Probe download failed")?
+        .error_for_status().context("Probe download was refused")?
+        .bytes().await?;
+    if back.as_ref() != body.as_slice() {
+        bail!("Probe object came back different ({} of {PROBE_BYTES} bytes)", back.len());
+    }
+
+    if let Some(delete_url) = urls["delete_url"].as_str() {
+        if let Err(e) = client.delete(delete_url).send_retrying().await.and_then(|r| Ok(r.error_for_status()?)) {
+            log::warn!("[bucket] Could not delete probe object: {e}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,197 @@
+// src-tauri/src/sync/connectivity.rs
+// Connection state machine driven by OS network changes and a lightweight
+// reachability probe.
+//
+// The probe is a plain TCP connect to the configured server (no TLS, no auth),
+// or to the proxy when requests go through one. It runs whenever the OS
+// reports an interface or address coming or going, quickly while a lost
+// connection is being confirmed, and otherwise once a minute to notice the
+// server itself going away or coming back. Where network changes can't be
+// watched it falls back to probing every few seconds. Transitions are
+// published on a watch channel + Tauri event, and a reconnect wakes the sync
+// engine immediately instead of waiting for the next 30s tick.
+
+use if_watch::tokio::IfWatcher;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::{watch, Notify};
+
+use crate::db;
+
+/// Between probes while the network is watched and the state is settled.
+const PROBE_INTERVAL:   Duration = Duration::from_secs(60);
+/// Between probes while confirming a lost connection, or when the network
+/// can't be watched.
+const RETRY_INTERVAL:   Duration = Duration::from_secs(5);
+const PROBE_TIMEOUT:    Duration = Duration::from_secs(3);
+/// Consecutive failed probes before Reconnecting → Offline.
+const OFFLINE_AFTER:    u32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionState {
+    /// No probe has completed yet (app just started).
+    Unknown,
+    Online,
+    /// Last probe failed; retrying before declaring offline.
+    Reconnecting,
+    Offline,
+}
+
+pub struct ConnectivityMonitor {
+    tx:          watch::Sender<ConnectionState>,
+    failures:    std::sync::atomic::AtomicU32,
+    reconnected: Notify,
+}
+
+impl Default for ConnectivityMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConnectivityMonitor {
+    pub fn new() -> Self {
+        let (tx, _) = watch::channel(ConnectionState::Unknown);
+        Self {
+            tx,
+            failures:    std::sync::atomic::AtomicU32::new(0),
+            reconnected: Notify::new(),
+        }
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        *self.tx.borrow()
+    }
+
+    /// Optimistic: Unknown and Reconnecting still count as online so the UI
+    /// doesn't flash "offline" on a single dropped probe.
+    pub fn is_online(&self) -> bool {
+        self.state() != ConnectionState::Offline
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<ConnectionState> {
+        self.tx.subscribe()
+    }
+
+    /// Resolves the next time the connection comes back after being lost,
+    /// or at once if it came back since the last call returned: one waiter
+    /// (the sync engine) gets every reconnect, even one that happened while
+    /// it was busy syncing.
+    pub async fn reconnected(&self) {
+        self.reconnected.notified().await
+    }
+
+    /// Feed a probe / request outcome into the state machine.
+    /// Returns the new state if it changed.
+    pub fn report(&self, reachable: bool) -> Option<ConnectionState> {
+        use std::sync::atomic::Ordering;
+        use ConnectionState::*;
+
+        let prev = self.state();
+        let next = if reachable {
+            self.failures.store(0, Ordering::Relaxed);
+            Online
+        } else {
+            let n = self.failures.fetch_add(1, Ordering::Relaxed) + 1;
+            match prev {
+                Offline                 => Offline,
+                _ if n >= OFFLINE_AFTER => Offline,
+                Unknown                 => Offline,
+                _                       => Reconnecting,
+            }
+        };
+
+        if next == prev {
+            return None;
+        }
+        self.tx.send_replace(next);
+
+        if next == Online && matches!(prev, Offline | Reconnecting) {
+            self.reconnected.notify_one();
+        }
+        Some(next)
+    }
+}
+
+/// Background task: probe the server and publish state transitions.
+pub async fn watch(app: AppHandle) {
+    let changed = Arc::new(Notify::new());
+    let watched = match IfWatcher::new() {
+        Ok(watcher) => {
+            tauri::async_runtime::spawn(network_changes(watcher, Arc::clone(&changed)));
+            true
+        }
+        Err(e) => {
+            log::warn!("[net] Cannot watch network changes, polling instead: {e}");
+            false
+        }
+    };
+
+    loop {
+        let reachable = probe(&app).await;
+        let monitor   = &app.state::<crate::AppState>().connectivity;
+
+        if let Some(next) = monitor.report(reachable) {
+            log::info!("[net] Connection state → {next:?}");
+            let _ = app.emit("connection-state-changed", next);
+        }
+
+        let settled  = matches!(monitor.state(), ConnectionState::Online | ConnectionState::Offline);
+        let interval = if watched && settled { PROBE_INTERVAL } else { RETRY_INTERVAL };
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = changed.notified() => {
+                log::debug!("[net] Network changed — probing");
+            }
+        }
+    }
+}
+
+/// Wake the probe on every interface or address change. A change during a
+/// probe is kept (notify_one) and wakes the next wait at once.
+async fn network_changes(mut watcher: IfWatcher, changed: Arc<Notify>) {
+    loop {
+        match std::future::poll_fn(|cx| watcher.poll_if_event(cx)).await {
+            Ok(event) => {
+                log::debug!("[net] {event:?}");
+                changed.notify_one();
+            }
+            Err(e) => {
+                log::warn!("[net] Network change watch failed: {e}");
+                tokio::time::sleep(RETRY_INTERVAL).await;
+            }
+        }
+    }
+}
+
+async fn probe(app: &AppHandle) -> bool {
+    let server_url = {
+        let state = app.state::<crate::AppState>();
+        match db::connect(&state.db).await {
+            Ok(conn) => super::engine::query_server_url(&conn).await,
+            Err(_)   => return false,
+        }
+    };
+
+    let Some(addr) = server_addr(&server_url) else {
+        log::warn!("[net] Cannot parse server_url {server_url}");
+        return false;
+    };
+
+    matches!(
+        tokio::time::timeout(PROBE_TIMEOUT, tokio::net::TcpStream::connect(addr)).await,
+        Ok(Ok(_))
+    )
+}
+
+/// Where the probe connects: the server, or the proxy that reaches it.
+fn server_addr(server_url: &str) -> Option<String> {
+    let url  = reqwest::Url::parse(server_url).ok()?;
+    let url  = crate::http::proxy_for(&url).unwrap_or(url);
+    let host = url.host_str()?;
+    let port = url.port_or_known_default()?;
+    Some(format!("{host}:{port}"))
+}
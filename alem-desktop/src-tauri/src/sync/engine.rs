@@ -2,12 +2,31 @@
 // Fully rewritten for libsql — all db.lock() / rusqlite::params! removed.
 // Each sync operation gets its own libsql::Connection (cheap, from the shared Database).
 
-use crate::commands::auth::get_oauth_token;
+use super::connectivity::ConnectionState;
+use super::metrics::Direction;
+use super::throttle::BandwidthLimiter;
+use crate::http::{self, SendRetrying};
+use crate::oauth;
+use crate::storage::{blob::BlobStore, compress, ipfs::{self, IpfsStore}};
+use crate::db::{
+    self, acl, annotations, conflicts, contacts, identity,
+    models::{
+        AclFailure, Annotation, ApiRequest, IpfsSettings, Profile, RequestConflict, SyncConflict, SyncFilter,
+        SyncSettings, TrustLevel,
+    },
+    profiles, settings, tenants, timing, watches,
+};
 use anyhow::{Context, Result};
 use libsql::Value;
 use serde_json::Value as Json;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::sync::{Arc, LazyLock, Mutex};
 use std::time::Duration;
-use tauri::{AppHandle, Manager};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
 
 pub async fn start(app: AppHandle) {
     tokio::time::sleep(Duration::from_secs(3)).await;
@@ -15,7 +34,19 @@ pub async fn start(app: AppHandle) {
         if let Err(e) = run_sync_cycle(&app).await {
             log::warn!("[sync] Cycle error: {e}");
         }
-        tokio::time::sleep(Duration::from_secs(30)).await;
+
+        // Sleep until the next tick, or wake early when connectivity returns;
+        // a reconnect during the cycle just run wakes this at once
+        let monitor = Arc::clone(&app.state::<crate::AppState>().connectivity);
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(30)) => {}
+            _ = monitor.reconnected() => {
+                log::info!("[sync] Connectivity restored — syncing now");
+            }
+            _ = oauth::session_restored() => {
+                log::info!("[sync] Signed in again — syncing now");
+            }
+        }
     }
 }
 
@@ -24,33 +55,58 @@ pub async fn run_once(app: &AppHandle) -> Result<()> {
 }
 
 async fn run_sync_cycle(app: &AppHandle) -> Result<()> {
-    let token = match get_oauth_token().await.ok().flatten() {
+    let state      = app.state::<crate::AppState>();
+    if state.connectivity.state() == ConnectionState::Offline {
+        log::debug!("[sync] Offline — skipping");
+        return Ok(());
+    }
+    let conn       = db::connect(&state.db).await?;
+    // Queued ops wait for the account to sign in again
+    if oauth::session_expired(&conn).await? {
+        log::debug!("[sync] Session expired — skipping");
+        return Ok(());
+    }
+    let token = match oauth::access_token(app).await.ok().flatten() {
         Some(t) => t,
         None    => return Ok(()),
     };
-
-    let state      = app.state::<crate::AppState>();
-    let conn       = state.db.connect()?;
     let server_url = query_server_url(&conn).await;
 
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(8))
-        .build()?;
+    // A server that asked for a pause is left alone until it ends; queued ops
+    // stay pending
+    if let Some(wait) = http::paused_for(&server_url) {
+        log::info!("[sync] Server is rate limiting — skipping for another {}s", wait.as_secs().max(1));
+        return Ok(());
+    }
+
+    let client = http::client(Duration::from_secs(8))?;
 
-    if client.get(format!("{server_url}/api/v1/health")).send().await.is_err() {
+    if client.get(format!("{server_url}/api/v1/health")).send_retrying().await.is_err() {
         log::debug!("[sync] Server unreachable — skipping");
+        state.connectivity.report(false);
         return Ok(());
     }
 
-    process_pending_ops(app, &client, &server_url, &token).await?;
-    pull_server_changes(app, &client, &server_url, &token).await?;
-
-    Ok(())
+    let result = async {
+        process_pending_ops(app, &client, &server_url, &token).await?;
+        pull_server_changes(app, &client, &server_url, &token).await?;
+        super::shares::receive(app, &client, &server_url, &token).await.map(|_| ())
+    }.await;
+    // A token the server turned away early (revoked, or a clock out of step)
+    // is refreshed for the next cycle; one that can't be ends the session
+    if let Err(e) = &result {
+        if oauth::is_unauthorized(e) {
+            if let Err(e) = oauth::unauthorized(app, &token).await {
+                log::warn!("[sync] Token refresh after 401 failed: {e:#}");
+            }
+        }
+    }
+    result
 }
 
 // ── Helpers ──────────────────────────────────────────────────────────────────
 
-async fn query_server_url(conn: &libsql::Connection) -> String {
+pub(crate) async fn query_server_url(conn: &libsql::Connection) -> String {
     if let Ok(mut rows) = conn.query(
         "SELECT server_url FROM local_identity WHERE id='singleton'", ()
     ).await {
@@ -70,6 +126,27 @@ fn text(row: &libsql::Row, i: i32) -> Option<String> {
 
 // ── Pending operation queue ───────────────────────────────────────────────────
 
+/// Documents currently being processed by a worker (this cycle or a
+/// concurrently triggered manual sync) — never run two ops on one doc at once.
+static IN_FLIGHT: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(Default::default);
+
+/// Releases a document's in-flight claim when the worker finishes or panics.
+struct InFlightGuard(String);
+
+impl InFlightGuard {
+    fn claim(key: &str) -> Option<Self> {
+        IN_FLIGHT.lock().unwrap().insert(key.to_string()).then(|| Self(key.to_string()))
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        IN_FLIGHT.lock().unwrap().remove(&self.0);
+    }
+}
+
+type PendingOp = (String, String, Json);
+
 async fn process_pending_ops(
     app: &AppHandle,
     client: &reqwest::Client,
@@ -78,15 +155,22 @@ async fn process_pending_ops(
 ) -> Result<()> {
     // Snapshot the pending ops — use a fresh connection so we don't hold it
     // across await points in upload_document
-    let ops = {
+    let (ops, settings) = {
         let state = app.state::<crate::AppState>();
-        let conn  = state.db.connect()?;
-        let mut rows = conn.query(
-            "SELECT id, op_type, payload
-             FROM offline_operations
-             WHERE status = 'pending' AND retry_count < 5
-             ORDER BY created_at ASC LIMIT 20",
-            (),
+        let conn  = db::connect(&state.db).await?;
+        let (_, tenant_id) = identity::current(&conn).await?;
+        // A document in conflict keeps its state ops until the user resolves it
+        let mut rows = timing::query(&conn,
+            &format!(
+                "SELECT id, op_type, payload
+                 FROM offline_operations
+                 WHERE tenant_id = ?1 AND status = 'pending' AND retry_count < 5
+                   AND NOT (op_type IN {} AND COALESCE(json_extract(payload, '$.doc_id'), '') IN
+                            (SELECT document_id FROM sync_conflicts WHERE resolved_at IS NULL))
+                 ORDER BY created_at ASC LIMIT 20",
+                conflicts::HELD_OPS,
+            ),
+            libsql::params![tenant_id],
         ).await?;
 
         let mut v = Vec::new();
@@ -94,60 +178,139 @@ async fn process_pending_ops(
             let id      = text(&row, 0).unwrap_or_default();
             let op_type = text(&row, 1).unwrap_or_default();
             let payload = text(&row, 2).unwrap_or_else(|| "{}".into());
-            v.push((id, op_type, payload));
+            v.push((id, op_type, serde_json::from_str(&payload).unwrap_or(Json::Null)));
         }
-        v
+
+        let settings: SyncSettings = settings::get(&conn, settings::SYNC_SETTINGS).await?
+            .unwrap_or_default();
+        (v, settings)
     };
 
-    for (op_id, op_type, payload_str) in ops {
-        let payload: Json = serde_json::from_str(&payload_str).unwrap_or(Json::Null);
+    // Group by document, keeping queue order inside each group: ops on the same
+    // doc run sequentially in one worker, different docs run in parallel.
+    let mut groups: Vec<(String, Vec<PendingOp>)> = Vec::new();
+    for (op_id, op_type, payload) in ops {
+        // Queued API requests keep their order per path
+        let key = payload["doc_id"].as_str()
+            .or_else(|| (op_type == "api_request").then(|| payload["path"].as_str()).flatten())
+            .map(str::to_string)
+            .unwrap_or_else(|| op_id.clone());
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, g)) => g.push((op_id, op_type, payload)),
+            None         => groups.push((key, vec![(op_id, op_type, payload)])),
+        }
+    }
+
+    let permits = Arc::new(Semaphore::new(settings.upload_concurrency.max(1)));
+    let limiter = Arc::new(BandwidthLimiter::new(settings.bandwidth_limit_kbps));
+    let mut workers = JoinSet::new();
 
-        let result = match op_type.as_str() {
-            "upload_document" => upload_document(app, client, server_url, token, &payload).await,
-            "delete_document" => delete_document_on_server(client, server_url, token, &payload).await,
-            other => { log::warn!("[sync] Unknown op: {other}"); Ok(()) }
+    for (key, group) in groups {
+        let Some(guard) = InFlightGuard::claim(&key) else {
+            log::debug!("[sync] {key} already in flight — skipping");
+            continue;
         };
+        let permit = Arc::clone(&permits).acquire_owned().await?;
 
-        // Update op status — new connection per update to avoid lock contention
-        let state = app.state::<crate::AppState>();
-        let conn  = state.db.connect()?;
-        match result {
-            Ok(_) => {
-                conn.execute(
-                    "UPDATE offline_operations SET status='done', updated_at=datetime('now') WHERE id=?1",
-                    libsql::params![op_id],
-                ).await?;
-            }
-            Err(e) => {
-                log::warn!("[sync] Op {op_id} failed: {e}");
-                conn.execute(
-                    "UPDATE offline_operations
-                     SET retry_count = retry_count + 1, status = 'failed',
-                         error_msg = ?1, updated_at = datetime('now')
-                     WHERE id = ?2",
-                    libsql::params![e.to_string(), op_id],
-                ).await?;
+        let app        = app.clone();
+        let client     = client.clone();
+        let server_url = server_url.to_string();
+        let token      = token.to_string();
+        let limiter    = Arc::clone(&limiter);
+
+        workers.spawn(async move {
+            let _permit = permit;
+            let _guard  = guard;
+            for (op_id, op_type, payload) in group {
+                let result = run_op(&app, &client, &server_url, &token, &limiter, &op_type, &payload).await;
+                // Not the op's fault: it stays pending, without using up a
+                // retry, for the cycle after the pause or a new token (and
+                // so do the document's later ops, to keep their order)
+                if let Err(e) = &result {
+                    if http::is_throttled(e) || oauth::is_unauthorized(e) {
+                        log::info!("[sync] Op {op_id} held back: {e}");
+                        break;
+                    }
+                }
+                if let Err(e) = record_op_result(&app, &op_id, result).await {
+                    log::warn!("[sync] Could not record result of {op_id}: {e}");
+                }
             }
+        });
+    }
+
+    while let Some(res) = workers.join_next().await {
+        if let Err(e) = res {
+            log::warn!("[sync] Worker panicked: {e}");
         }
     }
 
     Ok(())
 }
 
+async fn run_op(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    server_url: &str,
+    token: &str,
+    limiter: &BandwidthLimiter,
+    op_type: &str,
+    payload: &Json,
+) -> Result<()> {
+    match op_type {
+        "upload_document" => upload_document(app, client, server_url, token, limiter, payload).await,
+        "delete_document" => delete_document_on_server(client, server_url, token, payload).await,
+        "update_document" => push_document_update(app, client, server_url, token, payload).await,
+        "sync_collection" => push_collection(app, client, server_url, token, payload).await,
+        "trash_document" | "restore_document" => push_trash_state(app, client, server_url, token, payload).await,
+        "sync_profile"    => push_profile(app, client, server_url, token, payload).await,
+        "propagate_acl"   => push_acl_changeset(app, client, server_url, token, payload).await,
+        "sync_annotation" => push_annotation(app, client, server_url, token, payload).await,
+        "api_request"     => send_api_request(app, client, server_url, token, payload).await,
+        other => { log::warn!("[sync] Unknown op: {other}"); Ok(()) }
+    }
+}
+
+async fn record_op_result(app: &AppHandle, op_id: &str, result: Result<()>) -> Result<()> {
+    // Update op status — new connection per update to avoid lock contention
+    let state = app.state::<crate::AppState>();
+    let conn  = db::connect(&state.db).await?;
+    match result {
+        Ok(_) => {
+            timing::execute(&conn,
+                "UPDATE offline_operations SET status='done', updated_at=datetime('now') WHERE id=?1",
+                libsql::params![op_id],
+            ).await?;
+        }
+        Err(e) => {
+            log::warn!("[sync] Op {op_id} failed: {e}");
+            conn.execute(
+                "UPDATE offline_operations
+                 SET retry_count = retry_count + 1, status = 'failed',
+                     error_msg = ?1, updated_at = datetime('now')
+                 WHERE id = ?2",
+                libsql::params![e.to_string(), op_id],
+            ).await?;
+        }
+    }
+    Ok(())
+}
+
 async fn upload_document(
     app: &AppHandle,
     client: &reqwest::Client,
     server_url: &str,
     token: &str,
+    limiter: &BandwidthLimiter,
     payload: &Json,
 ) -> Result<()> {
     let doc_id = payload["doc_id"].as_str().context("Missing doc_id")?;
 
-    let (filename, local_path, content_type, metadata) = {
+    let (filename, local_path, content_type, metadata, content_hash) = {
         let state = app.state::<crate::AppState>();
-        let conn  = state.db.connect()?;
+        let conn  = db::connect(&state.db).await?;
         let mut rows = conn.query(
-            "SELECT filename, local_path, content_type, metadata FROM documents WHERE id=?1",
+            "SELECT filename, local_path, content_type, metadata, content_hash FROM documents WHERE id=?1",
             libsql::params![doc_id],
         ).await?;
 
@@ -157,6 +320,7 @@ async fn upload_document(
                 text(&row, 1),
                 text(&row, 2),
                 text(&row, 3).unwrap_or_else(|| "{}".into()),
+                text(&row, 4).filter(|h| !h.is_empty()),
             )
         } else {
             anyhow::bail!("Document {doc_id} not found in local DB");
@@ -164,60 +328,109 @@ async fn upload_document(
     };
 
     let local_path = local_path.context("Document has no local_path")?;
+    let ct         = content_type.unwrap_or_else(|| "application/octet-stream".into());
 
-    // 1. Get presigned S3 URL from Phoenix
-    let url_resp: Json = client
-        .post(format!("{server_url}/api/v1/sync/upload-url"))
-        .bearer_auth(token)
-        .json(&serde_json::json!({ "doc_id": doc_id, "filename": filename }))
-        .send().await?
-        .json().await?;
+    // Identical content already on the server is referenced, not sent again
+    let existing = {
+        let state = app.state::<crate::AppState>();
+        let conn  = db::connect(&state.db).await?;
+        uploaded_copy(&conn, doc_id).await?
+    };
+    let ipfs: IpfsSettings = {
+        let state = app.state::<crate::AppState>();
+        let conn  = db::connect(&state.db).await?;
+        settings::get(&conn, settings::IPFS_SETTINGS).await?.unwrap_or_default()
+    };
+    let object_key = match existing {
+        Some(object_key) => {
+            log::info!("[sync] {doc_id} has the same content as {object_key}; skipping upload");
+            object_key
+        }
+        None if ipfs.enabled => {
+            // Pinned on the user's IPFS node; the server only records the CID
+            let store = IpfsStore::new(&ipfs.api_url)?;
+            let path  = compress::plain_path(std::path::Path::new(&local_path)).await?;
+            let size  = tokio::fs::metadata(&path).await?.len();
+            limiter.acquire(size as usize).await;
+            let started = std::time::Instant::now();
+            let blob    = store.put(&path).await.context("Pinning to IPFS failed")?;
+            app.state::<crate::AppState>().transfer_metrics.record(Direction::Upload, size, started.elapsed());
+            ipfs::object_key(&blob.key)
+        }
+        None => {
+            // 1. Get presigned S3 URL from Phoenix, against the user's own
+            //    bucket if one is registered
+            let target = {
+                let state = app.state::<crate::AppState>();
+                let conn  = db::connect(&state.db).await?;
+                super::bucket::target(&conn).await?
+            };
+            // With the content hash the server hands out a content-addressed
+            // key, which identical documents can later share
+            let mut request = serde_json::json!({ "doc_id": doc_id, "filename": filename, "content_hash": content_hash });
+            if let Some(bucket_id) = target.bucket_id {
+                request["bucket_id"] = bucket_id.into();
+            }
+            let url_resp: Json = client
+                .post(format!("{server_url}/api/v1/sync/upload-url"))
+                .bearer_auth(token)
+                .json(&request)
+                .send_retrying().await?
+                .json().await?;
 
-    let upload_url = url_resp["upload_url"].as_str().context("No upload_url")?;
-    let object_key = url_resp["object_key"].as_str().context("No object_key")?;
+            let upload_url = url_resp["upload_url"].as_str().context("No upload_url")?;
+            let object_key = url_resp["object_key"].as_str().context("No object_key")?;
 
-    // 2. Upload file bytes directly to S3 (presigned PUT)
-    let ct         = content_type.unwrap_or_else(|| "application/octet-stream".into());
-    let file_bytes = tokio::fs::read(&local_path).await
-        .with_context(|| format!("Cannot read {local_path}"))?;
-        
-        
-        client
-    .put(upload_url)
-    .body(file_bytes)
-    .send().await?
-    .error_for_status()?;
+            // 2. Upload file bytes directly to S3 (presigned PUT). A compressed
+            //    blob goes as is when the server takes zstd bodies.
+            let path = std::path::Path::new(&local_path);
+            let send_zstd = compress::is_compressed(path)
+                && url_resp["accept_encoding"].as_array().is_some_and(|e| e.iter().any(|e| e == compress::ZSTD));
+            let file_bytes = if send_zstd {
+                tokio::fs::read(path).await.with_context(|| format!("Cannot read {local_path}"))?
+            } else {
+                compress::read(path).await?
+            };
+
+            let size = file_bytes.len() as u64;
+            limiter.acquire(file_bytes.len()).await;
+            let started = std::time::Instant::now();
+            let mut put = client.put(upload_url);
+            if send_zstd {
+                put = put.header(reqwest::header::CONTENT_ENCODING, compress::ZSTD);
+            }
+            put.body(file_bytes)
+                .send_retrying().await?
+                .error_for_status()?;
+            app.state::<crate::AppState>().transfer_metrics.record(Direction::Upload, size, started.elapsed());
+            object_key.to_string()
+        }
+    };
 
     // 3. Tell Phoenix the upload is done
-    client
-        .post(format!("{server_url}/api/v1/sync/apply"))
-        .bearer_auth(token)
-        .json(&serde_json::json!({
-            "changes": [{
-                "type": "create_document",
-                "id":   doc_id,
-                "data": {
-                    "id":           doc_id,
-                    "filename":     filename,
-                    "content_type": ct,
-                    "object_key":   object_key,
-                    "metadata":     serde_json::from_str::<Json>(&metadata).unwrap_or(Json::Null)
-                }
-            }]
-        }))
-        .send().await?
-        .error_for_status()?;
+    push_change(client, server_url, token, serde_json::json!({
+        "type": "create_document",
+        "id":   doc_id,
+        "data": {
+            "id":           doc_id,
+            "filename":     filename,
+            "content_type": ct,
+            "object_key":   object_key,
+            "content_hash": content_hash,
+            "metadata":     serde_json::from_str::<Json>(&metadata).unwrap_or(Json::Null)
+        }
+    })).await?;
 
     // 4. Mark local record as synced
     {
         let state = app.state::<crate::AppState>();
-        let conn  = state.db.connect()?;
+        let conn  = db::connect(&state.db).await?;
         conn.execute(
             "UPDATE documents
              SET status='synced', object_key=?1, is_synced=1,
                  needs_upload=0, last_synced_at=datetime('now'), updated_at=datetime('now')
              WHERE id=?2",
-            libsql::params![object_key, doc_id],
+            libsql::params![object_key.as_str(), doc_id],
         ).await?;
     }
 
@@ -225,6 +438,27 @@ async fn upload_document(
     Ok(())
 }
 
+/// The object_key of another synced document of the same tenant with the same
+/// content, if any: the server already holds those bytes. Only
+/// content-addressed keys (`blobs/<user>/<sha256>`, also inside the user's
+/// own bucket, and IPFS CIDs) are shared; a per-document `uploads/` key is
+/// overwritten when that document is edited.
+async fn uploaded_copy(conn: &libsql::Connection, doc_id: &str) -> Result<Option<String>> {
+    let mut rows = conn.query(
+        "SELECT o.object_key FROM documents d
+         JOIN documents o ON o.tenant_id = d.tenant_id AND o.content_hash = d.content_hash
+         WHERE d.id = ?1 AND COALESCE(d.content_hash, '') != '' AND o.id != d.id
+           AND o.status = 'synced' AND o.needs_upload = 0
+           AND (o.object_key LIKE 'blobs/%' OR o.object_key LIKE 'byo/%/blobs/%' OR o.object_key LIKE ?2 || '%')
+         LIMIT 1",
+        libsql::params![doc_id, ipfs::OBJECT_KEY_PREFIX],
+    ).await?;
+    Ok(match rows.next().await? {
+        Some(row) => text(&row, 0),
+        None      => None,
+    })
+}
+
 async fn delete_document_on_server(
     client: &reqwest::Client,
     server_url: &str,
@@ -232,15 +466,401 @@ async fn delete_document_on_server(
     payload: &Json,
 ) -> Result<()> {
     let doc_id = payload["doc_id"].as_str().context("Missing doc_id")?;
-    client
+    push_change(client, server_url, token, serde_json::json!({
+        "type": "delete_document", "id": doc_id, "data": {"id": doc_id}
+    })).await
+}
+
+/// Metadata-only change (rename, move, tags) — sends the document's current state.
+async fn push_document_update(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    server_url: &str,
+    token: &str,
+    payload: &Json,
+) -> Result<()> {
+    let doc_id = payload["doc_id"].as_str().context("Missing doc_id")?;
+
+    let data = {
+        let state = app.state::<crate::AppState>();
+        let conn  = db::connect(&state.db).await?;
+        let mut rows = conn.query(
+            &format!(
+                "SELECT filename, collection_id, {}, metadata, local_version
+                 FROM documents WHERE id=?1",
+                crate::db::tags::json_expr("documents.id"),
+            ),
+            libsql::params![doc_id],
+        ).await?;
+        let Some(row) = rows.next().await? else {
+            log::debug!("[sync] {doc_id} gone before update was sent — dropping");
+            return Ok(());
+        };
+        serde_json::json!({
+            "id":            doc_id,
+            "filename":      text(&row, 0),
+            "collection_id": text(&row, 1),
+            "tags":          serde_json::from_str::<Json>(&text(&row, 2).unwrap_or_default()).unwrap_or(Json::Null),
+            "metadata":      serde_json::from_str::<Json>(&text(&row, 3).unwrap_or_default()).unwrap_or(Json::Null),
+            "local_version": match row.get_value(4) { Ok(Value::Integer(n)) => n, _ => 0 },
+        })
+    };
+
+    push_change(client, server_url, token, serde_json::json!({
+        "type": "update_document", "id": doc_id, "data": data
+    })).await
+}
+
+/// Upsert or delete a collection depending on whether it still exists locally.
+async fn push_collection(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    server_url: &str,
+    token: &str,
+    payload: &Json,
+) -> Result<()> {
+    let id = payload["collection_id"].as_str().context("Missing collection_id")?;
+
+    let change = {
+        let state = app.state::<crate::AppState>();
+        let conn  = db::connect(&state.db).await?;
+        let mut rows = conn.query(
+            "SELECT name, parent_id FROM collections WHERE id=?1",
+            libsql::params![id],
+        ).await?;
+        match rows.next().await? {
+            Some(row) => serde_json::json!({
+                "type": "upsert_collection",
+                "id":   id,
+                "data": { "id": id, "name": text(&row, 0), "parent_id": text(&row, 1) }
+            }),
+            None => serde_json::json!({ "type": "delete_collection", "id": id, "data": { "id": id } }),
+        }
+    };
+
+    push_change(client, server_url, token, change).await
+}
+
+/// Trash and restore both send the document's current trash state, so a stale
+/// op queued before the opposite one can't undo it on the server.
+async fn push_trash_state(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    server_url: &str,
+    token: &str,
+    payload: &Json,
+) -> Result<()> {
+    let doc_id = payload["doc_id"].as_str().context("Missing doc_id")?;
+
+    let trashed_at = {
+        let state = app.state::<crate::AppState>();
+        let conn  = db::connect(&state.db).await?;
+        let mut rows = conn.query(
+            "SELECT trashed_at FROM documents WHERE id=?1 AND status != 'deleted'",
+            libsql::params![doc_id],
+        ).await?;
+        let Some(row) = rows.next().await? else {
+            log::debug!("[sync] {doc_id} gone before trash state was sent — dropping");
+            return Ok(());
+        };
+        text(&row, 0)
+    };
+
+    let change = match trashed_at {
+        Some(at) => serde_json::json!({
+            "type": "trash_document", "id": doc_id, "data": { "id": doc_id, "trashed_at": at }
+        }),
+        None => serde_json::json!({
+            "type": "restore_document", "id": doc_id, "data": { "id": doc_id }
+        }),
+    };
+    push_change(client, server_url, token, change).await
+}
+
+/// Sends the stored (already signed) profile; the server relays it as-is.
+async fn push_profile(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    server_url: &str,
+    token: &str,
+    payload: &Json,
+) -> Result<()> {
+    let did = payload["did"].as_str().context("Missing did")?;
+    let (profile, document) = {
+        let state = app.state::<crate::AppState>();
+        let conn  = db::connect(&state.db).await?;
+        (profiles::get(&conn, did).await?, crate::crypto::keys::document(&conn, did).await?)
+    };
+    let Some(profile) = profile else {
+        log::debug!("[sync] Profile for {did} gone before it was sent — dropping");
+        return Ok(());
+    };
+    // The signed DID document goes along: it is what binds the signing key
+    // the profile names to the DID for whoever receives it
+    let mut data = serde_json::to_value(&profile)?;
+    data["did_document"] = document;
+    let change = serde_json::json!({ "type": "update_profile", "id": did, "data": data });
+    push_change(client, server_url, token, change).await
+}
+
+/// Send an ACL change-set's pending documents to the batch endpoint, one
+/// batch at a time. Documents the server rejects are recorded as failed and
+/// not resent; a failed request leaves the rest pending for the op's retry.
+async fn push_acl_changeset(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    server_url: &str,
+    token: &str,
+    payload: &Json,
+) -> Result<()> {
+    let id = payload["changeset_id"].as_str().context("Missing changeset_id")?;
+
+    loop {
+        let (changeset, batch) = {
+            let state = app.state::<crate::AppState>();
+            let conn  = db::connect(&state.db).await?;
+            let Some(changeset) = acl::changeset(&conn, id).await? else {
+                log::debug!("[sync] ACL change-set {id} gone — dropping");
+                return Ok(());
+            };
+            (changeset, acl::pending_batch(&conn, id).await?)
+        };
+        if batch.is_empty() {
+            log::info!(
+                "[sync] ACL change-set {id}: {}/{} applied, {} failed",
+                changeset.applied, changeset.total, changeset.failed.len(),
+            );
+            return Ok(());
+        }
+
+        let resp: Json = client
+            .post(format!("{server_url}/api/v1/acl/batch"))
+            .bearer_auth(token)
+            .json(&serde_json::json!({
+                "changeset_id":  id,
+                "collection_id": changeset.collection_id,
+                "acl":           changeset.acl,
+                "document_ids":  batch,
+            }))
+            .send_retrying().await?
+            .error_for_status()?
+            .json().await?;
+
+        // {"failed": [{"id": …, "error": …}]}; anything not listed was applied
+        let failed: Vec<AclFailure> = resp["failed"].as_array().into_iter().flatten()
+            .filter_map(|f| Some(AclFailure {
+                document_id: f["id"].as_str()?.to_string(),
+                error:       f["error"].as_str().unwrap_or("Rejected by server").to_string(),
+            }))
+            .collect();
+
+        let state = app.state::<crate::AppState>();
+        let conn  = db::connect(&state.db).await?;
+        acl::record_batch(&conn, id, &batch, &failed).await?;
+        if let Some(progress) = acl::changeset(&conn, id).await? {
+            let _ = app.emit("acl-propagation-progress", progress);
+        }
+    }
+}
+
+/// Sends the annotation's current state; a tombstone becomes a delete.
+async fn push_annotation(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    server_url: &str,
+    token: &str,
+    payload: &Json,
+) -> Result<()> {
+    let id = payload["annotation_id"].as_str().context("Missing annotation_id")?;
+    let annotation = {
+        let state = app.state::<crate::AppState>();
+        let conn  = db::connect(&state.db).await?;
+        annotations::get(&conn, id).await?
+    };
+    let Some(annotation) = annotation else {
+        log::debug!("[sync] Annotation {id} gone before it was sent — dropping");
+        return Ok(());
+    };
+    let change = match annotation.deleted_at {
+        Some(_) => serde_json::json!({ "type": "delete_annotation", "id": id, "data": annotation }),
+        None    => serde_json::json!({ "type": "upsert_annotation", "id": id, "data": annotation }),
+    };
+    push_change(client, server_url, token, change).await
+}
+
+/// Replay a queued API call (db/ops.rs). A 409 or 412 answer goes to the
+/// request's conflict hook.
+async fn send_api_request(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    server_url: &str,
+    token: &str,
+    payload: &Json,
+) -> Result<()> {
+    let request: ApiRequest = serde_json::from_value(payload.clone()).context("Malformed api_request")?;
+    let method = reqwest::Method::from_bytes(request.method.as_bytes())?;
+    let mut builder = client
+        .request(method, format!("{server_url}{}", request.path))
+        .bearer_auth(token)
+        .header("Idempotency-Key", &request.idempotency_key);
+    if let Some(body) = &request.body {
+        builder = builder.json(body);
+    }
+    let resp = builder.send_retrying().await?;
+
+    let status = resp.status();
+    if status != reqwest::StatusCode::CONFLICT && status != reqwest::StatusCode::PRECONDITION_FAILED {
+        resp.error_for_status()?;
+        return Ok(());
+    }
+    let answer: Json = resp.json().await.unwrap_or(Json::Null);
+    if request.on_conflict == RequestConflict::Discard {
+        log::info!("[sync] {} {} conflicts with the server ({status}) — dropping", request.method, request.path);
+        return Ok(());
+    }
+    if request.on_conflict == RequestConflict::Notify {
+        let _ = app.emit("api-request-conflict", serde_json::json!({
+            "idempotency_key": request.idempotency_key,
+            "method":          request.method,
+            "path":            request.path,
+            "status":          status.as_u16(),
+            "response":        answer,
+        }));
+    }
+    anyhow::bail!("{} {} conflicts with the server ({status}): {answer}", request.method, request.path)
+}
+
+/// Send one change to /sync/apply. The server answers 200 even for changes it
+/// failed or skipped, so success is its per-change status: anything but
+/// "applied" is an error and the op stays queued.
+async fn push_change(client: &reqwest::Client, server_url: &str, token: &str, change: Json) -> Result<()> {
+    let kind = change["type"].as_str().unwrap_or("change").to_string();
+    let response: Json = client
         .post(format!("{server_url}/api/v1/sync/apply"))
         .bearer_auth(token)
-        .json(&serde_json::json!({
-            "changes": [{"type": "delete_document", "id": doc_id, "data": {"id": doc_id}}]
-        }))
-        .send().await?
-        .error_for_status()?;
-    Ok(())
+        .json(&serde_json::json!({ "changes": [change] }))
+        .send_retrying().await?
+        .error_for_status()?
+        .json().await?;
+    let result = &response["results"][0];
+    match result["status"].as_str() {
+        Some("applied") => Ok(()),
+        status => anyhow::bail!(
+            "Server did not apply {kind}: {}",
+            result["error"].as_str().or(status).unwrap_or("no result returned"),
+        ),
+    }
+}
+
+// ── On-demand download ────────────────────────────────────────────────────────
+
+/// Fetch a document's bytes from object storage into the local store and point
+/// documents.local_path at them. Used when local content is missing or corrupt.
+pub(crate) async fn download_document(app: &AppHandle, doc_id: &str) -> Result<std::path::PathBuf> {
+    let token = oauth::access_token(app).await?.context("Not authenticated")?;
+
+    let (server_url, object_key, expected_hash) = {
+        let state = app.state::<crate::AppState>();
+        let conn  = db::connect(&state.db).await?;
+        let server_url = query_server_url(&conn).await;
+        let mut rows = conn.query(
+            "SELECT object_key, content_hash FROM documents WHERE id=?1",
+            libsql::params![doc_id],
+        ).await?;
+        let row = rows.next().await?.with_context(|| format!("Document {doc_id} not found"))?;
+        (server_url, text(&row, 0).filter(|k| !k.is_empty()), text(&row, 1))
+    };
+    let object_key = object_key.context("Document has no object_key — nothing to download")?;
+
+    // Another document may already hold the same bytes locally
+    if let Some(hash) = expected_hash.as_deref().filter(|h| crate::storage::cas::is_sha256_hex(h)) {
+        let path = crate::storage::cas::blob_path(&crate::storage::cas::files_dir(app)?, &hash.to_lowercase());
+        if tokio::fs::metadata(&path).await.is_ok() {
+            let state = app.state::<crate::AppState>();
+            let conn  = db::connect(&state.db).await?;
+            conn.execute(
+                "UPDATE documents SET local_path = ?1, compression = NULL, needs_download = 0, is_cached_locally = 1 WHERE id = ?2",
+                libsql::params![path.to_string_lossy().to_string(), doc_id],
+            ).await?;
+            log::info!("[sync] {doc_id} shares content with a local blob; not downloaded");
+            return Ok(path);
+        }
+    }
+
+    let started = std::time::Instant::now();
+    let bytes: Vec<u8> = match ipfs::cid(&object_key) {
+        Some(cid) => {
+            let state = app.state::<crate::AppState>();
+            let conn  = db::connect(&state.db).await?;
+            ipfs::store(&conn).await?.get(cid).await.context("Fetching from IPFS failed")?
+        }
+        None => {
+            let client = http::client(Duration::from_secs(120))?;
+
+            // Same presigned flow as uploads: ask Phoenix for a GET URL, then hit S3 directly
+            let url_resp: Json = client
+                .post(format!("{server_url}/api/v1/sync/download-url"))
+                .bearer_auth(&token)
+                .json(&serde_json::json!({ "doc_id": doc_id, "object_key": object_key }))
+                .send_retrying().await?
+                .error_for_status()?
+                .json().await?;
+            let download_url = url_resp["download_url"].as_str().context("No download_url")?;
+            client.get(download_url).send_retrying().await?.error_for_status()?.bytes().await?.into()
+        }
+    };
+    app.state::<crate::AppState>().transfer_metrics
+        .record(Direction::Download, bytes.len() as u64, started.elapsed());
+
+    // Checked before anything is stored, so a bad download leaves no blob behind
+    if let Some(expected) = expected_hash.filter(|h| crate::storage::cas::is_sha256_hex(h)) {
+        let actual = crate::storage::cas::hex(&Sha256::digest(&bytes));
+        if !expected.eq_ignore_ascii_case(&actual) {
+            anyhow::bail!("Downloaded content for {doc_id} does not match content_hash");
+        }
+    }
+    let files_dir    = crate::storage::cas::files_dir(app)?;
+    let (path, hash) = crate::storage::cas::store_bytes(&files_dir, &bytes).await?;
+
+    let state = app.state::<crate::AppState>();
+    let conn  = db::connect(&state.db).await?;
+    conn.execute(
+        "UPDATE documents
+         SET local_path = ?1, compression = NULL, needs_download = 0, is_cached_locally = 1,
+             content_hash = COALESCE(NULLIF(content_hash, ''), ?2),
+             file_size = COALESCE(file_size, ?3)
+         WHERE id = ?4",
+        libsql::params![path.to_string_lossy().to_string(), hash, bytes.len() as i64, doc_id],
+    ).await?;
+
+    log::info!("[sync] Downloaded {doc_id} ← {object_key}");
+    Ok(path)
+}
+
+/// Documents being fetched by fetch_in_background.
+static DOWNLOADING: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(Default::default);
+
+/// Start download_document for `doc_id` unless it is already running, and
+/// emit "document-downloaded" (or "document-download-failed") when it ends.
+pub(crate) fn fetch_in_background(app: &AppHandle, doc_id: &str) {
+    if !DOWNLOADING.lock().unwrap().insert(doc_id.to_string()) {
+        return;
+    }
+    let app    = app.clone();
+    let doc_id = doc_id.to_string();
+    tauri::async_runtime::spawn(async move {
+        let result = download_document(&app, &doc_id).await;
+        DOWNLOADING.lock().unwrap().remove(&doc_id);
+        match result {
+            Ok(_) => {
+                let _ = app.emit("document-downloaded", serde_json::json!({ "doc_id": doc_id }));
+            }
+            Err(e) => {
+                log::warn!("[sync] On-demand download of {doc_id} failed: {e}");
+                let _ = app.emit("document-download-failed", serde_json::json!({ "doc_id": doc_id, "error": e.to_string() }));
+            }
+        }
+    });
 }
 
 // ── Pull server changes ───────────────────────────────────────────────────────
@@ -251,48 +871,204 @@ async fn pull_server_changes(
     server_url: &str,
     token: &str,
 ) -> Result<()> {
-    let since = {
+    // Each tenant pulls from its own cursor, through its own filter
+    let (tenant_id, remote_tenant, since, etag, filter) = {
         let state = app.state::<crate::AppState>();
-        let conn  = state.db.connect()?;
-        let mut rows = conn.query(
-            "SELECT COALESCE(last_sync_at,'2000-01-01T00:00:00Z')
-             FROM local_identity WHERE id='singleton'",
-            (),
-        ).await?;
-        if let Some(row) = rows.next().await? {
-            text(&row, 0).unwrap_or_else(|| "2000-01-01T00:00:00Z".into())
-        } else {
-            "2000-01-01T00:00:00Z".into()
-        }
+        let conn  = db::connect(&state.db).await?;
+        let (_, tenant_id) = identity::current(&conn).await?;
+        let remote_tenant = tenants::remote_id(&conn, &tenant_id).await?;
+        let since = tenants::sync_cursor(&conn, &tenant_id).await?;
+        let etag  = tenants::changes_etag(&conn, &tenant_id).await?;
+        let filter = super::filter::for_pull(&conn, &tenant_id).await?;
+        (tenant_id, remote_tenant, since, etag, filter)
     };
 
-    let resp: Json = client
+    let query = [("since", since), ("tenant_id", remote_tenant.clone())];
+    let mut request = client
         .get(format!("{server_url}/api/v1/sync/changes"))
         .bearer_auth(token)
-        .query(&[("since", &since)])
-        .send().await?
-        .json().await?;
+        .query(&query);
+    if let Some(etag) = &etag {
+        request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+    }
+    let resp = request.send_retrying().await?;
+
+    // 304: the same change set as last time, which is already applied
+    if resp.status() == reqwest::StatusCode::NOT_MODIFIED {
+        log::debug!("[sync] No new changes for tenant {tenant_id}");
+        return Ok(());
+    }
+    let resp = resp.error_for_status()?;
+    let etag = resp.headers().get(reqwest::header::ETAG)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let resp: Json = resp.json().await?;
 
     let changes = resp["changes"].as_array().cloned().unwrap_or_default();
-    log::info!("[sync] Pulled {} changes from server", changes.len());
+    log::info!("[sync] Pulled {} changes from server for tenant {tenant_id}", changes.len());
 
-    for change in &changes {
-        apply_server_change(app, change).await?;
+    let mut watched = Vec::new();
+    for mut change in changes {
+        // Rows are stored under the local id of the tenant (db/tenants.rs)
+        if change["data"]["tenant_id"] == remote_tenant.as_str() && remote_tenant != tenant_id {
+            change["data"]["tenant_id"] = tenant_id.as_str().into();
+        }
+        let change = &change;
+        if let Some(conflict) = apply_server_change(app, &tenant_id, filter.as_ref(), change).await? {
+            notify_conflict(app, &conflict);
+            continue;
+        }
+        if let Some(doc_id) = watched_change(app, change).await? {
+            watched.push((doc_id, change["type"].as_str().unwrap_or("").to_string()));
+        }
     }
 
-    // Update last_sync_at
     let state = app.state::<crate::AppState>();
-    let conn  = state.db.connect()?;
+    let conn  = db::connect(&state.db).await?;
+    tenants::advance_sync_cursor(&conn, &tenant_id, etag.as_deref()).await?;
+
+    for (doc_id, kind) in watched {
+        notify_watched_change(app, &doc_id, &kind).await;
+    }
+
+    Ok(())
+}
+
+/// The document id when `change` touches a watched document. New content for
+/// it is marked for download, so a failed download below is retried by
+/// prefetch.
+async fn watched_change(app: &AppHandle, change: &Json) -> Result<Option<String>> {
+    let kind = change["type"].as_str().unwrap_or("");
+    let Some(doc_id) = change["data"]["id"].as_str().filter(|id| !id.is_empty() && kind.starts_with("document_")) else {
+        return Ok(None);
+    };
+    let state = app.state::<crate::AppState>();
+    let conn  = db::connect(&state.db).await?;
+    if !watches::changed(&conn, doc_id).await? {
+        return Ok(None);
+    }
+    if matches!(kind, "document_created" | "document_updated") {
+        conn.execute(
+            "UPDATE documents SET needs_download = 1 WHERE id = ?1 AND COALESCE(object_key, '') != ''",
+            libsql::params![doc_id],
+        ).await?;
+    }
+    Ok(Some(doc_id.to_string()))
+}
+
+/// Download a watched document's new content ahead of prefetch, then tell
+/// the user: a desktop notification and a "watched-document-changed" event.
+async fn notify_watched_change(app: &AppHandle, doc_id: &str, kind: &str) {
+    if matches!(kind, "document_created" | "document_updated") {
+        if let Err(e) = download_document(app, doc_id).await {
+            log::warn!("[sync] Download of watched document {doc_id} failed, left for prefetch: {e}");
+        }
+    }
+
+    let filename = async {
+        let state = app.state::<crate::AppState>();
+        let conn  = db::connect(&state.db).await.ok()?;
+        let mut rows = conn.query("SELECT filename FROM documents WHERE id=?1", libsql::params![doc_id]).await.ok()?;
+        text(&rows.next().await.ok()??, 0)
+    }.await.unwrap_or_else(|| doc_id.to_string());
+    let what = match kind {
+        "document_trashed"  => "was moved to the trash",
+        "document_restored" => "was restored",
+        "document_deleted"  => "was deleted",
+        _                   => "was changed",
+    };
+
+    if let Err(e) = app.notification().builder()
+        .title("Watched document changed")
+        .body(format!("{filename} {what}"))
+        .show()
+    {
+        log::warn!("[sync] Could not show notification for {doc_id}: {e}");
+    }
+    let _ = app.emit("watched-document-changed", serde_json::json!({ "doc_id": doc_id, "change": kind }));
+}
+
+/// Text the server extracted for a document (`text_content`, or just a
+/// `snippet`), so search finds documents whose bytes aren't local yet. It
+/// only fills documents without text or replaces earlier server text; text
+/// extracted here from the real bytes is never overwritten.
+async fn store_server_text(app: &AppHandle, conn: &libsql::Connection, doc_id: &str, data: &Json) -> Result<()> {
+    let Some(mut text) = data["text_content"].as_str()
+        .or_else(|| data["snippet"].as_str())
+        .filter(|t| !t.trim().is_empty())
+        .map(str::to_string)
+    else {
+        return Ok(());
+    };
+
+    let files_dir = crate::storage::cas::files_dir(app)?;
+    let cut = crate::storage::text::cut(&files_dir, conn, &mut text).await?;
+    let truncated = cut.as_ref().map(|(_, full)| {
+        serde_json::json!({ "stored_bytes": text.len(), "full_bytes": full }).to_string()
+    });
     conn.execute(
-        "UPDATE local_identity SET last_sync_at=datetime('now') WHERE id='singleton'", ()
+        "UPDATE documents
+         SET text_content   = ?1,
+             full_text_path = ?2,
+             metadata       = CASE WHEN ?3 IS NULL
+                                  THEN json_remove(json_set(metadata, '$.text_extractor', 'server'), '$.text_truncated')
+                                  ELSE json_set(metadata, '$.text_extractor', 'server', '$.text_truncated', json(?3))
+                              END
+         WHERE id = ?4 AND json_valid(metadata)
+           AND (COALESCE(text_content, '') = '' OR json_extract(metadata, '$.text_extractor') = 'server')",
+        libsql::params![
+            text,
+            cut.map(|(path, _)| path.to_string_lossy().to_string()),
+            truncated,
+            doc_id,
+        ],
     ).await?;
-
     Ok(())
 }
 
-async fn apply_server_change(app: &AppHandle, change: &Json) -> Result<()> {
+/// Tell the UI a conflict needs resolving: a "sync-conflict" event carrying
+/// both versions and the strategies resolve_conflict accepts, and a desktop
+/// notification.
+fn notify_conflict(app: &AppHandle, conflict: &SyncConflict) {
+    let filename = conflict.local.filename.as_deref().unwrap_or(&conflict.document_id);
+    if let Err(e) = app.notification().builder()
+        .title("Sync conflict")
+        .body(format!("{filename} was changed here and on the server"))
+        .show()
+    {
+        log::warn!("[sync] Could not show notification for conflict {}: {e}", conflict.id);
+    }
+    let _ = app.emit("sync-conflict", conflict);
+}
+
+/// A collaborator's profile. The controller-signed DID document sent along
+/// adds the signing key it names to their key history first; unsigned or
+/// forged profiles and those of revoked contacts are rejected, new DIDs go in
+/// the contact book.
+async fn store_profile(conn: &libsql::Connection, data: &Json) -> Result<()> {
+    let profile: Profile = serde_json::from_value(data.clone())?;
+    if contacts::trust(conn, &profile.did).await? == Some(TrustLevel::Revoked) {
+        anyhow::bail!("{} is a revoked contact", profile.did);
+    }
+    if let Some(document) = data.get("did_document").filter(|d| !d.is_null()) {
+        contacts::add_document_keys(conn, &profile.did, document).await?;
+    }
+    profiles::store(conn, &profile).await?;
+    contacts::seen(conn, &profile.did).await
+}
+
+/// `tenant_id` is the tenant the change was pulled for, used when the
+/// change doesn't name one. A document change that would overwrite an unsent
+/// local change is held as a conflict instead, and returned. A new document
+/// outside `filter` is not added.
+async fn apply_server_change(
+    app: &AppHandle,
+    tenant_id: &str,
+    filter: Option<&SyncFilter>,
+    change: &Json,
+) -> Result<Option<SyncConflict>> {
     let state = app.state::<crate::AppState>();
-    let conn  = state.db.connect()?;
+    let conn  = db::connect(&state.db).await?;
     let data  = &change["data"];
 
     match change["type"].as_str().unwrap_or("") {
@@ -307,17 +1083,29 @@ async fn apply_server_change(app: &AppHandle, change: &Json) -> Result<()> {
             let exists = if let Some(row) = rows.next().await? {
                 matches!(row.get_value(0), Ok(Value::Integer(n)) if n > 0)
             } else { false };
+            drop(rows);
+
+            if exists && conflicts::conflicts_with_local(&conn, id, data).await? {
+                let conflict = conflicts::record(&conn, id, data).await?;
+                log::info!("[sync] {id} changed locally and on the server — held as conflict {}", conflict.id);
+                return Ok(Some(conflict));
+            }
+
+            if !exists && filter.is_some_and(|f| !f.admits(data)) {
+                log::debug!("[sync] {id} is outside the sync filter — not added");
+                return Ok(None);
+            }
 
             if !exists {
                 conn.execute(
                     "INSERT OR IGNORE INTO documents
                      (id, user_id, tenant_id, filename, content_type, object_key,
-                      status, needs_upload, needs_download, is_synced)
-                     VALUES (?1,?2,?3,?4,?5,?6,'synced',0,1,1)",
+                      status, needs_upload, needs_download, is_synced, is_cached_locally)
+                     VALUES (?1,?2,?3,?4,?5,?6,'synced',0,1,1,0)",
                     libsql::params![
                         id,
                         data["user_id"].as_str().unwrap_or(""),
-                        data["tenant_id"].as_str().unwrap_or("default"),
+                        data["tenant_id"].as_str().unwrap_or(tenant_id),
                         data["filename"].as_str().unwrap_or(""),
                         data["content_type"].as_str().unwrap_or(""),
                         data["object_key"].as_str().unwrap_or(""),
@@ -335,6 +1123,79 @@ async fn apply_server_change(app: &AppHandle, change: &Json) -> Result<()> {
                     ],
                 ).await?;
             }
+
+            // Only touch the folder when the server says something about it
+            if let Some(collection) = data.get("collection_id") {
+                conn.execute(
+                    "UPDATE documents SET collection_id=?1 WHERE id=?2",
+                    libsql::params![collection.as_str(), id],
+                ).await?;
+            }
+
+            store_server_text(app, &conn, id, data).await?;
+        }
+        "collection_created" | "collection_updated" => {
+            let id = data["id"].as_str().unwrap_or("");
+            conn.execute(
+                "INSERT INTO collections (id, user_id, tenant_id, parent_id, name)
+                 VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT(id) DO UPDATE SET
+                     parent_id  = excluded.parent_id,
+                     name       = excluded.name,
+                     updated_at = datetime('now')",
+                libsql::params![
+                    id,
+                    data["user_id"].as_str().unwrap_or(""),
+                    data["tenant_id"].as_str().unwrap_or(tenant_id),
+                    data["parent_id"].as_str(),
+                    data["name"].as_str().unwrap_or(""),
+                ],
+            ).await?;
+        }
+        "collection_deleted" => {
+            // Same semantics as a local delete: contents move up to the parent
+            let id = data["id"].as_str().unwrap_or("");
+            conn.execute(
+                "UPDATE collections SET parent_id = (SELECT parent_id FROM collections WHERE id=?1)
+                 WHERE parent_id=?1",
+                libsql::params![id],
+            ).await?;
+            conn.execute(
+                "UPDATE documents SET collection_id = (SELECT parent_id FROM collections WHERE id=?1)
+                 WHERE collection_id=?1",
+                libsql::params![id],
+            ).await?;
+            conn.execute("DELETE FROM collections WHERE id=?1", libsql::params![id]).await?;
+        }
+        "document_trashed" => {
+            let id = data["id"].as_str().unwrap_or("");
+            conn.execute(
+                "UPDATE documents SET trashed_at = COALESCE(?1, datetime('now')) WHERE id=?2",
+                libsql::params![data["trashed_at"].as_str(), id],
+            ).await?;
+        }
+        "document_restored" => {
+            let id = data["id"].as_str().unwrap_or("");
+            conn.execute(
+                "UPDATE documents SET trashed_at = NULL WHERE id=?1",
+                libsql::params![id],
+            ).await?;
+        }
+        "profile_updated" => {
+            if let Err(e) = store_profile(&conn, data).await {
+                log::warn!("[sync] Rejected profile update: {e}");
+            }
+        }
+        "annotation_created" | "annotation_updated" | "annotation_deleted" => {
+            match serde_json::from_value::<Annotation>(data.clone()) {
+                Ok(mut annotation) => {
+                    if change["type"] == "annotation_deleted" && annotation.deleted_at.is_none() {
+                        annotation.deleted_at = Some(annotation.updated_at.clone());
+                    }
+                    annotations::apply_remote(&conn, &annotation).await?;
+                }
+                Err(e) => log::warn!("[sync] Malformed annotation change: {e}"),
+            }
         }
         "document_deleted" => {
             let id = data["id"].as_str().unwrap_or("");
@@ -346,5 +1207,5 @@ async fn apply_server_change(app: &AppHandle, change: &Json) -> Result<()> {
         other => log::debug!("[sync] Unknown change type: {other}"),
     }
 
-    Ok(())
+    Ok(None)
 }
\ No newline at end of file
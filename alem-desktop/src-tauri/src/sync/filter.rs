@@ -0,0 +1,52 @@
+// src-tauri/src/sync/filter.rs
+// Selective sync. A tenant's SyncFilter picks which of its documents this
+// device receives, so a laptop short on storage can hold a subset of a large
+// account. The filter is local to this device: the server sends every
+// change, and a pulled document outside the filter is simply not inserted.
+// Documents already here are kept and still receive their updates. Other
+// changes (folders, annotations, profiles) are not filtered.
+use anyhow::Result;
+use serde_json::Value as Json;
+
+use crate::db::{models::SyncFilter, tenants};
+
+impl SyncFilter {
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+            && self.collection_ids.is_empty()
+            && self.content_types.is_empty()
+            && self.max_file_size.is_none()
+    }
+
+    /// Whether a pulled document change's data passes. A rule the change
+    /// carries no field for passes.
+    pub fn admits(&self, data: &Json) -> bool {
+        let tags_ok = self.tags.is_empty() || match data["tags"].as_array() {
+            Some(tags) => tags.iter()
+                .filter_map(Json::as_str)
+                .any(|t| self.tags.iter().any(|want| want.eq_ignore_ascii_case(t))),
+            None => true,
+        };
+        let collection_ok = self.collection_ids.is_empty() || match data.get("collection_id") {
+            Some(Json::String(c)) => self.collection_ids.contains(c),
+            Some(Json::Null)      => false,
+            _                     => true,
+        };
+        let type_ok = self.content_types.is_empty() || match data["content_type"].as_str() {
+            Some(t) => self.content_types.iter().any(|want| {
+                if want.ends_with('/') { t.starts_with(want.as_str()) } else { t.eq_ignore_ascii_case(want) }
+            }),
+            None => true,
+        };
+        let size_ok = match (self.max_file_size, data["file_size"].as_i64()) {
+            (Some(max), Some(size)) => size <= max,
+            _                       => true,
+        };
+        tags_ok && collection_ok && type_ok && size_ok
+    }
+}
+
+/// The tenant's filter, None when it has none or an empty one.
+pub async fn for_pull(conn: &libsql::Connection, tenant_id: &str) -> Result<Option<SyncFilter>> {
+    Ok(tenants::sync_filter(conn, tenant_id).await?.filter.filter(|f| !f.is_empty()))
+}
@@ -0,0 +1,56 @@
+// src-tauri/src/sync/metrics.rs
+// Rolling transfer throughput over the last few uploads/downloads, used to
+// report speed and an ETA for the remaining queue in get_sync_status.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Transfers kept per direction for the rolling average.
+const WINDOW: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Upload,
+    Download,
+}
+
+#[derive(Default)]
+pub struct TransferMetrics {
+    uploads:   Mutex<VecDeque<(u64, Duration)>>,
+    downloads: Mutex<VecDeque<(u64, Duration)>>,
+}
+
+impl TransferMetrics {
+    pub fn record(&self, direction: Direction, bytes: u64, elapsed: Duration) {
+        let mut samples = self.samples(direction).lock().unwrap();
+        if samples.len() == WINDOW {
+            samples.pop_front();
+        }
+        samples.push_back((bytes, elapsed));
+    }
+
+    /// Bytes/s over the window, or None before the first transfer.
+    pub fn throughput(&self, direction: Direction) -> Option<f64> {
+        let samples = self.samples(direction).lock().unwrap();
+        let bytes: u64 = samples.iter().map(|(b, _)| b).sum();
+        let secs: f64  = samples.iter().map(|(_, d)| d.as_secs_f64()).sum();
+        (secs > 0.0).then(|| bytes as f64 / secs)
+    }
+
+    /// Seconds to move `bytes` at the current rate.
+    pub fn eta(&self, direction: Direction, bytes: i64) -> Option<u64> {
+        if bytes <= 0 {
+            return Some(0);
+        }
+        let rate = self.throughput(direction).filter(|r| *r > 0.0)?;
+        Some((bytes as f64 / rate).ceil() as u64)
+    }
+
+    fn samples(&self, direction: Direction) -> &Mutex<VecDeque<(u64, Duration)>> {
+        match direction {
+            Direction::Upload   => &self.uploads,
+            Direction::Download => &self.downloads,
+        }
+    }
+}
@@ -0,0 +1,298 @@
+// src-tauri/src/sync/migrate.rs
+// Account-to-account migration: copy every document and folder of another
+// Alem account (on this server or another one) into the current tenant.
+// The source is read through the endpoints a syncing client uses, with a
+// token for that account:
+//
+// GET  /api/v1/sync/changes?since=…  → {"changes": [...]}, folded into the
+//                                      account's current state
+// POST /api/v1/sync/download-url     {doc_id, object_key} → {"download_url"}
+//
+// Nothing is written to the source. Documents get new ids here and are
+// queued for upload like fresh imports. Filename, type, hash, metadata, tags,
+// folder and created_at carry over. Trashed and deleted documents are left
+// behind.
+//
+// account_imports records each source id as it lands. Starting the migration
+// again with the same server picks up after the last document copied.
+use anyhow::{Context, Result};
+use libsql::{Connection, Value};
+use serde_json::Value as Json;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use uuid::Uuid;
+
+use crate::commands::documents::{insert_document, CreateDocumentInput};
+use crate::db::{self, identity, ops, tenants};
+use crate::http::{self, SendRetrying};
+use crate::jobs::JobHandle;
+use crate::storage::{cas, text};
+use crate::{extract, ocr, scan};
+
+/// The source account's live documents and collections, each as of the
+/// latest change that named it.
+struct Source {
+    documents:   Vec<Json>,
+    collections: Vec<Json>,
+}
+
+/// Where copied documents go.
+struct Destination {
+    files_dir: PathBuf,
+    user_id:   String,
+    tenant_id: String,
+}
+
+/// Copy everything from the account behind `token` on `server_url`.
+/// Returns { imported, skipped, missing, collections }.
+pub async fn run(app: &AppHandle, server_url: &str, token: &str, job: &JobHandle) -> Result<Json> {
+    let server_url = server_url.trim_end_matches('/');
+    let client = http::client(Duration::from_secs(120))?;
+
+    job.advance(0, Some("Reading source account".into()));
+    let source = fetch_state(&client, server_url, token).await?;
+    job.set_total(source.documents.len() as u64);
+
+    let conn = db::connect(&app.state::<crate::AppState>().db).await?;
+    let (user_id, tenant_id) = identity::current(&conn).await?;
+    let dest = Destination { files_dir: cas::files_dir(app)?, user_id, tenant_id };
+    let mut done = imported(&conn, server_url).await?;
+
+    let tx = conn.transaction().await?;
+    let new_collections = import_collections(&tx, server_url, &source.collections, &mut done, &dest).await?;
+    tx.commit().await?;
+
+    let (mut copied, mut skipped, mut missing) = (0u64, 0u64, 0u64);
+    for data in &source.documents {
+        job.check_cancelled()?;
+        let remote_id = data["id"].as_str().unwrap_or_default();
+        if done.contains_key(remote_id) {
+            skipped += 1;
+        } else {
+            match download(&client, &dest, server_url, token, data).await {
+                Ok(Some(blob)) => {
+                    let collection = data["collection_id"].as_str().and_then(|c| done.get(c)).cloned();
+                    let id = ingest(app, &conn, &dest, server_url, data, blob, collection).await?;
+                    done.insert(remote_id.to_string(), id);
+                    copied += 1;
+                }
+                Ok(None) => missing += 1,
+                Err(e) => {
+                    log::warn!("[migrate] {remote_id} from {server_url}: {e}");
+                    missing += 1;
+                }
+            }
+        }
+        job.advance(1, None);
+    }
+
+    log::info!("[migrate] {server_url}: {copied} copied, {skipped} already here, {missing} missing");
+    Ok(serde_json::json!({
+        "imported":    copied,
+        "skipped":     skipped,
+        "missing":     missing,
+        "collections": new_collections,
+    }))
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+/// Replay the source's whole change feed into its live documents and
+/// collections, in the order they were first seen.
+async fn fetch_state(client: &reqwest::Client, server_url: &str, token: &str) -> Result<Source> {
+    let resp: Json = client
+        .get(format!("{server_url}/api/v1/sync/changes"))
+        .bearer_auth(token)
+        .query(&[("since", tenants::EPOCH)])
+        .send_retrying().await?
+        .error_for_status()
+        .context("Source server rejected the token")?
+        .json().await?;
+
+    let mut documents:   (Vec<String>, HashMap<String, Json>) = Default::default();
+    let mut collections: (Vec<String>, HashMap<String, Json>) = Default::default();
+    let mut gone = HashSet::new();
+
+    for change in resp["changes"].as_array().into_iter().flatten() {
+        let data = &change["data"];
+        let Some(id) = data["id"].as_str().filter(|id| !id.is_empty()) else { continue };
+        let id = id.to_string();
+        match change["type"].as_str().unwrap_or("") {
+            "document_created" | "document_updated" => {
+                if !documents.1.contains_key(&id) {
+                    documents.0.push(id.clone());
+                }
+                documents.1.insert(id, data.clone());
+            }
+            "document_trashed" | "document_deleted" => { gone.insert(id); }
+            "document_restored" => { gone.remove(&id); }
+            "collection_created" | "collection_updated" => {
+                if !collections.1.contains_key(&id) {
+                    collections.0.push(id.clone());
+                }
+                collections.1.insert(id, data.clone());
+            }
+            "collection_deleted" => { collections.1.remove(&id); }
+            _ => {}
+        }
+    }
+
+    let pick = |(order, mut by_id): (Vec<String>, HashMap<String, Json>), skip: &HashSet<String>| {
+        order.into_iter().filter(|id| !skip.contains(id)).filter_map(|id| by_id.remove(&id)).collect()
+    };
+    Ok(Source {
+        documents:   pick(documents, &gone),
+        collections: pick(collections, &HashSet::new()),
+    })
+}
+
+/// Source id → local id of everything already copied from `source`.
+async fn imported(conn: &Connection, source: &str) -> Result<HashMap<String, String>> {
+    let mut rows = conn.query(
+        "SELECT remote_id, local_id FROM account_imports WHERE source = ?1",
+        libsql::params![source],
+    ).await?;
+    let mut map = HashMap::new();
+    while let Some(row) = rows.next().await? {
+        if let (Value::Text(remote), Value::Text(local)) = (row.get_value(0)?, row.get_value(1)?) {
+            map.insert(remote, local);
+        }
+    }
+    Ok(map)
+}
+
+async fn record(conn: &Connection, source: &str, remote_id: &str, kind: &str, local_id: &str) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO account_imports (source, remote_id, kind, local_id) VALUES (?1, ?2, ?3, ?4)",
+        libsql::params![source, remote_id, kind, local_id],
+    ).await?;
+    Ok(())
+}
+
+/// Create the collections not copied yet, parents first. Returns how many
+/// were created; `done` gains their ids.
+async fn import_collections(
+    conn: &Connection,
+    source: &str,
+    collections: &[Json],
+    done: &mut HashMap<String, String>,
+    dest: &Destination,
+) -> Result<u64> {
+    let ids: HashSet<&str> = collections.iter().filter_map(|c| c["id"].as_str()).collect();
+    let mut pending: Vec<&Json> = collections.iter()
+        .filter(|c| c["id"].as_str().is_some_and(|id| !done.contains_key(id)))
+        .collect();
+
+    let mut created = 0;
+    while !pending.is_empty() {
+        // A parent the source no longer has puts the child at the top level
+        let (ready, waiting): (Vec<&Json>, Vec<&Json>) = pending.into_iter().partition(|c| {
+            c["parent_id"].as_str().is_none_or(|p| done.contains_key(p) || !ids.contains(p))
+        });
+        if ready.is_empty() {
+            anyhow::bail!("Source collection hierarchy has a cycle");
+        }
+        for c in ready {
+            let remote_id = c["id"].as_str().unwrap_or_default();
+            let id        = Uuid::new_v4().to_string();
+            let parent    = c["parent_id"].as_str().and_then(|p| done.get(p)).cloned();
+            conn.execute(
+                "INSERT INTO collections (id, user_id, tenant_id, parent_id, name) VALUES (?1,?2,?3,?4,?5)",
+                libsql::params![
+                    id.as_str(), dest.user_id.as_str(), dest.tenant_id.as_str(),
+                    parent, c["name"].as_str().unwrap_or(""),
+                ],
+            ).await?;
+            ops::enqueue(conn, &dest.user_id, "sync_collection", serde_json::json!({ "collection_id": id })).await?;
+            record(conn, source, remote_id, "collection", &id).await?;
+            done.insert(remote_id.to_string(), id);
+            created += 1;
+        }
+        pending = waiting;
+    }
+    Ok(created)
+}
+
+/// Download a source document into the local store: (path, hash), or None
+/// when it was never uploaded there.
+async fn download(
+    client: &reqwest::Client,
+    dest: &Destination,
+    server_url: &str,
+    token: &str,
+    data: &Json,
+) -> Result<Option<(PathBuf, String)>> {
+    let Some(object_key) = data["object_key"].as_str().filter(|k| !k.is_empty()) else {
+        return Ok(None);
+    };
+    let url_resp: Json = client
+        .post(format!("{server_url}/api/v1/sync/download-url"))
+        .bearer_auth(token)
+        .json(&serde_json::json!({ "doc_id": data["id"], "object_key": object_key }))
+        .send_retrying().await?
+        .error_for_status()?
+        .json().await?;
+    let download_url = url_resp["download_url"].as_str().context("No download_url")?;
+    let bytes = client.get(download_url).send_retrying().await?.error_for_status()?.bytes().await?;
+
+    // Checked before anything is stored, so a bad download leaves no blob behind
+    if let Some(expected) = data["content_hash"].as_str().filter(|h| cas::is_sha256_hex(h)) {
+        if !expected.eq_ignore_ascii_case(&cas::hex(&Sha256::digest(&bytes))) {
+            anyhow::bail!("Downloaded content does not match content_hash");
+        }
+    }
+    Ok(Some(cas::store_bytes(&dest.files_dir, &bytes).await?))
+}
+
+/// Create the local document for a downloaded source document, through the
+/// same pipeline as a file import. Returns the new id.
+async fn ingest(
+    app: &AppHandle,
+    conn: &Connection,
+    dest: &Destination,
+    source: &str,
+    data: &Json,
+    (path, hash): (PathBuf, String),
+    collection: Option<String>,
+) -> Result<String> {
+    let remote_id = data["id"].as_str().unwrap_or_default();
+    let mut metadata = match &data["metadata"] {
+        Json::Object(m) => Json::Object(m.clone()),
+        _               => serde_json::json!({}),
+    };
+    metadata["migrated_from"] = serde_json::json!({ "server": source, "id": remote_id });
+
+    let filename = data["filename"].as_str().unwrap_or("untitled").to_string();
+    let mut input = CreateDocumentInput {
+        content_type:   extract::sniff::detect(&path, data["content_type"].as_str().unwrap_or(""), &filename).await,
+        filename,
+        local_path:     path.to_string_lossy().to_string(),
+        file_size:      tokio::fs::metadata(&path).await?.len() as i64,
+        content_hash:   hash,
+        text_content:   data["text_content"].as_str().filter(|t| !t.is_empty()).map(str::to_string),
+        metadata:       Some(metadata),
+        tags:           serde_json::from_value(data["tags"].clone()).ok(),
+        full_text_path: None,
+    };
+    extract::fill(&mut input).await;
+    extract::media::fill(conn, &mut input).await?;
+    scan::scan(conn, &mut input).await?;
+    text::limit(&dest.files_dir, conn, &mut input).await?;
+
+    let id = Uuid::new_v4().to_string();
+    let tx = conn.transaction().await?;
+    insert_document(&tx, &id, &dest.user_id, &dest.tenant_id, &input).await?;
+    tx.execute(
+        "UPDATE documents SET collection_id = ?1, created_at = COALESCE(?2, created_at) WHERE id = ?3",
+        libsql::params![collection, data["created_at"].as_str(), id.as_str()],
+    ).await?;
+    record(&tx, source, remote_id, "document", &id).await?;
+    ops::enqueue(&tx, &dest.user_id, "upload_document", serde_json::json!({ "doc_id": id })).await?;
+    tx.commit().await?;
+    scan::notify(app, &id, &input);
+    ocr::queue_if_needed(conn, &id, &input).await?;
+    Ok(id)
+}
@@ -1 +1,9 @@
-pub mod engine;
\ No newline at end of file
+pub mod activity;
+pub mod bucket;
+pub mod connectivity;
+pub mod engine;
+pub mod filter;
+pub mod metrics;
+pub mod migrate;
+pub mod shares;
+pub mod throttle;
\ No newline at end of file
@@ -0,0 +1,56 @@
+// src-tauri/src/sync/shares.rs
+// The receiving end of DID shares (sharing.rs). Each sync cycle asks the
+// server what has been shared with the account's DID since the last time:
+//
+// GET /api/v1/shares/incoming?since=<updated_at> → {shares: [...], cursor}
+//
+// and keeps it in shared_with_me (db/did_shares.rs) for the "Shared with me"
+// view. Revoked shares come too and are kept revoked, so the cursor holds.
+// A share new to this device is announced with "shared-document-received".
+// A server without DID shares answers 404 and is left alone.
+use anyhow::Result;
+use serde_json::Value as Json;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::db::{self, accounts, did_shares};
+use crate::http::SendRetrying;
+
+/// The server's page size.
+const PAGE: usize = 500;
+/// Pages fetched per cycle; the rest waits for the next one.
+const MAX_PAGES: usize = 10;
+
+/// Fetch shares with the active account's DID. Returns how many were new.
+pub async fn receive(app: &AppHandle, client: &reqwest::Client, server_url: &str, token: &str) -> Result<usize> {
+    let conn = db::connect(&app.state::<crate::AppState>().db).await?;
+    let Some(account_id) = accounts::active_id(&conn).await? else { return Ok(0) };
+
+    let mut received = 0;
+    for _ in 0..MAX_PAGES {
+        let mut request = client
+            .get(format!("{server_url}/api/v1/shares/incoming"))
+            .bearer_auth(token);
+        if let Some(since) = did_shares::incoming_cursor(&conn, &account_id).await? {
+            request = request.query(&[("since", since)]);
+        }
+        let resp = request.send_retrying().await?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            log::debug!("[sync] Server has no DID shares");
+            return Ok(received);
+        }
+        let body: Json = resp.error_for_status()?.json().await?;
+        let records = body["shares"].as_array().cloned().unwrap_or_default();
+
+        for shared in did_shares::store_incoming(&conn, &account_id, &records).await? {
+            log::info!("[sync] {} shared with this account by {}", shared.doc_id,
+                       shared.owner_did.as_deref().unwrap_or("an unknown DID"));
+            let _ = app.emit("shared-document-received", &shared);
+            received += 1;
+        }
+        // The cursor's second comes back again; a full page means there's more
+        if records.len() < PAGE {
+            break;
+        }
+    }
+    Ok(received)
+}
@@ -0,0 +1,40 @@
+// src-tauri/src/sync/throttle.rs
+// Global bandwidth pacing shared by all upload workers.
+//
+// Each transfer reserves a time slot proportional to its size; concurrent
+// workers queue behind each other so the aggregate rate stays under the cap.
+// Bodies are sent whole once their slot arrives, so the cap holds on average
+// rather than per-packet.
+
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+pub struct BandwidthLimiter {
+    bytes_per_sec: Option<u64>,
+    next_free:     Mutex<Instant>,
+}
+
+impl BandwidthLimiter {
+    /// `limit_kbps` is in kilobits per second; `None` or `Some(0)` disables
+    /// limiting.
+    pub fn new(limit_kbps: Option<u64>) -> Self {
+        Self {
+            bytes_per_sec: limit_kbps.filter(|k| *k > 0).map(|k| (k * 1000 / 8).max(1)),
+            next_free:     Mutex::new(Instant::now()),
+        }
+    }
+
+    pub async fn acquire(&self, bytes: usize) {
+        let Some(rate) = self.bytes_per_sec else { return };
+
+        let start = {
+            let mut next = self.next_free.lock().await;
+            let now      = Instant::now();
+            let start    = (*next).max(now);
+            *next = start + Duration::from_secs_f64(bytes as f64 / rate as f64);
+            start
+        };
+        tokio::time::sleep_until(start).await;
+    }
+}
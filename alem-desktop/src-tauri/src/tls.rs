@@ -0,0 +1,466 @@
+// src-tauri/src/tls.rs
+// TLS for every HTTP client (http.rs): rustls, trusting the system's root
+// certificates plus those of the network settings, and pinning for
+// self-hosted servers.
+//
+// A pinned host is trusted by fingerprint alone. Whatever it presents must
+// match the pin (the whole certificate, or only its public key), and a chain
+// to a trusted root counts for nothing, so a self-signed server can be pinned
+// on first use and a MITM'd connection to a pinned one fails in the
+// handshake, before any request (or token) is sent. Pins are stored in
+// server_pins (db/pins.rs); PINS is the copy the handshake reads.
+use anyhow::{anyhow, bail, Context, Result};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, RootCertStore, SignatureScheme};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex, RwLock};
+use std::time::Duration;
+
+use crate::crypto::base64;
+use crate::db::models::{PinKind, ServerPin};
+
+const INSPECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+static PINS: LazyLock<RwLock<HashMap<String, ServerPin>>> = LazyLock::new(Default::default);
+/// Built by set_roots(); config() builds it with the system's roots alone
+/// if nothing has yet.
+static TRUST: RwLock<Option<Trust>> = RwLock::new(None);
+/// The system's root certificates, read once.
+static NATIVE_ROOTS: LazyLock<Vec<CertificateDer<'static>>> = LazyLock::new(|| {
+    rustls_native_certs::load_native_certs().unwrap_or_else(|e| {
+        log::warn!("[tls] Could not load the system's root certificates: {e}");
+        Vec::new()
+    })
+});
+
+/// A connection refused because the host's certificate doesn't match its pin.
+#[derive(Debug)]
+pub struct PinMismatch {
+    pub host: String,
+}
+
+impl std::fmt::Display for PinMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} presented a certificate that doesn't match its pin", self.host)
+    }
+}
+
+impl std::error::Error for PinMismatch {}
+
+/// What a server presented during inspect().
+pub struct Presented {
+    pub host: String,
+    pub certificate_fingerprint: String,
+    pub public_key_fingerprint: String,
+    /// Chains to a trusted root, i.e. would be accepted without a pin.
+    pub trusted: bool,
+}
+
+/// The rustls config for a new client.
+pub fn config() -> Result<rustls::ClientConfig> {
+    trust(|t| t.config.clone())
+}
+
+/// Trust `extra` besides the system's roots, from the next client on.
+pub fn set_roots(extra: &[CertificateDer<'static>]) -> Result<()> {
+    let mut roots = RootCertStore::empty();
+    roots.add_parsable_certificates(NATIVE_ROOTS.iter().cloned());
+    for (i, cert) in extra.iter().enumerate() {
+        roots.add(cert.clone()).with_context(|| format!("Root certificate {} can't be used", i + 1))?;
+    }
+    let webpki = WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider())
+        .build()
+        .context("No root certificates to trust")?;
+    let config = client_config(Arc::new(Verifier { webpki: Arc::clone(&webpki) }))?;
+    *TRUST.write().unwrap() = Some(Trust { webpki, config });
+    Ok(())
+}
+
+/// Replace the pins the handshake checks.
+pub fn set_pins(pins: Vec<ServerPin>) {
+    *PINS.write().unwrap() = pins.into_iter().map(|p| (p.host.clone(), p)).collect();
+}
+
+/// The certificates in `pem` (one, or a bundle).
+pub fn parse_pem(pem: &str) -> Result<Vec<CertificateDer<'static>>> {
+    let certs = CertificateDer::pem_slice_iter(pem.as_bytes())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| anyhow!("Not valid PEM: {e}"))?;
+    if certs.is_empty() {
+        bail!("No certificate in the PEM text");
+    }
+    Ok(certs)
+}
+
+/// How pins name `url`'s host.
+pub fn host_key(url: &reqwest::Url) -> Option<String> {
+    // IPv6 without its brackets, as the handshake sees it
+    let host = url.host_str()?.trim_start_matches('[').trim_end_matches(']');
+    Some(host.to_ascii_lowercase())
+}
+
+/// A fingerprint as typed or pasted (with or without colons, "sha256:" or
+/// openssl's "SHA256 Fingerprint=") in the form pins store: what it pins
+/// and the fingerprint.
+pub fn normalize(fingerprint: &str) -> Option<(PinKind, String)> {
+    let fingerprint = fingerprint.trim();
+    if let Some(b64) = fingerprint.strip_prefix("sha256/") {
+        let digest = base64::STANDARD.decode(b64).ok()?;
+        return (digest.len() == 32).then(|| (PinKind::PublicKey, key_fingerprint(&digest)));
+    }
+    let hex = fingerprint.rsplit('=').next()?;
+    let hex = hex.strip_prefix("sha256:").or_else(|| hex.strip_prefix("SHA256:")).unwrap_or(hex);
+    let digits: String = hex.chars().filter(|c| *c != ':' && !c.is_whitespace()).collect();
+    if digits.len() != 64 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some((PinKind::Certificate, colon_hex(&digits)))
+}
+
+/// Connect to `url`'s server and report the certificate it presents,
+/// trusted or not. Nothing but a HEAD request is sent.
+pub async fn inspect(url: &reqwest::Url) -> Result<Presented> {
+    if url.scheme() != "https" {
+        bail!("{url} doesn't use https; there is no certificate to pin");
+    }
+    let host = host_key(url).with_context(|| format!("No host in {url}"))?;
+    let webpki = trust(|t| Arc::clone(&t.webpki))?;
+    let capture = Arc::new(Capture { webpki: Arc::clone(&webpki), seen: Mutex::default() });
+    let client = crate::http::client_with_tls(INSPECT_TIMEOUT, client_config(capture.clone())?)?;
+    // Once the handshake is done the answer doesn't matter
+    let result = client.head(url.clone()).send().await;
+
+    let Some((end_entity, intermediates)) = capture.seen.lock().unwrap().take() else {
+        result?;
+        bail!("{host} presented no certificate");
+    };
+    let name = ServerName::try_from(host.clone())?;
+    let trusted = webpki.verify_server_cert(&end_entity, &intermediates, &name, &[], UnixTime::now()).is_ok();
+    Ok(Presented {
+        certificate_fingerprint: certificate_fingerprint(&end_entity),
+        public_key_fingerprint:  public_key_fingerprint(&end_entity)
+            .with_context(|| format!("{host}'s certificate can't be parsed"))?,
+        host,
+        trusted,
+    })
+}
+
+/// Whether the connection behind `e` was refused by a pin.
+pub fn is_pin_mismatch(e: &(dyn std::error::Error + 'static)) -> bool {
+    let mut cause = Some(e);
+    while let Some(e) = cause {
+        if e.is::<PinMismatch>() {
+            return true;
+        }
+        // io::Error's source() skips the error it wraps
+        if let Some(inner) = e.downcast_ref::<std::io::Error>().and_then(|io| io.get_ref()) {
+            if is_pin_mismatch(inner) {
+                return true;
+            }
+        }
+        if let Some(rustls::Error::Other(other)) = e.downcast_ref::<rustls::Error>() {
+            if other.0.is::<PinMismatch>() {
+                return true;
+            }
+        }
+        cause = e.source();
+    }
+    false
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+struct Trust {
+    webpki: Arc<WebPkiServerVerifier>,
+    config: rustls::ClientConfig,
+}
+
+/// A certificate and the intermediates sent with it.
+type Chain = (CertificateDer<'static>, Vec<CertificateDer<'static>>);
+
+fn trust<T>(f: impl Fn(&Trust) -> T) -> Result<T> {
+    if let Some(trust) = TRUST.read().unwrap().as_ref() {
+        return Ok(f(trust));
+    }
+    set_roots(&[])?;
+    trust(f)
+}
+
+/// Pins first, the roots for every other host.
+#[derive(Debug)]
+struct Verifier {
+    webpki: Arc<WebPkiServerVerifier>,
+}
+
+impl ServerCertVerifier for Verifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let host = name_of(server_name);
+        let pin = PINS.read().unwrap().get(&host).cloned();
+        match pin {
+            None => self.webpki.verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now),
+            Some(pin) if matches(&pin, end_entity) => Ok(ServerCertVerified::assertion()),
+            Some(_) => {
+                log::warn!("[tls] {host} presented a certificate that doesn't match its pin; refusing");
+                Err(rustls::Error::Other(rustls::OtherError(Arc::new(PinMismatch { host }))))
+            }
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.webpki.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.webpki.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.webpki.supported_verify_schemes()
+    }
+}
+
+/// Accepts any certificate and keeps it, for inspect(). The handshake
+/// signatures are still checked, so what it keeps is what the server holds
+/// the key of.
+#[derive(Debug)]
+struct Capture {
+    webpki: Arc<WebPkiServerVerifier>,
+    seen:   Mutex<Option<Chain>>,
+}
+
+impl ServerCertVerifier for Capture {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let intermediates = intermediates.iter().map(|c| c.clone().into_owned()).collect();
+        *self.seen.lock().unwrap() = Some((end_entity.clone().into_owned(), intermediates));
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.webpki.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.webpki.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.webpki.supported_verify_schemes()
+    }
+}
+
+/// Whether `pin` accepts the certificate `cert`.
+fn matches(pin: &ServerPin, cert: &[u8]) -> bool {
+    match pin.kind {
+        PinKind::Certificate => certificate_fingerprint(cert) == pin.fingerprint,
+        PinKind::PublicKey   => public_key_fingerprint(cert).is_some_and(|f| f == pin.fingerprint),
+    }
+}
+
+fn provider() -> Arc<CryptoProvider> {
+    Arc::new(rustls::crypto::ring::default_provider())
+}
+
+fn client_config(verifier: Arc<dyn ServerCertVerifier>) -> Result<rustls::ClientConfig> {
+    Ok(rustls::ClientConfig::builder_with_provider(provider())
+        .with_safe_default_protocol_versions()?
+        .dangerous()
+        .with_custom_certificate_verifier(verifier)
+        .with_no_client_auth())
+}
+
+/// The host as pins name it (host_key()).
+fn name_of(server_name: &ServerName<'_>) -> String {
+    match server_name {
+        ServerName::DnsName(name) => name.as_ref().to_ascii_lowercase(),
+        ServerName::IpAddress(ip) => std::net::IpAddr::from(*ip).to_string(),
+        _                         => String::new(),
+    }
+}
+
+/// SHA-256 of the certificate, as colon-separated upper-case hex (how
+/// browsers and openssl show it).
+fn certificate_fingerprint(cert: &[u8]) -> String {
+    colon_hex(&crate::storage::cas::hex(&Sha256::digest(cert)))
+}
+
+/// "sha256/" and the base64 SHA-256 of the SubjectPublicKeyInfo (the form
+/// curl's --pinnedpubkey takes).
+fn public_key_fingerprint(cert: &[u8]) -> Option<String> {
+    Some(key_fingerprint(&Sha256::digest(spki(cert)?)))
+}
+
+fn key_fingerprint(digest: &[u8]) -> String {
+    format!("sha256/{}", base64::STANDARD.encode(digest))
+}
+
+fn colon_hex(digits: &str) -> String {
+    digits.as_bytes()
+        .chunks(2)
+        .map(|pair| std::str::from_utf8(pair).unwrap_or_default().to_ascii_uppercase())
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// The DER SubjectPublicKeyInfo of certificate `cert`: the seventh element
+/// of tbsCertificate once the optional [0] version is skipped.
+///
+///   Certificate ::= SEQUENCE { tbsCertificate SEQUENCE { [0] version OPTIONAL,
+///       serialNumber, signature, issuer, validity, subject,
+///       subjectPublicKeyInfo, … }, … }
+fn spki(cert: &[u8]) -> Option<&[u8]> {
+    let (0x30, certificate, _) = der(cert)? else { return None };
+    let (0x30, tbs, _) = der(content(certificate)?)? else { return None };
+    let mut rest = content(tbs)?;
+    if rest.first() == Some(&0xa0) {
+        rest = der(rest)?.2;
+    }
+    // serialNumber, signature, issuer, validity, subject
+    for _ in 0..5 {
+        rest = der(rest)?.2;
+    }
+    match der(rest)? {
+        (0x30, spki, _) => Some(spki),
+        _ => None,
+    }
+}
+
+/// The first DER element of `input`: its tag, the whole element (header
+/// included) and what follows it.
+fn der(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = input.split_first()?;
+    let (&first, rest) = rest.split_first()?;
+    let (len, header) = if first < 0x80 {
+        (first as usize, 2)
+    } else {
+        let n = (first & 0x7f) as usize;
+        if n == 0 || n > 4 || rest.len() < n {
+            return None;
+        }
+        (rest[..n].iter().fold(0usize, |len, b| len << 8 | *b as usize), 2 + n)
+    };
+    let end = header.checked_add(len).filter(|end| *end <= input.len())?;
+    Some((tag, &input[..end], &input[end..]))
+}
+
+/// The content of DER element `element`, without its header.
+fn content(element: &[u8]) -> Option<&[u8]> {
+    let header = if element.get(1)? & 0x80 == 0 { 2 } else { 2 + (element[1] & 0x7f) as usize };
+    element.get(header..)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Self-signed P-256 certificate for "pin.test". The fingerprints are
+    /// openssl's:
+    ///   openssl x509 -pubkey -noout | openssl pkey -pubin -outform der | openssl dgst -sha256 -binary | base64
+    ///   openssl x509 -noout -fingerprint -sha256
+    const CERT: &str = "\
+        MIIBfTCCASOgAwIBAgIULYb8Wl1b6Drhmzp1/j/EkitnqVowCgYIKoZIzj0EAwIw\
+        EzERMA8GA1UEAwwIcGluLnRlc3QwIBcNMjYxMDE2MTgzOTI1WhgPMjEyNjA5MjIx\
+        ODM5MjVaMBMxETAPBgNVBAMMCHBpbi50ZXN0MFkwEwYHKoZIzj0CAQYIKoZIzj0D\
+        AQcDQgAEvg5AajcQKah9eZi8FcvEaX6eKbxkY5T9Tuosf3C7Ir4FBmRNAVcHZgvM\
+        h5JPQL/lL4a6TopL2pu0vEyu2fh4aaNTMFEwHQYDVR0OBBYEFIzfEHCfPADT2v2R\
+        8kxJdUJd3hVGMB8GA1UdIwQYMBaAFIzfEHCfPADT2v2R8kxJdUJd3hVGMA8GA1Ud\
+        EwEB/wQFMAMBAf8wCgYIKoZIzj0EAwIDSAAwRQIhAM/odMeHVoqiZe5WfoVfD+AZ\
+        cQVb4OzKJKM7D61SGQ5wAiAUu5HrdGcE/FaX0aHVjw8yWlEOQVf+fiFP0AmJ/4x/\
+        jQ==";
+    const SPKI_PIN: &str = "sha256/+8860q9yIt6OMYNqXb3lc/lZMo3Xw13es3SZ3Kx4sek=";
+    const CERT_PIN: &str =
+        "78:63:47:66:C9:F7:D3:DD:6D:62:DE:0B:1A:BC:C3:52:02:10:5A:07:16:22:F1:5D:5C:20:60:DB:5F:B9:93:C7";
+
+    fn cert() -> Vec<u8> {
+        base64::STANDARD.decode(CERT).unwrap()
+    }
+
+    #[test]
+    fn fingerprints_match_openssl() {
+        let cert = cert();
+        assert_eq!(public_key_fingerprint(&cert).as_deref(), Some(SPKI_PIN));
+        assert_eq!(certificate_fingerprint(&cert), CERT_PIN);
+    }
+
+    #[test]
+    fn spki_is_the_subject_public_key_info() {
+        let cert = cert();
+        let spki = spki(&cert).unwrap();
+        // SEQUENCE { SEQUENCE { id-ecPublicKey, prime256v1 }, BIT STRING (65-byte point) }
+        assert_eq!(spki.len(), 91);
+        assert_eq!(&spki[..4], &[0x30, 0x59, 0x30, 0x13]);
+    }
+
+    #[test]
+    fn truncated_certificates_have_no_spki() {
+        let cert = cert();
+        for len in 0..cert.len() {
+            assert_eq!(spki(&cert[..len]), None, "prefix of {len} bytes");
+        }
+    }
+
+    #[test]
+    fn der_lengths() {
+        // Short form, and what follows
+        assert_eq!(der(&[0x04, 0x01, 0xaa, 0xbb]), Some((0x04, &[0x04, 0x01, 0xaa][..], &[0xbb][..])));
+        // Long form
+        let mut long = vec![0x04, 0x81, 0x80];
+        long.extend([0u8; 0x80]);
+        assert_eq!(der(&long).map(|(tag, element, rest)| (tag, element.len(), rest.len())), Some((0x04, 131, 0)));
+        // Longer than the input
+        assert_eq!(der(&[0x04, 0x02, 0xaa]), None);
+        assert_eq!(der(&[0x04, 0x82, 0x01]), None);
+        // Indefinite length, and length fields over four bytes
+        assert_eq!(der(&[0x30, 0x80, 0x00, 0x00]), None);
+        assert_eq!(der(&[0x04, 0x85, 0, 0, 0, 0, 1, 0xaa]), None);
+        // Header cut short
+        assert_eq!(der(&[]), None);
+        assert_eq!(der(&[0x04]), None);
+    }
+
+    #[test]
+    fn content_skips_the_header() {
+        assert_eq!(content(&[0x04, 0x02, 0xaa, 0xbb]), Some(&[0xaa, 0xbb][..]));
+        assert_eq!(content(&[0x04, 0x81, 0x01, 0xaa]), Some(&[0xaa][..]));
+        assert_eq!(content(&[0x04]), None);
+        assert_eq!(content(&[0x04, 0x82, 0x01]), None);
+    }
+}
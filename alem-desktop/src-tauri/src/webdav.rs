@@ -0,0 +1,1072 @@
+// src-tauri/src/webdav.rs
+// The library as a WebDAV share on localhost, so other apps (Finder,
+// Explorer, office suites) can open and save documents in place. The
+// current tenant's collections are folders and their live documents files:
+//
+//   http://127.0.0.1:<port>/<token>/Collection/Subcollection/report.pdf
+//
+// Only 127.0.0.1 is listened on. Other programs on the machine can still
+// reach the port, so the share sits under a token made when it's first
+// turned on, and a Host naming anything else (a web page rebinding its name
+// to 127.0.0.1) is turned away.
+//
+// Saving a file gives its document new content as an edit in a mirrored
+// folder does (mirror.rs): a new local_version, queued for upload. A new file
+// is imported into the folder's collection, MKCOL makes a collection, MOVE
+// renames or moves a document or collection, and DELETE trashes a document
+// (folders aren't deleted from here). Content not stored here is downloaded
+// when a file is opened.
+//
+// Hidden files, Office lock files and partial saves aren't documents: they're
+// scratch files, kept while the server runs. Apps that save by moving the
+// original aside to such a name, then moving their temp file into its place,
+// leave the document where it was with the new content (see PARKED).
+//
+// HTTP/1.1 and WebDAV class 1 and 2, as far as those clients need: LOCK is
+// granted to anyone and enforces nothing, PROPPATCH is acknowledged and
+// dropped, COPY isn't supported. Turned on with update_webdav_settings and
+// started at launch while on.
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use libsql::Connection;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, Url};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::commands::import::{self, ImportOptions};
+use crate::db::{self, identity, models::{Columns, WebDavSettings, WebDavStatus}, ops, settings, trash};
+use crate::mirror;
+use crate::storage::{cas, compress};
+
+/// Longest request line and headers.
+const MAX_HEAD: usize = 64 * 1024;
+/// Largest body of anything but a PUT (PROPFIND, LOCK and PROPPATCH XML,
+/// read and ignored).
+const MAX_XML_BODY: u64 = 1024 * 1024;
+/// Largest file saved through the share.
+const MAX_UPLOAD: u64 = 4 * 1024 * 1024 * 1024;
+/// A kept-alive connection with nothing more to ask is closed after this.
+const IDLE: Duration = Duration::from_secs(60);
+/// How long a document moved aside waits for a file to take its place.
+const PARK_FOR: Duration = Duration::from_secs(5 * 60);
+const ALLOW: &str = "OPTIONS, PROPFIND, PROPPATCH, GET, HEAD, PUT, DELETE, MKCOL, MOVE, LOCK, UNLOCK";
+const DOC_COLUMNS: &str =
+    "id, filename, content_type, file_size, content_hash, local_path, needs_download, created_at, updated_at";
+
+/// The running share: its URL, and the task taking connections.
+struct Server {
+    url:  String,
+    task: tauri::async_runtime::JoinHandle<()>,
+}
+
+static SERVER: Mutex<Option<Server>> = Mutex::new(None);
+/// Scratch files, by their path in the share (segments joined with '/').
+static SCRATCH: LazyLock<Mutex<HashMap<String, PathBuf>>> = LazyLock::new(Default::default);
+/// Documents an app moved aside to a scratch name while saving over them:
+/// by the path they left, the document and when. The document keeps its
+/// name and content, hidden from that path, until a file is moved or written
+/// there (and becomes its content) or PARK_FOR passes.
+static PARKED: LazyLock<Mutex<HashMap<String, (String, Instant)>>> = LazyLock::new(Default::default);
+
+/// What every connection shares.
+struct Share {
+    app:     AppHandle,
+    token:   String,
+    port:    u16,
+    /// Where scratch files and uploads in progress are kept.
+    scratch: PathBuf,
+}
+
+/// One request's view of the library: the current tenant's, as its user.
+struct Session<'a> {
+    share:     &'a Share,
+    conn:      &'a Connection,
+    user_id:   String,
+    tenant_id: String,
+}
+
+/// What a path in the share names.
+enum Node {
+    /// A collection; None is the share's root.
+    Folder { id: Option<String>, updated_at: Option<String> },
+    File(DocFile),
+    Scratch(PathBuf),
+}
+
+/// A live document, as a file.
+struct DocFile {
+    id:             String,
+    filename:       String,
+    content_type:   String,
+    size:           u64,
+    content_hash:   String,
+    local_path:     Option<String>,
+    needs_download: bool,
+    created_at:     Option<String>,
+    updated_at:     Option<String>,
+}
+
+struct Request {
+    method:  String,
+    target:  String,
+    /// By lowercased name.
+    headers: HashMap<String, String>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+}
+
+enum Body {
+    Empty,
+    Bytes(Vec<u8>),
+    File(PathBuf),
+    /// The length of content not sent (HEAD).
+    Length(u64),
+}
+
+struct Response {
+    status:  &'static str,
+    headers: Vec<(&'static str, String)>,
+    body:    Body,
+}
+
+impl Response {
+    fn new(status: &'static str) -> Self {
+        Self { status, headers: Vec::new(), body: Body::Empty }
+    }
+
+    fn xml(status: &'static str, xml: String) -> Self {
+        Self::new(status).header("Content-Type", "application/xml; charset=utf-8").body(Body::Bytes(xml.into_bytes()))
+    }
+
+    fn header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    fn body(mut self, body: Body) -> Self {
+        self.body = body;
+        self
+    }
+}
+
+/// Start the share at launch when it's turned on.
+pub async fn start(app: AppHandle) {
+    let result = async {
+        let conn = db::connect(&app.state::<crate::AppState>().db).await?;
+        let cfg: WebDavSettings = settings::get(&conn, settings::WEBDAV_SETTINGS).await?.unwrap_or_default();
+        if cfg.enabled && !cfg.token.is_empty() {
+            serve(&app, &cfg).await?;
+        }
+        anyhow::Ok(())
+    }.await;
+    if let Err(e) = result {
+        log::warn!("[webdav] Share not started: {e:#}");
+    }
+}
+
+/// Keep `cfg` (but the token, made here the first time) and start, restart
+/// or stop the share to match. Nothing is kept when the share can't start.
+pub async fn configure(app: &AppHandle, mut cfg: WebDavSettings) -> Result<WebDavStatus> {
+    let conn = db::connect(&app.state::<crate::AppState>().db).await?;
+    let current: WebDavSettings = settings::get(&conn, settings::WEBDAV_SETTINGS).await?.unwrap_or_default();
+    cfg.token = if current.token.is_empty() { uuid::Uuid::new_v4().simple().to_string() } else { current.token };
+    stop();
+    if cfg.enabled {
+        serve(app, &cfg).await?;
+    }
+    settings::set(&conn, settings::WEBDAV_SETTINGS, &cfg).await?;
+    Ok(status())
+}
+
+pub fn status() -> WebDavStatus {
+    let server = SERVER.lock().unwrap();
+    WebDavStatus {
+        running: server.is_some(),
+        url:     server.as_ref().map(|s| s.url.clone()),
+    }
+}
+
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
+async fn serve(app: &AppHandle, cfg: &WebDavSettings) -> Result<()> {
+    stop();
+    let listener = TcpListener::bind(("127.0.0.1", cfg.port)).await
+        .with_context(|| format!("Cannot listen on port {}", cfg.port))?;
+    let port = listener.local_addr()?.port();
+
+    // Scratch files don't outlive the server
+    let scratch = app.path().app_data_dir()?.join("webdav");
+    let _ = tokio::fs::remove_dir_all(&scratch).await;
+    tokio::fs::create_dir_all(&scratch).await
+        .with_context(|| format!("Cannot create {}", scratch.display()))?;
+    SCRATCH.lock().unwrap().clear();
+    PARKED.lock().unwrap().clear();
+
+    let share = Arc::new(Share { app: app.clone(), token: cfg.token.clone(), port, scratch });
+    let task = tauri::async_runtime::spawn(accept(listener, share));
+    log::info!("[webdav] Sharing the library on port {port}");
+    *SERVER.lock().unwrap() = Some(Server { url: format!("http://127.0.0.1:{port}/{}/", cfg.token), task });
+    Ok(())
+}
+
+fn stop() {
+    if let Some(server) = SERVER.lock().unwrap().take() {
+        server.task.abort();
+        log::info!("[webdav] Share stopped");
+    }
+}
+
+async fn accept(listener: TcpListener, share: Arc<Share>) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let share = Arc::clone(&share);
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = connection(&share, stream).await {
+                        log::debug!("[webdav] Connection dropped: {e:#}");
+                    }
+                });
+            }
+            Err(e) => {
+                log::warn!("[webdav] Accept failed: {e}");
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        }
+    }
+}
+
+/// Answer requests on one connection until it's closed or idle.
+async fn connection(share: &Share, stream: TcpStream) -> Result<()> {
+    let (read, mut write) = stream.into_split();
+    let mut reader = BufReader::new(read);
+    loop {
+        let request = match tokio::time::timeout(IDLE, read_head(&mut reader)).await {
+            Ok(head) => match head? {
+                Some(request) => request,
+                None => return Ok(()),
+            },
+            Err(_) => return Ok(()),
+        };
+        // Turned away before any of the body is asked for or read; the
+        // connection can't be reused after an unread body
+        let segs = match admit(&share.token, share.port, &request) {
+            Ok(segs) => segs,
+            Err(response) => return respond(&mut write, response, request.method == "HEAD", true).await,
+        };
+        let close = request.header("connection").is_some_and(|c| c.eq_ignore_ascii_case("close"));
+        if request.header("expect").is_some_and(|e| e.eq_ignore_ascii_case("100-continue")) {
+            write.write_all(b"HTTP/1.1 100 Continue\r\n\r\n").await?;
+        }
+
+        // Once admitted, the body is read whatever the answer, so the next
+        // request starts where it should
+        let upload = share.scratch.join(format!(".upload-{}", uuid::Uuid::new_v4()));
+        if request.method == "PUT" {
+            let mut file = tokio::fs::File::create(&upload).await?;
+            read_body(&mut reader, &request, &mut file, MAX_UPLOAD).await?;
+            file.flush().await?;
+        } else {
+            read_body(&mut reader, &request, &mut tokio::io::sink(), MAX_XML_BODY).await?;
+        }
+
+        let response = match handle(share, &request, segs, &upload).await {
+            Ok(response) => response,
+            Err(e) => {
+                log::warn!("[webdav] {} {} failed: {e:#}", request.method, request.target);
+                Response::new("500 Internal Server Error")
+            }
+        };
+        let _ = tokio::fs::remove_file(&upload).await;
+        respond(&mut write, response, request.method == "HEAD", close).await?;
+        if close {
+            return Ok(());
+        }
+    }
+}
+
+/// The request's path in the share, if it may be answered at all: None only
+/// for OPTIONS outside the share, which is asked of the server's root too.
+fn admit(token: &str, port: u16, req: &Request) -> Result<Option<Vec<String>>, Response> {
+    let host = req.header("host").unwrap_or_default();
+    if host != format!("127.0.0.1:{port}") && host != format!("localhost:{port}") {
+        return Err(Response::new("403 Forbidden"));
+    }
+    match segments(token, &req.target) {
+        Some(segs) => Ok(Some(segs)),
+        None if req.method == "OPTIONS" => Ok(None),
+        None => Err(Response::new("404 Not Found")),
+    }
+}
+
+async fn handle(share: &Share, req: &Request, segs: Option<Vec<String>>, upload: &Path) -> Result<Response> {
+    if req.method == "OPTIONS" {
+        return Ok(Response::new("200 OK")
+            .header("DAV", "1, 2")
+            .header("MS-Author-Via", "DAV")
+            .header("Allow", ALLOW));
+    }
+    let Some(segs) = segs else {
+        return Ok(Response::new("404 Not Found"));
+    };
+
+    let conn = db::connect(&share.app.state::<crate::AppState>().db).await?;
+    let (user_id, tenant_id) = identity::current(&conn).await?;
+    let s = Session { share, conn: &conn, user_id, tenant_id };
+    match req.method.as_str() {
+        "PROPFIND"    => propfind(&s, req, &segs).await,
+        "GET"         => get(&s, &segs, false).await,
+        "HEAD"        => get(&s, &segs, true).await,
+        "PUT"         => put(&s, &segs, upload).await,
+        "DELETE"      => delete(&s, &segs).await,
+        "MKCOL"       => mkcol(&s, &segs).await,
+        "MOVE"        => move_to(&s, req, &segs).await,
+        "LOCK"        => Ok(lock(req, &share.href(&segs, false))),
+        "UNLOCK"      => Ok(Response::new("204 No Content")),
+        "PROPPATCH"   => Ok(proppatch(&share.href(&segs, false))),
+        _             => Ok(Response::new("405 Method Not Allowed").header("Allow", ALLOW)),
+    }
+}
+
+async fn propfind(s: &Session<'_>, req: &Request, segs: &[String]) -> Result<Response> {
+    let Some(node) = resolve(s, segs).await? else {
+        return Ok(Response::new("404 Not Found"));
+    };
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<D:multistatus xmlns:D=\"DAV:\">\n");
+    let name = segs.last().map(String::as_str).unwrap_or_default();
+    entry(&mut xml, &s.share.href(segs, node.is_folder()), name, &node).await;
+    // Depth: infinity is answered as 1
+    if let Node::Folder { id, .. } = &node {
+        if req.header("depth") != Some("0") {
+            for (name, child) in children(s, segs, id.as_deref()).await? {
+                let path = [segs, std::slice::from_ref(&name)].concat();
+                entry(&mut xml, &s.share.href(&path, child.is_folder()), &name, &child).await;
+            }
+        }
+    }
+    xml.push_str("</D:multistatus>\n");
+    Ok(Response::xml("207 Multi-Status", xml))
+}
+
+async fn get(s: &Session<'_>, segs: &[String], head: bool) -> Result<Response> {
+    match resolve(s, segs).await? {
+        None => Ok(Response::new("404 Not Found")),
+        Some(Node::Folder { .. }) => Ok(Response::new("405 Method Not Allowed").header("Allow", ALLOW)),
+        Some(Node::Scratch(path)) => Ok(Response::new("200 OK")
+            .header("Content-Type", "application/octet-stream")
+            .body(Body::File(path))),
+        Some(Node::File(doc)) => {
+            let body = if head { Body::Length(doc.size) } else { Body::File(content(&s.share.app, &doc).await?) };
+            let mut response = Response::new("200 OK")
+                .header("Content-Type", doc.content_type.clone())
+                .header("ETag", format!("\"{}\"", doc.content_hash))
+                .body(body);
+            if let Some(at) = doc.updated_at.as_deref().and_then(parse_time) {
+                response = response.header("Last-Modified", http_date(at));
+            }
+            Ok(response)
+        }
+    }
+}
+
+async fn put(s: &Session<'_>, segs: &[String], upload: &Path) -> Result<Response> {
+    let Some(name) = segs.last() else {
+        return Ok(Response::new("405 Method Not Allowed").header("Allow", ALLOW));
+    };
+    let key = segs.join("/");
+    if mirror::ignored(name) {
+        let replaced = keep_scratch(s.share, &key, upload).await?;
+        return Ok(Response::new(if replaced { "204 No Content" } else { "201 Created" }));
+    }
+    match resolve_target(s, segs).await? {
+        Some(Node::File(doc)) => {
+            save(s, &doc, upload).await?;
+            unpark(&key);
+            Ok(Response::new("204 No Content"))
+        }
+        Some(_) => Ok(Response::new("405 Method Not Allowed").header("Allow", ALLOW)),
+        None => match parent(s, segs).await? {
+            Some(collection_id) => {
+                import(s, name, collection_id, upload).await?;
+                Ok(Response::new("201 Created"))
+            }
+            None => Ok(Response::new("409 Conflict")),
+        },
+    }
+}
+
+async fn delete(s: &Session<'_>, segs: &[String]) -> Result<Response> {
+    match resolve(s, segs).await? {
+        None => Ok(Response::new("404 Not Found")),
+        Some(Node::Scratch(_)) => {
+            drop_scratch(&segs.join("/")).await;
+            Ok(Response::new("204 No Content"))
+        }
+        Some(Node::File(doc)) => {
+            trash::trash(s.conn, &s.user_id, &doc.id).await?;
+            log::info!("[webdav] Trashed {}", doc.id);
+            Ok(Response::new("204 No Content"))
+        }
+        // A whole collection is one keystroke away in a file manager
+        Some(Node::Folder { .. }) => Ok(Response::new("403 Forbidden")),
+    }
+}
+
+async fn mkcol(s: &Session<'_>, segs: &[String]) -> Result<Response> {
+    let Some(name) = segs.last() else {
+        return Ok(Response::new("405 Method Not Allowed").header("Allow", ALLOW));
+    };
+    if resolve(s, segs).await?.is_some() {
+        return Ok(Response::new("405 Method Not Allowed").header("Allow", ALLOW));
+    }
+    if mirror::ignored(name) {
+        return Ok(Response::new("403 Forbidden"));
+    }
+    let Some(parent_id) = parent(s, segs).await? else {
+        return Ok(Response::new("409 Conflict"));
+    };
+    let id = uuid::Uuid::new_v4().to_string();
+    s.conn.execute(
+        "INSERT INTO collections (id, user_id, tenant_id, parent_id, name) VALUES (?1, ?2, ?3, ?4, ?5)",
+        libsql::params![id.as_str(), s.user_id.as_str(), s.tenant_id.as_str(), parent_id, name.as_str()],
+    ).await?;
+    ops::enqueue(s.conn, &s.user_id, "sync_collection", json!({ "collection_id": id })).await?;
+    Ok(Response::new("201 Created"))
+}
+
+async fn move_to(s: &Session<'_>, req: &Request, segs: &[String]) -> Result<Response> {
+    let Some(dest) = req.header("destination").and_then(|d| s.share.segments(d)) else {
+        return Ok(Response::new("400 Bad Request"));
+    };
+    let (Some(name), Some(_)) = (dest.last(), segs.last()) else {
+        return Ok(Response::new("403 Forbidden"));
+    };
+    let Some(node) = resolve(s, segs).await? else {
+        return Ok(Response::new("404 Not Found"));
+    };
+    let Some(parent_id) = parent(s, &dest).await? else {
+        return Ok(Response::new("409 Conflict"));
+    };
+    let (from, to) = (segs.join("/"), dest.join("/"));
+    if from == to {
+        return Ok(Response::new("403 Forbidden"));
+    }
+    let existing = resolve_target(s, &dest).await?;
+    if existing.is_some() && req.header("overwrite") == Some("F") {
+        return Ok(Response::new("412 Precondition Failed"));
+    }
+    let status = if existing.is_some() { "204 No Content" } else { "201 Created" };
+
+    match node {
+        Node::Scratch(path) if mirror::ignored(name) => {
+            keep_scratch(s.share, &to, &path).await?;
+            SCRATCH.lock().unwrap().remove(&from);
+        }
+        // An app's save: its temp file moved into place
+        Node::Scratch(path) => {
+            match existing {
+                Some(Node::File(doc)) => save(s, &doc, &path).await?,
+                Some(_) => return Ok(Response::new("409 Conflict")),
+                None => {
+                    import(s, name, parent_id, &path).await?;
+                }
+            }
+            drop_scratch(&from).await;
+            unpark(&to);
+        }
+        // The original moved aside while an app saves over it
+        Node::File(doc) if mirror::ignored(name) => {
+            let copy = s.share.scratch.join(format!(".upload-{}", uuid::Uuid::new_v4()));
+            tokio::fs::copy(content(&s.share.app, &doc).await?, &copy).await?;
+            keep_scratch(s.share, &to, &copy).await?;
+            PARKED.lock().unwrap().insert(from, (doc.id.clone(), Instant::now()));
+        }
+        Node::File(doc) => {
+            match &existing {
+                Some(Node::File(other)) if other.id != doc.id => {
+                    trash::trash(s.conn, &s.user_id, &other.id).await?;
+                }
+                Some(Node::Folder { .. }) => return Ok(Response::new("409 Conflict")),
+                _ => {}
+            }
+            rename(s, &doc, name, parent_id).await?;
+        }
+        Node::Folder { id: Some(id), .. } => {
+            if existing.is_some() || mirror::ignored(name) || dest.starts_with(segs) {
+                return Ok(Response::new("403 Forbidden"));
+            }
+            s.conn.execute(
+                "UPDATE collections SET name = ?2, parent_id = ?3, updated_at = datetime('now') WHERE id = ?1",
+                libsql::params![id.as_str(), name.as_str(), parent_id],
+            ).await?;
+            ops::enqueue(s.conn, &s.user_id, "sync_collection", json!({ "collection_id": id })).await?;
+        }
+        Node::Folder { id: None, .. } => return Ok(Response::new("403 Forbidden")),
+    }
+    Ok(Response::new(status))
+}
+
+/// A write lock for whoever asks. Apps that won't save without one get one;
+/// nobody is kept out by it.
+fn lock(req: &Request, href: &str) -> Response {
+    // A refresh names its lock in If: (<token>)
+    let token = req.header("if")
+        .and_then(|h| h.split_once('<'))
+        .and_then(|(_, rest)| rest.split_once('>'))
+        .map(|(token, _)| token.to_string())
+        .unwrap_or_else(|| format!("opaquelocktoken:{}", uuid::Uuid::new_v4()));
+    let xml = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <D:prop xmlns:D=\"DAV:\"><D:lockdiscovery><D:activelock>\
+         <D:locktype><D:write/></D:locktype><D:lockscope><D:exclusive/></D:lockscope>\
+         <D:depth>0</D:depth><D:timeout>Second-3600</D:timeout>\
+         <D:locktoken><D:href>{}</D:href></D:locktoken>\
+         <D:lockroot><D:href>{}</D:href></D:lockroot>\
+         </D:activelock></D:lockdiscovery></D:prop>\n",
+        escape(&token),
+        escape(href),
+    );
+    Response::xml("200 OK", xml).header("Lock-Token", format!("<{token}>"))
+}
+
+fn proppatch(href: &str) -> Response {
+    let xml = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <D:multistatus xmlns:D=\"DAV:\"><D:response><D:href>{}</D:href>\
+         <D:propstat><D:prop/><D:status>HTTP/1.1 200 OK</D:status></D:propstat>\
+         </D:response></D:multistatus>\n",
+        escape(href),
+    );
+    Response::xml("207 Multi-Status", xml)
+}
+
+/// One <D:response> of a PROPFIND.
+async fn entry(xml: &mut String, href: &str, name: &str, node: &Node) {
+    let mut props = format!("<D:displayname>{}</D:displayname>", escape(name));
+    let (modified, created) = match node {
+        Node::Folder { updated_at, .. } => {
+            props.push_str("<D:resourcetype><D:collection/></D:resourcetype>");
+            (updated_at.as_deref().and_then(parse_time), None)
+        }
+        Node::File(doc) => {
+            let _ = write!(
+                props,
+                "<D:resourcetype/><D:getcontentlength>{}</D:getcontentlength>\
+                 <D:getcontenttype>{}</D:getcontenttype><D:getetag>\"{}\"</D:getetag>",
+                doc.size,
+                escape(&doc.content_type),
+                escape(&doc.content_hash),
+            );
+            (doc.updated_at.as_deref().and_then(parse_time), doc.created_at.as_deref().and_then(parse_time))
+        }
+        Node::Scratch(path) => {
+            let meta = tokio::fs::metadata(path).await.ok();
+            let _ = write!(
+                props,
+                "<D:resourcetype/><D:getcontentlength>{}</D:getcontentlength>",
+                meta.as_ref().map_or(0, |m| m.len()),
+            );
+            (meta.and_then(|m| m.modified().ok()).map(DateTime::<Utc>::from), None)
+        }
+    };
+    if let Some(at) = modified {
+        let _ = write!(props, "<D:getlastmodified>{}</D:getlastmodified>", http_date(at));
+    }
+    if let Some(at) = created {
+        let _ = write!(props, "<D:creationdate>{}</D:creationdate>", at.to_rfc3339());
+    }
+    props.push_str(
+        "<D:supportedlock><D:lockentry><D:lockscope><D:exclusive/></D:lockscope>\
+         <D:locktype><D:write/></D:locktype></D:lockentry></D:supportedlock>",
+    );
+    let _ = writeln!(
+        xml,
+        "<D:response><D:href>{}</D:href><D:propstat><D:prop>{props}</D:prop>\
+         <D:status>HTTP/1.1 200 OK</D:status></D:propstat></D:response>",
+        escape(href),
+    );
+}
+
+/// What's in the folder at `segs` (collection `collection_id`, None for the
+/// root), by name: its collections, its documents and the scratch files put
+/// there. Names are as a mirrored folder has them.
+async fn children(s: &Session<'_>, segs: &[String], collection_id: Option<&str>) -> Result<Vec<(String, Node)>> {
+    let mut taken = HashSet::new();
+    let mut out = Vec::new();
+
+    let mut rows = s.conn.query(
+        "SELECT id, name, updated_at FROM collections WHERE tenant_id = ?1 AND parent_id IS ?2 ORDER BY created_at, id",
+        libsql::params![s.tenant_id.as_str(), collection_id.map(str::to_string)],
+    ).await?;
+    while let Some(row) = rows.next().await? {
+        let c = Columns::new(&row);
+        let name = mirror::unique_name(&c.str("name").unwrap_or_default(), &mut taken);
+        out.push((name, Node::Folder { id: Some(c.required("id")?), updated_at: c.str("updated_at") }));
+    }
+
+    let parked = parked_ids();
+    let mut rows = s.conn.query(
+        &format!("SELECT {DOC_COLUMNS} FROM documents
+                  WHERE tenant_id = ?1 AND collection_id IS ?2 AND status != 'deleted' AND trashed_at IS NULL
+                  ORDER BY created_at, id"),
+        libsql::params![s.tenant_id.as_str(), collection_id.map(str::to_string)],
+    ).await?;
+    while let Some(row) = rows.next().await? {
+        let doc = row_to_file(&row)?;
+        if !parked.contains(&doc.id) {
+            out.push((mirror::unique_name(&doc.filename, &mut taken), Node::File(doc)));
+        }
+    }
+
+    let dir = segs.join("/");
+    for (key, path) in SCRATCH.lock().unwrap().iter() {
+        let (parent, name) = key.rsplit_once('/').unwrap_or(("", key.as_str()));
+        if parent == dir && taken.insert(name.to_string()) {
+            out.push((name.to_string(), Node::Scratch(path.clone())));
+        }
+    }
+    Ok(out)
+}
+
+/// What `segs` names, if anything.
+async fn resolve(s: &Session<'_>, segs: &[String]) -> Result<Option<Node>> {
+    if let Some(path) = SCRATCH.lock().unwrap().get(&segs.join("/")) {
+        return Ok(Some(Node::Scratch(path.clone())));
+    }
+    let mut node = Node::Folder { id: None, updated_at: None };
+    for (i, seg) in segs.iter().enumerate() {
+        let Node::Folder { id, .. } = &node else { return Ok(None) };
+        let found = children(s, &segs[..i], id.as_deref()).await?.into_iter().find(|(name, _)| name == seg);
+        match found {
+            Some((_, child)) => node = child,
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(node))
+}
+
+/// Like resolve, with a document moved aside from `segs` still there.
+async fn resolve_target(s: &Session<'_>, segs: &[String]) -> Result<Option<Node>> {
+    if let Some(id) = parked_at(&segs.join("/")) {
+        let mut rows = s.conn.query(
+            &format!("SELECT {DOC_COLUMNS} FROM documents WHERE id = ?1 AND status != 'deleted' AND trashed_at IS NULL"),
+            libsql::params![id],
+        ).await?;
+        if let Some(row) = rows.next().await? {
+            return Ok(Some(Node::File(row_to_file(&row)?)));
+        }
+    }
+    resolve(s, segs).await
+}
+
+/// The collection of the folder `segs` is in (None: the root), or None when
+/// that isn't a folder.
+async fn parent(s: &Session<'_>, segs: &[String]) -> Result<Option<Option<String>>> {
+    let Some((_, dir)) = segs.split_last() else { return Ok(None) };
+    Ok(match resolve(s, dir).await? {
+        Some(Node::Folder { id, .. }) => Some(id),
+        _ => None,
+    })
+}
+
+/// A plain file with the document's content, downloaded first when it isn't
+/// stored here.
+async fn content(app: &AppHandle, doc: &DocFile) -> Result<PathBuf> {
+    if let Some(path) = doc.local_path.as_deref().filter(|_| !doc.needs_download) {
+        if tokio::fs::metadata(path).await.is_ok() {
+            return compress::plain_path(Path::new(path)).await;
+        }
+    }
+    let path = crate::sync::engine::download_document(app, &doc.id).await?;
+    compress::plain_path(&path).await
+}
+
+/// Make the file at `path` the content of `doc`, unless it already is.
+async fn save(s: &Session<'_>, doc: &DocFile, path: &Path) -> Result<()> {
+    if cas::hash_file(path).await? == doc.content_hash {
+        return Ok(());
+    }
+    mirror::take_in(s.conn, &cas::files_dir(&s.share.app)?, &s.user_id, &doc.id, &doc.filename, path).await?;
+    log::info!("[webdav] Saved {}", doc.id);
+    Ok(())
+}
+
+async fn import(s: &Session<'_>, name: &str, collection_id: Option<String>, path: &Path) -> Result<String> {
+    let options = ImportOptions { collection_id, ..Default::default() };
+    import::import_file_as(&s.share.app, path, name, json!({ "source": "webdav" }), &options).await
+}
+
+/// Rename `doc` to `name` in `collection_id`, queued like any edit.
+async fn rename(s: &Session<'_>, doc: &DocFile, name: &str, collection_id: Option<String>) -> Result<()> {
+    let tx = s.conn.transaction().await?;
+    tx.execute(
+        "UPDATE documents
+         SET filename = ?2, collection_id = ?3, local_version = local_version + 1,
+             needs_upload = 1, is_synced = 0, status = 'local', updated_at = datetime('now')
+         WHERE id = ?1",
+        libsql::params![doc.id.as_str(), name, collection_id],
+    ).await?;
+    ops::enqueue(&tx, &s.user_id, "update_document", json!({ "doc_id": doc.id })).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Keep the file at `from` as scratch file `key`, in place of any before it.
+/// Returns whether there was one.
+async fn keep_scratch(share: &Share, key: &str, from: &Path) -> Result<bool> {
+    let path = share.scratch.join(uuid::Uuid::new_v4().to_string());
+    tokio::fs::rename(from, &path).await?;
+    let old = SCRATCH.lock().unwrap().insert(key.to_string(), path);
+    if let Some(old) = &old {
+        let _ = tokio::fs::remove_file(old).await;
+    }
+    Ok(old.is_some())
+}
+
+async fn drop_scratch(key: &str) {
+    let path = SCRATCH.lock().unwrap().remove(key);
+    if let Some(path) = path {
+        let _ = tokio::fs::remove_file(path).await;
+    }
+}
+
+/// Documents moved aside, once those waiting too long are let go.
+fn parked_ids() -> HashSet<String> {
+    let mut parked = PARKED.lock().unwrap();
+    parked.retain(|_, (_, at)| at.elapsed() < PARK_FOR);
+    parked.values().map(|(id, _)| id.clone()).collect()
+}
+
+fn parked_at(key: &str) -> Option<String> {
+    let mut parked = PARKED.lock().unwrap();
+    parked.retain(|_, (_, at)| at.elapsed() < PARK_FOR);
+    parked.get(key).map(|(id, _)| id.clone())
+}
+
+fn unpark(key: &str) {
+    PARKED.lock().unwrap().remove(key);
+}
+
+impl Node {
+    fn is_folder(&self) -> bool {
+        matches!(self, Node::Folder { .. })
+    }
+}
+
+impl Share {
+    fn segments(&self, target: &str) -> Option<Vec<String>> {
+        segments(&self.token, target)
+    }
+
+    fn href(&self, segs: &[String], folder: bool) -> String {
+        let mut href = format!("/{}/", self.token);
+        href.push_str(&segs.iter().map(|s| encode(s)).collect::<Vec<_>>().join("/"));
+        if folder && !segs.is_empty() {
+            href.push('/');
+        }
+        href
+    }
+}
+
+/// The path in the share under `token` that `target` (a path, or a URL as in
+/// Destination) names, decoded; None outside it.
+fn segments(token: &str, target: &str) -> Option<Vec<String>> {
+    let url = if target.starts_with('/') {
+        Url::parse(&format!("http://127.0.0.1{target}")).ok()?
+    } else {
+        Url::parse(target).ok()?
+    };
+    let mut segs = url.path_segments()?.map(decode).collect::<Option<Vec<String>>>()?;
+    if segs.first().map(String::as_str) != Some(token) {
+        return None;
+    }
+    segs.remove(0);
+    if segs.last().is_some_and(String::is_empty) {
+        segs.pop();
+    }
+    if segs.iter().any(|s| s.is_empty() || s.contains('/') || s == "." || s == "..") {
+        return None;
+    }
+    Some(segs)
+}
+
+/// The request line and headers; None when the connection closes between
+/// requests.
+async fn read_head<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Option<Request>> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut size = 0;
+    loop {
+        let mut line = String::new();
+        let n = reader.read_line(&mut line).await?;
+        if n == 0 {
+            if lines.is_empty() {
+                return Ok(None);
+            }
+            bail!("Connection closed mid-request");
+        }
+        size += n;
+        if size > MAX_HEAD {
+            bail!("Request head too large");
+        }
+        let line = line.trim_end_matches(['\r', '\n']);
+        if !line.is_empty() {
+            lines.push(line.to_string());
+            continue;
+        }
+        // Blank lines before a request are let through
+        if lines.is_empty() {
+            continue;
+        }
+        let mut first = lines[0].split(' ');
+        let (Some(method), Some(target)) = (first.next(), first.next()) else {
+            bail!("Malformed request line");
+        };
+        let headers = lines[1..].iter()
+            .filter_map(|l| l.split_once(':'))
+            .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+            .collect();
+        return Ok(Some(Request { method: method.to_ascii_uppercase(), target: target.to_string(), headers }));
+    }
+}
+
+/// Copy the request's body, sized or chunked, into `sink`.
+async fn read_body<R, W>(reader: &mut R, req: &Request, sink: &mut W, limit: u64) -> Result<()>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    if req.header("transfer-encoding").is_some_and(|t| t.to_ascii_lowercase().contains("chunked")) {
+        let mut total: u64 = 0;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            reader.read_line(&mut line).await?;
+            let size = line.trim().split(';').next().unwrap_or_default().trim();
+            let size = u64::from_str_radix(size, 16).context("Malformed chunk size")?;
+            if size == 0 {
+                // Trailers, up to the blank line
+                loop {
+                    line.clear();
+                    if reader.read_line(&mut line).await? == 0 || line.trim().is_empty() {
+                        return Ok(());
+                    }
+                }
+            }
+            total = match total.checked_add(size) {
+                Some(t) if t <= limit => t,
+                _ => bail!("Request body over {limit} bytes"),
+            };
+            if tokio::io::copy(&mut (&mut *reader).take(size), sink).await? < size {
+                bail!("Request body cut short");
+            }
+            line.clear();
+            reader.read_line(&mut line).await?;
+        }
+    }
+    let length: u64 = match req.header("content-length") {
+        Some(n) => n.parse().context("Malformed Content-Length")?,
+        None    => 0,
+    };
+    if length > limit {
+        bail!("Request body over {limit} bytes");
+    }
+    if tokio::io::copy(&mut (&mut *reader).take(length), sink).await? < length {
+        bail!("Request body cut short");
+    }
+    Ok(())
+}
+
+async fn respond<W: AsyncWrite + Unpin>(write: &mut W, response: Response, head: bool, close: bool) -> Result<()> {
+    let length = match &response.body {
+        Body::Empty       => 0,
+        Body::Bytes(b)    => b.len() as u64,
+        Body::File(path)  => tokio::fs::metadata(path).await?.len(),
+        Body::Length(n)   => *n,
+    };
+    let mut out = format!("HTTP/1.1 {}\r\nContent-Length: {length}\r\n", response.status);
+    for (name, value) in &response.headers {
+        let _ = write!(out, "{name}: {value}\r\n");
+    }
+    if close {
+        out.push_str("Connection: close\r\n");
+    }
+    out.push_str("\r\n");
+    write.write_all(out.as_bytes()).await?;
+    if !head {
+        match response.body {
+            Body::Bytes(bytes) => write.write_all(&bytes).await?,
+            Body::File(path) => {
+                let mut file = tokio::fs::File::open(&path).await?;
+                tokio::io::copy(&mut file, write).await?;
+            }
+            Body::Empty | Body::Length(_) => {}
+        }
+    }
+    write.flush().await?;
+    Ok(())
+}
+
+fn row_to_file(row: &libsql::Row) -> Result<DocFile> {
+    let c = Columns::new(row);
+    Ok(DocFile {
+        id:             c.required("id")?,
+        filename:       c.str("filename").unwrap_or_default(),
+        content_type:   c.str("content_type").unwrap_or_else(|| "application/octet-stream".into()),
+        size:           c.i64("file_size").unwrap_or(0).max(0) as u64,
+        content_hash:   c.str("content_hash").unwrap_or_default(),
+        local_path:     c.str("local_path").filter(|p| !p.is_empty()),
+        needs_download: c.bool("needs_download"),
+        created_at:     c.str("created_at"),
+        updated_at:     c.str("updated_at"),
+    })
+}
+
+/// A time as stored: SQLite's datetime('now'), or RFC 3339 from the server.
+fn parse_time(s: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(s).map(|t| t.with_timezone(&Utc)).ok()
+        .or_else(|| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").ok().map(|t| t.and_utc()))
+}
+
+fn http_date(at: DateTime<Utc>) -> String {
+    at.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn encode(s: &str) -> String {
+    s.bytes().map(|b| match b {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'.' | b'-' | b'_' | b'~' => (b as char).to_string(),
+        _ => format!("%{b:02X}"),
+    }).collect()
+}
+
+fn decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            out.push(u8::from_str_radix(s.get(i + 1..i + 3)?, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "s3cret";
+    const PORT:  u16  = 8123;
+
+    async fn head(raw: &str) -> Result<Option<Request>> {
+        read_head(&mut BufReader::new(raw.as_bytes())).await
+    }
+
+    async fn body(headers: &str, raw: &[u8], limit: u64) -> Result<Vec<u8>> {
+        let req = head(&format!("PUT /{TOKEN}/a.txt HTTP/1.1\r\n{headers}\r\n")).await?.unwrap();
+        let mut out = Vec::new();
+        read_body(&mut BufReader::new(raw), &req, &mut out, limit).await?;
+        Ok(out)
+    }
+
+    fn request(method: &str, target: &str, host: Option<&str>) -> Request {
+        let headers = host.map(|h| ("host".to_string(), h.to_string())).into_iter().collect();
+        Request { method: method.into(), target: target.into(), headers }
+    }
+
+    #[tokio::test]
+    async fn head_is_parsed() {
+        let req = head("\r\npropfind /s3cret/ HTTP/1.1\r\nHost: 127.0.0.1:8123\r\nDepth:  1 \r\n\r\n").await.unwrap().unwrap();
+        assert_eq!(req.method, "PROPFIND");
+        assert_eq!(req.target, "/s3cret/");
+        assert_eq!(req.header("host"), Some("127.0.0.1:8123"));
+        assert_eq!(req.header("depth"), Some("1"));
+
+        assert!(head("").await.unwrap().is_none());
+        assert!(head("GET /s3cret/ HTTP/1.1\r\nHost: x").await.is_err());
+        assert!(head("GET\r\n\r\n").await.is_err());
+        let long = format!("GET /s3cret/ HTTP/1.1\r\nX-Pad: {}\r\n\r\n", "a".repeat(MAX_HEAD));
+        assert!(head(&long).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn sized_body() {
+        assert_eq!(body("Content-Length: 5\r\n", b"hello, and more", 10).await.unwrap(), b"hello");
+        assert!(body("", b"ignored", 10).await.unwrap().is_empty());
+        assert!(body("Content-Length: 11\r\n", b"hello world", 10).await.is_err());
+        assert!(body("Content-Length: 8\r\n", b"hello", 10).await.is_err());
+        assert!(body("Content-Length: -1\r\n", b"", 10).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn chunked_body() {
+        let te = "Transfer-Encoding: chunked\r\n";
+        let raw = b"5;ext=1\r\nhello\r\n6\r\n world\r\n0\r\nX-Trailer: t\r\n\r\n";
+        assert_eq!(body(te, raw, 11).await.unwrap(), b"hello world");
+        assert!(body(te, raw, 10).await.is_err());
+        // Truncated: mid-chunk, and before the last chunk
+        assert!(body(te, b"5\r\nhel", 100).await.is_err());
+        assert!(body(te, b"5\r\nhello\r\n", 100).await.is_err());
+        assert!(body(te, b"zz\r\n", 100).await.is_err());
+        // Sizes that would overflow the running total are over the limit
+        let raw = b"1\r\na\r\nffffffffffffffff\r\n";
+        assert!(body(te, raw, u64::MAX).await.unwrap_err().to_string().contains("over"));
+    }
+
+    #[test]
+    fn admit_checks_host_and_token() {
+        let ok = |host| admit(TOKEN, PORT, &request("GET", "/s3cret/Reports/a.pdf", Some(host)));
+        assert_eq!(ok("127.0.0.1:8123").ok().flatten(), Some(vec!["Reports".to_string(), "a.pdf".to_string()]));
+        assert!(ok("localhost:8123").is_ok());
+        for host in ["evil.example:8123", "127.0.0.1:8124", "127.0.0.1", ""] {
+            assert_eq!(ok(host).err().map(|r| r.status), Some("403 Forbidden"));
+        }
+        assert!(admit(TOKEN, PORT, &request("GET", "/s3cret/", None)).is_err());
+
+        let wrong_token = admit(TOKEN, PORT, &request("GET", "/guess/a.pdf", Some("127.0.0.1:8123")));
+        assert_eq!(wrong_token.err().map(|r| r.status), Some("404 Not Found"));
+        // OPTIONS is asked of the root too
+        let options = admit(TOKEN, PORT, &request("OPTIONS", "/", Some("127.0.0.1:8123")));
+        assert!(matches!(options, Ok(None)));
+    }
+
+    #[test]
+    fn paths_are_decoded_and_confined() {
+        let segs = |t| segments(TOKEN, t);
+        assert_eq!(segs("/s3cret/"), Some(vec![]));
+        assert_eq!(segs("/s3cret/Q%203/r%C3%A9sum%C3%A9.pdf"), Some(vec!["Q 3".into(), "résumé.pdf".into()]));
+        assert_eq!(segs("http://localhost:8123/s3cret/a/"), Some(vec!["a".into()]));
+        // An encoded slash can't name a path across folders
+        assert_eq!(segs("/s3cret/a%2Fb"), None);
+        assert_eq!(segs("/s3cret/a%2fb"), None);
+        // Dot segments can't leave the token's folder, encoded or not
+        assert_eq!(segs("/s3cret/a/../b"), Some(vec!["b".into()]));
+        assert_eq!(segs("/s3cret/../x"), None);
+        assert_eq!(segs("/s3cret/%2e%2e/x"), None);
+        assert_eq!(segs("/s3cret/%2E%2E"), None);
+        assert_eq!(segs("/s3cret//a"), None);
+        assert_eq!(segs("/s3cret/%ZZ"), None);
+        assert_eq!(segs("/s3cret/%C3"), None);
+
+        assert_eq!(decode("a%2Fb"), Some("a/b".into()));
+        assert_eq!(decode("%2e%2E"), Some("..".into()));
+        assert_eq!(decode("%2"), None);
+        assert_eq!(decode(&encode("a/b ..é")), Some("a/b ..é".into()));
+    }
+}
@@ -61,7 +61,7 @@ interface Document {
   filename: string;
   content_type: string;
   file_size: number;
-  text_content: string;
+  text_content?: string; // only on get_document(id), not in listings
   status: string;
   is_synced: boolean;
   needs_upload: boolean;
@@ -0,0 +1,36 @@
+defmodule Alem.Schemas.Annotation do
+  @moduledoc """
+  Schema for annotations (highlights, comments) on a document
+  authored_at, edited_at and deleted_at are the desktop client's timestamps
+  as sent; a deleted annotation keeps its row as a tombstone so other devices
+  learn about the delete
+  """
+
+  use Ecto.Schema
+  import Ecto.Changeset
+
+  @primary_key {:id, :string, autogenerate: false}
+  @timestamps_opts [type: :utc_datetime]
+
+  schema "annotations" do
+    field :doc_id, :string
+    field :user_id, :string
+    field :author_did, :string
+    field :kind, :string
+    field :anchor, :map, default: %{}
+    field :body, :string
+    field :authored_at, :string
+    field :edited_at, :string
+    field :deleted_at, :string
+
+    timestamps()
+  end
+
+  def changeset(annotation, attrs) do
+    annotation
+    |> cast(attrs, [:id, :doc_id, :user_id, :author_did, :kind, :anchor, :body,
+                    :authored_at, :edited_at, :deleted_at])
+    |> validate_required([:id, :doc_id, :user_id, :author_did, :kind, :authored_at, :edited_at])
+    |> unique_constraint(:id, name: :annotations_pkey)
+  end
+end
@@ -0,0 +1,30 @@
+defmodule Alem.Schemas.Collection do
+  @moduledoc """
+  Schema for a desktop client's folders
+  A deleted collection keeps its row with deleted_at set, so other devices
+  learn about the delete from the change feed
+  """
+
+  use Ecto.Schema
+  import Ecto.Changeset
+
+  @primary_key {:id, :string, autogenerate: false}
+  @timestamps_opts [type: :utc_datetime]
+
+  schema "collections" do
+    field :user_id, :string
+    field :tenant_id, :string
+    field :name, :string
+    field :parent_id, :string
+    field :deleted_at, :utc_datetime
+
+    timestamps()
+  end
+
+  def changeset(collection, attrs) do
+    collection
+    |> cast(attrs, [:id, :user_id, :tenant_id, :name, :parent_id, :deleted_at])
+    |> validate_required([:id, :user_id, :tenant_id, :name])
+    |> unique_constraint(:id, name: :collections_pkey)
+  end
+end
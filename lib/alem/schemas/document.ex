@@ -13,13 +13,17 @@ defmodule Alem.Schemas.Document do
     field :text_content, :string
     field :metadata, :map
     field :status, :string, default: "processing"
+    field :acl, {:array, :map}, default: []  # [%{"grantee" => did or user id, "role" => viewer | editor | owner}]
+    field :trashed_at, :utc_datetime
+    field :trash_changed_at, :utc_datetime
 
     timestamps(type: :utc_datetime)
   end
 
   def changeset(document, attrs) do
     document
-    |> cast(attrs, [:id, :tenant_id, :user_id, :filename, :content_type, :object_key, :content_hash, :text_content, :metadata, :status])
+    |> cast(attrs, [:id, :tenant_id, :user_id, :filename, :content_type, :object_key, :content_hash, :text_content, :metadata, :status, :acl,
+                    :trashed_at, :trash_changed_at])
     |> validate_required([:id, :tenant_id, :user_id, :filename])
     |> unique_constraint(:id, name: :documents_pkey)
   end
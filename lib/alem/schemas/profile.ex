@@ -0,0 +1,27 @@
+defmodule Alem.Schemas.Profile do
+  @moduledoc """
+  Schema for a DID's self-issued profile, as its desktop client signed it
+  The server stores and relays it; recipients check the signature
+  """
+
+  use Ecto.Schema
+  import Ecto.Changeset
+
+  @primary_key {:did, :string, autogenerate: false}
+  @timestamps_opts [type: :utc_datetime]
+
+  schema "profiles" do
+    field :user_id, :string
+    field :issued_at, :string
+    field :data, :map  # the signed profile plus the DID document binding its key
+
+    timestamps()
+  end
+
+  def changeset(profile, attrs) do
+    profile
+    |> cast(attrs, [:did, :user_id, :issued_at, :data])
+    |> validate_required([:did, :user_id, :issued_at, :data])
+    |> unique_constraint(:did, name: :profiles_pkey)
+  end
+end
@@ -0,0 +1,59 @@
+defmodule Alem.Schemas.Share do
+  @moduledoc """
+  Schema for shares of one document
+  Either a share link (a capability URL, scoped to a set of permissions,
+  that works without an account until it expires or is revoked) or a share
+  with one DID, which only that DID's account can open
+  """
+
+  use Ecto.Schema
+  import Ecto.Changeset
+
+  @permissions ["read", "download"]
+  @roles ["viewer", "editor"]
+
+  @primary_key {:id, :string, autogenerate: false}
+  @timestamps_opts [type: :utc_datetime]
+
+  schema "shares" do
+    field :user_id, :string
+    field :doc_id, :string
+    field :token_hash, :string
+    field :permissions, {:array, :string}, default: []  # read, download
+    field :expires_at, :utc_datetime
+    field :revoked_at, :utc_datetime
+    field :recipient_did, :string
+    field :owner_did, :string
+    field :role, :string  # viewer, editor (shares with a DID)
+
+    timestamps()
+  end
+
+  def permissions, do: @permissions
+
+  def changeset(share, attrs) do
+    share
+    |> cast(attrs, [:id, :user_id, :doc_id, :token_hash, :permissions, :expires_at, :revoked_at,
+                    :recipient_did, :owner_did, :role])
+    |> validate_required([:id, :user_id, :doc_id, :permissions])
+    |> validate_length(:permissions, min: 1)
+    |> validate_subset(:permissions, @permissions)
+    |> validate_recipient()
+    |> unique_constraint(:id, name: :shares_pkey)
+    |> unique_constraint(:token_hash)
+  end
+
+  # A link has a token; a share with a DID has a role instead
+  defp validate_recipient(changeset) do
+    case get_field(changeset, :recipient_did) do
+      nil ->
+        validate_required(changeset, [:token_hash])
+
+      _did ->
+        changeset
+        |> validate_required([:role])
+        |> validate_inclusion(:role, @roles)
+        |> validate_format(:recipient_did, ~r/^did:[a-z0-9]+:.+/)
+    end
+  end
+end
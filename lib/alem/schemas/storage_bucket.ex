@@ -0,0 +1,36 @@
+defmodule Alem.Schemas.StorageBucket do
+  @moduledoc """
+  Schema for a user's own S3-compatible bucket
+  The secret access key is stored encrypted (Alem.Storage.Buckets). A bucket
+  takes uploads only once active, which the desktop client sets after probing
+  it; at most one of a user's buckets is active
+  """
+
+  use Ecto.Schema
+  import Ecto.Changeset
+
+  @primary_key {:id, :string, autogenerate: false}
+  @timestamps_opts [type: :utc_datetime]
+
+  schema "storage_buckets" do
+    field :user_id, :string
+    field :endpoint, :string
+    field :region, :string
+    field :bucket, :string
+    field :access_key_id, :string
+    field :secret_ciphertext, :string
+    field :path_style, :boolean, default: false
+    field :active, :boolean, default: false
+
+    timestamps()
+  end
+
+  def changeset(bucket, attrs) do
+    bucket
+    |> cast(attrs, [:id, :user_id, :endpoint, :region, :bucket, :access_key_id, :secret_ciphertext,
+                    :path_style, :active])
+    |> validate_required([:id, :user_id, :endpoint, :region, :bucket, :access_key_id, :secret_ciphertext])
+    |> validate_format(:endpoint, ~r{^https?://[^/\s]+/?$})
+    |> unique_constraint(:id, name: :storage_buckets_pkey)
+  end
+end
@@ -0,0 +1,75 @@
+defmodule Alem.Storage.Buckets do
+  @moduledoc """
+  Users' own S3-compatible buckets (bring-your-own storage)
+  An object in one is recorded under the object key
+  byo/<bucket id>/<key in that bucket>; every other object key is in the
+  server's default bucket. Presigning goes through here so both kinds work
+  """
+
+  import Ecto.Query
+  alias Alem.Repo
+  alias Alem.Schemas.StorageBucket
+  alias Alem.Storage.ObjectStore
+
+  @default_bucket "perkeep"
+  @secret_salt "storage bucket secret"
+
+  @doc "The object key for `key` in `bucket`"
+  def object_key(%StorageBucket{id: id}, key), do: "byo/#{id}/#{key}"
+
+  @doc """
+  Presigned URL for `method` on the object `object_key`, in whichever bucket
+  holds it. Objects in a user's bucket are only reachable for that user
+  """
+  def presigned_url(method, user_id, object_key, opts \\ []) do
+    case String.split(object_key, "/", parts: 3) do
+      ["byo", id, key] ->
+        case Repo.get(StorageBucket, id) do
+          %StorageBucket{user_id: ^user_id} = bucket -> presigned_url_in(bucket, method, key, opts)
+          _ -> {:error, :unknown_bucket}
+        end
+
+      _ ->
+        ObjectStore.presigned_url(method, @default_bucket, object_key, opts)
+    end
+  end
+
+  @doc "Presigned URL for `method` on `key` in the user's bucket `bucket`"
+  def presigned_url_in(%StorageBucket{} = bucket, method, key, opts \\ []) do
+    ObjectStore.presigned_url(method, bucket.bucket, key,
+      Keyword.merge(opts, config: config(bucket), virtual_host: not bucket.path_style))
+  end
+
+  @doc "The user's active bucket `id`, nil if it isn't one"
+  def active(user_id, id) do
+    Repo.one(from b in StorageBucket, where: b.id == ^id and b.user_id == ^user_id and b.active)
+  end
+
+  @doc "Make `bucket` the user's active one, and no other"
+  def activate(%StorageBucket{} = bucket) do
+    Repo.transaction(fn ->
+      from(b in StorageBucket, where: b.user_id == ^bucket.user_id and b.id != ^bucket.id)
+      |> Repo.update_all(set: [active: false])
+
+      Repo.update!(StorageBucket.changeset(bucket, %{active: true}))
+    end)
+  end
+
+  def seal(secret), do: Plug.Crypto.encrypt(secret_key_base(), @secret_salt, secret)
+
+  defp config(%StorageBucket{} = bucket) do
+    {:ok, secret} = Plug.Crypto.decrypt(secret_key_base(), @secret_salt, bucket.secret_ciphertext, max_age: :infinity)
+    %URI{scheme: scheme, host: host, port: port} = URI.parse(bucket.endpoint)
+
+    ExAws.Config.new(:s3,
+      access_key_id:     bucket.access_key_id,
+      secret_access_key: secret,
+      region:            bucket.region,
+      scheme:            "#{scheme}://",
+      host:              host,
+      port:              port
+    )
+  end
+
+  defp secret_key_base, do: AlemWeb.Endpoint.config(:secret_key_base)
+end
@@ -98,31 +98,31 @@ defmodule Alem.Storage.ObjectStore do
   Generate presigned URL for upload
   """
   def presigned_upload_url(bucket, key, opts \\ []) do
-    expires_in = Keyword.get(opts, :expires_in, 3600)
-
-    {:ok, url} = ExAws.S3.presigned_url(
-      ExAws.Config.new(:s3),
-      :put,
-      bucket,
-      key,
-      expires_in: expires_in
-    )
-
-    {:ok, url}
+    presigned_url(:put, bucket, key, opts)
   end
 
   @doc """
   Generate presigned URL for download
   """
   def presigned_download_url(bucket, key, opts \\ []) do
-    expires_in = Keyword.get(opts, :expires_in, 3600)
+    presigned_url(:get, bucket, key, opts)
+  end
+
+  @doc """
+  Generate presigned URL for `method` (:put, :get, :delete)
+  Options: :expires_in (seconds), :config (an ExAws config, for a bucket
+  outside the default account) and :virtual_host
+  """
+  def presigned_url(method, bucket, key, opts \\ []) do
+    config = Keyword.get_lazy(opts, :config, fn -> ExAws.Config.new(:s3) end)
 
     {:ok, url} = ExAws.S3.presigned_url(
-      ExAws.Config.new(:s3),
-      :get,
+      config,
+      method,
       bucket,
       key,
-      expires_in: expires_in
+      expires_in: Keyword.get(opts, :expires_in, 3600),
+      virtual_host: Keyword.get(opts, :virtual_host, false)
     )
 
     {:ok, url}
@@ -0,0 +1,70 @@
+defmodule AlemWeb.AclController do
+  use AlemWeb, :controller
+  require Logger
+
+  import Ecto.Query
+  alias Alem.Repo
+  alias Alem.Schemas.Document
+
+  plug AlemWeb.Plugs.PleromaAuth
+
+  @roles ["viewer", "editor", "owner"]
+  @max_batch 500
+
+  @doc """
+  Set a collection's ACL on a batch of its documents. Documents the caller
+  doesn't own are listed as failed; every other one was applied
+  POST /api/v1/acl/batch  {changeset_id, collection_id, acl, document_ids}
+  """
+  def batch(conn, %{"acl" => acl, "document_ids" => document_ids} = params)
+      when is_list(acl) and is_list(document_ids) do
+    user_id = conn.assigns.pleroma_account_id
+
+    with {:ok, entries} <- validate_acl(acl),
+         :ok <- validate_ids(document_ids) do
+      owned =
+        from(d in Document, where: d.id in ^document_ids and d.user_id == ^user_id, select: d.id)
+        |> Repo.all()
+
+      {applied, _} =
+        from(d in Document, where: d.id in ^owned)
+        |> Repo.update_all(set: [acl: entries, updated_at: DateTime.utc_now() |> DateTime.truncate(:second)])
+
+      failed =
+        (document_ids -- owned)
+        |> Enum.uniq()
+        |> Enum.map(&%{id: &1, error: "Document not found"})
+
+      Logger.info("[AclController] Change-set #{params["changeset_id"]}: #{applied} applied, #{length(failed)} failed")
+      conn |> json(%{applied: applied, failed: failed})
+    else
+      {:error, reason} ->
+        conn |> put_status(:unprocessable_entity) |> json(%{error: reason})
+    end
+  end
+
+  def batch(conn, _params) do
+    conn |> put_status(:bad_request) |> json(%{error: "acl and document_ids are required"})
+  end
+
+  # Private helpers
+
+  defp validate_acl(acl) do
+    Enum.reduce_while(acl, {:ok, []}, fn
+      %{"grantee" => grantee, "role" => role}, {:ok, entries}
+      when is_binary(grantee) and grantee != "" and role in @roles ->
+        {:cont, {:ok, entries ++ [%{"grantee" => grantee, "role" => role}]}}
+
+      entry, _ ->
+        {:halt, {:error, "Invalid ACL entry #{inspect(entry)}"}}
+    end)
+  end
+
+  defp validate_ids(ids) do
+    cond do
+      length(ids) > @max_batch -> {:error, "At most #{@max_batch} documents per batch"}
+      not Enum.all?(ids, &is_binary/1) -> {:error, "document_ids must be strings"}
+      true -> :ok
+    end
+  end
+end
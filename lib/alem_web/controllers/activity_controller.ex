@@ -0,0 +1,110 @@
+defmodule AlemWeb.ActivityController do
+  use AlemWeb, :controller
+
+  import Ecto.Query
+  alias Alem.Repo
+  alias Alem.Schemas.{Annotation, Document, Share}
+
+  plug AlemWeb.Plugs.PleromaAuth
+
+  @page 200
+
+  @doc """
+  Events in one of the caller's workspaces since `since`, oldest first:
+  their documents being shared and shares revoked, and others annotating
+  their documents. Pass the returned cursor as the next `since`
+  GET /api/v1/activity?workspace=&since=
+  """
+  def index(conn, params) do
+    user_id   = conn.assigns.pleroma_account_id
+    workspace = params["workspace"] || "default"
+
+    # Timestamps are to the second: the cursor's second again, which the
+    # client takes as a replace of the events it already has
+    since =
+      case params["since"] && DateTime.from_iso8601(params["since"]) do
+        {:ok, since, _} -> since
+        _ -> ~U[1970-01-01 00:00:00Z]
+      end
+
+    # Each source is read oldest first, one row past a page, so the first
+    # page of the merge is exact and a longer backlog shows in has_more
+    events =
+      (shared(user_id, workspace, since) ++ revoked(user_id, workspace, since) ++ annotated(user_id, workspace, since))
+      |> Enum.sort_by(& &1.occurred_at, DateTime)
+
+    {page, rest} = Enum.split(events, @page)
+
+    cursor = case List.last(page) do
+      nil   -> params["since"]
+      event -> DateTime.to_iso8601(event.occurred_at)
+    end
+
+    conn |> json(%{events: page, cursor: cursor, has_more: rest != []})
+  end
+
+  # Private helpers
+
+  defp shared(user_id, workspace, since) do
+    shares(user_id, workspace)
+    |> where([s], s.inserted_at >= ^since)
+    |> order_by([s], asc: s.inserted_at)
+    |> Repo.all()
+    |> Enum.map(fn {share, filename} -> share_event(share, filename, "document_shared", share.inserted_at) end)
+  end
+
+  defp revoked(user_id, workspace, since) do
+    shares(user_id, workspace)
+    |> where([s], s.revoked_at >= ^since)
+    |> order_by([s], asc: s.revoked_at)
+    |> Repo.all()
+    |> Enum.map(fn {share, filename} -> share_event(share, filename, "share_revoked", share.revoked_at) end)
+  end
+
+  defp shares(user_id, workspace) do
+    from(s in Share,
+      join: d in Document, on: d.id == s.doc_id,
+      where: d.user_id == ^user_id and d.tenant_id == ^workspace,
+      limit: ^(@page + 1),
+      select: {s, d.filename}
+    )
+  end
+
+  defp share_event(share, filename, type, at) do
+    %{
+      id:          "#{type}:#{share.id}",
+      type:        type,
+      actor:       share.owner_did,
+      document_id: share.doc_id,
+      data: %{
+        filename:      filename,
+        recipient_did: share.recipient_did,
+        role:          share.role,
+        permissions:   share.permissions
+      },
+      occurred_at: at
+    }
+  end
+
+  defp annotated(user_id, workspace, since) do
+    from(a in Annotation,
+      join: d in Document, on: d.id == a.doc_id,
+      where: d.user_id == ^user_id and d.tenant_id == ^workspace,
+      where: a.user_id != ^user_id and a.inserted_at >= ^since,
+      order_by: [asc: a.inserted_at],
+      limit: ^(@page + 1),
+      select: {a, d.filename}
+    )
+    |> Repo.all()
+    |> Enum.map(fn {annotation, filename} ->
+      %{
+        id:          "annotation_created:#{annotation.id}",
+        type:        "annotation_created",
+        actor:       annotation.author_did,
+        document_id: annotation.doc_id,
+        data:        %{filename: filename, kind: annotation.kind},
+        occurred_at: annotation.inserted_at
+      }
+    end)
+  end
+end
@@ -85,6 +85,17 @@ defmodule AlemWeb.AuthController do
     end
   end
 
+  @doc """
+  Start an OAuth authorization in the browser (the desktop app's PKCE sign-in)
+  GET /api/v1/oauth/authorize
+
+  Redirects to Pleroma's authorization page with the same query.
+  """
+  def authorize(conn, _params) do
+    query = if conn.query_string == "", do: "", else: "?" <> conn.query_string
+    redirect(conn, external: "#{pleroma_base_url()}/oauth/authorize#{query}")
+  end
+
   @doc """
   Register a new user account
   POST /api/account/register
@@ -260,4 +271,46 @@ defmodule AlemWeb.AuthController do
         |> json(%{error: "Failed to connect to Pleroma API", details: inspect(reason)})
     end
   end
+
+  @doc """
+  Post a status (sharing a document to the fediverse)
+  POST /api/v1/statuses
+  """
+  def post_status(conn, params) do
+    url = "#{pleroma_base_url()}/api/v1/statuses"
+    Logger.info("Calling Pleroma API: POST #{url}")
+
+    # Pass the caller's token and idempotency key through, so a retried post
+    # isn't posted twice
+    headers =
+      ["authorization", "idempotency-key"]
+      |> Enum.flat_map(fn name ->
+        case Plug.Conn.get_req_header(conn, name) do
+          [value | _] -> [{name, value}]
+          [] -> []
+        end
+      end)
+
+    body =
+      params
+      |> Map.take(["status", "visibility", "content_type", "spoiler_text", "sensitive", "language", "in_reply_to_id"])
+      |> Enum.reject(fn {_, v} -> is_nil(v) or v == "" end)
+      |> Map.new()
+
+    case Req.post(url, json: body, headers: headers) do
+      {:ok, %{status: status, body: response_body}} when status in [200, 201] ->
+        Logger.info("Status posted")
+        conn |> put_status(status) |> json(parse_response_body(response_body))
+
+      {:ok, %{status: status, body: response_body}} ->
+        Logger.error("Pleroma API error: #{status} - URL: #{url} - Response: #{inspect(response_body)}")
+        conn |> put_status(status) |> json(parse_response_body(response_body))
+
+      {:error, reason} ->
+        Logger.error("Failed to call Pleroma API: #{inspect(reason)}")
+        conn
+        |> put_status(:bad_gateway)
+        |> json(%{error: "Failed to connect to Pleroma API", details: inspect(reason)})
+    end
+  end
 end
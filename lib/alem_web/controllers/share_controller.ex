@@ -0,0 +1,261 @@
+defmodule AlemWeb.ShareController do
+  use AlemWeb, :controller
+  require Logger
+
+  import Ecto.Query
+  alias Alem.Repo
+  alias Alem.Schemas.{Document, Share}
+  alias Alem.Storage.Buckets
+  alias Alem.Identity.Resolver
+
+  # Opening a share link needs nothing but the link
+  plug AlemWeb.Plugs.PleromaAuth when action in [:create, :index, :revoke, :incoming, :open_incoming]
+
+  @download_expires_in 300
+
+  @doc """
+  Share one of the caller's documents: a share link, or with one DID
+  POST /api/v1/shares  {doc_id, permissions, expires_at?}
+  POST /api/v1/shares  {id, doc_id, recipient_did, role}
+  """
+  def create(conn, %{"recipient_did" => recipient_did} = params) when is_binary(recipient_did) do
+    user_id = conn.assigns.pleroma_account_id
+    doc_id  = params["doc_id"]
+
+    case Repo.get(Document, doc_id || "") do
+      %Document{user_id: ^user_id} ->
+        attrs = %{
+          id:            params["id"] || Ecto.UUID.generate(),
+          user_id:       user_id,
+          doc_id:        doc_id,
+          permissions:   ["read", "download"],
+          recipient_did: recipient_did,
+          owner_did:     caller_did(conn),
+          role:          params["role"] || "viewer"
+        }
+
+        case Repo.insert(Share.changeset(%Share{}, attrs)) do
+          {:ok, share} ->
+            Logger.info("[ShareController] #{doc_id} shared with #{recipient_did}")
+            conn |> put_status(:created) |> json(format_share(share))
+
+          {:error, changeset} ->
+            # The same share sent again (a retried request)
+            case Repo.get(Share, attrs.id) do
+              %Share{user_id: ^user_id, recipient_did: ^recipient_did} = share ->
+                conn |> json(format_share(share))
+
+              _ ->
+                conn
+                |> put_status(:unprocessable_entity)
+                |> json(%{error: "Invalid share", details: inspect(changeset.errors)})
+            end
+        end
+
+      _ ->
+        conn |> put_status(:not_found) |> json(%{error: "Document not found"})
+    end
+  end
+
+  def create(conn, params) do
+    user_id = conn.assigns.pleroma_account_id
+    doc_id  = params["doc_id"]
+
+    case Repo.get(Document, doc_id || "") do
+      %Document{user_id: ^user_id} ->
+        token = :crypto.strong_rand_bytes(32) |> Base.url_encode64(padding: false)
+        attrs = %{
+          id:          params["id"] || Ecto.UUID.generate(),
+          user_id:     user_id,
+          doc_id:      doc_id,
+          token_hash:  hash(token),
+          permissions: params["permissions"] || ["read"],
+          expires_at:  params["expires_at"]
+        }
+
+        case Repo.insert(Share.changeset(%Share{}, attrs)) do
+          {:ok, share} ->
+            Logger.info("[ShareController] Share #{share.id} created for #{doc_id}")
+            conn
+            |> put_status(:created)
+            |> json(Map.merge(format_share(share), %{token: token, url: share_url(token)}))
+
+          {:error, changeset} ->
+            conn
+            |> put_status(:unprocessable_entity)
+            |> json(%{error: "Invalid share", details: inspect(changeset.errors)})
+        end
+
+      _ ->
+        conn |> put_status(:not_found) |> json(%{error: "Document not found"})
+    end
+  end
+
+  @doc """
+  The caller's share links, optionally of one document
+  GET /api/v1/shares?doc_id=
+  """
+  def index(conn, params) do
+    user_id = conn.assigns.pleroma_account_id
+
+    shares =
+      from(s in Share, where: s.user_id == ^user_id, order_by: [desc: s.inserted_at])
+      |> then(fn q -> if params["doc_id"], do: where(q, [s], s.doc_id == ^params["doc_id"]), else: q end)
+      |> Repo.all()
+
+    conn |> json(%{shares: Enum.map(shares, &format_share/1)})
+  end
+
+  @doc """
+  Revoke a share link; the link stops working at once
+  DELETE /api/v1/shares/:id
+  """
+  def revoke(conn, %{"id" => id}) do
+    user_id = conn.assigns.pleroma_account_id
+
+    case Repo.get(Share, id) do
+      %Share{user_id: ^user_id, revoked_at: nil} = share ->
+        {:ok, share} = Repo.update(Share.changeset(share, %{revoked_at: DateTime.utc_now()}))
+        Logger.info("[ShareController] Share #{id} revoked")
+        conn |> json(format_share(share))
+
+      %Share{user_id: ^user_id} = share ->
+        conn |> json(format_share(share))
+
+      _ ->
+        conn |> put_status(:not_found) |> json(%{error: "Share not found"})
+    end
+  end
+
+  @doc """
+  Open a share link: the document, as far as its permissions go
+  GET /api/v1/s/:token
+  """
+  def resolve(conn, %{"token" => token}) do
+    now = DateTime.utc_now()
+
+    with %Share{revoked_at: nil, recipient_did: nil} = share <- Repo.get_by(Share, token_hash: hash(token)),
+         true <- is_nil(share.expires_at) or DateTime.compare(share.expires_at, now) == :gt,
+         %Document{} = doc <- Repo.get(Document, share.doc_id) do
+      conn |> json(document_body(share, doc))
+    else
+      # Expired, revoked and unknown links look alike
+      _ -> conn |> put_status(:not_found) |> json(%{error: "Share link not found"})
+    end
+  end
+
+  @doc """
+  Shares with the caller's DID changed since `since` (revoked ones included,
+  so they can be dropped), oldest first
+  GET /api/v1/shares/incoming?since=
+  """
+  def incoming(conn, params) do
+    case caller_did(conn) do
+      nil ->
+        conn |> json(%{shares: [], cursor: params["since"]})
+
+      did ->
+        query =
+          from(s in Share,
+            join: d in Document, on: d.id == s.doc_id,
+            where: s.recipient_did == ^did,
+            order_by: [asc: s.updated_at],
+            limit: 500,
+            select: {s, d}
+          )
+
+        query =
+          case params["since"] && DateTime.from_iso8601(params["since"]) do
+            # Timestamps are to the second: the cursor's second again, which
+            # the client takes as an upsert
+            {:ok, since, _} -> where(query, [s], s.updated_at >= ^since)
+            _ -> query
+          end
+
+        shares =
+          Repo.all(query)
+          |> Enum.map(fn {share, doc} ->
+            share
+            |> format_share()
+            |> Map.merge(%{
+              owner_did:    share.owner_did,
+              filename:     doc.filename,
+              content_type: doc.content_type,
+              metadata:     doc.metadata || %{},
+              updated_at:   share.updated_at
+            })
+          end)
+
+        cursor = case List.last(shares) do
+          nil   -> params["since"]
+          share -> DateTime.to_iso8601(share.updated_at)
+        end
+
+        conn |> json(%{shares: shares, cursor: cursor})
+    end
+  end
+
+  @doc """
+  Open a document shared with the caller's DID
+  GET /api/v1/shares/incoming/:id
+  """
+  def open_incoming(conn, %{"id" => id}) do
+    did = caller_did(conn)
+
+    with true <- is_binary(did),
+         %Share{revoked_at: nil, recipient_did: ^did} = share <- Repo.get(Share, id),
+         %Document{} = doc <- Repo.get(Document, share.doc_id) do
+      conn |> json(Map.put(document_body(share, doc), :role, share.role))
+    else
+      _ -> conn |> put_status(:not_found) |> json(%{error: "Share not found"})
+    end
+  end
+
+  # Private helpers
+
+  # The document, as far as the share's permissions go
+  defp document_body(share, doc) do
+    body = %{
+      doc_id:       doc.id,
+      filename:     doc.filename,
+      content_type: doc.content_type,
+      metadata:     doc.metadata || %{},
+      permissions:  share.permissions,
+      expires_at:   share.expires_at
+    }
+
+    body = if "read" in share.permissions, do: Map.put(body, :text_content, doc.text_content), else: body
+
+    if "download" in share.permissions and doc.object_key do
+      {:ok, url} = Buckets.presigned_url(:get, doc.user_id, doc.object_key, expires_in: @download_expires_in)
+      Map.put(body, :download_url, url)
+    else
+      body
+    end
+  end
+
+  # The caller's DID: their namespace's, nil when it has none
+  defp caller_did(conn) do
+    case Resolver.resolve_to_namespace(conn.assigns.pleroma_account_id) do
+      {:ok, %{did: did}} when is_binary(did) -> did
+      _ -> nil
+    end
+  end
+
+  defp hash(token), do: :crypto.hash(:sha256, token) |> Base.encode16(case: :lower)
+
+  defp share_url(token), do: "#{AlemWeb.Endpoint.url()}/api/v1/s/#{token}"
+
+  defp format_share(share) do
+    %{
+      id:            share.id,
+      doc_id:        share.doc_id,
+      permissions:   share.permissions,
+      expires_at:    share.expires_at,
+      revoked_at:    share.revoked_at,
+      created_at:    share.inserted_at,
+      recipient_did: share.recipient_did,
+      role:          share.role
+    }
+  end
+end
@@ -0,0 +1,85 @@
+defmodule AlemWeb.StorageController do
+  use AlemWeb, :controller
+  require Logger
+
+  alias Alem.Repo
+  alias Alem.Schemas.StorageBucket
+  alias Alem.Storage.Buckets
+
+  plug AlemWeb.Plugs.PleromaAuth
+
+  @probe_expires_in 300
+
+  @doc """
+  Register one of the caller's own S3-compatible buckets. Uploads don't use
+  it until the client has probed and activated it
+  POST /api/v1/storage/buckets  {endpoint, region, bucket, access_key_id, secret_access_key, path_style}
+  """
+  def create(conn, %{"secret_access_key" => secret} = params) when is_binary(secret) and secret != "" do
+    attrs = %{
+      id:                Ecto.UUID.generate(),
+      user_id:           conn.assigns.pleroma_account_id,
+      endpoint:          params["endpoint"],
+      region:            params["region"],
+      bucket:            params["bucket"],
+      access_key_id:     params["access_key_id"],
+      secret_ciphertext: Buckets.seal(secret),
+      path_style:        params["path_style"] == true
+    }
+
+    case Repo.insert(StorageBucket.changeset(%StorageBucket{}, attrs)) do
+      {:ok, bucket} ->
+        Logger.info("[StorageController] Bucket #{bucket.id} registered for #{bucket.user_id}")
+        conn |> put_status(:created) |> json(%{bucket_id: bucket.id})
+
+      {:error, changeset} ->
+        conn
+        |> put_status(:unprocessable_entity)
+        |> json(%{error: "Invalid bucket", details: inspect(changeset.errors)})
+    end
+  end
+
+  def create(conn, _params) do
+    conn |> put_status(:bad_request) |> json(%{error: "secret_access_key is required"})
+  end
+
+  @doc """
+  Presigned URLs for a fresh probe object in one of the caller's buckets,
+  which the client writes, reads back and deletes
+  POST /api/v1/storage/buckets/:id/probe
+  """
+  def probe(conn, %{"id" => id}) do
+    with_bucket(conn, id, fn bucket ->
+      key  = "alem-probe/#{Ecto.UUID.generate()}"
+      opts = [expires_in: @probe_expires_in]
+      {:ok, put_url}    = Buckets.presigned_url_in(bucket, :put, key, opts)
+      {:ok, get_url}    = Buckets.presigned_url_in(bucket, :get, key, opts)
+      {:ok, delete_url} = Buckets.presigned_url_in(bucket, :delete, key, opts)
+
+      conn |> json(%{put_url: put_url, get_url: get_url, delete_url: delete_url})
+    end)
+  end
+
+  @doc """
+  Send the caller's uploads to this bucket from now on
+  POST /api/v1/storage/buckets/:id/activate
+  """
+  def activate(conn, %{"id" => id}) do
+    with_bucket(conn, id, fn bucket ->
+      {:ok, bucket} = Buckets.activate(bucket)
+      Logger.info("[StorageController] Bucket #{bucket.id} active for #{bucket.user_id}")
+      conn |> json(%{bucket_id: bucket.id, active: true})
+    end)
+  end
+
+  # Private helpers
+
+  defp with_bucket(conn, id, fun) do
+    user_id = conn.assigns.pleroma_account_id
+
+    case Repo.get(StorageBucket, id) do
+      %StorageBucket{user_id: ^user_id} = bucket -> fun.(bucket)
+      _ -> conn |> put_status(:not_found) |> json(%{error: "Bucket not found"})
+    end
+  end
+end
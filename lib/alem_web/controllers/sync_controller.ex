@@ -4,8 +4,9 @@ defmodule AlemWeb.SyncController do
 
   import Ecto.Query
   alias Alem.Repo
-  alias Alem.Schemas.{Document, Namespace}
-  alias Alem.Storage.ObjectStore
+  alias Alem.Schemas.{Annotation, Collection, Document, Namespace, Profile, Share}
+  alias Alem.Identity.Resolver
+  alias Alem.Storage.{Buckets, ObjectStore}
 
   plug AlemWeb.Plugs.PleromaAuth
 
@@ -39,24 +40,78 @@ defmodule AlemWeb.SyncController do
 
   # ── get_upload_url ──────────────────────────────────────────────────────────
   # Returns a real S3 presigned PUT URL so Tauri uploads directly to Linode S3.
+  # With a content_hash the key is content-addressed (blobs/<user>/<sha256>):
+  # documents with the same bytes share it, and an edit gets a new key instead
+  # of overwriting what another document points at. With a bucket_id the
+  # upload goes to that bucket of the user's own (Alem.Storage.Buckets).
   def get_upload_url(conn, params) do
     user_id  = conn.assigns.pleroma_account_id
     doc_id   = params["doc_id"]
     filename = params["filename"]
 
-    object_key = "uploads/#{user_id}/#{doc_id}/#{filename}"
+    key =
+      case params["content_hash"] do
+        hash when is_binary(hash) ->
+          if hash =~ ~r/\A[0-9a-f]{64}\z/,
+            do: "blobs/#{user_id}/#{hash}",
+            else: "uploads/#{user_id}/#{doc_id}/#{filename}"
+
+        _ ->
+          "uploads/#{user_id}/#{doc_id}/#{filename}"
+      end
+
+    presigned =
+      case params["bucket_id"] do
+        nil ->
+          {ObjectStore.presigned_upload_url(@bucket, key, expires_in: 3600), key}
 
-    case ObjectStore.presigned_upload_url(@bucket, object_key, expires_in: 3600) do
-      {:ok, presigned_url} ->
+        bucket_id ->
+          case Buckets.active(user_id, bucket_id) do
+            nil    -> {{:error, :unknown_bucket}, key}
+            bucket -> {Buckets.presigned_url_in(bucket, :put, key, expires_in: 3600), Buckets.object_key(bucket, key)}
+          end
+      end
+
+    case presigned do
+      {{:ok, presigned_url}, object_key} ->
         Logger.info("[SyncController] Generated presigned URL for #{object_key}")
         conn |> json(%{upload_url: presigned_url, object_key: object_key})
 
-      {:error, reason} ->
+      {{:error, :unknown_bucket}, _} ->
+        conn |> put_status(:conflict) |> json(%{error: "bucket_id is not an active bucket of yours"})
+
+      {{:error, reason}, _} ->
         Logger.error("[SyncController] Failed to generate presigned URL: #{inspect(reason)}")
         conn |> put_status(500) |> json(%{error: "Failed to generate upload URL"})
     end
   end
 
+  # ── get_download_url ────────────────────────────────────────────────────────
+  # Presigned GET for one of the caller's documents, for on-demand downloads.
+  # The object key is the document's own; a key sent along must match it.
+  def get_download_url(conn, params) do
+    user_id = conn.assigns.pleroma_account_id
+
+    case Repo.get(Document, params["doc_id"] || "") do
+      %Document{user_id: ^user_id, object_key: key} when is_binary(key) and key != "" ->
+        if params["object_key"] in [nil, key] do
+          case Buckets.presigned_url(:get, user_id, key, expires_in: 3600) do
+            {:ok, url} ->
+              conn |> json(%{download_url: url, object_key: key})
+
+            {:error, reason} ->
+              Logger.error("[SyncController] Failed to generate download URL: #{inspect(reason)}")
+              conn |> put_status(500) |> json(%{error: "Failed to generate download URL"})
+          end
+        else
+          conn |> put_status(:conflict) |> json(%{error: "object_key is not the document's"})
+        end
+
+      _ ->
+        conn |> put_status(:not_found) |> json(%{error: "Document not found"})
+    end
+  end
+
   # ── upload_file ─────────────────────────────────────────────────────────────
   # Fallback direct upload if presigned URL fails — Phoenix proxies to S3.
   def upload_file(conn, %{"doc_id" => doc_id} = params) do
@@ -108,12 +163,28 @@ defmodule AlemWeb.SyncController do
     limit   = String.to_integer(params["limit"] || "100")
 
     {:ok, changes} = get_user_changes(user_id, since, limit)
+    has_more = length(changes) >= limit
+
+    # The ETag covers the change set only (timestamp moves on every call), so
+    # a client that already applied it gets a 304 instead of it again
+    etag = changes_etag(changes, has_more)
+
+    if etag in get_req_header(conn, "if-none-match") do
+      conn |> put_resp_header("etag", etag) |> send_resp(304, "")
+    else
+      conn
+      |> put_resp_header("etag", etag)
+      |> json(%{
+        changes:   changes,
+        timestamp: DateTime.utc_now(),
+        has_more:  has_more
+      })
+    end
+  end
 
-    conn |> json(%{
-      changes:   changes,
-      timestamp: DateTime.utc_now(),
-      has_more:  length(changes) >= limit
-    })
+  defp changes_etag(changes, has_more) do
+    hash = :crypto.hash(:sha256, Jason.encode!([changes, has_more]))
+    ~s("#{Base.url_encode64(hash, padding: false)}")
   end
 
   # ── Private — change application ─────────────────────────────────────────────
@@ -121,6 +192,8 @@ defmodule AlemWeb.SyncController do
   defp apply_client_changes(user_id, changes) do
     results = Enum.map(changes, fn change ->
       case apply_single_change(user_id, change) do
+        {:ok, :skipped} ->
+          %{"change_id" => change["id"], "status" => "skipped", "error" => "unknown change type"}
         {:ok, result} ->
           %{"change_id" => change["id"], "status" => "applied", "result" => result}
         {:error, reason} ->
@@ -136,6 +209,13 @@ defmodule AlemWeb.SyncController do
       "create_document" -> create_document(user_id, change["data"])
       "update_document" -> update_document(user_id, change["data"])
       "delete_document" -> delete_document(user_id, change["data"])
+      "trash_document"   -> set_trashed(user_id, change["data"], trashed_at(change["data"]))
+      "restore_document" -> set_trashed(user_id, change["data"], nil)
+      "upsert_annotation" -> put_annotation(user_id, change["data"])
+      "delete_annotation" -> put_annotation(user_id, change["data"])
+      "update_profile"    -> update_profile(user_id, change["data"])
+      "upsert_collection" -> upsert_collection(user_id, change["data"])
+      "delete_collection" -> delete_collection(user_id, change["data"])
       type ->
         Logger.warning("[SyncController] Unknown change type: #{type}")
         {:ok, :skipped}
@@ -185,6 +265,10 @@ defmodule AlemWeb.SyncController do
   end
 
   # ── update_document ──────────────────────────────────────────────────────────
+  # Only the fields the client sent change: a rename carries no content_type or
+  # text_content, and must not blank them. sqld gets the whole updated row.
+
+  @updatable_fields ~w(filename content_type object_key content_hash text_content metadata)
 
   defp update_document(user_id, data) do
     now = DateTime.utc_now()
@@ -194,19 +278,11 @@ defmodule AlemWeb.SyncController do
         {:error, :not_found}
 
       %Document{user_id: ^user_id} = doc ->
-        attrs = %{
-          filename:     data["filename"],
-          content_type: data["content_type"],
-          object_key:   data["object_key"] || doc.object_key,
-          text_content: data["text_content"],
-          metadata:     data["metadata"] || %{}
-        }
-
-        pg_result   = Repo.update(Document.changeset(doc, attrs))
-        sqld_result = sqld_upsert_document(Map.merge(attrs, %{id: doc.id, user_id: user_id, tenant_id: doc.tenant_id}), now)
-
-        case pg_result do
+        attrs = Map.take(data, @updatable_fields)
+
+        case Repo.update(Document.changeset(doc, attrs)) do
           {:ok, d} ->
+            sqld_result = sqld_upsert_document(document_attrs(d), now)
             Logger.info("[SyncController] Document #{d.id} updated. sqld: #{inspect(sqld_result)}")
             {:ok, %{"id" => d.id, "status" => "updated"}}
           err -> err
@@ -217,6 +293,11 @@ defmodule AlemWeb.SyncController do
     end
   end
 
+  defp document_attrs(%Document{} = d) do
+    Map.take(d, [:id, :user_id, :tenant_id, :filename, :content_type, :object_key,
+                 :content_hash, :text_content, :metadata, :status])
+  end
+
   # ── delete_document ──────────────────────────────────────────────────────────
 
   defp delete_document(user_id, data) do
@@ -237,27 +318,203 @@ defmodule AlemWeb.SyncController do
     end
   end
 
+  # ── trash_document / restore_document ───────────────────────────────────────
+  # Both carry the client's current trash state; trash_changed_at is when the
+  # server learned of it, which is what the change feed goes by.
+
+  defp set_trashed(user_id, data, trashed_at) do
+    case Repo.get(Document, data["id"] || "") do
+      nil ->
+        {:error, :not_found}
+
+      %Document{user_id: ^user_id} = doc ->
+        now = DateTime.utc_now() |> DateTime.truncate(:second)
+        case Repo.update(Document.changeset(doc, %{trashed_at: trashed_at, trash_changed_at: now})) do
+          {:ok, d} -> {:ok, %{"id" => d.id, "status" => if(trashed_at, do: "trashed", else: "restored")}}
+          err -> err
+        end
+
+      %Document{} ->
+        {:error, :unauthorized}
+    end
+  end
+
+  # Clients send SQLite's datetime('now'), UTC without an offset
+  defp trashed_at(data) do
+    ts = data["trashed_at"] || ""
+
+    case {DateTime.from_iso8601(ts), NaiveDateTime.from_iso8601(ts)} do
+      {{:ok, dt, _}, _} -> DateTime.truncate(dt, :second)
+      {_, {:ok, naive}} -> naive |> DateTime.from_naive!("Etc/UTC") |> DateTime.truncate(:second)
+      _                 -> DateTime.utc_now() |> DateTime.truncate(:second)
+    end
+  end
+
+  # ── upsert_annotation / delete_annotation ───────────────────────────────────
+  # A delete is the annotation with deleted_at set. Like on the client, a
+  # tombstone is final and an older edit never replaces a newer one. Only the
+  # document's owner and DIDs it is shared with annotate it, and only the
+  # account that created an annotation changes it.
+
+  defp put_annotation(user_id, data) do
+    attrs = %{
+      id:          data["id"],
+      doc_id:      data["doc_id"],
+      user_id:     user_id,
+      author_did:  data["author_did"],
+      kind:        data["kind"],
+      anchor:      data["anchor"] || %{},
+      body:        data["body"],
+      authored_at: data["created_at"],
+      edited_at:   data["updated_at"],
+      deleted_at:  data["deleted_at"]
+    }
+
+    with %Document{} = doc <- Repo.get(Document, data["doc_id"] || "") || {:error, :not_found},
+         true <- may_annotate?(user_id, doc) || {:error, :unauthorized} do
+      case Repo.get(Annotation, data["id"] || "") do
+        nil ->
+          case Repo.insert(Annotation.changeset(%Annotation{}, attrs)) do
+            {:ok, a} -> {:ok, %{"id" => a.id, "status" => "created"}}
+            err -> err
+          end
+
+        %Annotation{user_id: ^user_id, deleted_at: nil} = annotation ->
+          if attrs.deleted_at || attrs.edited_at >= annotation.edited_at do
+            case Repo.update(Annotation.changeset(annotation, Map.take(attrs, [:anchor, :body, :edited_at, :deleted_at]))) do
+              {:ok, a} -> {:ok, %{"id" => a.id, "status" => if(a.deleted_at, do: "deleted", else: "updated")}}
+              err -> err
+            end
+          else
+            {:ok, %{"id" => annotation.id, "status" => "stale"}}
+          end
+
+        %Annotation{user_id: ^user_id} = annotation ->
+          {:ok, %{"id" => annotation.id, "status" => "deleted"}}
+
+        %Annotation{} ->
+          {:error, :unauthorized}
+      end
+    end
+  end
+
+  defp may_annotate?(user_id, %Document{user_id: user_id}), do: true
+  defp may_annotate?(user_id, %Document{id: doc_id}) do
+    case caller_did(user_id) do
+      nil -> false
+      did -> Repo.exists?(from s in Share, where: s.doc_id == ^doc_id and s.recipient_did == ^did and is_nil(s.revoked_at))
+    end
+  end
+
+  # The account's DID: its namespace's, nil when it has none
+  defp caller_did(user_id) do
+    case Resolver.resolve_to_namespace(user_id) do
+      {:ok, %{did: did}} when is_binary(did) -> did
+      _ -> nil
+    end
+  end
+
+  # ── update_profile ───────────────────────────────────────────────────────────
+  # Only the caller's own DID's profile, and never an older one over a newer
+  # one. The signature is checked by whoever receives it.
+
+  defp update_profile(user_id, data) do
+    did = data["did"]
+
+    cond do
+      not is_binary(did) or did != caller_did(user_id) ->
+        {:error, :unauthorized}
+
+      true ->
+        attrs = %{did: did, user_id: user_id, issued_at: data["issued_at"], data: data}
+
+        case Repo.get(Profile, did) do
+          nil ->
+            case Repo.insert(Profile.changeset(%Profile{}, attrs)) do
+              {:ok, _} -> {:ok, %{"did" => did, "status" => "created"}}
+              err -> err
+            end
+
+          %Profile{issued_at: issued_at} = profile ->
+            if is_binary(attrs.issued_at) and attrs.issued_at > issued_at do
+              case Repo.update(Profile.changeset(profile, attrs)) do
+                {:ok, _} -> {:ok, %{"did" => did, "status" => "updated"}}
+                err -> err
+              end
+            else
+              {:ok, %{"did" => did, "status" => "stale"}}
+            end
+        end
+    end
+  end
+
+  # ── upsert_collection / delete_collection ───────────────────────────────────
+
+  defp upsert_collection(user_id, data) do
+    attrs = %{
+      id:         data["id"],
+      user_id:    user_id,
+      tenant_id:  data["tenant_id"] || "default",
+      name:       data["name"],
+      parent_id:  data["parent_id"],
+      deleted_at: nil
+    }
+
+    case Repo.get(Collection, data["id"] || "") do
+      nil ->
+        case Repo.insert(Collection.changeset(%Collection{}, attrs)) do
+          {:ok, c} -> {:ok, %{"id" => c.id, "status" => "created"}}
+          err -> err
+        end
+
+      %Collection{user_id: ^user_id} = collection ->
+        case Repo.update(Collection.changeset(collection, Map.drop(attrs, [:tenant_id]))) do
+          {:ok, c} -> {:ok, %{"id" => c.id, "status" => "updated"}}
+          err -> err
+        end
+
+      %Collection{} ->
+        {:error, :unauthorized}
+    end
+  end
+
+  defp delete_collection(user_id, data) do
+    case Repo.get(Collection, data["id"] || "") do
+      nil ->
+        {:ok, %{"id" => data["id"], "status" => "not_found"}}
+
+      %Collection{user_id: ^user_id} = collection ->
+        now = DateTime.utc_now() |> DateTime.truncate(:second)
+        case Repo.update(Collection.changeset(collection, %{deleted_at: now})) do
+          {:ok, c} -> {:ok, %{"id" => c.id, "status" => "deleted"}}
+          err -> err
+        end
+
+      %Collection{} ->
+        {:error, :unauthorized}
+    end
+  end
+
   # ── get_user_changes ─────────────────────────────────────────────────────────
 
   defp get_user_changes(user_id, since, limit) do
-    # Try sqld first
-    case sqld_get_document_changes(user_id, since, limit) do
-      {:ok, docs} when length(docs) >= 0 ->
-        nss = get_namespace_changes(user_id, since)
-        all = (docs ++ nss)
-              |> Enum.sort_by(& &1["timestamp"], {:desc, DateTime})
-              |> Enum.take(limit)
-        {:ok, all}
+    # Documents from sqld first, PG if it fails; everything else lives in PG only
+    docs =
+      case sqld_get_document_changes(user_id, since, limit) do
+        {:ok, docs} ->
+          docs
 
-      {:error, reason} ->
-        Logger.warning("[SyncController] sqld query failed, falling back to PG: #{inspect(reason)}")
-        docs = get_document_changes_from_pg(user_id, since, limit)
-        nss  = get_namespace_changes(user_id, since)
-        all  = (docs ++ nss)
-               |> Enum.sort_by(& &1["timestamp"], {:desc, DateTime})
-               |> Enum.take(limit)
-        {:ok, all}
-    end
+        {:error, reason} ->
+          Logger.warning("[SyncController] sqld query failed, falling back to PG: #{inspect(reason)}")
+          get_document_changes_from_pg(user_id, since, limit)
+      end
+
+    all = (docs ++ get_trash_changes(user_id, since) ++ get_namespace_changes(user_id, since) ++
+             get_collection_changes(user_id, since) ++ get_annotation_changes(user_id, since) ++
+             get_profile_changes(user_id, since))
+          |> Enum.sort_by(& &1["timestamp"], {:desc, DateTime})
+          |> Enum.take(limit)
+    {:ok, all}
   end
 
   # ── sqld helpers ─────────────────────────────────────────────────────────────
@@ -373,6 +630,94 @@ defmodule AlemWeb.SyncController do
     end)
   end
 
+  defp get_trash_changes(user_id, since) do
+    from(d in Document,
+      where: d.user_id == ^user_id and d.trash_changed_at > ^since,
+      order_by: [desc: d.trash_changed_at]
+    )
+    |> Repo.all()
+    |> Enum.map(fn d ->
+      type = if d.trashed_at, do: "document_trashed", else: "document_restored"
+
+      %{"type" => type, "id" => d.id, "timestamp" => d.trash_changed_at,
+        "data" => %{"id" => d.id, "trashed_at" => d.trashed_at}}
+    end)
+  end
+
+  # Annotations on the caller's documents, on documents shared with their
+  # DID, and their own
+  defp get_annotation_changes(user_id, since) do
+    shared =
+      case caller_did(user_id) do
+        nil -> []
+        did -> Repo.all(from s in Share, where: s.recipient_did == ^did and is_nil(s.revoked_at), select: s.doc_id)
+      end
+
+    from(a in Annotation,
+      join: d in Document, on: d.id == a.doc_id,
+      where: a.updated_at > ^since and
+             (d.user_id == ^user_id or a.user_id == ^user_id or a.doc_id in ^shared),
+      order_by: [desc: a.updated_at]
+    )
+    |> Repo.all()
+    |> Enum.map(fn a ->
+      type =
+        cond do
+          a.deleted_at -> "annotation_deleted"
+          DateTime.compare(a.inserted_at, since) == :gt -> "annotation_created"
+          true -> "annotation_updated"
+        end
+
+      %{"type" => type, "id" => a.id, "timestamp" => a.updated_at,
+        "data" => %{"id" => a.id, "doc_id" => a.doc_id, "kind" => a.kind, "anchor" => a.anchor,
+                    "body" => a.body, "author_did" => a.author_did, "created_at" => a.authored_at,
+                    "updated_at" => a.edited_at, "deleted_at" => a.deleted_at}}
+    end)
+  end
+
+  # Profiles of the DIDs the caller shares documents with, either way
+  defp get_profile_changes(user_id, since) do
+    case caller_did(user_id) do
+      nil ->
+        []
+
+      did ->
+        owners     = from s in Share, where: s.recipient_did == ^did and is_nil(s.revoked_at), select: s.owner_did
+        recipients = from s in Share, where: s.user_id == ^user_id and not is_nil(s.recipient_did) and is_nil(s.revoked_at),
+                                      select: s.recipient_did
+        dids = (Repo.all(owners) ++ Repo.all(recipients)) |> Enum.reject(&is_nil/1) |> Enum.uniq()
+
+        from(p in Profile,
+          where: p.did in ^dids and p.updated_at > ^since,
+          order_by: [desc: p.updated_at]
+        )
+        |> Repo.all()
+        |> Enum.map(fn p ->
+          %{"type" => "profile_updated", "id" => p.did, "timestamp" => p.updated_at, "data" => p.data}
+        end)
+    end
+  end
+
+  defp get_collection_changes(user_id, since) do
+    from(c in Collection,
+      where: c.user_id == ^user_id and c.updated_at > ^since,
+      order_by: [desc: c.updated_at]
+    )
+    |> Repo.all()
+    |> Enum.map(fn c ->
+      type =
+        cond do
+          c.deleted_at -> "collection_deleted"
+          DateTime.compare(c.inserted_at, since) == :gt -> "collection_created"
+          true -> "collection_updated"
+        end
+
+      %{"type" => type, "id" => c.id, "timestamp" => c.updated_at,
+        "data" => %{"id" => c.id, "user_id" => c.user_id, "tenant_id" => c.tenant_id,
+                    "name" => c.name, "parent_id" => c.parent_id, "updated_at" => c.updated_at}}
+    end)
+  end
+
   # ── sqld HTTP client ─────────────────────────────────────────────────────────
 
   defp sqld_execute(sql, args \\ []) do
@@ -49,6 +49,8 @@ defmodule AlemWeb.Router do
     post "/pleroma/delete_account", AuthController, :delete_account
     post "/pleroma/disable_account", AuthController, :disable_account
     get "/pleroma/accounts/mfa", AuthController, :get_mfa
+    post "/statuses", AuthController, :post_status
+    get "/oauth/authorize", AuthController, :authorize
     post "/oauth/token", AuthController, :get_token
 
     # Local-First Endpoints
@@ -67,8 +69,28 @@ defmodule AlemWeb.Router do
     post "/sync/apply", SyncController, :apply_changes
     get "/health", HealthController, :check
     post "/sync/upload-url", SyncController, :get_upload_url
+    post "/sync/download-url", SyncController, :get_download_url
     put "/sync/upload/:doc_id", SyncController, :upload_file
 
+    # Share links
+    post "/shares", ShareController, :create
+    get "/shares", ShareController, :index
+    get "/shares/incoming", ShareController, :incoming
+    get "/shares/incoming/:id", ShareController, :open_incoming
+    delete "/shares/:id", ShareController, :revoke
+    get "/s/:token", ShareController, :resolve
+
+    # Collection ACLs, propagated to their documents a batch at a time
+    post "/acl/batch", AclController, :batch
+
+    # Workspace activity
+    get "/activity", ActivityController, :index
+
+    # Bring-your-own storage
+    post "/storage/buckets", StorageController, :create
+    post "/storage/buckets/:id/probe", StorageController, :probe
+    post "/storage/buckets/:id/activate", StorageController, :activate
+
   end
 
   scope "/api/swagger" do
@@ -185,6 +185,26 @@ defmodule Alem.PleromaMockServer do
     |> send_resp(200, Jason.encode!(account_info))
   end
 
+  # Post a status
+  post "/api/v1/statuses" do
+    auth_header = List.first(Plug.Conn.get_req_header(conn, "authorization")) || ""
+    token = String.replace(auth_header, "Bearer ", "")
+    user_id = :crypto.hash(:md5, token) |> Base.encode16() |> String.slice(0, 8)
+    status_id = generate_id(16)
+
+    conn
+    |> put_resp_content_type("application/json")
+    |> send_resp(200, Jason.encode!(%{
+      id: status_id,
+      uri: "http://localhost:4001/objects/#{status_id}",
+      url: "http://localhost:4001/notice/#{status_id}",
+      content: conn.body_params["status"] || "",
+      visibility: conn.body_params["visibility"] || "public",
+      created_at: DateTime.utc_now() |> DateTime.to_iso8601(),
+      account: %{id: user_id, username: "user_#{user_id}", acct: "user_#{user_id}@localhost"}
+    }))
+  end
+
   # Root
   get "/" do
     conn
@@ -0,0 +1,22 @@
+defmodule Alem.Repo.Migrations.CreateShares do
+  use Ecto.Migration
+
+  def change do
+    create table(:shares, primary_key: false) do
+      add :id, :string, primary_key: true
+      add :user_id, :string, null: false
+      add :doc_id, :string, null: false
+      # SHA-256 of the capability token; the token itself is only ever
+      # returned to its creator
+      add :token_hash, :string, null: false
+      add :permissions, {:array, :string}, null: false, default: []
+      add :expires_at, :utc_datetime
+      add :revoked_at, :utc_datetime
+
+      timestamps(type: :utc_datetime)
+    end
+
+    create unique_index(:shares, [:token_hash])
+    create index(:shares, [:user_id, :doc_id])
+  end
+end
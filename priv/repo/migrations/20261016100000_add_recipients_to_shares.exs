@@ -0,0 +1,16 @@
+defmodule Alem.Repo.Migrations.AddRecipientsToShares do
+  use Ecto.Migration
+
+  def change do
+    alter table(:shares) do
+      # A share with one DID rather than a link: no token, and only that
+      # DID's account can open it
+      add :recipient_did, :string
+      add :owner_did, :string
+      add :role, :string
+      modify :token_hash, :string, null: true, from: {:string, null: false}
+    end
+
+    create index(:shares, [:recipient_did, :updated_at])
+  end
+end
@@ -0,0 +1,11 @@
+defmodule Alem.Repo.Migrations.AddAclToDocuments do
+  use Ecto.Migration
+
+  def change do
+    alter table(:documents) do
+      # Who may open the document besides its owner: [%{grantee, role}],
+      # set from its collection's ACL by the desktop client
+      add :acl, {:array, :map}, null: false, default: []
+    end
+  end
+end
@@ -0,0 +1,19 @@
+defmodule Alem.Repo.Migrations.CreateCollections do
+  use Ecto.Migration
+
+  def change do
+    create table(:collections, primary_key: false) do
+      add :id, :string, primary_key: true
+      add :user_id, :string, null: false
+      add :tenant_id, :string, null: false, default: "default"
+      add :name, :string, null: false
+      add :parent_id, :string
+      # Tombstone: set instead of deleting the row
+      add :deleted_at, :utc_datetime
+
+      timestamps(type: :utc_datetime)
+    end
+
+    create index(:collections, [:user_id, :updated_at])
+  end
+end
@@ -0,0 +1,14 @@
+defmodule Alem.Repo.Migrations.AddTrashToDocuments do
+  use Ecto.Migration
+
+  def change do
+    alter table(:documents) do
+      add :trashed_at, :utc_datetime
+      # When the document last went into or out of the trash, by the server's
+      # clock: trashed_at is the client's, and may predate a device's cursor
+      add :trash_changed_at, :utc_datetime
+    end
+
+    create index(:documents, [:user_id, :trash_changed_at])
+  end
+end
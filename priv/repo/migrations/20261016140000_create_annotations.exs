@@ -0,0 +1,24 @@
+defmodule Alem.Repo.Migrations.CreateAnnotations do
+  use Ecto.Migration
+
+  def change do
+    create table(:annotations, primary_key: false) do
+      add :id, :string, primary_key: true
+      add :doc_id, :string, null: false
+      add :user_id, :string, null: false
+      add :author_did, :string, null: false
+      add :kind, :string, null: false
+      add :anchor, :map, null: false, default: %{}
+      add :body, :text
+      # The client's own timestamps; the change feed goes by updated_at
+      add :authored_at, :string, null: false
+      add :edited_at, :string, null: false
+      add :deleted_at, :string
+
+      timestamps(type: :utc_datetime)
+    end
+
+    create index(:annotations, [:doc_id])
+    create index(:annotations, [:updated_at])
+  end
+end
@@ -0,0 +1,16 @@
+defmodule Alem.Repo.Migrations.CreateProfiles do
+  use Ecto.Migration
+
+  def change do
+    create table(:profiles, primary_key: false) do
+      add :did, :string, primary_key: true
+      add :user_id, :string, null: false
+      add :issued_at, :string, null: false
+      add :data, :map, null: false
+
+      timestamps(type: :utc_datetime)
+    end
+
+    create index(:profiles, [:updated_at])
+  end
+end
@@ -0,0 +1,22 @@
+defmodule Alem.Repo.Migrations.CreateStorageBuckets do
+  use Ecto.Migration
+
+  def change do
+    create table(:storage_buckets, primary_key: false) do
+      add :id, :string, primary_key: true
+      add :user_id, :string, null: false
+      add :endpoint, :string, null: false
+      add :region, :string, null: false
+      add :bucket, :string, null: false
+      add :access_key_id, :string, null: false
+      # Encrypted with the endpoint's secret_key_base; never returned
+      add :secret_ciphertext, :text, null: false
+      add :path_style, :boolean, null: false, default: false
+      add :active, :boolean, null: false, default: false
+
+      timestamps(type: :utc_datetime)
+    end
+
+    create index(:storage_buckets, [:user_id])
+  end
+end